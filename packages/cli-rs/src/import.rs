@@ -0,0 +1,90 @@
+//! Importing existing git repositories.
+//!
+//! `vanish import` clones a repository (from GitHub, GitLab or any HTTPS
+//! remote) into a bare mirror and pushes its default branch through
+//! `git-remote-vanish`, which packs and pins the archive exactly as a
+//! `git push` to a `vanish://` remote does. Mirroring keeps fetching and
+//! pushes again whenever the default branch moves.
+
+use std::process::Command;
+
+use anyhow::{bail, Context, Result};
+use tempfile::TempDir;
+
+/// A bare mirror of the repository being imported.
+pub struct Mirror {
+    dir: TempDir,
+}
+
+impl Mirror {
+    pub fn clone(url: &str) -> Result<Self> {
+        let dir = tempfile::tempdir()?;
+        git(
+            None,
+            &[
+                "clone",
+                "--mirror",
+                "--quiet",
+                url,
+                &dir.path().to_string_lossy(),
+            ],
+            &[],
+        )
+        .with_context(|| format!("failed to clone {url}"))?;
+        Ok(Self { dir })
+    }
+
+    /// The default branch's head commit.
+    pub fn head(&self) -> Result<String> {
+        self.git(&["rev-parse", "--verify", "HEAD^{commit}"], &[])
+    }
+
+    /// Bring every ref up to date with the source.
+    pub fn fetch(&self) -> Result<()> {
+        self.git(&["remote", "update", "--prune"], &[]).map(drop)
+    }
+
+    /// Push the default branch to `remote`, a `vanish://creator/name` url,
+    /// with `env` configuring the remote helper.
+    pub fn push(&self, remote: &str, env: &[(&str, &str)]) -> Result<()> {
+        self.git(&["push", "--quiet", remote, "HEAD"], env)
+            .map(drop)
+    }
+
+    fn git(&self, args: &[&str], env: &[(&str, &str)]) -> Result<String> {
+        git(Some(&self.dir.path().to_string_lossy()), args, env)
+    }
+}
+
+/// The repository name a clone url ends in, such as `vanish` for
+/// `https://github.com/getvanish/vanish.git`.
+pub fn repo_name(url: &str) -> Option<String> {
+    let name = url
+        .trim_end_matches('/')
+        .rsplit(['/', ':'])
+        .next()?
+        .trim_end_matches(".git");
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+fn git(dir: Option<&str>, args: &[&str], env: &[(&str, &str)]) -> Result<String> {
+    let mut command = Command::new("git");
+    if let Some(dir) = dir {
+        command.args(["-C", dir]);
+    }
+    let output = command
+        .args(args)
+        .envs(env.iter().copied())
+        .output()
+        .context("failed to run git")?;
+
+    if !output.status.success() {
+        bail!(
+            "git {} failed: {}",
+            args[0],
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
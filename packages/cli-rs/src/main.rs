@@ -0,0 +1,778 @@
+//! Vanish command-line tool.
+//!
+//! A thin wrapper over `vanish-client` for CI pipelines and scripts. Every
+//! command prints one line for people, or a single JSON object with
+//! `--json`, and exits non-zero on failure. Reads go through the on-disk
+//! account cache, so repeated invocations do not refetch everything.
+
+mod import;
+mod repo_ref;
+
+use std::path::Path;
+use std::process::ExitCode;
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Context, Result};
+use clap::{Args, Parser, Subcommand};
+use serde_json::{json, Value};
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{read_keypair_file, Keypair},
+    signer::Signer,
+};
+use vanish_client::{
+    archive::Archive,
+    cache::{Cache, CachedClient},
+    cluster::{Cluster, Profile},
+    commit,
+    cost::Operation,
+    offline,
+    program::{PushEntry, PushNote, Repository},
+    snapshot::SignedSnapshot,
+    ComputeBudget, ComputeUnits, PriorityFee, VanishClient,
+};
+
+use crate::import::Mirror;
+use crate::repo_ref::{parse_commit, RepoRef};
+
+#[derive(Parser)]
+#[command(
+    name = "vanish",
+    version,
+    about = "Manage Vanish repositories on Solana"
+)]
+struct Cli {
+    /// Cluster to use: localnet, devnet or mainnet
+    #[arg(long, global = true, env = "VANISH_CLUSTER", default_value = "devnet")]
+    cluster: Cluster,
+
+    /// Solana RPC endpoint, by default the cluster's
+    #[arg(long, global = true, env = "VANISH_RPC_URL")]
+    url: Option<String>,
+
+    /// Websocket endpoint, by default the one paired with the RPC endpoint
+    #[arg(long, global = true, env = "VANISH_WS_URL")]
+    ws_url: Option<String>,
+
+    /// Address of the program deployment, by default the cluster's
+    #[arg(long, global = true, env = "VANISH_PROGRAM_ID")]
+    program_id: Option<Pubkey>,
+
+    /// Keypair that signs and pays for transactions
+    #[arg(
+        long,
+        global = true,
+        env = "VANISH_KEYPAIR",
+        default_value = "~/.config/solana/id.json"
+    )]
+    keypair: String,
+
+    /// Compute unit limit: `auto` to simulate, `default`, or a number
+    #[arg(
+        long,
+        global = true,
+        env = "VANISH_COMPUTE_UNITS",
+        default_value = "auto"
+    )]
+    compute_units: ComputeUnits,
+
+    /// Priority fee in micro-lamports per compute unit: `auto` to follow
+    /// recent fees, `none`, or a number
+    #[arg(
+        long,
+        global = true,
+        env = "VANISH_PRIORITY_FEE",
+        default_value = "none"
+    )]
+    priority_fee: PriorityFee,
+
+    /// Print results as JSON
+    #[arg(long, global = true)]
+    json: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Create, push to, inspect and star repositories
+    #[command(subcommand)]
+    Repo(RepoCommand),
+
+    /// Manage collaborators
+    #[command(subcommand)]
+    Collab(CollabCommand),
+
+    /// Sign and submit transactions prepared for offline signing
+    #[command(subcommand)]
+    Tx(TxCommand),
+
+    /// Pack and unpack deterministic repository archives
+    #[command(subcommand)]
+    Archive(ArchiveCommand),
+
+    /// Preview the rent and fees an operation will cost the keypair
+    #[command(subcommand)]
+    Cost(CostCommand),
+
+    /// Import a git repository from an HTTPS remote such as GitHub or
+    /// GitLab, pushing through git-remote-vanish
+    Import {
+        /// Clone url of the repository
+        url: String,
+
+        /// Name on Vanish, by default the url's last path segment
+        #[arg(long)]
+        name: Option<String>,
+
+        #[arg(long, default_value = "")]
+        description: String,
+
+        /// Keep running, pushing new commits on the default branch
+        #[arg(long)]
+        mirror: bool,
+
+        /// Seconds between fetches while mirroring
+        #[arg(long, default_value_t = 300)]
+        interval: u64,
+    },
+}
+
+#[derive(Subcommand)]
+enum RepoCommand {
+    /// Create a repository owned by the keypair
+    Create {
+        name: String,
+
+        #[arg(long, default_value = "")]
+        description: String,
+
+        /// Encrypt the repository's archives
+        #[arg(long)]
+        private: bool,
+    },
+
+    /// Show a repository's on-chain record
+    Show(RepoArg),
+
+    /// List the repositories an address created
+    List { creator: Pubkey },
+
+    /// Move the default branch to a commit already uploaded to IPFS
+    Push {
+        #[command(flatten)]
+        repo: RepoArg,
+
+        /// Git commit id, 40 (SHA-1) or 64 (SHA-256) hex digits
+        #[arg(long)]
+        commit: String,
+
+        /// CID of the uploaded archive
+        #[arg(long)]
+        cid: String,
+
+        /// CID of a changelog for the push
+        #[arg(long)]
+        changelog: Option<String>,
+
+        /// One-line summary of the push, at most 72 bytes
+        #[arg(long)]
+        message: Option<String>,
+
+        /// Archive file uploaded as --cid, to record its binding to the
+        /// commit
+        #[arg(long)]
+        archive: Option<String>,
+    },
+
+    /// Write a signed snapshot of a repository's on-chain state
+    Export {
+        #[command(flatten)]
+        repo: RepoArg,
+
+        /// File to write the snapshot to, instead of printing it
+        #[arg(long)]
+        out: Option<String>,
+    },
+
+    /// Recreate a repository from a snapshot, owned by the keypair
+    Import {
+        /// Snapshot written by `repo export`
+        file: String,
+    },
+
+    /// Star a repository
+    Star {
+        #[command(flatten)]
+        repo: RepoArg,
+
+        /// Lamports to tip into the repository's donation vault
+        #[arg(long, default_value_t = 0)]
+        tip: u64,
+    },
+}
+
+#[derive(Subcommand)]
+enum CollabCommand {
+    /// Add a collaborator to a public repository
+    Add {
+        #[command(flatten)]
+        repo: RepoArg,
+
+        user: Pubkey,
+
+        /// Let the collaborator push
+        #[arg(long)]
+        push: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum TxCommand {
+    /// Add the keypair's signature to a payload, without network access
+    Sign { payload: String },
+
+    /// Send a fully signed payload
+    Submit { payload: String },
+}
+
+#[derive(Subcommand)]
+enum ArchiveCommand {
+    /// Pack a branch of a local repository the way git-remote-vanish
+    /// pushes it, so its CID can be compared with the one on chain
+    Pack {
+        branch: String,
+
+        /// Repository to pack from
+        #[arg(long, default_value = ".")]
+        repo: String,
+
+        /// File to write the archive to
+        #[arg(long)]
+        out: String,
+    },
+
+    /// Check an archive and copy its objects into a local repository
+    Unpack {
+        file: String,
+
+        /// Repository to unpack into
+        #[arg(long, default_value = ".")]
+        repo: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum CostCommand {
+    /// Create a repository and add collaborators to it
+    CreateRepo {
+        name: String,
+
+        /// Collaborators to add after creating it
+        #[arg(long, default_value_t = 0)]
+        collaborators: u32,
+
+        /// Give each collaborator an access key to the encrypted archives
+        #[arg(long)]
+        private: bool,
+    },
+
+    /// Add a collaborator
+    AddCollaborator {
+        /// The repository is private, so the collaborator gets an access key
+        #[arg(long)]
+        private: bool,
+    },
+
+    /// Push a new branch
+    PushBranch {
+        /// The keypair has not pushed to the repository before
+        #[arg(long)]
+        new_contributor: bool,
+    },
+
+    /// Star a repository
+    Star,
+}
+
+impl From<CostCommand> for Operation {
+    fn from(command: CostCommand) -> Self {
+        match command {
+            CostCommand::CreateRepo {
+                name,
+                collaborators,
+                private,
+            } => Self::CreateRepo {
+                name,
+                collaborators,
+                private,
+            },
+            CostCommand::AddCollaborator { private } => Self::AddCollaborator { private },
+            CostCommand::PushBranch { new_contributor } => Self::PushBranch { new_contributor },
+            CostCommand::Star => Self::Star,
+        }
+    }
+}
+
+#[derive(Args)]
+struct RepoArg {
+    /// Repository, as `creator/name` or an account address
+    repo: RepoRef,
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let json = cli.json;
+    match run(cli).await {
+        Ok((message, value)) => {
+            if json {
+                println!("{value}");
+            } else {
+                println!("{message}");
+            }
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            if json {
+                println!("{}", json!({ "error": format!("{err:#}") }));
+            } else {
+                eprintln!("error: {err:#}");
+            }
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Run the command, returning a line for people and a JSON value for scripts.
+async fn run(cli: Cli) -> Result<(String, Value)> {
+    // Another invocation holding the cache gets a throwaway one
+    let cache = Cache::open(&Cache::cluster_path(cli.cluster)).or_else(|_| Cache::temporary())?;
+    let mut profile = Profile::new(cli.cluster);
+    if let Some(url) = cli.url {
+        profile = profile.with_rpc_url(url);
+    }
+    if let Some(url) = cli.ws_url {
+        profile.ws_url = url;
+    }
+    if let Some(id) = cli.program_id {
+        profile.program_id = id;
+    }
+    let client = VanishClient::from_profile(&profile)?.with_compute_budget(ComputeBudget {
+        units: cli.compute_units,
+        fee: cli.priority_fee,
+    });
+    let cached = CachedClient::new(client, cache);
+    let output = execute(&cached, &profile, &cli.keypair, cli.command).await;
+    cached.settle().await;
+    output
+}
+
+async fn execute(
+    cached: &CachedClient,
+    profile: &Profile,
+    keypair_path: &str,
+    command: Command,
+) -> Result<(String, Value)> {
+    let client = cached.client();
+
+    match command {
+        Command::Repo(RepoCommand::Create {
+            name,
+            description,
+            private,
+        }) => {
+            let owner = keypair(keypair_path)?;
+            let address = client
+                .create_repo(&owner, &name, &description, private)
+                .await
+                .with_context(|| format!("failed to create repository {name}"))?;
+            cached.invalidate_repositories(&owner.pubkey())?;
+            Ok((
+                format!("created {}/{name} at {address}", owner.pubkey()),
+                json!({ "repository": address.to_string(), "name": name }),
+            ))
+        }
+        Command::Repo(RepoCommand::Show(RepoArg { repo })) => {
+            let address = repo.address();
+            let repo = fetch(cached, &address).await?;
+            let message = format!(
+                "{}/{} ({} stars, head {})",
+                repo.creator,
+                repo.name,
+                repo.stars,
+                if repo.ipfs_cid.is_empty() {
+                    "not pushed".to_string()
+                } else {
+                    repo.ipfs_cid.to_string()
+                }
+            );
+            Ok((message, describe(&address, &repo)))
+        }
+        Command::Repo(RepoCommand::List { creator }) => {
+            let repositories = cached
+                .repositories(&creator)
+                .await
+                .with_context(|| format!("failed to list repositories of {creator}"))?;
+            let message = repositories
+                .iter()
+                .map(|(address, repo)| format!("{}\t{address}", repo.name))
+                .collect::<Vec<_>>()
+                .join("\n");
+            let value = repositories
+                .iter()
+                .map(|(address, repo)| describe(address, repo))
+                .collect();
+            Ok((message, Value::Array(value)))
+        }
+        Command::Repo(RepoCommand::Push {
+            repo,
+            commit,
+            cid,
+            changelog,
+            message,
+            archive,
+        }) => {
+            let pusher = keypair(keypair_path)?;
+            let address = repo.repo.address();
+            let head = parse_commit(&commit)?;
+            let binding = match archive {
+                Some(file) => {
+                    let bytes =
+                        std::fs::read(&file).with_context(|| format!("failed to read {file}"))?;
+                    let archive = Archive::from_bytes(&bytes)?;
+                    if archive.head != commit::to_hex(&head) {
+                        bail!("{file} holds {} rather than {commit}", archive.head);
+                    }
+                    Some(archive.binding())
+                }
+                None => None,
+            };
+            let note = (changelog.is_some() || message.is_some()).then(|| PushNote {
+                changelog_cid: changelog.unwrap_or_default(),
+                message: message.unwrap_or_default(),
+            });
+            if note.as_ref().is_some_and(|note| !note.is_valid()) {
+                bail!(
+                    "--changelog must be at most {} bytes and --message at most {}",
+                    PushEntry::MAX_CHANGELOG_CID_LEN,
+                    PushEntry::MAX_MESSAGE_LEN
+                );
+            }
+            let signature = client
+                .push(&pusher, &address, head, &cid, note, binding)
+                .await
+                .with_context(|| format!("failed to push to {address}"))?;
+            cached.invalidate(&address)?;
+            Ok((
+                format!("pushed {commit} to {address}"),
+                json!({
+                    "repository": address.to_string(),
+                    "commit": commit,
+                    "cid": cid,
+                    "signature": signature.to_string(),
+                }),
+            ))
+        }
+        Command::Repo(RepoCommand::Export { repo, out }) => {
+            let signer = keypair(keypair_path)?;
+            let address = repo.repo.address();
+            let signed = client
+                .export_repo(&address)
+                .await
+                .with_context(|| format!("failed to export {address}"))?
+                .sign(&signer)?;
+            let value = serde_json::to_value(&signed)?;
+            let Some(out) = out else {
+                return Ok((serde_json::to_string_pretty(&signed)?, value));
+            };
+            std::fs::write(&out, serde_json::to_string_pretty(&signed)?)
+                .with_context(|| format!("failed to write {out}"))?;
+            Ok((format!("exported {address} to {out}"), value))
+        }
+        Command::Repo(RepoCommand::Import { file }) => {
+            let owner = keypair(keypair_path)?;
+            let text =
+                std::fs::read_to_string(&file).with_context(|| format!("failed to read {file}"))?;
+            let signed: SignedSnapshot = serde_json::from_str(&text)
+                .with_context(|| format!("{file} is not a repository snapshot"))?;
+            let signer = signed.verify()?;
+            let report = client
+                .import_repo(&owner, &signed.snapshot)
+                .await
+                .with_context(|| format!("failed to import {}", signed.snapshot.name))?;
+            cached.invalidate_repositories(&owner.pubkey())?;
+            let mut message = format!(
+                "imported {} (signed by {signer}) at {}",
+                signed.snapshot.name, report.address
+            );
+            for skipped in &report.skipped {
+                message.push_str(&format!("\nskipped {skipped}"));
+            }
+            Ok((
+                message,
+                json!({
+                    "repository": report.address.to_string(),
+                    "source": signed.snapshot.address,
+                    "signer": signer.to_string(),
+                    "skipped": report.skipped,
+                }),
+            ))
+        }
+        Command::Repo(RepoCommand::Star { repo, tip }) => {
+            let user = keypair(keypair_path)?;
+            let address = repo.repo.address();
+            let signature = client
+                .star_repo(&user, &address, tip)
+                .await
+                .with_context(|| format!("failed to star {address}"))?;
+            cached.invalidate(&address)?;
+            Ok((
+                format!("starred {address}"),
+                json!({
+                    "repository": address.to_string(),
+                    "tip": tip,
+                    "signature": signature.to_string(),
+                }),
+            ))
+        }
+        Command::Collab(CollabCommand::Add { repo, user, push }) => {
+            let authority = keypair(keypair_path)?;
+            let address = repo.repo.address();
+            if fetch(cached, &address).await?.is_private {
+                // The collaborator needs the repository key sealed to them,
+                // which only the key holder's client can produce
+                bail!("{address} is private; add collaborators with the key holder's client");
+            }
+            let signature = client
+                .add_collaborator(&authority, &address, &user, push, None)
+                .await
+                .with_context(|| format!("failed to add {user} to {address}"))?;
+            cached.invalidate(&address)?;
+            Ok((
+                format!("added {user} to {address}"),
+                json!({
+                    "repository": address.to_string(),
+                    "collaborator": user.to_string(),
+                    "can_push": push,
+                    "signature": signature.to_string(),
+                }),
+            ))
+        }
+        Command::Import {
+            url: source,
+            name,
+            description,
+            mirror,
+            interval,
+        } => {
+            let owner = keypair(keypair_path)?;
+            let name = match name {
+                Some(name) => name,
+                None => import::repo_name(&source)
+                    .with_context(|| format!("cannot tell a name from {source}; pass --name"))?,
+            };
+            let clone = Mirror::clone(&source)?;
+            let address = client
+                .create_repo(&owner, &name, &description, false)
+                .await
+                .with_context(|| format!("failed to create repository {name}"))?;
+            cached.invalidate_repositories(&owner.pubkey())?;
+
+            let remote = format!("vanish://{}/{name}", owner.pubkey());
+            let keypair_file = expand_home(keypair_path);
+            let cluster = profile.cluster.to_string();
+            let program_id = profile.program_id.to_string();
+            let env = [
+                ("VANISH_CLUSTER", cluster.as_str()),
+                ("VANISH_RPC_URL", profile.rpc_url.as_str()),
+                ("VANISH_WS_URL", profile.ws_url.as_str()),
+                ("VANISH_PROGRAM_ID", program_id.as_str()),
+                ("VANISH_KEYPAIR", keypair_file.as_str()),
+            ];
+            clone
+                .push(&remote, &env)
+                .with_context(|| format!("failed to push to {remote}"))?;
+            let mut head = clone.head()?;
+
+            if mirror {
+                eprintln!("imported {source} at {head}, mirroring every {interval}s");
+                loop {
+                    tokio::time::sleep(Duration::from_secs(interval)).await;
+                    // A failed round is retried on the next one
+                    let synced = clone.fetch().and_then(|()| {
+                        let latest = clone.head()?;
+                        if latest != head {
+                            clone.push(&remote, &env)?;
+                        }
+                        Ok(latest)
+                    });
+                    match synced {
+                        Ok(latest) if latest != head => {
+                            cached.invalidate(&address)?;
+                            eprintln!("mirrored {latest}");
+                            head = latest;
+                        }
+                        Ok(_) => {}
+                        Err(err) => eprintln!("mirroring {source} failed: {err:#}"),
+                    }
+                }
+            }
+
+            Ok((
+                format!(
+                    "imported {source} as {}/{name} at {address}",
+                    owner.pubkey()
+                ),
+                json!({
+                    "repository": address.to_string(),
+                    "name": name,
+                    "source": source,
+                    "commit": head,
+                }),
+            ))
+        }
+        Command::Tx(TxCommand::Sign { payload }) => {
+            let signer = keypair(keypair_path)?;
+            let mut transaction = offline::decode(&payload)?;
+            offline::sign(&mut transaction, &signer)
+                .with_context(|| format!("{} cannot sign this payload", signer.pubkey()))?;
+            let missing = offline::missing_signers(&transaction);
+            let payload = offline::encode(&transaction);
+            Ok((
+                payload.clone(),
+                json!({
+                    "payload": payload,
+                    "missing_signers": missing.iter().map(ToString::to_string).collect::<Vec<_>>(),
+                }),
+            ))
+        }
+        Command::Tx(TxCommand::Submit { payload }) => {
+            let transaction = offline::decode(&payload)?;
+            let signature = client
+                .submit(&transaction)
+                .await
+                .context("failed to submit transaction")?;
+            Ok((
+                format!("submitted {signature}"),
+                json!({ "signature": signature.to_string() }),
+            ))
+        }
+        Command::Archive(ArchiveCommand::Pack { branch, repo, out }) => {
+            let archive = Archive::pack_branch(Some(Path::new(&repo)), &branch)
+                .with_context(|| format!("failed to pack {branch}"))?;
+            let bytes = archive.to_bytes();
+            std::fs::write(&out, &bytes).with_context(|| format!("failed to write {out}"))?;
+            Ok((
+                format!(
+                    "packed {branch} at {} ({} objects) into {out}",
+                    archive.head,
+                    archive.object_count()
+                ),
+                json!({
+                    "branch": branch,
+                    "head": archive.head,
+                    "binding": archive
+                        .binding()
+                        .iter()
+                        .map(|byte| format!("{byte:02x}"))
+                        .collect::<String>(),
+                    "objects": archive.object_count(),
+                    "size": bytes.len(),
+                    "file": out,
+                }),
+            ))
+        }
+        Command::Cost(command) => {
+            let payer = keypair(keypair_path)?.pubkey();
+            let estimate = client
+                .estimate(&payer, &command.into())
+                .await
+                .context("failed to estimate the cost")?;
+            Ok((
+                format!(
+                    "{} lamports: {} in rent deposits, {} staked, {} program fee, {} in fees for {} transactions",
+                    estimate.total(),
+                    estimate.rent,
+                    estimate.stake,
+                    estimate.program_fee,
+                    estimate.transaction_fees,
+                    estimate.transactions
+                ),
+                json!({
+                    "total": estimate.total(),
+                    "rent": estimate.rent,
+                    "deposits": estimate
+                        .deposits
+                        .iter()
+                        .map(|deposit| json!({
+                            "account": deposit.account,
+                            "count": deposit.count,
+                            "space": deposit.space,
+                            "lamports": deposit.lamports,
+                        }))
+                        .collect::<Vec<_>>(),
+                    "stake": estimate.stake,
+                    "program_fee": estimate.program_fee,
+                    "transactions": estimate.transactions,
+                    "transaction_fees": estimate.transaction_fees,
+                }),
+            ))
+        }
+        Command::Archive(ArchiveCommand::Unpack { file, repo }) => {
+            let bytes = std::fs::read(&file).with_context(|| format!("failed to read {file}"))?;
+            let archive = Archive::from_bytes(&bytes)?;
+            archive
+                .unpack(Some(Path::new(&repo)))
+                .with_context(|| format!("failed to unpack into {repo}"))?;
+            Ok((
+                format!("unpacked {} into {repo}", archive.head),
+                json!({
+                    "head": archive.head,
+                    "refs": archive
+                        .refs
+                        .iter()
+                        .map(|(name, commit)| json!({ "name": name, "commit": commit }))
+                        .collect::<Vec<_>>(),
+                    "objects": archive.object_count(),
+                }),
+            ))
+        }
+    }
+}
+
+async fn fetch(cached: &CachedClient, address: &Pubkey) -> Result<Repository> {
+    cached
+        .account(address)
+        .await
+        .with_context(|| format!("failed to fetch repository {address}"))
+}
+
+fn describe(address: &Pubkey, repo: &Repository) -> Value {
+    json!({
+        "repository": address.to_string(),
+        "owner": repo.owner.to_string(),
+        "creator": repo.creator.to_string(),
+        "name": repo.name,
+        "description": repo.description,
+        "is_private": repo.is_private,
+        "flags": repo.flags,
+        "ipfs_cid": repo.ipfs_cid.to_string(),
+        "stars": repo.stars,
+        "collaborators": repo.collaborator_count,
+        "updated_at": repo.updated_at,
+    })
+}
+
+fn keypair(path: &str) -> Result<Keypair> {
+    read_keypair_file(expand_home(path))
+        .map_err(|err| anyhow!("failed to read keypair {path}: {err}"))
+}
+
+fn expand_home(path: &str) -> String {
+    match (path.strip_prefix("~/"), std::env::var("HOME")) {
+        (Some(rest), Ok(home)) => format!("{home}/{rest}"),
+        _ => path.to_string(),
+    }
+}
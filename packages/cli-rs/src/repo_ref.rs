@@ -0,0 +1,47 @@
+//! Parsing of command-line repository and commit arguments.
+
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+use solana_sdk::pubkey::Pubkey;
+use vanish_client::{commit, pda, program::Multihash};
+
+/// A repository named on the command line.
+#[derive(Clone)]
+pub enum RepoRef {
+    Address(Pubkey),
+    Name { creator: Pubkey, name: String },
+}
+
+impl RepoRef {
+    pub fn address(&self) -> Pubkey {
+        match self {
+            Self::Address(address) => *address,
+            Self::Name { creator, name } => pda::repo(creator, name),
+        }
+    }
+}
+
+impl FromStr for RepoRef {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.split_once('/') {
+            Some((creator, name)) => Ok(Self::Name {
+                creator: creator
+                    .parse()
+                    .map_err(|_| anyhow!("{creator} is not a valid creator address"))?,
+                name: name.to_string(),
+            }),
+            None => s
+                .parse()
+                .map(Self::Address)
+                .map_err(|_| anyhow!("expected creator/name or an address, got {s}")),
+        }
+    }
+}
+
+/// Parse a hex git commit id into the multihash push_update takes.
+pub fn parse_commit(hex: &str) -> Result<Multihash> {
+    commit::from_hex(hex).ok_or_else(|| anyhow!("commit id must be 40 or 64 hex digits, got {hex}"))
+}
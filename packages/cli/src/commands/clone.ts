@@ -3,7 +3,7 @@ import ora from 'ora';
 import { PublicKey } from '@solana/web3.js';
 import * as path from 'path';
 import * as fs from 'fs';
-import { VanishClient } from '@vanish/sdk';
+import { VanishClient, REPO_FLAGS } from '@vanish/sdk';
 import { getConfig, getKeypair } from '../config';
 
 interface CloneOptions {
@@ -92,6 +92,14 @@ export async function cloneCommand(
     console.log(chalk.gray('  IPFS CID: ') + chalk.white(repoInfo.ipfsCid));
     console.log();
 
+    if (repoInfo.flags & REPO_FLAGS.DEPRECATED) {
+      console.log(chalk.yellow('  This repository is deprecated.'));
+      if (repoInfo.successor) {
+        console.log(chalk.gray('  Moved to: ') + chalk.cyan(repoInfo.successor.toBase58()));
+      }
+      console.log();
+    }
+
   } catch (error) {
     spinner.fail('Clone failed');
     console.error(chalk.red(error instanceof Error ? error.message : 'Unknown error'));
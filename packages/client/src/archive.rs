@@ -0,0 +1,529 @@
+//! Deterministic repository archives.
+//!
+//! A git bundle depends on how the packing repository happens to store its
+//! objects: delta choices, compression level and object order all vary, so
+//! two people bundling the same commit get different bytes and different
+//! CIDs. An [`Archive`] depends only on its refs and the objects they reach.
+//! Anyone can repack a pushed commit and compare CIDs to check what the push
+//! contains.
+//!
+//! The format is a header line, the refs, and a blank line, followed by
+//! every object in ascending id order:
+//!
+//! ```text
+//! vanish-archive 1
+//! <commit id> HEAD
+//! <commit id> refs/heads/main
+//!
+//! <type> <size>\0<content>...
+//! ```
+//!
+//! Objects are stored uncompressed, exactly as git hashes them, so their ids
+//! are implied and checked on unpacking rather than stored.
+//!
+//! Pushes carry a [binding](Archive::binding): a hash over the header and
+//! the head commit object. [`verify_binding`] re-derives it from downloaded
+//! bytes, so a reader can tell the commit recorded on chain is the one the
+//! archive at the CID holds.
+
+use std::io::{Read, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use flate2::{write::ZlibEncoder, Compression};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+use vanish_program::Multihash;
+
+use crate::{commit, Error, Result};
+
+const MAGIC: &[u8] = b"vanish-archive 1\n";
+
+/// Domain separator for bindings, so they cannot collide with other hashes
+/// over archive bytes
+const BINDING_TAG: &[u8] = b"vanish-binding 1\n";
+
+/// A git object kind, numbered as in pack files.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    Commit = 1,
+    Tree = 2,
+    Blob = 3,
+    Tag = 4,
+}
+
+impl Kind {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Commit => "commit",
+            Self::Tree => "tree",
+            Self::Blob => "blob",
+            Self::Tag => "tag",
+        }
+    }
+
+    fn parse(name: &[u8]) -> Option<Self> {
+        match name {
+            b"commit" => Some(Self::Commit),
+            b"tree" => Some(Self::Tree),
+            b"blob" => Some(Self::Blob),
+            b"tag" => Some(Self::Tag),
+            _ => None,
+        }
+    }
+}
+
+struct Object {
+    id: String,
+    kind: Kind,
+    content: Vec<u8>,
+}
+
+impl Object {
+    /// Header and content, the bytes git hashes
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = format!("{} {}\0", self.kind.name(), self.content.len()).into_bytes();
+        bytes.extend(&self.content);
+        bytes
+    }
+}
+
+pub struct Archive {
+    /// Commit HEAD points at
+    pub head: String,
+    /// `(name, commit)` for each ref, sorted by name
+    pub refs: Vec<(String, String)>,
+    objects: Vec<Object>,
+}
+
+impl Archive {
+    /// Whether `bytes` start like an archive rather than, say, a git bundle
+    pub fn is_archive(bytes: &[u8]) -> bool {
+        bytes.starts_with(MAGIC)
+    }
+
+    /// Pack `head` and `refs`, `(name, commit)` pairs, with every object
+    /// they reach, from the repository at `dir`, or the one `GIT_DIR` names.
+    pub fn pack(dir: Option<&Path>, head: &str, refs: &[(&str, &str)]) -> Result<Self> {
+        let mut refs: Vec<_> = refs
+            .iter()
+            .map(|(name, commit)| (name.to_string(), commit.to_string()))
+            .collect();
+        refs.sort();
+        refs.dedup_by(|a, b| a.0 == b.0);
+
+        let mut tips = vec![head.to_string()];
+        tips.extend(refs.iter().map(|(_, commit)| commit.clone()));
+        let listing = git(
+            dir,
+            &["rev-list", "--objects", "--stdin"],
+            tips.join("\n") + "\n",
+        )?;
+        let mut ids: Vec<&str> = std::str::from_utf8(&listing)
+            .map_err(|_| archive_error("git listed a non-UTF-8 object"))?
+            .lines()
+            .filter_map(|line| line.split(' ').next())
+            .filter(|id| !id.is_empty())
+            .collect();
+        ids.sort_unstable();
+        ids.dedup();
+
+        let batch = git(dir, &["cat-file", "--batch"], ids.join("\n") + "\n")?;
+        let mut objects = Vec::with_capacity(ids.len());
+        let mut rest = batch.as_slice();
+        for id in ids {
+            let end = rest
+                .iter()
+                .position(|&byte| byte == b'\n')
+                .ok_or_else(|| archive_error("truncated cat-file output"))?;
+            let header = std::str::from_utf8(&rest[..end]).unwrap_or_default();
+            let mut fields = header.split(' ');
+            let (Some(listed), Some(kind), Some(size)) =
+                (fields.next(), fields.next(), fields.next())
+            else {
+                return Err(archive_error(format!("object {id} is missing")));
+            };
+            let kind = Kind::parse(kind.as_bytes())
+                .ok_or_else(|| archive_error(format!("object {id} has unknown type {kind}")))?;
+            let size: usize = size
+                .parse()
+                .map_err(|_| archive_error(format!("object {id} has no size")))?;
+            if listed != id || rest.len() < end + 1 + size + 1 {
+                return Err(archive_error("truncated cat-file output"));
+            }
+            objects.push(Object {
+                id: id.to_string(),
+                kind,
+                content: rest[end + 1..end + 1 + size].to_vec(),
+            });
+            rest = &rest[end + 1 + size + 1..];
+        }
+
+        Ok(Self {
+            head: head.to_string(),
+            refs,
+            objects,
+        })
+    }
+
+    /// Pack `branch` of the repository at `dir` as git-remote-vanish pushes
+    /// it: the branch is both HEAD and the only ref.
+    pub fn pack_branch(dir: Option<&Path>, branch: &str) -> Result<Self> {
+        let name = format!("refs/heads/{branch}");
+        let commit = git(
+            dir,
+            &["rev-parse", "--verify", &format!("{name}^{{commit}}")],
+            Vec::new(),
+        )?;
+        let commit = String::from_utf8_lossy(&commit).trim().to_string();
+        Self::pack(dir, &commit, &[(&name, &commit)])
+    }
+
+    /// Read an archive, checking every object hashes to an id greater than
+    /// the last and every ref names a commit in it.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut rest = bytes
+            .strip_prefix(MAGIC)
+            .ok_or_else(|| archive_error("not a vanish archive"))?;
+
+        let mut head = None;
+        let mut refs: Vec<(String, String)> = Vec::new();
+        loop {
+            let end = rest
+                .iter()
+                .position(|&byte| byte == b'\n')
+                .ok_or_else(|| archive_error("truncated ref list"))?;
+            let line = std::str::from_utf8(&rest[..end])
+                .map_err(|_| archive_error("ref names must be UTF-8"))?;
+            rest = &rest[end + 1..];
+            if line.is_empty() {
+                break;
+            }
+            let (commit, name) = line
+                .split_once(' ')
+                .ok_or_else(|| archive_error(format!("malformed ref {line}")))?;
+            if name == "HEAD" {
+                if head.is_some() || !refs.is_empty() {
+                    return Err(archive_error("HEAD must come first"));
+                }
+                head = Some(commit.to_string());
+            } else {
+                if matches!(refs.last(), Some((last, _)) if last.as_str() >= name) {
+                    return Err(archive_error("refs out of order"));
+                }
+                refs.push((name.to_string(), commit.to_string()));
+            }
+        }
+        let head = head.ok_or_else(|| archive_error("no HEAD"))?;
+
+        let mut objects: Vec<Object> = Vec::new();
+        while !rest.is_empty() {
+            let end = rest
+                .iter()
+                .position(|&byte| byte == 0)
+                .ok_or_else(|| archive_error("truncated object header"))?;
+            let header = &rest[..end];
+            let space = header
+                .iter()
+                .position(|&byte| byte == b' ')
+                .ok_or_else(|| archive_error("malformed object header"))?;
+            let kind = Kind::parse(&header[..space])
+                .ok_or_else(|| archive_error("unknown object type"))?;
+            let size: usize = std::str::from_utf8(&header[space + 1..])
+                .ok()
+                .and_then(|size| size.parse().ok())
+                .ok_or_else(|| archive_error("malformed object size"))?;
+            let object = end
+                .checked_add(1)
+                .and_then(|start| start.checked_add(size))
+                .and_then(|len| rest.get(..len))
+                .ok_or_else(|| archive_error("truncated object"))?;
+
+            let id = object_id(head.len(), object)?;
+            if objects.last().is_some_and(|last| last.id >= id) {
+                return Err(archive_error("objects out of order"));
+            }
+            objects.push(Object {
+                id,
+                kind,
+                content: object[end + 1..].to_vec(),
+            });
+            rest = &rest[object.len()..];
+        }
+
+        let archive = Self {
+            head,
+            refs,
+            objects,
+        };
+        for commit in std::iter::once(&archive.head).chain(archive.refs.iter().map(|(_, id)| id)) {
+            if archive.kind_of(commit) != Some(Kind::Commit) {
+                return Err(archive_error(format!(
+                    "{commit} is not a commit in the archive"
+                )));
+            }
+        }
+        // Anything else, such as padded sizes, would give the same objects
+        // a second CID
+        if archive.to_bytes() != bytes {
+            return Err(archive_error("archive is not in canonical form"));
+        }
+        Ok(archive)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.header();
+        for object in &self.objects {
+            bytes.extend(object.to_bytes());
+        }
+        bytes
+    }
+
+    /// SHA-256 over a domain tag, the header and the head commit object:
+    /// what push_update's `binding` commits to.
+    pub fn binding(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(BINDING_TAG);
+        hasher.update(self.header());
+        if let Some(head) = self.object(&self.head) {
+            hasher.update(head.to_bytes());
+        }
+        hasher.finalize().into()
+    }
+
+    /// Write the objects into the repository at `dir`, or the one `GIT_DIR`
+    /// names, then check the head is complete there: archives may leave out
+    /// objects the repository already has.
+    pub fn unpack(&self, dir: Option<&Path>) -> Result<()> {
+        git(dir, &["unpack-objects", "-q"], self.pack_file()?)?;
+        git(
+            dir,
+            &["rev-list", "--objects", "--quiet", &self.head],
+            Vec::new(),
+        )
+        .map(drop)
+    }
+
+    pub fn object_count(&self) -> usize {
+        self.objects.len()
+    }
+
+    fn kind_of(&self, id: &str) -> Option<Kind> {
+        self.object(id).map(|object| object.kind)
+    }
+
+    fn object(&self, id: &str) -> Option<&Object> {
+        self.objects
+            .binary_search_by(|object| object.id.as_str().cmp(id))
+            .ok()
+            .map(|index| &self.objects[index])
+    }
+
+    /// The magic line, refs and blank line before the objects
+    fn header(&self) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend(format!("{} HEAD\n", self.head).as_bytes());
+        for (name, commit) in &self.refs {
+            bytes.extend(format!("{commit} {name}\n").as_bytes());
+        }
+        bytes.push(b'\n');
+        bytes
+    }
+
+    /// The objects as a version 2 pack with no deltas, which git can read
+    fn pack_file(&self) -> Result<Vec<u8>> {
+        let mut pack = b"PACK".to_vec();
+        pack.extend(2u32.to_be_bytes());
+        pack.extend((self.objects.len() as u32).to_be_bytes());
+        for object in &self.objects {
+            // Type and size: 4 bits of size in the first byte, 7 in each
+            // byte after, least significant first
+            let mut size = object.content.len();
+            let mut byte = (object.kind as u8) << 4 | (size & 0x0f) as u8;
+            size >>= 4;
+            while size > 0 {
+                pack.push(byte | 0x80);
+                byte = (size & 0x7f) as u8;
+                size >>= 7;
+            }
+            pack.push(byte);
+
+            let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(&object.content)?;
+            pack.extend(encoder.finish()?);
+        }
+        let checksum = match self.head.len() {
+            40 => Sha1::digest(&pack).to_vec(),
+            _ => Sha256::digest(&pack).to_vec(),
+        };
+        pack.extend(checksum);
+        Ok(pack)
+    }
+}
+
+/// Check that `bytes`, downloaded from a push's CID, are an archive whose
+/// head is `head_commit` and whose binding is the one the push recorded.
+pub fn verify_binding(bytes: &[u8], head_commit: &Multihash, binding: &[u8; 32]) -> Result<()> {
+    let archive = Archive::from_bytes(bytes)?;
+    let head = commit::to_hex(head_commit);
+    if archive.head != head {
+        return Err(Error::Binding(format!(
+            "archive holds {} rather than {head}",
+            archive.head
+        )));
+    }
+    if archive.binding() != *binding {
+        return Err(Error::Binding(format!(
+            "binding does not match the archive of {head}"
+        )));
+    }
+    Ok(())
+}
+
+/// The id git gives `object`, a header and content, in a repository whose
+/// ids are `len` hex digits.
+fn object_id(len: usize, object: &[u8]) -> Result<String> {
+    let digest = match len {
+        40 => Sha1::digest(object).to_vec(),
+        64 => Sha256::digest(object).to_vec(),
+        _ => return Err(archive_error("HEAD is not a commit id")),
+    };
+    Ok(digest.iter().map(|byte| format!("{byte:02x}")).collect())
+}
+
+fn archive_error(message: impl Into<String>) -> Error {
+    Error::Archive(message.into())
+}
+
+fn git(dir: Option<&Path>, args: &[&str], input: impl Into<Vec<u8>>) -> Result<Vec<u8>> {
+    let mut command = Command::new("git");
+    if let Some(dir) = dir {
+        command.arg("-C").arg(dir);
+    }
+    let mut child = command
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    // Written from another thread so a full stdout pipe cannot deadlock us
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let input = input.into();
+    let writer = std::thread::spawn(move || stdin.write_all(&input));
+    let mut stdout = Vec::new();
+    child
+        .stdout
+        .take()
+        .expect("stdout is piped")
+        .read_to_end(&mut stdout)?;
+    let output = child.wait_with_output()?;
+    writer.join().expect("stdin writer panicked").ok();
+
+    if !output.status.success() {
+        return Err(archive_error(format!(
+            "git {} failed: {}",
+            args[0],
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(stdout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COMMIT: &[u8] = b"tree 4b825dc642cb6eb9a060e54bf8d69288fbee4904\n\nmessage\n";
+
+    /// An archive of one commit, its object written with `header`
+    fn single_commit(header: &str) -> Vec<u8> {
+        let mut object = header.as_bytes().to_vec();
+        object.extend(COMMIT);
+        let id = object_id(40, &object).unwrap();
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend(format!("{id} HEAD\n{id} refs/heads/main\n\n").as_bytes());
+        bytes.extend(object);
+        bytes
+    }
+
+    #[test]
+    fn packed_archives_round_trip() {
+        let dir = std::env::temp_dir().join(format!("vanish-archive-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        git(Some(&dir), &["init", "-q", "-b", "main"], Vec::new()).unwrap();
+        std::fs::write(dir.join("README"), "vanish\n").unwrap();
+        git(Some(&dir), &["add", "README"], Vec::new()).unwrap();
+        git(
+            Some(&dir),
+            &[
+                "-c",
+                "user.name=vanish",
+                "-c",
+                "user.email=vanish@example.com",
+                "commit",
+                "-q",
+                "-m",
+                "first",
+            ],
+            Vec::new(),
+        )
+        .unwrap();
+
+        let archive = Archive::pack_branch(Some(&dir), "main").unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        // The commit, its tree and the blob
+        assert_eq!(archive.object_count(), 3);
+        assert_eq!(
+            archive.refs,
+            [("refs/heads/main".to_string(), archive.head.clone())]
+        );
+
+        let bytes = archive.to_bytes();
+        assert!(Archive::is_archive(&bytes));
+        let read = Archive::from_bytes(&bytes).unwrap();
+        assert_eq!(read.head, archive.head);
+        assert_eq!(read.to_bytes(), bytes);
+        assert_eq!(read.binding(), archive.binding());
+    }
+
+    #[test]
+    fn hand_written_archives_read_back() {
+        let bytes = single_commit(&format!("commit {}\0", COMMIT.len()));
+        let archive = Archive::from_bytes(&bytes).unwrap();
+        assert_eq!(archive.object_count(), 1);
+        assert_eq!(archive.to_bytes(), bytes);
+    }
+
+    #[test]
+    fn non_canonical_archives_are_rejected() {
+        // A padded size hashes to a different id for the same content
+        let padded = single_commit(&format!("commit 0{}\0", COMMIT.len()));
+        assert!(matches!(
+            Archive::from_bytes(&padded),
+            Err(Error::Archive(message)) if message.contains("canonical")
+        ));
+
+        let mut swapped = MAGIC.to_vec();
+        swapped.extend(b"refs/heads/main HEAD\n\n");
+        assert!(Archive::from_bytes(&swapped).is_err());
+        assert!(Archive::from_bytes(b"PACK").is_err());
+    }
+
+    #[test]
+    fn truncated_and_oversized_objects_are_rejected() {
+        let bytes = single_commit(&format!("commit {}\0", COMMIT.len()));
+        for len in [MAGIC.len(), bytes.len() - COMMIT.len() - 1, bytes.len() - 1] {
+            assert!(Archive::from_bytes(&bytes[..len]).is_err());
+        }
+
+        for size in [COMMIT.len() + 1, usize::MAX, usize::MAX - 1] {
+            let bytes = single_commit(&format!("commit {size}\0"));
+            assert!(matches!(
+                Archive::from_bytes(&bytes),
+                Err(Error::Archive(message)) if message == "truncated object"
+            ));
+        }
+    }
+}
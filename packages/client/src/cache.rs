@@ -0,0 +1,282 @@
+//! On-disk account cache.
+//!
+//! [`CachedClient`] keeps the raw data of every account it reads in a sled
+//! database, with the slot it was read at. Reads are stale-while-revalidate:
+//! anything cached is returned at once, and entries older than the maximum
+//! age are refreshed in the background. Short-lived processes should await
+//! [`CachedClient::settle`] before exiting so those refreshes land.
+
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anchor_lang::AccountDeserialize;
+use solana_sdk::{account::Account, pubkey::Pubkey};
+use tokio::task::JoinHandle;
+use vanish_program::Repository;
+
+use crate::cluster::Cluster;
+use crate::{client::decode, Error, Result, VanishClient};
+
+/// Slot the data was read at, then when it was fetched, in seconds
+const HEADER_LEN: usize = 8 + 8;
+
+struct Entry {
+    slot: u64,
+    fetched_at: u64,
+    data: Vec<u8>,
+}
+
+impl Entry {
+    fn encode(slot: u64, data: &[u8]) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_LEN + data.len());
+        bytes.extend_from_slice(&slot.to_le_bytes());
+        bytes.extend_from_slice(&now().to_le_bytes());
+        bytes.extend_from_slice(data);
+        bytes
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        Some(Self {
+            slot: u64::from_le_bytes(bytes.get(..8)?.try_into().ok()?),
+            fetched_at: u64::from_le_bytes(bytes.get(8..HEADER_LEN)?.try_into().ok()?),
+            data: bytes[HEADER_LEN..].to_vec(),
+        })
+    }
+
+    fn is_fresh(&self, max_age: Duration) -> bool {
+        now().saturating_sub(self.fetched_at) < max_age.as_secs()
+    }
+}
+
+/// The cache database: account data by address, and the addresses of each
+/// creator's repositories by creator.
+#[derive(Clone)]
+pub struct Cache {
+    accounts: sled::Tree,
+    repositories: sled::Tree,
+    max_age: Duration,
+}
+
+impl Cache {
+    /// Open or create the cache at `path`. Entries go stale after a minute.
+    pub fn open(path: &Path) -> Result<Self> {
+        Self::from_db(sled::open(path)?)
+    }
+
+    /// A cache deleted when dropped, for when the on-disk one is locked by
+    /// another process.
+    pub fn temporary() -> Result<Self> {
+        Self::from_db(sled::Config::new().temporary(true).open()?)
+    }
+
+    fn from_db(db: sled::Db) -> Result<Self> {
+        Ok(Self {
+            accounts: db.open_tree("accounts")?,
+            repositories: db.open_tree("repositories")?,
+            max_age: Duration::from_secs(60),
+        })
+    }
+
+    /// `$XDG_CACHE_HOME/vanish`, falling back to `~/.cache/vanish`.
+    pub fn default_path() -> PathBuf {
+        let base = std::env::var_os("XDG_CACHE_HOME")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| {
+                PathBuf::from(std::env::var_os("HOME").unwrap_or_default()).join(".cache")
+            });
+        base.join("vanish")
+    }
+
+    /// `vanish-<cluster>` next to `default_path`, so accounts from different
+    /// clusters never share a cache.
+    pub fn cluster_path(cluster: Cluster) -> PathBuf {
+        Self::default_path().with_file_name(format!("vanish-{cluster}"))
+    }
+
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = max_age;
+        self
+    }
+
+    fn account(&self, address: &Pubkey) -> Result<Option<Entry>> {
+        Ok(self
+            .accounts
+            .get(address)?
+            .and_then(|bytes| Entry::decode(&bytes)))
+    }
+
+    /// Record `account` as read at `slot`, unless something newer is cached.
+    /// `None` (the account is gone) drops the entry.
+    fn store_account(&self, address: &Pubkey, slot: u64, account: Option<&Account>) -> Result<()> {
+        if self
+            .account(address)?
+            .is_some_and(|entry| entry.slot > slot)
+        {
+            return Ok(());
+        }
+        match account {
+            Some(account) if account.owner == crate::program_id() => {
+                self.accounts
+                    .insert(address, Entry::encode(slot, &account.data))?;
+            }
+            _ => {
+                self.accounts.remove(address)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn repositories(&self, creator: &Pubkey) -> Result<Option<(Entry, Vec<Pubkey>)>> {
+        let Some(entry) = self
+            .repositories
+            .get(creator)?
+            .and_then(|bytes| Entry::decode(&bytes))
+        else {
+            return Ok(None);
+        };
+        let addresses = entry
+            .data
+            .chunks_exact(32)
+            .map(|address| Pubkey::try_from(address).expect("32-byte chunk"))
+            .collect();
+        Ok(Some((entry, addresses)))
+    }
+
+    fn store_repositories(
+        &self,
+        creator: &Pubkey,
+        slot: u64,
+        accounts: &[(Pubkey, Account)],
+    ) -> Result<()> {
+        let mut addresses = Vec::with_capacity(accounts.len() * 32);
+        for (address, account) in accounts {
+            self.store_account(address, slot, Some(account))?;
+            addresses.extend_from_slice(address.as_ref());
+        }
+        self.repositories
+            .insert(creator, Entry::encode(slot, &addresses))?;
+        Ok(())
+    }
+}
+
+/// A [`VanishClient`] whose reads go through a [`Cache`].
+pub struct CachedClient {
+    client: Arc<VanishClient>,
+    cache: Cache,
+    /// Background refreshes not yet awaited
+    refreshing: Mutex<Vec<JoinHandle<()>>>,
+}
+
+impl CachedClient {
+    pub fn new(client: VanishClient, cache: Cache) -> Self {
+        Self {
+            client: Arc::new(client),
+            cache,
+            refreshing: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// The uncached client, for writes and anything not cached.
+    pub fn client(&self) -> &VanishClient {
+        &self.client
+    }
+
+    /// Like [`VanishClient::account`], served from the cache when possible.
+    pub async fn account<T: AccountDeserialize>(&self, address: &Pubkey) -> Result<T> {
+        if let Some(entry) = self.cache.account(address)? {
+            // Data cached under an older layout is refetched instead
+            if let Ok(value) = decode(address, &crate::program_id(), &entry.data) {
+                if !entry.is_fresh(self.cache.max_age) {
+                    self.refresh_account(*address);
+                }
+                return Ok(value);
+            }
+        }
+
+        let (slot, account) = self.client.raw_account(address).await?;
+        self.cache.store_account(address, slot, account.as_ref())?;
+        let account = account.ok_or(Error::AccountNotFound(*address))?;
+        decode(address, &account.owner, &account.data)
+    }
+
+    /// Like [`VanishClient::repositories`], served from the cache when
+    /// possible. Repositories whose cached data no longer decodes are left
+    /// out until the refresh lands.
+    pub async fn repositories(&self, creator: &Pubkey) -> Result<Vec<(Pubkey, Repository)>> {
+        if let Some((entry, addresses)) = self.cache.repositories(creator)? {
+            if !entry.is_fresh(self.cache.max_age) {
+                self.refresh_repositories(*creator);
+            }
+            let mut repositories = Vec::with_capacity(addresses.len());
+            for address in addresses {
+                if let Some(entry) = self.cache.account(&address)? {
+                    if let Ok(repo) = decode(&address, &crate::program_id(), &entry.data) {
+                        repositories.push((address, repo));
+                    }
+                }
+            }
+            return Ok(repositories);
+        }
+
+        let (slot, accounts) = self.client.raw_repositories(creator).await?;
+        self.cache.store_repositories(creator, slot, &accounts)?;
+        accounts
+            .iter()
+            .map(|(address, account)| {
+                decode(address, &account.owner, &account.data).map(|repo| (*address, repo))
+            })
+            .collect()
+    }
+
+    /// Drop what is cached for `address`, after a write changed it.
+    pub fn invalidate(&self, address: &Pubkey) -> Result<()> {
+        self.cache.accounts.remove(address)?;
+        Ok(())
+    }
+
+    /// Drop the cached list of `creator`'s repositories, after one was
+    /// created.
+    pub fn invalidate_repositories(&self, creator: &Pubkey) -> Result<()> {
+        self.cache.repositories.remove(creator)?;
+        Ok(())
+    }
+
+    /// Wait for background refreshes to finish.
+    pub async fn settle(&self) {
+        let pending = std::mem::take(&mut *self.refreshing.lock().unwrap());
+        for task in pending {
+            // A failed refresh leaves the stale entry for next time
+            let _ = task.await;
+        }
+    }
+
+    fn refresh_account(&self, address: Pubkey) {
+        let (client, cache) = (self.client.clone(), self.cache.clone());
+        self.spawn(async move {
+            if let Ok((slot, account)) = client.raw_account(&address).await {
+                let _ = cache.store_account(&address, slot, account.as_ref());
+            }
+        });
+    }
+
+    fn refresh_repositories(&self, creator: Pubkey) {
+        let (client, cache) = (self.client.clone(), self.cache.clone());
+        self.spawn(async move {
+            if let Ok((slot, accounts)) = client.raw_repositories(&creator).await {
+                let _ = cache.store_repositories(&creator, slot, &accounts);
+            }
+        });
+    }
+
+    fn spawn(&self, refresh: impl std::future::Future<Output = ()> + Send + 'static) {
+        self.refreshing.lock().unwrap().push(tokio::spawn(refresh));
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
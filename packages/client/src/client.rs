@@ -0,0 +1,759 @@
+use std::time::Duration;
+
+use anchor_lang::{AccountDeserialize, AnchorDeserialize, Discriminator};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{
+    client_error::ClientError,
+    nonblocking::rpc_client::RpcClient,
+    nonce_utils,
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig, RpcSimulateTransactionConfig},
+    rpc_filter::{Memcmp, RpcFilterType},
+};
+use solana_sdk::{
+    account::Account,
+    address_lookup_table::{self, state::AddressLookupTable, AddressLookupTableAccount},
+    commitment_config::CommitmentConfig,
+    instruction::Instruction,
+    message::{v0, CompileError, Message, VersionedMessage},
+    nonce::State as NonceState,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature},
+    signer::{Signer, SignerError},
+    system_instruction,
+    transaction::{Transaction, TransactionError, VersionedTransaction},
+};
+use vanish_program::{
+    AccessKey, Branch, Cid, Collaborator, CollaboratorSlot, Config, KeyEnvelope, Multihash,
+    ProjectRepo, PushNote, Repository, Star,
+};
+
+use crate::archive::Archive;
+use crate::cluster::Profile;
+use crate::compute_budget::ComputeBudget;
+use crate::encryption::{self, RepoKey};
+use crate::instructions::{self, ProjectPush};
+use crate::offline;
+use crate::pda;
+use crate::storage::Storage;
+use crate::subscribe::{self, ProgramLogs, Subscription};
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    Rpc(#[from] Box<ClientError>),
+    #[error(transparent)]
+    Signer(#[from] SignerError),
+    #[error(transparent)]
+    Compile(#[from] CompileError),
+    #[error(transparent)]
+    Nonce(#[from] nonce_utils::Error),
+    #[error("payload is not an encoded transaction")]
+    Payload,
+    #[error("invalid snapshot: {0}")]
+    Snapshot(String),
+    #[error("transaction is missing signatures from {0:?}")]
+    MissingSignatures(Vec<Pubkey>),
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+    #[error("{0}")]
+    Storage(String),
+    #[error("invalid archive: {0}")]
+    Archive(String),
+    #[error("archive does not match its push: {0}")]
+    Binding(String),
+    #[error("{0}")]
+    Encryption(String),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("{0} is not a CID")]
+    Cid(String),
+    #[error("{0} is not a program id")]
+    ProgramId(String),
+    #[error("already talking to the program at {0}")]
+    ProgramInUse(Pubkey),
+    #[cfg(feature = "cache")]
+    #[error(transparent)]
+    Cache(#[from] sled::Error),
+    #[error("transaction simulation failed: {err}")]
+    Simulation {
+        err: TransactionError,
+        logs: Vec<String>,
+    },
+    #[error("the program returned no value")]
+    NoReturnData,
+    #[error("account {0} does not exist")]
+    AccountNotFound(Pubkey),
+    #[error("account {0} is not owned by the Vanish program")]
+    WrongOwner(Pubkey),
+    #[error("account {0} is not an address lookup table")]
+    NotLookupTable(Pubkey),
+    #[error("account {address} could not be decoded")]
+    Decode {
+        address: Pubkey,
+        source: anchor_lang::error::Error,
+    },
+}
+
+impl From<ClientError> for Error {
+    fn from(err: ClientError) -> Self {
+        Self::Rpc(Box::new(err))
+    }
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Offset of `Repository::creator` in account data (discriminator + owner)
+const CREATOR_OFFSET: usize = 8 + 32;
+
+/// Addresses per extend_lookup_table transaction, leaving room in the
+/// packet for the instruction's own accounts and signatures
+const LOOKUP_TABLE_CHUNK: usize = 20;
+
+/// How often to poll for a new slot while a lookup table warms up
+const SLOT_POLL_INTERVAL: Duration = Duration::from_millis(400);
+
+/// Async client for the Vanish program.
+pub struct VanishClient {
+    rpc: RpcClient,
+    ws_url: String,
+    budget: ComputeBudget,
+}
+
+impl VanishClient {
+    /// Connect to `url` at `confirmed` commitment.
+    pub fn new(url: String) -> Self {
+        Self::from_rpc(RpcClient::new_with_commitment(
+            url,
+            CommitmentConfig::confirmed(),
+        ))
+    }
+
+    /// Connect to `profile`'s cluster at `confirmed` commitment, deriving
+    /// addresses under its program id from here on. A process talks to one
+    /// deployment, so this fails once another program id is in use.
+    pub fn from_profile(profile: &Profile) -> Result<Self> {
+        vanish_interface::use_program_id(profile.program_id).map_err(Error::ProgramInUse)?;
+        Ok(Self::new(profile.rpc_url.clone()).with_websocket(profile.ws_url.clone()))
+    }
+
+    /// Wrap an RPC client, subscribing over the websocket endpoint paired
+    /// with its URL.
+    pub fn from_rpc(rpc: RpcClient) -> Self {
+        let ws_url = subscribe::websocket_url(&rpc.url());
+        Self {
+            rpc,
+            ws_url,
+            budget: ComputeBudget::default(),
+        }
+    }
+
+    /// Subscribe over `ws_url` instead of the endpoint derived from the RPC
+    /// URL.
+    pub fn with_websocket(mut self, ws_url: String) -> Self {
+        self.ws_url = ws_url;
+        self
+    }
+
+    /// Set the compute unit limit and priority fee of every transaction
+    /// `send` sends.
+    pub fn with_compute_budget(mut self, budget: ComputeBudget) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    pub fn compute_budget(&self) -> ComputeBudget {
+        self.budget
+    }
+
+    pub fn rpc(&self) -> &RpcClient {
+        &self.rpc
+    }
+
+    /// Fetch and decode a program account, checking its owner and
+    /// discriminator.
+    pub async fn account<T: AccountDeserialize>(&self, address: &Pubkey) -> Result<T> {
+        self.try_account(address)
+            .await?
+            .ok_or(Error::AccountNotFound(*address))
+    }
+
+    /// Like `account`, but `None` when the account does not exist.
+    pub async fn try_account<T: AccountDeserialize>(&self, address: &Pubkey) -> Result<Option<T>> {
+        let (_, account) = self.raw_account(address).await?;
+        account
+            .map(|account| decode(address, &account.owner, &account.data))
+            .transpose()
+    }
+
+    /// The undecoded account and the slot it was read at.
+    pub(crate) async fn raw_account(&self, address: &Pubkey) -> Result<(u64, Option<Account>)> {
+        let response = self
+            .rpc
+            .get_account_with_commitment(address, self.rpc.commitment())
+            .await?;
+        Ok((response.context.slot, response.value))
+    }
+
+    pub async fn config(&self) -> Result<Config> {
+        self.account(&pda::config()).await
+    }
+
+    /// The repository `creator` created as `name`.
+    pub async fn repository(&self, creator: &Pubkey, name: &str) -> Result<Repository> {
+        self.account(&pda::repo(creator, name)).await
+    }
+
+    /// Every repository `creator` created that still exists, by address.
+    pub async fn repositories(&self, creator: &Pubkey) -> Result<Vec<(Pubkey, Repository)>> {
+        let (_, accounts) = self.raw_repositories(creator).await?;
+        accounts
+            .iter()
+            .map(|(address, account)| {
+                decode(address, &account.owner, &account.data).map(|repo| (*address, repo))
+            })
+            .collect()
+    }
+
+    /// The undecoded repository accounts and a slot at or before which they
+    /// were read.
+    pub(crate) async fn raw_repositories(
+        &self,
+        creator: &Pubkey,
+    ) -> Result<(u64, Vec<(Pubkey, Account)>)> {
+        let slot = self.rpc.get_slot().await?;
+        let filters = vec![
+            RpcFilterType::Memcmp(Memcmp::new_base58_encoded(0, &Repository::DISCRIMINATOR)),
+            RpcFilterType::Memcmp(Memcmp::new_base58_encoded(CREATOR_OFFSET, creator.as_ref())),
+        ];
+        let accounts = self
+            .rpc
+            .get_program_accounts_with_config(
+                &crate::program_id(),
+                RpcProgramAccountsConfig {
+                    filters: Some(filters),
+                    account_config: RpcAccountInfoConfig {
+                        encoding: Some(UiAccountEncoding::Base64),
+                        commitment: Some(self.rpc.commitment()),
+                        ..RpcAccountInfoConfig::default()
+                    },
+                    ..RpcProgramAccountsConfig::default()
+                },
+            )
+            .await?;
+        Ok((slot, accounts))
+    }
+
+    pub async fn branch(&self, repo: &Repository, name: &str) -> Result<Branch> {
+        self.account(&pda::branch(&repo.id, name)).await
+    }
+
+    pub async fn collaborator(&self, repo: &Repository, user: &Pubkey) -> Result<Collaborator> {
+        self.account(&pda::collab(&repo.id, user)).await
+    }
+
+    pub async fn star(&self, repo: &Repository, user: &Pubkey) -> Result<Option<Star>> {
+        self.try_account(&pda::star(user, &repo.id)).await
+    }
+
+    /// Stream the decoded account at `address`, starting with its current
+    /// state and then on every change.
+    pub fn watch_account<T>(&self, address: &Pubkey) -> Subscription<T>
+    where
+        T: AccountDeserialize + Send + 'static,
+    {
+        subscribe::watch_account(
+            self.rpc.url(),
+            self.ws_url.clone(),
+            self.rpc.commitment(),
+            *address,
+        )
+    }
+
+    pub fn watch_repository(&self, address: &Pubkey) -> Subscription<Repository> {
+        self.watch_account(address)
+    }
+
+    /// Stream the events of every successful transaction that invokes the
+    /// program.
+    pub fn watch_events(&self) -> Subscription<ProgramLogs> {
+        subscribe::watch_logs(self.ws_url.clone(), self.rpc.commitment())
+    }
+
+    /// Every indexed collaborator, in slot order. Collaborators added before
+    /// index slots existed are missing until index_collaborator runs.
+    pub async fn collaborators(&self, repo: &Repository) -> Result<Vec<Collaborator>> {
+        let slots = (0..repo.collaborator_count)
+            .map(|index| pda::collab_slot(&repo.id, index))
+            .collect::<Vec<_>>();
+        let slots = self.multiple::<CollaboratorSlot>(&slots).await?;
+
+        let collaborators = slots
+            .iter()
+            .map(|slot| pda::collab(&repo.id, &slot.user))
+            .collect::<Vec<_>>();
+        self.multiple(&collaborators).await
+    }
+
+    /// Fetch several accounts of one type, skipping any that do not exist.
+    async fn multiple<T: AccountDeserialize>(&self, addresses: &[Pubkey]) -> Result<Vec<T>> {
+        // getMultipleAccounts takes at most 100 keys
+        let mut decoded = Vec::with_capacity(addresses.len());
+        for chunk in addresses.chunks(100) {
+            let accounts = self.rpc.get_multiple_accounts(chunk).await?;
+            for (address, account) in chunk.iter().zip(accounts) {
+                if let Some(account) = account {
+                    decoded.push(decode(address, &account.owner, &account.data)?);
+                }
+            }
+        }
+        Ok(decoded)
+    }
+
+    /// Sign `instructions` with `payer` and `signers`, send them behind the
+    /// client's compute budget instructions and wait for confirmation.
+    pub async fn send(
+        &self,
+        instructions: &[Instruction],
+        payer: &Keypair,
+        signers: &[&Keypair],
+    ) -> Result<Signature> {
+        let budgeted = self.budgeted(instructions, payer, &[]).await?;
+        let blockhash = self.rpc.get_latest_blockhash().await?;
+        let mut transaction = Transaction::new_with_payer(&budgeted, Some(&payer.pubkey()));
+        transaction.try_partial_sign(&[payer], blockhash)?;
+        transaction.try_sign(signers, blockhash)?;
+        Ok(self.rpc.send_and_confirm_transaction(&transaction).await?)
+    }
+
+    /// Like `send`, but as a v0 transaction that can name accounts through
+    /// the lookup tables `tables`, for batches too large for a legacy one.
+    pub async fn send_versioned(
+        &self,
+        instructions: &[Instruction],
+        payer: &Keypair,
+        signers: &[&Keypair],
+        tables: &[AddressLookupTableAccount],
+    ) -> Result<Signature> {
+        let budgeted = self.budgeted(instructions, payer, tables).await?;
+        let blockhash = self.rpc.get_latest_blockhash().await?;
+        let message = v0::Message::try_compile(&payer.pubkey(), &budgeted, tables, blockhash)?;
+
+        let mut keypairs = Vec::with_capacity(signers.len() + 1);
+        keypairs.push(payer);
+        keypairs.extend(
+            signers
+                .iter()
+                .filter(|signer| signer.pubkey() != payer.pubkey()),
+        );
+        let transaction = VersionedTransaction::try_new(VersionedMessage::V0(message), &keypairs)?;
+        Ok(self.rpc.send_and_confirm_transaction(&transaction).await?)
+    }
+
+    /// `instructions` behind the client's compute budget instructions.
+    async fn budgeted(
+        &self,
+        instructions: &[Instruction],
+        payer: &Keypair,
+        tables: &[AddressLookupTableAccount],
+    ) -> Result<Vec<Instruction>> {
+        let mut budgeted = self
+            .budget
+            .instructions(&self.rpc, instructions, &payer.pubkey(), tables)
+            .await?;
+        budgeted.extend_from_slice(instructions);
+        Ok(budgeted)
+    }
+
+    /// Create a durable nonce account that `authority` advances, for
+    /// `prepare_offline`.
+    pub async fn create_nonce_account(
+        &self,
+        payer: &Keypair,
+        nonce: &Keypair,
+        authority: &Pubkey,
+    ) -> Result<Signature> {
+        let rent = self
+            .rpc
+            .get_minimum_balance_for_rent_exemption(NonceState::size())
+            .await?;
+        let create = system_instruction::create_nonce_account(
+            &payer.pubkey(),
+            &nonce.pubkey(),
+            authority,
+            rent,
+        );
+        // Not budgeted: the nonce account does not exist to simulate against
+        let blockhash = self.rpc.get_latest_blockhash().await?;
+        let transaction = Transaction::new_signed_with_payer(
+            &create,
+            Some(&payer.pubkey()),
+            &[payer, nonce],
+            blockhash,
+        );
+        Ok(self.rpc.send_and_confirm_transaction(&transaction).await?)
+    }
+
+    /// Build an unsigned transaction of `instructions` against the durable
+    /// nonce in `nonce`, which `nonce_authority` advances. It stays valid
+    /// until the nonce advances, so it can be signed offline with
+    /// `offline::sign` and sent much later with `submit`.
+    pub async fn prepare_offline(
+        &self,
+        instructions: &[Instruction],
+        payer: &Pubkey,
+        nonce: &Pubkey,
+        nonce_authority: &Pubkey,
+    ) -> Result<Transaction> {
+        let account = nonce_utils::nonblocking::get_account_with_commitment(
+            &self.rpc,
+            nonce,
+            self.rpc.commitment(),
+        )
+        .await?;
+        let durable_nonce = nonce_utils::data_from_account(&account)?.blockhash();
+
+        // The nonce advance has to come first for the runtime to accept the
+        // nonce in place of a recent blockhash
+        let mut prepared = vec![system_instruction::advance_nonce_account(
+            nonce,
+            nonce_authority,
+        )];
+        prepared.extend(
+            self.budget
+                .instructions(&self.rpc, instructions, payer, &[])
+                .await?,
+        );
+        prepared.extend_from_slice(instructions);
+
+        let mut transaction = Transaction::new_with_payer(&prepared, Some(payer));
+        transaction.message.recent_blockhash = durable_nonce;
+        Ok(transaction)
+    }
+
+    /// Send a transaction from `prepare_offline` once everyone has signed it.
+    pub async fn submit(&self, transaction: &Transaction) -> Result<Signature> {
+        let missing = offline::missing_signers(transaction);
+        if !missing.is_empty() {
+            return Err(Error::MissingSignatures(missing));
+        }
+        Ok(self.rpc.send_and_confirm_transaction(transaction).await?)
+    }
+
+    /// Fetch the lookup table at `address` for `send_versioned`.
+    pub async fn lookup_table(&self, address: &Pubkey) -> Result<AddressLookupTableAccount> {
+        let (_, account) = self.raw_account(address).await?;
+        let account = account.ok_or(Error::AccountNotFound(*address))?;
+        if account.owner != address_lookup_table::program::ID {
+            return Err(Error::NotLookupTable(*address));
+        }
+        let table = AddressLookupTable::deserialize(&account.data)
+            .map_err(|_| Error::NotLookupTable(*address))?;
+        Ok(AddressLookupTableAccount {
+            key: *address,
+            addresses: table.addresses.to_vec(),
+        })
+    }
+
+    /// Create a lookup table that `authority` controls and pays for, holding
+    /// `addresses`, and wait until it can be used. Returns its address.
+    pub async fn create_lookup_table(
+        &self,
+        authority: &Keypair,
+        addresses: &[Pubkey],
+    ) -> Result<Pubkey> {
+        // The table's address derives from a slot the runtime still
+        // remembers, so take a rooted one
+        let recent_slot = self
+            .rpc
+            .get_slot_with_commitment(CommitmentConfig::finalized())
+            .await?;
+        let (create, table) = address_lookup_table::instruction::create_lookup_table(
+            authority.pubkey(),
+            authority.pubkey(),
+            recent_slot,
+        );
+        self.send(&[create], authority, &[]).await?;
+        self.extend_lookup_table(authority, &table, addresses)
+            .await?;
+        Ok(table)
+    }
+
+    /// Append the `addresses` that the lookup table at `table` does not hold
+    /// yet, and wait until they can be used.
+    pub async fn extend_lookup_table(
+        &self,
+        authority: &Keypair,
+        table: &Pubkey,
+        addresses: &[Pubkey],
+    ) -> Result<()> {
+        let held = self.lookup_table(table).await?.addresses;
+        let mut missing = Vec::new();
+        for address in addresses {
+            if !held.contains(address) && !missing.contains(address) {
+                missing.push(*address);
+            }
+        }
+        if missing.is_empty() {
+            return Ok(());
+        }
+
+        for chunk in missing.chunks(LOOKUP_TABLE_CHUNK) {
+            let extend = address_lookup_table::instruction::extend_lookup_table(
+                *table,
+                authority.pubkey(),
+                Some(authority.pubkey()),
+                chunk.to_vec(),
+            );
+            self.send(&[extend], authority, &[]).await?;
+        }
+
+        // Addresses only resolve from the slot after they were added
+        let extended_at = self.rpc.get_slot().await?;
+        while self.rpc.get_slot().await? <= extended_at {
+            tokio::time::sleep(SLOT_POLL_INTERVAL).await;
+        }
+        Ok(())
+    }
+
+    /// Create a repository, staking the name if the config asks for it.
+    /// Returns its address.
+    pub async fn create_repo(
+        &self,
+        owner: &Keypair,
+        name: &str,
+        description: &str,
+        is_private: bool,
+    ) -> Result<Pubkey> {
+        let config = self.config().await?;
+        let stake_name = name.len() < config.stake_name_len as usize;
+        let create =
+            instructions::create_repo(&owner.pubkey(), name, description, is_private, stake_name);
+        self.send(&[create], owner, &[]).await?;
+        Ok(pda::repo(&owner.pubkey(), name))
+    }
+
+    /// Move the default branch of the repository at `address` forward to
+    /// `head_commit`, with an optional changelog and summary and the
+    /// archive's [binding](crate::archive::Archive::binding). Fails with
+    /// `StaleHead` if someone else pushed since the repository was read.
+    pub async fn push(
+        &self,
+        pusher: &Keypair,
+        address: &Pubkey,
+        head_commit: Multihash,
+        ipfs_cid: &str,
+        note: Option<PushNote>,
+        binding: Option<[u8; 32]>,
+    ) -> Result<Signature> {
+        let ipfs_cid = Cid::parse(ipfs_cid).ok_or_else(|| Error::Cid(ipfs_cid.to_string()))?;
+        let repo = self.account::<Repository>(address).await?;
+        let project = self.project_push(&repo, &pusher.pubkey()).await?;
+        let push = instructions::push_update(
+            &pusher.pubkey(),
+            &repo,
+            head_commit,
+            ipfs_cid,
+            Some(repo.head_commit.to_multihash()),
+            project.as_ref(),
+            note,
+            binding,
+        );
+        self.send(&[push], pusher, &[]).await
+    }
+
+    /// The project accounts `pusher` needs to push to `repo`, `None` unless
+    /// it is in a project. Pushers who are neither the owner nor a
+    /// collaborator with push access push as project members.
+    pub async fn project_push(
+        &self,
+        repo: &Repository,
+        pusher: &Pubkey,
+    ) -> Result<Option<ProjectPush>> {
+        let Some(link) = self
+            .try_account::<ProjectRepo>(&pda::project_repo(&repo.id))
+            .await?
+        else {
+            return Ok(None);
+        };
+        let as_member = repo.owner != *pusher
+            && !self
+                .try_account::<Collaborator>(&pda::collab(&repo.id, pusher))
+                .await?
+                .is_some_and(|collaborator| collaborator.can_push);
+        Ok(Some(ProjectPush {
+            project: link.project,
+            as_member,
+        }))
+    }
+
+    /// Simulate `instruction`, a read-only view, with `payer` as fee payer
+    /// and decode the value it returns. Nothing is signed or sent.
+    pub async fn view<T: AnchorDeserialize>(
+        &self,
+        payer: &Pubkey,
+        instruction: Instruction,
+    ) -> Result<T> {
+        let transaction = Transaction::new_unsigned(Message::new(&[instruction], Some(payer)));
+        let result = self
+            .rpc
+            .simulate_transaction_with_config(
+                &transaction,
+                RpcSimulateTransactionConfig {
+                    sig_verify: false,
+                    replace_recent_blockhash: true,
+                    commitment: Some(self.rpc.commitment()),
+                    ..RpcSimulateTransactionConfig::default()
+                },
+            )
+            .await?
+            .value;
+        if let Some(err) = result.err {
+            return Err(Error::Simulation {
+                err,
+                logs: result.logs.unwrap_or_default(),
+            });
+        }
+
+        let data = result
+            .return_data
+            .filter(|data| data.program_id == crate::program_id().to_string())
+            .ok_or(Error::NoReturnData)?;
+        let bytes = STANDARD
+            .decode(data.data.0)
+            .map_err(|_| Error::NoReturnData)?;
+        T::deserialize(&mut bytes.as_slice()).map_err(|_| Error::NoReturnData)
+    }
+
+    /// Whether `user` may push to `repo`, or to its `branch`, as the
+    /// program would decide now. `payer` only pays for the simulation's
+    /// fee, which is never charged.
+    pub async fn can_push(
+        &self,
+        payer: &Pubkey,
+        repo: &Repository,
+        user: &Pubkey,
+        branch: Option<&str>,
+    ) -> Result<bool> {
+        let project = self
+            .try_account::<ProjectRepo>(&pda::project_repo(&repo.id))
+            .await?
+            .map(|link| link.project);
+        let view = instructions::can_push(repo, user, branch, project.as_ref());
+        self.view(payer, view).await
+    }
+
+    /// Whether `user` may read `repo`, as the program would decide now.
+    pub async fn can_read(&self, payer: &Pubkey, repo: &Repository, user: &Pubkey) -> Result<bool> {
+        self.view(payer, instructions::can_read(repo, user)).await
+    }
+
+    /// `reader`'s copy of the current key of private `repo`, opened from
+    /// their AccessKey. Fails when the key was rotated since it was issued.
+    pub async fn repo_key(&self, repo: &Repository, reader: &Keypair) -> Result<RepoKey> {
+        let access_key: AccessKey = self
+            .account(&pda::access(&repo.id, &reader.pubkey()))
+            .await?;
+        if access_key.key_epoch != repo.key_epoch {
+            return Err(Error::Encryption(format!(
+                "access key is for key epoch {}, but the repository is at {}; ask an admin to reissue it",
+                access_key.key_epoch, repo.key_epoch
+            )));
+        }
+        encryption::open(&access_key.envelope, reader)
+    }
+
+    /// Add and pin `archive`, then record it as the new head of the
+    /// repository at `address`. Nothing is sent until the archive is
+    /// pinned. Deterministic archives are pushed with their binding. Returns
+    /// the archive's CID.
+    pub async fn push_archive(
+        &self,
+        storage: &Storage,
+        pusher: &Keypair,
+        address: &Pubkey,
+        head_commit: Multihash,
+        archive: Vec<u8>,
+    ) -> Result<(String, Signature)> {
+        let binding = Archive::is_archive(&archive)
+            .then(|| Archive::from_bytes(&archive))
+            .transpose()?
+            .map(|archive| archive.binding());
+        let cid = storage.add(archive).await?;
+        let signature = self
+            .push(pusher, address, head_commit, &cid, None, binding)
+            .await?;
+        Ok((cid, signature))
+    }
+
+    pub async fn star_repo(&self, user: &Keypair, address: &Pubkey, tip: u64) -> Result<Signature> {
+        let repo = self.account::<Repository>(address).await?;
+        let star = instructions::star_repo(&user.pubkey(), &repo, tip);
+        self.send(&[star], user, &[]).await
+    }
+
+    pub async fn unstar_repo(&self, user: &Keypair, address: &Pubkey) -> Result<Signature> {
+        let repo = self.account::<Repository>(address).await?;
+        let unstar = instructions::unstar_repo(&user.pubkey(), &repo);
+        self.send(&[unstar], user, &[]).await
+    }
+
+    pub async fn add_collaborator(
+        &self,
+        authority: &Keypair,
+        address: &Pubkey,
+        collaborator: &Pubkey,
+        can_push: bool,
+        key_envelope: Option<KeyEnvelope>,
+    ) -> Result<Signature> {
+        let repo = self.account::<Repository>(address).await?;
+        let add = instructions::add_collaborator(
+            &authority.pubkey(),
+            &repo,
+            collaborator,
+            can_push,
+            key_envelope,
+        );
+        self.send(&[add], authority, &[]).await
+    }
+
+    /// Remove `user`, looking up the last index slot the program needs to
+    /// fill the gap.
+    pub async fn remove_collaborator(
+        &self,
+        authority: &Keypair,
+        address: &Pubkey,
+        user: &Pubkey,
+    ) -> Result<Signature> {
+        let repo = self.account::<Repository>(address).await?;
+        let collaborator = self.collaborator(&repo, user).await?;
+        let last_slot = match (collaborator.index, repo.collaborator_count.checked_sub(1)) {
+            (Some(_), Some(last)) => Some(
+                self.account::<CollaboratorSlot>(&pda::collab_slot(&repo.id, last))
+                    .await?,
+            ),
+            _ => None,
+        };
+        let remove = instructions::remove_collaborator(
+            &authority.pubkey(),
+            &repo,
+            &collaborator,
+            last_slot.as_ref(),
+        );
+        self.send(&[remove], authority, &[]).await
+    }
+}
+
+pub(crate) fn decode<T: AccountDeserialize>(
+    address: &Pubkey,
+    owner: &Pubkey,
+    data: &[u8],
+) -> Result<T> {
+    if *owner != crate::program_id() {
+        return Err(Error::WrongOwner(*address));
+    }
+    T::try_deserialize(&mut &data[..]).map_err(|source| Error::Decode {
+        address: *address,
+        source,
+    })
+}
@@ -0,0 +1,127 @@
+//! Cluster profiles.
+//!
+//! A [`Profile`] gathers what it takes to talk to Vanish on one cluster:
+//! the RPC and websocket endpoints and the address the program is deployed
+//! at. [`Profile::new`] gives a cluster's defaults, and
+//! [`Profile::from_env`] lets `VANISH_RPC_URL`, `VANISH_WS_URL` and
+//! `VANISH_PROGRAM_ID` override them, so an integrator can point the same
+//! code at a local validator, devnet or mainnet.
+//!
+//! ```no_run
+//! # fn example() -> vanish_client::Result<()> {
+//! use vanish_client::{cluster::{Cluster, Profile}, VanishClient};
+//!
+//! let profile = Profile::from_env(Cluster::Localnet)?;
+//! let client = VanishClient::from_profile(&profile)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt;
+use std::str::FromStr;
+
+use solana_sdk::pubkey::Pubkey;
+
+use crate::{subscribe, Error, Result};
+
+/// A Solana cluster Vanish runs on.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Cluster {
+    /// A `solana-test-validator` on this machine
+    Localnet,
+    #[default]
+    Devnet,
+    Mainnet,
+}
+
+impl Cluster {
+    /// The public RPC endpoint, or the test validator's default one.
+    pub fn rpc_url(self) -> &'static str {
+        match self {
+            Self::Localnet => "http://127.0.0.1:8899",
+            Self::Devnet => "https://api.devnet.solana.com",
+            Self::Mainnet => "https://api.mainnet-beta.solana.com",
+        }
+    }
+
+    /// The address the program is deployed at. Every cluster uses the
+    /// address the program is built with; other deployments are picked with
+    /// `VANISH_PROGRAM_ID` or [`Profile::program_id`].
+    pub fn program_id(self) -> Pubkey {
+        crate::ID
+    }
+}
+
+impl FromStr for Cluster {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "localnet" | "localhost" => Ok(Self::Localnet),
+            "devnet" => Ok(Self::Devnet),
+            "mainnet" | "mainnet-beta" => Ok(Self::Mainnet),
+            other => Err(format!(
+                "unknown cluster {other}; expected localnet, devnet or mainnet"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Cluster {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::Localnet => "localnet",
+            Self::Devnet => "devnet",
+            Self::Mainnet => "mainnet",
+        })
+    }
+}
+
+/// Endpoints and program deployment for one cluster.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Profile {
+    pub cluster: Cluster,
+    pub rpc_url: String,
+    pub ws_url: String,
+    pub program_id: Pubkey,
+}
+
+impl Profile {
+    /// `cluster`'s defaults.
+    pub fn new(cluster: Cluster) -> Self {
+        Self {
+            cluster,
+            rpc_url: cluster.rpc_url().to_string(),
+            ws_url: subscribe::websocket_url(cluster.rpc_url()),
+            program_id: cluster.program_id(),
+        }
+    }
+
+    /// `cluster`'s defaults, overridden by whichever of `VANISH_RPC_URL`,
+    /// `VANISH_WS_URL` and `VANISH_PROGRAM_ID` are set.
+    pub fn from_env(cluster: Cluster) -> Result<Self> {
+        let mut profile = Self::new(cluster);
+        if let Ok(url) = std::env::var("VANISH_RPC_URL") {
+            profile = profile.with_rpc_url(url);
+        }
+        if let Ok(url) = std::env::var("VANISH_WS_URL") {
+            profile.ws_url = url;
+        }
+        if let Ok(id) = std::env::var("VANISH_PROGRAM_ID") {
+            profile.program_id = id.parse().map_err(|_| Error::ProgramId(id))?;
+        }
+        Ok(profile)
+    }
+
+    /// Use `url` for RPC, and the websocket endpoint paired with it.
+    pub fn with_rpc_url(mut self, url: String) -> Self {
+        self.ws_url = subscribe::websocket_url(&url);
+        self.rpc_url = url;
+        self
+    }
+
+    /// The deployment's Config account.
+    pub fn config(&self) -> Pubkey {
+        Pubkey::find_program_address(&[vanish_interface::seeds::CONFIG], &self.program_id).0
+    }
+}
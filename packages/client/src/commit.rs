@@ -0,0 +1,31 @@
+//! Conversion between hex git commit ids and the multihashes the program
+//! stores them as.
+
+use vanish_program::Multihash;
+
+/// Parse a 40 (SHA-1) or 64 (SHA-256) digit hex commit id.
+pub fn from_hex(hex: &str) -> Option<Multihash> {
+    let code = match hex.len() {
+        40 => Multihash::SHA1,
+        64 => Multihash::SHA2_256,
+        _ => return None,
+    };
+    if !hex.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let digest = (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).expect("checked hex digits"))
+        .collect();
+    Some(Multihash { code, digest })
+}
+
+/// The commit id as git prints it; empty for a repository never pushed.
+pub fn to_hex(commit: &Multihash) -> String {
+    commit
+        .digest
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
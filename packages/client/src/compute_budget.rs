@@ -0,0 +1,230 @@
+//! Compute budget and priority fees.
+//!
+//! [`VanishClient::send`](crate::VanishClient::send) prepends compute budget
+//! instructions to every transaction it sends, as a [`ComputeBudget`] says.
+//! By default the compute unit limit comes from simulating the transaction
+//! and no priority fee is paid; both can be fixed or derived from recent
+//! fees on the accounts the transaction writes.
+
+use std::str::FromStr;
+
+use solana_client::{nonblocking::rpc_client::RpcClient, rpc_config::RpcSimulateTransactionConfig};
+use solana_sdk::{
+    address_lookup_table::AddressLookupTableAccount,
+    compute_budget::ComputeBudgetInstruction,
+    hash::Hash,
+    instruction::Instruction,
+    message::{v0, VersionedMessage},
+    pubkey::Pubkey,
+    signature::Signature,
+    transaction::VersionedTransaction,
+};
+
+use crate::{Error, Result};
+
+/// Most compute units a transaction can ask for.
+pub const MAX_COMPUTE_UNITS: u32 = 1_400_000;
+
+/// Headroom added to simulated usage, in percent, since state can change
+/// between simulation and execution.
+pub const DEFAULT_MARGIN_PERCENT: u32 = 10;
+
+/// Percentile of recent fees `auto` pays.
+pub const DEFAULT_FEE_PERCENTILE: u8 = 75;
+
+/// Highest price, in micro-lamports per compute unit, `auto` pays.
+pub const DEFAULT_MAX_PRICE: u64 = 1_000_000;
+
+/// How the compute unit limit is set.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ComputeUnits {
+    /// Leave the runtime's default limit in place
+    Default,
+    Fixed(u32),
+    /// Simulate the transaction and ask for what it used plus a margin
+    Simulate {
+        margin_percent: u32,
+    },
+}
+
+/// How the priority fee is set, in micro-lamports per compute unit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PriorityFee {
+    None,
+    Fixed(u64),
+    /// Pay the given percentile of the fees recently paid to write the
+    /// accounts the transaction writes, up to `max`
+    Recent {
+        percentile: u8,
+        max: u64,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ComputeBudget {
+    pub units: ComputeUnits,
+    pub fee: PriorityFee,
+}
+
+impl Default for ComputeBudget {
+    fn default() -> Self {
+        Self {
+            units: ComputeUnits::Simulate {
+                margin_percent: DEFAULT_MARGIN_PERCENT,
+            },
+            fee: PriorityFee::None,
+        }
+    }
+}
+
+impl ComputeBudget {
+    /// The compute budget instructions to put ahead of `instructions`, paid
+    /// for by `payer` and sent with lookup tables `tables`.
+    pub async fn instructions(
+        &self,
+        rpc: &RpcClient,
+        instructions: &[Instruction],
+        payer: &Pubkey,
+        tables: &[AddressLookupTableAccount],
+    ) -> Result<Vec<Instruction>> {
+        let mut budget = Vec::with_capacity(2);
+        if let Some(units) = self.unit_limit(rpc, instructions, payer, tables).await? {
+            budget.push(ComputeBudgetInstruction::set_compute_unit_limit(units));
+        }
+        if let Some(price) = self.unit_price(rpc, instructions).await? {
+            budget.push(ComputeBudgetInstruction::set_compute_unit_price(price));
+        }
+        Ok(budget)
+    }
+
+    async fn unit_limit(
+        &self,
+        rpc: &RpcClient,
+        instructions: &[Instruction],
+        payer: &Pubkey,
+        tables: &[AddressLookupTableAccount],
+    ) -> Result<Option<u32>> {
+        let margin_percent = match self.units {
+            ComputeUnits::Default => return Ok(None),
+            ComputeUnits::Fixed(units) => return Ok(Some(units)),
+            ComputeUnits::Simulate { margin_percent } => margin_percent,
+        };
+
+        // Simulate under the maximum limit so the estimate is not capped by
+        // the default one
+        let mut simulated = vec![ComputeBudgetInstruction::set_compute_unit_limit(
+            MAX_COMPUTE_UNITS,
+        )];
+        simulated.extend_from_slice(instructions);
+        let message = v0::Message::try_compile(payer, &simulated, tables, Hash::default())?;
+        let transaction = VersionedTransaction {
+            signatures: vec![
+                Signature::default();
+                usize::from(message.header.num_required_signatures)
+            ],
+            message: VersionedMessage::V0(message),
+        };
+        let result = rpc
+            .simulate_transaction_with_config(
+                &transaction,
+                RpcSimulateTransactionConfig {
+                    sig_verify: false,
+                    replace_recent_blockhash: true,
+                    commitment: Some(rpc.commitment()),
+                    ..RpcSimulateTransactionConfig::default()
+                },
+            )
+            .await?
+            .value;
+        if let Some(err) = result.err {
+            return Err(Error::Simulation {
+                err,
+                logs: result.logs.unwrap_or_default(),
+            });
+        }
+
+        // Nodes that do not report usage get the default limit
+        Ok(result.units_consumed.map(|used| {
+            let units = used.saturating_mul(100 + u64::from(margin_percent)) / 100;
+            units.min(u64::from(MAX_COMPUTE_UNITS)) as u32
+        }))
+    }
+
+    async fn unit_price(
+        &self,
+        rpc: &RpcClient,
+        instructions: &[Instruction],
+    ) -> Result<Option<u64>> {
+        match self.fee {
+            PriorityFee::None => Ok(None),
+            PriorityFee::Fixed(price) => Ok(Some(price)),
+            PriorityFee::Recent { percentile, max } => {
+                let mut writable = instructions
+                    .iter()
+                    .flat_map(|instruction| &instruction.accounts)
+                    .filter(|meta| meta.is_writable)
+                    .map(|meta| meta.pubkey)
+                    .collect::<Vec<_>>();
+                writable.sort_unstable();
+                writable.dedup();
+
+                let mut fees = rpc
+                    .get_recent_prioritization_fees(&writable)
+                    .await?
+                    .into_iter()
+                    .map(|fee| fee.prioritization_fee)
+                    .collect::<Vec<_>>();
+                Ok(percentile_of(&mut fees, percentile).map(|price| price.min(max)))
+            }
+        }
+    }
+}
+
+/// The `percentile`th smallest of `values`, `None` when there are none.
+fn percentile_of(values: &mut [u64], percentile: u8) -> Option<u64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_unstable();
+    let index = (values.len() - 1) * usize::from(percentile.min(100)) / 100;
+    Some(values[index])
+}
+
+/// `auto`, `default` or a number of units.
+impl FromStr for ComputeUnits {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(Self::Simulate {
+                margin_percent: DEFAULT_MARGIN_PERCENT,
+            }),
+            "default" => Ok(Self::Default),
+            units => match units.parse() {
+                Ok(units) if units <= MAX_COMPUTE_UNITS => Ok(Self::Fixed(units)),
+                _ => Err(format!(
+                    "expected `auto`, `default` or at most {MAX_COMPUTE_UNITS} units, got {s}"
+                )),
+            },
+        }
+    }
+}
+
+/// `auto`, `none` or a price in micro-lamports per compute unit.
+impl FromStr for PriorityFee {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(Self::Recent {
+                percentile: DEFAULT_FEE_PERCENTILE,
+                max: DEFAULT_MAX_PRICE,
+            }),
+            "none" => Ok(Self::None),
+            price => price
+                .parse()
+                .map(Self::Fixed)
+                .map_err(|_| format!("expected `auto`, `none` or micro-lamports, got {s}")),
+        }
+    }
+}
@@ -0,0 +1,212 @@
+//! Cost previews.
+//!
+//! Every account Vanish creates holds a rent-exempt deposit, and some
+//! operations create several at once: a repository with ten collaborators
+//! makes thirty-odd accounts. [`VanishClient::estimate`] prices an
+//! [`Operation`] before anything is signed, from the cluster's current rent
+//! and fee schedule and the deployment's config.
+//!
+//! ```no_run
+//! # async fn example(client: vanish_client::VanishClient, owner: solana_sdk::pubkey::Pubkey) -> vanish_client::Result<()> {
+//! use vanish_client::cost::Operation;
+//!
+//! let plan = Operation::CreateRepo {
+//!     name: "vanish".to_string(),
+//!     collaborators: 3,
+//!     private: true,
+//! };
+//! let estimate = client.estimate(&owner, &plan).await?;
+//! println!("{} lamports", estimate.total());
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+
+use solana_sdk::{message::Message, pubkey::Pubkey};
+use vanish_program::{
+    AccessKey, AuditEntry, Branch, Collaborator, CollaboratorSlot, Contribution, NameLock,
+    NameStake, Profile, Repository, Star,
+};
+
+use crate::compute_budget::{ComputeUnits, PriorityFee};
+use crate::{pda, Result, VanishClient};
+
+/// Something a user plans to do.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Operation {
+    /// Create a repository named `name`, then add `collaborators`
+    /// collaborators to it, each with an AccessKey when `private`
+    CreateRepo {
+        name: String,
+        collaborators: u32,
+        private: bool,
+    },
+    /// Add one collaborator, with an AccessKey when the repository is
+    /// private
+    AddCollaborator {
+        private: bool,
+    },
+    /// Push a branch for the first time, as a first-time contributor when
+    /// `new_contributor`
+    PushBranch {
+        new_contributor: bool,
+    },
+    Star,
+}
+
+impl Operation {
+    /// Transactions the client sends for the operation, each signed once.
+    pub fn transactions(&self) -> u32 {
+        match self {
+            Self::CreateRepo { collaborators, .. } => 1 + collaborators,
+            _ => 1,
+        }
+    }
+
+    /// Accounts the operation creates, as the account type, its space and
+    /// how many. `has_profile` is whether the payer's Profile exists, and
+    /// `stake` whether the repository's name needs a NameStake.
+    pub fn accounts(&self, has_profile: bool, stake: bool) -> Vec<(&'static str, usize, u32)> {
+        let collaborator = |count: u32, private: bool| {
+            let mut accounts = vec![
+                ("Collaborator", Collaborator::SPACE, count),
+                ("CollaboratorSlot", CollaboratorSlot::SPACE, count),
+                ("AuditEntry", AuditEntry::SPACE, count),
+            ];
+            if private {
+                accounts.push(("AccessKey", AccessKey::SPACE, count));
+            }
+            accounts
+        };
+        let profile = (!has_profile).then_some(("Profile", Profile::SPACE, 1));
+
+        let mut accounts = match self {
+            Self::CreateRepo {
+                collaborators,
+                private,
+                ..
+            } => {
+                let mut accounts = vec![
+                    ("Repository", Repository::SPACE, 1),
+                    ("NameLock", NameLock::SPACE, 1),
+                ];
+                accounts.extend(stake.then_some(("NameStake", NameStake::SPACE, 1)));
+                accounts.extend(profile);
+                accounts.extend(collaborator(*collaborators, *private));
+                accounts
+            }
+            Self::AddCollaborator { private } => collaborator(1, *private),
+            Self::PushBranch { new_contributor } => {
+                let mut accounts = vec![("Branch", Branch::SPACE, 1)];
+                if *new_contributor {
+                    accounts.push(("Contribution", Contribution::SPACE, 1));
+                }
+                accounts
+            }
+            Self::Star => {
+                let mut accounts = vec![("Star", Star::SPACE, 1)];
+                accounts.extend(profile);
+                accounts
+            }
+        };
+        accounts.retain(|(_, _, count)| *count > 0);
+        accounts
+    }
+}
+
+/// Rent deposited into accounts of one type.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Deposit {
+    pub account: &'static str,
+    pub count: u32,
+    pub space: usize,
+    /// For all `count` of them
+    pub lamports: u64,
+}
+
+/// What an operation costs the payer, in lamports.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Estimate {
+    pub deposits: Vec<Deposit>,
+    /// Sum of the deposits, returned when the accounts are closed
+    pub rent: u64,
+    /// Name stake escrowed for a short repository name, returned once the
+    /// stake period passes
+    pub stake: u64,
+    /// `Config::repo_fee`, kept by the treasury
+    pub program_fee: u64,
+    pub transactions: u32,
+    /// Signature fees, plus priority fees when the client's compute budget
+    /// fixes both the unit limit and the price
+    pub transaction_fees: u64,
+}
+
+impl Estimate {
+    /// Lamports the payer needs on hand.
+    pub fn total(&self) -> u64 {
+        self.rent + self.stake + self.program_fee + self.transaction_fees
+    }
+}
+
+impl VanishClient {
+    /// Price `operation` for `payer` at the cluster's current rent and fees.
+    pub async fn estimate(&self, payer: &Pubkey, operation: &Operation) -> Result<Estimate> {
+        let (_, profile) = self.raw_account(&pda::profile(payer)).await?;
+        let (stake, program_fee) = match operation {
+            Operation::CreateRepo { name, .. } => {
+                let config = self.config().await?;
+                let stake = if name.len() < config.stake_name_len as usize {
+                    config.name_stake
+                } else {
+                    0
+                };
+                (stake, config.repo_fee)
+            }
+            _ => (0, 0),
+        };
+
+        let mut minimums = HashMap::new();
+        let mut deposits = Vec::new();
+        for (account, space, count) in operation.accounts(profile.is_some(), stake > 0) {
+            let minimum = match minimums.get(&space) {
+                Some(minimum) => *minimum,
+                None => {
+                    let minimum = self
+                        .rpc()
+                        .get_minimum_balance_for_rent_exemption(space)
+                        .await?;
+                    *minimums.entry(space).or_insert(minimum)
+                }
+            };
+            deposits.push(Deposit {
+                account,
+                count,
+                space,
+                lamports: minimum * u64::from(count),
+            });
+        }
+
+        let signature_fee = self
+            .rpc()
+            .get_fee_for_message(&Message::new(&[], Some(payer)))
+            .await?;
+        let budget = self.compute_budget();
+        let priority_fee = match (budget.units, budget.fee) {
+            (ComputeUnits::Fixed(units), PriorityFee::Fixed(price)) => {
+                (u64::from(units) * price).div_ceil(1_000_000)
+            }
+            _ => 0,
+        };
+        let transactions = operation.transactions();
+
+        Ok(Estimate {
+            rent: deposits.iter().map(|deposit| deposit.lamports).sum(),
+            deposits,
+            stake,
+            program_fee,
+            transactions,
+            transaction_fees: (signature_fee + priority_fee) * u64::from(transactions),
+        })
+    }
+}
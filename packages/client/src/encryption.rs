@@ -0,0 +1,254 @@
+//! Encryption for private repositories.
+//!
+//! A private repository's archives are encrypted with XChaCha20-Poly1305
+//! under a 32-byte [`RepoKey`], which the program never sees. Every reader
+//! gets the key in an `AccessKey` account, sealed into a [`KeyEnvelope`]:
+//! an ephemeral X25519 key is agreed with the reader's Solana key, converted
+//! from Ed25519 to X25519, and the shared secret hashed with both public
+//! keys gives the key the repository key is encrypted with. Only the reader
+//! can open the envelope, with the keypair they sign with.
+//!
+//! ```no_run
+//! # fn example(reader: solana_sdk::signature::Keypair, archive: Vec<u8>) -> vanish_client::Result<()> {
+//! use solana_sdk::signer::Signer;
+//! use vanish_client::encryption::{self, RepoKey};
+//!
+//! let key = RepoKey::generate();
+//! let envelope = encryption::seal(&key, &reader.pubkey())?;
+//! let encrypted = encryption::encrypt(&key, &archive);
+//!
+//! let opened = encryption::open(&envelope, &reader)?;
+//! assert_eq!(encryption::decrypt(&opened, &encrypted)?, archive);
+//! # Ok(())
+//! # }
+//! ```
+
+use chacha20poly1305::aead::{Aead, NewAead};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use curve25519_dalek::{
+    constants::X25519_BASEPOINT, edwards::CompressedEdwardsY, montgomery::MontgomeryPoint,
+    scalar::Scalar,
+};
+use rand::{rngs::OsRng, RngCore};
+use sha2::{Digest, Sha256, Sha512};
+use solana_sdk::{pubkey::Pubkey, signature::Keypair};
+use vanish_program::KeyEnvelope;
+
+use crate::{Error, Result};
+
+/// Starts every encrypted archive, so it can be told apart from a plain one.
+pub const MAGIC: &[u8] = b"vanish-encrypted 1\n";
+
+/// Domain separator for the key envelopes are sealed with.
+const ENVELOPE_TAG: &[u8] = b"vanish-envelope 1\n";
+
+const NONCE_LEN: usize = 24;
+
+/// A private repository's symmetric key for one key epoch.
+#[derive(Clone, PartialEq, Eq)]
+pub struct RepoKey([u8; 32]);
+
+impl RepoKey {
+    /// A fresh random key, for a new repository or key epoch.
+    pub fn generate() -> Self {
+        let mut key = [0; 32];
+        OsRng.fill_bytes(&mut key);
+        Self(key)
+    }
+
+    pub fn from_bytes(bytes: [u8; 32]) -> Self {
+        Self(bytes)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+/// `user`'s Solana key as an X25519 public key, the key envelopes to them
+/// are sealed to.
+pub fn x25519_public(user: &Pubkey) -> Result<[u8; 32]> {
+    let point = CompressedEdwardsY(user.to_bytes())
+        .decompress()
+        .ok_or_else(|| Error::Encryption(format!("{user} is not an Ed25519 public key")))?;
+    Ok(point.to_montgomery().to_bytes())
+}
+
+/// The X25519 secret matching [`x25519_public`] of the keypair's public
+/// key: the clamped first half of the SHA-512 of its seed, as Ed25519 uses.
+fn x25519_secret(keypair: &Keypair) -> Scalar {
+    let hash = Sha512::digest(&keypair.to_bytes()[..32]);
+    let mut secret = [0; 32];
+    secret.copy_from_slice(&hash[..32]);
+    clamp(secret)
+}
+
+fn clamp(mut bytes: [u8; 32]) -> Scalar {
+    bytes[0] &= 248;
+    bytes[31] &= 127;
+    bytes[31] |= 64;
+    Scalar::from_bits(bytes)
+}
+
+/// `shared`, unless it is all zeros: what a low-order public key yields
+/// whatever the secret, which would make the envelope key public.
+fn check_shared(shared: MontgomeryPoint) -> Result<MontgomeryPoint> {
+    if shared.as_bytes() == &[0; 32] {
+        return Err(Error::Encryption(
+            "key agreement produced an all-zero shared secret".to_string(),
+        ));
+    }
+    Ok(shared)
+}
+
+/// The key an envelope's repository key is encrypted with.
+fn envelope_key(shared: &MontgomeryPoint, ephemeral: &[u8; 32], reader: &[u8; 32]) -> Key {
+    let mut hasher = Sha256::new();
+    hasher.update(ENVELOPE_TAG);
+    hasher.update(shared.as_bytes());
+    hasher.update(ephemeral);
+    hasher.update(reader);
+    hasher.finalize()
+}
+
+/// Seal `key` to `reader`, for add_collaborator, grant_read_access or
+/// reissue_access_key.
+pub fn seal(key: &RepoKey, reader: &Pubkey) -> Result<KeyEnvelope> {
+    let reader = x25519_public(reader)?;
+    let mut secret = [0; 32];
+    OsRng.fill_bytes(&mut secret);
+    let secret = clamp(secret);
+    let ephemeral_pubkey = (X25519_BASEPOINT * secret).to_bytes();
+    let shared = check_shared(MontgomeryPoint(reader) * secret)?;
+
+    let mut nonce = [0; NONCE_LEN];
+    OsRng.fill_bytes(&mut nonce);
+    let sealed = XChaCha20Poly1305::new(&envelope_key(&shared, &ephemeral_pubkey, &reader))
+        .encrypt(&XNonce::from(nonce), &key.0[..])
+        .map_err(|_| Error::Encryption("failed to seal the repository key".to_string()))?;
+
+    let mut ciphertext = [0; 48];
+    ciphertext.copy_from_slice(&sealed);
+    Ok(KeyEnvelope {
+        ephemeral_pubkey,
+        nonce,
+        ciphertext,
+    })
+}
+
+/// Open an envelope sealed to `reader`.
+pub fn open(envelope: &KeyEnvelope, reader: &Keypair) -> Result<RepoKey> {
+    let secret = x25519_secret(reader);
+    let public = (X25519_BASEPOINT * secret).to_bytes();
+    let shared = check_shared(MontgomeryPoint(envelope.ephemeral_pubkey) * secret)?;
+
+    let key = XChaCha20Poly1305::new(&envelope_key(&shared, &envelope.ephemeral_pubkey, &public))
+        .decrypt(&XNonce::from(envelope.nonce), &envelope.ciphertext[..])
+        .map_err(|_| Error::Encryption("the envelope is not sealed to this keypair".to_string()))?;
+
+    let mut bytes = [0; 32];
+    bytes.copy_from_slice(&key);
+    Ok(RepoKey(bytes))
+}
+
+/// Whether `bytes` were produced by [`encrypt`].
+pub fn is_encrypted(bytes: &[u8]) -> bool {
+    bytes.starts_with(MAGIC)
+}
+
+/// Encrypt an archive: [`MAGIC`], a random nonce, then the ciphertext.
+pub fn encrypt(key: &RepoKey, plaintext: &[u8]) -> Vec<u8> {
+    let mut nonce = [0; NONCE_LEN];
+    OsRng.fill_bytes(&mut nonce);
+    let ciphertext = XChaCha20Poly1305::new(&Key::from(key.0))
+        .encrypt(&XNonce::from(nonce), plaintext)
+        .expect("XChaCha20-Poly1305 encrypts messages of any practical length");
+
+    let mut bytes = Vec::with_capacity(MAGIC.len() + NONCE_LEN + ciphertext.len());
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&nonce);
+    bytes.extend_from_slice(&ciphertext);
+    bytes
+}
+
+/// Decrypt bytes produced by [`encrypt`] under `key`.
+pub fn decrypt(key: &RepoKey, bytes: &[u8]) -> Result<Vec<u8>> {
+    let body = bytes
+        .strip_prefix(MAGIC)
+        .filter(|body| body.len() >= NONCE_LEN)
+        .ok_or_else(|| Error::Encryption("not an encrypted archive".to_string()))?;
+    let (nonce, ciphertext) = body.split_at(NONCE_LEN);
+    let nonce: [u8; NONCE_LEN] = nonce.try_into().unwrap();
+    XChaCha20Poly1305::new(&Key::from(key.0))
+        .decrypt(&XNonce::from(nonce), ciphertext)
+        .map_err(|_| Error::Encryption("the archive is not encrypted under this key".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::signer::Signer;
+
+    use super::*;
+
+    #[test]
+    fn envelopes_open_for_their_reader_only() {
+        let reader = Keypair::new();
+        let key = RepoKey::generate();
+        let envelope = seal(&key, &reader.pubkey()).unwrap();
+
+        // The secret derived from the keypair matches its converted public key
+        assert_eq!(
+            (X25519_BASEPOINT * x25519_secret(&reader)).to_bytes(),
+            x25519_public(&reader.pubkey()).unwrap()
+        );
+        assert!(open(&envelope, &reader).unwrap() == key);
+        assert!(open(&envelope, &Keypair::new()).is_err());
+
+        let mut tampered = envelope;
+        tampered.ciphertext[0] ^= 1;
+        assert!(open(&tampered, &reader).is_err());
+    }
+
+    #[test]
+    fn low_order_keys_are_refused() {
+        let reader = Keypair::new();
+        let key = RepoKey::generate();
+        let mut envelope = seal(&key, &reader.pubkey()).unwrap();
+
+        // The Ed25519 identity converts to the X25519 point zero, which
+        // any secret multiplies to zero
+        let mut identity = [0; 32];
+        identity[0] = 1;
+        assert!(seal(&key, &Pubkey::new_from_array(identity)).is_err());
+
+        envelope.ephemeral_pubkey = [0; 32];
+        assert!(matches!(
+            open(&envelope, &reader),
+            Err(Error::Encryption(message)) if message.contains("all-zero")
+        ));
+    }
+
+    #[test]
+    fn archives_decrypt_under_their_key_only() {
+        let key = RepoKey::generate();
+        let archive = b"vanish-archive 1\n".repeat(100);
+        let encrypted = encrypt(&key, &archive);
+        assert!(is_encrypted(&encrypted));
+        assert!(!is_encrypted(&archive));
+        assert_eq!(decrypt(&key, &encrypted).unwrap(), archive);
+
+        assert!(decrypt(&RepoKey::generate(), &encrypted).is_err());
+        let mut tampered = encrypted.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(decrypt(&key, &tampered).is_err());
+        for len in [
+            0,
+            MAGIC.len(),
+            MAGIC.len() + NONCE_LEN - 1,
+            encrypted.len() - 1,
+        ] {
+            assert!(decrypt(&key, &encrypted[..len]).is_err());
+        }
+        assert!(decrypt(&key, &archive).is_err());
+    }
+}
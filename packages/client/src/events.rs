@@ -0,0 +1,93 @@
+//! Decoding program events out of transaction logs.
+//!
+//! `emit_event!` always logs the event as `Program data: <base64>`, even in
+//! `event-cpi` builds, so the logs alone carry every event. Only data logged
+//! while the Vanish program is the innermost running program counts; other
+//! programs may log look-alikes.
+
+use anchor_lang::{AnchorDeserialize, Discriminator};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use vanish_program::{
+    ActivitySeen, BranchPushed, CollaboratorAdded, CollaboratorIndexed, CollaboratorRemoved,
+    OwnershipRenounced, OwnershipTransferred, RepoCreated, RepoDeleted, RepoPurged, RepoPushed,
+    RepoRenamed, RepoRestored, RepoStarred, RepoUnstarred, StarsReconciled,
+};
+
+/// The events the index tables are built from.
+pub enum Event {
+    RepoCreated(RepoCreated),
+    RepoPushed(RepoPushed),
+    BranchPushed(BranchPushed),
+    RepoRenamed(RepoRenamed),
+    OwnershipTransferred(OwnershipTransferred),
+    OwnershipRenounced(OwnershipRenounced),
+    RepoDeleted(RepoDeleted),
+    RepoRestored(RepoRestored),
+    RepoPurged(RepoPurged),
+    RepoStarred(RepoStarred),
+    RepoUnstarred(RepoUnstarred),
+    StarsReconciled(StarsReconciled),
+    CollaboratorAdded(CollaboratorAdded),
+    CollaboratorIndexed(CollaboratorIndexed),
+    CollaboratorRemoved(CollaboratorRemoved),
+    ActivitySeen(ActivitySeen),
+}
+
+/// Events in the order the program emitted them.
+pub fn parse_logs(logs: &[String]) -> Vec<Event> {
+    let invoke = format!("Program {} invoke", crate::program_id());
+    // Whether each program on the invocation stack is Vanish
+    let mut stack = Vec::new();
+    let mut events = Vec::new();
+
+    for log in logs {
+        if let Some(data) = log.strip_prefix("Program data: ") {
+            if stack.last() == Some(&true) {
+                events.extend(STANDARD.decode(data).ok().and_then(|data| decode(&data)));
+            }
+        } else if log.starts_with("Program ") && log.contains(" invoke [") {
+            stack.push(log.starts_with(&invoke));
+        } else if log.starts_with("Program ")
+            && (log.ends_with(" success") || log.contains(" failed"))
+        {
+            stack.pop();
+        }
+    }
+
+    events
+}
+
+fn decode(data: &[u8]) -> Option<Event> {
+    let discriminator = data.get(..8)?;
+    let mut body = &data[8..];
+
+    macro_rules! decode {
+        ($($event:ident),* $(,)?) => {
+            $(
+                if discriminator == $event::DISCRIMINATOR {
+                    return $event::deserialize(&mut body).ok().map(Event::$event);
+                }
+            )*
+        };
+    }
+    decode!(
+        RepoCreated,
+        RepoPushed,
+        BranchPushed,
+        RepoRenamed,
+        OwnershipTransferred,
+        OwnershipRenounced,
+        RepoDeleted,
+        RepoRestored,
+        RepoPurged,
+        RepoStarred,
+        RepoUnstarred,
+        StarsReconciled,
+        CollaboratorAdded,
+        CollaboratorIndexed,
+        CollaboratorRemoved,
+        ActivitySeen,
+    );
+
+    None
+}
@@ -0,0 +1,427 @@
+//! Instruction builders.
+//!
+//! Each builder derives the accounts its instruction needs from the
+//! repository state it is given, so callers pass keys and arguments rather
+//! than account lists. Builders that take a `Repository` expect it fresh:
+//! seeds such as the next audit entry depend on its counters.
+
+use anchor_lang::{system_program, InstructionData, ToAccountMetas};
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+use vanish_program::{
+    accounts, instruction, Cid, Collaborator, CollaboratorSlot, KeyEnvelope, Multihash, PushNote,
+    ReleaseAsset, Repository,
+};
+
+use crate::pda;
+
+fn ix(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: crate::program_id(),
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+/// The caller's Collaborator account, which the program wants whenever the
+/// caller acts on a repository it does not own.
+fn collaborator_of(repo: &Repository, user: &Pubkey) -> Option<Pubkey> {
+    (repo.owner != *user).then(|| pda::collab(&repo.id, user))
+}
+
+fn address(repo: &Repository) -> Pubkey {
+    pda::repo(&repo.creator, &repo.name)
+}
+
+/// Create a repository. `stake_name` must be set when the name is shorter
+/// than `Config::stake_name_len`.
+pub fn create_repo(
+    owner: &Pubkey,
+    name: &str,
+    description: &str,
+    is_private: bool,
+    stake_name: bool,
+) -> Instruction {
+    let repository = pda::repo(owner, name);
+    ix(
+        accounts::CreateRepo {
+            owner: *owner,
+            repository,
+            tombstone: pda::tombstone(&repository),
+            name_lock: pda::name_lock(owner, name),
+            name_stake: stake_name.then(|| pda::stake(&repository)),
+            profile: pda::profile(owner),
+            config: pda::config(),
+            treasury: pda::treasury(),
+            system_program: system_program::ID,
+        },
+        instruction::CreateRepo {
+            name: name.to_string(),
+            description: description.to_string(),
+            is_private,
+        },
+    )
+}
+
+/// Accounts push_update needs when the repository is in a project
+pub struct ProjectPush {
+    pub project: Pubkey,
+    /// Push through a ProjectMember account rather than as owner or
+    /// collaborator
+    pub as_member: bool,
+}
+
+/// Move the default branch to `head_commit`, archived at `ipfs_cid`.
+/// `expected_head` guards against overwriting a concurrent push. `project`
+/// must be set when the repository is in a project. `note` is recorded in the
+/// push log and the push event, and `binding` in the push event.
+#[allow(clippy::too_many_arguments)]
+pub fn push_update(
+    pusher: &Pubkey,
+    repo: &Repository,
+    head_commit: Multihash,
+    ipfs_cid: Cid,
+    expected_head: Option<Multihash>,
+    project: Option<&ProjectPush>,
+    note: Option<PushNote>,
+    binding: Option<[u8; 32]>,
+) -> Instruction {
+    let as_member = project.is_some_and(|project| project.as_member);
+    ix(
+        accounts::PushUpdate {
+            pusher: *pusher,
+            repository: address(repo),
+            collaborator_account: collaborator_of(repo, pusher).filter(|_| !as_member),
+            contribution: pda::contribution(&repo.id, pusher),
+            push_log: None,
+            project_repo: pda::project_repo(&repo.id),
+            project: project.map(|project| project.project),
+            project_member: project
+                .filter(|project| project.as_member)
+                .map(|project| pda::project_member(&project.project, pusher)),
+            badge_config: None,
+            badge_mint: None,
+            badge_token_account: None,
+            token_2022_program: None,
+            system_program: system_program::ID,
+        },
+        instruction::PushUpdate {
+            head_commit,
+            ipfs_cid,
+            expected_head,
+            co_authors: vec![],
+            note,
+            binding,
+        },
+    )
+}
+
+/// Move `branch` to `head_commit`, archived at `ipfs_cid`. `proof` holds the
+/// branch's siblings in the repository's refs tree, or those of the next
+/// free leaf for a new branch.
+pub fn push_branch(
+    pusher: &Pubkey,
+    repo: &Repository,
+    branch: &str,
+    head_commit: Multihash,
+    ipfs_cid: &str,
+    expected_head: Option<Multihash>,
+    proof: Vec<[u8; 32]>,
+) -> Instruction {
+    ix(
+        accounts::PushBranch {
+            pusher: *pusher,
+            repository: address(repo),
+            collaborator_account: collaborator_of(repo, pusher),
+            branch_grant: None,
+            contribution: pda::contribution(&repo.id, pusher),
+            branch: pda::branch(&repo.id, branch),
+            project_repo: None,
+            rent_vault: None,
+            system_program: system_program::ID,
+        },
+        instruction::PushBranch {
+            branch_name: branch.to_string(),
+            head_commit,
+            ipfs_cid: ipfs_cid.to_string(),
+            expected_head,
+            proof,
+        },
+    )
+}
+
+pub fn set_branch_force_push(
+    authority: &Pubkey,
+    repo: &Repository,
+    branch: &str,
+    allow: bool,
+) -> Instruction {
+    ix(
+        accounts::SetBranchForcePush {
+            authority: *authority,
+            repository: address(repo),
+            admin: collaborator_of(repo, authority),
+            branch: pda::branch(&repo.id, branch),
+        },
+        instruction::SetBranchForcePush { allow },
+    )
+}
+
+/// Star a repository, tipping `tip` lamports into its donation vault.
+pub fn star_repo(user: &Pubkey, repo: &Repository, tip: u64) -> Instruction {
+    ix(
+        accounts::StarRepo {
+            user: *user,
+            repository: address(repo),
+            star_account: pda::star(user, &repo.id),
+            donation_vault: pda::donations(&repo.id),
+            profile: pda::profile(user),
+            config: pda::config(),
+            system_program: system_program::ID,
+        },
+        instruction::StarRepo { tip },
+    )
+}
+
+pub fn unstar_repo(user: &Pubkey, repo: &Repository) -> Instruction {
+    ix(
+        accounts::UnstarRepo {
+            user: *user,
+            repository: address(repo),
+            star_account: pda::star(user, &repo.id),
+        },
+        instruction::UnstarRepo {},
+    )
+}
+
+/// Add `collaborator`. Private repositories need `key_envelope`, the
+/// repository key sealed to the collaborator.
+pub fn add_collaborator(
+    authority: &Pubkey,
+    repo: &Repository,
+    collaborator: &Pubkey,
+    can_push: bool,
+    key_envelope: Option<KeyEnvelope>,
+) -> Instruction {
+    ix(
+        accounts::AddCollaborator {
+            authority: *authority,
+            repository: address(repo),
+            admin: collaborator_of(repo, authority),
+            collaborator_account: pda::collab(&repo.id, collaborator),
+            collaborator_slot: pda::collab_slot(&repo.id, repo.collaborator_count),
+            access_key: key_envelope.map(|_| pda::access(&repo.id, collaborator)),
+            audit_entry: pda::audit(&repo.id, repo.audit_count),
+            project_repo: None,
+            rent_vault: None,
+            system_program: system_program::ID,
+        },
+        instruction::AddCollaborator {
+            collaborator: *collaborator,
+            can_push,
+            key_envelope,
+        },
+    )
+}
+
+pub fn set_collaborator_admin(
+    authority: &Pubkey,
+    repo: &Repository,
+    collaborator: &Pubkey,
+    is_admin: bool,
+) -> Instruction {
+    ix(
+        accounts::SetCollaboratorAdmin {
+            authority: *authority,
+            repository: address(repo),
+            admin: collaborator_of(repo, authority),
+            collaborator_account: pda::collab(&repo.id, collaborator),
+            audit_entry: pda::audit(&repo.id, repo.audit_count),
+            system_program: system_program::ID,
+        },
+        instruction::SetCollaboratorAdmin { is_admin },
+    )
+}
+
+/// Remove `collaborator`. `last_slot` is the repository's last index slot
+/// (at `collaborator_count - 1`), required when the collaborator holds a
+/// slot: its holder is moved into the freed one.
+pub fn remove_collaborator(
+    authority: &Pubkey,
+    repo: &Repository,
+    collaborator: &Collaborator,
+    last_slot: Option<&CollaboratorSlot>,
+) -> Instruction {
+    let moved = match (collaborator.index, last_slot) {
+        (Some(index), Some(last)) if index != last.index => Some((index, last.user)),
+        _ => None,
+    };
+
+    ix(
+        accounts::RemoveCollaborator {
+            authority: *authority,
+            repository: address(repo),
+            admin: collaborator_of(repo, authority),
+            collaborator_account: pda::collab(&repo.id, &collaborator.user),
+            access_key: repo
+                .is_private
+                .then(|| pda::access(&repo.id, &collaborator.user)),
+            last_slot: collaborator
+                .index
+                .and(last_slot)
+                .map(|last| pda::collab_slot(&repo.id, last.index)),
+            collaborator_slot: moved.map(|(index, _)| pda::collab_slot(&repo.id, index)),
+            moved_collaborator: moved.map(|(_, user)| pda::collab(&repo.id, &user)),
+            audit_entry: pda::audit(&repo.id, repo.audit_count),
+            system_program: system_program::ID,
+        },
+        instruction::RemoveCollaborator {},
+    )
+}
+
+/// Give `collaborator`, added before index slots existed, the next slot.
+pub fn index_collaborator(payer: &Pubkey, repo: &Repository, collaborator: &Pubkey) -> Instruction {
+    ix(
+        accounts::IndexCollaborator {
+            payer: *payer,
+            repository: address(repo),
+            collaborator_account: pda::collab(&repo.id, collaborator),
+            collaborator_slot: pda::collab_slot(&repo.id, repo.collaborator_count),
+            system_program: system_program::ID,
+        },
+        instruction::IndexCollaborator {},
+    )
+}
+
+/// Set the release policy. Replacing one takes `threshold` of its
+/// `current_managers` signing too, unless the repository's timelock has the
+/// new policy queued.
+pub fn set_release_policy(
+    authority: &Pubkey,
+    repo: &Repository,
+    managers: Vec<Pubkey>,
+    threshold: u8,
+    current_managers: &[Pubkey],
+) -> Instruction {
+    let mut instruction = ix(
+        accounts::SetReleasePolicy {
+            authority: *authority,
+            repository: address(repo),
+            admin: collaborator_of(repo, authority),
+            release_policy: pda::release_policy(&repo.id),
+            timelock: pda::timelock(&repo.id),
+            pending_action: None,
+            system_program: system_program::ID,
+        },
+        instruction::SetReleasePolicy {
+            managers,
+            threshold,
+        },
+    );
+    instruction.accounts.extend(
+        current_managers
+            .iter()
+            .map(|manager| AccountMeta::new_readonly(*manager, true)),
+    );
+    instruction
+}
+
+/// Propose release `tag`. `proposer` must be a release manager.
+pub fn create_release(
+    proposer: &Pubkey,
+    repo: &Repository,
+    tag: &str,
+    commit: Multihash,
+    ipfs_cid: &str,
+) -> Instruction {
+    ix(
+        accounts::CreateRelease {
+            proposer: *proposer,
+            repository: address(repo),
+            release_policy: pda::release_policy(&repo.id),
+            release: pda::release(&repo.id, tag),
+            system_program: system_program::ID,
+        },
+        instruction::CreateRelease {
+            tag: tag.to_string(),
+            commit,
+            ipfs_cid: ipfs_cid.to_string(),
+        },
+    )
+}
+
+pub fn add_release_asset(
+    manager: &Pubkey,
+    repo: &Repository,
+    tag: &str,
+    asset: ReleaseAsset,
+) -> Instruction {
+    ix(
+        accounts::AddReleaseAsset {
+            manager: *manager,
+            repository: address(repo),
+            release_policy: pda::release_policy(&repo.id),
+            release: pda::release(&repo.id, tag),
+        },
+        instruction::AddReleaseAsset { asset },
+    )
+}
+
+/// Ask whether `user` may push to `repo`, or to its `branch`. `project` is
+/// the project `repo` is linked to, if any, whose members may push to the
+/// default branch. Read-only: simulate it for the answer.
+pub fn can_push(
+    repo: &Repository,
+    user: &Pubkey,
+    branch: Option<&str>,
+    project: Option<&Pubkey>,
+) -> Instruction {
+    ix(
+        accounts::CanPush {
+            repository: address(repo),
+            collaborator_account: pda::collab(&repo.id, user),
+            branch_grant: pda::branch_grant(&repo.id, user),
+            project_repo: pda::project_repo(&repo.id),
+            project_member: project.map(|project| pda::project_member(project, user)),
+        },
+        instruction::CanPush {
+            user: *user,
+            branch: branch.map(str::to_string),
+        },
+    )
+}
+
+/// Ask whether `user` may read `repo`. Read-only: simulate it for the
+/// answer.
+pub fn can_read(repo: &Repository, user: &Pubkey) -> Instruction {
+    ix(
+        accounts::CanRead {
+            repository: address(repo),
+            collaborator_account: pda::collab(&repo.id, user),
+            read_grant: pda::read(&repo.id, user),
+            access_grant: pda::grant(&repo.id, user),
+        },
+        instruction::CanRead { user: *user },
+    )
+}
+
+/// The accounts `instructions` reference that a lookup table can stand in
+/// for, in first-use order: everything but signers and invoked programs,
+/// which a v0 message must list itself.
+pub fn lookup_addresses(instructions: &[Instruction]) -> Vec<Pubkey> {
+    let mut addresses = Vec::new();
+    for meta in instructions
+        .iter()
+        .flat_map(|instruction| &instruction.accounts)
+    {
+        let invoked = instructions
+            .iter()
+            .any(|instruction| instruction.program_id == meta.pubkey);
+        if !meta.is_signer && !invoked && !addresses.contains(&meta.pubkey) {
+            addresses.push(meta.pubkey);
+        }
+    }
+    addresses
+}
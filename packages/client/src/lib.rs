@@ -0,0 +1,50 @@
+//! Rust client for the Vanish program.
+//!
+//! Derives the program's PDAs, builds its instructions with the accounts
+//! they need already filled in, fetches and decodes its accounts over the
+//! nonblocking RPC client, sends legacy or v0 transactions (with managed
+//! address lookup tables) under a simulated compute budget and optional
+//! priority fee (or prepares them for offline signing against a durable
+//! nonce), and streams account changes and events over websocket
+//! subscriptions, so consumers do not have to hand-roll Anchor
+//! discriminators or seed layouts. Permission checks such as
+//! [`VanishClient::can_push`] simulate the program's read-only views, so
+//! they answer exactly as the program would. [`cluster`] profiles pick the endpoints
+//! and program deployment per cluster, [`encryption`] seals and opens
+//! private repositories' keys and archives, and [`cost`] prices operations
+//! before they are signed.
+//!
+//! ```no_run
+//! # async fn example(owner: solana_sdk::signature::Keypair) -> vanish_client::Result<()> {
+//! use solana_sdk::signer::Signer;
+//! use vanish_client::VanishClient;
+//!
+//! let client = VanishClient::new("https://api.devnet.solana.com".to_string());
+//! client.create_repo(&owner, "vanish", "Git on Solana", false).await?;
+//! let repo = client.repository(&owner.pubkey(), "vanish").await?;
+//! println!("{} has {} stars", repo.name, repo.stars);
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod archive;
+#[cfg(feature = "cache")]
+pub mod cache;
+mod client;
+pub mod cluster;
+pub mod commit;
+pub mod compute_budget;
+pub mod cost;
+pub mod encryption;
+pub mod events;
+pub mod instructions;
+pub mod offline;
+pub mod snapshot;
+pub mod storage;
+mod subscribe;
+
+pub use client::{Error, Result, VanishClient};
+pub use compute_budget::{ComputeBudget, ComputeUnits, PriorityFee};
+pub use subscribe::{ProgramLogs, Subscription};
+pub use vanish_interface::{pda, program_id};
+pub use vanish_program::{self as program, ID};
@@ -0,0 +1,51 @@
+//! Offline signing.
+//!
+//! [`VanishClient::prepare_offline`](crate::VanishClient::prepare_offline)
+//! builds an unsigned transaction against a durable nonce, so it stays valid
+//! for as long as signing takes. It travels between machines as a payload
+//! from [`encode`]. Each signer, online or air-gapped, signs it with [`sign`],
+//! and [`VanishClient::submit`](crate::VanishClient::submit) sends it once
+//! [`missing_signers`] is empty.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use solana_sdk::{
+    pubkey::Pubkey, sanitize::Sanitize, signature::Signature, signer::Signer,
+    transaction::Transaction,
+};
+
+use crate::{Error, Result};
+
+/// A transaction, with whatever signatures it has so far, as base64 text.
+pub fn encode(transaction: &Transaction) -> String {
+    let bytes = bincode::serialize(transaction).expect("transactions always serialize");
+    STANDARD.encode(bytes)
+}
+
+/// Read back a payload from `encode`.
+pub fn decode(payload: &str) -> Result<Transaction> {
+    let bytes = STANDARD
+        .decode(payload.trim())
+        .map_err(|_| Error::Payload)?;
+    let transaction: Transaction = bincode::deserialize(&bytes).map_err(|_| Error::Payload)?;
+    transaction.sanitize().map_err(|_| Error::Payload)?;
+    Ok(transaction)
+}
+
+/// Add `signer`'s signature, keeping the durable nonce the transaction was
+/// built against. Needs no network access.
+pub fn sign(transaction: &mut Transaction, signer: &dyn Signer) -> Result<()> {
+    let nonce = transaction.message.recent_blockhash;
+    transaction.try_partial_sign(&[signer], nonce)?;
+    Ok(())
+}
+
+/// The keys that still have to sign.
+pub fn missing_signers(transaction: &Transaction) -> Vec<Pubkey> {
+    let required = usize::from(transaction.message.header.num_required_signatures);
+    transaction.message.account_keys[..required]
+        .iter()
+        .zip(&transaction.signatures)
+        .filter(|(_, signature)| **signature == Signature::default())
+        .map(|(key, _)| *key)
+        .collect()
+}
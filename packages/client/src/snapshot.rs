@@ -0,0 +1,432 @@
+//! Portable repository snapshots.
+//!
+//! [`VanishClient::export_repo`] reads a repository's on-chain state (the
+//! repository itself, its branches, collaborators, release policy and
+//! releases) into a [`RepoSnapshot`]. [`RepoSnapshot::sign`] seals it into a
+//! JSON bundle for moving to another cluster or keeping for disaster
+//! recovery. [`VanishClient::import_repo`] replays a snapshot into a fresh
+//! repository on whichever cluster the client points at.
+//!
+//! Replaying only covers what the importing owner can sign for alone.
+//! Collaborators of private repositories need the repository key sealed to
+//! them again, and releases need their managers to approve them again.
+//! [`ImportReport::skipped`] lists what was left out.
+
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anchor_lang::{AccountDeserialize, Discriminator};
+use serde::{Deserialize, Serialize};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::{Memcmp, RpcFilterType},
+};
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signature},
+    signer::Signer,
+};
+use vanish_program::{
+    refs_tree, Branch, Multihash, Release, ReleaseAsset, ReleasePolicy, Repository,
+};
+
+use crate::client::decode;
+use crate::{commit, instructions, pda, Error, Result, VanishClient};
+
+/// Bumped whenever the snapshot layout changes incompatibly.
+pub const FORMAT_VERSION: u32 = 1;
+
+/// Offset of the repository id in Branch and Release data (discriminator)
+const REPOSITORY_OFFSET: usize = 8;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RepoSnapshot {
+    pub version: u32,
+    /// Repository address on the cluster it was exported from
+    pub address: String,
+    /// Slot observed before the accounts were read
+    pub slot: u64,
+    pub exported_at: u64,
+    pub name: String,
+    pub description: String,
+    pub is_private: bool,
+    /// Default branch head, `None` for a repository never pushed
+    pub head_commit: Option<Digest>,
+    pub ipfs_cid: String,
+    /// In refs tree order
+    pub branches: Vec<BranchSnapshot>,
+    pub collaborators: Vec<CollaboratorSnapshot>,
+    pub release_policy: Option<ReleasePolicySnapshot>,
+    pub releases: Vec<ReleaseSnapshot>,
+}
+
+/// A multihash with its digest in hex.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Digest {
+    pub code: u64,
+    pub digest: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BranchSnapshot {
+    pub name: String,
+    pub head_commit: Digest,
+    pub ipfs_cid: String,
+    pub allow_force_push: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CollaboratorSnapshot {
+    pub user: String,
+    pub can_push: bool,
+    pub is_admin: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ReleasePolicySnapshot {
+    pub managers: Vec<String>,
+    pub threshold: u8,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ReleaseSnapshot {
+    pub tag: String,
+    pub commit: Digest,
+    pub ipfs_cid: String,
+    pub assets: Vec<AssetSnapshot>,
+    pub is_final: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AssetSnapshot {
+    pub filename: String,
+    pub size: u64,
+    pub ipfs_cid: String,
+    pub checksum: Digest,
+}
+
+/// A snapshot and the exporter's signature over its JSON encoding.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SignedSnapshot {
+    pub snapshot: RepoSnapshot,
+    pub signer: String,
+    pub signature: String,
+}
+
+/// What `import_repo` created, and what it could not replay.
+#[derive(Clone, Debug)]
+pub struct ImportReport {
+    pub address: Pubkey,
+    pub skipped: Vec<String>,
+}
+
+impl From<&Multihash> for Digest {
+    fn from(hash: &Multihash) -> Self {
+        Self {
+            code: hash.code,
+            digest: commit::to_hex(hash),
+        }
+    }
+}
+
+impl TryFrom<&Digest> for Multihash {
+    type Error = Error;
+
+    fn try_from(digest: &Digest) -> Result<Self> {
+        let hex = &digest.digest;
+        if !hex.len().is_multiple_of(2) || !hex.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+            return Err(Error::Snapshot(format!("{hex} is not a hex digest")));
+        }
+        Ok(Self {
+            code: digest.code,
+            digest: (0..hex.len())
+                .step_by(2)
+                .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).expect("checked hex digits"))
+                .collect(),
+        })
+    }
+}
+
+impl RepoSnapshot {
+    /// Sign the snapshot as `signer`, who vouches for it to importers.
+    pub fn sign(self, signer: &Keypair) -> Result<SignedSnapshot> {
+        let message = serde_json::to_vec(&self).map_err(|err| Error::Snapshot(err.to_string()))?;
+        let signature = signer.try_sign_message(&message)?;
+        Ok(SignedSnapshot {
+            snapshot: self,
+            signer: signer.pubkey().to_string(),
+            signature: signature.to_string(),
+        })
+    }
+}
+
+impl SignedSnapshot {
+    /// Check the signature and format version, returning the signer.
+    pub fn verify(&self) -> Result<Pubkey> {
+        let signer = parse_key(&self.signer)?;
+        let signature = Signature::from_str(&self.signature)
+            .map_err(|_| Error::Snapshot(format!("{} is not a signature", self.signature)))?;
+        let message =
+            serde_json::to_vec(&self.snapshot).map_err(|err| Error::Snapshot(err.to_string()))?;
+        if !signature.verify(signer.as_ref(), &message) {
+            return Err(Error::Snapshot(format!(
+                "signature does not match {signer}"
+            )));
+        }
+        if self.snapshot.version != FORMAT_VERSION {
+            return Err(Error::Snapshot(format!(
+                "snapshot format version {} is not supported (expected {FORMAT_VERSION})",
+                self.snapshot.version
+            )));
+        }
+        Ok(signer)
+    }
+}
+
+impl VanishClient {
+    /// Read the repository at `address` into a snapshot.
+    pub async fn export_repo(&self, address: &Pubkey) -> Result<RepoSnapshot> {
+        let slot = self.rpc().get_slot().await?;
+        let repo = self.account::<Repository>(address).await?;
+
+        let mut branches = self.owned_by_repo::<Branch>(&repo).await?;
+        branches.sort_by_key(|branch| branch.ref_index.unwrap_or(u32::MAX));
+        let collaborators = self.collaborators(&repo).await?;
+        let release_policy = self
+            .try_account::<ReleasePolicy>(&pda::release_policy(&repo.id))
+            .await?;
+        let mut releases = self.owned_by_repo::<Release>(&repo).await?;
+        releases.sort_by_key(|release| release.created_at);
+
+        let exported_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default();
+
+        Ok(RepoSnapshot {
+            version: FORMAT_VERSION,
+            address: address.to_string(),
+            slot,
+            exported_at,
+            name: repo.name.clone(),
+            description: repo.description.clone(),
+            is_private: repo.is_private,
+            head_commit: (!repo.head_commit.digest().is_empty())
+                .then(|| Digest::from(&repo.head_commit.to_multihash())),
+            ipfs_cid: repo.ipfs_cid.to_string(),
+            branches: branches
+                .iter()
+                .map(|branch| BranchSnapshot {
+                    name: branch.name.clone(),
+                    head_commit: Digest::from(&branch.head_commit),
+                    ipfs_cid: branch.ipfs_cid.clone(),
+                    allow_force_push: branch.allow_force_push,
+                })
+                .collect(),
+            collaborators: collaborators
+                .iter()
+                .map(|collaborator| CollaboratorSnapshot {
+                    user: collaborator.user.to_string(),
+                    can_push: collaborator.can_push,
+                    is_admin: collaborator.is_admin,
+                })
+                .collect(),
+            release_policy: release_policy.map(|policy| ReleasePolicySnapshot {
+                managers: policy.managers.iter().map(ToString::to_string).collect(),
+                threshold: policy.threshold,
+            }),
+            releases: releases
+                .iter()
+                .map(|release| ReleaseSnapshot {
+                    tag: release.tag.clone(),
+                    commit: Digest::from(&release.commit),
+                    ipfs_cid: release.ipfs_cid.clone(),
+                    assets: release
+                        .assets
+                        .iter()
+                        .map(|asset| AssetSnapshot {
+                            filename: asset.filename.clone(),
+                            size: asset.size,
+                            ipfs_cid: asset.ipfs_cid.clone(),
+                            checksum: Digest::from(&asset.checksum),
+                        })
+                        .collect(),
+                    is_final: release.is_final,
+                })
+                .collect(),
+        })
+    }
+
+    /// Recreate `snapshot` as a new repository owned by `owner`, one
+    /// transaction per step. The name must be free for `owner`.
+    pub async fn import_repo(
+        &self,
+        owner: &Keypair,
+        snapshot: &RepoSnapshot,
+    ) -> Result<ImportReport> {
+        let mut skipped = Vec::new();
+        let address = self
+            .create_repo(
+                owner,
+                &snapshot.name,
+                &snapshot.description,
+                snapshot.is_private,
+            )
+            .await?;
+
+        if let Some(head) = &snapshot.head_commit {
+            self.push(
+                owner,
+                &address,
+                head.try_into()?,
+                &snapshot.ipfs_cid,
+                None,
+                None,
+            )
+            .await?;
+        }
+
+        let mut leaves = Vec::with_capacity(snapshot.branches.len());
+        for branch in &snapshot.branches {
+            let repo = self.account::<Repository>(&address).await?;
+            let head = Multihash::try_from(&branch.head_commit)?;
+            // A fresh repository's tree holds exactly the branches pushed so
+            // far, so the next free leaf's proof follows from them
+            let proof = refs_tree::proof(&leaves, leaves.len() as u32);
+            let push = instructions::push_branch(
+                &owner.pubkey(),
+                &repo,
+                &branch.name,
+                head.clone(),
+                &branch.ipfs_cid,
+                None,
+                proof,
+            );
+            self.send(&[push], owner, &[]).await?;
+            leaves.push(refs_tree::leaf(&branch.name, &head));
+
+            if branch.allow_force_push {
+                let allow =
+                    instructions::set_branch_force_push(&owner.pubkey(), &repo, &branch.name, true);
+                self.send(&[allow], owner, &[]).await?;
+            }
+        }
+
+        for collaborator in &snapshot.collaborators {
+            if snapshot.is_private {
+                skipped.push(format!(
+                    "collaborator {}: the repository key has to be sealed to them again",
+                    collaborator.user
+                ));
+                continue;
+            }
+            let user = parse_key(&collaborator.user)?;
+            if user == owner.pubkey() {
+                skipped.push(format!("collaborator {user}: now the owner"));
+                continue;
+            }
+            self.add_collaborator(owner, &address, &user, collaborator.can_push, None)
+                .await?;
+            if collaborator.is_admin {
+                let repo = self.account::<Repository>(&address).await?;
+                let promote =
+                    instructions::set_collaborator_admin(&owner.pubkey(), &repo, &user, true);
+                self.send(&[promote], owner, &[]).await?;
+            }
+        }
+
+        let Some(policy) = &snapshot.release_policy else {
+            return Ok(ImportReport { address, skipped });
+        };
+        let managers = policy
+            .managers
+            .iter()
+            .map(|manager| parse_key(manager))
+            .collect::<Result<Vec<_>>>()?;
+        let repo = self.account::<Repository>(&address).await?;
+        let set_policy = instructions::set_release_policy(
+            &owner.pubkey(),
+            &repo,
+            managers.clone(),
+            policy.threshold,
+            &[],
+        );
+        self.send(&[set_policy], owner, &[]).await?;
+
+        for release in &snapshot.releases {
+            if !managers.contains(&owner.pubkey()) {
+                skipped.push(format!(
+                    "release {}: only a release manager can propose it",
+                    release.tag
+                ));
+                continue;
+            }
+            let create = instructions::create_release(
+                &owner.pubkey(),
+                &repo,
+                &release.tag,
+                (&release.commit).try_into()?,
+                &release.ipfs_cid,
+            );
+            self.send(&[create], owner, &[]).await?;
+            for asset in &release.assets {
+                let asset = ReleaseAsset {
+                    filename: asset.filename.clone(),
+                    size: asset.size,
+                    ipfs_cid: asset.ipfs_cid.clone(),
+                    checksum: (&asset.checksum).try_into()?,
+                };
+                let add =
+                    instructions::add_release_asset(&owner.pubkey(), &repo, &release.tag, asset);
+                self.send(&[add], owner, &[]).await?;
+            }
+            if release.is_final {
+                skipped.push(format!(
+                    "release {}: proposed again, needs {} approvals to be final",
+                    release.tag, policy.threshold
+                ));
+            }
+        }
+
+        Ok(ImportReport { address, skipped })
+    }
+
+    /// Every account of type `T` whose first field is `repo`'s id.
+    async fn owned_by_repo<T: AccountDeserialize + Discriminator>(
+        &self,
+        repo: &Repository,
+    ) -> Result<Vec<T>> {
+        let filters = vec![
+            RpcFilterType::Memcmp(Memcmp::new_base58_encoded(0, &T::DISCRIMINATOR)),
+            RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+                REPOSITORY_OFFSET,
+                repo.id.as_ref(),
+            )),
+        ];
+        let accounts = self
+            .rpc()
+            .get_program_accounts_with_config(
+                &crate::program_id(),
+                RpcProgramAccountsConfig {
+                    filters: Some(filters),
+                    account_config: RpcAccountInfoConfig {
+                        encoding: Some(UiAccountEncoding::Base64),
+                        commitment: Some(self.rpc().commitment()),
+                        ..RpcAccountInfoConfig::default()
+                    },
+                    ..RpcProgramAccountsConfig::default()
+                },
+            )
+            .await?;
+        accounts
+            .iter()
+            .map(|(address, account)| decode(address, &account.owner, &account.data))
+            .collect()
+    }
+}
+
+fn parse_key(key: &str) -> Result<Pubkey> {
+    key.parse()
+        .map_err(|_| Error::Snapshot(format!("{key} is not a valid address")))
+}
@@ -0,0 +1,333 @@
+//! Archive storage on IPFS.
+//!
+//! Archives are added through a [`PinningService`] backend and fetched back
+//! through a public gateway. A CID is only worth recording on chain once
+//! something has pinned it, so [`Storage::add`] waits for the backend to
+//! report the pin before returning.
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use reqwest::multipart::{Form, Part};
+use serde::Deserialize;
+
+use crate::{Error, Result};
+
+/// Attempts per request before a transient failure is returned.
+const ATTEMPTS: u32 = 3;
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PinStatus {
+    Queued,
+    Pinning,
+    Pinned,
+    Failed,
+}
+
+/// A service that adds data to IPFS and keeps it pinned.
+#[async_trait]
+pub trait PinningService: Send + Sync {
+    /// Add and pin `data`, returning its CID. The pin may still be in
+    /// progress when this returns.
+    async fn add(&self, data: Vec<u8>) -> Result<String>;
+
+    async fn status(&self, cid: &str) -> Result<PinStatus>;
+}
+
+/// A local or self-hosted Kubo node, through its RPC API (usually
+/// `http://127.0.0.1:5001`).
+pub struct Kubo {
+    http: reqwest::Client,
+    api: String,
+}
+
+impl Kubo {
+    pub fn new(api: &str) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            api: api.trim_end_matches('/').to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct KuboAdded {
+    #[serde(rename = "Hash")]
+    hash: String,
+}
+
+#[async_trait]
+impl PinningService for Kubo {
+    async fn add(&self, data: Vec<u8>) -> Result<String> {
+        let added: KuboAdded = self
+            .http
+            .post(format!("{}/api/v0/add?pin=true&cid-version=1", self.api))
+            .multipart(archive_form(data))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(added.hash)
+    }
+
+    async fn status(&self, cid: &str) -> Result<PinStatus> {
+        // Kubo answers pin/ls with an error for CIDs it has not pinned
+        let response = self
+            .http
+            .post(format!("{}/api/v0/pin/ls", self.api))
+            .query(&[("arg", cid)])
+            .send()
+            .await?;
+        Ok(if response.status().is_success() {
+            PinStatus::Pinned
+        } else {
+            PinStatus::Failed
+        })
+    }
+}
+
+/// Pinata, the service the TypeScript SDK pins to.
+pub struct Pinata {
+    http: reqwest::Client,
+    endpoint: String,
+    api_key: String,
+    api_secret: String,
+}
+
+impl Pinata {
+    pub const ENDPOINT: &'static str = "https://api.pinata.cloud";
+
+    pub fn new(api_key: String, api_secret: String) -> Self {
+        Self::with_endpoint(Self::ENDPOINT, api_key, api_secret)
+    }
+
+    pub fn with_endpoint(endpoint: &str, api_key: String, api_secret: String) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            api_key,
+            api_secret,
+        }
+    }
+
+    fn request(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        request
+            .header("pinata_api_key", &self.api_key)
+            .header("pinata_secret_api_key", &self.api_secret)
+    }
+}
+
+#[derive(Deserialize)]
+struct PinataPinned {
+    #[serde(rename = "IpfsHash")]
+    ipfs_hash: String,
+}
+
+#[derive(Deserialize)]
+struct PinataPinList {
+    count: u64,
+}
+
+#[async_trait]
+impl PinningService for Pinata {
+    async fn add(&self, data: Vec<u8>) -> Result<String> {
+        let url = format!("{}/pinning/pinFileToIPFS", self.endpoint);
+        let pinned: PinataPinned = self
+            .request(self.http.post(url))
+            .multipart(archive_form(data))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(pinned.ipfs_hash)
+    }
+
+    async fn status(&self, cid: &str) -> Result<PinStatus> {
+        let url = format!("{}/data/pinList", self.endpoint);
+        let pins: PinataPinList = self
+            .request(self.http.get(url))
+            .query(&[("hashContains", cid), ("status", "pinned")])
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(if pins.count > 0 {
+            PinStatus::Pinned
+        } else {
+            PinStatus::Pinning
+        })
+    }
+}
+
+/// web3.storage, through its HTTP upload API.
+pub struct Web3Storage {
+    http: reqwest::Client,
+    endpoint: String,
+    token: String,
+}
+
+impl Web3Storage {
+    pub const ENDPOINT: &'static str = "https://api.web3.storage";
+
+    pub fn new(token: String) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            endpoint: Self::ENDPOINT.to_string(),
+            token,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Web3Uploaded {
+    cid: String,
+}
+
+#[derive(Deserialize)]
+struct Web3Status {
+    pins: Vec<Web3Pin>,
+}
+
+#[derive(Deserialize)]
+struct Web3Pin {
+    status: String,
+}
+
+#[async_trait]
+impl PinningService for Web3Storage {
+    async fn add(&self, data: Vec<u8>) -> Result<String> {
+        let uploaded: Web3Uploaded = self
+            .http
+            .post(format!("{}/upload", self.endpoint))
+            .bearer_auth(&self.token)
+            .header("X-Name", "repo.bundle")
+            .body(data)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(uploaded.cid)
+    }
+
+    async fn status(&self, cid: &str) -> Result<PinStatus> {
+        let status: Web3Status = self
+            .http
+            .get(format!("{}/status/{cid}", self.endpoint))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        // Pinned once any pinning node has it
+        let statuses = status.pins.iter().map(|pin| pin.status.as_str());
+        Ok(
+            statuses.fold(PinStatus::Queued, |best, status| match (best, status) {
+                (PinStatus::Pinned, _) | (_, "Pinned") => PinStatus::Pinned,
+                (_, "Pinning") => PinStatus::Pinning,
+                (PinStatus::Queued, "PinError") => PinStatus::Failed,
+                (best, _) => best,
+            }),
+        )
+    }
+}
+
+/// A pinning backend plus the gateway archives are read back through.
+pub struct Storage {
+    backend: Box<dyn PinningService>,
+    http: reqwest::Client,
+    gateway: String,
+    /// How long `add` waits for the pin
+    pin_timeout: Duration,
+}
+
+impl Storage {
+    pub const GATEWAY: &'static str = "https://ipfs.io/ipfs/";
+
+    pub fn new(backend: impl PinningService + 'static) -> Self {
+        Self {
+            backend: Box::new(backend),
+            http: reqwest::Client::new(),
+            gateway: Self::GATEWAY.to_string(),
+            pin_timeout: Duration::from_secs(120),
+        }
+    }
+
+    pub fn with_gateway(mut self, gateway: &str) -> Self {
+        self.gateway = gateway.to_string();
+        self
+    }
+
+    pub fn with_pin_timeout(mut self, timeout: Duration) -> Self {
+        self.pin_timeout = timeout;
+        self
+    }
+
+    /// Add `data` and wait until the backend reports it pinned. Returns the
+    /// CID.
+    pub async fn add(&self, data: Vec<u8>) -> Result<String> {
+        let cid = retry(|| self.backend.add(data.clone())).await?;
+        self.wait_pinned(&cid).await?;
+        Ok(cid)
+    }
+
+    /// Poll the backend until `cid` is pinned, failing if the pin fails or
+    /// the pin timeout passes first.
+    pub async fn wait_pinned(&self, cid: &str) -> Result<()> {
+        let deadline = tokio::time::Instant::now() + self.pin_timeout;
+        loop {
+            match retry(|| self.backend.status(cid)).await? {
+                PinStatus::Pinned => return Ok(()),
+                PinStatus::Failed => return Err(Error::Storage(format!("pinning {cid} failed"))),
+                PinStatus::Queued | PinStatus::Pinning => {}
+            }
+            if tokio::time::Instant::now() + POLL_INTERVAL > deadline {
+                return Err(Error::Storage(format!("{cid} was not pinned in time")));
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    /// Download `cid` through the gateway.
+    pub async fn get(&self, cid: &str) -> Result<Vec<u8>> {
+        let url = format!("{}/{}", self.gateway.trim_end_matches('/'), cid);
+        retry(|| async {
+            let response = self.http.get(&url).send().await?.error_for_status()?;
+            Ok(response.bytes().await?.to_vec())
+        })
+        .await
+    }
+}
+
+fn archive_form(data: Vec<u8>) -> Form {
+    Form::new().part("file", Part::bytes(data).file_name("repo.bundle"))
+}
+
+/// Run `request` until it succeeds or `ATTEMPTS` is used up, backing off
+/// between attempts. Client errors (4xx) are not retried.
+async fn retry<T, F, Fut>(mut request: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = Result<T>>,
+{
+    let mut delay = RETRY_DELAY;
+    let mut attempt = 1;
+    loop {
+        match request().await {
+            Err(Error::Http(err))
+                if attempt < ATTEMPTS && !err.status().is_some_and(|s| s.is_client_error()) =>
+            {
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
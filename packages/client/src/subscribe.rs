@@ -0,0 +1,176 @@
+//! Websocket subscriptions.
+//!
+//! Each subscription runs in its own task, which reconnects with backoff
+//! whenever the websocket drops and resubscribes, so a `Subscription` keeps
+//! yielding until it is dropped. Account subscriptions re-read the account
+//! after every (re)subscribe, so an update missed while disconnected still
+//! shows up; log notifications missed while disconnected are lost.
+
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use anchor_lang::AccountDeserialize;
+use futures::{Stream, StreamExt};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{
+    nonblocking::{pubsub_client::PubsubClient, rpc_client::RpcClient},
+    rpc_config::{RpcAccountInfoConfig, RpcTransactionLogsConfig, RpcTransactionLogsFilter},
+};
+use solana_sdk::{
+    account::Account, commitment_config::CommitmentConfig, pubkey::Pubkey, signature::Signature,
+};
+use tokio::{sync::mpsc, task::JoinHandle};
+
+use crate::events::{self, Event};
+
+const MIN_RETRY: Duration = Duration::from_millis(500);
+const MAX_RETRY: Duration = Duration::from_secs(30);
+
+/// A stream of decoded notifications. Dropping it unsubscribes.
+pub struct Subscription<T> {
+    items: mpsc::UnboundedReceiver<T>,
+    task: JoinHandle<()>,
+}
+
+impl<T> Stream for Subscription<T> {
+    type Item = T;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        self.items.poll_recv(cx)
+    }
+}
+
+impl<T> Drop for Subscription<T> {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Events from one successful transaction that invoked the program.
+pub struct ProgramLogs {
+    pub signature: Signature,
+    pub slot: u64,
+    pub events: Vec<Event>,
+}
+
+pub(crate) fn watch_account<T>(
+    rpc_url: String,
+    ws_url: String,
+    commitment: CommitmentConfig,
+    address: Pubkey,
+) -> Subscription<T>
+where
+    T: AccountDeserialize + Send + 'static,
+{
+    let (sender, items) = mpsc::unbounded_channel();
+    let task = tokio::spawn(async move {
+        let rpc = RpcClient::new_with_commitment(rpc_url, commitment);
+        let config = RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            commitment: Some(commitment),
+            ..RpcAccountInfoConfig::default()
+        };
+        // Returns whether anyone is still listening. Accounts that fail to
+        // decode (closed, or not yet created) are skipped.
+        let forward = |account: Option<Account>| match account.and_then(|account| {
+            crate::client::decode::<T>(&address, &account.owner, &account.data).ok()
+        }) {
+            Some(value) => sender.send(value).is_ok(),
+            None => true,
+        };
+
+        let mut retry = MIN_RETRY;
+        loop {
+            if let Ok(pubsub) = PubsubClient::new(&ws_url).await {
+                if let Ok((mut updates, _unsubscribe)) = pubsub
+                    .account_subscribe(&address, Some(config.clone()))
+                    .await
+                {
+                    retry = MIN_RETRY;
+                    if let Ok(current) = rpc.get_account_with_commitment(&address, commitment).await
+                    {
+                        if !forward(current.value) {
+                            return;
+                        }
+                    }
+                    while let Some(update) = updates.next().await {
+                        if !forward(update.value.decode()) {
+                            return;
+                        }
+                    }
+                }
+            }
+
+            if sender.is_closed() {
+                return;
+            }
+            tokio::time::sleep(retry).await;
+            retry = (retry * 2).min(MAX_RETRY);
+        }
+    });
+
+    Subscription { items, task }
+}
+
+pub(crate) fn watch_logs(
+    ws_url: String,
+    commitment: CommitmentConfig,
+) -> Subscription<ProgramLogs> {
+    let (sender, items) = mpsc::unbounded_channel();
+    let task = tokio::spawn(async move {
+        let filter = RpcTransactionLogsFilter::Mentions(vec![crate::program_id().to_string()]);
+        let config = RpcTransactionLogsConfig {
+            commitment: Some(commitment),
+        };
+
+        let mut retry = MIN_RETRY;
+        loop {
+            if let Ok(pubsub) = PubsubClient::new(&ws_url).await {
+                if let Ok((mut notifications, _unsubscribe)) =
+                    pubsub.logs_subscribe(filter.clone(), config.clone()).await
+                {
+                    retry = MIN_RETRY;
+                    while let Some(notification) = notifications.next().await {
+                        let logs = notification.value;
+                        let Ok(signature) = logs.signature.parse() else {
+                            continue;
+                        };
+                        if logs.err.is_some() {
+                            continue;
+                        }
+                        let item = ProgramLogs {
+                            signature,
+                            slot: notification.context.slot,
+                            events: events::parse_logs(&logs.logs),
+                        };
+                        if sender.send(item).is_err() {
+                            return;
+                        }
+                    }
+                }
+            }
+
+            if sender.is_closed() {
+                return;
+            }
+            tokio::time::sleep(retry).await;
+            retry = (retry * 2).min(MAX_RETRY);
+        }
+    });
+
+    Subscription { items, task }
+}
+
+/// The websocket endpoint paired with an RPC endpoint: same host, `ws`
+/// scheme, and the port above for a local validator's 8899.
+pub(crate) fn websocket_url(rpc_url: &str) -> String {
+    let url = if let Some(rest) = rpc_url.strip_prefix("https://") {
+        format!("wss://{rest}")
+    } else if let Some(rest) = rpc_url.strip_prefix("http://") {
+        format!("ws://{rest}")
+    } else {
+        rpc_url.to_string()
+    };
+    url.replace(":8899", ":8900")
+}
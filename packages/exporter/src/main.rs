@@ -0,0 +1,99 @@
+//! Vanish state exporter.
+//!
+//! `snapshot` captures every account owned by the Vanish program into a
+//! single JSON file. `replay` turns a snapshot into an account directory that
+//! `solana-test-validator --account-dir` loads at genesis, giving a local
+//! cluster that mirrors the source cluster's repositories for recovery drills
+//! or analytics.
+
+mod replay;
+mod snapshot;
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use clap::{Parser, Subcommand};
+use solana_client::rpc_client::RpcClient;
+
+use crate::snapshot::Snapshot;
+
+#[derive(Parser)]
+#[command(
+    name = "vanish-export",
+    version,
+    about = "Export Vanish program state and replay it onto another cluster"
+)]
+struct Args {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Write every account owned by the program to a snapshot file
+    Snapshot {
+        /// Solana RPC endpoint to export from
+        #[arg(
+            long,
+            env = "VANISH_RPC_URL",
+            default_value = "https://api.devnet.solana.com"
+        )]
+        url: String,
+
+        /// Snapshot file to write
+        #[arg(long, short)]
+        out: PathBuf,
+    },
+
+    /// Write a snapshot's accounts as a `solana-test-validator --account-dir`
+    Replay {
+        /// Snapshot file produced by `snapshot`
+        #[arg(long)]
+        snapshot: PathBuf,
+
+        /// Directory to write one account file per account into
+        #[arg(long, short)]
+        out: PathBuf,
+    },
+}
+
+fn main() -> Result<()> {
+    match Args::parse().command {
+        Command::Snapshot { url, out } => {
+            let rpc = RpcClient::new(url.clone());
+            let snapshot = Snapshot::capture(&rpc, &url)?;
+
+            let file = File::create(&out)
+                .with_context(|| format!("failed to create {}", out.display()))?;
+            serde_json::to_writer(BufWriter::new(file), &snapshot)?;
+
+            for (kind, count) in snapshot.counts() {
+                eprintln!("{kind:>16}  {count}");
+            }
+            eprintln!(
+                "exported {} accounts at slot {} to {}",
+                snapshot.accounts.len(),
+                snapshot.slot,
+                out.display()
+            );
+        }
+        Command::Replay { snapshot, out } => {
+            let file = File::open(&snapshot)
+                .with_context(|| format!("failed to open {}", snapshot.display()))?;
+            let snapshot: Snapshot = serde_json::from_reader(BufReader::new(file))
+                .context("failed to parse snapshot")?;
+
+            let written = replay::write_account_dir(&snapshot, &out)?;
+            eprintln!("wrote {written} accounts to {}", out.display());
+            eprintln!(
+                "start a mirror with: solana-test-validator --account-dir {} --bpf-program {} vanish_program.so",
+                out.display(),
+                snapshot.program_id
+            );
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,56 @@
+//! Replaying snapshots onto a local cluster.
+//!
+//! Program-owned accounts cannot be written on a live cluster, so replay
+//! targets genesis: each account is written in the JSON format produced by
+//! `solana account --output json`, which `solana-test-validator` loads with
+//! `--account-dir`.
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+use crate::snapshot::Snapshot;
+
+#[derive(Serialize)]
+struct CliAccount<'a> {
+    pubkey: &'a str,
+    account: UiAccount<'a>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct UiAccount<'a> {
+    lamports: u64,
+    data: (&'a str, &'static str),
+    owner: &'a str,
+    executable: bool,
+    rent_epoch: u64,
+}
+
+/// Write one `<address>.json` file per snapshot account into `dir`, returning
+/// how many were written.
+pub fn write_account_dir(snapshot: &Snapshot, dir: &Path) -> Result<usize> {
+    snapshot.check_version()?;
+    fs::create_dir_all(dir).with_context(|| format!("failed to create {}", dir.display()))?;
+
+    for account in &snapshot.accounts {
+        let cli_account = CliAccount {
+            pubkey: &account.address,
+            account: UiAccount {
+                lamports: account.lamports,
+                data: (&account.data, "base64"),
+                owner: &account.owner,
+                executable: account.executable,
+                rent_epoch: account.rent_epoch,
+            },
+        };
+
+        let path = dir.join(format!("{}.json", account.address));
+        fs::write(&path, serde_json::to_vec_pretty(&cli_account)?)
+            .with_context(|| format!("failed to write {}", path.display()))?;
+    }
+
+    Ok(snapshot.accounts.len())
+}
@@ -0,0 +1,186 @@
+//! Snapshot format.
+//!
+//! A snapshot is one JSON document holding the raw data of every account the
+//! program owns, tagged with its Anchor account type so analytics jobs can
+//! pick out what they need without decoding everything.
+
+use std::collections::BTreeMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anchor_lang::Discriminator;
+use anyhow::{bail, Context, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{account::Account, pubkey::Pubkey};
+use vanish_program::{
+    AccessGrant, AccessKey, AccessPricing, ActivityCursor, Attestation, AuditEntry, BadgeConfig,
+    Branch, BranchGrant, Builder, CheckRun, CiAttestor, Collaborator, CollaboratorSlot, Config,
+    Contribution, ContributorSnapshot, DealOracle, FilecoinDeal, ForkLink, History, List,
+    ListEntry, NameLock, NameStake, Package, PackageVersion, PendingAction, PinAttestation, Pinner,
+    Profile, Project, ProjectAuditEntry, ProjectMember, ProjectRepo, PurgeReceipt, PushLog,
+    ReadGrant, ReclaimNotice, RecoveryPlan, Redirect, Release, ReleaseApproval, ReleasePolicy,
+    RentVault, RepoName, RepoNft, Repository, RewardPool, Star, StarTree, Stats, Submodule,
+    Timelock, Tombstone, Verification, Verifier, Webhook,
+};
+
+/// Bumped whenever the snapshot layout changes incompatibly.
+pub const FORMAT_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+pub struct Snapshot {
+    pub version: u32,
+    pub program_id: String,
+    /// RPC endpoint the snapshot was taken from
+    pub source: String,
+    /// Slot observed just before the accounts were fetched
+    pub slot: u64,
+    pub exported_at: u64,
+    pub accounts: Vec<SnapshotAccount>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SnapshotAccount {
+    pub address: String,
+    /// Anchor account type, or `Unknown` if the discriminator is not recognised
+    pub kind: String,
+    pub lamports: u64,
+    pub owner: String,
+    pub executable: bool,
+    pub rent_epoch: u64,
+    /// Base64-encoded account data
+    pub data: String,
+}
+
+impl Snapshot {
+    /// Fetch every account owned by the Vanish program.
+    pub fn capture(rpc: &RpcClient, source: &str) -> Result<Self> {
+        let slot = rpc.get_slot().context("failed to fetch slot")?;
+        let accounts = rpc
+            .get_program_accounts(&vanish_program::ID)
+            .context("failed to fetch program accounts")?;
+
+        let exported_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default();
+
+        Ok(Self {
+            version: FORMAT_VERSION,
+            program_id: vanish_program::ID.to_string(),
+            source: source.to_string(),
+            slot,
+            exported_at,
+            accounts: accounts
+                .into_iter()
+                .map(|(address, account)| SnapshotAccount::new(&address, &account))
+                .collect(),
+        })
+    }
+
+    /// Number of accounts of each kind.
+    pub fn counts(&self) -> BTreeMap<&str, usize> {
+        let mut counts = BTreeMap::new();
+        for account in &self.accounts {
+            *counts.entry(account.kind.as_str()).or_default() += 1;
+        }
+        counts
+    }
+
+    pub fn check_version(&self) -> Result<()> {
+        if self.version != FORMAT_VERSION {
+            bail!(
+                "snapshot format version {} is not supported (expected {FORMAT_VERSION})",
+                self.version
+            );
+        }
+        Ok(())
+    }
+}
+
+impl SnapshotAccount {
+    fn new(address: &Pubkey, account: &Account) -> Self {
+        Self {
+            address: address.to_string(),
+            kind: account_kind(&account.data).to_string(),
+            lamports: account.lamports,
+            owner: account.owner.to_string(),
+            executable: account.executable,
+            rent_epoch: account.rent_epoch,
+            data: STANDARD.encode(&account.data),
+        }
+    }
+}
+
+/// Name the Anchor account type `data` holds, by its discriminator.
+fn account_kind(data: &[u8]) -> &'static str {
+    let Some(discriminator) = data.get(..8) else {
+        return "Unknown";
+    };
+
+    let kinds: [(&[u8], &str); 58] = [
+        (&Config::DISCRIMINATOR, "Config"),
+        (&Profile::DISCRIMINATOR, "Profile"),
+        (&Project::DISCRIMINATOR, "Project"),
+        (&ProjectRepo::DISCRIMINATOR, "ProjectRepo"),
+        (&ProjectMember::DISCRIMINATOR, "ProjectMember"),
+        (&RentVault::DISCRIMINATOR, "RentVault"),
+        (&ProjectAuditEntry::DISCRIMINATOR, "ProjectAuditEntry"),
+        (&List::DISCRIMINATOR, "List"),
+        (&ListEntry::DISCRIMINATOR, "ListEntry"),
+        (&ActivityCursor::DISCRIMINATOR, "ActivityCursor"),
+        (&Repository::DISCRIMINATOR, "Repository"),
+        (&Redirect::DISCRIMINATOR, "Redirect"),
+        (&RepoName::DISCRIMINATOR, "RepoName"),
+        (&NameLock::DISCRIMINATOR, "NameLock"),
+        (&Tombstone::DISCRIMINATOR, "Tombstone"),
+        (&PurgeReceipt::DISCRIMINATOR, "PurgeReceipt"),
+        (&NameStake::DISCRIMINATOR, "NameStake"),
+        (&ReclaimNotice::DISCRIMINATOR, "ReclaimNotice"),
+        (&RecoveryPlan::DISCRIMINATOR, "RecoveryPlan"),
+        (&Branch::DISCRIMINATOR, "Branch"),
+        (&ForkLink::DISCRIMINATOR, "ForkLink"),
+        (&Submodule::DISCRIMINATOR, "Submodule"),
+        (&Collaborator::DISCRIMINATOR, "Collaborator"),
+        (&CollaboratorSlot::DISCRIMINATOR, "CollaboratorSlot"),
+        (&BranchGrant::DISCRIMINATOR, "BranchGrant"),
+        (&Webhook::DISCRIMINATOR, "Webhook"),
+        (&Contribution::DISCRIMINATOR, "Contribution"),
+        (&ContributorSnapshot::DISCRIMINATOR, "ContributorSnapshot"),
+        (&History::DISCRIMINATOR, "History"),
+        (&AuditEntry::DISCRIMINATOR, "AuditEntry"),
+        (&ReadGrant::DISCRIMINATOR, "ReadGrant"),
+        (&AccessKey::DISCRIMINATOR, "AccessKey"),
+        (&AccessPricing::DISCRIMINATOR, "AccessPricing"),
+        (&AccessGrant::DISCRIMINATOR, "AccessGrant"),
+        (&CiAttestor::DISCRIMINATOR, "CiAttestor"),
+        (&CheckRun::DISCRIMINATOR, "CheckRun"),
+        (&ReleasePolicy::DISCRIMINATOR, "ReleasePolicy"),
+        (&Release::DISCRIMINATOR, "Release"),
+        (&ReleaseApproval::DISCRIMINATOR, "ReleaseApproval"),
+        (&Builder::DISCRIMINATOR, "Builder"),
+        (&Attestation::DISCRIMINATOR, "Attestation"),
+        (&RepoNft::DISCRIMINATOR, "RepoNft"),
+        (&Timelock::DISCRIMINATOR, "Timelock"),
+        (&PendingAction::DISCRIMINATOR, "PendingAction"),
+        (&BadgeConfig::DISCRIMINATOR, "BadgeConfig"),
+        (&PushLog::DISCRIMINATOR, "PushLog"),
+        (&Stats::DISCRIMINATOR, "Stats"),
+        (&Package::DISCRIMINATOR, "Package"),
+        (&PackageVersion::DISCRIMINATOR, "PackageVersion"),
+        (&Star::DISCRIMINATOR, "Star"),
+        (&StarTree::DISCRIMINATOR, "StarTree"),
+        (&Verifier::DISCRIMINATOR, "Verifier"),
+        (&Verification::DISCRIMINATOR, "Verification"),
+        (&DealOracle::DISCRIMINATOR, "DealOracle"),
+        (&FilecoinDeal::DISCRIMINATOR, "FilecoinDeal"),
+        (&Pinner::DISCRIMINATOR, "Pinner"),
+        (&PinAttestation::DISCRIMINATOR, "PinAttestation"),
+        (&RewardPool::DISCRIMINATOR, "RewardPool"),
+    ];
+
+    kinds
+        .into_iter()
+        .find(|(known, _)| *known == discriminator)
+        .map_or("Unknown", |(_, kind)| kind)
+}
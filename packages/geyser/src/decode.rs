@@ -0,0 +1,68 @@
+//! JSON views of the account types the plugin publishes.
+
+use anchor_lang::{AccountDeserialize, Discriminator};
+use serde_json::{json, Value};
+use vanish_program::{Collaborator, Multihash, Repository, Star};
+
+/// Account type name and JSON state, for the kinds the plugin publishes.
+pub fn account(data: &[u8]) -> Option<(&'static str, Value)> {
+    let discriminator = data.get(..8)?;
+    let mut data = data;
+
+    if discriminator == Repository::DISCRIMINATOR {
+        let repo = Repository::try_deserialize(&mut data).ok()?;
+        Some((
+            "Repository",
+            json!({
+                "id": repo.id.to_string(),
+                "owner": repo.owner.to_string(),
+                "creator": repo.creator.to_string(),
+                "name": repo.name,
+                "description": repo.description,
+                "is_private": repo.is_private,
+                "flags": repo.flags,
+                "head_commit": hex(&repo.head_commit.to_multihash()),
+                "ipfs_cid": repo.ipfs_cid.to_string(),
+                "stars": repo.stars,
+                "collaborator_count": repo.collaborator_count,
+                "created_at": repo.created_at,
+                "updated_at": repo.updated_at,
+                "deleted_at": repo.deleted_at,
+            }),
+        ))
+    } else if discriminator == Collaborator::DISCRIMINATOR {
+        let collaborator = Collaborator::try_deserialize(&mut data).ok()?;
+        Some((
+            "Collaborator",
+            json!({
+                "repository": collaborator.repository.to_string(),
+                "user": collaborator.user.to_string(),
+                "can_push": collaborator.can_push,
+                "is_admin": collaborator.is_admin,
+                "index": collaborator.index,
+                "added_at": collaborator.added_at,
+                "last_push_at": collaborator.last_push_at,
+            }),
+        ))
+    } else if discriminator == Star::DISCRIMINATOR {
+        let star = Star::try_deserialize(&mut data).ok()?;
+        Some((
+            "Star",
+            json!({
+                "user": star.user.to_string(),
+                "repository": star.repository.to_string(),
+                "starred_at": star.starred_at,
+            }),
+        ))
+    } else {
+        None
+    }
+}
+
+fn hex(commit: &Multihash) -> String {
+    commit
+        .digest
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
@@ -0,0 +1,152 @@
+//! Geyser plugin streaming Vanish account state to NATS.
+//!
+//! The validator hands every account write to `update_account`. Writes to
+//! Repository, Collaborator and Star accounts owned by the Vanish program are
+//! decoded and published as JSON on `<subject_prefix>.<kind>`, so explorers
+//! can track every repository without polling RPC or parsing logs.
+//! Publishing happens on a background thread; the validator thread only
+//! decodes and queues. Kafka is not supported yet: rdkafka needs librdkafka
+//! built into the validator host.
+//!
+//! Closed accounts are handed back to the system program before the
+//! validator reports them, so closes are not published; consumers that need
+//! them should follow the program's events.
+//!
+//! Configuration, in the file passed to the validator's
+//! `--geyser-plugin-config`:
+//!
+//! ```json
+//! {
+//!     "libpath": "/path/to/libvanish_geyser.so",
+//!     "nats_url": "nats://127.0.0.1:4222",
+//!     "subject_prefix": "vanish"
+//! }
+//! ```
+
+mod decode;
+mod nats;
+
+use serde::Deserialize;
+use solana_geyser_plugin_interface::geyser_plugin_interface::{
+    GeyserPlugin, GeyserPluginError, ReplicaAccountInfoVersions, Result,
+};
+use solana_sdk::pubkey::Pubkey;
+
+use nats::{Message, Publisher};
+
+#[derive(Deserialize)]
+struct Config {
+    nats_url: String,
+    #[serde(default = "default_subject_prefix")]
+    subject_prefix: String,
+}
+
+fn default_subject_prefix() -> String {
+    "vanish".to_string()
+}
+
+#[derive(Debug, Default)]
+pub struct VanishGeyser {
+    /// Set between on_load and on_unload
+    publisher: Option<Publisher>,
+    subject_prefix: String,
+}
+
+impl GeyserPlugin for VanishGeyser {
+    fn name(&self) -> &'static str {
+        "vanish-geyser"
+    }
+
+    fn on_load(&mut self, config_file: &str, _is_reload: bool) -> Result<()> {
+        let config = std::fs::read_to_string(config_file)?;
+        let config: Config = serde_json::from_str(&config).map_err(|err| {
+            GeyserPluginError::ConfigFileReadError {
+                msg: err.to_string(),
+            }
+        })?;
+
+        let publisher = Publisher::start(&config.nats_url)
+            .map_err(|err| GeyserPluginError::Custom(err.into()))?;
+        self.publisher = Some(publisher);
+        self.subject_prefix = config.subject_prefix;
+        Ok(())
+    }
+
+    fn on_unload(&mut self) {
+        if let Some(publisher) = self.publisher.take() {
+            publisher.stop();
+        }
+    }
+
+    fn update_account(
+        &self,
+        account: ReplicaAccountInfoVersions,
+        slot: u64,
+        is_startup: bool,
+    ) -> Result<()> {
+        let Some(publisher) = &self.publisher else {
+            return Ok(());
+        };
+
+        let (pubkey, owner, data, write_version) = match account {
+            ReplicaAccountInfoVersions::V0_0_1(info) => {
+                (info.pubkey, info.owner, info.data, info.write_version)
+            }
+            ReplicaAccountInfoVersions::V0_0_2(info) => {
+                (info.pubkey, info.owner, info.data, info.write_version)
+            }
+            ReplicaAccountInfoVersions::V0_0_3(info) => {
+                (info.pubkey, info.owner, info.data, info.write_version)
+            }
+        };
+        if owner != vanish_program::ID.as_ref() {
+            return Ok(());
+        }
+        let Some((kind, state)) = decode::account(data) else {
+            return Ok(());
+        };
+
+        let pubkey =
+            Pubkey::try_from(pubkey).map_err(|_| GeyserPluginError::AccountsUpdateError {
+                msg: "account pubkey is not 32 bytes".to_string(),
+            })?;
+        let payload = serde_json::json!({
+            "pubkey": pubkey.to_string(),
+            "slot": slot,
+            "write_version": write_version,
+            "is_startup": is_startup,
+            "kind": kind,
+            "account": state,
+        });
+        let message = Message {
+            subject: format!("{}.{}", self.subject_prefix, kind.to_lowercase()),
+            payload: payload.to_string().into_bytes(),
+        };
+        if !publisher.send(message) {
+            return Err(GeyserPluginError::AccountsUpdateError {
+                msg: "publisher stopped".to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    fn account_data_notifications_enabled(&self) -> bool {
+        true
+    }
+
+    fn transaction_notifications_enabled(&self) -> bool {
+        false
+    }
+}
+
+/// Entry point the validator loads the plugin through.
+///
+/// # Safety
+///
+/// Called by the validator's plugin loader, which takes ownership of the
+/// returned plugin.
+#[no_mangle]
+#[allow(improper_ctypes_definitions)]
+pub unsafe extern "C" fn _create_plugin() -> *mut dyn GeyserPlugin {
+    Box::into_raw(Box::<VanishGeyser>::default())
+}
@@ -0,0 +1,160 @@
+//! Minimal NATS publisher.
+//!
+//! Speaks the core text protocol directly: the plugin only ever publishes,
+//! and async-nats cannot share a build with solana-sdk 1.18 (they pin
+//! incompatible `zeroize` versions).
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// A message on its way to NATS
+pub struct Message {
+    pub subject: String,
+    pub payload: Vec<u8>,
+}
+
+/// Background thread publishing queued messages, reconnecting as needed.
+#[derive(Debug)]
+pub struct Publisher {
+    queue: mpsc::Sender<Message>,
+    thread: JoinHandle<()>,
+}
+
+impl Publisher {
+    /// Connect to `url` and start publishing. Fails if the first connection
+    /// cannot be made, so a bad config is caught at load time.
+    pub fn start(url: &str) -> io::Result<Self> {
+        let address = url.strip_prefix("nats://").unwrap_or(url).to_string();
+        let connection = Connection::open(&address)?;
+        let (queue, messages) = mpsc::channel();
+        let thread = thread::Builder::new()
+            .name("vanish-geyser-nats".to_string())
+            .spawn(move || run(address, connection, messages))?;
+        Ok(Self { queue, thread })
+    }
+
+    /// Queue a message. Only fails once the publisher thread has stopped.
+    pub fn send(&self, message: Message) -> bool {
+        self.queue.send(message).is_ok()
+    }
+
+    /// Publish whatever is still queued and stop.
+    pub fn stop(self) {
+        drop(self.queue);
+        let _ = self.thread.join();
+    }
+}
+
+fn run(address: String, mut connection: Connection, messages: mpsc::Receiver<Message>) {
+    let mut backoff = Duration::from_millis(500);
+    loop {
+        for message in &messages {
+            if let Err(err) = connection.publish(&message) {
+                // The message is dropped rather than holding up the queue
+                log::warn!("vanish-geyser: publish to {address} failed: {err}");
+                break;
+            }
+            backoff = Duration::from_millis(500);
+        }
+        if !connection.is_broken() {
+            // Queue closed: the plugin is unloading
+            let _ = connection.flush();
+            return;
+        }
+
+        connection = loop {
+            thread::sleep(backoff);
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+            match Connection::open(&address) {
+                Ok(connection) => break connection,
+                Err(err) => log::warn!("vanish-geyser: reconnect to {address} failed: {err}"),
+            }
+        };
+    }
+}
+
+struct Connection {
+    /// Shared with the reader thread, which answers server PINGs
+    writer: Arc<Mutex<io::BufWriter<TcpStream>>>,
+    broken: bool,
+}
+
+impl Connection {
+    fn open(address: &str) -> io::Result<Self> {
+        let stream = TcpStream::connect(address)?;
+        stream.set_nodelay(true)?;
+        let mut reader = BufReader::new(stream.try_clone()?);
+
+        let mut info = String::new();
+        reader.read_line(&mut info)?;
+        if !info.starts_with("INFO") {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unexpected greeting: {}", info.trim_end()),
+            ));
+        }
+
+        let writer = Arc::new(Mutex::new(io::BufWriter::new(stream)));
+        {
+            let mut writer = writer.lock().unwrap();
+            writer.write_all(
+                b"CONNECT {\"verbose\":false,\"pedantic\":false,\"name\":\"vanish-geyser\"}\r\n",
+            )?;
+            writer.flush()?;
+        }
+
+        let pong = Arc::clone(&writer);
+        thread::Builder::new()
+            .name("vanish-geyser-nats-read".to_string())
+            .spawn(move || {
+                for line in reader.lines() {
+                    let Ok(line) = line else { return };
+                    if line.starts_with("PING") {
+                        let mut writer = pong.lock().unwrap();
+                        if writer
+                            .write_all(b"PONG\r\n")
+                            .and_then(|_| writer.flush())
+                            .is_err()
+                        {
+                            return;
+                        }
+                    } else if line.starts_with("-ERR") {
+                        log::warn!("vanish-geyser: server error: {line}");
+                    }
+                }
+            })?;
+
+        Ok(Self {
+            writer,
+            broken: false,
+        })
+    }
+
+    fn publish(&mut self, message: &Message) -> io::Result<()> {
+        let mut writer = self.writer.lock().unwrap();
+        let result = write!(
+            writer,
+            "PUB {} {}\r\n",
+            message.subject,
+            message.payload.len()
+        )
+        .and_then(|_| writer.write_all(&message.payload))
+        .and_then(|_| writer.write_all(b"\r\n"))
+        .and_then(|_| writer.flush());
+        self.broken = result.is_err();
+        result
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.lock().unwrap().flush()
+    }
+
+    fn is_broken(&self) -> bool {
+        self.broken
+    }
+}
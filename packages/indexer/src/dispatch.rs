@@ -0,0 +1,368 @@
+//! Webhook delivery.
+//!
+//! Repository admins register webhooks on chain, but a `Webhook` account
+//! holds only hashes of its URL and secret. The indexer is configured with
+//! the endpoints it may call, and delivers an event to one only when a
+//! webhook of the event's repository subscribes to the event's class and
+//! commits to that endpoint's URL and, bound to the repository (see
+//! `Webhook::commitment`), its secret. Deliveries are POSTs of a JSON
+//! body signed with HMAC-SHA256 under the secret, in the
+//! `X-Vanish-Signature: sha256=<hex>` header.
+//!
+//! Events are delivered before their transaction is stored, so a restart
+//! can deliver an event twice but never skips one; receivers should dedupe
+//! on `X-Vanish-Delivery`. Failed deliveries are logged and not retried.
+
+use std::{fs, path::Path, time::Duration};
+
+use anchor_lang::{AccountDeserialize, Discriminator};
+use anyhow::{bail, Context, Result};
+use hmac::{Hmac, Mac};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use solana_client::{
+    nonblocking::rpc_client::RpcClient,
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::{Memcmp, RpcFilterType},
+};
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+use vanish_client::{commit, events::Event};
+use vanish_program::{webhook_events, Webhook};
+
+use crate::store::Store;
+
+/// How long an endpoint has to answer.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// An endpoint the indexer may deliver to. The same URL may be configured
+/// with several secrets, one per repository delivering to it.
+struct Endpoint {
+    url: String,
+    secret: String,
+    url_hash: [u8; 32],
+}
+
+/// A registered webhook with a configured endpoint.
+struct Subscription {
+    /// Repository id
+    repository: Pubkey,
+    events: u32,
+    endpoint: usize,
+}
+
+pub struct Dispatcher {
+    http: reqwest::Client,
+    endpoints: Vec<Endpoint>,
+    subscriptions: Vec<Subscription>,
+}
+
+impl Dispatcher {
+    /// Read endpoints from `path`, one `<url> <secret>` per line. Blank
+    /// lines and lines starting with `#` are skipped.
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+
+        let mut endpoints = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((url, secret)) = line.split_once(char::is_whitespace) else {
+                bail!(
+                    "{}:{}: expected `<url> <secret>`",
+                    path.display(),
+                    number + 1
+                );
+            };
+            let secret = secret.trim();
+            endpoints.push(Endpoint {
+                url: url.to_string(),
+                secret: secret.to_string(),
+                url_hash: Sha256::digest(url).into(),
+            });
+        }
+
+        Ok(Self {
+            http: reqwest::Client::builder().timeout(TIMEOUT).build()?,
+            endpoints,
+            subscriptions: Vec::new(),
+        })
+    }
+
+    /// Reload the webhooks registered on chain, keeping those whose URL and
+    /// secret commitment match a configured endpoint.
+    pub async fn refresh(&mut self, rpc: &RpcClient) -> Result<()> {
+        let accounts = rpc
+            .get_program_accounts_with_config(
+                &vanish_program::ID,
+                RpcProgramAccountsConfig {
+                    filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+                        0,
+                        &Webhook::DISCRIMINATOR,
+                    ))]),
+                    account_config: RpcAccountInfoConfig {
+                        commitment: Some(rpc.commitment()),
+                        ..RpcAccountInfoConfig::default()
+                    },
+                    ..RpcProgramAccountsConfig::default()
+                },
+            )
+            .await
+            .context("failed to fetch webhooks")?;
+
+        self.subscriptions.clear();
+        for (address, account) in accounts {
+            let Ok(webhook) = Webhook::try_deserialize(&mut account.data.as_slice()) else {
+                continue;
+            };
+            let mut candidates = self
+                .endpoints
+                .iter()
+                .enumerate()
+                .filter(|(_, endpoint)| endpoint.url_hash == webhook.url_hash)
+                .peekable();
+            let Some((_, first)) = candidates.peek() else {
+                continue;
+            };
+            let url = first.url.clone();
+            let Some((endpoint, _)) = candidates.find(|(_, endpoint)| {
+                Webhook::commitment(&webhook.repository, endpoint.secret.as_bytes())
+                    == webhook.secret_commitment
+            }) else {
+                eprintln!("webhook {address}: no secret configured for {url} matches");
+                continue;
+            };
+            self.subscriptions.push(Subscription {
+                repository: webhook.repository,
+                events: webhook.events,
+                endpoint,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Deliver one transaction's events to every subscribed endpoint.
+    pub async fn deliver(
+        &self,
+        store: &Store,
+        signature: &Signature,
+        slot: u64,
+        events: &[Event],
+    ) -> Result<()> {
+        if self.subscriptions.is_empty() {
+            return Ok(());
+        }
+
+        for (index, event) in events.iter().enumerate() {
+            let Some((class, repository, kind, data)) = describe(event) else {
+                continue;
+            };
+            // Renamed repositories are known by their id, which is the
+            // address they were created at
+            let id = match event {
+                Event::RepoRenamed(e) => e.id,
+                _ => store.repo_id(&repository).await?.unwrap_or(repository),
+            };
+
+            let body = json!({
+                "event": kind,
+                "repository": repository.to_string(),
+                "signature": signature.to_string(),
+                "slot": slot,
+                "data": data,
+            })
+            .to_string();
+            let delivery = format!("{signature}:{index}");
+
+            for subscription in &self.subscriptions {
+                if subscription.repository != id || subscription.events & class == 0 {
+                    continue;
+                }
+                let endpoint = &self.endpoints[subscription.endpoint];
+                if let Err(err) = self.post(endpoint, kind, &delivery, &body).await {
+                    eprintln!("failed to deliver {delivery} to {}: {err:#}", endpoint.url);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn post(
+        &self,
+        endpoint: &Endpoint,
+        kind: &str,
+        delivery: &str,
+        body: &str,
+    ) -> Result<()> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(endpoint.secret.as_bytes())
+            .expect("HMAC takes keys of any length");
+        mac.update(body.as_bytes());
+        let signature: String = mac
+            .finalize()
+            .into_bytes()
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect();
+
+        self.http
+            .post(&endpoint.url)
+            .header("Content-Type", "application/json")
+            .header("X-Vanish-Event", kind)
+            .header("X-Vanish-Delivery", delivery)
+            .header("X-Vanish-Signature", format!("sha256={signature}"))
+            .body(body.to_string())
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+/// The `webhook_events` class of `event`, the repository address it
+/// concerns, its name and its fields. `None` for events no webhook can
+/// subscribe to.
+fn describe(event: &Event) -> Option<(u32, Pubkey, &'static str, Value)> {
+    Some(match event {
+        // No webhook can exist before its repository does
+        Event::RepoCreated(_) => return None,
+        Event::ActivitySeen(_) => return None,
+        Event::RepoPushed(e) => (
+            webhook_events::PUSH,
+            e.repository,
+            "RepoPushed",
+            json!({
+                "pusher": e.pusher.to_string(),
+                "head_commit": commit::to_hex(&e.head_commit),
+                "ipfs_cid": e.ipfs_cid.to_string(),
+                "binding": e.binding.map(|binding| crate::store::hex(&binding)),
+                "timestamp": e.timestamp,
+            }),
+        ),
+        Event::BranchPushed(e) => (
+            webhook_events::BRANCH_PUSH,
+            e.repository,
+            "BranchPushed",
+            json!({
+                "branch": e.branch,
+                "pusher": e.pusher.to_string(),
+                "head_commit": commit::to_hex(&e.head_commit),
+                "ipfs_cid": e.ipfs_cid,
+                "timestamp": e.timestamp,
+            }),
+        ),
+        Event::RepoRenamed(e) => (
+            webhook_events::LIFECYCLE,
+            e.new_address,
+            "RepoRenamed",
+            json!({
+                "old_address": e.old_address.to_string(),
+                "old_name": e.old_name,
+                "new_name": e.new_name,
+                "timestamp": e.timestamp,
+            }),
+        ),
+        Event::OwnershipTransferred(e) => (
+            webhook_events::LIFECYCLE,
+            e.repository,
+            "OwnershipTransferred",
+            json!({
+                "old_owner": e.old_owner.to_string(),
+                "new_owner": e.new_owner.to_string(),
+            }),
+        ),
+        Event::OwnershipRenounced(e) => (
+            webhook_events::LIFECYCLE,
+            e.repository,
+            "OwnershipRenounced",
+            json!({
+                "old_owner": e.old_owner.to_string(),
+                "timestamp": e.timestamp,
+            }),
+        ),
+        Event::RepoDeleted(e) => (
+            webhook_events::LIFECYCLE,
+            e.repository,
+            "RepoDeleted",
+            json!({
+                "purgeable_at": e.purgeable_at,
+                "timestamp": e.timestamp,
+            }),
+        ),
+        Event::RepoRestored(e) => (
+            webhook_events::LIFECYCLE,
+            e.repository,
+            "RepoRestored",
+            json!({ "timestamp": e.timestamp }),
+        ),
+        Event::RepoPurged(e) => (
+            webhook_events::LIFECYCLE,
+            e.repository,
+            "RepoPurged",
+            json!({ "timestamp": e.timestamp }),
+        ),
+        Event::RepoStarred(e) => (
+            webhook_events::STAR,
+            e.repository,
+            "RepoStarred",
+            json!({
+                "user": e.user.to_string(),
+                "tip": e.tip,
+                "timestamp": e.timestamp,
+            }),
+        ),
+        Event::RepoUnstarred(e) => (
+            webhook_events::STAR,
+            e.repository,
+            "RepoUnstarred",
+            json!({
+                "user": e.user.to_string(),
+                "timestamp": e.timestamp,
+            }),
+        ),
+        Event::StarsReconciled(e) => (
+            webhook_events::STAR,
+            e.repository,
+            "StarsReconciled",
+            json!({
+                "previous": e.previous,
+                "stars": e.stars,
+                "timestamp": e.timestamp,
+            }),
+        ),
+        Event::CollaboratorAdded(e) => (
+            webhook_events::COLLABORATOR,
+            e.repository,
+            "CollaboratorAdded",
+            json!({
+                "collaborator": e.collaborator.to_string(),
+                "can_push": e.can_push,
+                "index": e.index,
+                "timestamp": e.timestamp,
+            }),
+        ),
+        Event::CollaboratorIndexed(e) => (
+            webhook_events::COLLABORATOR,
+            e.repository,
+            "CollaboratorIndexed",
+            json!({
+                "collaborator": e.collaborator.to_string(),
+                "index": e.index,
+                "timestamp": e.timestamp,
+            }),
+        ),
+        Event::CollaboratorRemoved(e) => (
+            webhook_events::COLLABORATOR,
+            e.repository,
+            "CollaboratorRemoved",
+            json!({
+                "collaborator": e.collaborator.to_string(),
+                "index": e.index,
+                "timestamp": e.timestamp,
+            }),
+        ),
+    })
+}
@@ -0,0 +1,157 @@
+//! Vanish event indexer.
+//!
+//! Walks the program's transaction history oldest-first, decodes the events
+//! each transaction logged and keeps relational tables of repositories,
+//! stars, collaborators, pushes and activity up to date in Postgres. The
+//! `unseen` view gives each user the activity past their on-chain
+//! ActivityCursor. The first run backfills from the oldest signature the RPC
+//! node still has; later runs and polls resume from the last transaction
+//! stored. With `--webhooks`, it also delivers events to the webhooks
+//! repositories registered.
+
+mod dispatch;
+mod store;
+
+use std::{path::PathBuf, time::Duration};
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use solana_client::{
+    nonblocking::rpc_client::RpcClient, rpc_client::GetConfirmedSignaturesForAddress2Config,
+    rpc_config::RpcTransactionConfig, rpc_response::RpcConfirmedTransactionStatusWithSignature,
+};
+use solana_sdk::{commitment_config::CommitmentConfig, signature::Signature};
+use solana_transaction_status::{option_serializer::OptionSerializer, UiTransactionEncoding};
+use vanish_client::events;
+
+use crate::{dispatch::Dispatcher, store::Store};
+
+#[derive(Parser)]
+#[command(
+    name = "vanish-index",
+    version,
+    about = "Index Vanish program events into Postgres"
+)]
+struct Args {
+    /// Solana RPC endpoint
+    #[arg(
+        long,
+        env = "VANISH_RPC_URL",
+        default_value = "https://api.devnet.solana.com"
+    )]
+    url: String,
+
+    /// Postgres connection string
+    #[arg(long, env = "DATABASE_URL")]
+    database_url: String,
+
+    /// Seconds between polls for new transactions
+    #[arg(long, default_value_t = 10)]
+    interval: u64,
+
+    /// Catch up once and exit instead of polling
+    #[arg(long)]
+    once: bool,
+
+    /// File of webhook endpoints to deliver to, one `<url> <secret>` per
+    /// line
+    #[arg(long, env = "VANISH_WEBHOOKS")]
+    webhooks: Option<PathBuf>,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = Args::parse();
+    let rpc = RpcClient::new_with_commitment(args.url, CommitmentConfig::confirmed());
+    let mut store = Store::connect(&args.database_url).await?;
+    let mut dispatcher = args.webhooks.as_deref().map(Dispatcher::load).transpose()?;
+
+    loop {
+        let applied = catch_up(&rpc, &mut store, dispatcher.as_mut()).await?;
+        if applied > 0 {
+            eprintln!("indexed {applied} transactions");
+        }
+        if args.once {
+            return Ok(());
+        }
+        tokio::time::sleep(Duration::from_secs(args.interval)).await;
+    }
+}
+
+/// Apply every transaction newer than the cursor. Returns how many.
+async fn catch_up(
+    rpc: &RpcClient,
+    store: &mut Store,
+    mut dispatcher: Option<&mut Dispatcher>,
+) -> Result<usize> {
+    let cursor = store.cursor().await?;
+
+    // Signatures come newest first, a page at a time
+    let mut pending: Vec<RpcConfirmedTransactionStatusWithSignature> = Vec::new();
+    loop {
+        let before = pending
+            .last()
+            .map(|status| status.signature.parse())
+            .transpose()?;
+        let page = rpc
+            .get_signatures_for_address_with_config(
+                &vanish_program::ID,
+                GetConfirmedSignaturesForAddress2Config {
+                    before,
+                    until: cursor,
+                    limit: Some(1000),
+                    commitment: Some(rpc.commitment()),
+                },
+            )
+            .await
+            .context("failed to fetch signatures")?;
+        if page.is_empty() {
+            break;
+        }
+        pending.extend(page);
+    }
+
+    let applied = pending.len();
+    if applied > 0 {
+        if let Some(dispatcher) = dispatcher.as_deref_mut() {
+            dispatcher.refresh(rpc).await?;
+        }
+    }
+    for status in pending.into_iter().rev() {
+        let signature: Signature = status.signature.parse()?;
+        // Failed transactions emitted nothing, but still move the cursor
+        let events = match status.err {
+            Some(_) => Vec::new(),
+            None => events::parse_logs(&logs(rpc, &signature).await?),
+        };
+        if let Some(dispatcher) = dispatcher.as_deref() {
+            dispatcher
+                .deliver(store, &signature, status.slot, &events)
+                .await?;
+        }
+        store.apply(&signature, status.slot, &events).await?;
+    }
+
+    Ok(applied)
+}
+
+async fn logs(rpc: &RpcClient, signature: &Signature) -> Result<Vec<String>> {
+    let transaction = rpc
+        .get_transaction_with_config(
+            signature,
+            RpcTransactionConfig {
+                encoding: Some(UiTransactionEncoding::Json),
+                commitment: Some(rpc.commitment()),
+                max_supported_transaction_version: Some(0),
+            },
+        )
+        .await
+        .with_context(|| format!("failed to fetch transaction {signature}"))?;
+
+    Ok(
+        match transaction.transaction.meta.map(|meta| meta.log_messages) {
+            Some(OptionSerializer::Some(logs)) => logs,
+            _ => Vec::new(),
+        },
+    )
+}
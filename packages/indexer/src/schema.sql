@@ -0,0 +1,117 @@
+-- Applied on every start; every statement must be idempotent.
+
+CREATE TABLE IF NOT EXISTS repos (
+    -- Current address; follows renames
+    address     TEXT PRIMARY KEY,
+    -- Address at creation, which child accounts are seeded by
+    id          TEXT NOT NULL UNIQUE,
+    -- NULL once ownership is renounced
+    owner       TEXT,
+    name        TEXT NOT NULL,
+    is_private  BOOLEAN NOT NULL,
+    head_commit TEXT,
+    ipfs_cid    TEXT,
+    stars       BIGINT NOT NULL DEFAULT 0,
+    created_at  BIGINT NOT NULL,
+    updated_at  BIGINT NOT NULL,
+    deleted_at  BIGINT
+);
+
+CREATE TABLE IF NOT EXISTS stars (
+    repository TEXT NOT NULL REFERENCES repos (address) ON UPDATE CASCADE ON DELETE CASCADE,
+    "user"     TEXT NOT NULL,
+    tip        BIGINT NOT NULL,
+    starred_at BIGINT NOT NULL,
+    PRIMARY KEY (repository, "user")
+);
+
+CREATE TABLE IF NOT EXISTS collaborators (
+    repository   TEXT NOT NULL REFERENCES repos (address) ON UPDATE CASCADE ON DELETE CASCADE,
+    collaborator TEXT NOT NULL,
+    can_push     BOOLEAN NOT NULL,
+    -- Index slot, NULL for collaborators added before slots existed
+    slot         BIGINT,
+    added_at     BIGINT NOT NULL,
+    PRIMARY KEY (repository, collaborator)
+);
+
+CREATE TABLE IF NOT EXISTS pushes (
+    id          BIGSERIAL PRIMARY KEY,
+    signature   TEXT NOT NULL,
+    slot        BIGINT NOT NULL,
+    repository  TEXT NOT NULL REFERENCES repos (address) ON UPDATE CASCADE ON DELETE CASCADE,
+    -- NULL for the default branch
+    branch      TEXT,
+    pusher      TEXT NOT NULL,
+    head_commit TEXT NOT NULL,
+    ipfs_cid    TEXT NOT NULL,
+    pushed_at   BIGINT NOT NULL
+);
+
+-- Hex hash tying the head commit to the archive, when the pusher sent one
+ALTER TABLE pushes ADD COLUMN IF NOT EXISTS binding TEXT;
+
+CREATE INDEX IF NOT EXISTS pushes_repository ON pushes (repository, pushed_at);
+
+-- What happened to each repository, newest last. Maintenance events
+-- (reconciled star counts, re-indexed collaborators) are left out.
+CREATE TABLE IF NOT EXISTS activity (
+    id         BIGSERIAL PRIMARY KEY,
+    signature  TEXT NOT NULL,
+    slot       BIGINT NOT NULL,
+    repository TEXT NOT NULL REFERENCES repos (address) ON UPDATE CASCADE ON DELETE CASCADE,
+    -- push, branch_push, create, rename, transfer, renounce, delete, restore,
+    -- star, unstar, collaborator_added or collaborator_removed
+    kind       TEXT NOT NULL,
+    -- Who acted or was acted on, where the event names someone
+    actor      TEXT,
+    at         BIGINT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS activity_repository ON activity (repository, slot);
+
+-- Activity per repository per UTC day, for clients that only need counts
+CREATE TABLE IF NOT EXISTS activity_digests (
+    repository    TEXT NOT NULL REFERENCES repos (address) ON UPDATE CASCADE ON DELETE CASCADE,
+    -- Days since the Unix epoch
+    day           BIGINT NOT NULL,
+    pushes        BIGINT NOT NULL DEFAULT 0,
+    stars         BIGINT NOT NULL DEFAULT 0,
+    unstars       BIGINT NOT NULL DEFAULT 0,
+    collaborators BIGINT NOT NULL DEFAULT 0,
+    lifecycle     BIGINT NOT NULL DEFAULT 0,
+    PRIMARY KEY (repository, day)
+);
+
+-- Each user's on-chain ActivityCursor
+CREATE TABLE IF NOT EXISTS activity_cursors (
+    "user"  TEXT PRIMARY KEY,
+    slot    BIGINT NOT NULL,
+    seen_at BIGINT NOT NULL
+);
+
+-- Activity on the repositories each user owns, stars or collaborates on,
+-- other than their own
+CREATE OR REPLACE VIEW feed AS
+SELECT watchers."user", activity.*
+FROM activity
+JOIN (
+    SELECT address AS repository, owner AS "user" FROM repos WHERE owner IS NOT NULL
+    UNION SELECT repository, "user" FROM stars
+    UNION SELECT repository, collaborator FROM collaborators
+) AS watchers ON watchers.repository = activity.repository
+WHERE activity.actor IS DISTINCT FROM watchers."user";
+
+-- The part of each user's feed past their cursor, what clients show as new
+CREATE OR REPLACE VIEW unseen AS
+SELECT feed.*
+FROM feed
+LEFT JOIN activity_cursors ON activity_cursors."user" = feed."user"
+WHERE feed.slot > COALESCE(activity_cursors.slot, -1);
+
+-- Newest transaction applied, where the next poll resumes
+CREATE TABLE IF NOT EXISTS indexer_cursor (
+    singleton BOOLEAN PRIMARY KEY DEFAULT TRUE CHECK (singleton),
+    signature TEXT NOT NULL,
+    slot      BIGINT NOT NULL
+);
@@ -0,0 +1,383 @@
+//! Postgres sink.
+//!
+//! Each transaction's events are applied together with the cursor, so a
+//! restart resumes exactly after the last transaction stored. Events for a
+//! repository the index never saw created (history pruned by the RPC node)
+//! are dropped rather than failing the batch.
+
+use anyhow::{Context, Result};
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+use tokio_postgres::{Client, NoTls};
+use vanish_client::{commit, events::Event};
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+pub struct Store {
+    client: Client,
+}
+
+/// One row of the `activity` table.
+struct Activity {
+    repository: String,
+    kind: &'static str,
+    actor: Option<String>,
+    at: i64,
+}
+
+impl Activity {
+    fn of(event: &Event) -> Option<Self> {
+        let (repository, kind, actor, at) = match event {
+            Event::RepoCreated(e) => (e.repository, "create", Some(e.owner), e.timestamp),
+            Event::RepoPushed(e) => (e.repository, "push", Some(e.pusher), e.timestamp),
+            Event::BranchPushed(e) => (e.repository, "branch_push", Some(e.pusher), e.timestamp),
+            Event::RepoRenamed(e) => (e.new_address, "rename", None, e.timestamp),
+            Event::OwnershipRenounced(e) => {
+                (e.repository, "renounce", Some(e.old_owner), e.timestamp)
+            }
+            Event::RepoDeleted(e) => (e.repository, "delete", None, e.timestamp),
+            Event::RepoRestored(e) => (e.repository, "restore", None, e.timestamp),
+            Event::RepoStarred(e) => (e.repository, "star", Some(e.user), e.timestamp),
+            Event::RepoUnstarred(e) => (e.repository, "unstar", Some(e.user), e.timestamp),
+            Event::CollaboratorAdded(e) => (
+                e.repository,
+                "collaborator_added",
+                Some(e.collaborator),
+                e.timestamp,
+            ),
+            Event::CollaboratorRemoved(e) => (
+                e.repository,
+                "collaborator_removed",
+                Some(e.collaborator),
+                e.timestamp,
+            ),
+            // Transfers carry no timestamp to date them by
+            Event::OwnershipTransferred(_)
+            | Event::RepoPurged(_)
+            | Event::StarsReconciled(_)
+            | Event::CollaboratorIndexed(_)
+            | Event::ActivitySeen(_) => return None,
+        };
+        Some(Self {
+            repository: repository.to_string(),
+            kind,
+            actor: actor.map(|actor| actor.to_string()),
+            at,
+        })
+    }
+}
+
+/// The `activity_digests` column counting activity of `kind`.
+fn digest_counter(kind: &str) -> &'static str {
+    match kind {
+        "push" | "branch_push" => "pushes",
+        "star" => "stars",
+        "unstar" => "unstars",
+        "collaborator_added" | "collaborator_removed" => "collaborators",
+        _ => "lifecycle",
+    }
+}
+
+impl Store {
+    /// Connect and create any missing tables.
+    pub async fn connect(url: &str) -> Result<Self> {
+        let (client, connection) = tokio_postgres::connect(url, NoTls)
+            .await
+            .context("failed to connect to postgres")?;
+        tokio::spawn(async move {
+            if let Err(err) = connection.await {
+                eprintln!("postgres connection closed: {err}");
+            }
+        });
+
+        client
+            .batch_execute(include_str!("schema.sql"))
+            .await
+            .context("failed to create tables")?;
+        Ok(Self { client })
+    }
+
+    /// Signature of the newest transaction applied.
+    pub async fn cursor(&self) -> Result<Option<Signature>> {
+        let row = self
+            .client
+            .query_opt("SELECT signature FROM indexer_cursor", &[])
+            .await?;
+        row.map(|row| {
+            let signature: String = row.get(0);
+            signature
+                .parse()
+                .with_context(|| format!("stored cursor {signature} is not a signature"))
+        })
+        .transpose()
+    }
+
+    /// Id of the repository now at `address`, if the index has it.
+    pub async fn repo_id(&self, address: &Pubkey) -> Result<Option<Pubkey>> {
+        let row = self
+            .client
+            .query_opt(
+                "SELECT id FROM repos WHERE address = $1",
+                &[&address.to_string()],
+            )
+            .await?;
+        row.map(|row| {
+            let id: String = row.get(0);
+            id.parse()
+                .with_context(|| format!("stored repository id {id} is not an address"))
+        })
+        .transpose()
+    }
+
+    /// Apply one transaction's events and move the cursor past it.
+    pub async fn apply(
+        &mut self,
+        signature: &Signature,
+        slot: u64,
+        events: &[Event],
+    ) -> Result<()> {
+        let tx = self.client.transaction().await?;
+        let signature = signature.to_string();
+        let slot = slot as i64;
+
+        for event in events {
+            match event {
+                Event::RepoCreated(e) => {
+                    tx.execute(
+                        "INSERT INTO repos (address, id, owner, name, is_private, created_at, updated_at)
+                         VALUES ($1, $1, $2, $3, $4, $5, $5)
+                         ON CONFLICT (address) DO NOTHING",
+                        &[
+                            &e.repository.to_string(),
+                            &e.owner.to_string(),
+                            &e.name,
+                            &e.is_private,
+                            &e.timestamp,
+                        ],
+                    )
+                    .await?;
+                }
+                Event::RepoPushed(e) => {
+                    let head = commit::to_hex(&e.head_commit);
+                    let ipfs_cid = e.ipfs_cid.to_string();
+                    let binding = e.binding.map(|binding| hex(&binding));
+                    let repository = e.repository.to_string();
+                    tx.execute(
+                        "UPDATE repos SET head_commit = $2, ipfs_cid = $3, updated_at = $4
+                         WHERE address = $1",
+                        &[&repository, &head, &ipfs_cid, &e.timestamp],
+                    )
+                    .await?;
+                    tx.execute(
+                        "INSERT INTO pushes (signature, slot, repository, pusher, head_commit, ipfs_cid, binding, pushed_at)
+                         SELECT $1, $2, address, $4, $5, $6, $7, $8 FROM repos WHERE address = $3",
+                        &[
+                            &signature,
+                            &slot,
+                            &repository,
+                            &e.pusher.to_string(),
+                            &head,
+                            &ipfs_cid,
+                            &binding,
+                            &e.timestamp,
+                        ],
+                    )
+                    .await?;
+                }
+                Event::BranchPushed(e) => {
+                    let repository = e.repository.to_string();
+                    tx.execute(
+                        "UPDATE repos SET updated_at = $2 WHERE address = $1",
+                        &[&repository, &e.timestamp],
+                    )
+                    .await?;
+                    tx.execute(
+                        "INSERT INTO pushes (signature, slot, repository, branch, pusher, head_commit, ipfs_cid, pushed_at)
+                         SELECT $1, $2, address, $4, $5, $6, $7, $8 FROM repos WHERE address = $3",
+                        &[
+                            &signature,
+                            &slot,
+                            &repository,
+                            &e.branch,
+                            &e.pusher.to_string(),
+                            &commit::to_hex(&e.head_commit),
+                            &e.ipfs_cid,
+                            &e.timestamp,
+                        ],
+                    )
+                    .await?;
+                }
+                Event::RepoRenamed(e) => {
+                    tx.execute(
+                        "UPDATE repos SET address = $2, name = $3, updated_at = $4 WHERE address = $1",
+                        &[
+                            &e.old_address.to_string(),
+                            &e.new_address.to_string(),
+                            &e.new_name,
+                            &e.timestamp,
+                        ],
+                    )
+                    .await?;
+                }
+                Event::OwnershipTransferred(e) => {
+                    tx.execute(
+                        "UPDATE repos SET owner = $2 WHERE address = $1",
+                        &[&e.repository.to_string(), &e.new_owner.to_string()],
+                    )
+                    .await?;
+                }
+                Event::OwnershipRenounced(e) => {
+                    tx.execute(
+                        "UPDATE repos SET owner = NULL, updated_at = $2 WHERE address = $1",
+                        &[&e.repository.to_string(), &e.timestamp],
+                    )
+                    .await?;
+                }
+                Event::RepoDeleted(e) => {
+                    tx.execute(
+                        "UPDATE repos SET deleted_at = $2 WHERE address = $1",
+                        &[&e.repository.to_string(), &e.timestamp],
+                    )
+                    .await?;
+                }
+                Event::RepoRestored(e) => {
+                    tx.execute(
+                        "UPDATE repos SET deleted_at = NULL WHERE address = $1",
+                        &[&e.repository.to_string()],
+                    )
+                    .await?;
+                }
+                Event::RepoPurged(e) => {
+                    tx.execute(
+                        "DELETE FROM repos WHERE address = $1",
+                        &[&e.repository.to_string()],
+                    )
+                    .await?;
+                }
+                Event::RepoStarred(e) => {
+                    let repository = e.repository.to_string();
+                    tx.execute(
+                        "INSERT INTO stars (repository, \"user\", tip, starred_at)
+                         SELECT address, $2, $3, $4 FROM repos WHERE address = $1
+                         ON CONFLICT DO NOTHING",
+                        &[
+                            &repository,
+                            &e.user.to_string(),
+                            &(e.tip as i64),
+                            &e.timestamp,
+                        ],
+                    )
+                    .await?;
+                    tx.execute(
+                        "UPDATE repos SET stars = stars + 1 WHERE address = $1",
+                        &[&repository],
+                    )
+                    .await?;
+                }
+                Event::RepoUnstarred(e) => {
+                    let repository = e.repository.to_string();
+                    tx.execute(
+                        "DELETE FROM stars WHERE repository = $1 AND \"user\" = $2",
+                        &[&repository, &e.user.to_string()],
+                    )
+                    .await?;
+                    tx.execute(
+                        "UPDATE repos SET stars = GREATEST(stars - 1, 0) WHERE address = $1",
+                        &[&repository],
+                    )
+                    .await?;
+                }
+                Event::StarsReconciled(e) => {
+                    tx.execute(
+                        "UPDATE repos SET stars = $2 WHERE address = $1",
+                        &[&e.repository.to_string(), &(e.stars as i64)],
+                    )
+                    .await?;
+                }
+                Event::CollaboratorAdded(e) => {
+                    tx.execute(
+                        "INSERT INTO collaborators (repository, collaborator, can_push, slot, added_at)
+                         SELECT address, $2, $3, $4, $5 FROM repos WHERE address = $1
+                         ON CONFLICT (repository, collaborator)
+                         DO UPDATE SET can_push = $3, slot = $4, added_at = $5",
+                        &[
+                            &e.repository.to_string(),
+                            &e.collaborator.to_string(),
+                            &e.can_push,
+                            &i64::from(e.index),
+                            &e.timestamp,
+                        ],
+                    )
+                    .await?;
+                }
+                Event::CollaboratorIndexed(e) => {
+                    tx.execute(
+                        "UPDATE collaborators SET slot = $3 WHERE repository = $1 AND collaborator = $2",
+                        &[
+                            &e.repository.to_string(),
+                            &e.collaborator.to_string(),
+                            &i64::from(e.index),
+                        ],
+                    )
+                    .await?;
+                }
+                Event::CollaboratorRemoved(e) => {
+                    tx.execute(
+                        "DELETE FROM collaborators WHERE repository = $1 AND collaborator = $2",
+                        &[&e.repository.to_string(), &e.collaborator.to_string()],
+                    )
+                    .await?;
+                }
+                Event::ActivitySeen(e) => {
+                    tx.execute(
+                        "INSERT INTO activity_cursors (\"user\", slot, seen_at) VALUES ($1, $2, $3)
+                         ON CONFLICT (\"user\") DO UPDATE SET slot = $2, seen_at = $3",
+                        &[&e.user.to_string(), &(e.slot as i64), &e.timestamp],
+                    )
+                    .await?;
+                }
+            }
+
+            // Recorded after the event's own changes, so a created or
+            // renamed repository is found at its new address
+            if let Some(Activity {
+                repository,
+                kind,
+                actor,
+                at,
+            }) = Activity::of(event)
+            {
+                tx.execute(
+                    "INSERT INTO activity (signature, slot, repository, kind, actor, at)
+                     SELECT $1, $2, address, $4, $5, $6 FROM repos WHERE address = $3",
+                    &[&signature, &slot, &repository, &kind, &actor, &at],
+                )
+                .await?;
+                let counter = digest_counter(kind);
+                tx.execute(
+                    &format!(
+                        "INSERT INTO activity_digests (repository, day, {counter})
+                         SELECT address, $2, 1 FROM repos WHERE address = $1
+                         ON CONFLICT (repository, day)
+                         DO UPDATE SET {counter} = activity_digests.{counter} + 1"
+                    ),
+                    &[&repository, &at.div_euclid(SECONDS_PER_DAY)],
+                )
+                .await?;
+            }
+        }
+
+        tx.execute(
+            "INSERT INTO indexer_cursor (signature, slot) VALUES ($1, $2)
+             ON CONFLICT (singleton) DO UPDATE SET signature = $1, slot = $2",
+            &[&signature, &slot],
+        )
+        .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+}
+
+/// `bytes` as lowercase hex
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
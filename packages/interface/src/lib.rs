@@ -0,0 +1,95 @@
+//! Interface for programs that compose with Vanish.
+//!
+//! Re-exports the program's account types and Anchor CPI bindings, and adds
+//! its seed layouts and checks for reading Vanish state passed into another
+//! program. Everything here builds for SBF.
+//!
+//! ```ignore
+//! use vanish_interface::{cpi, program::VanishProgram, Repository};
+//!
+//! // Star a repository on the user's behalf
+//! cpi::star_repo(
+//!     CpiContext::new(ctx.accounts.vanish_program.to_account_info(), cpi::accounts::StarRepo {
+//!         user: ctx.accounts.user.to_account_info(),
+//!         repository: ctx.accounts.repository.to_account_info(),
+//!         star_account: ctx.accounts.star_account.to_account_info(),
+//!         donation_vault: ctx.accounts.donation_vault.to_account_info(),
+//!         profile: ctx.accounts.profile.to_account_info(),
+//!         config: ctx.accounts.config.to_account_info(),
+//!         system_program: ctx.accounts.system_program.to_account_info(),
+//!     }),
+//!     0,
+//! )?;
+//! ```
+
+pub mod pda;
+pub mod seeds;
+
+use anchor_lang::prelude::*;
+
+pub use vanish_program::{
+    accounts, cpi, instruction, program, repo_flags, AccessKey, Branch, Collaborator,
+    CollaboratorSlot, Config, KeyEnvelope, Multihash, Profile, ReadGrant, RepoName, Repository,
+    Star, VanishError, Verification, ID,
+};
+
+#[cfg(not(target_os = "solana"))]
+static PROGRAM_ID: std::sync::OnceLock<Pubkey> = std::sync::OnceLock::new();
+
+/// The address of the deployment PDAs are derived under: `ID`, unless an
+/// off-chain caller picked another with [`use_program_id`].
+pub fn program_id() -> Pubkey {
+    #[cfg(not(target_os = "solana"))]
+    if let Some(id) = PROGRAM_ID.get() {
+        return *id;
+    }
+    ID
+}
+
+/// Talk to the program deployed at `id` rather than `ID` for the rest of
+/// the process, such as a build deployed to a local validator under its own
+/// keypair. Fails with the id already in use if another was picked first.
+#[cfg(not(target_os = "solana"))]
+pub fn use_program_id(id: Pubkey) -> std::result::Result<(), Pubkey> {
+    match *PROGRAM_ID.get_or_init(|| id) {
+        chosen if chosen == id => Ok(()),
+        chosen => Err(chosen),
+    }
+}
+
+/// Decode `account` as `user`'s Collaborator on `repo`, checking its owner
+/// and address. `None` when the account does not exist.
+pub fn collaborator(
+    account: &AccountInfo,
+    repo: &Repository,
+    user: &Pubkey,
+) -> Result<Option<Collaborator>> {
+    require_keys_eq!(
+        account.key(),
+        pda::collab(&repo.id, user),
+        VanishError::Unauthorized
+    );
+    if account.data_is_empty() {
+        return Ok(None);
+    }
+    require_keys_eq!(*account.owner, ID, VanishError::Unauthorized);
+
+    let data = account.try_borrow_data()?;
+    Ok(Some(Collaborator::try_deserialize(&mut &data[..])?))
+}
+
+/// Whether `user` may push to `repo`: they own it, or `collaborator` is
+/// their Collaborator account with push access.
+pub fn can_push(
+    repo: &Repository,
+    user: &Pubkey,
+    collaborator: Option<&AccountInfo>,
+) -> Result<bool> {
+    if repo.owner == *user {
+        return Ok(true);
+    }
+    Ok(match collaborator {
+        Some(account) => self::collaborator(account, repo, user)?.is_some_and(|c| c.can_push),
+        None => false,
+    })
+}
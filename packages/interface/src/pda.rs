@@ -0,0 +1,296 @@
+//! Program-derived addresses.
+//!
+//! Every seed layout the program uses, in one place. Child accounts of a
+//! repository are seeded by its `id`, which stays the same across renames,
+//! not by its current address; pass `Repository::id` where a function takes
+//! `id`.
+//!
+//! Addresses are derived under [`crate::program_id`], so they follow the
+//! deployment an off-chain caller picked.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::hash::hash;
+use vanish_program::{Multihash, SemVer};
+
+use crate::seeds;
+
+fn find(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &crate::program_id()).0
+}
+
+pub fn config() -> Pubkey {
+    find(&[seeds::CONFIG])
+}
+
+pub fn treasury() -> Pubkey {
+    find(&[seeds::TREASURY])
+}
+
+pub fn verifier(verifier: &Pubkey) -> Pubkey {
+    find(&[seeds::VERIFIER, verifier.as_ref()])
+}
+
+pub fn deal_oracle(oracle: &Pubkey) -> Pubkey {
+    find(&[seeds::DEAL_ORACLE, oracle.as_ref()])
+}
+
+pub fn pinner(operator: &Pubkey) -> Pubkey {
+    find(&[seeds::PINNER, operator.as_ref()])
+}
+
+pub fn profile(user: &Pubkey) -> Pubkey {
+    find(&[seeds::USER, user.as_ref()])
+}
+
+/// Address of the repository `creator` created as `name`. After a rename
+/// this is still where it lives, under its new name.
+pub fn repo(creator: &Pubkey, name: &str) -> Pubkey {
+    find(&[seeds::REPO, creator.as_ref(), name.as_bytes()])
+}
+
+pub fn redirect(creator: &Pubkey, name: &str) -> Pubkey {
+    find(&[seeds::REDIRECT, creator.as_ref(), name.as_bytes()])
+}
+
+/// Lock on `name` among `creator`'s repositories, whatever its case
+pub fn name_lock(creator: &Pubkey, name: &str) -> Pubkey {
+    find(&[
+        seeds::NAME_LOCK,
+        creator.as_ref(),
+        &hash(name.to_ascii_lowercase().as_bytes()).to_bytes(),
+    ])
+}
+
+/// Tombstone counting the repositories that have left `address`
+pub fn tombstone(address: &Pubkey) -> Pubkey {
+    find(&[seeds::TOMBSTONE, address.as_ref()])
+}
+
+/// Receipt for the purge of the repository with `id`
+pub fn purge_receipt(id: &Pubkey) -> Pubkey {
+    find(&[seeds::PURGED, id.as_ref()])
+}
+
+/// Global directory entry for a repository name
+pub fn repo_name(name: &str) -> Pubkey {
+    find(&[seeds::DIR, &hash(name.as_bytes()).to_bytes()])
+}
+
+pub fn timelock(id: &Pubkey) -> Pubkey {
+    find(&[seeds::TIMELOCK, id.as_ref()])
+}
+
+/// Action queued behind the repository's timelock
+pub fn pending_action(id: &Pubkey) -> Pubkey {
+    find(&[seeds::PENDING, id.as_ref()])
+}
+
+pub fn filecoin_deal(id: &Pubkey) -> Pubkey {
+    find(&[seeds::FILECOIN, id.as_ref()])
+}
+
+pub fn reward_pool(id: &Pubkey) -> Pubkey {
+    find(&[seeds::REWARD_POOL, id.as_ref()])
+}
+
+/// `operator`'s latest attestation that it pins the repository
+pub fn pin_attestation(id: &Pubkey, operator: &Pubkey) -> Pubkey {
+    find(&[seeds::PIN_ATTESTATION, id.as_ref(), operator.as_ref()])
+}
+
+pub fn stats(id: &Pubkey) -> Pubkey {
+    find(&[seeds::STATS, id.as_ref()])
+}
+
+pub fn project(admin: &Pubkey, name: &str) -> Pubkey {
+    find(&[seeds::PROJECT, admin.as_ref(), name.as_bytes()])
+}
+
+/// The link putting a repository in a project, if it is in one
+pub fn project_repo(id: &Pubkey) -> Pubkey {
+    find(&[seeds::PROJECT_REPO, id.as_ref()])
+}
+
+pub fn project_member(project: &Pubkey, user: &Pubkey) -> Pubkey {
+    find(&[seeds::PROJECT_MEMBER, project.as_ref(), user.as_ref()])
+}
+
+/// The lamports a project keeps to cover its repositories' rent
+pub fn rent_vault(project: &Pubkey) -> Pubkey {
+    find(&[seeds::RENT_VAULT, project.as_ref()])
+}
+
+/// Entry `seq` of the project's audit log
+pub fn project_audit(project: &Pubkey, seq: u64) -> Pubkey {
+    find(&[seeds::PROJECT_AUDIT, project.as_ref(), &seq.to_le_bytes()])
+}
+
+pub fn stake(id: &Pubkey) -> Pubkey {
+    find(&[seeds::STAKE, id.as_ref()])
+}
+
+pub fn verification(id: &Pubkey) -> Pubkey {
+    find(&[seeds::VERIFICATION, id.as_ref()])
+}
+
+pub fn branch(id: &Pubkey, name: &str) -> Pubkey {
+    find(&[
+        seeds::BRANCH,
+        id.as_ref(),
+        &hash(name.as_bytes()).to_bytes(),
+    ])
+}
+
+pub fn fork_link(id: &Pubkey) -> Pubkey {
+    find(&[seeds::UPSTREAM, id.as_ref()])
+}
+
+pub fn submodule(id: &Pubkey, path: &str) -> Pubkey {
+    find(&[
+        seeds::SUBMODULE,
+        id.as_ref(),
+        &hash(path.as_bytes()).to_bytes(),
+    ])
+}
+
+pub fn collab(id: &Pubkey, user: &Pubkey) -> Pubkey {
+    find(&[seeds::COLLAB, id.as_ref(), user.as_ref()])
+}
+
+pub fn collab_slot(id: &Pubkey, index: u32) -> Pubkey {
+    find(&[seeds::COLLAB_SLOT, id.as_ref(), &index.to_le_bytes()])
+}
+
+pub fn branch_grant(id: &Pubkey, user: &Pubkey) -> Pubkey {
+    find(&[seeds::BRANCH_GRANT, id.as_ref(), user.as_ref()])
+}
+
+/// Webhook for the URL hashing to `url_hash`.
+pub fn webhook(id: &Pubkey, url_hash: &[u8; 32]) -> Pubkey {
+    find(&[seeds::WEBHOOK, id.as_ref(), url_hash])
+}
+
+pub fn read(id: &Pubkey, reader: &Pubkey) -> Pubkey {
+    find(&[seeds::READ, id.as_ref(), reader.as_ref()])
+}
+
+pub fn access(id: &Pubkey, user: &Pubkey) -> Pubkey {
+    find(&[seeds::ACCESS, id.as_ref(), user.as_ref()])
+}
+
+pub fn pricing(id: &Pubkey) -> Pubkey {
+    find(&[seeds::PRICING, id.as_ref()])
+}
+
+pub fn grant(id: &Pubkey, user: &Pubkey) -> Pubkey {
+    find(&[seeds::GRANT, id.as_ref(), user.as_ref()])
+}
+
+pub fn contribution(id: &Pubkey, user: &Pubkey) -> Pubkey {
+    find(&[seeds::CONTRIBUTION, id.as_ref(), user.as_ref()])
+}
+
+/// Audit entry number `seq`; the next one is at `Repository::audit_count`.
+pub fn audit(id: &Pubkey, seq: u64) -> Pubkey {
+    find(&[seeds::AUDIT, id.as_ref(), &seq.to_le_bytes()])
+}
+
+/// The contributor snapshot `taker` took for `slot`
+pub fn snapshot(id: &Pubkey, taker: &Pubkey, slot: u64) -> Pubkey {
+    find(&[
+        seeds::SNAPSHOT,
+        id.as_ref(),
+        taker.as_ref(),
+        &slot.to_le_bytes(),
+    ])
+}
+
+/// Page `page` of the repository's growth history
+pub fn history(id: &Pubkey, page: u32) -> Pubkey {
+    find(&[seeds::HISTORY, id.as_ref(), &page.to_le_bytes()])
+}
+
+pub fn badges(id: &Pubkey) -> Pubkey {
+    find(&[seeds::BADGES, id.as_ref()])
+}
+
+pub fn push_log(id: &Pubkey) -> Pubkey {
+    find(&[seeds::PUSH_LOG, id.as_ref()])
+}
+
+pub fn ci(id: &Pubkey, attestor: &Pubkey) -> Pubkey {
+    find(&[seeds::CI, id.as_ref(), attestor.as_ref()])
+}
+
+pub fn check(id: &Pubkey, commit: &Multihash, attestor: &Pubkey) -> Pubkey {
+    find(&[seeds::CHECK, id.as_ref(), &commit.seed(), attestor.as_ref()])
+}
+
+pub fn release_policy(id: &Pubkey) -> Pubkey {
+    find(&[seeds::RELEASE_POLICY, id.as_ref()])
+}
+
+pub fn release(id: &Pubkey, tag: &str) -> Pubkey {
+    find(&[seeds::RELEASE, id.as_ref(), tag.as_bytes()])
+}
+
+pub fn approval(release: &Pubkey, manager: &Pubkey) -> Pubkey {
+    find(&[seeds::APPROVAL, release.as_ref(), manager.as_ref()])
+}
+
+pub fn builder(id: &Pubkey, builder: &Pubkey) -> Pubkey {
+    find(&[seeds::BUILDER, id.as_ref(), builder.as_ref()])
+}
+
+pub fn attestation(release: &Pubkey, checksum: &Multihash, builder: &Pubkey) -> Pubkey {
+    find(&[
+        seeds::ATTESTATION,
+        release.as_ref(),
+        &checksum.seed(),
+        builder.as_ref(),
+    ])
+}
+
+pub fn nft(id: &Pubkey) -> Pubkey {
+    find(&[seeds::NFT, id.as_ref()])
+}
+
+pub fn package(name: &str) -> Pubkey {
+    find(&[seeds::PACKAGE, name.as_bytes()])
+}
+
+pub fn version(package: &Pubkey, version: &SemVer) -> Pubkey {
+    find(&[
+        seeds::VERSION,
+        package.as_ref(),
+        &version.major.to_le_bytes(),
+        &version.minor.to_le_bytes(),
+        &version.patch.to_le_bytes(),
+    ])
+}
+
+pub fn star(user: &Pubkey, id: &Pubkey) -> Pubkey {
+    find(&[seeds::STAR, user.as_ref(), id.as_ref()])
+}
+
+pub fn star_tree() -> Pubkey {
+    find(&[seeds::STAR_TREE])
+}
+
+/// Vault collecting star tips for the repository's owner.
+pub fn donations(id: &Pubkey) -> Pubkey {
+    find(&[seeds::DONATIONS, id.as_ref()])
+}
+
+/// `owner`'s list called `name`
+pub fn list(owner: &Pubkey, name: &str) -> Pubkey {
+    find(&[seeds::LIST, owner.as_ref(), name.as_bytes()])
+}
+
+pub fn list_entry(list: &Pubkey, id: &Pubkey) -> Pubkey {
+    find(&[seeds::LIST_ENTRY, list.as_ref(), id.as_ref()])
+}
+
+pub fn activity(user: &Pubkey) -> Pubkey {
+    find(&[seeds::ACTIVITY, user.as_ref()])
+}
@@ -0,0 +1,61 @@
+//! Seed prefixes of every PDA the program derives. See [`crate::pda`] for
+//! the full seed layouts.
+
+pub const CONFIG: &[u8] = b"config";
+pub const TREASURY: &[u8] = b"treasury";
+pub const VERIFIER: &[u8] = b"verifier";
+pub const DEAL_ORACLE: &[u8] = b"deal_oracle";
+pub const PINNER: &[u8] = b"pinner";
+pub const USER: &[u8] = b"user";
+pub const REPO: &[u8] = b"repo";
+pub const REDIRECT: &[u8] = b"redirect";
+pub const NAME_LOCK: &[u8] = b"name_lock";
+pub const TOMBSTONE: &[u8] = b"tombstone";
+pub const PURGED: &[u8] = b"purged";
+pub const DIR: &[u8] = b"dir";
+pub const TIMELOCK: &[u8] = b"timelock";
+pub const PENDING: &[u8] = b"pending";
+pub const STATS: &[u8] = b"stats";
+pub const PROJECT: &[u8] = b"project";
+pub const PROJECT_REPO: &[u8] = b"project_repo";
+pub const PROJECT_MEMBER: &[u8] = b"project_member";
+pub const PROJECT_AUDIT: &[u8] = b"project_audit";
+pub const RENT_VAULT: &[u8] = b"rent_vault";
+pub const STAKE: &[u8] = b"stake";
+pub const VERIFICATION: &[u8] = b"verification";
+pub const FILECOIN: &[u8] = b"filecoin";
+pub const REWARD_POOL: &[u8] = b"reward_pool";
+pub const PIN_ATTESTATION: &[u8] = b"pin";
+pub const BRANCH: &[u8] = b"branch";
+pub const UPSTREAM: &[u8] = b"upstream";
+pub const SUBMODULE: &[u8] = b"submodule";
+pub const COLLAB: &[u8] = b"collab";
+pub const COLLAB_SLOT: &[u8] = b"collab_slot";
+pub const BRANCH_GRANT: &[u8] = b"branch_grant";
+pub const WEBHOOK: &[u8] = b"webhook";
+pub const READ: &[u8] = b"read";
+pub const ACCESS: &[u8] = b"access";
+pub const PRICING: &[u8] = b"pricing";
+pub const GRANT: &[u8] = b"grant";
+pub const CONTRIBUTION: &[u8] = b"contribution";
+pub const AUDIT: &[u8] = b"audit";
+pub const SNAPSHOT: &[u8] = b"snapshot";
+pub const HISTORY: &[u8] = b"history";
+pub const BADGES: &[u8] = b"badges";
+pub const PUSH_LOG: &[u8] = b"push_log";
+pub const CI: &[u8] = b"ci";
+pub const CHECK: &[u8] = b"check";
+pub const RELEASE_POLICY: &[u8] = b"release_policy";
+pub const RELEASE: &[u8] = b"release";
+pub const APPROVAL: &[u8] = b"approval";
+pub const BUILDER: &[u8] = b"builder";
+pub const ATTESTATION: &[u8] = b"attestation";
+pub const NFT: &[u8] = b"nft";
+pub const PACKAGE: &[u8] = b"package";
+pub const VERSION: &[u8] = b"version";
+pub const STAR: &[u8] = b"star";
+pub const STAR_TREE: &[u8] = b"star_tree";
+pub const DONATIONS: &[u8] = b"donations";
+pub const LIST: &[u8] = b"list";
+pub const LIST_ENTRY: &[u8] = b"list_entry";
+pub const ACTIVITY: &[u8] = b"activity";
@@ -1,402 +1,12580 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::{hash, hashv};
+use anchor_lang::system_program;
+use anchor_lang::Discriminator;
+use anchor_spl::token::{Mint, TokenAccount};
+use anchor_spl::token_2022::spl_token_2022::extension::{
+    non_transferable::NonTransferable, transfer_fee::TransferFeeConfig, BaseStateWithExtensions,
+    StateWithExtensions,
+};
+use anchor_spl::token_2022::{self, spl_token_2022, Token2022};
+use anchor_spl::token_interface;
 
 declare_id!("VanishXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX");
 
+/// Emit an event from a handler. With the `event-cpi` feature the event is
+/// also carried by a self-CPI, so indexers can read it from inner
+/// instructions when the transaction logs are truncated.
+macro_rules! emit_event {
+    ($ctx:ident, $event:expr) => {{
+        let event = $event;
+        #[cfg(feature = "event-cpi")]
+        emit_cpi_event(
+            &event,
+            &$ctx.accounts.event_authority,
+            $ctx.bumps.event_authority,
+        )?;
+        emit!(event);
+    }};
+}
+
 #[program]
 pub mod vanish_program {
     use super::*;
 
-    /// Create a new repository
-    pub fn create_repo(
-        ctx: Context<CreateRepo>,
-        name: String,
-        description: String,
-        is_private: bool,
+    /// Create the program-wide Config. Runs once per deployment; the signer
+    /// becomes the config admin and funds the treasury to rent exemption.
+    pub fn initialize_config(
+        ctx: Context<InitializeConfig>,
+        repo_fee: u64,
+        flags: u8,
     ) -> Result<()> {
-        require!(name.len() <= 64, VanishError::NameTooLong);
-        require!(description.len() <= 256, VanishError::DescriptionTooLong);
-        require!(!name.is_empty(), VanishError::NameEmpty);
+        require!(
+            flags & !config_flags::ALL == 0,
+            VanishError::InvalidConfigFlags
+        );
 
-        let repo = &mut ctx.accounts.repository;
+        let config = &mut ctx.accounts.config;
         let clock = Clock::get()?;
 
-        repo.owner = ctx.accounts.owner.key();
-        repo.name = name;
-        repo.description = description;
-        repo.is_private = is_private;
-        repo.created_at = clock.unix_timestamp;
-        repo.updated_at = clock.unix_timestamp;
-        repo.head_commit = String::new();
-        repo.ipfs_cid = String::new();
-        repo.stars = 0;
-        repo.bump = ctx.bumps.repository;
-
-        emit!(RepoCreated {
-            owner: repo.owner,
-            name: repo.name.clone(),
-            is_private,
-            timestamp: clock.unix_timestamp,
-        });
+        config.admin = ctx.accounts.admin.key();
+        config.repo_fee = repo_fee;
+        config.flags = flags;
+        config.bump = ctx.bumps.config;
+        config.treasury_bump = ctx.bumps.treasury;
+        config.name_stake = 0;
+        config.stake_name_len = 0;
+        config.stake_period = 0;
+        config.max_repos = 0;
+        config.reserved_names = Vec::new();
+        config.reclaim_idle_period = 0;
+        config.reclaim_grace_period = 0;
+        config.reclaim_bond = 0;
+        config.rate_window_slots = 0;
+        config.max_creations_per_window = 0;
+        config.max_stars_per_window = 0;
+
+        // Fees smaller than the rent minimum could not open the account
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.admin.to_account_info(),
+                    to: ctx.accounts.treasury.to_account_info(),
+                },
+            ),
+            Rent::get()?.minimum_balance(0),
+        )?;
+
+        emit_event!(
+            ctx,
+            ConfigUpdated {
+                admin: config.admin,
+                repo_fee,
+                flags,
+                timestamp: clock.unix_timestamp,
+            }
+        );
 
         Ok(())
     }
 
-    /// Update repository with new commit and IPFS CID
-    pub fn push_update(
-        ctx: Context<PushUpdate>,
-        head_commit: String,
-        ipfs_cid: String,
+    /// Change the program config. Only the config admin can, and may hand
+    /// the role to another key (a DAO's governance PDA, say).
+    pub fn update_config(
+        ctx: Context<UpdateConfig>,
+        admin: Pubkey,
+        repo_fee: u64,
+        flags: u8,
     ) -> Result<()> {
-        require!(head_commit.len() == 40, VanishError::InvalidCommitHash);
-        require!(ipfs_cid.len() <= 64, VanishError::InvalidIpfsCid);
+        require!(
+            flags & !config_flags::ALL == 0,
+            VanishError::InvalidConfigFlags
+        );
 
-        let repo = &mut ctx.accounts.repository;
+        let config = &mut ctx.accounts.config;
         let clock = Clock::get()?;
 
-        repo.head_commit = head_commit.clone();
-        repo.ipfs_cid = ipfs_cid.clone();
-        repo.updated_at = clock.unix_timestamp;
+        config.admin = admin;
+        config.repo_fee = repo_fee;
+        config.flags = flags;
 
-        emit!(RepoPushed {
-            owner: repo.owner,
-            name: repo.name.clone(),
-            head_commit,
-            ipfs_cid,
-            timestamp: clock.unix_timestamp,
-        });
+        emit_event!(
+            ctx,
+            ConfigUpdated {
+                admin,
+                repo_fee,
+                flags,
+                timestamp: clock.unix_timestamp,
+            }
+        );
 
         Ok(())
     }
 
-    /// Add a collaborator to a repository
-    pub fn add_collaborator(
-        ctx: Context<AddCollaborator>,
-        collaborator: Pubkey,
-        can_push: bool,
+    /// Set the refundable stake create_repo escrows for names shorter than
+    /// `max_name_len`, and how long a repository must stay active before its
+    /// creator can take the stake back. A zero `amount` turns staking off.
+    pub fn set_name_stake(
+        ctx: Context<UpdateConfig>,
+        amount: u64,
+        max_name_len: u8,
+        lock_period: i64,
     ) -> Result<()> {
-        let collab = &mut ctx.accounts.collaborator_account;
-        let clock = Clock::get()?;
+        require!(lock_period >= 0, VanishError::InvalidNameStake);
 
-        collab.repository = ctx.accounts.repository.key();
-        collab.user = collaborator;
-        collab.can_push = can_push;
-        collab.added_at = clock.unix_timestamp;
-        collab.bump = ctx.bumps.collaborator_account;
+        let config = &mut ctx.accounts.config;
+        config.name_stake = amount;
+        config.stake_name_len = max_name_len;
+        config.stake_period = lock_period;
 
-        emit!(CollaboratorAdded {
-            repository: ctx.accounts.repository.key(),
-            collaborator,
-            can_push,
-            timestamp: clock.unix_timestamp,
-        });
+        emit_event!(
+            ctx,
+            NameStakeUpdated {
+                amount,
+                max_name_len,
+                lock_period,
+                timestamp: Clock::get()?.unix_timestamp,
+            }
+        );
 
         Ok(())
     }
 
-    /// Remove a collaborator from a repository
-    pub fn remove_collaborator(_ctx: Context<RemoveCollaborator>) -> Result<()> {
-        // Account will be closed automatically via close constraint
+    /// Cap how many repositories one user can have created and not yet
+    /// purged. Zero lifts the cap.
+    pub fn set_repo_quota(ctx: Context<UpdateConfig>, max_repos: u32) -> Result<()> {
+        ctx.accounts.config.max_repos = max_repos;
+
+        emit_event!(
+            ctx,
+            RepoQuotaUpdated {
+                user: None,
+                max_repos,
+                timestamp: Clock::get()?.unix_timestamp,
+            }
+        );
+
         Ok(())
     }
 
-    /// Star a repository
-    pub fn star_repo(ctx: Context<StarRepo>) -> Result<()> {
-        let star = &mut ctx.accounts.star_account;
-        let repo = &mut ctx.accounts.repository;
-        let clock = Clock::get()?;
+    /// Replace the names no repository may take, matched ignoring case.
+    pub fn set_reserved_names(ctx: Context<UpdateConfig>, names: Vec<String>) -> Result<()> {
+        require!(
+            names.len() <= Config::MAX_RESERVED_NAMES
+                && names.iter().all(|name| {
+                    !name.is_empty()
+                        && name.len() <= Config::MAX_RESERVED_NAME_LEN
+                        && is_valid_repo_name(name)
+                }),
+            VanishError::InvalidReservedNames
+        );
 
-        star.user = ctx.accounts.user.key();
-        star.repository = ctx.accounts.repository.key();
-        star.starred_at = clock.unix_timestamp;
-        star.bump = ctx.bumps.star_account;
+        ctx.accounts.config.reserved_names = names.clone();
 
-        repo.stars = repo.stars.checked_add(1).unwrap_or(repo.stars);
+        emit_event!(
+            ctx,
+            ReservedNamesUpdated {
+                names,
+                timestamp: Clock::get()?.unix_timestamp,
+            }
+        );
 
-        emit!(RepoStarred {
-            user: star.user,
-            repository: star.repository,
-            timestamp: clock.unix_timestamp,
-        });
+        Ok(())
+    }
+
+    /// Let anyone reclaim a repository that has gone `idle_period` seconds
+    /// without a push or update, by filing a notice with a `bond` and
+    /// waiting out `grace_period` seconds for the owner to answer. A zero
+    /// `idle_period` turns reclamation off.
+    pub fn set_reclaim_policy(
+        ctx: Context<UpdateConfig>,
+        idle_period: i64,
+        grace_period: i64,
+        bond: u64,
+    ) -> Result<()> {
+        require!(
+            idle_period >= 0 && grace_period >= 0,
+            VanishError::InvalidReclaimPolicy
+        );
+
+        let config = &mut ctx.accounts.config;
+        config.reclaim_idle_period = idle_period;
+        config.reclaim_grace_period = grace_period;
+        config.reclaim_bond = bond;
+
+        emit_event!(
+            ctx,
+            ReclaimPolicyUpdated {
+                idle_period,
+                grace_period,
+                bond,
+                timestamp: Clock::get()?.unix_timestamp,
+            }
+        );
 
         Ok(())
     }
 
-    /// Unstar a repository
-    pub fn unstar_repo(ctx: Context<UnstarRepo>) -> Result<()> {
-        let repo = &mut ctx.accounts.repository;
-        repo.stars = repo.stars.saturating_sub(1);
-        // Star account will be closed automatically
+    /// Limit how many repositories each signer may create, and how many
+    /// repositories they may star, per window of `window_slots` slots. A
+    /// zero limit leaves that action unlimited, and a zero window turns rate
+    /// limiting off.
+    pub fn set_rate_limits(
+        ctx: Context<UpdateConfig>,
+        window_slots: u64,
+        max_creations: u32,
+        max_stars: u32,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.rate_window_slots = window_slots;
+        config.max_creations_per_window = max_creations;
+        config.max_stars_per_window = max_stars;
+
+        emit_event!(
+            ctx,
+            RateLimitsUpdated {
+                window_slots,
+                max_creations,
+                max_stars,
+                timestamp: Clock::get()?.unix_timestamp,
+            }
+        );
+
         Ok(())
     }
 
-    /// Transfer repository ownership
-    pub fn transfer_ownership(ctx: Context<TransferOwnership>, new_owner: Pubkey) -> Result<()> {
-        let repo = &mut ctx.accounts.repository;
-        let old_owner = repo.owner;
+    /// Give `user` their own repository cap in place of the config's, e.g.
+    /// a raised one for verified users. Zero falls back to the config's cap.
+    pub fn set_repo_limit(ctx: Context<SetRepoLimit>, user: Pubkey, limit: u32) -> Result<()> {
+        let profile = &mut ctx.accounts.profile;
+        init_profile(profile, user, ctx.bumps.profile);
+        profile.repo_limit = limit;
 
-        repo.owner = new_owner;
+        emit_event!(
+            ctx,
+            RepoQuotaUpdated {
+                user: Some(user),
+                max_repos: limit,
+                timestamp: Clock::get()?.unix_timestamp,
+            }
+        );
 
-        emit!(OwnershipTransferred {
-            repository: ctx.accounts.repository.key(),
-            old_owner,
-            new_owner,
-        });
+        Ok(())
+    }
+
+    /// Move collected fees out of the treasury. Config admin only; the
+    /// treasury keeps its rent-exempt minimum.
+    pub fn withdraw_treasury(ctx: Context<WithdrawTreasury>, amount: u64) -> Result<()> {
+        let treasury = &ctx.accounts.treasury;
+        let available = treasury
+            .lamports()
+            .saturating_sub(Rent::get()?.minimum_balance(0));
+        require!(amount <= available, VanishError::InsufficientTreasury);
+
+        system_program::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: treasury.to_account_info(),
+                    to: ctx.accounts.recipient.to_account_info(),
+                },
+                &[&[b"treasury", &[ctx.accounts.config.treasury_bump]]],
+            ),
+            amount,
+        )?;
+
+        emit_event!(
+            ctx,
+            TreasuryWithdrawn {
+                recipient: ctx.accounts.recipient.key(),
+                amount,
+                timestamp: Clock::get()?.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Let `verifier` verify repositories alongside the config admin. Config
+    /// admin only.
+    pub fn add_verifier(ctx: Context<AddVerifier>, verifier: Pubkey) -> Result<()> {
+        let verifier_account = &mut ctx.accounts.verifier_account;
+        let clock = Clock::get()?;
+
+        verifier_account.verifier = verifier;
+        verifier_account.added_at = clock.unix_timestamp;
+        verifier_account.bump = ctx.bumps.verifier_account;
+
+        emit_event!(
+            ctx,
+            VerifierAdded {
+                verifier,
+                timestamp: clock.unix_timestamp,
+            }
+        );
 
         Ok(())
     }
 
-    /// Delete a repository
-    pub fn delete_repo(_ctx: Context<DeleteRepo>) -> Result<()> {
+    /// Revoke a verifier. Verifications it already made stay until revoked
+    /// themselves.
+    pub fn remove_verifier(ctx: Context<RemoveVerifier>) -> Result<()> {
+        emit_event!(
+            ctx,
+            VerifierRemoved {
+                verifier: ctx.accounts.verifier_account.verifier,
+                timestamp: Clock::get()?.unix_timestamp,
+            }
+        );
+
         // Account will be closed automatically via close constraint
         Ok(())
     }
-}
 
-// ============================================================================
-// Accounts
-// ============================================================================
+    /// Let `oracle` attest Filecoin storage deals. Config admin only.
+    pub fn add_deal_oracle(ctx: Context<AddDealOracle>, oracle: Pubkey) -> Result<()> {
+        let oracle_account = &mut ctx.accounts.oracle_account;
+        let clock = Clock::get()?;
 
-#[derive(Accounts)]
-#[instruction(name: String)]
-pub struct CreateRepo<'info> {
-    #[account(mut)]
-    pub owner: Signer<'info>,
+        oracle_account.oracle = oracle;
+        oracle_account.added_at = clock.unix_timestamp;
+        oracle_account.bump = ctx.bumps.oracle_account;
 
-    #[account(
-        init,
-        payer = owner,
-        space = Repository::SPACE,
-        seeds = [b"repo", owner.key().as_ref(), name.as_bytes()],
-        bump
-    )]
-    pub repository: Account<'info, Repository>,
+        emit_event!(
+            ctx,
+            DealOracleAdded {
+                oracle,
+                timestamp: clock.unix_timestamp,
+            }
+        );
 
-    pub system_program: Program<'info, System>,
-}
+        Ok(())
+    }
 
-#[derive(Accounts)]
-pub struct PushUpdate<'info> {
-    pub owner: Signer<'info>,
+    /// Revoke a deal oracle. Deals it attested stay until they lapse.
+    pub fn remove_deal_oracle(ctx: Context<RemoveDealOracle>) -> Result<()> {
+        emit_event!(
+            ctx,
+            DealOracleRemoved {
+                oracle: ctx.accounts.oracle_account.oracle,
+                timestamp: Clock::get()?.unix_timestamp,
+            }
+        );
 
-    #[account(
-        mut,
-        seeds = [b"repo", owner.key().as_ref(), repository.name.as_bytes()],
-        bump = repository.bump,
-        has_one = owner
-    )]
-    pub repository: Account<'info, Repository>,
-}
+        // Account will be closed automatically via close constraint
+        Ok(())
+    }
 
-#[derive(Accounts)]
-#[instruction(collaborator: Pubkey)]
-pub struct AddCollaborator<'info> {
-    #[account(mut)]
-    pub owner: Signer<'info>,
+    /// Create a new repository
+    pub fn create_repo(
+        ctx: Context<CreateRepo>,
+        name: String,
+        description: String,
+        is_private: bool,
+    ) -> Result<()> {
+        let staked = init_repository(
+            NewRepository {
+                owner: &ctx.accounts.owner,
+                repository: &mut ctx.accounts.repository,
+                repository_bump: ctx.bumps.repository,
+                tombstone: &ctx.accounts.tombstone,
+                name_lock: &mut ctx.accounts.name_lock,
+                name_lock_bump: ctx.bumps.name_lock,
+                name_stake: &mut ctx.accounts.name_stake,
+                name_stake_bump: ctx.bumps.name_stake,
+                profile: &mut ctx.accounts.profile,
+                profile_bump: ctx.bumps.profile,
+                config: &ctx.accounts.config,
+                treasury: &ctx.accounts.treasury,
+                system_program: &ctx.accounts.system_program,
+            },
+            name,
+            description,
+            is_private,
+        )?;
 
-    #[account(
-        seeds = [b"repo", owner.key().as_ref(), repository.name.as_bytes()],
-        bump = repository.bump,
-        has_one = owner
-    )]
-    pub repository: Account<'info, Repository>,
+        let repo = &ctx.accounts.repository;
+        if let Some(name_stake) = staked {
+            emit_event!(ctx, name_stake);
+        }
+        emit_event!(
+            ctx,
+            RepoCreated {
+                repository: repo.key(),
+                owner: repo.owner,
+                name: repo.name.clone(),
+                is_private,
+                timestamp: repo.created_at,
+            }
+        );
 
-    #[account(
-        init,
-        payer = owner,
-        space = Collaborator::SPACE,
-        seeds = [b"collab", repository.key().as_ref(), collaborator.as_ref()],
-        bump
-    )]
-    pub collaborator_account: Account<'info, Collaborator>,
+        Ok(())
+    }
 
-    pub system_program: Program<'info, System>,
-}
+    /// Create a public repository starting from the current head of
+    /// `template`, a public repository flagged `repo_flags::TEMPLATE`. The
+    /// archive is shared by reference, and the new repository records the
+    /// template's `id` as its provenance.
+    pub fn create_from_template(
+        ctx: Context<CreateFromTemplate>,
+        name: String,
+        description: String,
+    ) -> Result<()> {
+        let template = &ctx.accounts.template;
+        require_live(template)?;
+        require!(
+            template.flags & repo_flags::TEMPLATE != 0 && !template.is_private,
+            VanishError::NotATemplate
+        );
 
-#[derive(Accounts)]
-pub struct RemoveCollaborator<'info> {
-    #[account(mut)]
-    pub owner: Signer<'info>,
+        let staked = init_repository(
+            NewRepository {
+                owner: &ctx.accounts.owner,
+                repository: &mut ctx.accounts.repository,
+                repository_bump: ctx.bumps.repository,
+                tombstone: &ctx.accounts.tombstone,
+                name_lock: &mut ctx.accounts.name_lock,
+                name_lock_bump: ctx.bumps.name_lock,
+                name_stake: &mut ctx.accounts.name_stake,
+                name_stake_bump: ctx.bumps.name_stake,
+                profile: &mut ctx.accounts.profile,
+                profile_bump: ctx.bumps.profile,
+                config: &ctx.accounts.config,
+                treasury: &ctx.accounts.treasury,
+                system_program: &ctx.accounts.system_program,
+            },
+            name,
+            description,
+            false,
+        )?;
 
-    #[account(
-        seeds = [b"repo", owner.key().as_ref(), repository.name.as_bytes()],
-        bump = repository.bump,
-        has_one = owner
-    )]
-    pub repository: Account<'info, Repository>,
+        let repo = &mut ctx.accounts.repository;
+        repo.head_commit = template.head_commit;
+        repo.ipfs_cid = template.ipfs_cid;
+        repo.objects_cid = template.objects_cid.clone();
+        repo.template = Some(template.id);
 
-    #[account(
-        mut,
-        close = owner,
-        seeds = [b"collab", repository.key().as_ref(), collaborator_account.user.as_ref()],
-        bump = collaborator_account.bump
-    )]
-    pub collaborator_account: Account<'info, Collaborator>,
-}
+        if let Some(name_stake) = staked {
+            emit_event!(ctx, name_stake);
+        }
+        emit_event!(
+            ctx,
+            RepoCreated {
+                repository: repo.key(),
+                owner: repo.owner,
+                name: repo.name.clone(),
+                is_private: false,
+                timestamp: repo.created_at,
+            }
+        );
+        emit_event!(
+            ctx,
+            RepoCreatedFromTemplate {
+                repository: repo.key(),
+                template: template.key(),
+                head_commit: repo.head_commit.to_multihash(),
+                timestamp: repo.created_at,
+            }
+        );
 
-#[derive(Accounts)]
-pub struct StarRepo<'info> {
-    #[account(mut)]
-    pub user: Signer<'info>,
+        Ok(())
+    }
 
-    #[account(mut)]
-    pub repository: Account<'info, Repository>,
+    /// Fork `upstream`, a public repository, into a new repository starting
+    /// from its current head. The fork is flagged `repo_flags::FORK`, linked
+    /// to the upstream for sync_with_upstream and counted in its `forks`.
+    pub fn fork_repo(ctx: Context<ForkRepo>, name: String, description: String) -> Result<()> {
+        let upstream = &mut ctx.accounts.upstream;
+        require_live(upstream)?;
+        require!(!upstream.is_private, VanishError::PrivateUpstream);
 
-    #[account(
-        init,
-        payer = user,
-        space = Star::SPACE,
-        seeds = [b"star", user.key().as_ref(), repository.key().as_ref()],
-        bump
-    )]
-    pub star_account: Account<'info, Star>,
+        let staked = init_repository(
+            NewRepository {
+                owner: &ctx.accounts.owner,
+                repository: &mut ctx.accounts.repository,
+                repository_bump: ctx.bumps.repository,
+                tombstone: &ctx.accounts.tombstone,
+                name_lock: &mut ctx.accounts.name_lock,
+                name_lock_bump: ctx.bumps.name_lock,
+                name_stake: &mut ctx.accounts.name_stake,
+                name_stake_bump: ctx.bumps.name_stake,
+                profile: &mut ctx.accounts.profile,
+                profile_bump: ctx.bumps.profile,
+                config: &ctx.accounts.config,
+                treasury: &ctx.accounts.treasury,
+                system_program: &ctx.accounts.system_program,
+            },
+            name,
+            description,
+            false,
+        )?;
 
-    pub system_program: Program<'info, System>,
-}
+        let repo = &mut ctx.accounts.repository;
+        repo.head_commit = upstream.head_commit;
+        repo.ipfs_cid = upstream.ipfs_cid;
+        repo.objects_cid = upstream.objects_cid.clone();
+        repo.flags |= repo_flags::FORK;
+        upstream.forks += 1;
 
-#[derive(Accounts)]
-pub struct UnstarRepo<'info> {
-    #[account(mut)]
-    pub user: Signer<'info>,
+        let link = &mut ctx.accounts.fork_link;
+        link.repository = repo.id;
+        link.upstream = upstream.id;
+        link.synced_commit = repo.head_commit.to_multihash();
+        link.synced_at = repo.created_at;
+        link.bump = ctx.bumps.fork_link;
 
-    #[account(mut)]
-    pub repository: Account<'info, Repository>,
+        if let Some(name_stake) = staked {
+            emit_event!(ctx, name_stake);
+        }
+        emit_event!(
+            ctx,
+            RepoCreated {
+                repository: repo.key(),
+                owner: repo.owner,
+                name: repo.name.clone(),
+                is_private: false,
+                timestamp: repo.created_at,
+            }
+        );
+        emit_event!(
+            ctx,
+            RepoForked {
+                repository: repo.key(),
+                upstream: upstream.key(),
+                head_commit: link.synced_commit.clone(),
+                timestamp: repo.created_at,
+            }
+        );
 
-    #[account(
-        mut,
-        close = user,
-        seeds = [b"star", user.key().as_ref(), repository.key().as_ref()],
-        bump = star_account.bump
-    )]
-    pub star_account: Account<'info, Star>,
-}
+        Ok(())
+    }
 
-#[derive(Accounts)]
-pub struct TransferOwnership<'info> {
-    pub owner: Signer<'info>,
+    /// Update repository with new commit and IPFS CID, the CID in binary
+    /// form (see `Cid`).
+    /// Pushes by anyone other than the owner must supply their Collaborator
+    /// account, which needs push access and records the push time.
+    /// `expected_head` is the head the pusher built on; the push is rejected
+    /// if it has moved since. Omitting it force-pushes, which only repos
+    /// with `ALLOW_FORCE_PUSH` accept once the head is set.
+    /// `co_authors` are credited with the push too; their Contribution
+    /// accounts follow as writable remaining accounts, in the same order,
+    /// and are created at the pusher's expense when missing.
+    /// `binding` is the pusher's hash tying `head_commit` to the archive at
+    /// `ipfs_cid`. The program cannot see the archive, so it only records
+    /// the binding in RepoPushed for whoever downloads the archive to check.
+    pub fn push_update<'info>(
+        ctx: Context<'_, '_, 'info, 'info, PushUpdate<'info>>,
+        head_commit: Multihash,
+        ipfs_cid: Cid,
+        expected_head: Option<Multihash>,
+        co_authors: Vec<Pubkey>,
+        note: Option<PushNote>,
+        binding: Option<[u8; 32]>,
+    ) -> Result<()> {
+        let head = ObjectId::from_multihash(&head_commit).ok_or(VanishError::InvalidCommitHash)?;
+        require!(ipfs_cid.is_valid(), VanishError::InvalidIpfsCid);
+        if let Some(note) = &note {
+            require!(note.is_valid(), VanishError::InvalidPushNote);
+        }
+        require!(
+            co_authors.len() <= Contribution::MAX_CO_AUTHORS
+                && co_authors.len() == ctx.remaining_accounts.len(),
+            VanishError::InvalidCoAuthors
+        );
+        for (i, co_author) in co_authors.iter().enumerate() {
+            require!(
+                *co_author != ctx.accounts.pusher.key() && !co_authors[..i].contains(co_author),
+                VanishError::InvalidCoAuthors
+            );
+        }
 
-    #[account(
-        mut,
-        seeds = [b"repo", owner.key().as_ref(), repository.name.as_bytes()],
-        bump = repository.bump,
-        has_one = owner
-    )]
-    pub repository: Account<'info, Repository>,
-}
+        let pusher = ctx.accounts.pusher.key();
+        let repo = &mut ctx.accounts.repository;
+        let clock = Clock::get()?;
 
-#[derive(Accounts)]
-pub struct DeleteRepo<'info> {
-    #[account(mut)]
-    pub owner: Signer<'info>,
+        let project_member = record_project_push(
+            &ctx.accounts.project_repo,
+            &mut ctx.accounts.project,
+            &ctx.accounts.project_member,
+            &pusher,
+            clock.unix_timestamp,
+        )?;
+        push_access(
+            repo,
+            &pusher,
+            ctx.accounts.collaborator_account.as_deref(),
+            None,
+            project_member,
+        )?;
+        record_push_time(
+            repo,
+            &pusher,
+            &mut ctx.accounts.collaborator_account,
+            clock.unix_timestamp,
+        );
+        require_expected_head(
+            &repo.head_commit,
+            expected_head.as_ref(),
+            repo.flags & repo_flags::ALLOW_FORCE_PUSH != 0,
+        )?;
+        record_contribution(
+            &mut ctx.accounts.contribution,
+            repo.id,
+            pusher,
+            ctx.bumps.contribution,
+            clock.unix_timestamp,
+        );
+        for (info, co_author) in ctx.remaining_accounts.iter().zip(&co_authors) {
+            credit_co_author(
+                info,
+                &ctx.accounts.pusher,
+                &ctx.accounts.system_program,
+                repo.id,
+                *co_author,
+                clock.unix_timestamp,
+            )?;
+        }
 
-    #[account(
-        mut,
-        close = owner,
-        seeds = [b"repo", owner.key().as_ref(), repository.name.as_bytes()],
-        bump = repository.bump,
-        has_one = owner
-    )]
-    pub repository: Account<'info, Repository>,
-}
+        repo.head_commit = head;
+        repo.ipfs_cid = ipfs_cid;
+        repo.updated_at = clock.unix_timestamp;
 
-// ============================================================================
-// State
-// ============================================================================
+        if let Some(push_log) = &ctx.accounts.push_log {
+            push_log
+                .load_mut()?
+                .record(pusher, &head_commit, note.as_ref(), clock.unix_timestamp);
+        }
 
-#[account]
-pub struct Repository {
-    pub owner: Pubkey,
-    pub name: String,
-    pub description: String,
-    pub is_private: bool,
-    pub created_at: i64,
-    pub updated_at: i64,
-    pub head_commit: String,
-    pub ipfs_cid: String,
-    pub stars: u64,
-    pub bump: u8,
-}
+        emit_event!(
+            ctx,
+            RepoPushed {
+                repository: repo.key(),
+                owner: repo.owner,
+                name: repo.name.clone(),
+                pusher,
+                co_authors,
+                head_commit,
+                ipfs_cid,
+                note,
+                binding,
+                timestamp: clock.unix_timestamp,
+            }
+        );
 
-impl Repository {
-    pub const SPACE: usize = 8  // discriminator
-        + 32  // owner
-        + 4 + 64  // name (string)
-        + 4 + 256  // description (string)
-        + 1  // is_private
-        + 8  // created_at
-        + 8  // updated_at
-        + 4 + 40  // head_commit (string)
-        + 4 + 64  // ipfs_cid (string)
-        + 8  // stars
-        + 1; // bump
+        if let Some(config) = ctx.accounts.badge_config.as_ref().filter(|c| c.enabled) {
+            let (Some(mint), Some(to), Some(token_program)) = (
+                &ctx.accounts.badge_mint,
+                &ctx.accounts.badge_token_account,
+                &ctx.accounts.token_2022_program,
+            ) else {
+                return err!(VanishError::InvalidBadgeAccounts);
+            };
+            require!(
+                mint.key() == config.mint && to.mint == config.mint && to.owner == pusher,
+                VanishError::InvalidBadgeAccounts
+            );
+
+            let repository_id = ctx.accounts.repository.id;
+            token_2022::mint_to(
+                CpiContext::new_with_signer(
+                    token_program.to_account_info(),
+                    token_2022::MintTo {
+                        mint: mint.to_account_info(),
+                        to: to.to_account_info(),
+                        authority: config.to_account_info(),
+                    },
+                    &[&[b"badges", repository_id.as_ref(), &[config.bump]]],
+                ),
+                1,
+            )?;
+
+            emit_event!(
+                ctx,
+                BadgeMinted {
+                    repository: ctx.accounts.repository.key(),
+                    contributor: pusher,
+                    mint: config.mint,
+                    timestamp: clock.unix_timestamp,
+                }
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Record statistics for the repository's current head, computed by the
+    /// pusher's client: how many commits the push added, the packed size of
+    /// the repository and its languages. Sent alongside push_update, once
+    /// per head.
+    pub fn update_stats(
+        ctx: Context<UpdateStats>,
+        commits_added: u64,
+        packed_size: u64,
+        languages: Vec<String>,
+    ) -> Result<()> {
+        require!(
+            commits_added <= Stats::MAX_COMMITS_PER_PUSH
+                && packed_size <= Stats::MAX_PACKED_SIZE
+                && languages.len() <= Stats::MAX_LANGUAGES
+                && languages.iter().all(|tag| is_valid_language_tag(tag)),
+            VanishError::InvalidStats
+        );
+
+        let pusher = ctx.accounts.pusher.key();
+        let repo = &ctx.accounts.repository;
+        let clock = Clock::get()?;
+
+        require_pusher(
+            repo,
+            &pusher,
+            &mut ctx.accounts.collaborator_account,
+            clock.unix_timestamp,
+        )?;
+
+        let head = repo.head_commit.to_multihash().seed();
+        let stats = &mut ctx.accounts.stats;
+        require!(stats.head != head, VanishError::StatsRecorded);
+
+        stats.repository = repo.id;
+        stats.head = head;
+        stats.commit_count = stats.commit_count.saturating_add(commits_added);
+        stats.packed_size = packed_size;
+        stats.languages = languages;
+        stats.updated_at = clock.unix_timestamp;
+        stats.bump = ctx.bumps.stats;
+
+        emit_event!(
+            ctx,
+            StatsUpdated {
+                repository: repo.key(),
+                commits_added,
+                commit_count: stats.commit_count,
+                packed_size,
+                languages: stats.languages.clone(),
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Record a branch's head commit and its own archive CID. The branch
+    /// archive holds refs only; objects live in the repository's shared
+    /// object store (`objects_cid`). Same permissions and `expected_head`
+    /// check as push_update, with force pushes allowed per branch, and
+    /// collaborators without push access may push the branches a BranchGrant
+    /// gives them. `proof` proves the branch's leaf in the refs tree (see
+    /// `refs_tree`).
+    pub fn push_branch(
+        ctx: Context<PushBranch>,
+        branch_name: String,
+        head_commit: Multihash,
+        ipfs_cid: String,
+        expected_head: Option<Multihash>,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        require!(
+            !branch_name.is_empty() && branch_name.len() <= 64,
+            VanishError::InvalidBranchName
+        );
+        require!(
+            head_commit.is_git_object_id(),
+            VanishError::InvalidCommitHash
+        );
+        require!(ipfs_cid.len() <= 64, VanishError::InvalidIpfsCid);
+
+        let pusher = ctx.accounts.pusher.key();
+        let repo = &mut ctx.accounts.repository;
+        let clock = Clock::get()?;
+
+        let grant = require_branch_pusher(
+            repo,
+            &pusher,
+            &mut ctx.accounts.collaborator_account,
+            &ctx.accounts.branch_grant,
+            clock.unix_timestamp,
+        )?;
+        if let Some(grant) = grant {
+            require!(grant.allows(&branch_name), VanishError::BranchNotGranted);
+        }
+
+        let branch = &mut ctx.accounts.branch;
+        require_expected_head(
+            &branch.head_commit,
+            expected_head.as_ref(),
+            branch.allow_force_push,
+        )?;
+
+        record_contribution(
+            &mut ctx.accounts.contribution,
+            repo.id,
+            pusher,
+            ctx.bumps.contribution,
+            clock.unix_timestamp,
+        );
+        repo.updated_at = clock.unix_timestamp;
+
+        let created = branch.repository == Pubkey::default();
+        branch.repository = repo.id;
+        branch.name = branch_name;
+        set_ref_tip(repo, branch, head_commit, &proof)?;
+        branch.ipfs_cid = ipfs_cid;
+        branch.updated_at = clock.unix_timestamp;
+        branch.bump = ctx.bumps.branch;
+        branch.version = Branch::VERSION;
+
+        emit_event!(
+            ctx,
+            BranchPushed {
+                repository: branch.repository,
+                branch: branch.name.clone(),
+                pusher,
+                head_commit: branch.head_commit.clone(),
+                ipfs_cid: branch.ipfs_cid.clone(),
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        if created {
+            let funded = fund_rent(
+                &mut ctx.accounts.rent_vault,
+                &ctx.accounts.project_repo,
+                &ctx.accounts.pusher,
+                Rent::get()?.minimum_balance(Branch::SPACE),
+                |vault| vault.branch_cap,
+            )?;
+            if funded > 0 {
+                emit_event!(
+                    ctx,
+                    RentFunded {
+                        project: ctx.accounts.project_repo.as_ref().unwrap().project,
+                        repository: ctx.accounts.repository.key(),
+                        payer: pusher,
+                        amount: funded,
+                        timestamp: clock.unix_timestamp,
+                    }
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Update the head and any number of branches in one go, all or
+    /// nothing, as a git push updating several refs does. The branches are
+    /// passed as writable remaining accounts in the order of `branches` and
+    /// must already exist; new ones are created with push_branch. Each update
+    /// gets the same `expected_head` check as a single push. A pusher limited
+    /// by a BranchGrant can only update the branches it covers, not the head.
+    pub fn push_refs<'info>(
+        ctx: Context<'_, '_, 'info, 'info, PushRefs<'info>>,
+        head: Option<HeadUpdate>,
+        branches: Vec<RefUpdate>,
+    ) -> Result<()> {
+        require!(
+            (head.is_some() || !branches.is_empty())
+                && branches.len() == ctx.remaining_accounts.len(),
+            VanishError::InvalidRefUpdates
+        );
+        if let Some(update) = &head {
+            require!(
+                update.head_commit.is_git_object_id(),
+                VanishError::InvalidCommitHash
+            );
+            require!(update.ipfs_cid.is_valid(), VanishError::InvalidIpfsCid);
+        }
+        for update in &branches {
+            require!(
+                update.head_commit.is_git_object_id(),
+                VanishError::InvalidCommitHash
+            );
+            require!(update.ipfs_cid.len() <= 64, VanishError::InvalidIpfsCid);
+        }
+
+        let pusher = ctx.accounts.pusher.key();
+        let repo = &mut ctx.accounts.repository;
+        let clock = Clock::get()?;
+
+        let grant = require_branch_pusher(
+            repo,
+            &pusher,
+            &mut ctx.accounts.collaborator_account,
+            &ctx.accounts.branch_grant,
+            clock.unix_timestamp,
+        )?;
+        require!(grant.is_none() || head.is_none(), VanishError::Unauthorized);
+        record_contribution(
+            &mut ctx.accounts.contribution,
+            repo.id,
+            pusher,
+            ctx.bumps.contribution,
+            clock.unix_timestamp,
+        );
+
+        repo.updated_at = clock.unix_timestamp;
+
+        if let Some(update) = head {
+            require_expected_head(
+                &repo.head_commit,
+                update.expected_head.as_ref(),
+                repo.flags & repo_flags::ALLOW_FORCE_PUSH != 0,
+            )?;
+            repo.head_commit = ObjectId::from_multihash(&update.head_commit)
+                .ok_or(VanishError::InvalidCommitHash)?;
+            repo.ipfs_cid = update.ipfs_cid;
+
+            emit_event!(
+                ctx,
+                RepoPushed {
+                    repository: repo.key(),
+                    owner: repo.owner,
+                    name: repo.name.clone(),
+                    pusher,
+                    co_authors: Vec::new(),
+                    head_commit: update.head_commit,
+                    ipfs_cid: update.ipfs_cid,
+                    note: None,
+                    binding: None,
+                    timestamp: clock.unix_timestamp,
+                }
+            );
+        }
+
+        for (info, update) in ctx.remaining_accounts.iter().zip(branches) {
+            let mut branch = Account::<Branch>::try_from(info)?;
+            require!(
+                info.is_writable && branch.repository == repo.id,
+                VanishError::InvalidRefUpdates
+            );
+            if let Some(grant) = grant {
+                require!(grant.allows(&branch.name), VanishError::BranchNotGranted);
+            }
+            require_expected_head(
+                &branch.head_commit,
+                update.expected_head.as_ref(),
+                branch.allow_force_push,
+            )?;
+
+            set_ref_tip(repo, &mut branch, update.head_commit, &update.proof)?;
+            branch.ipfs_cid = update.ipfs_cid;
+            branch.updated_at = clock.unix_timestamp;
+            // Written back now so a branch listed twice sees the first update
+            branch.exit(&crate::ID)?;
+
+            emit_event!(
+                ctx,
+                BranchPushed {
+                    repository: branch.repository,
+                    branch: branch.name.clone(),
+                    pusher,
+                    head_commit: branch.head_commit.clone(),
+                    ipfs_cid: branch.ipfs_cid.clone(),
+                    timestamp: clock.unix_timestamp,
+                }
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Bring a fork up to date with the upstream fork_repo linked it to by
+    /// copying the upstream's head, or with `branch_name` one of its
+    /// branches, without re-pushing the content. Branch syncs pass the branch's `proof` in the refs tree,
+    /// and both name the `expected_head` they replace, as pushes do.
+    pub fn sync_with_upstream(
+        ctx: Context<SyncWithUpstream>,
+        branch_name: Option<String>,
+        proof: Vec<[u8; 32]>,
+        expected_head: Option<Multihash>,
+    ) -> Result<()> {
+        let pusher = ctx.accounts.pusher.key();
+        let upstream = &ctx.accounts.upstream;
+        let repo = &mut ctx.accounts.repository;
+        let clock = Clock::get()?;
+
+        require_pusher(
+            repo,
+            &pusher,
+            &mut ctx.accounts.collaborator_account,
+            clock.unix_timestamp,
+        )?;
+        require_live(upstream)?;
+        let link = &mut ctx.accounts.fork_link;
+
+        let (head_commit, ipfs_cid) = match (
+            &branch_name,
+            &ctx.accounts.upstream_branch,
+            &mut ctx.accounts.branch,
+        ) {
+            (None, None, None) => {
+                require_expected_head(
+                    &repo.head_commit,
+                    expected_head.as_ref(),
+                    repo.flags & repo_flags::ALLOW_FORCE_PUSH != 0,
+                )?;
+                repo.head_commit = upstream.head_commit;
+                repo.ipfs_cid = upstream.ipfs_cid;
+                (repo.head_commit.to_multihash(), repo.ipfs_cid.to_string())
+            }
+            (Some(name), Some(source), Some(branch)) => {
+                require!(source.name == *name, VanishError::InvalidBranchName);
+                require_expected_head(
+                    &branch.head_commit,
+                    expected_head.as_ref(),
+                    branch.allow_force_push,
+                )?;
+                branch.repository = repo.id;
+                branch.name = name.clone();
+                set_ref_tip(repo, branch, source.head_commit.clone(), &proof)?;
+                branch.ipfs_cid = source.ipfs_cid.clone();
+                branch.updated_at = clock.unix_timestamp;
+                branch.bump = ctx.bumps.branch;
+                branch.version = Branch::VERSION;
+                (branch.head_commit.clone(), branch.ipfs_cid.clone())
+            }
+            _ => return err!(VanishError::InvalidBranchName),
+        };
+        require!(
+            head_commit.is_git_object_id(),
+            VanishError::InvalidCommitHash
+        );
+
+        repo.updated_at = clock.unix_timestamp;
+        link.synced_commit = head_commit.clone();
+        link.synced_at = clock.unix_timestamp;
+
+        emit_event!(
+            ctx,
+            ForkSynced {
+                repository: repo.key(),
+                upstream: upstream.key(),
+                branch: branch_name,
+                head_commit,
+                ipfs_cid,
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Pin another repository at `commit` under `path`, like a git
+    /// submodule. Anyone who can push can manage submodules.
+    pub fn add_submodule(
+        ctx: Context<AddSubmodule>,
+        path: String,
+        commit: Multihash,
+    ) -> Result<()> {
+        require!(
+            is_valid_submodule_path(&path),
+            VanishError::InvalidSubmodulePath
+        );
+
+        let submodule = &mut ctx.accounts.submodule;
+        submodule.repository = ctx.accounts.repository.id;
+        submodule.path = path;
+        submodule.bump = ctx.bumps.submodule;
+
+        pin_submodule(
+            &mut ctx.accounts.repository,
+            &ctx.accounts.pusher.key(),
+            &mut ctx.accounts.collaborator_account,
+            &ctx.accounts.target,
+            submodule,
+            commit,
+        )?;
+
+        emit_event!(
+            ctx,
+            SubmoduleUpdated {
+                repository: ctx.accounts.repository.key(),
+                path: submodule.path.clone(),
+                target: submodule.target,
+                commit: submodule.commit.clone(),
+                timestamp: submodule.updated_at,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Re-pin a submodule to another commit, or another repository
+    pub fn update_submodule(ctx: Context<UpdateSubmodule>, commit: Multihash) -> Result<()> {
+        let submodule = &mut ctx.accounts.submodule;
+
+        pin_submodule(
+            &mut ctx.accounts.repository,
+            &ctx.accounts.pusher.key(),
+            &mut ctx.accounts.collaborator_account,
+            &ctx.accounts.target,
+            submodule,
+            commit,
+        )?;
+
+        emit_event!(
+            ctx,
+            SubmoduleUpdated {
+                repository: ctx.accounts.repository.key(),
+                path: submodule.path.clone(),
+                target: submodule.target,
+                commit: submodule.commit.clone(),
+                timestamp: submodule.updated_at,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Drop a submodule, refunding its rent to the pusher
+    pub fn remove_submodule(ctx: Context<RemoveSubmodule>) -> Result<()> {
+        let clock = Clock::get()?;
+        require_pusher(
+            &ctx.accounts.repository,
+            &ctx.accounts.pusher.key(),
+            &mut ctx.accounts.collaborator_account,
+            clock.unix_timestamp,
+        )?;
+
+        emit_event!(
+            ctx,
+            SubmoduleRemoved {
+                repository: ctx.accounts.repository.key(),
+                path: ctx.accounts.submodule.path.clone(),
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        // Account will be closed automatically via close constraint
+        Ok(())
+    }
+
+    /// Point the repository at a new shared object store, e.g. after
+    /// repacking and dropping unreachable objects
+    pub fn gc_objects(ctx: Context<GcObjects>, objects_cid: String) -> Result<()> {
+        require_admin(
+            &ctx.accounts.repository,
+            &ctx.accounts.authority.key(),
+            &ctx.accounts.admin,
+        )?;
+
+        require!(
+            !objects_cid.is_empty() && objects_cid.len() <= 64,
+            VanishError::InvalidIpfsCid
+        );
+
+        let repo = &mut ctx.accounts.repository;
+        let clock = Clock::get()?;
+
+        let old_objects_cid = std::mem::replace(&mut repo.objects_cid, objects_cid.clone());
+        repo.updated_at = clock.unix_timestamp;
+
+        emit_event!(
+            ctx,
+            ObjectsRotated {
+                repository: repo.key(),
+                old_objects_cid,
+                objects_cid,
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Update the repository description, stored inline or as an IPFS CID
+    pub fn set_description(
+        ctx: Context<SetDescription>,
+        description: String,
+        storage: DescriptionStorage,
+    ) -> Result<()> {
+        require_admin(
+            &ctx.accounts.repository,
+            &ctx.accounts.authority.key(),
+            &ctx.accounts.admin,
+        )?;
+
+        match storage {
+            DescriptionStorage::Inline => {
+                require!(description.len() <= 256, VanishError::DescriptionTooLong);
+            }
+            DescriptionStorage::Ipfs => {
+                require!(
+                    !description.is_empty() && description.len() <= 64,
+                    VanishError::InvalidIpfsCid
+                );
+            }
+        }
+
+        let repo = &mut ctx.accounts.repository;
+        let clock = Clock::get()?;
+
+        repo.description = description.clone();
+        repo.description_storage = storage;
+
+        emit_event!(
+            ctx,
+            DescriptionUpdated {
+                repository: repo.key(),
+                description,
+                storage,
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Set or clear classification bits in `repo_flags::ALL`
+    pub fn set_repo_flags(ctx: Context<SetRepoFlags>, flags: u8, enabled: bool) -> Result<()> {
+        require_admin(
+            &ctx.accounts.repository,
+            &ctx.accounts.authority.key(),
+            &ctx.accounts.admin,
+        )?;
+
+        require!(
+            flags != 0 && flags & !repo_flags::ALL == 0,
+            VanishError::InvalidRepoFlags
+        );
+
+        let repo = &mut ctx.accounts.repository;
+        let clock = Clock::get()?;
+
+        if enabled {
+            repo.flags |= flags;
+        } else {
+            repo.flags &= !flags;
+        }
+
+        emit_event!(
+            ctx,
+            RepoFlagsUpdated {
+                repository: repo.key(),
+                flags: repo.flags,
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Block pushes from everyone but the owner, e.g. while a leaked
+    /// collaborator key is dealt with, or lift the block. Unlike archiving,
+    /// admins can neither freeze nor unfreeze.
+    pub fn set_frozen(ctx: Context<SetFrozen>, frozen: bool) -> Result<()> {
+        let repo = &mut ctx.accounts.repository;
+        let clock = Clock::get()?;
+
+        if frozen {
+            repo.flags |= repo_flags::FROZEN;
+        } else {
+            repo.flags &= !repo_flags::FROZEN;
+        }
+
+        emit_event!(
+            ctx,
+            RepoFrozen {
+                repository: repo.key(),
+                frozen,
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Mark the repository deprecated, pointing readers at `successor` if
+    /// there is a replacement. Calling it again changes the successor.
+    pub fn deprecate_repo(ctx: Context<DeprecateRepo>, successor: Option<Pubkey>) -> Result<()> {
+        require_admin(
+            &ctx.accounts.repository,
+            &ctx.accounts.authority.key(),
+            &ctx.accounts.admin,
+        )?;
+        require_live(&ctx.accounts.repository)?;
+
+        // The successor must be a live repository other than this one
+        match &ctx.accounts.successor {
+            Some(account) => {
+                require!(
+                    Some(account.key()) == successor
+                        && account.key() != ctx.accounts.repository.key()
+                        && account.deleted_at == 0,
+                    VanishError::InvalidSuccessor
+                );
+            }
+            None => require!(successor.is_none(), VanishError::InvalidSuccessor),
+        }
+
+        let repo = &mut ctx.accounts.repository;
+        let clock = Clock::get()?;
+
+        repo.flags |= repo_flags::DEPRECATED;
+        repo.successor = successor;
+
+        emit_event!(
+            ctx,
+            RepoDeprecated {
+                repository: repo.key(),
+                successor,
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Mark the repository as the canonical one for its project, backed by
+    /// the attestation at `uri`. Callable by the config admin or a registered
+    /// verifier; verifying again replaces the attestation.
+    pub fn verify_repo(ctx: Context<VerifyRepo>, uri: String) -> Result<()> {
+        require_verifier(
+            &ctx.accounts.config,
+            &ctx.accounts.verifier.key(),
+            &ctx.accounts.verifier_account,
+        )?;
+        require_live(&ctx.accounts.repository)?;
+        require!(
+            !uri.is_empty() && uri.len() <= Verification::MAX_URI_LEN,
+            VanishError::InvalidVerificationUri
+        );
+
+        let verification = &mut ctx.accounts.verification;
+        let clock = Clock::get()?;
+
+        verification.repository = ctx.accounts.repository.id;
+        verification.verifier = ctx.accounts.verifier.key();
+        verification.uri = uri;
+        verification.verified_at = clock.unix_timestamp;
+        verification.bump = ctx.bumps.verification;
+
+        emit_event!(
+            ctx,
+            RepoVerified {
+                repository: ctx.accounts.repository.key(),
+                verifier: verification.verifier,
+                uri: verification.uri.clone(),
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Withdraw a repository's verification. Any verifier may revoke, not
+    /// only the one who verified; the rent goes back to the latter.
+    pub fn revoke_verification(ctx: Context<RevokeVerification>) -> Result<()> {
+        require_verifier(
+            &ctx.accounts.config,
+            &ctx.accounts.authority.key(),
+            &ctx.accounts.verifier_account,
+        )?;
+
+        emit_event!(
+            ctx,
+            RepoVerificationRevoked {
+                repository: ctx.accounts.repository.key(),
+                revoked_by: ctx.accounts.authority.key(),
+                timestamp: Clock::get()?.unix_timestamp,
+            }
+        );
+
+        // Account will be closed automatically via close constraint
+        Ok(())
+    }
+
+    /// Declare that the repository's archives are stored on Filecoin. The
+    /// deal stays inactive until a deal oracle attests it.
+    pub fn declare_filecoin(ctx: Context<DeclareFilecoin>) -> Result<()> {
+        require_admin(
+            &ctx.accounts.repository,
+            &ctx.accounts.authority.key(),
+            &ctx.accounts.admin,
+        )?;
+
+        let deal = &mut ctx.accounts.filecoin_deal;
+        deal.repository = ctx.accounts.repository.id;
+        deal.payload_cid = String::new();
+        deal.deal_id = 0;
+        deal.expires_at = 0;
+        deal.oracle = Pubkey::default();
+        deal.attested_at = 0;
+        deal.active = false;
+        deal.bump = ctx.bumps.filecoin_deal;
+
+        emit_event!(
+            ctx,
+            FilecoinDeclared {
+                repository: ctx.accounts.repository.key(),
+                timestamp: Clock::get()?.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Attest that deal `deal_id`, running until `expires_at`, stores the
+    /// repository's current archive. Deal oracles only.
+    pub fn attest_filecoin_deal(
+        ctx: Context<AttestFilecoinDeal>,
+        deal_id: u64,
+        expires_at: i64,
+    ) -> Result<()> {
+        let repo = &ctx.accounts.repository;
+        require_live(repo)?;
+        require!(!repo.ipfs_cid.is_empty(), VanishError::InvalidIpfsCid);
+
+        let clock = Clock::get()?;
+        require!(
+            expires_at > clock.unix_timestamp,
+            VanishError::InvalidExpiry
+        );
+
+        let deal = &mut ctx.accounts.filecoin_deal;
+        deal.payload_cid = repo.ipfs_cid.to_string();
+        deal.deal_id = deal_id;
+        deal.expires_at = expires_at;
+        deal.oracle = ctx.accounts.oracle.key();
+        deal.attested_at = clock.unix_timestamp;
+        deal.active = true;
+
+        emit_event!(
+            ctx,
+            FilecoinDealAttested {
+                repository: repo.key(),
+                oracle: deal.oracle,
+                deal_id,
+                payload_cid: deal.payload_cid.clone(),
+                expires_at,
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Mark a repository's Filecoin deal lapsed, once it has expired or the
+    /// repository has moved on to an archive it does not cover. Anyone can.
+    pub fn flag_lapsed_deal(ctx: Context<FlagLapsedDeal>) -> Result<()> {
+        let repo = &ctx.accounts.repository;
+        let deal = &mut ctx.accounts.filecoin_deal;
+        let clock = Clock::get()?;
+
+        require!(
+            deal.active
+                && (clock.unix_timestamp >= deal.expires_at
+                    || deal.payload_cid != repo.ipfs_cid.to_string()),
+            VanishError::DealNotLapsed
+        );
+        deal.active = false;
+
+        emit_event!(
+            ctx,
+            FilecoinDealLapsed {
+                repository: repo.key(),
+                deal_id: deal.deal_id,
+                payload_cid: deal.payload_cid.clone(),
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Register the signer as a pinner, staking `stake` lamports on top of
+    /// the account's rent.
+    pub fn register_pinner(ctx: Context<RegisterPinner>, stake: u64) -> Result<()> {
+        require!(stake >= Pinner::MIN_STAKE, VanishError::PinnerStakeTooLow);
+
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.operator.to_account_info(),
+                    to: ctx.accounts.pinner.to_account_info(),
+                },
+            ),
+            stake,
+        )?;
+
+        let pinner = &mut ctx.accounts.pinner;
+        let clock = Clock::get()?;
+
+        pinner.operator = ctx.accounts.operator.key();
+        pinner.stake = stake;
+        pinner.registered_at = clock.unix_timestamp;
+        pinner.unbonding_at = 0;
+        pinner.bump = ctx.bumps.pinner;
+
+        emit_event!(
+            ctx,
+            PinnerRegistered {
+                operator: pinner.operator,
+                stake,
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Add the operator's pinner to a repository's pinner set. Both the
+    /// operator and the repository owner or an admin sign, so neither can
+    /// enroll a pinner the other has not agreed to.
+    pub fn enroll_pinner(ctx: Context<EnrollPinner>) -> Result<()> {
+        require_admin(
+            &ctx.accounts.repository,
+            &ctx.accounts.authority.key(),
+            &ctx.accounts.admin,
+        )?;
+        require!(
+            ctx.accounts.pinner.unbonding_at == 0,
+            VanishError::PinnerUnbonding
+        );
+
+        let repo = &mut ctx.accounts.repository;
+        require_live(repo)?;
+
+        let operator = ctx.accounts.operator.key();
+        require!(
+            !repo.pinners.contains(&operator),
+            VanishError::PinnerEnrolled
+        );
+        require!(
+            repo.pinners.len() < Repository::MAX_PINNERS,
+            VanishError::TooManyPinners
+        );
+        repo.pinners.push(operator);
+
+        emit_event!(
+            ctx,
+            PinnerEnrolled {
+                repository: repo.key(),
+                operator,
+                timestamp: Clock::get()?.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Remove `operator` from a repository's pinner set. The pinner itself
+    /// or a repository admin can.
+    pub fn unenroll_pinner(ctx: Context<UnenrollPinner>, operator: Pubkey) -> Result<()> {
+        if ctx.accounts.authority.key() != operator {
+            require_admin(
+                &ctx.accounts.repository,
+                &ctx.accounts.authority.key(),
+                &ctx.accounts.admin,
+            )?;
+        }
+
+        let repo = &mut ctx.accounts.repository;
+        let index = repo
+            .pinners
+            .iter()
+            .position(|pinner| *pinner == operator)
+            .ok_or(VanishError::PinnerNotEnrolled)?;
+        repo.pinners.remove(index);
+
+        emit_event!(
+            ctx,
+            PinnerUnenrolled {
+                repository: repo.key(),
+                operator,
+                timestamp: Clock::get()?.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Attest that the operator's pinner holds the repository's current
+    /// archive. A claim backed by the pinner's stake, which distribute_rewards
+    /// requires to be fresh before paying the pinner.
+    pub fn attest_pinned(ctx: Context<AttestPinned>) -> Result<()> {
+        require!(
+            ctx.accounts.pinner.unbonding_at == 0,
+            VanishError::PinnerUnbonding
+        );
+
+        let repo = &ctx.accounts.repository;
+        require_live(repo)?;
+        let operator = ctx.accounts.operator.key();
+        require!(
+            repo.pinners.contains(&operator),
+            VanishError::PinnerNotEnrolled
+        );
+        require!(!repo.ipfs_cid.is_empty(), VanishError::InvalidIpfsCid);
+
+        let attestation = &mut ctx.accounts.pin_attestation;
+        let clock = Clock::get()?;
+
+        attestation.repository = repo.id;
+        attestation.operator = operator;
+        attestation.cid = repo.ipfs_cid;
+        attestation.attested_at = clock.unix_timestamp;
+        attestation.bump = ctx.bumps.pin_attestation;
+
+        emit_event!(
+            ctx,
+            PinAttested {
+                repository: repo.key(),
+                operator,
+                cid: repo.ipfs_cid,
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Start withdrawing a pinner's stake. It can no longer enroll, and the
+    /// stake stays slashable for `Pinner::UNBONDING_PERIOD`.
+    pub fn unbond_pinner(ctx: Context<UnbondPinner>) -> Result<()> {
+        let pinner = &mut ctx.accounts.pinner;
+        require!(pinner.unbonding_at == 0, VanishError::PinnerUnbonding);
+
+        let clock = Clock::get()?;
+        pinner.unbonding_at = clock.unix_timestamp;
+
+        emit_event!(
+            ctx,
+            PinnerUnbonding {
+                operator: pinner.operator,
+                withdrawable_at: clock.unix_timestamp + Pinner::UNBONDING_PERIOD,
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Close an unbonded pinner, returning its stake and rent to the
+    /// operator.
+    pub fn withdraw_pinner_stake(ctx: Context<WithdrawPinnerStake>) -> Result<()> {
+        let pinner = &ctx.accounts.pinner;
+        let clock = Clock::get()?;
+        require!(
+            pinner.unbonding_at != 0
+                && clock.unix_timestamp >= pinner.unbonding_at + Pinner::UNBONDING_PERIOD,
+            VanishError::PinnerBonded
+        );
+
+        emit_event!(
+            ctx,
+            PinnerStakeWithdrawn {
+                operator: pinner.operator,
+                stake: pinner.stake,
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        // Account will be closed automatically via close constraint
+        Ok(())
+    }
+
+    /// Set how many lamports the repository's reward pool pays its pinners
+    /// per `RewardPool::INTERVAL`, creating the pool on first use.
+    pub fn set_reward_rate(ctx: Context<SetRewardRate>, rate: u64) -> Result<()> {
+        require_admin(
+            &ctx.accounts.repository,
+            &ctx.accounts.authority.key(),
+            &ctx.accounts.admin,
+        )?;
+        require_live(&ctx.accounts.repository)?;
+
+        let pool = &mut ctx.accounts.reward_pool;
+        let clock = Clock::get()?;
+
+        if pool.repository == Pubkey::default() {
+            pool.repository = ctx.accounts.repository.id;
+            pool.last_distributed_at = clock.unix_timestamp;
+            pool.bump = ctx.bumps.reward_pool;
+        }
+        pool.rate = rate;
+
+        emit_event!(
+            ctx,
+            RewardRateSet {
+                repository: ctx.accounts.repository.key(),
+                rate,
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Add `amount` lamports to a repository's reward pool. Anyone can.
+    pub fn fund_reward_pool(ctx: Context<FundRewardPool>, amount: u64) -> Result<()> {
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.funder.to_account_info(),
+                    to: ctx.accounts.reward_pool.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        emit_event!(
+            ctx,
+            RewardPoolFunded {
+                repository: ctx.accounts.repository.key(),
+                funder: ctx.accounts.funder.key(),
+                amount,
+                timestamp: Clock::get()?.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Fund a repository's reward pool out of the protocol treasury. Config
+    /// admin only; the treasury keeps its rent-exempt minimum.
+    pub fn fund_reward_pool_from_treasury(
+        ctx: Context<FundRewardPoolFromTreasury>,
+        amount: u64,
+    ) -> Result<()> {
+        let treasury = &ctx.accounts.treasury;
+        let available = treasury
+            .lamports()
+            .saturating_sub(Rent::get()?.minimum_balance(0));
+        require!(amount <= available, VanishError::InsufficientTreasury);
+
+        system_program::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: treasury.to_account_info(),
+                    to: ctx.accounts.reward_pool.to_account_info(),
+                },
+                &[&[b"treasury", &[ctx.accounts.config.treasury_bump]]],
+            ),
+            amount,
+        )?;
+
+        emit_event!(
+            ctx,
+            RewardPoolFunded {
+                repository: ctx.accounts.repository.key(),
+                funder: treasury.key(),
+                amount,
+                timestamp: Clock::get()?.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Pay the pool's rate for every interval since the last distribution,
+    /// as far as its balance goes, split evenly between the repository's
+    /// bonded pinners. Anyone can crank it. Remaining accounts are a
+    /// `[pinner, pin_attestation, operator]` triple for each of
+    /// `Repository::pinners`, in order; pinners that are unbonding or have
+    /// withdrawn, or have not attested the current archive within the last
+    /// `RewardPool::INTERVAL`, get no share.
+    pub fn distribute_rewards<'info>(
+        ctx: Context<'_, '_, '_, 'info, DistributeRewards<'info>>,
+    ) -> Result<()> {
+        let repo = &ctx.accounts.repository;
+        let clock = Clock::get()?;
+
+        let intervals = (clock.unix_timestamp - ctx.accounts.reward_pool.last_distributed_at)
+            / RewardPool::INTERVAL;
+        require!(intervals > 0, VanishError::RewardsNotDue);
+        require!(
+            ctx.remaining_accounts.len() == repo.pinners.len() * 3,
+            VanishError::InvalidRewardAccounts
+        );
+
+        let mut recipients = Vec::with_capacity(repo.pinners.len());
+        for (triple, operator) in ctx.remaining_accounts.chunks(3).zip(&repo.pinners) {
+            let [pinner, attestation, recipient] = triple else {
+                return err!(VanishError::InvalidRewardAccounts);
+            };
+            require_keys_eq!(
+                recipient.key(),
+                *operator,
+                VanishError::InvalidRewardAccounts
+            );
+            require_keys_eq!(
+                pinner.key(),
+                Pubkey::find_program_address(&[b"pinner", operator.as_ref()], &crate::ID).0,
+                VanishError::InvalidRewardAccounts
+            );
+            require_keys_eq!(
+                attestation.key(),
+                Pubkey::find_program_address(
+                    &[b"pin", repo.id.as_ref(), operator.as_ref()],
+                    &crate::ID
+                )
+                .0,
+                VanishError::InvalidRewardAccounts
+            );
+            if pinner.data_is_empty() || attestation.data_is_empty() {
+                continue;
+            }
+            let pinner = Pinner::try_deserialize(&mut &pinner.try_borrow_data()?[..])?;
+            let attestation =
+                PinAttestation::try_deserialize(&mut &attestation.try_borrow_data()?[..])?;
+            if pinner.unbonding_at == 0
+                && attestation.cid == repo.ipfs_cid
+                && attestation.attested_at > clock.unix_timestamp - RewardPool::INTERVAL
+            {
+                recipients.push(recipient);
+            }
+        }
+
+        let pool = &mut ctx.accounts.reward_pool;
+        let available = pool
+            .to_account_info()
+            .lamports()
+            .saturating_sub(Rent::get()?.minimum_balance(RewardPool::SPACE));
+        let due = pool.rate.saturating_mul(intervals as u64).min(available);
+        let share = match recipients.len() {
+            0 => 0,
+            count => due / count as u64,
+        };
+
+        if share > 0 {
+            for recipient in &recipients {
+                **pool.to_account_info().try_borrow_mut_lamports()? -= share;
+                **recipient.try_borrow_mut_lamports()? += share;
+            }
+        }
+
+        let paid = share * recipients.len() as u64;
+        pool.last_distributed_at += intervals * RewardPool::INTERVAL;
+        pool.distributed = pool.distributed.saturating_add(paid);
+
+        emit_event!(
+            ctx,
+            RewardsDistributed {
+                repository: repo.key(),
+                amount: paid,
+                pinners: recipients.len() as u8,
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Let a collaborator without push access push the branches matching
+    /// `patterns`: exact names, or prefixes ending in `*` such as `docs/*`.
+    /// Replaces any patterns granted before.
+    pub fn set_branch_grant(ctx: Context<SetBranchGrant>, patterns: Vec<String>) -> Result<()> {
+        require_admin(
+            &ctx.accounts.repository,
+            &ctx.accounts.authority.key(),
+            &ctx.accounts.admin,
+        )?;
+        require_live(&ctx.accounts.repository)?;
+        require!(
+            !patterns.is_empty()
+                && patterns.len() <= BranchGrant::MAX_PATTERNS
+                && patterns
+                    .iter()
+                    .all(|pattern| BranchGrant::is_valid_pattern(pattern)),
+            VanishError::InvalidBranchPattern
+        );
+
+        let grant = &mut ctx.accounts.branch_grant;
+        grant.repository = ctx.accounts.repository.id;
+        grant.user = ctx.accounts.collaborator_account.user;
+        grant.patterns = patterns;
+        grant.bump = ctx.bumps.branch_grant;
+
+        emit_event!(
+            ctx,
+            BranchGrantSet {
+                repository: ctx.accounts.repository.key(),
+                collaborator: grant.user,
+                patterns: grant.patterns.clone(),
+                timestamp: Clock::get()?.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Take a collaborator's branch grant away
+    pub fn remove_branch_grant(ctx: Context<RemoveBranchGrant>) -> Result<()> {
+        require_admin(
+            &ctx.accounts.repository,
+            &ctx.accounts.authority.key(),
+            &ctx.accounts.admin,
+        )?;
+
+        emit_event!(
+            ctx,
+            BranchGrantRemoved {
+                repository: ctx.accounts.repository.key(),
+                collaborator: ctx.accounts.branch_grant.user,
+                timestamp: Clock::get()?.unix_timestamp,
+            }
+        );
+
+        // Account will be closed automatically via close constraint
+        Ok(())
+    }
+
+    /// Allow or forbid force pushes to a branch. The repository head is
+    /// governed by `repo_flags::ALLOW_FORCE_PUSH` instead.
+    pub fn set_branch_force_push(ctx: Context<SetBranchForcePush>, allow: bool) -> Result<()> {
+        require_admin(
+            &ctx.accounts.repository,
+            &ctx.accounts.authority.key(),
+            &ctx.accounts.admin,
+        )?;
+
+        let branch = &mut ctx.accounts.branch;
+        let clock = Clock::get()?;
+
+        branch.allow_force_push = allow;
+
+        emit_event!(
+            ctx,
+            BranchForcePushUpdated {
+                repository: ctx.accounts.repository.key(),
+                branch: branch.name.clone(),
+                allow_force_push: allow,
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Register a webhook for the events in `events` (`webhook_events`
+    /// bits), or change the secret or events of an existing one. Only
+    /// hashes go on chain: dispatchers are configured with the URL and
+    /// secret themselves and deliver to a URL only if both hash to what the
+    /// webhook holds. `secret_commitment` is `Webhook::commitment`, which
+    /// binds the secret to this repository, so copying another webhook's
+    /// hashes gets no deliveries.
+    pub fn set_webhook(
+        ctx: Context<SetWebhook>,
+        url_hash: [u8; 32],
+        secret_commitment: [u8; 32],
+        events: u32,
+    ) -> Result<()> {
+        require_admin(
+            &ctx.accounts.repository,
+            &ctx.accounts.authority.key(),
+            &ctx.accounts.admin,
+        )?;
+        require_live(&ctx.accounts.repository)?;
+        require!(
+            events != 0 && events & !webhook_events::ALL == 0,
+            VanishError::InvalidWebhookEvents
+        );
+
+        let webhook = &mut ctx.accounts.webhook;
+        webhook.repository = ctx.accounts.repository.id;
+        webhook.url_hash = url_hash;
+        webhook.secret_commitment = secret_commitment;
+        webhook.events = events;
+        webhook.bump = ctx.bumps.webhook;
+
+        emit_event!(
+            ctx,
+            WebhookSet {
+                repository: ctx.accounts.repository.key(),
+                url_hash,
+                events,
+                timestamp: Clock::get()?.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Remove a webhook, stopping deliveries to its URL and returning its
+    /// rent to the authority.
+    pub fn remove_webhook(ctx: Context<RemoveWebhook>) -> Result<()> {
+        require_admin(
+            &ctx.accounts.repository,
+            &ctx.accounts.authority.key(),
+            &ctx.accounts.admin,
+        )?;
+
+        emit_event!(
+            ctx,
+            WebhookRemoved {
+                repository: ctx.accounts.repository.key(),
+                url_hash: ctx.accounts.webhook.url_hash,
+                timestamp: Clock::get()?.unix_timestamp,
+            }
+        );
+
+        // Account will be closed automatically via close constraint
+        Ok(())
+    }
+
+    /// Set (or clear, with an empty string) the IPNS name clients can follow
+    pub fn set_ipns(ctx: Context<SetIpns>, ipns_name: String) -> Result<()> {
+        require_admin(
+            &ctx.accounts.repository,
+            &ctx.accounts.authority.key(),
+            &ctx.accounts.admin,
+        )?;
+
+        require!(
+            ipns_name.is_empty() || is_valid_ipns_name(&ipns_name),
+            VanishError::InvalidIpnsName
+        );
+
+        let repo = &mut ctx.accounts.repository;
+        let clock = Clock::get()?;
+
+        repo.ipns_name = ipns_name.clone();
+
+        emit_event!(
+            ctx,
+            IpnsUpdated {
+                repository: repo.key(),
+                ipns_name,
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Set (or clear, with an empty CID) the CID of the repository's README,
+    /// pinned on its own so explorers can render it without the archive
+    pub fn set_readme_cid(ctx: Context<SetRepoDocument>, readme_cid: Cid) -> Result<()> {
+        require_admin(
+            &ctx.accounts.repository,
+            &ctx.accounts.authority.key(),
+            &ctx.accounts.admin,
+        )?;
+        require!(readme_cid.is_valid(), VanishError::InvalidIpfsCid);
+
+        let repo = &mut ctx.accounts.repository;
+        let clock = Clock::get()?;
+
+        repo.readme_cid = readme_cid;
+
+        emit_event!(
+            ctx,
+            ReadmeUpdated {
+                repository: repo.key(),
+                readme_cid,
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Set (or clear, with an empty CID) the CID of the repository's
+    /// structured metadata, such as its languages and links
+    pub fn set_metadata_cid(ctx: Context<SetRepoDocument>, metadata_cid: Cid) -> Result<()> {
+        require_admin(
+            &ctx.accounts.repository,
+            &ctx.accounts.authority.key(),
+            &ctx.accounts.admin,
+        )?;
+        require!(metadata_cid.is_valid(), VanishError::InvalidIpfsCid);
+
+        let repo = &mut ctx.accounts.repository;
+        let clock = Clock::get()?;
+
+        repo.metadata_cid = metadata_cid;
+
+        emit_event!(
+            ctx,
+            MetadataUpdated {
+                repository: repo.key(),
+                metadata_cid,
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Grow a repository account created under an older, smaller layout to
+    /// the current `Repository::SPACE`. Fields are only ever appended, so the
+    /// old data stays in place; the authority pays the extra rent.
+    pub fn resize_repository(ctx: Context<ResizeRepository>) -> Result<()> {
+        require_admin(
+            &ctx.accounts.repository,
+            &ctx.accounts.authority.key(),
+            &ctx.accounts.admin,
+        )?;
+
+        let repo = &ctx.accounts.repository;
+        let clock = Clock::get()?;
+
+        emit_event!(
+            ctx,
+            RepositoryResized {
+                repository: repo.key(),
+                space: Repository::SPACE as u32,
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        // Account was grown automatically via realloc constraint
+        Ok(())
+    }
+
+    /// Upgrade a Repository, Collaborator, Star, Branch, Profile or PushLog created
+    /// under an older layout to the current one. Repositories and
+    /// collaborators from the first deployment are read field by field and
+    /// rewritten; later layouts only gain zeroed fields. Migration carries
+    /// every field over and grants nothing, so anyone may pay for it.
+    pub fn migrate_account(ctx: Context<MigrateAccount>) -> Result<()> {
+        let account = ctx.accounts.account.to_account_info();
+        let payer = ctx.accounts.payer.to_account_info();
+        let system_program = ctx.accounts.system_program.to_account_info();
+        let clock = Clock::get()?;
+
+        let discriminator = account
+            .try_borrow_data()?
+            .get(..8)
+            .map(<[u8]>::to_vec)
+            .ok_or(VanishError::UnsupportedAccount)?;
+
+        let version = if discriminator == Repository::DISCRIMINATOR {
+            migrate_repository(&account, &payer, &system_program)?
+        } else if discriminator == Collaborator::DISCRIMINATOR
+            && account.data_len() == LegacyCollaborator::SPACE
+        {
+            let legacy = LegacyCollaborator::deserialize(&mut &account.try_borrow_data()?[8..])?;
+            rewrite_legacy(
+                &account,
+                &payer,
+                &system_program,
+                Collaborator::SPACE,
+                &legacy.upgrade(),
+            )?;
+            Collaborator::VERSION
+        } else if discriminator == Collaborator::DISCRIMINATOR {
+            migrate_layout(
+                &account,
+                &payer,
+                &system_program,
+                Collaborator::SPACE,
+                Collaborator::VERSION,
+                |collab: &mut Collaborator| &mut collab.version,
+            )?
+        } else if discriminator == Star::DISCRIMINATOR {
+            migrate_layout(
+                &account,
+                &payer,
+                &system_program,
+                Star::SPACE,
+                Star::VERSION,
+                |star: &mut Star| &mut star.version,
+            )?
+        } else if discriminator == Branch::DISCRIMINATOR {
+            migrate_layout(
+                &account,
+                &payer,
+                &system_program,
+                Branch::SPACE,
+                Branch::VERSION,
+                |branch: &mut Branch| &mut branch.version,
+            )?
+        } else if discriminator == Profile::DISCRIMINATOR {
+            migrate_layout(
+                &account,
+                &payer,
+                &system_program,
+                Profile::SPACE,
+                Profile::VERSION,
+                |profile: &mut Profile| &mut profile.version,
+            )?
+        } else if discriminator == PushLog::DISCRIMINATOR {
+            migrate_push_log(&account, &payer, &system_program)?
+        } else {
+            return err!(VanishError::UnsupportedAccount);
+        };
+
+        emit_event!(
+            ctx,
+            AccountMigrated {
+                account: account.key(),
+                version,
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Add a collaborator to a repository. Private repositories also require
+    /// the repository key sealed to the collaborator, stored as an AccessKey.
+    pub fn add_collaborator(
+        ctx: Context<AddCollaborator>,
+        collaborator: Pubkey,
+        can_push: bool,
+        key_envelope: Option<KeyEnvelope>,
+    ) -> Result<()> {
+        require_admin(
+            &ctx.accounts.repository,
+            &ctx.accounts.authority.key(),
+            &ctx.accounts.admin,
+        )?;
+
+        let is_private = ctx.accounts.repository.is_private;
+        let key_epoch = ctx.accounts.repository.key_epoch;
+        let collab = &mut ctx.accounts.collaborator_account;
+        let clock = Clock::get()?;
+
+        record_audit(
+            &mut ctx.accounts.repository,
+            &mut ctx.accounts.audit_entry,
+            ctx.bumps.audit_entry,
+            ctx.accounts.authority.key(),
+            AuditAction::CollaboratorAdded,
+            collaborator,
+            clock.unix_timestamp,
+        );
+
+        collab.repository = ctx.accounts.repository.id;
+        collab.user = collaborator;
+        collab.can_push = can_push;
+        collab.is_admin = false;
+        collab.added_at = clock.unix_timestamp;
+        collab.last_push_at = 0;
+        collab.bump = ctx.bumps.collaborator_account;
+        collab.version = Collaborator::VERSION;
+
+        let repo = &mut ctx.accounts.repository;
+        let index = repo.collaborator_count;
+        let slot = &mut ctx.accounts.collaborator_slot;
+        slot.repository = repo.id;
+        slot.index = index;
+        slot.user = collaborator;
+        slot.bump = ctx.bumps.collaborator_slot;
+        collab.index = Some(index);
+        repo.collaborator_count = index
+            .checked_add(1)
+            .ok_or(VanishError::TooManyCollaborators)?;
+
+        match (&mut ctx.accounts.access_key, key_envelope) {
+            (Some(access_key), Some(envelope)) if is_private => {
+                access_key.repository = ctx.accounts.repository.id;
+                access_key.user = collaborator;
+                access_key.envelope = envelope;
+                access_key.key_epoch = key_epoch;
+                access_key.bump = ctx.bumps.access_key;
+            }
+            (None, None) if !is_private => {}
+            _ => return err!(VanishError::InvalidAccessKey),
+        }
+
+        emit_event!(
+            ctx,
+            CollaboratorAdded {
+                repository: ctx.accounts.repository.key(),
+                collaborator,
+                can_push,
+                index,
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        let rent = Rent::get()?;
+        let mut cost = rent.minimum_balance(Collaborator::SPACE)
+            + rent.minimum_balance(CollaboratorSlot::SPACE)
+            + rent.minimum_balance(AuditEntry::SPACE);
+        if ctx.accounts.access_key.is_some() {
+            cost += rent.minimum_balance(AccessKey::SPACE);
+        }
+        let funded = fund_rent(
+            &mut ctx.accounts.rent_vault,
+            &ctx.accounts.project_repo,
+            &ctx.accounts.authority,
+            cost,
+            |vault| vault.collaborator_cap,
+        )?;
+        if funded > 0 {
+            emit_event!(
+                ctx,
+                RentFunded {
+                    project: ctx.accounts.project_repo.as_ref().unwrap().project,
+                    repository: ctx.accounts.repository.key(),
+                    payer: ctx.accounts.authority.key(),
+                    amount: funded,
+                    timestamp: clock.unix_timestamp,
+                }
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Start a new key epoch for a private repository. Existing AccessKeys
+    /// become stale until re-issued, so removed collaborators cannot read
+    /// anything encrypted under the new key.
+    pub fn rotate_repo_key(ctx: Context<RotateRepoKey>) -> Result<()> {
+        require_admin(
+            &ctx.accounts.repository,
+            &ctx.accounts.authority.key(),
+            &ctx.accounts.admin,
+        )?;
+
+        let repo = &mut ctx.accounts.repository;
+        let clock = Clock::get()?;
+
+        require!(repo.is_private, VanishError::RepoNotPrivate);
+
+        repo.key_epoch = repo
+            .key_epoch
+            .checked_add(1)
+            .ok_or(VanishError::KeyEpochOverflow)?;
+
+        emit_event!(
+            ctx,
+            RepoKeyRotated {
+                repository: repo.key(),
+                key_epoch: repo.key_epoch,
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Replace a collaborator's or reader's AccessKey with the current
+    /// epoch's key
+    pub fn reissue_access_key(ctx: Context<ReissueAccessKey>, envelope: KeyEnvelope) -> Result<()> {
+        require_admin(
+            &ctx.accounts.repository,
+            &ctx.accounts.authority.key(),
+            &ctx.accounts.admin,
+        )?;
+
+        let clock = Clock::get()?;
+        let can_read = ctx.accounts.collaborator_account.is_some()
+            || ctx
+                .accounts
+                .read_grant
+                .as_ref()
+                .is_some_and(|grant| grant.is_active(clock.unix_timestamp));
+        require!(can_read, VanishError::NoReadAccess);
+
+        let key_epoch = ctx.accounts.repository.key_epoch;
+        let access_key = &mut ctx.accounts.access_key;
+
+        access_key.envelope = envelope;
+        access_key.key_epoch = key_epoch;
+
+        emit_event!(
+            ctx,
+            AccessKeyReissued {
+                repository: access_key.repository,
+                user: access_key.user,
+                key_epoch,
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Grant or revoke a collaborator's admin role
+    pub fn set_collaborator_admin(
+        ctx: Context<SetCollaboratorAdmin>,
+        is_admin: bool,
+    ) -> Result<()> {
+        require_admin(
+            &ctx.accounts.repository,
+            &ctx.accounts.authority.key(),
+            &ctx.accounts.admin,
+        )?;
+
+        let repo = &mut ctx.accounts.repository;
+        let collab = &mut ctx.accounts.collaborator_account;
+        let clock = Clock::get()?;
+
+        // Recorded even when nothing changes, as the entry was created anyway
+        record_audit(
+            repo,
+            &mut ctx.accounts.audit_entry,
+            ctx.bumps.audit_entry,
+            ctx.accounts.authority.key(),
+            if is_admin {
+                AuditAction::CollaboratorPromoted
+            } else {
+                AuditAction::CollaboratorDemoted
+            },
+            collab.user,
+            clock.unix_timestamp,
+        );
+
+        if collab.is_admin == is_admin {
+            return Ok(());
+        }
+
+        if is_admin {
+            repo.admin_count = repo.admin_count.saturating_add(1);
+        } else {
+            require!(
+                repo.owner != Pubkey::default() || repo.admin_count > 1,
+                VanishError::LastAdmin
+            );
+            repo.admin_count = repo.admin_count.saturating_sub(1);
+        }
+        collab.is_admin = is_admin;
+
+        emit_event!(
+            ctx,
+            CollaboratorAdminChanged {
+                repository: repo.key(),
+                collaborator: collab.user,
+                is_admin,
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Remove a collaborator from a repository
+    pub fn remove_collaborator(ctx: Context<RemoveCollaborator>) -> Result<()> {
+        require_admin(
+            &ctx.accounts.repository,
+            &ctx.accounts.authority.key(),
+            &ctx.accounts.admin,
+        )?;
+
+        // A private repository's AccessKey must be closed alongside the collaborator
+        require!(
+            ctx.accounts.access_key.is_some() || !ctx.accounts.repository.is_private,
+            VanishError::InvalidAccessKey
+        );
+
+        let collaborator = &ctx.accounts.collaborator_account;
+        let repo = &mut ctx.accounts.repository;
+        let clock = Clock::get()?;
+
+        // Free the collaborator's slot by moving the last slot's collaborator
+        // into it, then closing the last slot
+        let mut moved = None;
+        match (collaborator.index, &ctx.accounts.last_slot) {
+            (None, None) => {}
+            (Some(index), Some(last_slot)) => {
+                require!(
+                    last_slot.index.checked_add(1) == Some(repo.collaborator_count),
+                    VanishError::InvalidCollaboratorSlot
+                );
+                match (
+                    &mut ctx.accounts.collaborator_slot,
+                    &mut ctx.accounts.moved_collaborator,
+                ) {
+                    (None, None) if index == last_slot.index => {}
+                    (Some(slot), Some(moved_collaborator))
+                        if index != last_slot.index
+                            && slot.index == index
+                            && moved_collaborator.user == last_slot.user =>
+                    {
+                        slot.user = moved_collaborator.user;
+                        moved_collaborator.index = Some(index);
+                        moved = Some(moved_collaborator.user);
+                    }
+                    _ => return err!(VanishError::InvalidCollaboratorSlot),
+                }
+                repo.collaborator_count = last_slot.index;
+            }
+            _ => return err!(VanishError::InvalidCollaboratorSlot),
+        }
+
+        if collaborator.is_admin {
+            require!(
+                repo.owner != Pubkey::default() || repo.admin_count > 1,
+                VanishError::LastAdmin
+            );
+            repo.admin_count = repo.admin_count.saturating_sub(1);
+        }
+
+        record_audit(
+            repo,
+            &mut ctx.accounts.audit_entry,
+            ctx.bumps.audit_entry,
+            ctx.accounts.authority.key(),
+            AuditAction::CollaboratorRemoved,
+            collaborator.user,
+            clock.unix_timestamp,
+        );
+
+        emit_event!(
+            ctx,
+            CollaboratorRemoved {
+                repository: repo.key(),
+                collaborator: collaborator.user,
+                index: collaborator.index,
+                timestamp: clock.unix_timestamp,
+            }
+        );
+        if let (Some(collaborator), Some(index)) = (moved, collaborator.index) {
+            emit_event!(
+                ctx,
+                CollaboratorIndexed {
+                    repository: ctx.accounts.repository.key(),
+                    collaborator,
+                    index,
+                    timestamp: clock.unix_timestamp,
+                }
+            );
+        }
+
+        // Accounts will be closed automatically via close constraint
+        Ok(())
+    }
+
+    /// Give a collaborator added before index slots existed the next slot.
+    /// Anyone may pay for it.
+    pub fn index_collaborator(ctx: Context<IndexCollaborator>) -> Result<()> {
+        let repo = &mut ctx.accounts.repository;
+        let collaborator = &mut ctx.accounts.collaborator_account;
+        let clock = Clock::get()?;
+
+        require!(
+            collaborator.index.is_none(),
+            VanishError::CollaboratorIndexed
+        );
+
+        let index = repo.collaborator_count;
+        let slot = &mut ctx.accounts.collaborator_slot;
+        slot.repository = repo.id;
+        slot.index = index;
+        slot.user = collaborator.user;
+        slot.bump = ctx.bumps.collaborator_slot;
+        collaborator.index = Some(index);
+        repo.collaborator_count = index
+            .checked_add(1)
+            .ok_or(VanishError::TooManyCollaborators)?;
+
+        emit_event!(
+            ctx,
+            CollaboratorIndexed {
+                repository: repo.key(),
+                collaborator: collaborator.user,
+                index,
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Create a project grouping repositories under one membership list
+    pub fn create_project(ctx: Context<CreateProject>, name: String) -> Result<()> {
+        require!(name.len() <= 64, VanishError::NameTooLong);
+        require!(!name.is_empty(), VanishError::NameEmpty);
+
+        let project = &mut ctx.accounts.project;
+        let clock = Clock::get()?;
+
+        project.admin = ctx.accounts.admin.key();
+        project.name = name;
+        project.repo_count = 0;
+        project.member_count = 0;
+        project.push_count = 0;
+        project.created_at = clock.unix_timestamp;
+        project.updated_at = clock.unix_timestamp;
+        project.bump = ctx.bumps.project;
+        project.audit_count = 0;
+
+        emit_event!(
+            ctx,
+            ProjectCreated {
+                project: project.key(),
+                admin: project.admin,
+                name: project.name.clone(),
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Put a repository in a project, with the consent of both the project
+    /// admin and the repository owner. A repository is in one project at most.
+    pub fn add_repo_to_project(ctx: Context<AddRepoToProject>) -> Result<()> {
+        let repo = &ctx.accounts.repository;
+        require_live(repo)?;
+
+        let project = &mut ctx.accounts.project;
+        let clock = Clock::get()?;
+
+        let link = &mut ctx.accounts.project_repo;
+        link.project = project.key();
+        link.repository = repo.id;
+        link.added_at = clock.unix_timestamp;
+        link.bump = ctx.bumps.project_repo;
+        project.repo_count += 1;
+        record_project_audit(
+            project,
+            &mut ctx.accounts.audit_entry,
+            ctx.bumps.audit_entry,
+            ctx.accounts.admin.key(),
+            ProjectAuditAction::RepoAdded,
+            repo.id,
+            clock.unix_timestamp,
+        );
+
+        emit_event!(
+            ctx,
+            ProjectRepoAdded {
+                project: project.key(),
+                repository: repo.key(),
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Take a repository out of its project. Either the project admin or the
+    /// repository owner can; the admin can also clear links of repositories
+    /// that have since been purged.
+    pub fn remove_repo_from_project(ctx: Context<RemoveRepoFromProject>) -> Result<()> {
+        let authority = ctx.accounts.authority.key();
+        let project = &mut ctx.accounts.project;
+
+        if authority != project.admin {
+            let repo = ctx
+                .accounts
+                .repository
+                .as_ref()
+                .ok_or(VanishError::Unauthorized)?;
+            require!(
+                repo.id == ctx.accounts.project_repo.repository && repo.owner == authority,
+                VanishError::Unauthorized
+            );
+        }
+
+        let clock = Clock::get()?;
+        project.repo_count = project.repo_count.saturating_sub(1);
+        record_project_audit(
+            project,
+            &mut ctx.accounts.audit_entry,
+            ctx.bumps.audit_entry,
+            authority,
+            ProjectAuditAction::RepoRemoved,
+            ctx.accounts.project_repo.repository,
+            clock.unix_timestamp,
+        );
+
+        emit_event!(
+            ctx,
+            ProjectRepoRemoved {
+                project: project.key(),
+                repository: ctx.accounts.project_repo.repository,
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        // Account will be closed automatically via close constraint
+        Ok(())
+    }
+
+    /// Add a member to a project. Members with `can_push` may push_update
+    /// every repository in the project without a Collaborator account.
+    pub fn add_project_member(
+        ctx: Context<AddProjectMember>,
+        user: Pubkey,
+        can_push: bool,
+    ) -> Result<()> {
+        let project = &mut ctx.accounts.project;
+        let clock = Clock::get()?;
+
+        let member = &mut ctx.accounts.project_member;
+        member.project = project.key();
+        member.user = user;
+        member.can_push = can_push;
+        member.added_at = clock.unix_timestamp;
+        member.bump = ctx.bumps.project_member;
+        project.member_count += 1;
+        record_project_audit(
+            project,
+            &mut ctx.accounts.audit_entry,
+            ctx.bumps.audit_entry,
+            ctx.accounts.admin.key(),
+            ProjectAuditAction::MemberAdded,
+            user,
+            clock.unix_timestamp,
+        );
+
+        emit_event!(
+            ctx,
+            ProjectMemberAdded {
+                project: project.key(),
+                user,
+                can_push,
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    pub fn remove_project_member(ctx: Context<RemoveProjectMember>) -> Result<()> {
+        let project = &mut ctx.accounts.project;
+        let user = ctx.accounts.project_member.user;
+        let clock = Clock::get()?;
+
+        project.member_count = project.member_count.saturating_sub(1);
+        record_project_audit(
+            project,
+            &mut ctx.accounts.audit_entry,
+            ctx.bumps.audit_entry,
+            ctx.accounts.admin.key(),
+            ProjectAuditAction::MemberRemoved,
+            user,
+            clock.unix_timestamp,
+        );
+
+        emit_event!(
+            ctx,
+            ProjectMemberRemoved {
+                project: project.key(),
+                user,
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        // Account will be closed automatically via close constraint
+        Ok(())
+    }
+
+    /// Give a project member push access to the project's repositories, or
+    /// take it away.
+    pub fn set_project_member_role(
+        ctx: Context<SetProjectMemberRole>,
+        can_push: bool,
+    ) -> Result<()> {
+        let project = &mut ctx.accounts.project;
+        let member = &mut ctx.accounts.project_member;
+        let clock = Clock::get()?;
+
+        member.can_push = can_push;
+        record_project_audit(
+            project,
+            &mut ctx.accounts.audit_entry,
+            ctx.bumps.audit_entry,
+            ctx.accounts.admin.key(),
+            ProjectAuditAction::RoleChanged,
+            member.user,
+            clock.unix_timestamp,
+        );
+
+        emit_event!(
+            ctx,
+            ProjectMemberRoleChanged {
+                project: project.key(),
+                user: member.user,
+                can_push,
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Open the project's RentVault, or change its caps. The vault covers
+    /// the rent of accounts created for the project's repositories, up to
+    /// `collaborator_cap` lamports per add_collaborator and `branch_cap` per
+    /// new branch; 0 leaves that action to its payer.
+    pub fn set_rent_vault(
+        ctx: Context<SetRentVault>,
+        collaborator_cap: u64,
+        branch_cap: u64,
+    ) -> Result<()> {
+        let vault = &mut ctx.accounts.rent_vault;
+        vault.project = ctx.accounts.project.key();
+        vault.collaborator_cap = collaborator_cap;
+        vault.branch_cap = branch_cap;
+        vault.bump = ctx.bumps.rent_vault;
+
+        emit_event!(
+            ctx,
+            RentVaultUpdated {
+                project: vault.project,
+                collaborator_cap,
+                branch_cap,
+                timestamp: Clock::get()?.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Add lamports to a project's RentVault. Anyone can deposit.
+    pub fn deposit_rent_vault(ctx: Context<DepositRentVault>, amount: u64) -> Result<()> {
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.depositor.to_account_info(),
+                    to: ctx.accounts.rent_vault.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        emit_event!(
+            ctx,
+            RentVaultDeposited {
+                project: ctx.accounts.rent_vault.project,
+                depositor: ctx.accounts.depositor.key(),
+                amount,
+                timestamp: Clock::get()?.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Move lamports out of the project's RentVault. Project admin only; the
+    /// vault keeps its own rent.
+    pub fn withdraw_rent_vault(ctx: Context<WithdrawRentVault>, amount: u64) -> Result<()> {
+        let info = ctx.accounts.rent_vault.to_account_info();
+        let available = info
+            .lamports()
+            .saturating_sub(Rent::get()?.minimum_balance(RentVault::SPACE));
+        require!(amount <= available, VanishError::InsufficientRentVault);
+
+        **info.try_borrow_mut_lamports()? -= amount;
+        **ctx.accounts.recipient.try_borrow_mut_lamports()? += amount;
+
+        emit_event!(
+            ctx,
+            RentVaultWithdrawn {
+                project: ctx.accounts.project.key(),
+                recipient: ctx.accounts.recipient.key(),
+                amount,
+                timestamp: Clock::get()?.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Start a named list of repositories, curated by its owner. Private
+    /// lists are still readable on chain; the flag asks clients and indexers
+    /// not to show them to anyone else.
+    pub fn create_list(
+        ctx: Context<CreateList>,
+        name: String,
+        description: String,
+        is_private: bool,
+    ) -> Result<()> {
+        require!(
+            !name.is_empty() && name.len() <= List::MAX_NAME_LEN,
+            VanishError::InvalidListName
+        );
+        require!(
+            description.len() <= List::MAX_DESCRIPTION_LEN,
+            VanishError::DescriptionTooLong
+        );
+
+        let list = &mut ctx.accounts.list;
+        let clock = Clock::get()?;
+
+        list.owner = ctx.accounts.owner.key();
+        list.name = name;
+        list.description = description;
+        list.is_private = is_private;
+        list.entry_count = 0;
+        list.created_at = clock.unix_timestamp;
+        list.updated_at = clock.unix_timestamp;
+        list.bump = ctx.bumps.list;
+
+        emit_event!(
+            ctx,
+            ListCreated {
+                list: list.key(),
+                owner: list.owner,
+                name: list.name.clone(),
+                is_private,
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    pub fn update_list(
+        ctx: Context<UpdateList>,
+        description: String,
+        is_private: bool,
+    ) -> Result<()> {
+        require!(
+            description.len() <= List::MAX_DESCRIPTION_LEN,
+            VanishError::DescriptionTooLong
+        );
+
+        let list = &mut ctx.accounts.list;
+        let clock = Clock::get()?;
+
+        list.description = description;
+        list.is_private = is_private;
+        list.updated_at = clock.unix_timestamp;
+
+        emit_event!(
+            ctx,
+            ListUpdated {
+                list: list.key(),
+                is_private,
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Close an empty list, refunding its owner.
+    pub fn delete_list(ctx: Context<DeleteList>) -> Result<()> {
+        require!(
+            ctx.accounts.list.entry_count == 0,
+            VanishError::ListNotEmpty
+        );
+
+        emit_event!(
+            ctx,
+            ListDeleted {
+                list: ctx.accounts.list.key(),
+                timestamp: Clock::get()?.unix_timestamp,
+            }
+        );
+
+        // Account will be closed automatically via close constraint
+        Ok(())
+    }
+
+    /// Put a repository on one of the owner's lists. Entries hold the
+    /// repository's `id`, so they survive renames.
+    pub fn add_to_list(ctx: Context<AddToList>) -> Result<()> {
+        let repo = &ctx.accounts.repository;
+        require_live(repo)?;
+
+        let list = &mut ctx.accounts.list;
+        let clock = Clock::get()?;
+
+        let entry = &mut ctx.accounts.list_entry;
+        entry.list = list.key();
+        entry.repository = repo.id;
+        entry.added_at = clock.unix_timestamp;
+        entry.bump = ctx.bumps.list_entry;
+        list.entry_count += 1;
+        list.updated_at = clock.unix_timestamp;
+
+        emit_event!(
+            ctx,
+            ListEntryAdded {
+                list: list.key(),
+                repository: repo.key(),
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Take a repository off a list. Works for repositories that have since
+    /// been purged, as the entry is found by the id it holds.
+    pub fn remove_from_list(ctx: Context<RemoveFromList>) -> Result<()> {
+        let list = &mut ctx.accounts.list;
+        let clock = Clock::get()?;
+
+        list.entry_count = list.entry_count.saturating_sub(1);
+        list.updated_at = clock.unix_timestamp;
+
+        emit_event!(
+            ctx,
+            ListEntryRemoved {
+                list: list.key(),
+                repository: ctx.accounts.list_entry.repository,
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        // Account will be closed automatically via close constraint
+        Ok(())
+    }
+
+    /// Record that the user has seen activity up to `slot`, so any of their
+    /// clients can ask an indexer for what happened since. The cursor only
+    /// moves forward, and not past the current slot.
+    pub fn mark_activity_seen(ctx: Context<MarkActivitySeen>, slot: u64) -> Result<()> {
+        let cursor = &mut ctx.accounts.activity_cursor;
+        let clock = Clock::get()?;
+        require!(
+            slot >= cursor.slot && slot <= clock.slot,
+            VanishError::InvalidActivitySlot
+        );
+
+        cursor.user = ctx.accounts.user.key();
+        cursor.slot = slot;
+        cursor.seen_at = clock.unix_timestamp;
+        cursor.bump = ctx.bumps.activity_cursor;
+
+        emit_event!(
+            ctx,
+            ActivitySeen {
+                user: cursor.user,
+                slot,
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Let `reader` read the repository without making them a collaborator,
+    /// e.g. an auditor or a client, until `expires_at` (0 for no expiry).
+    /// Private repositories seal their key to the reader, as for
+    /// collaborators.
+    pub fn grant_read(
+        ctx: Context<GrantRead>,
+        reader: Pubkey,
+        expires_at: i64,
+        key_envelope: Option<KeyEnvelope>,
+    ) -> Result<()> {
+        require_admin(
+            &ctx.accounts.repository,
+            &ctx.accounts.authority.key(),
+            &ctx.accounts.admin,
+        )?;
+
+        let clock = Clock::get()?;
+        require!(
+            expires_at == 0 || expires_at > clock.unix_timestamp,
+            VanishError::InvalidExpiry
+        );
+
+        let is_private = ctx.accounts.repository.is_private;
+        let key_epoch = ctx.accounts.repository.key_epoch;
+
+        record_audit(
+            &mut ctx.accounts.repository,
+            &mut ctx.accounts.audit_entry,
+            ctx.bumps.audit_entry,
+            ctx.accounts.authority.key(),
+            AuditAction::ReadGranted,
+            reader,
+            clock.unix_timestamp,
+        );
+
+        let grant = &mut ctx.accounts.read_grant;
+        grant.repository = ctx.accounts.repository.id;
+        grant.reader = reader;
+        grant.granted_by = ctx.accounts.authority.key();
+        grant.granted_at = clock.unix_timestamp;
+        grant.expires_at = expires_at;
+        grant.bump = ctx.bumps.read_grant;
+
+        match (&mut ctx.accounts.access_key, key_envelope) {
+            (Some(access_key), Some(envelope)) if is_private => {
+                access_key.repository = ctx.accounts.repository.id;
+                access_key.user = reader;
+                access_key.envelope = envelope;
+                access_key.key_epoch = key_epoch;
+                access_key.bump = ctx.bumps.access_key;
+            }
+            (None, None) if !is_private => {}
+            _ => return err!(VanishError::InvalidAccessKey),
+        }
+
+        emit_event!(
+            ctx,
+            ReadGranted {
+                repository: ctx.accounts.repository.key(),
+                reader,
+                expires_at,
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Withdraw a read grant, closing the reader's AccessKey with it
+    pub fn revoke_read(ctx: Context<RevokeRead>) -> Result<()> {
+        require_admin(
+            &ctx.accounts.repository,
+            &ctx.accounts.authority.key(),
+            &ctx.accounts.admin,
+        )?;
+
+        // A private repository's AccessKey must be closed alongside the grant
+        require!(
+            ctx.accounts.access_key.is_some() || !ctx.accounts.repository.is_private,
+            VanishError::InvalidAccessKey
+        );
+
+        let reader = ctx.accounts.read_grant.reader;
+        let repo = &mut ctx.accounts.repository;
+        let clock = Clock::get()?;
+
+        record_audit(
+            repo,
+            &mut ctx.accounts.audit_entry,
+            ctx.bumps.audit_entry,
+            ctx.accounts.authority.key(),
+            AuditAction::ReadRevoked,
+            reader,
+            clock.unix_timestamp,
+        );
+
+        emit_event!(
+            ctx,
+            ReadRevoked {
+                repository: repo.key(),
+                reader,
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        // Accounts will be closed automatically via close constraint
+        Ok(())
+    }
+
+    /// Offer paid read access to a private repository, or change the offer.
+    /// `mint` selects an SPL token to be paid in; `None` means lamports.
+    pub fn set_access_pricing(
+        ctx: Context<SetAccessPricing>,
+        mint: Option<Pubkey>,
+        price: u64,
+        duration: i64,
+    ) -> Result<()> {
+        require_admin(
+            &ctx.accounts.repository,
+            &ctx.accounts.authority.key(),
+            &ctx.accounts.admin,
+        )?;
+
+        require!(
+            ctx.accounts.repository.is_private,
+            VanishError::RepoNotPrivate
+        );
+        require!(price > 0 && duration > 0, VanishError::InvalidPricing);
+
+        let pricing = &mut ctx.accounts.pricing;
+        pricing.repository = ctx.accounts.repository.id;
+        pricing.mint = mint;
+        pricing.price = price;
+        pricing.duration = duration;
+        pricing.bump = ctx.bumps.pricing;
+
+        emit_event!(
+            ctx,
+            AccessPricingUpdated {
+                repository: pricing.repository,
+                mint,
+                price,
+                duration,
+                timestamp: Clock::get()?.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Stop selling read access. Existing grants stay valid until they expire.
+    pub fn remove_access_pricing(ctx: Context<RemoveAccessPricing>) -> Result<()> {
+        require_admin(
+            &ctx.accounts.repository,
+            &ctx.accounts.authority.key(),
+            &ctx.accounts.admin,
+        )?;
+
+        emit_event!(
+            ctx,
+            AccessPricingRemoved {
+                repository: ctx.accounts.repository.key(),
+                timestamp: Clock::get()?.unix_timestamp,
+            }
+        );
+
+        // Account will be closed automatically via close constraint
+        Ok(())
+    }
+
+    /// Pay the repository owner for one access period. Buying again while a
+    /// grant is still active extends it rather than restarting it.
+    ///
+    /// Token payments go through either token program. A Token-2022 mint's
+    /// transfer fee is added on top of the price so the owner still receives
+    /// all of it, and a transfer hook's extra accounts are passed as
+    /// remaining accounts.
+    pub fn purchase_access<'info>(
+        ctx: Context<'_, '_, '_, 'info, PurchaseAccess<'info>>,
+    ) -> Result<()> {
+        require_live(&ctx.accounts.repository)?;
+
+        let pricing = &ctx.accounts.pricing;
+        let clock = Clock::get()?;
+        let mut fee = 0;
+
+        // Ownerless repositories have nobody to pay
+        require!(
+            ctx.accounts.owner.key() != Pubkey::default(),
+            VanishError::InvalidPaymentAccount
+        );
+
+        match pricing.mint {
+            None => {
+                system_program::transfer(
+                    CpiContext::new(
+                        ctx.accounts.system_program.to_account_info(),
+                        system_program::Transfer {
+                            from: ctx.accounts.buyer.to_account_info(),
+                            to: ctx.accounts.owner.to_account_info(),
+                        },
+                    ),
+                    pricing.price,
+                )?;
+            }
+            Some(mint) => {
+                let (Some(from), Some(to), Some(payment_mint), Some(token_program)) = (
+                    &ctx.accounts.buyer_token_account,
+                    &ctx.accounts.owner_token_account,
+                    &ctx.accounts.payment_mint,
+                    &ctx.accounts.token_program,
+                ) else {
+                    return err!(VanishError::InvalidPaymentAccount);
+                };
+                require!(
+                    payment_mint.key() == mint
+                        && to.owner == ctx.accounts.owner.key()
+                        && to.mint == mint
+                        && from.mint == mint,
+                    VanishError::InvalidPaymentAccount
+                );
+
+                fee = inverse_transfer_fee(&payment_mint.to_account_info(), pricing.price)?;
+                spl_token_2022::onchain::invoke_transfer_checked(
+                    token_program.key,
+                    from.to_account_info(),
+                    payment_mint.to_account_info(),
+                    to.to_account_info(),
+                    ctx.accounts.buyer.to_account_info(),
+                    ctx.remaining_accounts,
+                    pricing
+                        .price
+                        .checked_add(fee)
+                        .ok_or(VanishError::InvalidPricing)?,
+                    payment_mint.decimals,
+                    &[],
+                )?;
+            }
+        }
+
+        let grant = &mut ctx.accounts.grant;
+        let starts_at = grant.expires_at.max(clock.unix_timestamp);
+
+        grant.repository = ctx.accounts.repository.id;
+        grant.user = ctx.accounts.buyer.key();
+        grant.expires_at = starts_at
+            .checked_add(pricing.duration)
+            .ok_or(VanishError::InvalidPricing)?;
+        grant.bump = ctx.bumps.grant;
+
+        emit_event!(
+            ctx,
+            AccessPurchased {
+                repository: grant.repository,
+                user: grant.user,
+                mint: pricing.mint,
+                amount: pricing.price,
+                fee,
+                expires_at: grant.expires_at,
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Configure contribution badges: a non-transferable Token-2022 mint,
+    /// whose mint authority is the BadgeConfig PDA, that pushes mint one
+    /// token from. Pushers opt in by passing the badge accounts to push_update.
+    pub fn configure_badges(ctx: Context<ConfigureBadges>, enabled: bool) -> Result<()> {
+        require_admin(
+            &ctx.accounts.repository,
+            &ctx.accounts.authority.key(),
+            &ctx.accounts.admin,
+        )?;
+
+        let config_key = ctx.accounts.badge_config.key();
+        require!(
+            is_badge_mint(&ctx.accounts.badge_mint.to_account_info(), &config_key)?,
+            VanishError::InvalidBadgeMint
+        );
+
+        let config = &mut ctx.accounts.badge_config;
+        config.repository = ctx.accounts.repository.id;
+        config.mint = ctx.accounts.badge_mint.key();
+        config.enabled = enabled;
+        config.bump = ctx.bumps.badge_config;
+
+        emit_event!(
+            ctx,
+            BadgeConfigUpdated {
+                repository: config.repository,
+                mint: config.mint,
+                enabled,
+                timestamp: Clock::get()?.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Create the repository's push log. Once it exists, pushers pass it to
+    /// push_update to have each push recorded.
+    pub fn init_push_log(ctx: Context<InitPushLog>) -> Result<()> {
+        require_admin(
+            &ctx.accounts.repository,
+            &ctx.accounts.authority.key(),
+            &ctx.accounts.admin,
+        )?;
+
+        let mut push_log = ctx.accounts.push_log.load_init()?;
+        push_log.repository = ctx.accounts.repository.id;
+        push_log.bump = ctx.bumps.push_log;
+
+        Ok(())
+    }
+
+    /// Allow `attestor` (a CI system's key) to post check runs
+    pub fn add_ci_attestor(ctx: Context<AddCiAttestor>, attestor: Pubkey) -> Result<()> {
+        require_admin(
+            &ctx.accounts.repository,
+            &ctx.accounts.authority.key(),
+            &ctx.accounts.admin,
+        )?;
+
+        let ci_attestor = &mut ctx.accounts.ci_attestor;
+        let clock = Clock::get()?;
+
+        ci_attestor.repository = ctx.accounts.repository.id;
+        ci_attestor.attestor = attestor;
+        ci_attestor.added_at = clock.unix_timestamp;
+        ci_attestor.bump = ctx.bumps.ci_attestor;
+
+        emit_event!(
+            ctx,
+            CiAttestorAdded {
+                repository: ctx.accounts.repository.key(),
+                attestor,
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Stop accepting check runs from an attestor. Runs it already posted stay.
+    pub fn remove_ci_attestor(ctx: Context<RemoveCiAttestor>) -> Result<()> {
+        require_admin(
+            &ctx.accounts.repository,
+            &ctx.accounts.authority.key(),
+            &ctx.accounts.admin,
+        )?;
+
+        emit_event!(
+            ctx,
+            CiAttestorRemoved {
+                repository: ctx.accounts.repository.key(),
+                attestor: ctx.accounts.ci_attestor.attestor,
+                timestamp: Clock::get()?.unix_timestamp,
+            }
+        );
+
+        // Account will be closed automatically via close constraint
+        Ok(())
+    }
+
+    /// Post (or update, on a re-run) the attestor's result for a commit
+    pub fn post_check_run(
+        ctx: Context<PostCheckRun>,
+        commit: Multihash,
+        status: CheckStatus,
+        details_cid: String,
+    ) -> Result<()> {
+        require_live(&ctx.accounts.repository)?;
+
+        require!(commit.is_git_object_id(), VanishError::InvalidCommitHash);
+        require!(details_cid.len() <= 64, VanishError::InvalidIpfsCid);
+
+        let check_run = &mut ctx.accounts.check_run;
+        let clock = Clock::get()?;
+
+        check_run.repository = ctx.accounts.repository.id;
+        check_run.commit = commit;
+        check_run.attestor = ctx.accounts.attestor.key();
+        check_run.status = status;
+        check_run.details_cid = details_cid;
+        check_run.updated_at = clock.unix_timestamp;
+        check_run.bump = ctx.bumps.check_run;
+
+        emit_event!(
+            ctx,
+            CheckRunPosted {
+                repository: check_run.repository,
+                commit: check_run.commit.clone(),
+                attestor: check_run.attestor,
+                status,
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Register the release managers and how many of them must approve a
+    /// release before it is final. Replacing a policy takes `threshold` of
+    /// its managers, signing as remaining accounts, or a SetReleasePolicy
+    /// action queued through the repository's timelock.
+    pub fn set_release_policy<'info>(
+        ctx: Context<'_, '_, '_, 'info, SetReleasePolicy<'info>>,
+        managers: Vec<Pubkey>,
+        threshold: u8,
+    ) -> Result<()> {
+        require_admin(
+            &ctx.accounts.repository,
+            &ctx.accounts.authority.key(),
+            &ctx.accounts.admin,
+        )?;
+
+        let current = &ctx.accounts.release_policy;
+        let signed = current
+            .managers
+            .iter()
+            .filter(|manager| {
+                ctx.remaining_accounts
+                    .iter()
+                    .any(|info| info.is_signer && info.key == *manager)
+            })
+            .count();
+        if signed < current.threshold as usize {
+            let delay = try_load::<Timelock>(&ctx.accounts.timelock)?.map_or(0, |t| t.delay);
+            require!(delay > 0, VanishError::ReleasePolicyLocked);
+            require_queued(
+                delay,
+                &ctx.accounts.pending_action,
+                &TimelockedAction::SetReleasePolicy {
+                    policy: ReleasePolicy::digest(&managers, threshold),
+                },
+            )?;
+        }
+
+        require!(
+            !managers.is_empty() && managers.len() <= ReleasePolicy::MAX_MANAGERS,
+            VanishError::InvalidReleasePolicy
+        );
+        require!(
+            threshold > 0 && threshold as usize <= managers.len(),
+            VanishError::InvalidReleasePolicy
+        );
+        for (i, manager) in managers.iter().enumerate() {
+            require!(
+                !managers[..i].contains(manager),
+                VanishError::InvalidReleasePolicy
+            );
+        }
+
+        let policy = &mut ctx.accounts.release_policy;
+        policy.repository = ctx.accounts.repository.id;
+        policy.managers = managers;
+        policy.threshold = threshold;
+        policy.bump = ctx.bumps.release_policy;
+
+        emit_event!(
+            ctx,
+            ReleasePolicyUpdated {
+                repository: policy.repository,
+                managers: policy.managers.clone(),
+                threshold,
+                timestamp: Clock::get()?.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Propose a release. It stays pending until `threshold` release managers
+    /// have approved it.
+    pub fn create_release(
+        ctx: Context<CreateRelease>,
+        tag: String,
+        commit: Multihash,
+        ipfs_cid: String,
+    ) -> Result<()> {
+        require_live(&ctx.accounts.repository)?;
+
+        require!(
+            !tag.is_empty() && tag.len() <= 32,
+            VanishError::InvalidReleaseTag
+        );
+        require!(commit.is_git_object_id(), VanishError::InvalidCommitHash);
+        require!(ipfs_cid.len() <= 64, VanishError::InvalidIpfsCid);
+
+        let proposer = ctx.accounts.proposer.key();
+        require!(
+            ctx.accounts.release_policy.managers.contains(&proposer),
+            VanishError::NotReleaseManager
+        );
+
+        let release = &mut ctx.accounts.release;
+        let clock = Clock::get()?;
+
+        release.repository = ctx.accounts.repository.id;
+        release.tag = tag;
+        release.commit = commit;
+        release.ipfs_cid = ipfs_cid;
+        release.proposer = proposer;
+        release.approvers = Vec::new();
+        release.is_final = false;
+        release.created_at = clock.unix_timestamp;
+        release.assets = Vec::new();
+        release.finalized_at = 0;
+        release.bump = ctx.bumps.release;
+
+        emit_event!(
+            ctx,
+            ReleaseCreated {
+                repository: release.repository,
+                tag: release.tag.clone(),
+                commit: release.commit.clone(),
+                proposer,
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Attach a downloadable asset to a pending release. Assets are locked
+    /// once the first approval is in, so every approval covers the same set.
+    pub fn add_release_asset(ctx: Context<AddReleaseAsset>, asset: ReleaseAsset) -> Result<()> {
+        require_live(&ctx.accounts.repository)?;
+
+        let manager = ctx.accounts.manager.key();
+        let release = &mut ctx.accounts.release;
+        let clock = Clock::get()?;
+
+        require!(!release.is_final, VanishError::ReleaseAlreadyFinal);
+        require!(release.approvers.is_empty(), VanishError::ReleaseLocked);
+        require!(
+            ctx.accounts.release_policy.managers.contains(&manager),
+            VanishError::NotReleaseManager
+        );
+        require!(
+            !asset.filename.is_empty()
+                && asset.filename.len() <= 64
+                && !asset.ipfs_cid.is_empty()
+                && asset.ipfs_cid.len() <= 64,
+            VanishError::InvalidReleaseAsset
+        );
+        require!(asset.checksum.is_valid(), VanishError::InvalidMultihash);
+        require!(
+            release
+                .assets
+                .iter()
+                .all(|existing| existing.filename != asset.filename),
+            VanishError::InvalidReleaseAsset
+        );
+        require!(
+            release.assets.len() < Release::MAX_ASSETS,
+            VanishError::TooManyReleaseAssets
+        );
+
+        emit_event!(
+            ctx,
+            ReleaseAssetAdded {
+                repository: release.repository,
+                tag: release.tag.clone(),
+                filename: asset.filename.clone(),
+                size: asset.size,
+                ipfs_cid: asset.ipfs_cid.clone(),
+                checksum: asset.checksum.clone(),
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        release.assets.push(asset);
+
+        Ok(())
+    }
+
+    /// Register a build system key allowed to attest release asset provenance
+    pub fn add_builder(ctx: Context<AddBuilder>, builder: Pubkey) -> Result<()> {
+        require_admin(
+            &ctx.accounts.repository,
+            &ctx.accounts.authority.key(),
+            &ctx.accounts.admin,
+        )?;
+
+        let builder_account = &mut ctx.accounts.builder_account;
+        let clock = Clock::get()?;
+
+        builder_account.repository = ctx.accounts.repository.id;
+        builder_account.builder = builder;
+        builder_account.added_at = clock.unix_timestamp;
+        builder_account.bump = ctx.bumps.builder_account;
+
+        emit_event!(
+            ctx,
+            BuilderAdded {
+                repository: ctx.accounts.repository.key(),
+                builder,
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Revoke a builder. Attestations it already made stay on-chain.
+    pub fn remove_builder(ctx: Context<RemoveBuilder>) -> Result<()> {
+        require_admin(
+            &ctx.accounts.repository,
+            &ctx.accounts.authority.key(),
+            &ctx.accounts.admin,
+        )?;
+
+        emit_event!(
+            ctx,
+            BuilderRemoved {
+                repository: ctx.accounts.repository.key(),
+                builder: ctx.accounts.builder_account.builder,
+                timestamp: Clock::get()?.unix_timestamp,
+            }
+        );
+
+        // Account will be closed automatically via close constraint
+        Ok(())
+    }
+
+    /// Bind a release asset, by checksum, to the provenance document of the
+    /// build that produced it
+    pub fn attest_build(
+        ctx: Context<AttestBuild>,
+        checksum: Multihash,
+        provenance_cid: String,
+    ) -> Result<()> {
+        require_live(&ctx.accounts.repository)?;
+
+        require!(
+            !provenance_cid.is_empty() && provenance_cid.len() <= 64,
+            VanishError::InvalidIpfsCid
+        );
+
+        let release = &ctx.accounts.release;
+        require!(
+            release
+                .assets
+                .iter()
+                .any(|asset| asset.checksum == checksum),
+            VanishError::UnknownReleaseAsset
+        );
+
+        let attestation = &mut ctx.accounts.attestation;
+        let clock = Clock::get()?;
+
+        attestation.release = release.key();
+        attestation.checksum = checksum;
+        attestation.builder = ctx.accounts.builder.key();
+        attestation.provenance_cid = provenance_cid;
+        attestation.attested_at = clock.unix_timestamp;
+        attestation.bump = ctx.bumps.attestation;
+
+        emit_event!(
+            ctx,
+            BuildAttested {
+                repository: release.repository,
+                tag: release.tag.clone(),
+                checksum: attestation.checksum.clone(),
+                builder: attestation.builder,
+                provenance_cid: attestation.provenance_cid.clone(),
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Approve a pending release as one of the repository's release managers.
+    /// Only approvals from current managers count, and the one that reaches
+    /// the policy threshold finalizes the release.
+    pub fn approve_release(ctx: Context<ApproveRelease>) -> Result<()> {
+        require_live(&ctx.accounts.repository)?;
+
+        let manager = ctx.accounts.manager.key();
+        let policy = &ctx.accounts.release_policy;
+        let release = &mut ctx.accounts.release;
+        let clock = Clock::get()?;
+
+        require!(!release.is_final, VanishError::ReleaseAlreadyFinal);
+        require!(
+            policy.managers.contains(&manager),
+            VanishError::NotReleaseManager
+        );
+
+        let approval = &mut ctx.accounts.approval;
+        approval.release = release.key();
+        approval.manager = manager;
+        approval.approved_at = clock.unix_timestamp;
+        approval.bump = ctx.bumps.approval;
+
+        // Managers removed since they approved no longer count
+        release
+            .approvers
+            .retain(|approver| policy.managers.contains(approver));
+        release.approvers.push(manager);
+        let approvals = release.approvers.len() as u8;
+
+        emit_event!(
+            ctx,
+            ReleaseApproved {
+                repository: release.repository,
+                tag: release.tag.clone(),
+                manager,
+                approvals,
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        if approvals >= policy.threshold {
+            release.is_final = true;
+            release.finalized_at = clock.unix_timestamp;
+
+            emit_event!(
+                ctx,
+                ReleaseFinalized {
+                    repository: release.repository,
+                    tag: release.tag.clone(),
+                    commit: release.commit.clone(),
+                    ipfs_cid: release.ipfs_cid.clone(),
+                    timestamp: clock.unix_timestamp,
+                }
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Register a package name for a repository. Names are global and first
+    /// come, first served.
+    pub fn create_package(ctx: Context<CreatePackage>, name: String) -> Result<()> {
+        require_admin(
+            &ctx.accounts.repository,
+            &ctx.accounts.authority.key(),
+            &ctx.accounts.admin,
+        )?;
+
+        require!(
+            is_valid_package_name(&name),
+            VanishError::InvalidPackageName
+        );
+
+        let package = &mut ctx.accounts.package;
+        let clock = Clock::get()?;
+
+        package.name = name;
+        package.repository = ctx.accounts.repository.id;
+        package.latest = None;
+        package.version_count = 0;
+        package.created_at = clock.unix_timestamp;
+        package.bump = ctx.bumps.package;
+
+        emit_event!(
+            ctx,
+            PackageCreated {
+                package: package.key(),
+                name: package.name.clone(),
+                repository: package.repository,
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Publish a package version. Versions must be strictly greater than the
+    /// latest one, so a published version can never be replaced.
+    pub fn publish_version(
+        ctx: Context<PublishVersion>,
+        version: SemVer,
+        ipfs_cid: String,
+    ) -> Result<()> {
+        require_admin(
+            &ctx.accounts.repository,
+            &ctx.accounts.authority.key(),
+            &ctx.accounts.admin,
+        )?;
+
+        require!(
+            !ipfs_cid.is_empty() && ipfs_cid.len() <= 64,
+            VanishError::InvalidIpfsCid
+        );
+
+        let package = &mut ctx.accounts.package;
+        let clock = Clock::get()?;
+
+        require!(
+            package.latest < Some(version),
+            VanishError::VersionNotIncreasing
+        );
+
+        package.latest = Some(version);
+        package.version_count = package.version_count.saturating_add(1);
+
+        let package_version = &mut ctx.accounts.package_version;
+        package_version.package = package.key();
+        package_version.version = version;
+        package_version.ipfs_cid = ipfs_cid;
+        package_version.published_at = clock.unix_timestamp;
+        package_version.bump = ctx.bumps.package_version;
+
+        emit_event!(
+            ctx,
+            VersionPublished {
+                package: package_version.package,
+                name: package.name.clone(),
+                version,
+                ipfs_cid: package_version.ipfs_cid.clone(),
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Star a repository, sending `tip` lamports (0 for none) to its
+    /// donation vault along with the star
+    pub fn star_repo(ctx: Context<StarRepo>, tip: u64) -> Result<()> {
+        require_live(&ctx.accounts.repository)?;
+        let profile = &mut ctx.accounts.profile;
+        init_profile(profile, ctx.accounts.user.key(), ctx.bumps.profile);
+        let config = &ctx.accounts.config;
+        record_action(profile, config, config.max_stars_per_window, |profile| {
+            &mut profile.window_stars
+        })?;
+
+        if tip > 0 {
+            let vault = &ctx.accounts.donation_vault;
+            require!(
+                vault.lamports().saturating_add(tip) >= Rent::get()?.minimum_balance(0),
+                VanishError::TipBelowRent
+            );
+
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.user.to_account_info(),
+                        to: vault.to_account_info(),
+                    },
+                ),
+                tip,
+            )?;
+        }
+
+        let star = &mut ctx.accounts.star_account;
+        let repo = &mut ctx.accounts.repository;
+        let clock = Clock::get()?;
+
+        star.user = ctx.accounts.user.key();
+        star.repository = repo.id;
+        star.starred_at = clock.unix_timestamp;
+        star.bump = ctx.bumps.star_account;
+        star.version = Star::VERSION;
+
+        repo.stars = repo.stars.checked_add(1).unwrap_or(repo.stars);
+        repo.decay_stars(clock.unix_timestamp);
+        repo.weighted_stars = repo.weighted_stars.saturating_add(Repository::STAR_WEIGHT);
+
+        emit_event!(
+            ctx,
+            RepoStarred {
+                user: star.user,
+                repository: star.repository,
+                tip,
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Move tips out of the repository's donation vault to the owner. The
+    /// vault keeps its rent-exempt minimum.
+    pub fn withdraw_donations(ctx: Context<WithdrawDonations>, amount: u64) -> Result<()> {
+        let vault = &ctx.accounts.donation_vault;
+        let available = vault
+            .lamports()
+            .saturating_sub(Rent::get()?.minimum_balance(0));
+        require!(amount <= available, VanishError::InsufficientDonations);
+
+        let repository_id = ctx.accounts.repository.id;
+        system_program::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: vault.to_account_info(),
+                    to: ctx.accounts.owner.to_account_info(),
+                },
+                &[&[
+                    b"donations",
+                    repository_id.as_ref(),
+                    &[ctx.bumps.donation_vault],
+                ]],
+            ),
+            amount,
+        )?;
+
+        emit_event!(
+            ctx,
+            DonationsWithdrawn {
+                repository: ctx.accounts.repository.key(),
+                amount,
+                timestamp: Clock::get()?.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Unstar a repository, taking what is left of the star's weight off
+    /// the trending score
+    pub fn unstar_repo(ctx: Context<UnstarRepo>) -> Result<()> {
+        let repo = &mut ctx.accounts.repository;
+        let star = &ctx.accounts.star_account;
+        let clock = Clock::get()?;
+
+        repo.stars = repo.stars.saturating_sub(1);
+        repo.decay_stars(clock.unix_timestamp);
+        repo.weighted_stars = repo.weighted_stars.saturating_sub(Repository::decayed(
+            Repository::STAR_WEIGHT,
+            clock.unix_timestamp - star.starred_at,
+        ));
+
+        emit_event!(
+            ctx,
+            RepoUnstarred {
+                user: star.user,
+                repository: star.repository,
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        // Star account will be closed automatically
+        Ok(())
+    }
+
+    /// Bring `weighted_stars` up to date with the time since it last moved,
+    /// so trending scores of repositories starred at different times compare
+    /// fairly. Anyone may call it.
+    pub fn decay_stars(ctx: Context<DecayStars>) -> Result<()> {
+        let repo = &mut ctx.accounts.repository;
+        let clock = Clock::get()?;
+
+        repo.decay_stars(clock.unix_timestamp);
+
+        emit_event!(
+            ctx,
+            StarsDecayed {
+                repository: repo.key(),
+                weighted_stars: repo.weighted_stars,
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Recount `stars` from the repository's Star accounts, passed as
+    /// remaining accounts in ascending address order, plus `compressed` stars
+    /// in the star tree. Anyone may call it, but without the config admin's
+    /// signature the counter only goes up and `compressed` must be 0: leaving
+    /// accounts out could otherwise pull it down, and the tree cannot be
+    /// counted per repository on-chain.
+    pub fn reconcile_stars<'info>(
+        ctx: Context<'_, '_, '_, 'info, ReconcileStars<'info>>,
+        compressed: u64,
+    ) -> Result<()> {
+        let is_admin = match &ctx.accounts.admin {
+            Some(admin) => {
+                require_keys_eq!(
+                    admin.key(),
+                    ctx.accounts.config.admin,
+                    VanishError::Unauthorized
+                );
+                true
+            }
+            None => false,
+        };
+        require!(is_admin || compressed == 0, VanishError::Unauthorized);
+
+        let repo = &mut ctx.accounts.repository;
+        let mut previous_key = None;
+        for info in ctx.remaining_accounts {
+            require!(
+                previous_key < Some(info.key()),
+                VanishError::InvalidStarAccounts
+            );
+            previous_key = Some(info.key());
+
+            // Every Star layout starts with the discriminator, user and
+            // repository, so older ones count without migrating first
+            require_keys_eq!(*info.owner, crate::ID, VanishError::InvalidStarAccounts);
+            let data = info.try_borrow_data()?;
+            require!(
+                data.get(..8) == Some(&Star::DISCRIMINATOR[..])
+                    && data.get(40..72) == Some(repo.id.as_ref()),
+                VanishError::InvalidStarAccounts
+            );
+        }
+
+        let counted = (ctx.remaining_accounts.len() as u64).saturating_add(compressed);
+        if counted == repo.stars || (counted < repo.stars && !is_admin) {
+            return Ok(());
+        }
+
+        let previous = repo.stars;
+        repo.stars = counted;
+
+        emit_event!(
+            ctx,
+            StarsReconciled {
+                repository: repo.key(),
+                previous,
+                stars: counted,
+                timestamp: Clock::get()?.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Set up the shared concurrent merkle tree compressed stars are recorded
+    /// in. The caller allocates `merkle_tree` for the compression program
+    /// beforehand; the StarTree PDA becomes its authority.
+    pub fn init_star_tree(
+        ctx: Context<InitStarTree>,
+        max_depth: u32,
+        max_buffer_size: u32,
+    ) -> Result<()> {
+        let star_tree = &mut ctx.accounts.star_tree;
+        star_tree.merkle_tree = ctx.accounts.merkle_tree.key();
+        star_tree.bump = ctx.bumps.star_tree;
+
+        let mut args = max_depth.to_le_bytes().to_vec();
+        args.extend_from_slice(&max_buffer_size.to_le_bytes());
+        compression::invoke(
+            "init_empty_merkle_tree",
+            &args,
+            &ctx.accounts.compression_program,
+            &ctx.accounts.merkle_tree,
+            star_tree,
+            &ctx.accounts.noop_program,
+            &[],
+        )
+    }
+
+    /// Star a repository with a leaf in the star tree instead of a Star
+    /// account, so starring costs no rent beyond the user's Profile. The
+    /// tree cannot tell that a user already starred, so compressed stars
+    /// leave `stars` and `weighted_stars` alone: indexers count distinct
+    /// leaves, and the config admin folds them in with reconcile_stars.
+    /// Counted against the same rate limit as star_repo.
+    pub fn star_repo_compressed(ctx: Context<CompressedStar>) -> Result<()> {
+        require_live(&ctx.accounts.repository)?;
+        let profile = &mut ctx.accounts.profile;
+        init_profile(profile, ctx.accounts.user.key(), ctx.bumps.profile);
+        let config = &ctx.accounts.config;
+        record_action(profile, config, config.max_stars_per_window, |profile| {
+            &mut profile.window_stars
+        })?;
+
+        let user = ctx.accounts.user.key();
+        let repo = &ctx.accounts.repository;
+        let clock = Clock::get()?;
+
+        compression::invoke(
+            "append",
+            &compression::star_leaf(&user, &repo.id),
+            &ctx.accounts.compression_program,
+            &ctx.accounts.merkle_tree,
+            &ctx.accounts.star_tree,
+            &ctx.accounts.noop_program,
+            &[],
+        )?;
+
+        emit_event!(
+            ctx,
+            RepoStarred {
+                user,
+                repository: repo.id,
+                tip: 0,
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Remove the user's compressed star at leaf `index`. The proof nodes are
+    /// passed as remaining accounts and checked against `root` by the
+    /// compression program. Like starring, leaves `stars` alone.
+    pub fn unstar_repo_compressed<'info>(
+        ctx: Context<'_, '_, '_, 'info, CompressedStar<'info>>,
+        root: [u8; 32],
+        index: u32,
+    ) -> Result<()> {
+        let user = ctx.accounts.user.key();
+        let repo = &ctx.accounts.repository;
+
+        let mut args = root.to_vec();
+        args.extend_from_slice(&compression::star_leaf(&user, &repo.id));
+        args.extend_from_slice(&[0; 32]);
+        args.extend_from_slice(&index.to_le_bytes());
+        compression::invoke(
+            "replace_leaf",
+            &args,
+            &ctx.accounts.compression_program,
+            &ctx.accounts.merkle_tree,
+            &ctx.accounts.star_tree,
+            &ctx.accounts.noop_program,
+            ctx.remaining_accounts,
+        )?;
+
+        emit_event!(
+            ctx,
+            RepoUnstarred {
+                user,
+                repository: repo.id,
+                timestamp: Clock::get()?.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Move the repository to the PDA for `new_name`, closing the old account.
+    /// Accounts hanging off the repository are seeded by its `id`, so they
+    /// carry over. With `leave_redirect`, a Redirect under the old name points
+    /// clients at the new address. The name lock moves too, so a rename that
+    /// only changes case is refused.
+    pub fn rename_repo(
+        ctx: Context<RenameRepo>,
+        new_name: String,
+        leave_redirect: bool,
+    ) -> Result<()> {
+        require_admin(
+            &ctx.accounts.repository,
+            &ctx.accounts.authority.key(),
+            &ctx.accounts.admin,
+        )?;
+
+        require!(new_name.len() <= 64, VanishError::NameTooLong);
+        require!(!new_name.is_empty(), VanishError::NameEmpty);
+        check_repo_name(&new_name, &ctx.accounts.config)?;
+        require_unlocked(
+            &ctx.accounts.timelock,
+            &ctx.accounts.pending_action,
+            &TimelockedAction::Rename {
+                new_name: new_name.clone(),
+            },
+        )?;
+
+        let old = &ctx.accounts.repository;
+        let clock = Clock::get()?;
+
+        let new_repo = &mut ctx.accounts.new_repository;
+        new_repo.set_inner(Repository {
+            name: new_name.clone(),
+            updated_at: clock.unix_timestamp,
+            bump: ctx.bumps.new_repository,
+            version: Repository::VERSION,
+            ..(**old).clone()
+        });
+        let new_address = new_repo.key();
+        leave_address(&mut ctx.accounts.tombstone, old.key(), ctx.bumps.tombstone);
+        let name_lock = &mut ctx.accounts.new_name_lock;
+        name_lock.repository = old.id;
+        name_lock.bump = ctx.bumps.new_name_lock;
+        record_audit(
+            new_repo,
+            &mut ctx.accounts.audit_entry,
+            ctx.bumps.audit_entry,
+            ctx.accounts.authority.key(),
+            AuditAction::Renamed,
+            new_address,
+            clock.unix_timestamp,
+        );
+
+        match (&mut ctx.accounts.redirect, leave_redirect) {
+            (Some(redirect), true) => {
+                redirect.creator = old.creator;
+                redirect.target = new_repo.key();
+                redirect.name = old.name.clone();
+                redirect.created_at = clock.unix_timestamp;
+                redirect.bump = ctx.bumps.redirect;
+            }
+            (None, false) => {}
+            _ => return err!(VanishError::InvalidRedirect),
+        }
+
+        emit_event!(
+            ctx,
+            RepoRenamed {
+                id: old.id,
+                old_address: old.key(),
+                new_address: new_repo.key(),
+                old_name: old.name.clone(),
+                new_name,
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        // Old repository account will be closed automatically via close constraint
+        Ok(())
+    }
+
+    /// List the repository in the global directory under its name, so clients
+    /// can find it knowing only the name. Entries are first come, first
+    /// served, and resolve through `creator` and `name` to the repository's
+    /// address, or to its Redirect once it has been renamed.
+    pub fn claim_repo_name(ctx: Context<ClaimRepoName>) -> Result<()> {
+        require_admin(
+            &ctx.accounts.repository,
+            &ctx.accounts.authority.key(),
+            &ctx.accounts.admin,
+        )?;
+
+        let repo = &ctx.accounts.repository;
+        let repo_name = &mut ctx.accounts.repo_name;
+        let clock = Clock::get()?;
+
+        repo_name.name = repo.name.clone();
+        repo_name.creator = repo.creator;
+        repo_name.repository = repo.id;
+        repo_name.claimer = ctx.accounts.authority.key();
+        repo_name.claimed_at = clock.unix_timestamp;
+        repo_name.bump = ctx.bumps.repo_name;
+
+        emit_event!(
+            ctx,
+            RepoNameClaimed {
+                repo_name: repo_name.key(),
+                name: repo_name.name.clone(),
+                repository: repo.key(),
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Remove a directory entry, refunding its claimer. The repository's
+    /// admins can release it while it lives at the entry's address; once it
+    /// has been renamed or purged anyone can, so stale names return to the
+    /// pool.
+    pub fn release_repo_name(ctx: Context<ReleaseRepoName>) -> Result<()> {
+        let repo_name = &ctx.accounts.repo_name;
+        let info = ctx.accounts.repository.to_account_info();
+
+        if *info.owner == crate::ID && !info.data_is_empty() {
+            let repo = Repository::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+            if repo.id == repo_name.repository {
+                require_admin(&repo, &ctx.accounts.authority.key(), &ctx.accounts.admin)?;
+            }
+        }
+
+        emit_event!(
+            ctx,
+            RepoNameReleased {
+                repo_name: repo_name.key(),
+                name: repo_name.name.clone(),
+                timestamp: Clock::get()?.unix_timestamp,
+            }
+        );
+
+        // Account will be closed automatically via close constraint
+        Ok(())
+    }
+
+    /// Set the delay timelocked actions (transfer_ownership, wrap_repo_to_nft,
+    /// rename_repo and delete_repo) wait for after being queued. Raising the
+    /// delay takes effect at once; lowering it, or removing it with 0, is
+    /// itself a timelocked action.
+    pub fn set_timelock(ctx: Context<SetTimelock>, delay: i64) -> Result<()> {
+        require_live(&ctx.accounts.repository)?;
+        require!(
+            (0..=Timelock::MAX_DELAY).contains(&delay),
+            VanishError::InvalidTimelockDelay
+        );
+
+        let timelock = &mut ctx.accounts.timelock;
+        if delay < timelock.delay {
+            require_queued(
+                timelock.delay,
+                &ctx.accounts.pending_action,
+                &TimelockedAction::SetDelay { delay },
+            )?;
+        }
+
+        timelock.repository = ctx.accounts.repository.id;
+        timelock.delay = delay;
+        timelock.bump = ctx.bumps.timelock;
+
+        emit_event!(
+            ctx,
+            TimelockSet {
+                repository: ctx.accounts.repository.key(),
+                delay,
+                timestamp: Clock::get()?.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Queue a timelocked action. It can be carried out by its instruction
+    /// once the delay has passed, and cancelled by the owner until then. One
+    /// action can be queued at a time.
+    pub fn queue_action(ctx: Context<QueueAction>, action: TimelockedAction) -> Result<()> {
+        require_live(&ctx.accounts.repository)?;
+
+        let timelock = &ctx.accounts.timelock;
+        require!(timelock.delay > 0, VanishError::NoTimelock);
+        match &action {
+            TimelockedAction::TransferOwnership { new_owner } => {
+                require!(*new_owner != Pubkey::default(), VanishError::Unauthorized)
+            }
+            TimelockedAction::Rename { new_name } => {
+                require!(new_name.len() <= 64, VanishError::NameTooLong);
+                require!(!new_name.is_empty(), VanishError::NameEmpty);
+                require!(is_valid_repo_name(new_name), VanishError::InvalidRepoName);
+            }
+            TimelockedAction::SetDelay { delay } => require!(
+                (0..timelock.delay).contains(delay),
+                VanishError::InvalidTimelockDelay
+            ),
+            TimelockedAction::WrapToNft { .. }
+            | TimelockedAction::Delete
+            | TimelockedAction::SetReleasePolicy { .. } => {}
+        }
+
+        let pending = &mut ctx.accounts.pending_action;
+        let clock = Clock::get()?;
+
+        pending.repository = ctx.accounts.repository.id;
+        pending.action = action;
+        pending.queued_at = clock.unix_timestamp;
+        pending.executable_at = clock.unix_timestamp + timelock.delay;
+        pending.bump = ctx.bumps.pending_action;
+
+        emit_event!(
+            ctx,
+            ActionQueued {
+                repository: ctx.accounts.repository.key(),
+                action: pending.action.clone(),
+                executable_at: pending.executable_at,
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Drop the queued action before it is carried out
+    pub fn cancel_action(ctx: Context<CancelAction>) -> Result<()> {
+        emit_event!(
+            ctx,
+            ActionCancelled {
+                repository: ctx.accounts.repository.key(),
+                action: ctx.accounts.pending_action.action.clone(),
+                timestamp: Clock::get()?.unix_timestamp,
+            }
+        );
+
+        // Account will be closed automatically via close constraint
+        Ok(())
+    }
+
+    /// Transfer repository ownership
+    pub fn transfer_ownership(ctx: Context<TransferOwnership>, new_owner: Pubkey) -> Result<()> {
+        require_live(&ctx.accounts.repository)?;
+
+        // Giving up ownership goes through renounce_ownership, which checks
+        // that someone is left to administer the repository
+        require!(new_owner != Pubkey::default(), VanishError::Unauthorized);
+        require_unlocked(
+            &ctx.accounts.timelock,
+            &ctx.accounts.pending_action,
+            &TimelockedAction::TransferOwnership { new_owner },
+        )?;
+
+        let repo = &mut ctx.accounts.repository;
+        let old_owner = repo.owner;
+
+        repo.owner = new_owner;
+        record_audit(
+            repo,
+            &mut ctx.accounts.audit_entry,
+            ctx.bumps.audit_entry,
+            old_owner,
+            AuditAction::OwnershipTransferred,
+            new_owner,
+            Clock::get()?.unix_timestamp,
+        );
+
+        emit_event!(
+            ctx,
+            OwnershipTransferred {
+                repository: ctx.accounts.repository.key(),
+                old_owner,
+                new_owner,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Renounce ownership for good, leaving administration to the admin
+    /// collaborators. At least one admin must exist.
+    pub fn renounce_ownership(ctx: Context<RenounceOwnership>) -> Result<()> {
+        require_live(&ctx.accounts.repository)?;
+
+        let repo = &mut ctx.accounts.repository;
+        let clock = Clock::get()?;
+
+        require!(repo.admin_count > 0, VanishError::LastAdmin);
+
+        let old_owner = repo.owner;
+        repo.owner = Pubkey::default();
+        record_audit(
+            repo,
+            &mut ctx.accounts.audit_entry,
+            ctx.bumps.audit_entry,
+            old_owner,
+            AuditAction::OwnershipTransferred,
+            Pubkey::default(),
+            clock.unix_timestamp,
+        );
+
+        emit_event!(
+            ctx,
+            OwnershipRenounced {
+                repository: repo.key(),
+                old_owner,
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Hand control of the repository to whoever holds `mint`, a fixed-supply
+    /// NFT the owner currently holds. While wrapped the owner is the RepoNft
+    /// PDA, so nobody can act as owner until the holder unwraps.
+    pub fn wrap_repo_to_nft(ctx: Context<WrapRepoToNft>) -> Result<()> {
+        require_live(&ctx.accounts.repository)?;
+
+        let mint = &ctx.accounts.mint;
+        require!(
+            mint.decimals == 0 && mint.supply == 1 && mint.mint_authority.is_none(),
+            VanishError::InvalidNftMint
+        );
+        require_unlocked(
+            &ctx.accounts.timelock,
+            &ctx.accounts.pending_action,
+            &TimelockedAction::WrapToNft { mint: mint.key() },
+        )?;
+
+        let repo_nft = &mut ctx.accounts.repo_nft;
+        repo_nft.repository = ctx.accounts.repository.id;
+        repo_nft.mint = mint.key();
+        repo_nft.bump = ctx.bumps.repo_nft;
+
+        let repo = &mut ctx.accounts.repository;
+        let clock = Clock::get()?;
+
+        repo.owner = repo_nft.key();
+        record_audit(
+            repo,
+            &mut ctx.accounts.audit_entry,
+            ctx.bumps.audit_entry,
+            ctx.accounts.owner.key(),
+            AuditAction::OwnershipTransferred,
+            repo_nft.key(),
+            clock.unix_timestamp,
+        );
+
+        emit_event!(
+            ctx,
+            RepoWrapped {
+                repository: repo.key(),
+                mint: repo_nft.mint,
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Take direct ownership of a wrapped repository by proving you hold its NFT
+    pub fn unwrap_repo_from_nft(ctx: Context<UnwrapRepoFromNft>) -> Result<()> {
+        let holder = ctx.accounts.holder.key();
+        let repo = &mut ctx.accounts.repository;
+        let clock = Clock::get()?;
+
+        repo.owner = holder;
+        record_audit(
+            repo,
+            &mut ctx.accounts.audit_entry,
+            ctx.bumps.audit_entry,
+            holder,
+            AuditAction::OwnershipTransferred,
+            holder,
+            clock.unix_timestamp,
+        );
+
+        emit_event!(
+            ctx,
+            RepoUnwrapped {
+                repository: repo.key(),
+                mint: ctx.accounts.mint.key(),
+                owner: holder,
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        // RepoNft account will be closed automatically via close constraint
+        Ok(())
+    }
+
+    /// Delete a repository. It is only marked deleted: writes are blocked,
+    /// and after `recovery_window` seconds it can be purged; until then
+    /// restore_repo brings it back.
+    pub fn delete_repo(ctx: Context<DeleteRepo>, recovery_window: i64) -> Result<()> {
+        require_live(&ctx.accounts.repository)?;
+        require!(
+            recovery_window >= Repository::MIN_RECOVERY_WINDOW,
+            VanishError::RecoveryWindowTooShort
+        );
+        require_unlocked(
+            &ctx.accounts.timelock,
+            &ctx.accounts.pending_action,
+            &TimelockedAction::Delete,
+        )?;
+
+        let repo = &mut ctx.accounts.repository;
+        let clock = Clock::get()?;
+
+        repo.deleted_at = clock.unix_timestamp;
+        repo.purgeable_at = clock
+            .unix_timestamp
+            .checked_add(recovery_window)
+            .ok_or(VanishError::RecoveryWindowTooShort)?;
+        let address = repo.key();
+        record_audit(
+            repo,
+            &mut ctx.accounts.audit_entry,
+            ctx.bumps.audit_entry,
+            ctx.accounts.owner.key(),
+            AuditAction::Deleted,
+            address,
+            clock.unix_timestamp,
+        );
+
+        emit_event!(
+            ctx,
+            RepoDeleted {
+                repository: repo.key(),
+                purgeable_at: repo.purgeable_at,
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Undo delete_repo while the repository has not been purged yet
+    pub fn restore_repo(ctx: Context<RestoreRepo>) -> Result<()> {
+        let repo = &mut ctx.accounts.repository;
+        let clock = Clock::get()?;
+
+        require!(repo.deleted_at != 0, VanishError::RepoNotDeleted);
+
+        repo.deleted_at = 0;
+        repo.purgeable_at = 0;
+
+        emit_event!(
+            ctx,
+            RepoRestored {
+                repository: repo.key(),
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Close a deleted repository once its recovery window has passed. Its
+    /// child accounts can be closed along with it by passing them as
+    /// remaining accounts, each followed by the account whose rent it returns
+    /// to (see `child_rent_payer`). Any left open stay with the purged `id`,
+    /// which no later repository takes, until sweep_purged closes them.
+    pub fn purge_repo<'info>(ctx: Context<'_, '_, '_, 'info, PurgeRepo<'info>>) -> Result<()> {
+        let repo = &ctx.accounts.repository;
+        let clock = Clock::get()?;
+
+        require!(repo.deleted_at != 0, VanishError::RepoNotDeleted);
+        require!(
+            clock.unix_timestamp >= repo.purgeable_at,
+            VanishError::RecoveryWindowOpen
+        );
+
+        if let Some(profile) = &mut ctx.accounts.profile {
+            profile.repo_count = profile.repo_count.saturating_sub(1);
+        }
+
+        let owner = ctx.accounts.owner.key();
+        close_children(ctx.remaining_accounts, &repo.id, &owner)?;
+
+        leave_address(&mut ctx.accounts.tombstone, repo.key(), ctx.bumps.tombstone);
+        let receipt = &mut ctx.accounts.receipt;
+        receipt.repository = repo.id;
+        receipt.owner = owner;
+        receipt.purged_at = clock.unix_timestamp;
+        receipt.bump = ctx.bumps.receipt;
+
+        emit_event!(
+            ctx,
+            RepoPurged {
+                repository: repo.key(),
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        // Account will be closed automatically via close constraint
+        Ok(())
+    }
+
+    /// Close child accounts a purged repository left behind, passed as in
+    /// purge_repo. Anyone may sweep, since the rent goes back to whoever
+    /// purge_repo would have paid it to.
+    pub fn sweep_purged<'info>(ctx: Context<'_, '_, '_, 'info, SweepPurged<'info>>) -> Result<()> {
+        let receipt = &ctx.accounts.receipt;
+        let closed = close_children(ctx.remaining_accounts, &receipt.repository, &receipt.owner)?;
+
+        emit_event!(
+            ctx,
+            PurgedChildrenSwept {
+                repository: receipt.repository,
+                closed,
+                timestamp: Clock::get()?.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Return a name stake to its staker once the repository has been in use
+    /// for the config's lock period, measured from creation to its latest
+    /// update. Stakes of purged repositories are refunded by purge_repo.
+    pub fn release_name_stake(ctx: Context<ReleaseNameStake>) -> Result<()> {
+        let repo = &ctx.accounts.repository;
+        require_live(repo)?;
+        require!(
+            repo.updated_at - repo.created_at >= ctx.accounts.config.stake_period,
+            VanishError::StakeLocked
+        );
+
+        let name_stake = &ctx.accounts.name_stake;
+        emit_event!(
+            ctx,
+            NameStakeReleased {
+                repository: repo.key(),
+                staker: name_stake.staker,
+                amount: name_stake.amount,
+                timestamp: Clock::get()?.unix_timestamp,
+            }
+        );
+
+        // Account will be closed automatically via close constraint
+        Ok(())
+    }
+
+    /// Give notice that the claimant wants an abandoned repository, one with
+    /// no push or update for the config's idle period. The config's bond is
+    /// escrowed in the notice, and the owner has the grace period to answer
+    /// before reclaim_repo or reclaim_repo_name can go ahead.
+    pub fn file_reclaim_notice(ctx: Context<FileReclaimNotice>) -> Result<()> {
+        let repo = &ctx.accounts.repository;
+        let config = &ctx.accounts.config;
+        let clock = Clock::get()?;
+
+        require_live(repo)?;
+        require!(config.reclaim_idle_period > 0, VanishError::ReclaimDisabled);
+        require!(
+            clock.unix_timestamp - repo.updated_at >= config.reclaim_idle_period,
+            VanishError::RepoNotAbandoned
+        );
+        let claimant = ctx.accounts.claimant.key();
+        require_keys_neq!(claimant, repo.owner, VanishError::Unauthorized);
+
+        let bond = config.reclaim_bond;
+        if bond > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.claimant.to_account_info(),
+                        to: ctx.accounts.notice.to_account_info(),
+                    },
+                ),
+                bond,
+            )?;
+        }
+
+        let notice = &mut ctx.accounts.notice;
+        notice.repository = repo.id;
+        notice.claimant = claimant;
+        notice.bond = bond;
+        notice.filed_at = clock.unix_timestamp;
+        notice.deadline = clock
+            .unix_timestamp
+            .saturating_add(config.reclaim_grace_period);
+        notice.bump = ctx.bumps.notice;
+
+        emit_event!(
+            ctx,
+            ReclaimNoticeFiled {
+                repository: repo.key(),
+                claimant,
+                bond,
+                deadline: notice.deadline,
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Answer a reclaim notice, closing it and sending its bond to the
+    /// treasury. The repository's admins can answer at any point before the
+    /// claim goes through; a push or update does the same, after which
+    /// anyone can close the notice.
+    pub fn contest_reclaim(ctx: Context<ContestReclaim>) -> Result<()> {
+        let repo = &ctx.accounts.repository;
+        let notice = &ctx.accounts.notice;
+
+        if repo.updated_at < notice.filed_at {
+            require_admin(repo, &ctx.accounts.authority.key(), &ctx.accounts.admin)?;
+        }
+
+        let info = notice.to_account_info();
+        **info.try_borrow_mut_lamports()? -= notice.bond;
+        **ctx.accounts.treasury.try_borrow_mut_lamports()? += notice.bond;
+
+        emit_event!(
+            ctx,
+            ReclaimContested {
+                repository: repo.key(),
+                claimant: notice.claimant,
+                bond: notice.bond,
+                timestamp: Clock::get()?.unix_timestamp,
+            }
+        );
+
+        // Account will be closed automatically via close constraint
+        Ok(())
+    }
+
+    /// Take over a repository whose owner let a reclaim notice lapse. The
+    /// claimant becomes the owner and gets the bond back.
+    pub fn reclaim_repo(ctx: Context<ReclaimRepo>) -> Result<()> {
+        let clock = Clock::get()?;
+        require_reclaimable(
+            &ctx.accounts.repository,
+            &ctx.accounts.notice,
+            clock.unix_timestamp,
+        )?;
+
+        let claimant = ctx.accounts.claimant.key();
+        let repo = &mut ctx.accounts.repository;
+        let old_owner = repo.owner;
+
+        repo.owner = claimant;
+        repo.updated_at = clock.unix_timestamp;
+        record_audit(
+            repo,
+            &mut ctx.accounts.audit_entry,
+            ctx.bumps.audit_entry,
+            claimant,
+            AuditAction::OwnershipTransferred,
+            claimant,
+            clock.unix_timestamp,
+        );
+
+        emit_event!(
+            ctx,
+            RepoReclaimed {
+                repository: repo.key(),
+                claimant,
+                old_owner,
+                timestamp: clock.unix_timestamp,
+            }
+        );
+        emit_event!(
+            ctx,
+            OwnershipTransferred {
+                repository: ctx.accounts.repository.key(),
+                old_owner,
+                new_owner: claimant,
+            }
+        );
+
+        // Notice will be closed automatically via close constraint
+        Ok(())
+    }
+
+    /// Free the directory entry of a repository whose owner let a reclaim
+    /// notice lapse, so the claimant can list a repository of their own
+    /// under the name. The entry's rent goes back to whoever claimed it and
+    /// the bond to the claimant.
+    pub fn reclaim_repo_name(ctx: Context<ReclaimRepoName>) -> Result<()> {
+        let clock = Clock::get()?;
+        require_reclaimable(
+            &ctx.accounts.repository,
+            &ctx.accounts.notice,
+            clock.unix_timestamp,
+        )?;
+
+        let repo_name = &ctx.accounts.repo_name;
+        emit_event!(
+            ctx,
+            RepoNameReleased {
+                repo_name: repo_name.key(),
+                name: repo_name.name.clone(),
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        // Entry and notice will be closed automatically via close constraints
+        Ok(())
+    }
+
+    /// Name a recovery authority that can take the repository over once the
+    /// owner has gone `inactivity_period` seconds without pushing or calling
+    /// recovery_heartbeat. Replaces any earlier plan and counts as a
+    /// heartbeat.
+    pub fn set_recovery_plan(
+        ctx: Context<SetRecoveryPlan>,
+        recovery_authority: Pubkey,
+        inactivity_period: i64,
+    ) -> Result<()> {
+        let repo = &ctx.accounts.repository;
+        require_live(repo)?;
+        require!(
+            inactivity_period > 0
+                && recovery_authority != Pubkey::default()
+                && recovery_authority != repo.owner,
+            VanishError::InvalidRecoveryPlan
+        );
+
+        let now = Clock::get()?.unix_timestamp;
+        let plan = &mut ctx.accounts.recovery_plan;
+        plan.repository = repo.id;
+        plan.owner = repo.owner;
+        plan.recovery_authority = recovery_authority;
+        plan.inactivity_period = inactivity_period;
+        plan.last_heartbeat = now;
+        plan.bump = ctx.bumps.recovery_plan;
+
+        emit_event!(
+            ctx,
+            RecoveryPlanSet {
+                repository: repo.key(),
+                recovery_authority,
+                inactivity_period,
+                timestamp: now,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Show the owner is still around, restarting the recovery plan's
+    /// inactivity period.
+    pub fn recovery_heartbeat(ctx: Context<RecoveryHeartbeat>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        ctx.accounts.recovery_plan.last_heartbeat = now;
+
+        emit_event!(
+            ctx,
+            OwnerHeartbeat {
+                repository: ctx.accounts.repository.key(),
+                timestamp: now,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Drop the recovery plan, returning its rent to the owner.
+    pub fn cancel_recovery_plan(ctx: Context<CancelRecoveryPlan>) -> Result<()> {
+        emit_event!(
+            ctx,
+            RecoveryPlanCancelled {
+                repository: ctx.accounts.repository.key(),
+                timestamp: Clock::get()?.unix_timestamp,
+            }
+        );
+
+        // Account will be closed automatically via close constraint
+        Ok(())
+    }
+
+    /// Take over a repository whose owner has gone the plan's inactivity
+    /// period without a heartbeat or a push of their own. The recovery
+    /// authority becomes the owner. A plan only applies while the owner who
+    /// set it still owns the repository.
+    pub fn claim_recovery(ctx: Context<ClaimRecovery>) -> Result<()> {
+        let clock = Clock::get()?;
+        let plan = &ctx.accounts.recovery_plan;
+        require_live(&ctx.accounts.repository)?;
+        require_keys_eq!(
+            plan.owner,
+            ctx.accounts.repository.owner,
+            VanishError::RecoveryPlanLapsed
+        );
+
+        let last_push = try_load::<Contribution>(&ctx.accounts.owner_contribution)?
+            .map_or(i64::MIN, |contribution| contribution.last_contributed_at);
+        let last_active = plan.last_heartbeat.max(last_push);
+        require!(
+            clock.unix_timestamp - last_active >= plan.inactivity_period,
+            VanishError::OwnerActive
+        );
+
+        let recovery_authority = plan.recovery_authority;
+        let repo = &mut ctx.accounts.repository;
+        let old_owner = repo.owner;
+
+        repo.owner = recovery_authority;
+        record_audit(
+            repo,
+            &mut ctx.accounts.audit_entry,
+            ctx.bumps.audit_entry,
+            recovery_authority,
+            AuditAction::OwnershipTransferred,
+            recovery_authority,
+            clock.unix_timestamp,
+        );
+
+        emit_event!(
+            ctx,
+            RepoRecovered {
+                repository: repo.key(),
+                recovery_authority,
+                old_owner,
+                timestamp: clock.unix_timestamp,
+            }
+        );
+        emit_event!(
+            ctx,
+            OwnershipTransferred {
+                repository: ctx.accounts.repository.key(),
+                old_owner,
+                new_owner: recovery_authority,
+            }
+        );
+
+        // Plan will be closed automatically via close constraint
+        Ok(())
+    }
+
+    /// Freeze the repository's Contribution, Star and Collaborator accounts,
+    /// passed as remaining accounts in ascending address order, into a
+    /// merkle root (see `snapshot_tree`) for contributor airdrops. Every
+    /// Star and Collaborator account must be passed, so the counts have to
+    /// match the repository's `stars` and `collaborator_count`; collaborators
+    /// without an index slot need index_collaborator first. Anyone may take a
+    /// snapshot, at the current slot only, and each taker gets one per slot,
+    /// so a project distributing tokens relies on its own.
+    pub fn snapshot_contributors<'info>(
+        ctx: Context<'_, '_, '_, 'info, SnapshotContributors<'info>>,
+        slot: u64,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        require_eq!(clock.slot, slot, VanishError::SnapshotNotDue);
+
+        let id = ctx.accounts.repository.id;
+        let snapshot = &mut ctx.accounts.snapshot;
+        let mut leaves = Vec::with_capacity(ctx.remaining_accounts.len());
+        let mut previous_key = None;
+        for info in ctx.remaining_accounts {
+            require!(
+                previous_key < Some(info.key()),
+                VanishError::InvalidSnapshotAccounts
+            );
+            previous_key = Some(info.key());
+            require_keys_eq!(*info.owner, crate::ID, VanishError::InvalidSnapshotAccounts);
+
+            // Contribution and Star accounts have led with these fields in
+            // every layout, so they count without migrating first
+            let data = info.try_borrow_data()?;
+            let key = |range: std::ops::Range<usize>| {
+                data.get(range)
+                    .map(|bytes| Pubkey::try_from(bytes).unwrap())
+                    .ok_or(VanishError::InvalidSnapshotAccounts)
+            };
+            let discriminator = data.get(..8).unwrap_or_default();
+            if discriminator == Contribution::DISCRIMINATOR {
+                require_keys_eq!(key(8..40)?, id, VanishError::InvalidSnapshotAccounts);
+                let push_count = data
+                    .get(72..80)
+                    .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+                    .ok_or(VanishError::InvalidSnapshotAccounts)?;
+                leaves.push(snapshot_tree::contribution(&key(40..72)?, push_count));
+                snapshot.contributions += 1;
+            } else if discriminator == Star::DISCRIMINATOR {
+                require_keys_eq!(key(40..72)?, id, VanishError::InvalidSnapshotAccounts);
+                leaves.push(snapshot_tree::star(&key(8..40)?));
+                snapshot.stars += 1;
+            } else if discriminator == Collaborator::DISCRIMINATOR {
+                // The baseline layout has `added_at` where `is_admin` sits
+                // now, and only indexed collaborators are counted
+                let collab = Collaborator::try_deserialize(&mut &data[..])
+                    .map_err(|_| VanishError::InvalidSnapshotAccounts)?;
+                require_keys_eq!(collab.repository, id, VanishError::InvalidSnapshotAccounts);
+                require!(collab.index.is_some(), VanishError::InvalidSnapshotAccounts);
+                leaves.push(snapshot_tree::collaborator(
+                    &collab.user,
+                    collab.can_push,
+                    collab.is_admin,
+                ));
+                snapshot.collaborators += 1;
+            } else {
+                return err!(VanishError::InvalidSnapshotAccounts);
+            }
+        }
+        require!(
+            leaves.len() <= refs_tree::CAPACITY as usize
+                && snapshot.stars as u64 == ctx.accounts.repository.stars
+                && snapshot.collaborators == ctx.accounts.repository.collaborator_count,
+            VanishError::InvalidSnapshotAccounts
+        );
+
+        snapshot.repository = id;
+        snapshot.taker = ctx.accounts.taker.key();
+        snapshot.slot = slot;
+        snapshot.root = refs_tree::root(&leaves);
+        snapshot.bump = ctx.bumps.snapshot;
+
+        emit_event!(
+            ctx,
+            ContributorsSnapshotted {
+                repository: ctx.accounts.repository.key(),
+                taker: snapshot.taker,
+                slot,
+                root: snapshot.root,
+                contributions: snapshot.contributions,
+                stars: snapshot.stars,
+                collaborators: snapshot.collaborators,
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Close a snapshot once its airdrop is done, returning the rent to
+    /// the taker.
+    pub fn close_snapshot(_ctx: Context<CloseSnapshot>) -> Result<()> {
+        // Account will be closed automatically via close constraint
+        Ok(())
+    }
+
+    /// Append the repository's current star and fork counts to its History,
+    /// so growth charts can be drawn from chain state rather than from
+    /// whoever happened to be indexing. Stars are one per Star account plus
+    /// the compressed stars the config admin has reconciled, so starring
+    /// repeatedly cannot inflate a checkpoint. Anyone may checkpoint a live
+    /// repository, at most once every `History::INTERVAL_SLOTS`; whoever
+    /// starts a new page pays its rent.
+    pub fn checkpoint_repo(ctx: Context<CheckpointRepo>) -> Result<()> {
+        let repo = &mut ctx.accounts.repository;
+        let clock = Clock::get()?;
+
+        require_live(repo)?;
+        require!(
+            repo.checkpoint_count == 0
+                || clock.slot
+                    >= repo
+                        .checkpointed_slot
+                        .saturating_add(History::INTERVAL_SLOTS),
+            VanishError::CheckpointNotDue
+        );
+
+        let history = &mut ctx.accounts.history;
+        history.repository = repo.id;
+        history.page = repo.checkpoint_count / History::CAPACITY;
+        history.bump = ctx.bumps.history;
+        history.checkpoints.push(Checkpoint {
+            slot: clock.slot,
+            stars: repo.stars,
+            forks: repo.forks,
+        });
+
+        let index = repo.checkpoint_count;
+        repo.checkpoint_count += 1;
+        repo.checkpointed_slot = clock.slot;
+
+        emit_event!(
+            ctx,
+            RepoCheckpointed {
+                repository: repo.key(),
+                index,
+                slot: clock.slot,
+                stars: repo.stars,
+                forks: repo.forks,
+                timestamp: clock.unix_timestamp,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// View: whether `user` could push to the repository right now, to
+    /// `branch` when given and to the default branch otherwise, going by
+    /// `push_access` as the push instructions do. Changes nothing; clients
+    /// simulate it and read the return value. Missing optional accounts are
+    /// passed as their empty PDAs.
+    pub fn can_push(ctx: Context<CanPush>, user: Pubkey, branch: Option<String>) -> Result<bool> {
+        let collaborator = try_load::<Collaborator>(&ctx.accounts.collaborator_account)?;
+        // Branch pushes take a BranchGrant, default branch pushes a
+        // ProjectMember, like push_branch and push_update
+        let (grant, project_member) = match &branch {
+            Some(_) => (try_load::<BranchGrant>(&ctx.accounts.branch_grant)?, false),
+            None => {
+                let link = try_load::<ProjectRepo>(&ctx.accounts.project_repo)?;
+                let member = match &ctx.accounts.project_member {
+                    Some(member) => try_load::<ProjectMember>(member)?,
+                    None => None,
+                };
+                let project_member = match (link, member) {
+                    (Some(link), Some(member)) => pushes_as_member(&link, &member, &user),
+                    _ => false,
+                };
+                (None, project_member)
+            }
+        };
+
+        let access = push_access(
+            &ctx.accounts.repository,
+            &user,
+            collaborator.as_ref(),
+            grant.as_ref(),
+            project_member,
+        );
+        Ok(match (access, branch) {
+            (Ok(PushAccess::Branches(grant)), Some(branch)) => grant.allows(&branch),
+            (Ok(_), _) => true,
+            (Err(_), _) => false,
+        })
+    }
+
+    /// View: whether `user` may read the repository right now: it is public,
+    /// or they own it, collaborate on it, or hold an unexpired ReadGrant or
+    /// purchased AccessGrant. Simulated like can_push.
+    pub fn can_read(ctx: Context<CanRead>, user: Pubkey) -> Result<bool> {
+        let repo = &ctx.accounts.repository;
+        if require_live(repo).is_err() {
+            return Ok(false);
+        }
+        if !repo.is_private || user == repo.owner {
+            return Ok(true);
+        }
+
+        let now = Clock::get()?.unix_timestamp;
+        Ok(
+            try_load::<Collaborator>(&ctx.accounts.collaborator_account)?.is_some()
+                || try_load::<ReadGrant>(&ctx.accounts.read_grant)?
+                    .is_some_and(|grant| grant.is_active(now))
+                || try_load::<AccessGrant>(&ctx.accounts.access_grant)?
+                    .is_some_and(|grant| now < grant.expires_at),
+        )
+    }
+}
+
+// ============================================================================
+// Accounts
+// ============================================================================
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct InitializeConfig<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = Config::SPACE,
+        seeds = [b"config"],
+        bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(mut, seeds = [b"treasury"], bump)]
+    pub treasury: SystemAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct UpdateConfig<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ VanishError::Unauthorized
+    )]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[instruction(user: Pubkey)]
+pub struct SetRepoLimit<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ VanishError::Unauthorized
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = Profile::SPACE,
+        seeds = [b"user", user.as_ref()],
+        bump
+    )]
+    pub profile: Account<'info, Profile>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct WithdrawTreasury<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ VanishError::Unauthorized
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(mut, seeds = [b"treasury"], bump = config.treasury_bump)]
+    pub treasury: SystemAccount<'info>,
+
+    #[account(mut)]
+    pub recipient: SystemAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[instruction(verifier: Pubkey)]
+pub struct AddVerifier<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ VanishError::Unauthorized
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = Verifier::SPACE,
+        seeds = [b"verifier", verifier.as_ref()],
+        bump
+    )]
+    pub verifier_account: Account<'info, Verifier>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct RemoveVerifier<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ VanishError::Unauthorized
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        close = admin,
+        seeds = [b"verifier", verifier_account.verifier.as_ref()],
+        bump = verifier_account.bump
+    )]
+    pub verifier_account: Account<'info, Verifier>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[instruction(oracle: Pubkey)]
+pub struct AddDealOracle<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ VanishError::Unauthorized
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = DealOracle::SPACE,
+        seeds = [b"deal_oracle", oracle.as_ref()],
+        bump
+    )]
+    pub oracle_account: Account<'info, DealOracle>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct RemoveDealOracle<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ VanishError::Unauthorized
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        close = admin,
+        seeds = [b"deal_oracle", oracle_account.oracle.as_ref()],
+        bump = oracle_account.bump
+    )]
+    pub oracle_account: Account<'info, DealOracle>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[instruction(name: String)]
+pub struct CreateRepo<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = Repository::SPACE,
+        seeds = [b"repo", owner.key().as_ref(), name.as_bytes()],
+        bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    /// CHECK: The Tombstone at the repository's address, empty if no
+    /// repository has left it; read for the new repository's `id`
+    #[account(seeds = [b"tombstone", repository.key().as_ref()], bump)]
+    pub tombstone: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = NameLock::SPACE,
+        seeds = [b"name_lock", owner.key().as_ref(), &hash(name.to_ascii_lowercase().as_bytes()).to_bytes()],
+        bump
+    )]
+    pub name_lock: Account<'info, NameLock>,
+
+    /// Required when the name is short enough to need a stake
+    #[account(
+        init,
+        payer = owner,
+        space = NameStake::SPACE,
+        seeds = [b"stake", repository.key().as_ref()],
+        bump
+    )]
+    pub name_stake: Option<Account<'info, NameStake>>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = Profile::SPACE,
+        seeds = [b"user", owner.key().as_ref()],
+        bump
+    )]
+    pub profile: Account<'info, Profile>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    /// Receives `config.repo_fee`
+    #[account(mut, seeds = [b"treasury"], bump = config.treasury_bump)]
+    pub treasury: SystemAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[instruction(name: String)]
+pub struct CreateFromTemplate<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = Repository::SPACE,
+        seeds = [b"repo", owner.key().as_ref(), name.as_bytes()],
+        bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    /// CHECK: The Tombstone at the repository's address, empty if no
+    /// repository has left it; read for the new repository's `id`
+    #[account(seeds = [b"tombstone", repository.key().as_ref()], bump)]
+    pub tombstone: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = NameLock::SPACE,
+        seeds = [b"name_lock", owner.key().as_ref(), &hash(name.to_ascii_lowercase().as_bytes()).to_bytes()],
+        bump
+    )]
+    pub name_lock: Account<'info, NameLock>,
+
+    /// Required when the name is short enough to need a stake
+    #[account(
+        init,
+        payer = owner,
+        space = NameStake::SPACE,
+        seeds = [b"stake", repository.key().as_ref()],
+        bump
+    )]
+    pub name_stake: Option<Account<'info, NameStake>>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = Profile::SPACE,
+        seeds = [b"user", owner.key().as_ref()],
+        bump
+    )]
+    pub profile: Account<'info, Profile>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    /// Receives `config.repo_fee`
+    #[account(mut, seeds = [b"treasury"], bump = config.treasury_bump)]
+    pub treasury: SystemAccount<'info>,
+
+    #[account(
+        seeds = [b"repo", template.creator.as_ref(), template.name.as_bytes()],
+        bump = template.bump
+    )]
+    pub template: Account<'info, Repository>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[instruction(name: String)]
+pub struct ForkRepo<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = Repository::SPACE,
+        seeds = [b"repo", owner.key().as_ref(), name.as_bytes()],
+        bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    /// CHECK: The Tombstone at the repository's address, empty if no
+    /// repository has left it; read for the new repository's `id`
+    #[account(seeds = [b"tombstone", repository.key().as_ref()], bump)]
+    pub tombstone: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = NameLock::SPACE,
+        seeds = [b"name_lock", owner.key().as_ref(), &hash(name.to_ascii_lowercase().as_bytes()).to_bytes()],
+        bump
+    )]
+    pub name_lock: Account<'info, NameLock>,
+
+    /// Required when the name is short enough to need a stake
+    #[account(
+        init,
+        payer = owner,
+        space = NameStake::SPACE,
+        seeds = [b"stake", repository.key().as_ref()],
+        bump
+    )]
+    pub name_stake: Option<Account<'info, NameStake>>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = Profile::SPACE,
+        seeds = [b"user", owner.key().as_ref()],
+        bump
+    )]
+    pub profile: Account<'info, Profile>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    /// Receives `config.repo_fee`
+    #[account(mut, seeds = [b"treasury"], bump = config.treasury_bump)]
+    pub treasury: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"repo", upstream.creator.as_ref(), upstream.name.as_bytes()],
+        bump = upstream.bump
+    )]
+    pub upstream: Account<'info, Repository>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = ForkLink::SPACE,
+        seeds = [b"upstream", new_repository_id(&repository.key(), &tombstone)?.as_ref()],
+        bump
+    )]
+    pub fork_link: Account<'info, ForkLink>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct PushUpdate<'info> {
+    #[account(mut)]
+    pub pusher: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    /// Required when the pusher is not the repository owner
+    #[account(
+        mut,
+        seeds = [b"collab", repository.id.as_ref(), pusher.key().as_ref()],
+        bump = collaborator_account.bump
+    )]
+    pub collaborator_account: Option<Account<'info, Collaborator>>,
+
+    #[account(
+        init_if_needed,
+        payer = pusher,
+        space = Contribution::SPACE,
+        seeds = [b"contribution", repository.id.as_ref(), pusher.key().as_ref()],
+        bump
+    )]
+    pub contribution: Account<'info, Contribution>,
+
+    /// Recorded into when passed
+    #[account(
+        mut,
+        seeds = [b"push_log", repository.id.as_ref()],
+        bump = push_log.load()?.bump
+    )]
+    pub push_log: Option<AccountLoader<'info, PushLog>>,
+
+    /// CHECK: The repository's ProjectRepo, empty unless it is in a project;
+    /// read by record_project_push
+    #[account(seeds = [b"project_repo", repository.id.as_ref()], bump)]
+    pub project_repo: UncheckedAccount<'info>,
+
+    /// Required when the repository is in a project, whose activity the push
+    /// counts towards
+    #[account(mut)]
+    pub project: Option<Account<'info, Project>>,
+
+    /// Pusher's ProjectMember, when pushing as a project member
+    pub project_member: Option<Account<'info, ProjectMember>>,
+
+    /// Badge accounts, passed by pushers who want a contribution badge
+    #[account(
+        seeds = [b"badges", repository.id.as_ref()],
+        bump = badge_config.bump
+    )]
+    pub badge_config: Option<Account<'info, BadgeConfig>>,
+
+    #[account(mut)]
+    pub badge_mint: Option<InterfaceAccount<'info, token_interface::Mint>>,
+
+    #[account(mut)]
+    pub badge_token_account: Option<InterfaceAccount<'info, token_interface::TokenAccount>>,
+
+    pub token_2022_program: Option<Program<'info, Token2022>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct UpdateStats<'info> {
+    #[account(mut)]
+    pub pusher: Signer<'info>,
+
+    #[account(
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    /// Required when the pusher is not the repository owner
+    #[account(
+        mut,
+        seeds = [b"collab", repository.id.as_ref(), pusher.key().as_ref()],
+        bump = collaborator_account.bump
+    )]
+    pub collaborator_account: Option<Account<'info, Collaborator>>,
+
+    #[account(
+        init_if_needed,
+        payer = pusher,
+        space = Stats::SPACE,
+        seeds = [b"stats", repository.id.as_ref()],
+        bump
+    )]
+    pub stats: Account<'info, Stats>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[instruction(branch_name: String)]
+pub struct PushBranch<'info> {
+    #[account(mut)]
+    pub pusher: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    /// Required when the pusher is not the repository owner
+    #[account(
+        mut,
+        seeds = [b"collab", repository.id.as_ref(), pusher.key().as_ref()],
+        bump = collaborator_account.bump
+    )]
+    pub collaborator_account: Option<Account<'info, Collaborator>>,
+
+    /// Required when the collaborator may only push some branches
+    #[account(
+        seeds = [b"branch_grant", repository.id.as_ref(), pusher.key().as_ref()],
+        bump = branch_grant.bump
+    )]
+    pub branch_grant: Option<Account<'info, BranchGrant>>,
+
+    #[account(
+        init_if_needed,
+        payer = pusher,
+        space = Contribution::SPACE,
+        seeds = [b"contribution", repository.id.as_ref(), pusher.key().as_ref()],
+        bump
+    )]
+    pub contribution: Account<'info, Contribution>,
+
+    #[account(
+        init_if_needed,
+        payer = pusher,
+        space = Branch::SPACE,
+        seeds = [b"branch", repository.id.as_ref(), &hash(branch_name.as_bytes()).to_bytes()],
+        bump
+    )]
+    pub branch: Account<'info, Branch>,
+
+    /// The repository's project link, required with rent_vault
+    #[account(
+        seeds = [b"project_repo", repository.id.as_ref()],
+        bump = project_repo.bump
+    )]
+    pub project_repo: Option<Account<'info, ProjectRepo>>,
+
+    /// The project's RentVault, to cover a new branch's rent
+    #[account(
+        mut,
+        seeds = [b"rent_vault", rent_vault.project.as_ref()],
+        bump = rent_vault.bump
+    )]
+    pub rent_vault: Option<Account<'info, RentVault>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct PushRefs<'info> {
+    #[account(mut)]
+    pub pusher: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    /// Required when the pusher is not the repository owner
+    #[account(
+        mut,
+        seeds = [b"collab", repository.id.as_ref(), pusher.key().as_ref()],
+        bump = collaborator_account.bump
+    )]
+    pub collaborator_account: Option<Account<'info, Collaborator>>,
+
+    /// Required when the collaborator may only push some branches
+    #[account(
+        seeds = [b"branch_grant", repository.id.as_ref(), pusher.key().as_ref()],
+        bump = branch_grant.bump
+    )]
+    pub branch_grant: Option<Account<'info, BranchGrant>>,
+
+    #[account(
+        init_if_needed,
+        payer = pusher,
+        space = Contribution::SPACE,
+        seeds = [b"contribution", repository.id.as_ref(), pusher.key().as_ref()],
+        bump
+    )]
+    pub contribution: Account<'info, Contribution>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[instruction(branch_name: Option<String>)]
+pub struct SyncWithUpstream<'info> {
+    #[account(mut)]
+    pub pusher: Signer<'info>,
+
+    /// The fork
+    #[account(
+        mut,
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    /// Required when the pusher is not the repository owner
+    #[account(
+        mut,
+        seeds = [b"collab", repository.id.as_ref(), pusher.key().as_ref()],
+        bump = collaborator_account.bump
+    )]
+    pub collaborator_account: Option<Account<'info, Collaborator>>,
+
+    #[account(
+        seeds = [b"repo", upstream.creator.as_ref(), upstream.name.as_bytes()],
+        bump = upstream.bump,
+        constraint = upstream.id == fork_link.upstream @ VanishError::UpstreamMismatch
+    )]
+    pub upstream: Account<'info, Repository>,
+
+    /// Absent unless the repository was created by fork_repo
+    #[account(
+        mut,
+        seeds = [b"upstream", repository.id.as_ref()],
+        bump = fork_link.bump
+    )]
+    pub fork_link: Account<'info, ForkLink>,
+
+    /// Required, with `branch`, when syncing a branch
+    #[account(
+        seeds = [b"branch", upstream.id.as_ref(), &hash(upstream_branch.name.as_bytes()).to_bytes()],
+        bump = upstream_branch.bump
+    )]
+    pub upstream_branch: Option<Account<'info, Branch>>,
+
+    #[account(
+        init_if_needed,
+        payer = pusher,
+        space = Branch::SPACE,
+        seeds = [
+            b"branch",
+            repository.id.as_ref(),
+            &hash(branch_name.as_deref().unwrap_or_default().as_bytes()).to_bytes()
+        ],
+        bump
+    )]
+    pub branch: Option<Account<'info, Branch>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[instruction(path: String)]
+pub struct AddSubmodule<'info> {
+    #[account(mut)]
+    pub pusher: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    /// Required when the pusher is not the repository owner
+    #[account(
+        mut,
+        seeds = [b"collab", repository.id.as_ref(), pusher.key().as_ref()],
+        bump = collaborator_account.bump
+    )]
+    pub collaborator_account: Option<Account<'info, Collaborator>>,
+
+    /// Repository the submodule points at
+    #[account(
+        seeds = [b"repo", target.creator.as_ref(), target.name.as_bytes()],
+        bump = target.bump
+    )]
+    pub target: Account<'info, Repository>,
+
+    #[account(
+        init,
+        payer = pusher,
+        space = Submodule::SPACE,
+        seeds = [b"submodule", repository.id.as_ref(), &hash(path.as_bytes()).to_bytes()],
+        bump
+    )]
+    pub submodule: Account<'info, Submodule>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct UpdateSubmodule<'info> {
+    pub pusher: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    /// Required when the pusher is not the repository owner
+    #[account(
+        mut,
+        seeds = [b"collab", repository.id.as_ref(), pusher.key().as_ref()],
+        bump = collaborator_account.bump
+    )]
+    pub collaborator_account: Option<Account<'info, Collaborator>>,
+
+    #[account(
+        seeds = [b"repo", target.creator.as_ref(), target.name.as_bytes()],
+        bump = target.bump
+    )]
+    pub target: Account<'info, Repository>,
+
+    #[account(
+        mut,
+        seeds = [b"submodule", repository.id.as_ref(), &hash(submodule.path.as_bytes()).to_bytes()],
+        bump = submodule.bump
+    )]
+    pub submodule: Account<'info, Submodule>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct RemoveSubmodule<'info> {
+    #[account(mut)]
+    pub pusher: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    /// Required when the pusher is not the repository owner
+    #[account(
+        mut,
+        seeds = [b"collab", repository.id.as_ref(), pusher.key().as_ref()],
+        bump = collaborator_account.bump
+    )]
+    pub collaborator_account: Option<Account<'info, Collaborator>>,
+
+    #[account(
+        mut,
+        close = pusher,
+        seeds = [b"submodule", repository.id.as_ref(), &hash(submodule.path.as_bytes()).to_bytes()],
+        bump = submodule.bump
+    )]
+    pub submodule: Account<'info, Submodule>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct GcObjects<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    /// Required when the authority is an admin collaborator rather than the owner
+    #[account(
+        seeds = [b"collab", repository.id.as_ref(), authority.key().as_ref()],
+        bump = admin.bump
+    )]
+    pub admin: Option<Account<'info, Collaborator>>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct SetDescription<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    /// Required when the authority is an admin collaborator rather than the owner
+    #[account(
+        seeds = [b"collab", repository.id.as_ref(), authority.key().as_ref()],
+        bump = admin.bump
+    )]
+    pub admin: Option<Account<'info, Collaborator>>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct SetRepoFlags<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    /// Required when the authority is an admin collaborator rather than the owner
+    #[account(
+        seeds = [b"collab", repository.id.as_ref(), authority.key().as_ref()],
+        bump = admin.bump
+    )]
+    pub admin: Option<Account<'info, Collaborator>>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct DeprecateRepo<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    /// Required when the authority is an admin collaborator rather than the owner
+    #[account(
+        seeds = [b"collab", repository.id.as_ref(), authority.key().as_ref()],
+        bump = admin.bump
+    )]
+    pub admin: Option<Account<'info, Collaborator>>,
+
+    /// The repository named as successor, if any
+    pub successor: Option<Account<'info, Repository>>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct VerifyRepo<'info> {
+    #[account(mut)]
+    pub verifier: Signer<'info>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    /// Required when the verifier is not the config admin
+    #[account(
+        seeds = [b"verifier", verifier.key().as_ref()],
+        bump = verifier_account.bump
+    )]
+    pub verifier_account: Option<Account<'info, Verifier>>,
+
+    #[account(
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    #[account(
+        init_if_needed,
+        payer = verifier,
+        space = Verification::SPACE,
+        seeds = [b"verification", repository.id.as_ref()],
+        bump
+    )]
+    pub verification: Account<'info, Verification>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct RevokeVerification<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    /// Required when the authority is not the config admin
+    #[account(
+        seeds = [b"verifier", authority.key().as_ref()],
+        bump = verifier_account.bump
+    )]
+    pub verifier_account: Option<Account<'info, Verifier>>,
+
+    #[account(
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    #[account(
+        mut,
+        close = verifier,
+        seeds = [b"verification", repository.id.as_ref()],
+        bump = verification.bump,
+        has_one = verifier
+    )]
+    pub verification: Account<'info, Verification>,
+
+    /// Whoever verified the repository, and paid for the account
+    #[account(mut)]
+    pub verifier: SystemAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct SetFrozen<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump,
+        has_one = owner
+    )]
+    pub repository: Account<'info, Repository>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct DeclareFilecoin<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    /// Required when the authority is an admin collaborator rather than the owner
+    #[account(
+        seeds = [b"collab", repository.id.as_ref(), authority.key().as_ref()],
+        bump = admin.bump
+    )]
+    pub admin: Option<Account<'info, Collaborator>>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = FilecoinDeal::SPACE,
+        seeds = [b"filecoin", repository.id.as_ref()],
+        bump
+    )]
+    pub filecoin_deal: Account<'info, FilecoinDeal>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct AttestFilecoinDeal<'info> {
+    pub oracle: Signer<'info>,
+
+    #[account(
+        seeds = [b"deal_oracle", oracle.key().as_ref()],
+        bump = oracle_account.bump
+    )]
+    pub oracle_account: Account<'info, DealOracle>,
+
+    #[account(
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    #[account(
+        mut,
+        seeds = [b"filecoin", repository.id.as_ref()],
+        bump = filecoin_deal.bump
+    )]
+    pub filecoin_deal: Account<'info, FilecoinDeal>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct FlagLapsedDeal<'info> {
+    #[account(
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    #[account(
+        mut,
+        seeds = [b"filecoin", repository.id.as_ref()],
+        bump = filecoin_deal.bump
+    )]
+    pub filecoin_deal: Account<'info, FilecoinDeal>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct RegisterPinner<'info> {
+    #[account(mut)]
+    pub operator: Signer<'info>,
+
+    #[account(
+        init,
+        payer = operator,
+        space = Pinner::SPACE,
+        seeds = [b"pinner", operator.key().as_ref()],
+        bump
+    )]
+    pub pinner: Account<'info, Pinner>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct EnrollPinner<'info> {
+    pub operator: Signer<'info>,
+
+    /// The repository owner or an admin, approving the pinner
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"pinner", operator.key().as_ref()],
+        bump = pinner.bump
+    )]
+    pub pinner: Account<'info, Pinner>,
+
+    #[account(
+        mut,
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    /// Required when the authority is an admin collaborator rather than the owner
+    #[account(
+        seeds = [b"collab", repository.id.as_ref(), authority.key().as_ref()],
+        bump = admin.bump
+    )]
+    pub admin: Option<Account<'info, Collaborator>>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct UnenrollPinner<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    /// Required when the authority is an admin collaborator rather than the
+    /// owner or the pinner
+    #[account(
+        seeds = [b"collab", repository.id.as_ref(), authority.key().as_ref()],
+        bump = admin.bump
+    )]
+    pub admin: Option<Account<'info, Collaborator>>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct AttestPinned<'info> {
+    #[account(mut)]
+    pub operator: Signer<'info>,
+
+    #[account(
+        seeds = [b"pinner", operator.key().as_ref()],
+        bump = pinner.bump
+    )]
+    pub pinner: Account<'info, Pinner>,
+
+    #[account(
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    #[account(
+        init_if_needed,
+        payer = operator,
+        space = PinAttestation::SPACE,
+        seeds = [b"pin", repository.id.as_ref(), operator.key().as_ref()],
+        bump
+    )]
+    pub pin_attestation: Account<'info, PinAttestation>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct UnbondPinner<'info> {
+    pub operator: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"pinner", operator.key().as_ref()],
+        bump = pinner.bump
+    )]
+    pub pinner: Account<'info, Pinner>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct WithdrawPinnerStake<'info> {
+    #[account(mut)]
+    pub operator: Signer<'info>,
+
+    #[account(
+        mut,
+        close = operator,
+        seeds = [b"pinner", operator.key().as_ref()],
+        bump = pinner.bump
+    )]
+    pub pinner: Account<'info, Pinner>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct SetRewardRate<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    /// Required when the authority is an admin collaborator rather than the owner
+    #[account(
+        seeds = [b"collab", repository.id.as_ref(), authority.key().as_ref()],
+        bump = admin.bump
+    )]
+    pub admin: Option<Account<'info, Collaborator>>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = RewardPool::SPACE,
+        seeds = [b"reward_pool", repository.id.as_ref()],
+        bump
+    )]
+    pub reward_pool: Account<'info, RewardPool>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct FundRewardPool<'info> {
+    #[account(mut)]
+    pub funder: Signer<'info>,
+
+    #[account(
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    #[account(
+        mut,
+        seeds = [b"reward_pool", repository.id.as_ref()],
+        bump = reward_pool.bump
+    )]
+    pub reward_pool: Account<'info, RewardPool>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct FundRewardPoolFromTreasury<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ VanishError::Unauthorized
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(mut, seeds = [b"treasury"], bump = config.treasury_bump)]
+    pub treasury: SystemAccount<'info>,
+
+    #[account(
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    #[account(
+        mut,
+        seeds = [b"reward_pool", repository.id.as_ref()],
+        bump = reward_pool.bump
+    )]
+    pub reward_pool: Account<'info, RewardPool>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct DistributeRewards<'info> {
+    #[account(
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    #[account(
+        mut,
+        seeds = [b"reward_pool", repository.id.as_ref()],
+        bump = reward_pool.bump
+    )]
+    pub reward_pool: Account<'info, RewardPool>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct SetBranchGrant<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    /// Required when the authority is an admin collaborator rather than the owner
+    #[account(
+        seeds = [b"collab", repository.id.as_ref(), authority.key().as_ref()],
+        bump = admin.bump
+    )]
+    pub admin: Option<Account<'info, Collaborator>>,
+
+    #[account(
+        seeds = [b"collab", repository.id.as_ref(), collaborator_account.user.as_ref()],
+        bump = collaborator_account.bump
+    )]
+    pub collaborator_account: Account<'info, Collaborator>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = BranchGrant::SPACE,
+        seeds = [b"branch_grant", repository.id.as_ref(), collaborator_account.user.as_ref()],
+        bump
+    )]
+    pub branch_grant: Account<'info, BranchGrant>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct RemoveBranchGrant<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    /// Required when the authority is an admin collaborator rather than the owner
+    #[account(
+        seeds = [b"collab", repository.id.as_ref(), authority.key().as_ref()],
+        bump = admin.bump
+    )]
+    pub admin: Option<Account<'info, Collaborator>>,
+
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"branch_grant", repository.id.as_ref(), branch_grant.user.as_ref()],
+        bump = branch_grant.bump
+    )]
+    pub branch_grant: Account<'info, BranchGrant>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct SetBranchForcePush<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    /// Required when the authority is an admin collaborator rather than the owner
+    #[account(
+        seeds = [b"collab", repository.id.as_ref(), authority.key().as_ref()],
+        bump = admin.bump
+    )]
+    pub admin: Option<Account<'info, Collaborator>>,
+
+    #[account(
+        mut,
+        seeds = [b"branch", repository.id.as_ref(), &hash(branch.name.as_bytes()).to_bytes()],
+        bump = branch.bump
+    )]
+    pub branch: Account<'info, Branch>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[instruction(url_hash: [u8; 32])]
+pub struct SetWebhook<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    /// Required when the authority is an admin collaborator rather than the owner
+    #[account(
+        seeds = [b"collab", repository.id.as_ref(), authority.key().as_ref()],
+        bump = admin.bump
+    )]
+    pub admin: Option<Account<'info, Collaborator>>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = Webhook::SPACE,
+        seeds = [b"webhook", repository.id.as_ref(), url_hash.as_ref()],
+        bump
+    )]
+    pub webhook: Account<'info, Webhook>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct RemoveWebhook<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    /// Required when the authority is an admin collaborator rather than the owner
+    #[account(
+        seeds = [b"collab", repository.id.as_ref(), authority.key().as_ref()],
+        bump = admin.bump
+    )]
+    pub admin: Option<Account<'info, Collaborator>>,
+
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"webhook", repository.id.as_ref(), webhook.url_hash.as_ref()],
+        bump = webhook.bump
+    )]
+    pub webhook: Account<'info, Webhook>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct SetIpns<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    /// Required when the authority is an admin collaborator rather than the owner
+    #[account(
+        seeds = [b"collab", repository.id.as_ref(), authority.key().as_ref()],
+        bump = admin.bump
+    )]
+    pub admin: Option<Account<'info, Collaborator>>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct SetRepoDocument<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    /// Required when the authority is an admin collaborator rather than the owner
+    #[account(
+        seeds = [b"collab", repository.id.as_ref(), authority.key().as_ref()],
+        bump = admin.bump
+    )]
+    pub admin: Option<Account<'info, Collaborator>>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct ResizeRepository<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump,
+        realloc = Repository::SPACE,
+        realloc::payer = authority,
+        realloc::zero = false
+    )]
+    pub repository: Account<'info, Repository>,
+
+    /// Required when the authority is an admin collaborator rather than the owner
+    #[account(
+        seeds = [b"collab", repository.id.as_ref(), authority.key().as_ref()],
+        bump = admin.bump
+    )]
+    pub admin: Option<Account<'info, Collaborator>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct MigrateAccount<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: Any Repository, Collaborator, Star, Branch or PushLog; the type
+    /// is read from the discriminator and the data is checked when it is
+    /// deserialized
+    #[account(mut, owner = crate::ID)]
+    pub account: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[instruction(collaborator: Pubkey)]
+pub struct AddCollaborator<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    /// Required when the authority is an admin collaborator rather than the owner
+    #[account(
+        seeds = [b"collab", repository.id.as_ref(), authority.key().as_ref()],
+        bump = admin.bump
+    )]
+    pub admin: Option<Account<'info, Collaborator>>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = Collaborator::SPACE,
+        seeds = [b"collab", repository.id.as_ref(), collaborator.as_ref()],
+        bump
+    )]
+    pub collaborator_account: Account<'info, Collaborator>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = CollaboratorSlot::SPACE,
+        seeds = [
+            b"collab_slot",
+            repository.id.as_ref(),
+            &repository.collaborator_count.to_le_bytes()
+        ],
+        bump
+    )]
+    pub collaborator_slot: Account<'info, CollaboratorSlot>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = AccessKey::SPACE,
+        seeds = [b"access", repository.id.as_ref(), collaborator.as_ref()],
+        bump
+    )]
+    pub access_key: Option<Account<'info, AccessKey>>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = AuditEntry::SPACE,
+        seeds = [b"audit", repository.id.as_ref(), &repository.audit_count.to_le_bytes()],
+        bump
+    )]
+    pub audit_entry: Account<'info, AuditEntry>,
+
+    /// The repository's project link, required with rent_vault
+    #[account(
+        seeds = [b"project_repo", repository.id.as_ref()],
+        bump = project_repo.bump
+    )]
+    pub project_repo: Option<Account<'info, ProjectRepo>>,
+
+    /// The project's RentVault, to cover the new accounts' rent
+    #[account(
+        mut,
+        seeds = [b"rent_vault", rent_vault.project.as_ref()],
+        bump = rent_vault.bump
+    )]
+    pub rent_vault: Option<Account<'info, RentVault>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct RotateRepoKey<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    /// Required when the authority is an admin collaborator rather than the owner
+    #[account(
+        seeds = [b"collab", repository.id.as_ref(), authority.key().as_ref()],
+        bump = admin.bump
+    )]
+    pub admin: Option<Account<'info, Collaborator>>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct ReissueAccessKey<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    /// Required when the authority is an admin collaborator rather than the owner
+    #[account(
+        seeds = [b"collab", repository.id.as_ref(), authority.key().as_ref()],
+        bump = admin.bump
+    )]
+    pub admin: Option<Account<'info, Collaborator>>,
+
+    #[account(
+        mut,
+        seeds = [b"access", repository.id.as_ref(), access_key.user.as_ref()],
+        bump = access_key.bump
+    )]
+    pub access_key: Account<'info, AccessKey>,
+
+    /// Required when the key holder is a collaborator
+    #[account(
+        seeds = [b"collab", repository.id.as_ref(), access_key.user.as_ref()],
+        bump = collaborator_account.bump
+    )]
+    pub collaborator_account: Option<Account<'info, Collaborator>>,
+
+    /// Required when the key holder is a reader, whose grant must not have
+    /// expired
+    #[account(
+        seeds = [b"read", repository.id.as_ref(), access_key.user.as_ref()],
+        bump = read_grant.bump
+    )]
+    pub read_grant: Option<Account<'info, ReadGrant>>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct SetCollaboratorAdmin<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    /// Required when the authority is an admin collaborator rather than the owner
+    #[account(
+        seeds = [b"collab", repository.id.as_ref(), authority.key().as_ref()],
+        bump = admin.bump
+    )]
+    pub admin: Option<Account<'info, Collaborator>>,
+
+    #[account(
+        mut,
+        seeds = [b"collab", repository.id.as_ref(), collaborator_account.user.as_ref()],
+        bump = collaborator_account.bump
+    )]
+    pub collaborator_account: Account<'info, Collaborator>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = AuditEntry::SPACE,
+        seeds = [b"audit", repository.id.as_ref(), &repository.audit_count.to_le_bytes()],
+        bump
+    )]
+    pub audit_entry: Account<'info, AuditEntry>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct RemoveCollaborator<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    /// Required when the authority is an admin collaborator rather than the owner
+    #[account(
+        seeds = [b"collab", repository.id.as_ref(), authority.key().as_ref()],
+        bump = admin.bump
+    )]
+    pub admin: Option<Account<'info, Collaborator>>,
+
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"collab", repository.id.as_ref(), collaborator_account.user.as_ref()],
+        bump = collaborator_account.bump
+    )]
+    pub collaborator_account: Account<'info, Collaborator>,
+
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"access", repository.id.as_ref(), collaborator_account.user.as_ref()],
+        bump = access_key.bump
+    )]
+    pub access_key: Option<Account<'info, AccessKey>>,
+
+    /// The last index slot, closed. Required when the collaborator has a slot.
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"collab_slot", repository.id.as_ref(), &last_slot.index.to_le_bytes()],
+        bump = last_slot.bump
+    )]
+    pub last_slot: Option<Account<'info, CollaboratorSlot>>,
+
+    /// The collaborator's own slot, which `moved_collaborator` takes over.
+    /// Both are required unless the collaborator holds the last slot.
+    #[account(
+        mut,
+        seeds = [
+            b"collab_slot",
+            repository.id.as_ref(),
+            &collaborator_slot.index.to_le_bytes()
+        ],
+        bump = collaborator_slot.bump
+    )]
+    pub collaborator_slot: Option<Account<'info, CollaboratorSlot>>,
+
+    #[account(
+        mut,
+        seeds = [b"collab", repository.id.as_ref(), moved_collaborator.user.as_ref()],
+        bump = moved_collaborator.bump
+    )]
+    pub moved_collaborator: Option<Account<'info, Collaborator>>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = AuditEntry::SPACE,
+        seeds = [b"audit", repository.id.as_ref(), &repository.audit_count.to_le_bytes()],
+        bump
+    )]
+    pub audit_entry: Account<'info, AuditEntry>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct IndexCollaborator<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    #[account(
+        mut,
+        seeds = [b"collab", repository.id.as_ref(), collaborator_account.user.as_ref()],
+        bump = collaborator_account.bump
+    )]
+    pub collaborator_account: Account<'info, Collaborator>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = CollaboratorSlot::SPACE,
+        seeds = [
+            b"collab_slot",
+            repository.id.as_ref(),
+            &repository.collaborator_count.to_le_bytes()
+        ],
+        bump
+    )]
+    pub collaborator_slot: Account<'info, CollaboratorSlot>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[instruction(name: String)]
+pub struct CreateProject<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = Project::SPACE,
+        seeds = [b"project", admin.key().as_ref(), name.as_bytes()],
+        bump
+    )]
+    pub project: Account<'info, Project>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct AddRepoToProject<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"project", project.admin.as_ref(), project.name.as_bytes()],
+        bump = project.bump,
+        has_one = admin
+    )]
+    pub project: Account<'info, Project>,
+
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump,
+        has_one = owner
+    )]
+    pub repository: Account<'info, Repository>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = ProjectRepo::SPACE,
+        seeds = [b"project_repo", repository.id.as_ref()],
+        bump
+    )]
+    pub project_repo: Account<'info, ProjectRepo>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = ProjectAuditEntry::SPACE,
+        seeds = [b"project_audit", project.key().as_ref(), &project.audit_count.to_le_bytes()],
+        bump
+    )]
+    pub audit_entry: Account<'info, ProjectAuditEntry>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct RemoveRepoFromProject<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"project", project.admin.as_ref(), project.name.as_bytes()],
+        bump = project.bump,
+        has_one = admin
+    )]
+    pub project: Account<'info, Project>,
+
+    /// CHECK: receives the link's rent; must be the project admin, who paid it
+    #[account(mut)]
+    pub admin: UncheckedAccount<'info>,
+
+    /// Required when the authority is the repository owner rather than the
+    /// project admin
+    #[account(
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Option<Account<'info, Repository>>,
+
+    #[account(
+        mut,
+        close = admin,
+        has_one = project,
+        seeds = [b"project_repo", project_repo.repository.as_ref()],
+        bump = project_repo.bump
+    )]
+    pub project_repo: Account<'info, ProjectRepo>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = ProjectAuditEntry::SPACE,
+        seeds = [b"project_audit", project.key().as_ref(), &project.audit_count.to_le_bytes()],
+        bump
+    )]
+    pub audit_entry: Account<'info, ProjectAuditEntry>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[instruction(user: Pubkey)]
+pub struct AddProjectMember<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"project", project.admin.as_ref(), project.name.as_bytes()],
+        bump = project.bump,
+        has_one = admin
+    )]
+    pub project: Account<'info, Project>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = ProjectMember::SPACE,
+        seeds = [b"project_member", project.key().as_ref(), user.as_ref()],
+        bump
+    )]
+    pub project_member: Account<'info, ProjectMember>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = ProjectAuditEntry::SPACE,
+        seeds = [b"project_audit", project.key().as_ref(), &project.audit_count.to_le_bytes()],
+        bump
+    )]
+    pub audit_entry: Account<'info, ProjectAuditEntry>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct RemoveProjectMember<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"project", project.admin.as_ref(), project.name.as_bytes()],
+        bump = project.bump,
+        has_one = admin
+    )]
+    pub project: Account<'info, Project>,
+
+    #[account(
+        mut,
+        close = admin,
+        has_one = project,
+        seeds = [b"project_member", project.key().as_ref(), project_member.user.as_ref()],
+        bump = project_member.bump
+    )]
+    pub project_member: Account<'info, ProjectMember>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = ProjectAuditEntry::SPACE,
+        seeds = [b"project_audit", project.key().as_ref(), &project.audit_count.to_le_bytes()],
+        bump
+    )]
+    pub audit_entry: Account<'info, ProjectAuditEntry>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct SetProjectMemberRole<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"project", project.admin.as_ref(), project.name.as_bytes()],
+        bump = project.bump,
+        has_one = admin
+    )]
+    pub project: Account<'info, Project>,
+
+    #[account(
+        mut,
+        has_one = project,
+        seeds = [b"project_member", project.key().as_ref(), project_member.user.as_ref()],
+        bump = project_member.bump
+    )]
+    pub project_member: Account<'info, ProjectMember>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = ProjectAuditEntry::SPACE,
+        seeds = [b"project_audit", project.key().as_ref(), &project.audit_count.to_le_bytes()],
+        bump
+    )]
+    pub audit_entry: Account<'info, ProjectAuditEntry>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct SetRentVault<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [b"project", project.admin.as_ref(), project.name.as_bytes()],
+        bump = project.bump,
+        has_one = admin
+    )]
+    pub project: Account<'info, Project>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = RentVault::SPACE,
+        seeds = [b"rent_vault", project.key().as_ref()],
+        bump
+    )]
+    pub rent_vault: Account<'info, RentVault>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct DepositRentVault<'info> {
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"rent_vault", rent_vault.project.as_ref()],
+        bump = rent_vault.bump
+    )]
+    pub rent_vault: Account<'info, RentVault>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct WithdrawRentVault<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [b"project", project.admin.as_ref(), project.name.as_bytes()],
+        bump = project.bump,
+        has_one = admin
+    )]
+    pub project: Account<'info, Project>,
+
+    #[account(
+        mut,
+        seeds = [b"rent_vault", project.key().as_ref()],
+        bump = rent_vault.bump
+    )]
+    pub rent_vault: Account<'info, RentVault>,
+
+    /// CHECK: Any account can receive the lamports
+    #[account(mut)]
+    pub recipient: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[instruction(name: String)]
+pub struct CreateList<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = List::SPACE,
+        seeds = [b"list", owner.key().as_ref(), name.as_bytes()],
+        bump
+    )]
+    pub list: Account<'info, List>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct UpdateList<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"list", list.owner.as_ref(), list.name.as_bytes()],
+        bump = list.bump,
+        has_one = owner
+    )]
+    pub list: Account<'info, List>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct DeleteList<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"list", list.owner.as_ref(), list.name.as_bytes()],
+        bump = list.bump,
+        has_one = owner
+    )]
+    pub list: Account<'info, List>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct AddToList<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"list", list.owner.as_ref(), list.name.as_bytes()],
+        bump = list.bump,
+        has_one = owner
+    )]
+    pub list: Account<'info, List>,
+
+    #[account(
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = ListEntry::SPACE,
+        seeds = [b"list_entry", list.key().as_ref(), repository.id.as_ref()],
+        bump
+    )]
+    pub list_entry: Account<'info, ListEntry>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct RemoveFromList<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"list", list.owner.as_ref(), list.name.as_bytes()],
+        bump = list.bump,
+        has_one = owner
+    )]
+    pub list: Account<'info, List>,
+
+    #[account(
+        mut,
+        close = owner,
+        has_one = list,
+        seeds = [b"list_entry", list.key().as_ref(), list_entry.repository.as_ref()],
+        bump = list_entry.bump
+    )]
+    pub list_entry: Account<'info, ListEntry>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct MarkActivitySeen<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = ActivityCursor::SPACE,
+        seeds = [b"activity", user.key().as_ref()],
+        bump
+    )]
+    pub activity_cursor: Account<'info, ActivityCursor>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[instruction(reader: Pubkey)]
+pub struct GrantRead<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    /// Required when the authority is an admin collaborator rather than the owner
+    #[account(
+        seeds = [b"collab", repository.id.as_ref(), authority.key().as_ref()],
+        bump = admin.bump
+    )]
+    pub admin: Option<Account<'info, Collaborator>>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = ReadGrant::SPACE,
+        seeds = [b"read", repository.id.as_ref(), reader.as_ref()],
+        bump
+    )]
+    pub read_grant: Account<'info, ReadGrant>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = AccessKey::SPACE,
+        seeds = [b"access", repository.id.as_ref(), reader.as_ref()],
+        bump
+    )]
+    pub access_key: Option<Account<'info, AccessKey>>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = AuditEntry::SPACE,
+        seeds = [b"audit", repository.id.as_ref(), &repository.audit_count.to_le_bytes()],
+        bump
+    )]
+    pub audit_entry: Account<'info, AuditEntry>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct RevokeRead<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    /// Required when the authority is an admin collaborator rather than the owner
+    #[account(
+        seeds = [b"collab", repository.id.as_ref(), authority.key().as_ref()],
+        bump = admin.bump
+    )]
+    pub admin: Option<Account<'info, Collaborator>>,
+
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"read", repository.id.as_ref(), read_grant.reader.as_ref()],
+        bump = read_grant.bump
+    )]
+    pub read_grant: Account<'info, ReadGrant>,
+
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"access", repository.id.as_ref(), read_grant.reader.as_ref()],
+        bump = access_key.bump
+    )]
+    pub access_key: Option<Account<'info, AccessKey>>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = AuditEntry::SPACE,
+        seeds = [b"audit", repository.id.as_ref(), &repository.audit_count.to_le_bytes()],
+        bump
+    )]
+    pub audit_entry: Account<'info, AuditEntry>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct SetAccessPricing<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    /// Required when the authority is an admin collaborator rather than the owner
+    #[account(
+        seeds = [b"collab", repository.id.as_ref(), authority.key().as_ref()],
+        bump = admin.bump
+    )]
+    pub admin: Option<Account<'info, Collaborator>>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = AccessPricing::SPACE,
+        seeds = [b"pricing", repository.id.as_ref()],
+        bump
+    )]
+    pub pricing: Account<'info, AccessPricing>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct RemoveAccessPricing<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    /// Required when the authority is an admin collaborator rather than the owner
+    #[account(
+        seeds = [b"collab", repository.id.as_ref(), authority.key().as_ref()],
+        bump = admin.bump
+    )]
+    pub admin: Option<Account<'info, Collaborator>>,
+
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"pricing", repository.id.as_ref()],
+        bump = pricing.bump
+    )]
+    pub pricing: Account<'info, AccessPricing>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct PurchaseAccess<'info> {
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    #[account(
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump,
+        has_one = owner
+    )]
+    pub repository: Account<'info, Repository>,
+
+    /// CHECK: receives the payment; must be the repository owner
+    #[account(mut)]
+    pub owner: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"pricing", repository.id.as_ref()],
+        bump = pricing.bump
+    )]
+    pub pricing: Account<'info, AccessPricing>,
+
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = AccessGrant::SPACE,
+        seeds = [b"grant", repository.id.as_ref(), buyer.key().as_ref()],
+        bump
+    )]
+    pub grant: Account<'info, AccessGrant>,
+
+    /// Token accounts, mint and program, required when pricing is in an SPL
+    /// mint. Either SPL Token or Token-2022 is accepted.
+    #[account(mut)]
+    pub buyer_token_account: Option<InterfaceAccount<'info, token_interface::TokenAccount>>,
+
+    #[account(mut)]
+    pub owner_token_account: Option<InterfaceAccount<'info, token_interface::TokenAccount>>,
+
+    pub payment_mint: Option<InterfaceAccount<'info, token_interface::Mint>>,
+
+    pub token_program: Option<Interface<'info, token_interface::TokenInterface>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct ConfigureBadges<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    /// Required when the authority is an admin collaborator rather than the owner
+    #[account(
+        seeds = [b"collab", repository.id.as_ref(), authority.key().as_ref()],
+        bump = admin.bump
+    )]
+    pub admin: Option<Account<'info, Collaborator>>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = BadgeConfig::SPACE,
+        seeds = [b"badges", repository.id.as_ref()],
+        bump
+    )]
+    pub badge_config: Account<'info, BadgeConfig>,
+
+    pub badge_mint: InterfaceAccount<'info, token_interface::Mint>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitPushLog<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    /// Required when the authority is an admin collaborator rather than the owner
+    #[account(
+        seeds = [b"collab", repository.id.as_ref(), authority.key().as_ref()],
+        bump = admin.bump
+    )]
+    pub admin: Option<Account<'info, Collaborator>>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = PushLog::SPACE,
+        seeds = [b"push_log", repository.id.as_ref()],
+        bump
+    )]
+    pub push_log: AccountLoader<'info, PushLog>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[instruction(attestor: Pubkey)]
+pub struct AddCiAttestor<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    /// Required when the authority is an admin collaborator rather than the owner
+    #[account(
+        seeds = [b"collab", repository.id.as_ref(), authority.key().as_ref()],
+        bump = admin.bump
+    )]
+    pub admin: Option<Account<'info, Collaborator>>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = CiAttestor::SPACE,
+        seeds = [b"ci", repository.id.as_ref(), attestor.as_ref()],
+        bump
+    )]
+    pub ci_attestor: Account<'info, CiAttestor>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct RemoveCiAttestor<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    /// Required when the authority is an admin collaborator rather than the owner
+    #[account(
+        seeds = [b"collab", repository.id.as_ref(), authority.key().as_ref()],
+        bump = admin.bump
+    )]
+    pub admin: Option<Account<'info, Collaborator>>,
+
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"ci", repository.id.as_ref(), ci_attestor.attestor.as_ref()],
+        bump = ci_attestor.bump
+    )]
+    pub ci_attestor: Account<'info, CiAttestor>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[instruction(commit: Multihash)]
+pub struct PostCheckRun<'info> {
+    #[account(mut)]
+    pub attestor: Signer<'info>,
+
+    #[account(
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    /// Proves the signer is a registered attestor
+    #[account(
+        seeds = [b"ci", repository.id.as_ref(), attestor.key().as_ref()],
+        bump = ci_attestor.bump
+    )]
+    pub ci_attestor: Account<'info, CiAttestor>,
+
+    #[account(
+        init_if_needed,
+        payer = attestor,
+        space = CheckRun::SPACE,
+        seeds = [
+            b"check",
+            repository.id.as_ref(),
+            &commit.seed(),
+            attestor.key().as_ref()
+        ],
+        bump
+    )]
+    pub check_run: Account<'info, CheckRun>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct SetReleasePolicy<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    /// Required when the authority is an admin collaborator rather than the owner
+    #[account(
+        seeds = [b"collab", repository.id.as_ref(), authority.key().as_ref()],
+        bump = admin.bump
+    )]
+    pub admin: Option<Account<'info, Collaborator>>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = ReleasePolicy::SPACE,
+        seeds = [b"release_policy", repository.id.as_ref()],
+        bump
+    )]
+    pub release_policy: Account<'info, ReleasePolicy>,
+
+    /// CHECK: The repository's Timelock, empty if it never set one
+    #[account(seeds = [b"timelock", repository.id.as_ref()], bump)]
+    pub timelock: UncheckedAccount<'info>,
+
+    /// The queued policy, when the managers do not sign for it
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"pending", repository.id.as_ref()],
+        bump = pending_action.bump
+    )]
+    pub pending_action: Option<Account<'info, PendingAction>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[instruction(tag: String)]
+pub struct CreateRelease<'info> {
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+
+    #[account(
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    #[account(
+        seeds = [b"release_policy", repository.id.as_ref()],
+        bump = release_policy.bump
+    )]
+    pub release_policy: Account<'info, ReleasePolicy>,
+
+    #[account(
+        init,
+        payer = proposer,
+        space = Release::SPACE,
+        seeds = [b"release", repository.id.as_ref(), tag.as_bytes()],
+        bump
+    )]
+    pub release: Account<'info, Release>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct AddReleaseAsset<'info> {
+    pub manager: Signer<'info>,
+
+    #[account(
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    #[account(
+        seeds = [b"release_policy", repository.id.as_ref()],
+        bump = release_policy.bump
+    )]
+    pub release_policy: Account<'info, ReleasePolicy>,
+
+    #[account(
+        mut,
+        seeds = [b"release", repository.id.as_ref(), release.tag.as_bytes()],
+        bump = release.bump
+    )]
+    pub release: Account<'info, Release>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[instruction(builder: Pubkey)]
+pub struct AddBuilder<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    /// Required when the authority is an admin collaborator rather than the owner
+    #[account(
+        seeds = [b"collab", repository.id.as_ref(), authority.key().as_ref()],
+        bump = admin.bump
+    )]
+    pub admin: Option<Account<'info, Collaborator>>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = Builder::SPACE,
+        seeds = [b"builder", repository.id.as_ref(), builder.as_ref()],
+        bump
+    )]
+    pub builder_account: Account<'info, Builder>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct RemoveBuilder<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    /// Required when the authority is an admin collaborator rather than the owner
+    #[account(
+        seeds = [b"collab", repository.id.as_ref(), authority.key().as_ref()],
+        bump = admin.bump
+    )]
+    pub admin: Option<Account<'info, Collaborator>>,
+
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"builder", repository.id.as_ref(), builder_account.builder.as_ref()],
+        bump = builder_account.bump
+    )]
+    pub builder_account: Account<'info, Builder>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[instruction(checksum: Multihash)]
+pub struct AttestBuild<'info> {
+    #[account(mut)]
+    pub builder: Signer<'info>,
+
+    #[account(
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    /// Proves the signer is a registered builder
+    #[account(
+        seeds = [b"builder", repository.id.as_ref(), builder.key().as_ref()],
+        bump = builder_account.bump
+    )]
+    pub builder_account: Account<'info, Builder>,
+
+    #[account(
+        seeds = [b"release", repository.id.as_ref(), release.tag.as_bytes()],
+        bump = release.bump
+    )]
+    pub release: Account<'info, Release>,
+
+    #[account(
+        init,
+        payer = builder,
+        space = Attestation::SPACE,
+        seeds = [
+            b"attestation",
+            release.key().as_ref(),
+            &checksum.seed(),
+            builder.key().as_ref()
+        ],
+        bump
+    )]
+    pub attestation: Account<'info, Attestation>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct ApproveRelease<'info> {
+    #[account(mut)]
+    pub manager: Signer<'info>,
+
+    #[account(
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    #[account(
+        seeds = [b"release_policy", repository.id.as_ref()],
+        bump = release_policy.bump
+    )]
+    pub release_policy: Account<'info, ReleasePolicy>,
+
+    #[account(
+        mut,
+        seeds = [b"release", repository.id.as_ref(), release.tag.as_bytes()],
+        bump = release.bump
+    )]
+    pub release: Account<'info, Release>,
+
+    /// One approval per manager per release
+    #[account(
+        init,
+        payer = manager,
+        space = ReleaseApproval::SPACE,
+        seeds = [b"approval", release.key().as_ref(), manager.key().as_ref()],
+        bump
+    )]
+    pub approval: Account<'info, ReleaseApproval>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[instruction(name: String)]
+pub struct CreatePackage<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    /// Required when the authority is an admin collaborator rather than the owner
+    #[account(
+        seeds = [b"collab", repository.id.as_ref(), authority.key().as_ref()],
+        bump = admin.bump
+    )]
+    pub admin: Option<Account<'info, Collaborator>>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = Package::SPACE,
+        seeds = [b"package", name.as_bytes()],
+        bump
+    )]
+    pub package: Account<'info, Package>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[instruction(version: SemVer)]
+pub struct PublishVersion<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    /// Required when the authority is an admin collaborator rather than the owner
+    #[account(
+        seeds = [b"collab", repository.id.as_ref(), authority.key().as_ref()],
+        bump = admin.bump
+    )]
+    pub admin: Option<Account<'info, Collaborator>>,
+
+    #[account(
+        mut,
+        seeds = [b"package", package.name.as_bytes()],
+        bump = package.bump,
+        constraint = package.repository == repository.id @ VanishError::Unauthorized
+    )]
+    pub package: Account<'info, Package>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = PackageVersion::SPACE,
+        seeds = [
+            b"version",
+            package.key().as_ref(),
+            &version.major.to_le_bytes(),
+            &version.minor.to_le_bytes(),
+            &version.patch.to_le_bytes()
+        ],
+        bump
+    )]
+    pub package_version: Account<'info, PackageVersion>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct StarRepo<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(mut)]
+    pub repository: Account<'info, Repository>,
+
+    #[account(
+        init,
+        payer = user,
+        space = Star::SPACE,
+        seeds = [b"star", user.key().as_ref(), repository.id.as_ref()],
+        bump
+    )]
+    pub star_account: Account<'info, Star>,
+
+    /// Collects tips for the repository's owner
+    #[account(mut, seeds = [b"donations", repository.id.as_ref()], bump)]
+    pub donation_vault: SystemAccount<'info>,
+
+    /// Counts the user's stars against the config's rate limit
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = Profile::SPACE,
+        seeds = [b"user", user.key().as_ref()],
+        bump
+    )]
+    pub profile: Account<'info, Profile>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct WithdrawDonations<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump,
+        has_one = owner
+    )]
+    pub repository: Account<'info, Repository>,
+
+    #[account(mut, seeds = [b"donations", repository.id.as_ref()], bump)]
+    pub donation_vault: SystemAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitStarTree<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = StarTree::SPACE,
+        seeds = [b"star_tree"],
+        bump
+    )]
+    pub star_tree: Account<'info, StarTree>,
+
+    /// CHECK: Allocated for the compression program, which initializes it
+    #[account(mut, owner = compression::ID)]
+    pub merkle_tree: UncheckedAccount<'info>,
+
+    /// CHECK: spl-account-compression
+    #[account(address = compression::ID)]
+    pub compression_program: UncheckedAccount<'info>,
+
+    /// CHECK: spl-noop, which the compression program logs changes through
+    #[account(address = compression::noop::ID)]
+    pub noop_program: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct CompressedStar<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub repository: Account<'info, Repository>,
+
+    #[account(seeds = [b"star_tree"], bump = star_tree.bump, has_one = merkle_tree)]
+    pub star_tree: Account<'info, StarTree>,
+
+    /// CHECK: Matched against star_tree; the compression program checks the rest
+    #[account(mut)]
+    pub merkle_tree: UncheckedAccount<'info>,
+
+    /// CHECK: spl-account-compression
+    #[account(address = compression::ID)]
+    pub compression_program: UncheckedAccount<'info>,
+
+    /// CHECK: spl-noop, which the compression program logs changes through
+    #[account(address = compression::noop::ID)]
+    pub noop_program: UncheckedAccount<'info>,
+
+    /// Counts the user's stars against the config's rate limit
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = Profile::SPACE,
+        seeds = [b"user", user.key().as_ref()],
+        bump
+    )]
+    pub profile: Account<'info, Profile>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct UnstarRepo<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(mut)]
+    pub repository: Account<'info, Repository>,
+
+    #[account(
+        mut,
+        close = user,
+        seeds = [b"star", user.key().as_ref(), repository.id.as_ref()],
+        bump = star_account.bump
+    )]
+    pub star_account: Account<'info, Star>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct DecayStars<'info> {
+    #[account(
+        mut,
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct ReconcileStars<'info> {
+    #[account(
+        mut,
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    /// The config admin, required to lower the count or add compressed stars
+    pub admin: Option<Signer<'info>>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[instruction(new_name: String)]
+pub struct RenameRepo<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    /// Required when the authority is an admin collaborator rather than the owner
+    #[account(
+        seeds = [b"collab", repository.id.as_ref(), authority.key().as_ref()],
+        bump = admin.bump
+    )]
+    pub admin: Option<Account<'info, Collaborator>>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = Repository::SPACE,
+        seeds = [b"repo", repository.creator.as_ref(), new_name.as_bytes()],
+        bump
+    )]
+    pub new_repository: Account<'info, Repository>,
+
+    /// Counts the repository leaving its old address
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = Tombstone::SPACE,
+        seeds = [b"tombstone", repository.key().as_ref()],
+        bump
+    )]
+    pub tombstone: Account<'info, Tombstone>,
+
+    /// Absent for repositories created before names were locked
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"name_lock", repository.creator.as_ref(), &hash(repository.name.to_ascii_lowercase().as_bytes()).to_bytes()],
+        bump = name_lock.bump
+    )]
+    pub name_lock: Option<Account<'info, NameLock>>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = NameLock::SPACE,
+        seeds = [b"name_lock", repository.creator.as_ref(), &hash(new_name.to_ascii_lowercase().as_bytes()).to_bytes()],
+        bump
+    )]
+    pub new_name_lock: Account<'info, NameLock>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = Redirect::SPACE,
+        seeds = [b"redirect", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump
+    )]
+    pub redirect: Option<Account<'info, Redirect>>,
+
+    /// CHECK: The repository's Timelock, empty if it never set one; read by
+    /// require_unlocked
+    #[account(seeds = [b"timelock", repository.id.as_ref()], bump)]
+    pub timelock: UncheckedAccount<'info>,
+
+    /// The queued action being carried out, required while timelocked
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"pending", repository.id.as_ref()],
+        bump = pending_action.bump
+    )]
+    pub pending_action: Option<Account<'info, PendingAction>>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = AuditEntry::SPACE,
+        seeds = [b"audit", repository.id.as_ref(), &repository.audit_count.to_le_bytes()],
+        bump
+    )]
+    pub audit_entry: Account<'info, AuditEntry>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct ClaimRepoName<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    /// Required when the authority is an admin collaborator rather than the owner
+    #[account(
+        seeds = [b"collab", repository.id.as_ref(), authority.key().as_ref()],
+        bump = admin.bump
+    )]
+    pub admin: Option<Account<'info, Collaborator>>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = RepoName::SPACE,
+        seeds = [b"dir".as_ref(), &hash(repository.name.as_bytes()).to_bytes()],
+        bump
+    )]
+    pub repo_name: Account<'info, RepoName>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct ReleaseRepoName<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        close = claimer,
+        has_one = claimer,
+        seeds = [b"dir".as_ref(), &hash(repo_name.name.as_bytes()).to_bytes()],
+        bump = repo_name.bump
+    )]
+    pub repo_name: Account<'info, RepoName>,
+
+    /// CHECK: receives the entry's rent; must be its claimer
+    #[account(mut)]
+    pub claimer: UncheckedAccount<'info>,
+
+    /// CHECK: Where the entry resolves to, which may no longer hold the
+    /// repository; its data is checked when it is deserialized
+    #[account(
+        seeds = [b"repo", repo_name.creator.as_ref(), repo_name.name.as_bytes()],
+        bump
+    )]
+    pub repository: UncheckedAccount<'info>,
+
+    /// Required when the authority is an admin collaborator rather than the owner
+    #[account(
+        seeds = [b"collab", repo_name.repository.as_ref(), authority.key().as_ref()],
+        bump = admin.bump
+    )]
+    pub admin: Option<Account<'info, Collaborator>>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct SetTimelock<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump,
+        has_one = owner
+    )]
+    pub repository: Account<'info, Repository>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = Timelock::SPACE,
+        seeds = [b"timelock", repository.id.as_ref()],
+        bump
+    )]
+    pub timelock: Account<'info, Timelock>,
+
+    /// The queued SetDelay, required when lowering the delay
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"pending", repository.id.as_ref()],
+        bump = pending_action.bump
+    )]
+    pub pending_action: Option<Account<'info, PendingAction>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct QueueAction<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump,
+        has_one = owner
+    )]
+    pub repository: Account<'info, Repository>,
+
+    #[account(
+        seeds = [b"timelock", repository.id.as_ref()],
+        bump = timelock.bump
+    )]
+    pub timelock: Account<'info, Timelock>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = PendingAction::SPACE,
+        seeds = [b"pending", repository.id.as_ref()],
+        bump
+    )]
+    pub pending_action: Account<'info, PendingAction>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct CancelAction<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump,
+        has_one = owner
+    )]
+    pub repository: Account<'info, Repository>,
+
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"pending", repository.id.as_ref()],
+        bump = pending_action.bump
+    )]
+    pub pending_action: Account<'info, PendingAction>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct TransferOwnership<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump,
+        has_one = owner
+    )]
+    pub repository: Account<'info, Repository>,
+
+    /// CHECK: The repository's Timelock, empty if it never set one; read by
+    /// require_unlocked
+    #[account(seeds = [b"timelock", repository.id.as_ref()], bump)]
+    pub timelock: UncheckedAccount<'info>,
+
+    /// The queued action being carried out, required while timelocked
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"pending", repository.id.as_ref()],
+        bump = pending_action.bump
+    )]
+    pub pending_action: Option<Account<'info, PendingAction>>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = AuditEntry::SPACE,
+        seeds = [b"audit", repository.id.as_ref(), &repository.audit_count.to_le_bytes()],
+        bump
+    )]
+    pub audit_entry: Account<'info, AuditEntry>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct RenounceOwnership<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump,
+        has_one = owner
+    )]
+    pub repository: Account<'info, Repository>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = AuditEntry::SPACE,
+        seeds = [b"audit", repository.id.as_ref(), &repository.audit_count.to_le_bytes()],
+        bump
+    )]
+    pub audit_entry: Account<'info, AuditEntry>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct WrapRepoToNft<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump,
+        has_one = owner
+    )]
+    pub repository: Account<'info, Repository>,
+
+    pub mint: Account<'info, Mint>,
+
+    #[account(
+        constraint = owner_token_account.mint == mint.key()
+            && owner_token_account.owner == owner.key()
+            && owner_token_account.amount == 1 @ VanishError::InvalidNftMint
+    )]
+    pub owner_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = RepoNft::SPACE,
+        seeds = [b"nft", repository.id.as_ref()],
+        bump
+    )]
+    pub repo_nft: Account<'info, RepoNft>,
+
+    /// CHECK: The repository's Timelock, empty if it never set one; read by
+    /// require_unlocked
+    #[account(seeds = [b"timelock", repository.id.as_ref()], bump)]
+    pub timelock: UncheckedAccount<'info>,
+
+    /// The queued action being carried out, required while timelocked
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"pending", repository.id.as_ref()],
+        bump = pending_action.bump
+    )]
+    pub pending_action: Option<Account<'info, PendingAction>>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = AuditEntry::SPACE,
+        seeds = [b"audit", repository.id.as_ref(), &repository.audit_count.to_le_bytes()],
+        bump
+    )]
+    pub audit_entry: Account<'info, AuditEntry>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct UnwrapRepoFromNft<'info> {
+    #[account(mut)]
+    pub holder: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    #[account(
+        mut,
+        close = holder,
+        seeds = [b"nft", repository.id.as_ref()],
+        bump = repo_nft.bump,
+        has_one = mint
+    )]
+    pub repo_nft: Account<'info, RepoNft>,
+
+    pub mint: Account<'info, Mint>,
+
+    #[account(
+        constraint = holder_token_account.mint == mint.key()
+            && holder_token_account.owner == holder.key()
+            && holder_token_account.amount == 1 @ VanishError::InvalidNftMint
+    )]
+    pub holder_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = holder,
+        space = AuditEntry::SPACE,
+        seeds = [b"audit", repository.id.as_ref(), &repository.audit_count.to_le_bytes()],
+        bump
+    )]
+    pub audit_entry: Account<'info, AuditEntry>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct DeleteRepo<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump,
+        has_one = owner
+    )]
+    pub repository: Account<'info, Repository>,
+
+    /// CHECK: The repository's Timelock, empty if it never set one; read by
+    /// require_unlocked
+    #[account(seeds = [b"timelock", repository.id.as_ref()], bump)]
+    pub timelock: UncheckedAccount<'info>,
+
+    /// The queued action being carried out, required while timelocked
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"pending", repository.id.as_ref()],
+        bump = pending_action.bump
+    )]
+    pub pending_action: Option<Account<'info, PendingAction>>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = AuditEntry::SPACE,
+        seeds = [b"audit", repository.id.as_ref(), &repository.audit_count.to_le_bytes()],
+        bump
+    )]
+    pub audit_entry: Account<'info, AuditEntry>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct RestoreRepo<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump,
+        has_one = owner
+    )]
+    pub repository: Account<'info, Repository>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct PurgeRepo<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump,
+        has_one = owner
+    )]
+    pub repository: Account<'info, Repository>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = Tombstone::SPACE,
+        seeds = [b"tombstone", repository.key().as_ref()],
+        bump
+    )]
+    pub tombstone: Account<'info, Tombstone>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = PurgeReceipt::SPACE,
+        seeds = [b"purged", repository.id.as_ref()],
+        bump
+    )]
+    pub receipt: Account<'info, PurgeReceipt>,
+
+    /// The creator's profile, whose repository count the purge gives back
+    #[account(
+        mut,
+        seeds = [b"user", repository.creator.as_ref()],
+        bump = profile.bump
+    )]
+    pub profile: Option<Account<'info, Profile>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct SweepPurged<'info> {
+    #[account(seeds = [b"purged", receipt.repository.as_ref()], bump = receipt.bump)]
+    pub receipt: Account<'info, PurgeReceipt>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct ReleaseNameStake<'info> {
+    #[account(mut)]
+    pub staker: Signer<'info>,
+
+    #[account(
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        close = staker,
+        has_one = staker @ VanishError::Unauthorized,
+        constraint = name_stake.repository == repository.id @ VanishError::InvalidNameStake
+    )]
+    pub name_stake: Account<'info, NameStake>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct FileReclaimNotice<'info> {
+    #[account(mut)]
+    pub claimant: Signer<'info>,
+
+    #[account(
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        init,
+        payer = claimant,
+        space = ReclaimNotice::SPACE,
+        seeds = [b"reclaim", repository.id.as_ref()],
+        bump
+    )]
+    pub notice: Account<'info, ReclaimNotice>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct ContestReclaim<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    /// Required when the authority is an admin collaborator rather than the owner
+    #[account(
+        seeds = [b"collab", repository.id.as_ref(), authority.key().as_ref()],
+        bump = admin.bump
+    )]
+    pub admin: Option<Account<'info, Collaborator>>,
+
+    #[account(
+        mut,
+        close = claimant,
+        has_one = claimant,
+        seeds = [b"reclaim", repository.id.as_ref()],
+        bump = notice.bump
+    )]
+    pub notice: Account<'info, ReclaimNotice>,
+
+    /// CHECK: receives the notice's rent; must be its claimant
+    #[account(mut)]
+    pub claimant: UncheckedAccount<'info>,
+
+    #[account(mut, seeds = [b"treasury"], bump = config.treasury_bump)]
+    pub treasury: SystemAccount<'info>,
+
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct ReclaimRepo<'info> {
+    #[account(mut)]
+    pub claimant: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    #[account(
+        mut,
+        close = claimant,
+        has_one = claimant @ VanishError::Unauthorized,
+        seeds = [b"reclaim", repository.id.as_ref()],
+        bump = notice.bump
+    )]
+    pub notice: Account<'info, ReclaimNotice>,
+
+    #[account(
+        init,
+        payer = claimant,
+        space = AuditEntry::SPACE,
+        seeds = [b"audit", repository.id.as_ref(), &repository.audit_count.to_le_bytes()],
+        bump
+    )]
+    pub audit_entry: Account<'info, AuditEntry>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct ReclaimRepoName<'info> {
+    #[account(mut)]
+    pub claimant: Signer<'info>,
+
+    #[account(
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    #[account(
+        mut,
+        close = claimant,
+        has_one = claimant @ VanishError::Unauthorized,
+        seeds = [b"reclaim", repository.id.as_ref()],
+        bump = notice.bump
+    )]
+    pub notice: Account<'info, ReclaimNotice>,
+
+    #[account(
+        mut,
+        close = claimer,
+        has_one = claimer,
+        constraint = repo_name.repository == repository.id @ VanishError::InvalidRepoName,
+        seeds = [b"dir".as_ref(), &hash(repo_name.name.as_bytes()).to_bytes()],
+        bump = repo_name.bump
+    )]
+    pub repo_name: Account<'info, RepoName>,
+
+    /// CHECK: receives the entry's rent; must be its claimer
+    #[account(mut)]
+    pub claimer: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct SetRecoveryPlan<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump,
+        has_one = owner
+    )]
+    pub repository: Account<'info, Repository>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = RecoveryPlan::SPACE,
+        seeds = [b"recovery", repository.id.as_ref()],
+        bump
+    )]
+    pub recovery_plan: Account<'info, RecoveryPlan>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct RecoveryHeartbeat<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump,
+        has_one = owner
+    )]
+    pub repository: Account<'info, Repository>,
+
+    #[account(
+        mut,
+        seeds = [b"recovery", repository.id.as_ref()],
+        bump = recovery_plan.bump
+    )]
+    pub recovery_plan: Account<'info, RecoveryPlan>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct CancelRecoveryPlan<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump,
+        has_one = owner
+    )]
+    pub repository: Account<'info, Repository>,
+
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"recovery", repository.id.as_ref()],
+        bump = recovery_plan.bump
+    )]
+    pub recovery_plan: Account<'info, RecoveryPlan>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct ClaimRecovery<'info> {
+    #[account(mut)]
+    pub recovery_authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    #[account(
+        mut,
+        close = recovery_authority,
+        has_one = recovery_authority @ VanishError::Unauthorized,
+        seeds = [b"recovery", repository.id.as_ref()],
+        bump = recovery_plan.bump
+    )]
+    pub recovery_plan: Account<'info, RecoveryPlan>,
+
+    /// CHECK: The owner's Contribution account, empty if they never pushed;
+    /// its last push counts as activity
+    #[account(
+        seeds = [b"contribution", repository.id.as_ref(), repository.owner.as_ref()],
+        bump
+    )]
+    pub owner_contribution: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = recovery_authority,
+        space = AuditEntry::SPACE,
+        seeds = [b"audit", repository.id.as_ref(), &repository.audit_count.to_le_bytes()],
+        bump
+    )]
+    pub audit_entry: Account<'info, AuditEntry>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+#[instruction(slot: u64)]
+pub struct SnapshotContributors<'info> {
+    #[account(mut)]
+    pub taker: Signer<'info>,
+
+    #[account(
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    #[account(
+        init,
+        payer = taker,
+        space = ContributorSnapshot::SPACE,
+        seeds = [
+            b"snapshot",
+            repository.id.as_ref(),
+            taker.key().as_ref(),
+            &slot.to_le_bytes()
+        ],
+        bump
+    )]
+    pub snapshot: Account<'info, ContributorSnapshot>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct CheckpointRepo<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    /// The page the next checkpoint goes to
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = History::SPACE,
+        seeds = [
+            b"history",
+            repository.id.as_ref(),
+            &(repository.checkpoint_count / History::CAPACITY).to_le_bytes()
+        ],
+        bump
+    )]
+    pub history: Account<'info, History>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[cfg_attr(feature = "event-cpi", event_cpi)]
+pub struct CloseSnapshot<'info> {
+    #[account(mut)]
+    pub taker: Signer<'info>,
+
+    #[account(
+        mut,
+        close = taker,
+        has_one = taker @ VanishError::Unauthorized,
+        seeds = [
+            b"snapshot",
+            snapshot.repository.as_ref(),
+            taker.key().as_ref(),
+            &snapshot.slot.to_le_bytes()
+        ],
+        bump = snapshot.bump
+    )]
+    pub snapshot: Account<'info, ContributorSnapshot>,
+}
+
+#[derive(Accounts)]
+#[instruction(user: Pubkey)]
+pub struct CanPush<'info> {
+    #[account(
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    /// CHECK: The user's Collaborator account, empty if they are not one
+    #[account(seeds = [b"collab", repository.id.as_ref(), user.as_ref()], bump)]
+    pub collaborator_account: UncheckedAccount<'info>,
+
+    /// CHECK: The user's BranchGrant, empty if they have none
+    #[account(seeds = [b"branch_grant", repository.id.as_ref(), user.as_ref()], bump)]
+    pub branch_grant: UncheckedAccount<'info>,
+
+    /// CHECK: The repository's ProjectRepo link, empty unless it is in a
+    /// project
+    #[account(seeds = [b"project_repo", repository.id.as_ref()], bump)]
+    pub project_repo: UncheckedAccount<'info>,
+
+    /// CHECK: The user's ProjectMember account in the linked project, checked
+    /// against the link when decoded
+    pub project_member: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+#[instruction(user: Pubkey)]
+pub struct CanRead<'info> {
+    #[account(
+        seeds = [b"repo", repository.creator.as_ref(), repository.name.as_bytes()],
+        bump = repository.bump
+    )]
+    pub repository: Account<'info, Repository>,
+
+    /// CHECK: The user's Collaborator account, empty if they are not one
+    #[account(seeds = [b"collab", repository.id.as_ref(), user.as_ref()], bump)]
+    pub collaborator_account: UncheckedAccount<'info>,
+
+    /// CHECK: The user's ReadGrant, empty if they have none
+    #[account(seeds = [b"read", repository.id.as_ref(), user.as_ref()], bump)]
+    pub read_grant: UncheckedAccount<'info>,
+
+    /// CHECK: The user's AccessGrant, empty if they never bought access
+    #[account(seeds = [b"grant", repository.id.as_ref(), user.as_ref()], bump)]
+    pub access_grant: UncheckedAccount<'info>,
+}
+
+// ============================================================================
+// State
+// ============================================================================
+
+/// Program-wide settings, one per deployment
+#[account]
+pub struct Config {
+    /// Key allowed to change the config
+    pub admin: Pubkey,
+    /// Lamports charged for creating a repository, paid into the treasury
+    pub repo_fee: u64,
+    /// Program-wide switches, see `config_flags`
+    pub flags: u8,
+    pub bump: u8,
+    /// Bump of the `[b"treasury"]` PDA collecting fees
+    pub treasury_bump: u8,
+    /// Lamports escrowed when creating a repository with a short name
+    pub name_stake: u64,
+    /// Names shorter than this many bytes need the stake
+    pub stake_name_len: u8,
+    /// Seconds of activity after which the stake can be released
+    pub stake_period: i64,
+    /// Repositories one user may have created and not purged; 0 for no cap
+    pub max_repos: u32,
+    /// Names no repository may take, matched ignoring case
+    pub reserved_names: Vec<String>,
+    /// Seconds without a push or update before a repository can be
+    /// reclaimed; 0 turns reclamation off
+    pub reclaim_idle_period: i64,
+    /// Seconds the owner has to answer a reclaim notice
+    pub reclaim_grace_period: i64,
+    /// Lamports a claimant escrows with a reclaim notice, forfeited to the
+    /// treasury if the owner answers
+    pub reclaim_bond: u64,
+    /// Slots per rate limiting window; 0 turns rate limiting off
+    pub rate_window_slots: u64,
+    /// Repositories one signer may create per window; 0 for no limit
+    pub max_creations_per_window: u32,
+    /// Stars one signer may give per window; 0 for no limit
+    pub max_stars_per_window: u32,
+}
+
+impl Config {
+    pub const MAX_RESERVED_NAMES: usize = 16;
+    pub const MAX_RESERVED_NAME_LEN: usize = 32;
+
+    pub const SPACE: usize = 8  // discriminator
+        + 32  // admin
+        + 8  // repo_fee
+        + 1  // flags
+        + 1  // bump
+        + 1  // treasury_bump
+        + 8  // name_stake
+        + 1  // stake_name_len
+        + 8  // stake_period
+        + 4  // max_repos
+        + 4 + Self::MAX_RESERVED_NAMES * (4 + Self::MAX_RESERVED_NAME_LEN)  // reserved_names
+        + 8  // reclaim_idle_period
+        + 8  // reclaim_grace_period
+        + 8  // reclaim_bond
+        + 8  // rate_window_slots
+        + 4  // max_creations_per_window
+        + 4; // max_stars_per_window
+}
+
+/// Bits of `Config::flags`
+pub mod config_flags {
+    /// Reject create_repo
+    pub const PAUSE_CREATION: u8 = 1 << 0;
+
+    pub const ALL: u8 = PAUSE_CREATION;
+}
+
+/// Per-user state, created with the user's first repository
+#[account]
+pub struct Profile {
+    pub user: Pubkey,
+    /// Repositories the user created that have not been purged
+    pub repo_count: u32,
+    /// Replaces `Config::max_repos` for this user when non-zero
+    pub repo_limit: u32,
+    pub bump: u8,
+    /// Layout version, upgraded by migrate_account
+    pub version: u8,
+    /// Slot of the user's last rate limited action
+    pub last_action_slot: u64,
+    /// Repositories created in the window `last_action_slot` falls in
+    pub window_creations: u32,
+    /// Stars given in the window `last_action_slot` falls in
+    pub window_stars: u32,
+}
+
+impl Profile {
+    /// Version 1 adds the rate limiting counters
+    pub const VERSION: u8 = 1;
+
+    pub const SPACE: usize = 8  // discriminator
+        + 32  // user
+        + 4  // repo_count
+        + 4  // repo_limit
+        + 1  // bump
+        + 1  // version
+        + 8  // last_action_slot
+        + 4  // window_creations
+        + 4; // window_stars
+}
+
+#[account]
+pub struct Repository {
+    /// Current owner. `Pubkey::default()` once ownership is renounced, after
+    /// which the repository is administered by its admin collaborators.
+    pub owner: Pubkey,
+    /// Original owner, used in the PDA seeds so the address survives transfers
+    pub creator: Pubkey,
+    /// Classification bits (see `repo_flags`), at `FLAGS_OFFSET` for memcmp filters
+    pub flags: u8,
+    pub name: String,
+    pub description: String,
+    pub description_storage: DescriptionStorage,
+    pub is_private: bool,
+    pub key_epoch: u32,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub head_commit: ObjectId,
+    pub ipfs_cid: Cid,
+    /// Shared git object store the branch archives reference, rotated by gc_objects
+    pub objects_cid: String,
+    pub ipns_name: String,
+    pub stars: u64,
+    pub admin_count: u32,
+    /// Identity child accounts are seeded by, so they carry over renames.
+    /// The address the repository was created at, unless another repository
+    /// had left that address before (see `Repository::id_at`).
+    pub id: Pubkey,
+    pub bump: u8,
+    /// Layout version, upgraded by migrate_account
+    pub version: u8,
+    /// When delete_repo was called, 0 while the repository is live
+    pub deleted_at: i64,
+    /// When a deleted repository may be purged
+    pub purgeable_at: i64,
+    /// Root of the merkle tree over every branch tip (see `refs_tree`)
+    pub refs_root: [u8; 32],
+    /// Leaves used in the refs tree, one per branch ever pushed
+    pub ref_count: u32,
+    /// Trending score: each star adds `STAR_WEIGHT`, which halves every
+    /// `STAR_HALF_LIFE` seconds
+    pub weighted_stars: u64,
+    /// When `weighted_stars` was last decayed
+    pub stars_decayed_at: i64,
+    /// Entries in the audit log; the next AuditEntry is seeded by this
+    pub audit_count: u64,
+    /// Address of the repository that replaces this one once deprecated
+    pub successor: Option<Pubkey>,
+    /// `id` of the template the repository was created from
+    pub template: Option<Pubkey>,
+    /// Collaborators holding an index slot; slots run from 0 to this, so
+    /// clients can enumerate them without scanning program accounts
+    pub collaborator_count: u32,
+    /// Operators of the pinners enrolled to keep the archives available
+    pub pinners: Vec<Pubkey>,
+    /// README pinned apart from the archive; empty when unset
+    pub readme_cid: Cid,
+    /// Structured metadata (languages, links) pinned apart from the archive;
+    /// empty when unset
+    pub metadata_cid: Cid,
+    /// Forks ever created from the repository by fork_repo
+    pub forks: u32,
+    /// Checkpoints in the repository's History; the next goes to page
+    /// `checkpoint_count / History::CAPACITY`
+    pub checkpoint_count: u32,
+    /// Slot of the latest checkpoint
+    pub checkpointed_slot: u64,
+}
+
+impl Repository {
+    pub const SPACE: usize = 8  // discriminator
+        + 32  // owner
+        + 32  // creator
+        + 1  // flags
+        + 4 + 64  // name (string)
+        + 4 + 256  // description (string)
+        + 1  // description_storage
+        + 1  // is_private
+        + 4  // key_epoch
+        + 8  // created_at
+        + 8  // updated_at
+        + ObjectId::SPACE  // head_commit
+        + Cid::SPACE  // ipfs_cid
+        + 4 + 64  // objects_cid (string)
+        + 4 + 64  // ipns_name (string)
+        + 8  // stars
+        + 4  // admin_count
+        + 32  // id
+        + 1  // bump
+        + 1  // version
+        + 8  // deleted_at
+        + 8  // purgeable_at
+        + 32  // refs_root
+        + 4  // ref_count
+        + 8  // weighted_stars
+        + 8  // stars_decayed_at
+        + 8  // audit_count
+        + 1 + 32  // successor
+        + 1 + 32  // template
+        + 4  // collaborator_count
+        + 4 + 32 * Self::MAX_PINNERS  // pinners
+        + Cid::SPACE  // readme_cid
+        + Cid::SPACE  // metadata_cid
+        + 4  // forks
+        + 4  // checkpoint_count
+        + 8; // checkpointed_slot
+
+    /// Current layout version
+    pub const VERSION: u8 = 12;
+
+    /// Most pinners a repository can enroll
+    pub const MAX_PINNERS: usize = 8;
+
+    /// Shortest recovery window delete_repo accepts
+    pub const MIN_RECOVERY_WINDOW: i64 = 24 * 60 * 60;
+
+    /// Byte offset of `flags` in the account data. Every field before it is
+    /// fixed-size, so explorers can filter on it with `memcmp`.
+    pub const FLAGS_OFFSET: usize = 8 + 32 + 32;
+
+    /// The `id` of a repository created at `address` after `generation`
+    /// others have left it. The first takes the address itself; later ones
+    /// take a hash of it, so children a purged or renamed repository left
+    /// behind never attach to a newcomer.
+    pub fn id_at(address: &Pubkey, generation: u32) -> Pubkey {
+        if generation == 0 {
+            return *address;
+        }
+        Pubkey::new_from_array(
+            hashv(&[
+                b"vanish-repo-id",
+                address.as_ref(),
+                &generation.to_le_bytes(),
+            ])
+            .to_bytes(),
+        )
+    }
+
+    /// A fresh star's share of `weighted_stars`
+    pub const STAR_WEIGHT: u64 = 1 << 20;
+    /// Seconds for a star's weight to halve
+    pub const STAR_HALF_LIFE: i64 = 7 * 24 * 60 * 60;
+
+    /// `weight` after `elapsed` seconds of decay: shifted right once per
+    /// whole half-life, then scaled by 2^-x for the fraction x left over,
+    /// from a Taylor series in 32-bit fixed point. Decaying in steps gives
+    /// the same result as decaying at once, up to rounding.
+    pub fn decayed(weight: u64, elapsed: i64) -> u64 {
+        const ONE: i128 = 1 << 32;
+        const LN_2: i128 = 2_977_044_472; // ln 2 * 2^32
+
+        let elapsed = elapsed.max(0);
+        let halvings = elapsed / Self::STAR_HALF_LIFE;
+        if halvings >= 64 {
+            return 0;
+        }
+
+        // e^-y with y = x ln 2
+        let y = (elapsed % Self::STAR_HALF_LIFE) as i128 * LN_2 / Self::STAR_HALF_LIFE as i128;
+        let (mut term, mut factor) = (ONE, ONE);
+        for k in 1..=8 {
+            term = -term * y / ONE / k;
+            factor += term;
+        }
+
+        ((weight >> halvings) as i128 * factor / ONE) as u64
+    }
+
+    /// Apply the decay since `stars_decayed_at` to `weighted_stars`
+    pub fn decay_stars(&mut self, now: i64) {
+        self.weighted_stars = Self::decayed(self.weighted_stars, now - self.stars_decayed_at);
+        self.stars_decayed_at = now;
+    }
+}
+
+/// Left under a repository's old name when it is renamed
+#[account]
+pub struct Redirect {
+    pub creator: Pubkey,
+    /// Current repository address
+    pub target: Pubkey,
+    /// Old name
+    pub name: String,
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+impl Redirect {
+    pub const SPACE: usize = 8  // discriminator
+        + 32  // creator
+        + 32  // target
+        + 4 + 64  // name (string)
+        + 8  // created_at
+        + 1; // bump
+}
+
+/// Global directory entry for a repository name, at
+/// `[b"dir", sha256(name)]` since names can exceed the seed limit
+#[account]
+pub struct RepoName {
+    pub name: String,
+    /// With `name`, gives the repository's `[b"repo", creator, name]` address
+    pub creator: Pubkey,
+    /// `id` of the repository that claimed the name
+    pub repository: Pubkey,
+    /// Who paid the rent and gets it back on release
+    pub claimer: Pubkey,
+    pub claimed_at: i64,
+    pub bump: u8,
+}
+
+impl RepoName {
+    pub const SPACE: usize = 8  // discriminator
+        + 4 + 64  // name (string)
+        + 32  // creator
+        + 32  // repository
+        + 32  // claimer
+        + 8  // claimed_at
+        + 1; // bump
+}
+
+/// Claims a name, ignoring case, among its creator's repositories, at
+/// `[b"name_lock", creator, sha256(lowercased name)]`. Moves with renames
+/// and is closed when the repository is purged.
+#[account]
+pub struct NameLock {
+    /// `id` of the repository holding the name
+    pub repository: Pubkey,
+    pub bump: u8,
+}
+
+impl NameLock {
+    pub const SPACE: usize = 8  // discriminator
+        + 32  // repository
+        + 1; // bump
+}
+
+/// Left at `[b"tombstone", address]` once a repository has been renamed
+/// away from `address` or purged, counting the repositories that have left
+/// it. The next repository created there takes `Repository::id_at(address,
+/// generation)`.
+#[account]
+pub struct Tombstone {
+    pub address: Pubkey,
+    pub generation: u32,
+    pub bump: u8,
+}
+
+impl Tombstone {
+    pub const SPACE: usize = 8  // discriminator
+        + 32  // address
+        + 4  // generation
+        + 1; // bump
+}
+
+/// Proof that the repository with `id` was purged, at `[b"purged", id]`.
+/// Children it left behind can be closed by anyone through sweep_purged.
+#[account]
+pub struct PurgeReceipt {
+    /// `id` of the purged repository
+    pub repository: Pubkey,
+    /// Owner at the purge, who gets back the rent of children that would
+    /// have gone to the owner
+    pub owner: Pubkey,
+    pub purged_at: i64,
+    pub bump: u8,
+}
+
+impl PurgeReceipt {
+    pub const SPACE: usize = 8  // discriminator
+        + 32  // repository
+        + 32  // owner
+        + 8  // purged_at
+        + 1; // bump
+}
+
+/// Lamports a repository's creator escrowed for claiming a short name, on
+/// top of the account's rent
+#[account]
+pub struct NameStake {
+    pub repository: Pubkey,
+    /// Who gets the stake back
+    pub staker: Pubkey,
+    pub amount: u64,
+    pub staked_at: i64,
+    pub bump: u8,
+}
+
+impl NameStake {
+    pub const SPACE: usize = 8  // discriminator
+        + 32  // repository
+        + 32  // staker
+        + 8  // amount
+        + 8  // staked_at
+        + 1; // bump
+}
+
+/// A claim on an abandoned repository, at `[b"reclaim", id]`, escrowing the
+/// claimant's bond on top of the account's rent
+#[account]
+pub struct ReclaimNotice {
+    pub repository: Pubkey,
+    pub claimant: Pubkey,
+    pub bond: u64,
+    pub filed_at: i64,
+    /// When the owner's grace period ends and the claim can go ahead
+    pub deadline: i64,
+    pub bump: u8,
+}
+
+impl ReclaimNotice {
+    pub const SPACE: usize = 8  // discriminator
+        + 32  // repository
+        + 32  // claimant
+        + 8  // bond
+        + 8  // filed_at
+        + 8  // deadline
+        + 1; // bump
+}
+
+/// An owner's backup plan, at `[b"recovery", id]`: who takes the
+/// repository over if the owner goes quiet, and after how long
+#[account]
+pub struct RecoveryPlan {
+    pub repository: Pubkey,
+    /// Owner who set the plan, the only owner it applies to
+    pub owner: Pubkey,
+    pub recovery_authority: Pubkey,
+    /// Seconds without a heartbeat or owner push before the authority can
+    /// claim
+    pub inactivity_period: i64,
+    pub last_heartbeat: i64,
+    pub bump: u8,
+}
+
+impl RecoveryPlan {
+    pub const SPACE: usize = 8  // discriminator
+        + 32  // repository
+        + 32  // owner
+        + 32  // recovery_authority
+        + 8  // inactivity_period
+        + 8  // last_heartbeat
+        + 1; // bump
+}
+
+/// A repository's contributors, stargazers and collaborators frozen into a
+/// merkle root, at `[b"snapshot", id, taker, slot]`. Outlives the
+/// repository, so an airdrop can finish after it is deleted.
+#[account]
+pub struct ContributorSnapshot {
+    /// Repository id
+    pub repository: Pubkey,
+    pub taker: Pubkey,
+    /// Slot the snapshot was taken in
+    pub slot: u64,
+    /// Root of the tree over the leaves in `snapshot_tree`
+    pub root: [u8; 32],
+    pub contributions: u32,
+    pub stars: u32,
+    pub collaborators: u32,
+    pub bump: u8,
+}
+
+impl ContributorSnapshot {
+    pub const SPACE: usize = 8  // discriminator
+        + 32  // repository
+        + 32  // taker
+        + 8  // slot
+        + 32  // root
+        + 4  // contributions
+        + 4  // stars
+        + 4  // collaborators
+        + 1; // bump
+}
+
+/// One page of a repository's growth history, at `[b"history", id, page]`:
+/// checkpoints `page * CAPACITY` onwards, oldest first, as appended by
+/// checkpoint_repo. Outlives the repository.
+#[account]
+pub struct History {
+    /// Repository id
+    pub repository: Pubkey,
+    pub page: u32,
+    pub bump: u8,
+    pub checkpoints: Vec<Checkpoint>,
+}
+
+impl History {
+    /// Checkpoints per page
+    pub const CAPACITY: u32 = 64;
+
+    /// Fewest slots between checkpoints, about a day
+    pub const INTERVAL_SLOTS: u64 = 216_000;
+
+    pub const SPACE: usize = 8  // discriminator
+        + 32  // repository
+        + 4  // page
+        + 1  // bump
+        + 4 + Checkpoint::SPACE * Self::CAPACITY as usize; // checkpoints
+}
+
+/// A repository's counts as of `slot`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct Checkpoint {
+    pub slot: u64,
+    pub stars: u64,
+    pub forks: u32,
+}
+
+impl Checkpoint {
+    pub const SPACE: usize = 8  // slot
+        + 8  // stars
+        + 4; // forks
+}
+
+/// Bits of `Repository::flags`
+pub mod repo_flags {
+    /// Forked from another repository. Set by fork_repo alone
+    pub const FORK: u8 = 1 << 0;
+    /// Mirror of a repository hosted elsewhere
+    pub const MIRROR: u8 = 1 << 1;
+    /// Generated content (build output, vendored code, bots)
+    pub const GENERATED: u8 = 1 << 2;
+    /// Read-only; pushes are rejected
+    pub const ARCHIVED: u8 = 1 << 3;
+    /// Pushes may replace the head without naming the commit they expect
+    pub const ALLOW_FORCE_PUSH: u8 = 1 << 4;
+    /// Only the owner may push. Set by the owner alone, through set_frozen
+    pub const FROZEN: u8 = 1 << 5;
+    /// Superseded, possibly by `Repository::successor`. Set through
+    /// deprecate_repo
+    pub const DEPRECATED: u8 = 1 << 6;
+    /// Others may start repositories from it with create_from_template
+    pub const TEMPLATE: u8 = 1 << 7;
+
+    /// Bits set_repo_flags may change
+    pub const ALL: u8 = MIRROR | GENERATED | ARCHIVED | ALLOW_FORCE_PUSH | TEMPLATE;
+}
+
+/// Event classes a webhook can subscribe to, as bits of `Webhook::events`
+pub mod webhook_events {
+    /// Pushes to the default branch
+    pub const PUSH: u32 = 1 << 0;
+    /// Pushes to other branches
+    pub const BRANCH_PUSH: u32 = 1 << 1;
+    /// Collaborators added, indexed or removed
+    pub const COLLABORATOR: u32 = 1 << 2;
+    /// Stars and unstars
+    pub const STAR: u32 = 1 << 3;
+    /// Renames, ownership changes, deletion, restoration and purging
+    pub const LIFECYCLE: u32 = 1 << 4;
+
+    pub const ALL: u32 = PUSH | BRANCH_PUSH | COLLABORATOR | STAR | LIFECYCLE;
+}
+
+/// Where a repository's description lives
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum DescriptionStorage {
+    /// `description` holds the text itself
+    Inline,
+    /// `description` holds the IPFS CID of the text, for descriptions or
+    /// READMEs too long to keep on-chain
+    Ipfs,
+}
+
+/// A Repository as first deployed, before layouts carried a version, read
+/// only by migrate_account. Accounts at this layout are exactly `SPACE`
+/// bytes long, which no later layout is.
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct LegacyRepository {
+    pub owner: Pubkey,
+    pub name: String,
+    pub description: String,
+    pub is_private: bool,
+    pub created_at: i64,
+    pub updated_at: i64,
+    /// Hex SHA-1 commit id, empty before the first push
+    pub head_commit: String,
+    pub ipfs_cid: String,
+    pub stars: u64,
+    pub bump: u8,
+}
+
+impl LegacyRepository {
+    pub const SPACE: usize = 8  // discriminator
+        + 32  // owner
+        + 4 + 64  // name (string)
+        + 4 + 256  // description (string)
+        + 1  // is_private
+        + 8  // created_at
+        + 8  // updated_at
+        + 4 + 40  // head_commit (string)
+        + 4 + 64  // ipfs_cid (string)
+        + 8  // stars
+        + 1; // bump
+
+    /// The repository at `address` in the current layout. These accounts
+    /// were seeded by their owner, so one whose ownership was transferred no
+    /// longer matches its seeds and cannot be placed; a head or CID that
+    /// does not parse fails the migration rather than losing the archive.
+    fn upgrade(self, address: Pubkey) -> Result<Repository> {
+        let seeds: &[&[u8]] = &[
+            b"repo",
+            self.owner.as_ref(),
+            self.name.as_bytes(),
+            &[self.bump],
+        ];
+        require!(
+            Pubkey::create_program_address(seeds, &crate::ID).ok() == Some(address),
+            VanishError::UnsupportedAccount
+        );
+
+        let head_commit = if self.head_commit.is_empty() {
+            ObjectId::default()
+        } else {
+            let hex = self.head_commit.as_bytes();
+            require!(
+                hex.len() == 40 && hex.iter().all(u8::is_ascii_hexdigit),
+                VanishError::InvalidCommitHash
+            );
+            let digest = hex
+                .chunks(2)
+                .map(|pair| u8::from_str_radix(std::str::from_utf8(pair).unwrap(), 16).unwrap())
+                .collect();
+            ObjectId::from_multihash(&Multihash {
+                code: Multihash::SHA1,
+                digest,
+            })
+            .ok_or(VanishError::InvalidCommitHash)?
+        };
+        let ipfs_cid = Cid::parse(&self.ipfs_cid).ok_or(VanishError::InvalidIpfsCid)?;
+
+        Ok(Repository {
+            owner: self.owner,
+            creator: self.owner,
+            flags: 0,
+            name: self.name,
+            description: self.description,
+            description_storage: DescriptionStorage::Inline,
+            is_private: self.is_private,
+            key_epoch: 0,
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+            head_commit,
+            ipfs_cid,
+            objects_cid: String::new(),
+            ipns_name: String::new(),
+            stars: self.stars,
+            admin_count: 0,
+            id: address,
+            bump: self.bump,
+            version: Repository::VERSION,
+            deleted_at: 0,
+            purgeable_at: 0,
+            refs_root: [0; 32],
+            ref_count: 0,
+            weighted_stars: 0,
+            stars_decayed_at: 0,
+            audit_count: 0,
+            successor: None,
+            template: None,
+            collaborator_count: 0,
+            pinners: Vec::new(),
+            readme_cid: Cid::default(),
+            metadata_cid: Cid::default(),
+            forks: 0,
+            checkpoint_count: 0,
+            checkpointed_slot: 0,
+        })
+    }
+}
+
+/// A self-describing digest: a multicodec hash function code and the digest
+/// itself. Supporting a new hash function only needs a new code, not a new
+/// account layout.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default, PartialEq, Eq)]
+pub struct Multihash {
+    pub code: u64,
+    pub digest: Vec<u8>,
+}
+
+impl Multihash {
+    pub const SHA1: u64 = 0x11;
+    pub const SHA2_256: u64 = 0x12;
+    pub const SHA2_512: u64 = 0x13;
+    pub const BLAKE3: u64 = 0x1e;
+
+    pub const MAX_DIGEST_LEN: usize = 64;
+
+    pub const SPACE: usize = 8  // code
+        + 4 + Self::MAX_DIGEST_LEN; // digest (vec)
+
+    /// Digest length of a supported hash function
+    pub fn digest_len(code: u64) -> Option<usize> {
+        match code {
+            Self::SHA1 => Some(20),
+            Self::SHA2_256 | Self::BLAKE3 => Some(32),
+            Self::SHA2_512 => Some(64),
+            _ => None,
+        }
+    }
+
+    /// Whether the hash function is supported and the digest has its length
+    pub fn is_valid(&self) -> bool {
+        Self::digest_len(self.code) == Some(self.digest.len())
+    }
+
+    /// Whether this can name a git object: SHA-1, or SHA-256 for
+    /// repositories using git's newer object format
+    pub fn is_git_object_id(&self) -> bool {
+        matches!(self.code, Self::SHA1 | Self::SHA2_256) && self.is_valid()
+    }
+
+    /// Fixed-size PDA seed, since digests may be longer than a seed allows
+    pub fn seed(&self) -> [u8; 32] {
+        hashv(&[&self.code.to_le_bytes(), &self.digest]).to_bytes()
+    }
+}
+
+/// A git object id stored at a fixed size, so the repository head costs the
+/// same to write whichever object format the repository uses. SHA-1 ids fill
+/// the first 20 bytes of `bytes` and leave the rest zeroed.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ObjectId {
+    /// `Multihash::SHA1` or `Multihash::SHA2_256`, 0 before the first push
+    pub code: u8,
+    pub bytes: [u8; 32],
+}
+
+impl ObjectId {
+    pub const SPACE: usize = 1  // code
+        + 32; // bytes
+
+    /// `None` unless `commit` names a git object
+    pub fn from_multihash(commit: &Multihash) -> Option<Self> {
+        if !commit.is_git_object_id() {
+            return None;
+        }
+        let mut bytes = [0; 32];
+        bytes[..commit.digest.len()].copy_from_slice(&commit.digest);
+        Some(Self {
+            code: commit.code as u8,
+            bytes,
+        })
+    }
+
+    /// The id's digest, empty before the first push
+    pub fn digest(&self) -> &[u8] {
+        let len = Multihash::digest_len(u64::from(self.code)).unwrap_or(0);
+        &self.bytes[..len]
+    }
+
+    /// The id as instructions and events carry it
+    pub fn to_multihash(&self) -> Multihash {
+        Multihash {
+            code: u64::from(self.code),
+            digest: self.digest().to_vec(),
+        }
+    }
+}
+
+impl PartialEq<Multihash> for ObjectId {
+    fn eq(&self, other: &Multihash) -> bool {
+        u64::from(self.code) == other.code && self.digest() == other.digest.as_slice()
+    }
+}
+
+/// A CID in binary form rather than the multibase text IPFS prints, so
+/// pushes neither parse text nor resize the account. Clients convert with
+/// `parse` and `Display`, which use base58btc for CIDv0 and base32 for
+/// CIDv1, as IPFS does.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct Cid {
+    pub len: u8,
+    pub bytes: [u8; Cid::MAX_LEN],
+}
+
+impl Default for Cid {
+    fn default() -> Self {
+        Self {
+            len: 0,
+            bytes: [0; Self::MAX_LEN],
+        }
+    }
+}
+
+impl Cid {
+    /// Longest binary CID stored: a CIDv1 with a 32-byte digest takes 36
+    pub const MAX_LEN: usize = 40;
+
+    pub const SPACE: usize = 1  // len
+        + Self::MAX_LEN; // bytes
+
+    /// `None` when `bytes` is longer than `MAX_LEN`
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut cid = Self::default();
+        cid.bytes.get_mut(..bytes.len())?.copy_from_slice(bytes);
+        cid.len = bytes.len() as u8;
+        Some(cid)
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes[..usize::from(self.len).min(Self::MAX_LEN)]
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Whether the CID is empty, a CIDv0 (a bare SHA-256 multihash), or a
+    /// CIDv1 whose codec and multihash lengths add up
+    pub fn is_valid(&self) -> bool {
+        if usize::from(self.len) > Self::MAX_LEN {
+            return false;
+        }
+        let bytes = self.as_bytes();
+        match bytes {
+            [] => true,
+            [0x12, 0x20, digest @ ..] => digest.len() == 32,
+            [1, rest @ ..] => {
+                let mut rest = rest;
+                let (Some(_codec), Some(_hash), Some(len)) = (
+                    read_varint(&mut rest),
+                    read_varint(&mut rest),
+                    read_varint(&mut rest),
+                ) else {
+                    return false;
+                };
+                len == rest.len() as u64
+            }
+            _ => false,
+        }
+    }
+
+    /// Parse a CID's text form: base58btc CIDv0 (`Qm...`), or a CIDv1 in
+    /// base32 (`b...`) or base58btc (`z...`)
+    pub fn parse(text: &str) -> Option<Self> {
+        let bytes = if text.is_empty() {
+            Vec::new()
+        } else if text.starts_with("Qm") {
+            multibase::decode_base58(text)?
+        } else if let Some(rest) = text.strip_prefix('b') {
+            multibase::decode_base32(rest)?
+        } else if let Some(rest) = text.strip_prefix('z') {
+            multibase::decode_base58(rest)?
+        } else {
+            return None;
+        };
+        Self::from_bytes(&bytes).filter(Self::is_valid)
+    }
+}
+
+impl std::fmt::Display for Cid {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.as_bytes() {
+            [] => Ok(()),
+            bytes @ [0x12, 0x20, ..] => f.write_str(&multibase::encode_base58(bytes)),
+            bytes => write!(f, "b{}", multibase::encode_base32(bytes)),
+        }
+    }
+}
+
+/// An unsigned LEB128 varint off the front of `bytes`, as multiformats use
+fn read_varint(bytes: &mut &[u8]) -> Option<u64> {
+    let mut value = 0u64;
+    for (i, byte) in bytes.iter().enumerate().take(9) {
+        value |= u64::from(byte & 0x7f) << (7 * i);
+        if byte & 0x80 == 0 {
+            *bytes = &bytes[i + 1..];
+            return Some(value);
+        }
+    }
+    None
+}
+
+/// The two multibase encodings CIDs are printed in
+mod multibase {
+    const BASE58: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+    const BASE32: &[u8; 32] = b"abcdefghijklmnopqrstuvwxyz234567";
+
+    pub fn encode_base58(bytes: &[u8]) -> String {
+        // Base 58 digits, least significant first
+        let mut digits: Vec<u8> = Vec::new();
+        for &byte in bytes {
+            let mut carry = u32::from(byte);
+            for digit in digits.iter_mut() {
+                carry += u32::from(*digit) << 8;
+                *digit = (carry % 58) as u8;
+                carry /= 58;
+            }
+            while carry > 0 {
+                digits.push((carry % 58) as u8);
+                carry /= 58;
+            }
+        }
+        let zeros = bytes.iter().take_while(|&&byte| byte == 0).count();
+        let mut text = "1".repeat(zeros);
+        text.extend(
+            digits
+                .iter()
+                .rev()
+                .map(|&digit| BASE58[usize::from(digit)] as char),
+        );
+        text
+    }
+
+    pub fn decode_base58(text: &str) -> Option<Vec<u8>> {
+        // Bytes, least significant first
+        let mut bytes: Vec<u8> = Vec::new();
+        for c in text.bytes() {
+            let mut carry = BASE58.iter().position(|&digit| digit == c)? as u32;
+            for byte in bytes.iter_mut() {
+                carry += u32::from(*byte) * 58;
+                *byte = carry as u8;
+                carry >>= 8;
+            }
+            while carry > 0 {
+                bytes.push(carry as u8);
+                carry >>= 8;
+            }
+        }
+        let zeros = text.bytes().take_while(|&c| c == b'1').count();
+        bytes.resize(bytes.len() + zeros, 0);
+        bytes.reverse();
+        Some(bytes)
+    }
+
+    /// RFC 4648 base32, lowercase and unpadded
+    pub fn encode_base32(bytes: &[u8]) -> String {
+        let mut text = String::with_capacity(bytes.len() * 8 / 5 + 1);
+        let (mut buffer, mut bits) = (0u32, 0);
+        for &byte in bytes {
+            buffer = buffer << 8 | u32::from(byte);
+            bits += 8;
+            while bits >= 5 {
+                bits -= 5;
+                text.push(BASE32[(buffer >> bits) as usize & 31] as char);
+            }
+        }
+        if bits > 0 {
+            text.push(BASE32[(buffer << (5 - bits)) as usize & 31] as char);
+        }
+        text
+    }
+
+    pub fn decode_base32(text: &str) -> Option<Vec<u8>> {
+        let mut bytes = Vec::with_capacity(text.len() * 5 / 8);
+        let (mut buffer, mut bits) = (0u32, 0);
+        for c in text.bytes() {
+            let value = BASE32.iter().position(|&digit| digit == c)? as u32;
+            buffer = buffer << 5 | value;
+            bits += 5;
+            if bits >= 8 {
+                bits -= 8;
+                bytes.push((buffer >> bits) as u8);
+            }
+        }
+        Some(bytes)
+    }
+}
+
+/// A branch's refs archive. Objects are shared through the repository's
+/// `objects_cid`. Branch names can exceed the 32-byte seed limit, so the PDA
+/// is derived from the SHA-256 of the name.
+#[account]
+pub struct Branch {
+    pub repository: Pubkey,
+    pub name: String,
+    pub head_commit: Multihash,
+    pub ipfs_cid: String,
+    pub updated_at: i64,
+    pub bump: u8,
+    /// Pushes may replace the head without naming the commit they expect
+    pub allow_force_push: bool,
+    /// Layout version, upgraded by migrate_account
+    pub version: u8,
+    /// Leaf in the repository's refs tree, `None` until the branch is pushed
+    /// under the current layout
+    pub ref_index: Option<u32>,
+}
+
+impl Branch {
+    pub const SPACE: usize = 8  // discriminator
+        + 32  // repository
+        + 4 + 64  // name (string)
+        + Multihash::SPACE  // head_commit
+        + 4 + 64  // ipfs_cid (string)
+        + 8  // updated_at
+        + 1  // bump
+        + 1  // allow_force_push
+        + 1  // version
+        + 1 + 4; // ref_index (option)
+
+    /// Current layout version
+    pub const VERSION: u8 = 2;
+}
+
+/// The sparse merkle tree over a repository's branch tips, whose root is
+/// `Repository::refs_root`. A branch takes the next free leaf the first time
+/// it is pushed. Empty subtrees hash to zero, so an empty tree's root is
+/// zero too. Light clients rebuild the tree from a ref listing served
+/// off-chain to check the listing is complete and current.
+pub mod refs_tree {
+    use super::*;
+
+    pub const DEPTH: usize = 12;
+    pub const CAPACITY: u32 = 1 << DEPTH;
+
+    pub fn leaf(name: &str, tip: &Multihash) -> [u8; 32] {
+        hashv(&[
+            b"ref",
+            &(name.len() as u32).to_le_bytes(),
+            name.as_bytes(),
+            &tip.code.to_le_bytes(),
+            &tip.digest,
+        ])
+        .to_bytes()
+    }
+
+    pub fn parent(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+        if *left == [0; 32] && *right == [0; 32] {
+            return [0; 32];
+        }
+        hashv(&[left, right]).to_bytes()
+    }
+
+    /// Root of the tree with `leaf` at `index` and `proof` holding its
+    /// siblings from the bottom up
+    pub fn root_with(leaf: [u8; 32], index: u32, proof: &[[u8; 32]]) -> [u8; 32] {
+        proof
+            .iter()
+            .enumerate()
+            .fold(leaf, |node, (level, sibling)| {
+                if index >> level & 1 == 0 {
+                    parent(&node, sibling)
+                } else {
+                    parent(sibling, &node)
+                }
+            })
+    }
+
+    /// Root of the tree holding `leaves`, in leaf order
+    pub fn root(leaves: &[[u8; 32]]) -> [u8; 32] {
+        levels(leaves)[DEPTH].first().copied().unwrap_or_default()
+    }
+
+    /// Proof for the leaf at `index` of the tree holding `leaves`
+    pub fn proof(leaves: &[[u8; 32]], index: u32) -> Vec<[u8; 32]> {
+        levels(leaves)[..DEPTH]
+            .iter()
+            .enumerate()
+            .map(|(level, nodes)| {
+                nodes
+                    .get((index as usize >> level) ^ 1)
+                    .copied()
+                    .unwrap_or_default()
+            })
+            .collect()
+    }
+
+    /// Every level of the tree, leaves first, without the empty subtrees
+    /// to their right
+    fn levels(leaves: &[[u8; 32]]) -> Vec<Vec<[u8; 32]>> {
+        let mut levels = vec![leaves.to_vec()];
+        for level in 0..DEPTH {
+            let above = levels[level]
+                .chunks(2)
+                .map(|pair| parent(&pair[0], pair.get(1).unwrap_or(&[0; 32])))
+                .collect();
+            levels.push(above);
+        }
+        levels
+    }
+}
+
+/// Leaves of a ContributorSnapshot's tree, one per snapshotted account in
+/// ascending address order. The tree is shaped like `refs_tree`, so airdrop
+/// claims are checked with `refs_tree::root_with` against
+/// `ContributorSnapshot::root`.
+pub mod snapshot_tree {
+    use super::*;
+
+    pub fn contribution(contributor: &Pubkey, push_count: u64) -> [u8; 32] {
+        hashv(&[
+            b"contribution",
+            contributor.as_ref(),
+            &push_count.to_le_bytes(),
+        ])
+        .to_bytes()
+    }
+
+    pub fn star(user: &Pubkey) -> [u8; 32] {
+        hashv(&[b"star", user.as_ref()]).to_bytes()
+    }
+
+    pub fn collaborator(user: &Pubkey, can_push: bool, is_admin: bool) -> [u8; 32] {
+        hashv(&[
+            b"collaborator",
+            user.as_ref(),
+            &[can_push as u8, is_admin as u8],
+        ])
+        .to_bytes()
+    }
+}
+
+/// What a push changed, for release notes and commit summaries that can be
+/// read without unpacking the archive
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct PushNote {
+    /// CID of a changelog for the push, empty for none
+    pub changelog_cid: String,
+    /// One-line summary, such as the head commit's subject, empty for none
+    pub message: String,
+}
+
+impl PushNote {
+    /// Both fields fit a PushEntry, which pads them with zero bytes, and at
+    /// least one is set.
+    pub fn is_valid(&self) -> bool {
+        (!self.changelog_cid.is_empty() || !self.message.is_empty())
+            && self.changelog_cid.len() <= PushEntry::MAX_CHANGELOG_CID_LEN
+            && self.message.len() <= PushEntry::MAX_MESSAGE_LEN
+            && !self.changelog_cid.contains('\0')
+            && !self.message.contains('\0')
+    }
+}
+
+/// The repository head's new tip in push_refs, with the tip it is expected
+/// to replace
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct HeadUpdate {
+    pub head_commit: Multihash,
+    pub ipfs_cid: Cid,
+    pub expected_head: Option<Multihash>,
+}
+
+/// A branch's new tip in push_refs, with the tip it is expected to replace
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct RefUpdate {
+    pub head_commit: Multihash,
+    pub ipfs_cid: String,
+    pub expected_head: Option<Multihash>,
+    /// The branch's proof in the refs tree
+    pub proof: Vec<[u8; 32]>,
+}
+
+/// A fork's link to the repository it syncs from
+#[account]
+pub struct ForkLink {
+    pub repository: Pubkey,
+    /// `id` of the upstream repository
+    pub upstream: Pubkey,
+    /// Commit the last sync copied
+    pub synced_commit: Multihash,
+    pub synced_at: i64,
+    pub bump: u8,
+}
+
+impl ForkLink {
+    pub const SPACE: usize = 8  // discriminator
+        + 32  // repository
+        + 32  // upstream
+        + Multihash::SPACE  // synced_commit
+        + 8  // synced_at
+        + 1; // bump
+}
+
+/// Another repository pinned at a commit under a path, like a git submodule
+#[account]
+pub struct Submodule {
+    pub repository: Pubkey,
+    pub path: String,
+    /// `id` of the repository pinned
+    pub target: Pubkey,
+    pub commit: Multihash,
+    pub updated_at: i64,
+    pub bump: u8,
+}
+
+impl Submodule {
+    pub const SPACE: usize = 8  // discriminator
+        + 32  // repository
+        + 4 + 128  // path (string)
+        + 32  // target
+        + Multihash::SPACE  // commit
+        + 8  // updated_at
+        + 1; // bump
+}
+
+#[account]
+pub struct Collaborator {
+    pub repository: Pubkey,
+    pub user: Pubkey,
+    pub can_push: bool,
+    pub is_admin: bool,
+    pub added_at: i64,
+    pub last_push_at: i64,
+    pub bump: u8,
+    /// Layout version, upgraded by migrate_account
+    pub version: u8,
+    /// Index of the collaborator's CollaboratorSlot. `None` for collaborators
+    /// added before slots existed, until index_collaborator assigns one.
+    pub index: Option<u32>,
+}
+
+impl Collaborator {
+    pub const SPACE: usize = 8  // discriminator
+        + 32  // repository
+        + 32  // user
+        + 1  // can_push
+        + 1  // is_admin
+        + 8  // added_at
+        + 8  // last_push_at
+        + 1  // bump
+        + 1  // version
+        + 1 + 4; // index
+
+    /// Current layout version
+    pub const VERSION: u8 = 2;
+}
+
+/// A Collaborator as first deployed, before `is_admin` and `last_push_at`
+/// were inserted ahead of `bump`, read only by migrate_account. Accounts at
+/// this layout are exactly `SPACE` bytes long, which no later layout is.
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct LegacyCollaborator {
+    pub repository: Pubkey,
+    pub user: Pubkey,
+    pub can_push: bool,
+    pub added_at: i64,
+    pub bump: u8,
+}
+
+impl LegacyCollaborator {
+    pub const SPACE: usize = 8  // discriminator
+        + 32  // repository
+        + 32  // user
+        + 1  // can_push
+        + 8  // added_at
+        + 1; // bump
+
+    /// The collaborator in the current layout, without admin rights, since
+    /// the first layout had none to carry over
+    fn upgrade(self) -> Collaborator {
+        Collaborator {
+            repository: self.repository,
+            user: self.user,
+            can_push: self.can_push,
+            is_admin: false,
+            added_at: self.added_at,
+            last_push_at: 0,
+            bump: self.bump,
+            version: Collaborator::VERSION,
+            index: None,
+        }
+    }
+}
+
+/// Branches a collaborator may push without repository-wide push access
+#[account]
+pub struct BranchGrant {
+    /// `id` of the repository
+    pub repository: Pubkey,
+    pub user: Pubkey,
+    /// Branch names, or prefixes ending in `*`
+    pub patterns: Vec<String>,
+    pub bump: u8,
+}
+
+impl BranchGrant {
+    pub const MAX_PATTERNS: usize = 4;
+
+    pub const SPACE: usize = 8  // discriminator
+        + 32  // repository
+        + 32  // user
+        + 4 + Self::MAX_PATTERNS * (4 + 64)  // patterns
+        + 1; // bump
+
+    /// A branch name of at most 64 bytes, optionally ending in a `*` that
+    /// matches any suffix. `*` may not appear anywhere else.
+    pub fn is_valid_pattern(pattern: &str) -> bool {
+        let name = pattern.strip_suffix('*').unwrap_or(pattern);
+        !pattern.is_empty() && pattern.len() <= 64 && !name.contains('*')
+    }
+
+    /// Whether any of the patterns covers `branch`
+    pub fn allows(&self, branch: &str) -> bool {
+        self.patterns
+            .iter()
+            .any(|pattern| match pattern.strip_suffix('*') {
+                Some(prefix) => branch.starts_with(prefix),
+                None => branch == pattern,
+            })
+    }
+}
+
+/// A URL to notify of a repository's events, known on chain only by hash
+#[account]
+pub struct Webhook {
+    /// `id` of the repository
+    pub repository: Pubkey,
+    /// SHA-256 of the URL
+    pub url_hash: [u8; 32],
+    /// `commitment` of the secret deliveries are signed with
+    pub secret_commitment: [u8; 32],
+    /// `webhook_events` bits
+    pub events: u32,
+    pub bump: u8,
+}
+
+impl Webhook {
+    pub const SPACE: usize = 8  // discriminator
+        + 32  // repository
+        + 32  // url_hash
+        + 32  // secret_commitment
+        + 4  // events
+        + 1; // bump
+
+    /// Domain separator for secret commitments
+    pub const COMMITMENT_TAG: &'static [u8] = b"vanish-webhook 1\n";
+
+    /// SHA-256 over the tag, the repository's `id` and `secret`: the
+    /// commitment a webhook of that repository holds for it
+    pub fn commitment(repository_id: &Pubkey, secret: &[u8]) -> [u8; 32] {
+        hashv(&[Self::COMMITMENT_TAG, repository_id.as_ref(), secret]).to_bytes()
+    }
+}
+
+/// Maps one index in `0..Repository::collaborator_count` to a collaborator.
+/// Removing a collaborator moves the last slot's collaborator into the freed
+/// slot, so the indices stay dense.
+#[account]
+pub struct CollaboratorSlot {
+    /// `id` of the repository
+    pub repository: Pubkey,
+    pub index: u32,
+    pub user: Pubkey,
+    pub bump: u8,
+}
+
+impl CollaboratorSlot {
+    pub const SPACE: usize = 8  // discriminator
+        + 32  // repository
+        + 4  // index
+        + 32  // user
+        + 1; // bump
+}
+
+/// Repositories grouped under one admin, sharing a membership list
+#[account]
+pub struct Project {
+    pub admin: Pubkey,
+    pub name: String,
+    pub repo_count: u32,
+    pub member_count: u32,
+    /// push_update calls across every repository in the project
+    pub push_count: u64,
+    pub created_at: i64,
+    /// Last push to any repository in the project
+    pub updated_at: i64,
+    pub bump: u8,
+    /// Entries in the project's audit log, and the seq of the next one
+    pub audit_count: u64,
+}
+
+impl Project {
+    pub const SPACE: usize = 8  // discriminator
+        + 32  // admin
+        + 4 + 64  // name (string)
+        + 4  // repo_count
+        + 4  // member_count
+        + 8  // push_count
+        + 8  // created_at
+        + 8  // updated_at
+        + 1  // bump
+        + 8; // audit_count
+}
+
+/// Marks a repository as part of a project, at `[b"project_repo", id]`
+#[account]
+pub struct ProjectRepo {
+    pub project: Pubkey,
+    /// `id` of the repository
+    pub repository: Pubkey,
+    pub added_at: i64,
+    pub bump: u8,
+}
+
+impl ProjectRepo {
+    pub const SPACE: usize = 8  // discriminator
+        + 32  // project
+        + 32  // repository
+        + 8  // added_at
+        + 1; // bump
+}
+
+#[account]
+pub struct ProjectMember {
+    pub project: Pubkey,
+    pub user: Pubkey,
+    pub can_push: bool,
+    pub added_at: i64,
+    pub bump: u8,
+}
+
+impl ProjectMember {
+    pub const SPACE: usize = 8  // discriminator
+        + 32  // project
+        + 32  // user
+        + 1  // can_push
+        + 8  // added_at
+        + 1; // bump
+}
+
+/// Lamports a project keeps to cover rent for its repositories, at
+/// `[b"rent_vault", project]`. Holds its balance on top of its own rent.
+#[account]
+pub struct RentVault {
+    pub project: Pubkey,
+    /// Most lamports covered per add_collaborator
+    pub collaborator_cap: u64,
+    /// Most lamports covered per new branch
+    pub branch_cap: u64,
+    /// Lamports paid out so far
+    pub spent: u64,
+    pub bump: u8,
+}
+
+impl RentVault {
+    pub const SPACE: usize = 8  // discriminator
+        + 32  // project
+        + 8  // collaborator_cap
+        + 8  // branch_cap
+        + 8  // spent
+        + 1; // bump
+}
+
+/// A user's named collection of repositories, at `[b"list", owner, name]`
+#[account]
+pub struct List {
+    pub owner: Pubkey,
+    pub name: String,
+    pub description: String,
+    /// Asks clients not to show the list to anyone but its owner; its
+    /// accounts stay readable on chain
+    pub is_private: bool,
+    pub entry_count: u32,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub bump: u8,
+}
+
+impl List {
+    pub const MAX_NAME_LEN: usize = 32;
+    pub const MAX_DESCRIPTION_LEN: usize = 256;
+
+    pub const SPACE: usize = 8  // discriminator
+        + 32  // owner
+        + 4 + Self::MAX_NAME_LEN  // name (string)
+        + 4 + Self::MAX_DESCRIPTION_LEN  // description (string)
+        + 1  // is_private
+        + 4  // entry_count
+        + 8  // created_at
+        + 8  // updated_at
+        + 1; // bump
+}
+
+/// Puts a repository on a list, at `[b"list_entry", list, id]`
+#[account]
+pub struct ListEntry {
+    pub list: Pubkey,
+    /// `id` of the repository
+    pub repository: Pubkey,
+    pub added_at: i64,
+    pub bump: u8,
+}
+
+impl ListEntry {
+    pub const SPACE: usize = 8  // discriminator
+        + 32  // list
+        + 32  // repository
+        + 8  // added_at
+        + 1; // bump
+}
+
+/// How far through the activity feed a user has read, at
+/// `[b"activity", user]`
+#[account]
+pub struct ActivityCursor {
+    pub user: Pubkey,
+    /// Activity up to and including this slot has been seen
+    pub slot: u64,
+    pub seen_at: i64,
+    pub bump: u8,
+}
+
+impl ActivityCursor {
+    pub const SPACE: usize = 8  // discriminator
+        + 32  // user
+        + 8  // slot
+        + 8  // seen_at
+        + 1; // bump
+}
+
+/// One user's pushes to a repository, kept by every push so contributor
+/// graphs can be built from chain state alone
+#[account]
+pub struct Contribution {
+    pub repository: Pubkey,
+    pub contributor: Pubkey,
+    /// push_update, push_branch and push_refs calls made or co-authored,
+    /// one each
+    pub push_count: u64,
+    pub first_contributed_at: i64,
+    pub last_contributed_at: i64,
+    pub bump: u8,
+}
+
+impl Contribution {
+    pub const SPACE: usize = 8  // discriminator
+        + 32  // repository
+        + 32  // contributor
+        + 8  // push_count
+        + 8  // first_contributed_at
+        + 8  // last_contributed_at
+        + 1; // bump
+
+    /// Most co-authors push_update credits at once
+    pub const MAX_CO_AUTHORS: usize = 4;
+}
+
+/// One sensitive action taken on a repository. Entries are numbered from 0
+/// per repository, are never closed and outlive the repository, so they can
+/// be read back after it has been purged.
+#[account]
+pub struct AuditEntry {
+    /// `id` of the repository
+    pub repository: Pubkey,
+    /// Position in the repository's log
+    pub seq: u64,
+    /// Signer who took the action
+    pub actor: Pubkey,
+    pub action: AuditAction,
+    /// Who or what the action applied to (see `AuditAction`)
+    pub subject: Pubkey,
+    pub timestamp: i64,
+    pub bump: u8,
+}
+
+impl AuditEntry {
+    pub const SPACE: usize = 8  // discriminator
+        + 32  // repository
+        + 8  // seq
+        + 32  // actor
+        + 1  // action
+        + 32  // subject
+        + 8  // timestamp
+        + 1; // bump
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum AuditAction {
+    /// `subject` is the new owner: `Pubkey::default()` when renounced, the
+    /// RepoNft PDA when wrapped
+    OwnershipTransferred,
+    /// `subject` is the collaborator, here and for the three below
+    CollaboratorAdded,
+    CollaboratorPromoted,
+    CollaboratorDemoted,
+    CollaboratorRemoved,
+    /// `subject` is the repository's new address
+    Renamed,
+    /// `subject` is the repository's address
+    Deleted,
+    /// `subject` is the reader, here and below
+    ReadGranted,
+    ReadRevoked,
+}
+
+/// One change to a project's membership or repositories, numbered from 0
+/// per project at `[b"project_audit", project, seq]`. Together with each
+/// repository's own log, it shows who had access to what, and when. Entries
+/// are never closed.
+#[account]
+pub struct ProjectAuditEntry {
+    pub project: Pubkey,
+    /// Position in the project's log
+    pub seq: u64,
+    /// Signer who made the change
+    pub actor: Pubkey,
+    pub action: ProjectAuditAction,
+    /// The member or the repository's `id` (see `ProjectAuditAction`)
+    pub subject: Pubkey,
+    pub timestamp: i64,
+    pub bump: u8,
+}
+
+impl ProjectAuditEntry {
+    pub const SPACE: usize = 8  // discriminator
+        + 32  // project
+        + 8  // seq
+        + 32  // actor
+        + 1  // action
+        + 32  // subject
+        + 8  // timestamp
+        + 1; // bump
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum ProjectAuditAction {
+    /// `subject` is the member, here and for the two below
+    MemberAdded,
+    MemberRemoved,
+    /// The member's `can_push` changed
+    RoleChanged,
+    /// `subject` is the repository's `id`, here and below
+    RepoAdded,
+    RepoRemoved,
+}
+
+/// A key the config admin trusts to attest Filecoin storage deals
+#[account]
+pub struct DealOracle {
+    pub oracle: Pubkey,
+    pub added_at: i64,
+    pub bump: u8,
+}
+
+impl DealOracle {
+    pub const SPACE: usize = 8  // discriminator
+        + 32  // oracle
+        + 8  // added_at
+        + 1; // bump
+}
+
+/// A repository's declared Filecoin backend and the deal last attested for
+/// it. Seeded by the repository's `id`.
+#[account]
+pub struct FilecoinDeal {
+    /// `id` of the repository
+    pub repository: Pubkey,
+    /// Archive the deal stores, empty until the first attestation
+    pub payload_cid: String,
+    pub deal_id: u64,
+    pub expires_at: i64,
+    /// Deal oracle that attested the deal
+    pub oracle: Pubkey,
+    pub attested_at: i64,
+    /// Whether an attested deal covers the current archive; cleared by
+    /// flag_lapsed_deal
+    pub active: bool,
+    pub bump: u8,
+}
+
+impl FilecoinDeal {
+    pub const SPACE: usize = 8  // discriminator
+        + 32  // repository
+        + 4 + 64  // payload_cid (string)
+        + 8  // deal_id
+        + 8  // expires_at
+        + 32  // oracle
+        + 8  // attested_at
+        + 1  // active
+        + 1; // bump
+}
+
+/// A storage provider that pins repositories, with SOL staked against
+/// failing to keep them available
+#[account]
+pub struct Pinner {
+    pub operator: Pubkey,
+    /// Lamports staked on top of the account's rent
+    pub stake: u64,
+    pub registered_at: i64,
+    /// When unbond_pinner was called, 0 while bonded
+    pub unbonding_at: i64,
+    pub bump: u8,
+}
+
+impl Pinner {
+    pub const SPACE: usize = 8  // discriminator
+        + 32  // operator
+        + 8  // stake
+        + 8  // registered_at
+        + 8  // unbonding_at
+        + 1; // bump
+
+    /// Smallest stake register_pinner accepts
+    pub const MIN_STAKE: u64 = 1_000_000_000;
+    /// Seconds between unbond_pinner and withdraw_pinner_stake
+    pub const UNBONDING_PERIOD: i64 = 7 * 24 * 60 * 60;
+}
+
+/// A pinner's latest claim to hold a repository's archive, at
+/// `[b"pin", id, operator]`
+#[account]
+pub struct PinAttestation {
+    /// `id` of the repository
+    pub repository: Pubkey,
+    pub operator: Pubkey,
+    /// The archive attested, the repository's `ipfs_cid` at the time
+    pub cid: Cid,
+    pub attested_at: i64,
+    pub bump: u8,
+}
+
+impl PinAttestation {
+    pub const SPACE: usize = 8  // discriminator
+        + 32  // repository
+        + 32  // operator
+        + Cid::SPACE  // cid
+        + 8  // attested_at
+        + 1; // bump
+}
+
+/// Lamports set aside to pay a repository's pinners, held on top of the
+/// account's rent
+#[account]
+pub struct RewardPool {
+    /// `id` of the repository
+    pub repository: Pubkey,
+    /// Lamports paid out per `INTERVAL`, split between the bonded pinners
+    pub rate: u64,
+    /// End of the last interval paid for
+    pub last_distributed_at: i64,
+    /// Lamports paid out over the pool's lifetime
+    pub distributed: u64,
+    pub bump: u8,
+}
+
+impl RewardPool {
+    pub const SPACE: usize = 8  // discriminator
+        + 32  // repository
+        + 8  // rate
+        + 8  // last_distributed_at
+        + 8  // distributed
+        + 1; // bump
+
+    /// Seconds between distributions
+    pub const INTERVAL: i64 = 24 * 60 * 60;
+}
+
+/// A key besides the config admin's that may verify repositories
+#[account]
+pub struct Verifier {
+    pub verifier: Pubkey,
+    pub added_at: i64,
+    pub bump: u8,
+}
+
+impl Verifier {
+    pub const SPACE: usize = 8  // discriminator
+        + 32  // verifier
+        + 8  // added_at
+        + 1; // bump
+}
+
+/// Marks a repository as the canonical one for its project, so clients can
+/// tell it apart from look-alikes under other owners. Seeded by the
+/// repository's `id`, so it follows renames and transfers.
+#[account]
+pub struct Verification {
+    /// `id` of the repository
+    pub repository: Pubkey,
+    /// Config admin or registered verifier who verified it
+    pub verifier: Pubkey,
+    /// Where the attestation backing the verification lives
+    pub uri: String,
+    pub verified_at: i64,
+    pub bump: u8,
+}
+
+impl Verification {
+    pub const MAX_URI_LEN: usize = 200;
+
+    pub const SPACE: usize = 8  // discriminator
+        + 32  // repository
+        + 32  // verifier
+        + 4 + Self::MAX_URI_LEN  // uri (string)
+        + 8  // verified_at
+        + 1; // bump
+}
+
+/// A repository's symmetric key sealed to one reader's X25519 key
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct KeyEnvelope {
+    /// Ephemeral X25519 public key the key agreement was made with
+    pub ephemeral_pubkey: [u8; 32],
+    /// XChaCha20-Poly1305 nonce
+    pub nonce: [u8; 24],
+    /// 32-byte repository key followed by the 16-byte authentication tag
+    pub ciphertext: [u8; 48],
+}
+
+impl KeyEnvelope {
+    pub const SPACE: usize = 32  // ephemeral_pubkey
+        + 24  // nonce
+        + 48; // ciphertext
+}
+
+#[account]
+pub struct AccessKey {
+    pub repository: Pubkey,
+    pub user: Pubkey,
+    pub envelope: KeyEnvelope,
+    /// Repository key epoch the envelope was sealed for
+    pub key_epoch: u32,
+    pub bump: u8,
+}
+
+impl AccessKey {
+    pub const SPACE: usize = 8  // discriminator
+        + 32  // repository
+        + 32  // user
+        + KeyEnvelope::SPACE  // envelope
+        + 4  // key_epoch
+        + 1; // bump
+}
+
+#[account]
+pub struct AccessPricing {
+    pub repository: Pubkey,
+    /// SPL mint payments are made in, or `None` for lamports
+    pub mint: Option<Pubkey>,
+    pub price: u64,
+    /// Seconds of access bought with one payment
+    pub duration: i64,
+    pub bump: u8,
+}
+
+impl AccessPricing {
+    pub const SPACE: usize = 8  // discriminator
+        + 32  // repository
+        + 1 + 32  // mint (option)
+        + 8  // price
+        + 8  // duration
+        + 1; // bump
+}
+
+/// Time-limited read access to a private repository
+#[account]
+pub struct AccessGrant {
+    pub repository: Pubkey,
+    pub user: Pubkey,
+    pub expires_at: i64,
+    pub bump: u8,
+}
+
+impl AccessGrant {
+    pub const SPACE: usize = 8  // discriminator
+        + 32  // repository
+        + 32  // user
+        + 8  // expires_at
+        + 1; // bump
+}
+
+/// Read access to a repository granted by its owner or an admin, separate
+/// from collaborator access. Gateways serving private repositories check it,
+/// and it entitles the reader to an AccessKey.
+#[account]
+pub struct ReadGrant {
+    pub repository: Pubkey,
+    pub reader: Pubkey,
+    pub granted_by: Pubkey,
+    pub granted_at: i64,
+    /// When the grant lapses, 0 if it never does
+    pub expires_at: i64,
+    pub bump: u8,
+}
+
+impl ReadGrant {
+    pub const SPACE: usize = 8  // discriminator
+        + 32  // repository
+        + 32  // reader
+        + 32  // granted_by
+        + 8  // granted_at
+        + 8  // expires_at
+        + 1; // bump
+
+    pub fn is_active(&self, now: i64) -> bool {
+        self.expires_at == 0 || now < self.expires_at
+    }
+}
+
+/// Per-repository contribution badge settings. The PDA is the badge mint's
+/// mint authority.
+#[account]
+pub struct BadgeConfig {
+    pub repository: Pubkey,
+    pub mint: Pubkey,
+    pub enabled: bool,
+    pub bump: u8,
+}
+
+impl BadgeConfig {
+    pub const SPACE: usize = 8  // discriminator
+        + 32  // repository
+        + 32  // mint
+        + 1  // enabled
+        + 1; // bump
+}
+
+/// Repository statistics reported by pushers' clients, so explorers can show
+/// size and activity without fetching the archive. Self-reported: bounded,
+/// but not verified against the archive.
+#[account]
+pub struct Stats {
+    /// `id` of the repository
+    pub repository: Pubkey,
+    /// `Multihash::seed` of the head the stats describe
+    pub head: [u8; 32],
+    /// Commits added across every recorded push
+    pub commit_count: u64,
+    /// Packed size of the repository at `head`, in bytes
+    pub packed_size: u64,
+    /// Language tags, e.g. `rust` or `typescript`, most used first
+    pub languages: Vec<String>,
+    pub updated_at: i64,
+    pub bump: u8,
+}
+
+impl Stats {
+    pub const MAX_LANGUAGES: usize = 8;
+    pub const MAX_LANGUAGE_LEN: usize = 16;
+    /// Commits one push may claim to add
+    pub const MAX_COMMITS_PER_PUSH: u64 = 1_000_000;
+    /// 100 GiB
+    pub const MAX_PACKED_SIZE: u64 = 100 << 30;
+
+    pub const SPACE: usize = 8  // discriminator
+        + 32  // repository
+        + 32  // head
+        + 8  // commit_count
+        + 8  // packed_size
+        + 4 + Self::MAX_LANGUAGES * (4 + Self::MAX_LANGUAGE_LEN)  // languages (vec)
+        + 8  // updated_at
+        + 1; // bump
+}
+
+/// The last `CAPACITY` pushes to a repository, as a ring buffer. Zero-copy,
+/// so push_update writes one entry in place rather than deserializing and
+/// re-serializing the whole history.
+#[account(zero_copy)]
+pub struct PushLog {
+    pub repository: Pubkey,
+    /// Pushes recorded so far; the next one goes to `push_count % CAPACITY`
+    pub push_count: u64,
+    pub bump: u8,
+    pub _padding: [u8; 7],
+    pub entries: [PushEntry; 32],
+}
+
+impl PushLog {
+    pub const CAPACITY: usize = 32;
+
+    /// Bytes before the entries
+    const HEADER_SPACE: usize = 8  // discriminator
+        + 32  // repository
+        + 8  // push_count
+        + 1  // bump
+        + 7; // padding
+
+    pub const SPACE: usize = Self::HEADER_SPACE + PushEntry::SPACE * Self::CAPACITY; // entries
+
+    /// Size of logs created before entries carried push notes, until
+    /// migrate_account converts them
+    pub const V0_SPACE: usize = Self::HEADER_SPACE + PushEntry::V0_SPACE * Self::CAPACITY;
+
+    /// Current layout version. Not stored: a log is at version 0 while it
+    /// is `V0_SPACE` bytes long.
+    pub const VERSION: u8 = 1;
+
+    /// Record a push, overwriting the oldest entry once the log is full.
+    /// `commit` must be a git object id, which fits the entry's digest, and
+    /// `note` a valid PushNote.
+    pub fn record(
+        &mut self,
+        pusher: Pubkey,
+        commit: &Multihash,
+        note: Option<&PushNote>,
+        timestamp: i64,
+    ) {
+        let mut commit_digest = [0; 32];
+        commit_digest[..commit.digest.len()].copy_from_slice(&commit.digest);
+
+        let mut changelog_cid = [0; PushEntry::MAX_CHANGELOG_CID_LEN];
+        let mut message = [0; PushEntry::MAX_MESSAGE_LEN];
+        if let Some(note) = note {
+            changelog_cid[..note.changelog_cid.len()]
+                .copy_from_slice(note.changelog_cid.as_bytes());
+            message[..note.message.len()].copy_from_slice(note.message.as_bytes());
+        }
+
+        let slot = (self.push_count % Self::CAPACITY as u64) as usize;
+        self.entries[slot] = PushEntry {
+            pusher,
+            commit_code: commit.code,
+            commit_digest,
+            timestamp,
+            changelog_cid,
+            message,
+        };
+        self.push_count += 1;
+    }
+}
+
+/// One push in a PushLog. Fixed-size: the commit digest is zero-padded to
+/// 32 bytes and its length follows from `commit_code`; the push note's
+/// fields are zero-padded too, and all zeros when the push had none.
+#[zero_copy]
+pub struct PushEntry {
+    pub pusher: Pubkey,
+    pub commit_code: u64,
+    pub commit_digest: [u8; 32],
+    pub timestamp: i64,
+    pub changelog_cid: [u8; 64],
+    pub message: [u8; 72],
+}
+
+impl PushEntry {
+    pub const MAX_CHANGELOG_CID_LEN: usize = 64;
+    pub const MAX_MESSAGE_LEN: usize = 72;
+
+    pub const SPACE: usize = 32  // pusher
+        + 8  // commit_code
+        + 32  // commit_digest
+        + 8  // timestamp
+        + Self::MAX_CHANGELOG_CID_LEN  // changelog_cid
+        + Self::MAX_MESSAGE_LEN; // message
+
+    /// Size of entries before push notes
+    pub const V0_SPACE: usize = 32 + 8 + 32 + 8;
+
+    pub fn changelog_cid(&self) -> String {
+        unpad(&self.changelog_cid)
+    }
+
+    pub fn message(&self) -> String {
+        unpad(&self.message)
+    }
+
+    pub fn commit(&self) -> Multihash {
+        let len = Multihash::digest_len(self.commit_code).unwrap_or(0);
+        Multihash {
+            code: self.commit_code,
+            digest: self.commit_digest[..len].to_vec(),
+        }
+    }
+}
+
+/// The text in a zero-padded field
+fn unpad(field: &[u8]) -> String {
+    let len = field.iter().position(|&b| b == 0).unwrap_or(field.len());
+    String::from_utf8_lossy(&field[..len]).into_owned()
+}
+
+/// A CI system allowed to post check runs for a repository
+#[account]
+pub struct CiAttestor {
+    pub repository: Pubkey,
+    pub attestor: Pubkey,
+    pub added_at: i64,
+    pub bump: u8,
+}
+
+impl CiAttestor {
+    pub const SPACE: usize = 8  // discriminator
+        + 32  // repository
+        + 32  // attestor
+        + 8  // added_at
+        + 1; // bump
+}
+
+/// One attestor's latest result for one commit. The PDA is derived from
+/// `Multihash::seed` of the commit, which may be longer than a seed allows.
+#[account]
+pub struct CheckRun {
+    pub repository: Pubkey,
+    pub commit: Multihash,
+    pub attestor: Pubkey,
+    pub status: CheckStatus,
+    /// Logs or other details of the run, if published
+    pub details_cid: String,
+    pub updated_at: i64,
+    pub bump: u8,
+}
+
+impl CheckRun {
+    pub const SPACE: usize = 8  // discriminator
+        + 32  // repository
+        + Multihash::SPACE  // commit
+        + 32  // attestor
+        + 1  // status
+        + 4 + 64  // details_cid (string)
+        + 8  // updated_at
+        + 1; // bump
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    Success,
+    Failure,
+}
+
+/// Who may approve releases, and how many approvals finalize one
+#[account]
+pub struct ReleasePolicy {
+    pub repository: Pubkey,
+    pub managers: Vec<Pubkey>,
+    pub threshold: u8,
+    pub bump: u8,
+}
+
+impl ReleasePolicy {
+    pub const MAX_MANAGERS: usize = 10;
+
+    pub const SPACE: usize = 8  // discriminator
+        + 32  // repository
+        + 4 + 32 * Self::MAX_MANAGERS  // managers (vec)
+        + 1  // threshold
+        + 1; // bump
+
+    /// What a queued SetReleasePolicy action commits to
+    pub fn digest(managers: &[Pubkey], threshold: u8) -> [u8; 32] {
+        let threshold = [threshold];
+        let mut parts: Vec<&[u8]> = managers.iter().map(|manager| manager.as_ref()).collect();
+        parts.push(&threshold);
+        hashv(&parts).to_bytes()
+    }
+}
+
+/// A tagged release. Pending until enough release managers approve it;
+/// consumers should only trust releases with `is_final` set. The tag is a PDA
+/// seed, so it is capped at 32 bytes.
+#[account]
+pub struct Release {
+    pub repository: Pubkey,
+    pub tag: String,
+    pub commit: Multihash,
+    pub ipfs_cid: String,
+    pub assets: Vec<ReleaseAsset>,
+    pub proposer: Pubkey,
+    /// Managers who approved. Those no longer in the policy are dropped at
+    /// the next approval and do not count towards the threshold.
+    pub approvers: Vec<Pubkey>,
+    pub is_final: bool,
+    pub created_at: i64,
+    pub finalized_at: i64,
+    pub bump: u8,
+}
+
+impl Release {
+    pub const MAX_ASSETS: usize = 8;
+
+    pub const SPACE: usize = 8  // discriminator
+        + 32  // repository
+        + 4 + 32  // tag (string)
+        + Multihash::SPACE  // commit
+        + 4 + 64  // ipfs_cid (string)
+        + 4 + ReleaseAsset::SPACE * Self::MAX_ASSETS  // assets (vec)
+        + 32  // proposer
+        + 4 + 32 * ReleasePolicy::MAX_MANAGERS  // approvers (vec)
+        + 1  // is_final
+        + 8  // created_at
+        + 8  // finalized_at
+        + 1; // bump
+}
+
+/// A file published with a release
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ReleaseAsset {
+    pub filename: String,
+    /// Size in bytes
+    pub size: u64,
+    pub ipfs_cid: String,
+    /// Digest of the file contents, for verifying downloads
+    pub checksum: Multihash,
+}
+
+impl ReleaseAsset {
+    pub const SPACE: usize = 4 + 64  // filename (string)
+        + 8  // size
+        + 4 + 64  // ipfs_cid (string)
+        + Multihash::SPACE; // checksum
+}
+
+/// A build system key registered to attest release provenance
+#[account]
+pub struct Builder {
+    pub repository: Pubkey,
+    pub builder: Pubkey,
+    pub added_at: i64,
+    pub bump: u8,
+}
+
+impl Builder {
+    pub const SPACE: usize = 8  // discriminator
+        + 32  // repository
+        + 32  // builder
+        + 8  // added_at
+        + 1; // bump
+}
+
+/// SLSA-style provenance for one release asset: which builder produced the
+/// file with this checksum, and where its provenance document lives
+#[account]
+pub struct Attestation {
+    pub release: Pubkey,
+    pub checksum: Multihash,
+    pub builder: Pubkey,
+    pub provenance_cid: String,
+    pub attested_at: i64,
+    pub bump: u8,
+}
+
+impl Attestation {
+    pub const SPACE: usize = 8  // discriminator
+        + 32  // release
+        + Multihash::SPACE  // checksum
+        + 32  // builder
+        + 4 + 64  // provenance_cid (string)
+        + 8  // attested_at
+        + 1; // bump
+}
+
+/// One release manager's approval of one release
+#[account]
+pub struct ReleaseApproval {
+    pub release: Pubkey,
+    pub manager: Pubkey,
+    pub approved_at: i64,
+    pub bump: u8,
+}
+
+impl ReleaseApproval {
+    pub const SPACE: usize = 8  // discriminator
+        + 32  // release
+        + 32  // manager
+        + 8  // approved_at
+        + 1; // bump
+}
+
+/// Delay a repository's owner set on actions that hand it away or remove it
+#[account]
+pub struct Timelock {
+    /// `id` of the repository
+    pub repository: Pubkey,
+    /// Seconds between queueing an action and carrying it out; 0 disables
+    pub delay: i64,
+    pub bump: u8,
+}
+
+impl Timelock {
+    pub const SPACE: usize = 8  // discriminator
+        + 32  // repository
+        + 8  // delay
+        + 1; // bump
+
+    /// Longest delay set_timelock accepts
+    pub const MAX_DELAY: i64 = 30 * 24 * 60 * 60;
+}
+
+/// An action that waits out the repository's timelock, with the arguments
+/// it must be carried out with
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub enum TimelockedAction {
+    TransferOwnership {
+        new_owner: Pubkey,
+    },
+    WrapToNft {
+        mint: Pubkey,
+    },
+    Rename {
+        new_name: String,
+    },
+    Delete,
+    /// Lower the delay, or remove the timelock with 0
+    SetDelay {
+        delay: i64,
+    },
+    /// Replace the release policy, by `ReleasePolicy::digest`
+    SetReleasePolicy {
+        policy: [u8; 32],
+    },
+}
+
+impl TimelockedAction {
+    pub const SPACE: usize = 1 + 4 + 64; // largest variant, Rename
+}
+
+/// A timelocked action waiting for its delay to pass
+#[account]
+pub struct PendingAction {
+    /// `id` of the repository
+    pub repository: Pubkey,
+    pub action: TimelockedAction,
+    pub queued_at: i64,
+    pub executable_at: i64,
+    pub bump: u8,
+}
+
+impl PendingAction {
+    pub const SPACE: usize = 8  // discriminator
+        + 32  // repository
+        + TimelockedAction::SPACE  // action
+        + 8  // queued_at
+        + 8  // executable_at
+        + 1; // bump
+}
+
+/// Binds a repository to the NFT whose holder may unwrap it
+#[account]
+pub struct RepoNft {
+    pub repository: Pubkey,
+    pub mint: Pubkey,
+    pub bump: u8,
+}
+
+impl RepoNft {
+    pub const SPACE: usize = 8  // discriminator
+        + 32  // repository
+        + 32  // mint
+        + 1; // bump
+}
+
+/// A registry name pointing at the repository that publishes it
+#[account]
+pub struct Package {
+    pub name: String,
+    pub repository: Pubkey,
+    /// Highest published version, `None` until the first publish
+    pub latest: Option<SemVer>,
+    pub version_count: u32,
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+impl Package {
+    pub const SPACE: usize = 8  // discriminator
+        + 4 + 32  // name (string)
+        + 32  // repository
+        + 1 + SemVer::SPACE  // latest (option)
+        + 4  // version_count
+        + 8  // created_at
+        + 1; // bump
+}
+
+/// A semantic version. Pre-release and build metadata are not supported.
+/// Field order makes the derived ordering the semver precedence.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SemVer {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl SemVer {
+    pub const SPACE: usize = 4  // major
+        + 4  // minor
+        + 4; // patch
+}
+
+/// One published version of a package
+#[account]
+pub struct PackageVersion {
+    pub package: Pubkey,
+    pub version: SemVer,
+    /// Archive of the package contents
+    pub ipfs_cid: String,
+    pub published_at: i64,
+    pub bump: u8,
+}
+
+impl PackageVersion {
+    pub const SPACE: usize = 8  // discriminator
+        + 32  // package
+        + SemVer::SPACE  // version
+        + 4 + 64  // ipfs_cid (string)
+        + 8  // published_at
+        + 1; // bump
+}
+
+#[account]
+pub struct Star {
+    pub user: Pubkey,
+    pub repository: Pubkey,
+    pub starred_at: i64,
+    pub bump: u8,
+    /// Layout version, upgraded by migrate_account
+    pub version: u8,
+}
+
+impl Star {
+    pub const SPACE: usize = 8  // discriminator
+        + 32  // user
+        + 32  // repository
+        + 8  // starred_at
+        + 1  // bump
+        + 1; // version
+
+    /// Current layout version
+    pub const VERSION: u8 = 1;
+}
+
+/// The concurrent merkle tree compressed stars live in, shared by every
+/// repository. The PDA is the tree's authority.
+#[account]
+pub struct StarTree {
+    pub merkle_tree: Pubkey,
+    pub bump: u8,
+}
+
+impl StarTree {
+    pub const SPACE: usize = 8  // discriminator
+        + 32  // merkle_tree
+        + 1; // bump
+}
+
+/// Calls into spl-account-compression. Its instructions are built by hand
+/// from their Anchor discriminators rather than through its crate.
+pub mod compression {
+    use super::*;
+    use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+    use anchor_lang::solana_program::program::invoke_signed;
+
+    declare_id!("cmtDvXumGCrqC1Age74AVPhSRVXJMd8PJS91L8KbNCK");
+
+    /// spl-noop, which the compression program logs changes through
+    pub mod noop {
+        use super::*;
+
+        declare_id!("noopb9bkMVfRPU8AsbpTUg8AQkHtKwMYZiFUjNRtMmV");
+    }
+
+    /// Leaf recording that `user` starred the repository with `repository_id`
+    pub fn star_leaf(user: &Pubkey, repository_id: &Pubkey) -> [u8; 32] {
+        hashv(&[b"star", user.as_ref(), repository_id.as_ref()]).to_bytes()
+    }
+
+    /// Invoke the compression program's `name` instruction on `merkle_tree`
+    /// with `args` already serialized, signing as the star tree PDA.
+    /// `proof` nodes are appended as read-only accounts.
+    pub fn invoke<'info>(
+        name: &str,
+        args: &[u8],
+        compression_program: &AccountInfo<'info>,
+        merkle_tree: &AccountInfo<'info>,
+        star_tree: &Account<'info, StarTree>,
+        noop_program: &AccountInfo<'info>,
+        proof: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        let mut data = hash(format!("global:{name}").as_bytes()).to_bytes()[..8].to_vec();
+        data.extend_from_slice(args);
+
+        let mut accounts = vec![
+            AccountMeta::new(merkle_tree.key(), false),
+            AccountMeta::new_readonly(star_tree.key(), true),
+            AccountMeta::new_readonly(noop_program.key(), false),
+        ];
+        accounts.extend(
+            proof
+                .iter()
+                .map(|node| AccountMeta::new_readonly(node.key(), false)),
+        );
+
+        let mut infos = vec![
+            merkle_tree.clone(),
+            star_tree.to_account_info(),
+            noop_program.clone(),
+            compression_program.clone(),
+        ];
+        infos.extend_from_slice(proof);
+
+        invoke_signed(
+            &Instruction {
+                program_id: ID,
+                accounts,
+                data,
+            },
+            &infos,
+            &[&[b"star_tree", &[star_tree.bump]]],
+        )?;
+
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Events
+// ============================================================================
+
+#[event]
+pub struct ConfigUpdated {
+    pub admin: Pubkey,
+    pub repo_fee: u64,
+    pub flags: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TreasuryWithdrawn {
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct NameStakeUpdated {
+    pub amount: u64,
+    pub max_name_len: u8,
+    pub lock_period: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RateLimitsUpdated {
+    pub window_slots: u64,
+    pub max_creations: u32,
+    pub max_stars: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReclaimPolicyUpdated {
+    pub idle_period: i64,
+    pub grace_period: i64,
+    pub bond: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReservedNamesUpdated {
+    pub names: Vec<String>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RepoQuotaUpdated {
+    /// `None` for the config-wide cap
+    pub user: Option<Pubkey>,
+    pub max_repos: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RepoCreatedFromTemplate {
+    pub repository: Pubkey,
+    pub template: Pubkey,
+    pub head_commit: Multihash,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct NameStaked {
+    pub repository: Pubkey,
+    pub staker: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct NameStakeReleased {
+    pub repository: Pubkey,
+    pub staker: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReclaimNoticeFiled {
+    pub repository: Pubkey,
+    pub claimant: Pubkey,
+    pub bond: u64,
+    pub deadline: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReclaimContested {
+    pub repository: Pubkey,
+    pub claimant: Pubkey,
+    /// Lamports forfeited to the treasury
+    pub bond: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RepoReclaimed {
+    pub repository: Pubkey,
+    pub claimant: Pubkey,
+    pub old_owner: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RecoveryPlanSet {
+    pub repository: Pubkey,
+    pub recovery_authority: Pubkey,
+    pub inactivity_period: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OwnerHeartbeat {
+    pub repository: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RecoveryPlanCancelled {
+    pub repository: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ContributorsSnapshotted {
+    pub repository: Pubkey,
+    pub taker: Pubkey,
+    pub slot: u64,
+    pub root: [u8; 32],
+    pub contributions: u32,
+    pub stars: u32,
+    pub collaborators: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RepoCheckpointed {
+    pub repository: Pubkey,
+    /// Position of the checkpoint across every History page
+    pub index: u32,
+    pub slot: u64,
+    pub stars: u64,
+    pub forks: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RepoRecovered {
+    pub repository: Pubkey,
+    pub recovery_authority: Pubkey,
+    pub old_owner: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RepoCreated {
+    pub repository: Pubkey,
+    pub owner: Pubkey,
+    pub name: String,
+    pub is_private: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RepoPushed {
+    pub repository: Pubkey,
+    pub owner: Pubkey,
+    pub name: String,
+    pub pusher: Pubkey,
+    pub co_authors: Vec<Pubkey>,
+    pub head_commit: Multihash,
+    pub ipfs_cid: Cid,
+    pub note: Option<PushNote>,
+    /// Commitment tying `head_commit` to the archive, if the pusher sent one
+    pub binding: Option<[u8; 32]>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BranchPushed {
+    pub repository: Pubkey,
+    pub branch: String,
+    pub pusher: Pubkey,
+    pub head_commit: Multihash,
+    pub ipfs_cid: String,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RepoForked {
+    pub repository: Pubkey,
+    pub upstream: Pubkey,
+    pub head_commit: Multihash,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ForkSynced {
+    pub repository: Pubkey,
+    pub upstream: Pubkey,
+    /// `None` when the repository head was synced
+    pub branch: Option<String>,
+    pub head_commit: Multihash,
+    pub ipfs_cid: String,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SubmoduleUpdated {
+    pub repository: Pubkey,
+    pub path: String,
+    pub target: Pubkey,
+    pub commit: Multihash,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SubmoduleRemoved {
+    pub repository: Pubkey,
+    pub path: String,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ObjectsRotated {
+    pub repository: Pubkey,
+    pub old_objects_cid: String,
+    pub objects_cid: String,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DescriptionUpdated {
+    pub repository: Pubkey,
+    pub description: String,
+    pub storage: DescriptionStorage,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RepoFlagsUpdated {
+    pub repository: Pubkey,
+    pub flags: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RepoDeprecated {
+    pub repository: Pubkey,
+    pub successor: Option<Pubkey>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VerifierAdded {
+    pub verifier: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VerifierRemoved {
+    pub verifier: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DealOracleAdded {
+    pub oracle: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DealOracleRemoved {
+    pub oracle: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FilecoinDeclared {
+    pub repository: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FilecoinDealAttested {
+    pub repository: Pubkey,
+    pub oracle: Pubkey,
+    pub deal_id: u64,
+    pub payload_cid: String,
+    pub expires_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FilecoinDealLapsed {
+    pub repository: Pubkey,
+    pub deal_id: u64,
+    pub payload_cid: String,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PinnerRegistered {
+    pub operator: Pubkey,
+    pub stake: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PinnerEnrolled {
+    pub repository: Pubkey,
+    pub operator: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PinAttested {
+    pub repository: Pubkey,
+    pub operator: Pubkey,
+    pub cid: Cid,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PinnerUnenrolled {
+    pub repository: Pubkey,
+    pub operator: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PinnerUnbonding {
+    pub operator: Pubkey,
+    pub withdrawable_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PinnerStakeWithdrawn {
+    pub operator: Pubkey,
+    pub stake: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RewardRateSet {
+    pub repository: Pubkey,
+    pub rate: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RewardPoolFunded {
+    pub repository: Pubkey,
+    /// The treasury when funded by fund_reward_pool_from_treasury
+    pub funder: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RewardsDistributed {
+    pub repository: Pubkey,
+    pub amount: u64,
+    /// Pinners the amount was split between
+    pub pinners: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RepoVerified {
+    pub repository: Pubkey,
+    pub verifier: Pubkey,
+    pub uri: String,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RepoVerificationRevoked {
+    pub repository: Pubkey,
+    pub revoked_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RepoFrozen {
+    pub repository: Pubkey,
+    pub frozen: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BranchForcePushUpdated {
+    pub repository: Pubkey,
+    pub branch: String,
+    pub allow_force_push: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct IpnsUpdated {
+    pub repository: Pubkey,
+    pub ipns_name: String,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReadmeUpdated {
+    pub repository: Pubkey,
+    pub readme_cid: Cid,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MetadataUpdated {
+    pub repository: Pubkey,
+    pub metadata_cid: Cid,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AccountMigrated {
+    pub account: Pubkey,
+    pub version: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RepoDeleted {
+    pub repository: Pubkey,
+    pub purgeable_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RepoRestored {
+    pub repository: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RepoPurged {
+    pub repository: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PurgedChildrenSwept {
+    /// `id` of the purged repository
+    pub repository: Pubkey,
+    pub closed: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RepositoryResized {
+    pub repository: Pubkey,
+    pub space: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CollaboratorAdded {
+    pub repository: Pubkey,
+    pub collaborator: Pubkey,
+    pub can_push: bool,
+    /// Index slot the collaborator was given
+    pub index: u32,
+    pub timestamp: i64,
+}
+
+/// A collaborator was given an index slot, either by index_collaborator or
+/// by moving into the slot a removed collaborator freed
+#[event]
+pub struct CollaboratorIndexed {
+    pub repository: Pubkey,
+    pub collaborator: Pubkey,
+    pub index: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProjectCreated {
+    pub project: Pubkey,
+    pub admin: Pubkey,
+    pub name: String,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProjectRepoAdded {
+    pub project: Pubkey,
+    pub repository: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProjectRepoRemoved {
+    pub project: Pubkey,
+    /// `id` of the repository
+    pub repository: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProjectMemberAdded {
+    pub project: Pubkey,
+    pub user: Pubkey,
+    pub can_push: bool,
+    pub timestamp: i64,
 }
 
-#[account]
-pub struct Collaborator {
-    pub repository: Pubkey,
+#[event]
+pub struct ProjectMemberRoleChanged {
+    pub project: Pubkey,
     pub user: Pubkey,
     pub can_push: bool,
-    pub added_at: i64,
-    pub bump: u8,
+    pub timestamp: i64,
 }
 
-impl Collaborator {
-    pub const SPACE: usize = 8  // discriminator
-        + 32  // repository
-        + 32  // user
-        + 1  // can_push
-        + 8  // added_at
-        + 1; // bump
+#[event]
+pub struct ProjectMemberRemoved {
+    pub project: Pubkey,
+    pub user: Pubkey,
+    pub timestamp: i64,
 }
 
-#[account]
-pub struct Star {
+#[event]
+pub struct RentVaultUpdated {
+    pub project: Pubkey,
+    pub collaborator_cap: u64,
+    pub branch_cap: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RentVaultDeposited {
+    pub project: Pubkey,
+    pub depositor: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RentVaultWithdrawn {
+    pub project: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RentFunded {
+    pub project: Pubkey,
+    pub repository: Pubkey,
+    /// Who paid the rent up front and was paid back
+    pub payer: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ListCreated {
+    pub list: Pubkey,
+    pub owner: Pubkey,
+    pub name: String,
+    pub is_private: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ListUpdated {
+    pub list: Pubkey,
+    pub is_private: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ListDeleted {
+    pub list: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ListEntryAdded {
+    pub list: Pubkey,
+    pub repository: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ListEntryRemoved {
+    pub list: Pubkey,
+    /// `id` of the repository
+    pub repository: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ActivitySeen {
     pub user: Pubkey,
+    pub slot: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RepoKeyRotated {
     pub repository: Pubkey,
-    pub starred_at: i64,
-    pub bump: u8,
+    pub key_epoch: u32,
+    pub timestamp: i64,
 }
 
-impl Star {
-    pub const SPACE: usize = 8  // discriminator
-        + 32  // user
-        + 32  // repository
-        + 8  // starred_at
-        + 1; // bump
+#[event]
+pub struct AccessKeyReissued {
+    pub repository: Pubkey,
+    pub user: Pubkey,
+    pub key_epoch: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BranchGrantSet {
+    pub repository: Pubkey,
+    pub collaborator: Pubkey,
+    pub patterns: Vec<String>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BranchGrantRemoved {
+    pub repository: Pubkey,
+    pub collaborator: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct WebhookSet {
+    pub repository: Pubkey,
+    pub url_hash: [u8; 32],
+    pub events: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct WebhookRemoved {
+    pub repository: Pubkey,
+    pub url_hash: [u8; 32],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CollaboratorAdminChanged {
+    pub repository: Pubkey,
+    pub collaborator: Pubkey,
+    pub is_admin: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReadGranted {
+    pub repository: Pubkey,
+    pub reader: Pubkey,
+    pub expires_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReadRevoked {
+    pub repository: Pubkey,
+    pub reader: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CollaboratorRemoved {
+    pub repository: Pubkey,
+    pub collaborator: Pubkey,
+    /// Index slot the collaborator held, if any
+    pub index: Option<u32>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AccessPricingUpdated {
+    pub repository: Pubkey,
+    pub mint: Option<Pubkey>,
+    pub price: u64,
+    pub duration: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AccessPricingRemoved {
+    pub repository: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AccessPurchased {
+    pub repository: Pubkey,
+    pub user: Pubkey,
+    pub mint: Option<Pubkey>,
+    pub amount: u64,
+    /// Transfer fee the buyer paid on top of `amount`, for Token-2022 mints
+    pub fee: u64,
+    pub expires_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BadgeConfigUpdated {
+    pub repository: Pubkey,
+    pub mint: Pubkey,
+    pub enabled: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StatsUpdated {
+    pub repository: Pubkey,
+    pub commits_added: u64,
+    pub commit_count: u64,
+    pub packed_size: u64,
+    pub languages: Vec<String>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BadgeMinted {
+    pub repository: Pubkey,
+    pub contributor: Pubkey,
+    pub mint: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CiAttestorAdded {
+    pub repository: Pubkey,
+    pub attestor: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CiAttestorRemoved {
+    pub repository: Pubkey,
+    pub attestor: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CheckRunPosted {
+    pub repository: Pubkey,
+    pub commit: Multihash,
+    pub attestor: Pubkey,
+    pub status: CheckStatus,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReleasePolicyUpdated {
+    pub repository: Pubkey,
+    pub managers: Vec<Pubkey>,
+    pub threshold: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReleaseCreated {
+    pub repository: Pubkey,
+    pub tag: String,
+    pub commit: Multihash,
+    pub proposer: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReleaseAssetAdded {
+    pub repository: Pubkey,
+    pub tag: String,
+    pub filename: String,
+    pub size: u64,
+    pub ipfs_cid: String,
+    pub checksum: Multihash,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BuilderAdded {
+    pub repository: Pubkey,
+    pub builder: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BuilderRemoved {
+    pub repository: Pubkey,
+    pub builder: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BuildAttested {
+    pub repository: Pubkey,
+    pub tag: String,
+    pub checksum: Multihash,
+    pub builder: Pubkey,
+    pub provenance_cid: String,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReleaseApproved {
+    pub repository: Pubkey,
+    pub tag: String,
+    pub manager: Pubkey,
+    pub approvals: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReleaseFinalized {
+    pub repository: Pubkey,
+    pub tag: String,
+    pub commit: Multihash,
+    pub ipfs_cid: String,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PackageCreated {
+    pub package: Pubkey,
+    pub name: String,
+    pub repository: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VersionPublished {
+    pub package: Pubkey,
+    pub name: String,
+    pub version: SemVer,
+    pub ipfs_cid: String,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RepoStarred {
+    pub user: Pubkey,
+    pub repository: Pubkey,
+    /// Lamports sent to the donation vault along with the star
+    pub tip: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DonationsWithdrawn {
+    pub repository: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RepoUnstarred {
+    pub user: Pubkey,
+    pub repository: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StarsReconciled {
+    pub repository: Pubkey,
+    /// Count before reconciling
+    pub previous: u64,
+    pub stars: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StarsDecayed {
+    pub repository: Pubkey,
+    pub weighted_stars: u64,
+    pub timestamp: i64,
 }
 
-// ============================================================================
-// Events
-// ============================================================================
+#[event]
+pub struct RepoRenamed {
+    pub id: Pubkey,
+    pub old_address: Pubkey,
+    pub new_address: Pubkey,
+    pub old_name: String,
+    pub new_name: String,
+    pub timestamp: i64,
+}
 
 #[event]
-pub struct RepoCreated {
-    pub owner: Pubkey,
+pub struct RepoNameClaimed {
+    pub repo_name: Pubkey,
     pub name: String,
-    pub is_private: bool,
+    pub repository: Pubkey,
     pub timestamp: i64,
 }
 
 #[event]
-pub struct RepoPushed {
-    pub owner: Pubkey,
+pub struct RepoNameReleased {
+    pub repo_name: Pubkey,
     pub name: String,
-    pub head_commit: String,
-    pub ipfs_cid: String,
     pub timestamp: i64,
 }
 
 #[event]
-pub struct CollaboratorAdded {
+pub struct TimelockSet {
     pub repository: Pubkey,
-    pub collaborator: Pubkey,
-    pub can_push: bool,
+    pub delay: i64,
     pub timestamp: i64,
 }
 
 #[event]
-pub struct RepoStarred {
-    pub user: Pubkey,
+pub struct ActionQueued {
+    pub repository: Pubkey,
+    pub action: TimelockedAction,
+    pub executable_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ActionCancelled {
     pub repository: Pubkey,
+    pub action: TimelockedAction,
     pub timestamp: i64,
 }
 
@@ -407,6 +12585,53 @@ pub struct OwnershipTransferred {
     pub new_owner: Pubkey,
 }
 
+#[event]
+pub struct OwnershipRenounced {
+    pub repository: Pubkey,
+    pub old_owner: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RepoWrapped {
+    pub repository: Pubkey,
+    pub mint: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RepoUnwrapped {
+    pub repository: Pubkey,
+    pub mint: Pubkey,
+    pub owner: Pubkey,
+    pub timestamp: i64,
+}
+
+/// Log `event` as a self-CPI signed by the event authority, the way
+/// `emit_cpi!` does.
+#[cfg(feature = "event-cpi")]
+fn emit_cpi_event<'info, E: anchor_lang::Event>(
+    event: &E,
+    event_authority: &AccountInfo<'info>,
+    bump: u8,
+) -> Result<()> {
+    let data = anchor_lang::event::EVENT_IX_TAG_LE
+        .into_iter()
+        .chain(event.data())
+        .collect();
+    let ix = anchor_lang::solana_program::instruction::Instruction {
+        program_id: crate::ID,
+        accounts: vec![AccountMeta::new_readonly(event_authority.key(), true)],
+        data,
+    };
+    anchor_lang::solana_program::program::invoke_signed(
+        &ix,
+        std::slice::from_ref(event_authority),
+        &[&[b"__event_authority", &[bump]]],
+    )
+    .map_err(Into::into)
+}
+
 // ============================================================================
 // Errors
 // ============================================================================
@@ -422,7 +12647,7 @@ pub enum VanishError {
     #[msg("Repository name cannot be empty")]
     NameEmpty,
 
-    #[msg("Invalid commit hash (must be 40 characters)")]
+    #[msg("Invalid commit hash (expected a SHA-1 or SHA-256 multihash)")]
     InvalidCommitHash,
 
     #[msg("Invalid IPFS CID")]
@@ -430,4 +12655,1275 @@ pub enum VanishError {
 
     #[msg("Unauthorized")]
     Unauthorized,
+
+    #[msg("Invalid IPNS name (expected a base36 libp2p-key CID or base58 peer ID)")]
+    InvalidIpnsName,
+
+    #[msg("Private repositories require an access key envelope per collaborator; public ones take none")]
+    InvalidAccessKey,
+
+    #[msg("Repository is not private")]
+    RepoNotPrivate,
+
+    #[msg("Repository key epoch overflow")]
+    KeyEpochOverflow,
+
+    #[msg("A repository without an owner must keep at least one admin")]
+    LastAdmin,
+
+    #[msg("Access price and duration must be positive")]
+    InvalidPricing,
+
+    #[msg("Token accounts do not match the repository's pricing")]
+    InvalidPaymentAccount,
+
+    #[msg("Unknown repository flag bits, or ones set_repo_flags cannot change")]
+    InvalidRepoFlags,
+
+    #[msg("Repository is archived")]
+    RepoArchived,
+
+    #[msg("Release policy needs 1-10 distinct managers and a threshold between 1 and their count")]
+    InvalidReleasePolicy,
+
+    #[msg("Invalid release tag (1-32 characters)")]
+    InvalidReleaseTag,
+
+    #[msg("Signer is not a release manager of this repository")]
+    NotReleaseManager,
+
+    #[msg("Release is already final")]
+    ReleaseAlreadyFinal,
+
+    #[msg("Mint is not a fixed-supply NFT held by the signer")]
+    InvalidNftMint,
+
+    #[msg("Badge mint must be a non-transferable Token-2022 mint with no decimals, minted by the badge config")]
+    InvalidBadgeMint,
+
+    #[msg("Badge accounts do not match the repository's badge config")]
+    InvalidBadgeAccounts,
+
+    #[msg("Invalid branch name (1-64 characters)")]
+    InvalidBranchName,
+
+    #[msg("Release assets need a 1-64 character unique filename and an IPFS CID")]
+    InvalidReleaseAsset,
+
+    #[msg("Too many release assets")]
+    TooManyReleaseAssets,
+
+    #[msg("Release assets cannot change once approvals have started")]
+    ReleaseLocked,
+
+    #[msg("Invalid package name (1-32 lowercase letters, digits, '-' or '_', starting with a letter or digit)")]
+    InvalidPackageName,
+
+    #[msg("Version must be greater than the package's latest version")]
+    VersionNotIncreasing,
+
+    #[msg("No asset of this release has that checksum")]
+    UnknownReleaseAsset,
+
+    #[msg("Unsupported hash function or wrong digest length")]
+    InvalidMultihash,
+
+    #[msg("Pass the redirect account if and only if leave_redirect is set")]
+    InvalidRedirect,
+
+    #[msg("Only Repository, Collaborator and Star accounts can be migrated")]
+    UnsupportedAccount,
+
+    #[msg("Account is already at the current layout version")]
+    AccountUpToDate,
+
+    #[msg("Pass child accounts of the repository, each followed by its rent payer")]
+    InvalidChildAccount,
+
+    #[msg("Repository is deleted")]
+    RepoDeleted,
+
+    #[msg("Repository is not deleted")]
+    RepoNotDeleted,
+
+    #[msg("Recovery window is shorter than the minimum")]
+    RecoveryWindowTooShort,
+
+    #[msg("Repository can be restored until its recovery window has passed")]
+    RecoveryWindowOpen,
+    #[msg("Config flags contain unknown bits")]
+    InvalidConfigFlags,
+    #[msg("Repository creation is paused")]
+    CreationPaused,
+    #[msg("Treasury does not hold that much above its rent minimum")]
+    InsufficientTreasury,
+    #[msg("A name stake account is required exactly when the name needs a stake")]
+    InvalidNameStake,
+    #[msg("Name stake is locked until the repository has been active long enough")]
+    StakeLocked,
+    #[msg("User has reached their repository quota")]
+    RepoQuotaExceeded,
+    #[msg("Repository is not flagged as a fork")]
+    NotAFork,
+    #[msg("Upstream does not match the fork's linked upstream")]
+    UpstreamMismatch,
+    #[msg("Submodule path must be a relative path of at most 128 bytes")]
+    InvalidSubmodulePath,
+    #[msg("Head has moved since the expected commit")]
+    StaleHead,
+    #[msg("Force pushes are not allowed here")]
+    ForcePushNotAllowed,
+    #[msg("Ref updates must match the repository's branch accounts passed")]
+    InvalidRefUpdates,
+    #[msg("Proof does not match the repository's refs root")]
+    InvalidRefProof,
+    #[msg("Refs tree has no free leaves")]
+    RefsTreeFull,
+    #[msg(
+        "Co-authors must be distinct, exclude the pusher and come with their contribution accounts"
+    )]
+    InvalidCoAuthors,
+    #[msg("Repository is frozen; only the owner can push")]
+    RepoFrozen,
+    #[msg("Successor must be another live repository, passed as the successor account")]
+    InvalidSuccessor,
+    #[msg("Repository is not a public template")]
+    NotATemplate,
+    #[msg("Verification URI must be 1 to 200 bytes")]
+    InvalidVerificationUri,
+    #[msg("Tip would leave the donation vault below its rent-exempt minimum")]
+    TipBelowRent,
+    #[msg("Donation vault does not hold that much above its rent minimum")]
+    InsufficientDonations,
+    #[msg("Star accounts must belong to the repository and come in ascending address order")]
+    InvalidStarAccounts,
+    #[msg("Expiry must be 0 or in the future")]
+    InvalidExpiry,
+    #[msg("Key holder is neither a collaborator nor an active reader")]
+    NoReadAccess,
+    #[msg("Repository has as many collaborators as it can index")]
+    TooManyCollaborators,
+    #[msg("Index slot accounts do not match the collaborator being removed")]
+    InvalidCollaboratorSlot,
+    #[msg("Collaborator already has an index slot")]
+    CollaboratorIndexed,
+    #[msg("Timelock delay must be between 0 and 30 days, and below the current delay when queued")]
+    InvalidTimelockDelay,
+    #[msg("Repository has no timelock to queue actions behind")]
+    NoTimelock,
+    #[msg("Repository is timelocked; queue the action and wait out the delay")]
+    ActionNotQueued,
+    #[msg("Queued action has not waited out the timelock yet")]
+    TimelockPending,
+    #[msg("Queued action does not match the one being carried out")]
+    ActionMismatch,
+    #[msg("Repository is in a project; pass the project account")]
+    InvalidProjectAccounts,
+    #[msg("Stats out of bounds: at most 8 language tags of 1 to 16 lowercase characters")]
+    InvalidStats,
+    #[msg("Stats for the current head were already recorded")]
+    StatsRecorded,
+    #[msg("Deal is inactive, or still active and covering the current archive")]
+    DealNotLapsed,
+    #[msg("Pinner stake is below the minimum")]
+    PinnerStakeTooLow,
+    #[msg("Pinner is unbonding")]
+    PinnerUnbonding,
+    #[msg("Pinner is already enrolled in this repository")]
+    PinnerEnrolled,
+    #[msg("Pinner is not enrolled in this repository")]
+    PinnerNotEnrolled,
+    #[msg("Repository has as many pinners as it can enroll")]
+    TooManyPinners,
+    #[msg("Pinner stake is still bonded")]
+    PinnerBonded,
+    #[msg("No reward interval has passed since the last distribution")]
+    RewardsNotDue,
+    #[msg("Reward accounts do not match the repository's pinners")]
+    InvalidRewardAccounts,
+    #[msg("Branch patterns must be 1-4 names of up to 64 bytes, with `*` only at the end")]
+    InvalidBranchPattern,
+    #[msg("Collaborator may not push this branch")]
+    BranchNotGranted,
+    #[msg("Push notes need a changelog CID of up to 64 bytes or a message of up to 72")]
+    InvalidPushNote,
+    #[msg("Webhooks must subscribe to at least one known event class")]
+    InvalidWebhookEvents,
+    #[msg("Repository names may only use ASCII letters, digits, '-', '_' and '.', and may not start with '.'")]
+    InvalidRepoName,
+    #[msg("This repository name is reserved")]
+    NameReserved,
+    #[msg(
+        "Reserved names must be valid repository names of up to 32 characters, at most 16 of them"
+    )]
+    InvalidReservedNames,
+    #[msg("List names must be 1-32 bytes")]
+    InvalidListName,
+    #[msg("Remove every entry before deleting the list")]
+    ListNotEmpty,
+    #[msg("The activity cursor only moves forward, up to the current slot")]
+    InvalidActivitySlot,
+    #[msg("Reclaim periods cannot be negative")]
+    InvalidReclaimPolicy,
+    #[msg("Repository reclamation is turned off")]
+    ReclaimDisabled,
+    #[msg("The repository has been pushed to or updated within the idle period")]
+    RepoNotAbandoned,
+    #[msg("The owner can still answer the reclaim notice")]
+    ReclaimGraceOpen,
+    #[msg("The repository has been updated since the reclaim notice")]
+    ReclaimAnswered,
+    #[msg("Recovery plans need a period and an authority other than the owner")]
+    InvalidRecoveryPlan,
+    #[msg("The owner has been active within the recovery plan's inactivity period")]
+    OwnerActive,
+    #[msg("The recovery plan was set by a different owner")]
+    RecoveryPlanLapsed,
+    #[msg("The rent vault does not belong to the repository's project")]
+    InvalidRentVault,
+    #[msg("Rent vault balance is too low")]
+    InsufficientRentVault,
+    #[msg("Too many actions of this kind in the current window; try again later")]
+    RateLimited,
+    #[msg("A snapshot can only be taken at the current slot")]
+    SnapshotNotDue,
+    #[msg("Snapshot accounts must be the repository's Contribution accounts and all of its Star and indexed Collaborator accounts, in ascending address order")]
+    InvalidSnapshotAccounts,
+    #[msg("The repository was checkpointed too recently")]
+    CheckpointNotDue,
+    #[msg(
+        "Replacing the release policy needs its managers' approval or the repository's timelock"
+    )]
+    ReleasePolicyLocked,
+    #[msg("Private repositories cannot be forked")]
+    PrivateUpstream,
+}
+
+// ============================================================================
+// Creation
+// ============================================================================
+
+/// The accounts create_repo and create_from_template share
+struct NewRepository<'a, 'info> {
+    owner: &'a Signer<'info>,
+    repository: &'a mut Account<'info, Repository>,
+    repository_bump: u8,
+    tombstone: &'a UncheckedAccount<'info>,
+    name_lock: &'a mut Account<'info, NameLock>,
+    name_lock_bump: u8,
+    name_stake: &'a mut Option<Account<'info, NameStake>>,
+    name_stake_bump: u8,
+    profile: &'a mut Account<'info, Profile>,
+    profile_bump: u8,
+    config: &'a Account<'info, Config>,
+    treasury: &'a SystemAccount<'info>,
+    system_program: &'a Program<'info, System>,
+}
+
+/// Fill in a Profile that init_if_needed may have just created for `user`.
+fn init_profile(profile: &mut Profile, user: Pubkey, bump: u8) {
+    if profile.user == Pubkey::default() {
+        profile.user = user;
+        profile.bump = bump;
+        profile.version = Profile::VERSION;
+    }
+}
+
+/// Count an action against the user's allowance of `limit` per window of
+/// `Config::rate_window_slots` slots, where `counter` picks the action's
+/// count. Counts start over with each window.
+fn record_action(
+    profile: &mut Profile,
+    config: &Config,
+    limit: u32,
+    counter: fn(&mut Profile) -> &mut u32,
+) -> Result<()> {
+    let window = config.rate_window_slots;
+    if window == 0 {
+        return Ok(());
+    }
+    let slot = Clock::get()?.slot;
+    if slot / window != profile.last_action_slot / window {
+        profile.window_creations = 0;
+        profile.window_stars = 0;
+    }
+    profile.last_action_slot = slot;
+
+    let count = counter(profile);
+    require!(limit == 0 || *count < limit, VanishError::RateLimited);
+    *count += 1;
+    Ok(())
+}
+
+/// Fill in a freshly created, empty repository, after counting it against
+/// the owner's quota and collecting the config's fee and, for short names,
+/// the name stake. Returns the stake's event when one was taken.
+fn init_repository(
+    new: NewRepository,
+    name: String,
+    description: String,
+    is_private: bool,
+) -> Result<Option<NameStaked>> {
+    require!(name.len() <= 64, VanishError::NameTooLong);
+    require!(description.len() <= 256, VanishError::DescriptionTooLong);
+    require!(!name.is_empty(), VanishError::NameEmpty);
+    check_repo_name(&name, new.config)?;
+    require!(
+        new.config.flags & config_flags::PAUSE_CREATION == 0,
+        VanishError::CreationPaused
+    );
+
+    // Short names are the ones worth squatting, so they cost a stake
+    let config = new.config;
+    let stake = (name.len() < config.stake_name_len as usize)
+        .then_some(config.name_stake)
+        .filter(|amount| *amount > 0);
+    require!(
+        new.name_stake.is_some() == stake.is_some(),
+        VanishError::InvalidNameStake
+    );
+
+    let profile = new.profile;
+    init_profile(profile, new.owner.key(), new.profile_bump);
+    record_action(
+        profile,
+        config,
+        config.max_creations_per_window,
+        |profile| &mut profile.window_creations,
+    )?;
+    let limit = match profile.repo_limit {
+        0 => config.max_repos,
+        limit => limit,
+    };
+    require!(
+        limit == 0 || profile.repo_count < limit,
+        VanishError::RepoQuotaExceeded
+    );
+    profile.repo_count += 1;
+
+    let fee = config.repo_fee;
+    if fee > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                new.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: new.owner.to_account_info(),
+                    to: new.treasury.to_account_info(),
+                },
+            ),
+            fee,
+        )?;
+    }
+
+    let repo = new.repository;
+    let clock = Clock::get()?;
+    let id = new_repository_id(&repo.key(), new.tombstone)?;
+
+    let mut staked = None;
+    if let (Some(name_stake), Some(amount)) = (new.name_stake, stake) {
+        system_program::transfer(
+            CpiContext::new(
+                new.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: new.owner.to_account_info(),
+                    to: name_stake.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        name_stake.repository = id;
+        name_stake.staker = new.owner.key();
+        name_stake.amount = amount;
+        name_stake.staked_at = clock.unix_timestamp;
+        name_stake.bump = new.name_stake_bump;
+
+        staked = Some(NameStaked {
+            repository: repo.key(),
+            staker: name_stake.staker,
+            amount,
+            timestamp: clock.unix_timestamp,
+        });
+    }
+
+    repo.owner = new.owner.key();
+    repo.creator = new.owner.key();
+    repo.flags = 0;
+    repo.name = name;
+    repo.description = description;
+    repo.description_storage = DescriptionStorage::Inline;
+    repo.is_private = is_private;
+    repo.key_epoch = 0;
+    repo.created_at = clock.unix_timestamp;
+    repo.updated_at = clock.unix_timestamp;
+    repo.head_commit = ObjectId::default();
+    repo.ipfs_cid = Cid::default();
+    repo.objects_cid = String::new();
+    repo.ipns_name = String::new();
+    repo.stars = 0;
+    repo.admin_count = 0;
+    repo.id = id;
+    repo.bump = new.repository_bump;
+    new.name_lock.repository = repo.id;
+    new.name_lock.bump = new.name_lock_bump;
+    repo.version = Repository::VERSION;
+    repo.deleted_at = 0;
+    repo.purgeable_at = 0;
+    repo.refs_root = [0; 32];
+    repo.ref_count = 0;
+    repo.weighted_stars = 0;
+    repo.stars_decayed_at = clock.unix_timestamp;
+    repo.audit_count = 0;
+    repo.successor = None;
+    repo.template = None;
+    repo.collaborator_count = 0;
+    repo.pinners = Vec::new();
+    repo.readme_cid = Cid::default();
+    repo.metadata_cid = Cid::default();
+    repo.forks = 0;
+    repo.checkpoint_count = 0;
+    repo.checkpointed_slot = 0;
+
+    Ok(staked)
+}
+
+/// Check that `name` is well formed and not reserved by `config`.
+fn check_repo_name(name: &str, config: &Config) -> Result<()> {
+    require!(is_valid_repo_name(name), VanishError::InvalidRepoName);
+    require!(
+        !config
+            .reserved_names
+            .iter()
+            .any(|reserved| reserved.eq_ignore_ascii_case(name)),
+        VanishError::NameReserved
+    );
+    Ok(())
+}
+
+// ============================================================================
+// Migration
+// ============================================================================
+
+/// Grow `account` to `space`, topping up its rent from `payer`
+fn grow<'info>(
+    account: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    space: usize,
+) -> Result<()> {
+    let rent = Rent::get()?.minimum_balance(space);
+    if account.lamports() < rent {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.clone(),
+                system_program::Transfer {
+                    from: payer.clone(),
+                    to: account.clone(),
+                },
+            ),
+            rent - account.lamports(),
+        )?;
+    }
+    if account.data_len() < space {
+        account.realloc(space, true)?;
+    }
+    Ok(())
+}
+
+/// Grow `account` to `space` and stamp `version` into it. Layouts since
+/// versions were introduced only append fields, so an older one reads the
+/// new ones from the zeroed tail. Returns the new version.
+fn migrate_layout<'info, T: AccountSerialize + AccountDeserialize>(
+    account: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    space: usize,
+    version: u8,
+    version_of: fn(&mut T) -> &mut u8,
+) -> Result<u8> {
+    grow(account, payer, system_program, space)?;
+
+    let mut state = T::try_deserialize(&mut &account.try_borrow_data()?[..])?;
+    let current = version_of(&mut state);
+    require!(*current < version, VanishError::AccountUpToDate);
+    *current = version;
+    state.try_serialize(&mut &mut account.try_borrow_mut_data()?[..])?;
+
+    Ok(version)
+}
+
+/// Replace a first-deployment account with `state`, already upgraded from
+/// it, grown to `space`. The bytes past `state` are zeroed, so fields
+/// appended later read as zero when migrated.
+fn rewrite_legacy<'info, T: AccountSerialize>(
+    account: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    space: usize,
+    state: &T,
+) -> Result<()> {
+    grow(account, payer, system_program, space)?;
+
+    let mut data = account.try_borrow_mut_data()?;
+    let mut writer: &mut [u8] = &mut data;
+    state.try_serialize(&mut writer)?;
+    writer.fill(0);
+    Ok(())
+}
+
+/// Upgrade a repository. First-deployment accounts hold a different set of
+/// fields in a different order, so they are read as a LegacyRepository and
+/// rewritten; later layouts only append fields.
+fn migrate_repository<'info>(
+    account: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<u8> {
+    if account.data_len() != LegacyRepository::SPACE {
+        return migrate_layout(
+            account,
+            payer,
+            system_program,
+            Repository::SPACE,
+            Repository::VERSION,
+            |repo: &mut Repository| &mut repo.version,
+        );
+    }
+
+    let legacy = LegacyRepository::deserialize(&mut &account.try_borrow_data()?[8..])?;
+    let repo = legacy.upgrade(account.key())?;
+    rewrite_legacy(account, payer, system_program, Repository::SPACE, &repo)?;
+
+    Ok(Repository::VERSION)
+}
+
+/// Widen every entry of a version 0 push log to the current PushEntry,
+/// leaving the added push note fields zeroed. Entries are copied out before
+/// the account grows, since they move to wider slots.
+fn migrate_push_log<'info>(
+    account: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<u8> {
+    require!(
+        account.data_len() == PushLog::V0_SPACE,
+        VanishError::AccountUpToDate
+    );
+    let entries = account.try_borrow_data()?[PushLog::HEADER_SPACE..].to_vec();
+
+    grow(account, payer, system_program, PushLog::SPACE)?;
+
+    let mut data = account.try_borrow_mut_data()?;
+    for (i, entry) in entries.chunks(PushEntry::V0_SPACE).enumerate() {
+        let start = PushLog::HEADER_SPACE + i * PushEntry::SPACE;
+        data[start..start + PushEntry::V0_SPACE].copy_from_slice(entry);
+        data[start + PushEntry::V0_SPACE..start + PushEntry::SPACE].fill(0);
+    }
+
+    Ok(PushLog::VERSION)
+}
+
+// ============================================================================
+// Cleanup
+// ============================================================================
+
+/// Count a repository leaving `address`, in the Tombstone init_if_needed
+/// may have just created for it
+fn leave_address(tombstone: &mut Tombstone, address: Pubkey, bump: u8) {
+    tombstone.address = address;
+    tombstone.generation += 1;
+    tombstone.bump = bump;
+}
+
+/// Close the child accounts of the repository with `repository_id` in
+/// `accounts`, each followed by the account its rent goes back to: the one
+/// `child_rent_payer` names, or `owner`. Returns how many were closed.
+fn close_children(accounts: &[AccountInfo], repository_id: &Pubkey, owner: &Pubkey) -> Result<u32> {
+    for pair in accounts.chunks(2) {
+        let [child, refund] = pair else {
+            return err!(VanishError::InvalidChildAccount);
+        };
+        let payer = child_rent_payer(child, repository_id)?.unwrap_or(*owner);
+        require_keys_eq!(refund.key(), payer, VanishError::InvalidChildAccount);
+
+        let lamports = child.lamports();
+        **refund.try_borrow_mut_lamports()? += lamports;
+        **child.try_borrow_mut_lamports()? = 0;
+        child.assign(&system_program::ID);
+        child.realloc(0, false)?;
+    }
+    Ok((accounts.len() / 2) as u32)
+}
+
+/// Who gets the rent of `child`, a child account of the repository with
+/// `repository_id`, back: the user for stars and access grants, the attestor
+/// for check runs, the staker for name stakes, the contributor for
+/// contributions, the verifier for verifications, the operator for pin
+/// attestations, and `None` for the rest
+/// (collaborators and their index slots and branch grants, read grants,
+/// keys, branches, submodules, fork links, settings, webhooks, the name lock
+/// and the reward pool, with whatever it has not paid out), whose rent goes
+/// to the owner.
+/// Releases, packages, audit entries and the NFT escrow outlive the
+/// repository and cannot be closed this way.
+fn child_rent_payer(child: &AccountInfo, repository_id: &Pubkey) -> Result<Option<Pubkey>> {
+    require_keys_eq!(*child.owner, crate::ID, VanishError::InvalidChildAccount);
+
+    let data = child.try_borrow_data()?;
+    let discriminator = data.get(..8).ok_or(VanishError::InvalidChildAccount)?;
+    let mut reader: &[u8] = &data;
+
+    let (repository, payer) = if discriminator == Collaborator::DISCRIMINATOR {
+        (Collaborator::try_deserialize(&mut reader)?.repository, None)
+    } else if discriminator == CollaboratorSlot::DISCRIMINATOR {
+        (
+            CollaboratorSlot::try_deserialize(&mut reader)?.repository,
+            None,
+        )
+    } else if discriminator == ReadGrant::DISCRIMINATOR {
+        (ReadGrant::try_deserialize(&mut reader)?.repository, None)
+    } else if discriminator == AccessKey::DISCRIMINATOR {
+        (AccessKey::try_deserialize(&mut reader)?.repository, None)
+    } else if discriminator == Branch::DISCRIMINATOR {
+        (Branch::try_deserialize(&mut reader)?.repository, None)
+    } else if discriminator == Submodule::DISCRIMINATOR {
+        (Submodule::try_deserialize(&mut reader)?.repository, None)
+    } else if discriminator == ForkLink::DISCRIMINATOR {
+        (ForkLink::try_deserialize(&mut reader)?.repository, None)
+    } else if discriminator == AccessPricing::DISCRIMINATOR {
+        (
+            AccessPricing::try_deserialize(&mut reader)?.repository,
+            None,
+        )
+    } else if discriminator == CiAttestor::DISCRIMINATOR {
+        (CiAttestor::try_deserialize(&mut reader)?.repository, None)
+    } else if discriminator == BadgeConfig::DISCRIMINATOR {
+        (BadgeConfig::try_deserialize(&mut reader)?.repository, None)
+    } else if discriminator == BranchGrant::DISCRIMINATOR {
+        (BranchGrant::try_deserialize(&mut reader)?.repository, None)
+    } else if discriminator == RewardPool::DISCRIMINATOR {
+        (RewardPool::try_deserialize(&mut reader)?.repository, None)
+    } else if discriminator == Webhook::DISCRIMINATOR {
+        (Webhook::try_deserialize(&mut reader)?.repository, None)
+    } else if discriminator == NameLock::DISCRIMINATOR {
+        (NameLock::try_deserialize(&mut reader)?.repository, None)
+    } else if discriminator == PushLog::DISCRIMINATOR {
+        // Read in place, as logs at either layout version start the same way
+        let repository = data.get(8..40).ok_or(VanishError::InvalidChildAccount)?;
+        (Pubkey::try_from(repository).unwrap(), None)
+    } else if discriminator == Star::DISCRIMINATOR {
+        let star = Star::try_deserialize(&mut reader)?;
+        (star.repository, Some(star.user))
+    } else if discriminator == AccessGrant::DISCRIMINATOR {
+        let grant = AccessGrant::try_deserialize(&mut reader)?;
+        (grant.repository, Some(grant.user))
+    } else if discriminator == CheckRun::DISCRIMINATOR {
+        let run = CheckRun::try_deserialize(&mut reader)?;
+        (run.repository, Some(run.attestor))
+    } else if discriminator == NameStake::DISCRIMINATOR {
+        let stake = NameStake::try_deserialize(&mut reader)?;
+        (stake.repository, Some(stake.staker))
+    } else if discriminator == Contribution::DISCRIMINATOR {
+        let contribution = Contribution::try_deserialize(&mut reader)?;
+        (contribution.repository, Some(contribution.contributor))
+    } else if discriminator == Verification::DISCRIMINATOR {
+        let verification = Verification::try_deserialize(&mut reader)?;
+        (verification.repository, Some(verification.verifier))
+    } else if discriminator == PinAttestation::DISCRIMINATOR {
+        let attestation = PinAttestation::try_deserialize(&mut reader)?;
+        (attestation.repository, Some(attestation.operator))
+    } else if discriminator == ReclaimNotice::DISCRIMINATOR {
+        let notice = ReclaimNotice::try_deserialize(&mut reader)?;
+        (notice.repository, Some(notice.claimant))
+    } else if discriminator == RecoveryPlan::DISCRIMINATOR {
+        (RecoveryPlan::try_deserialize(&mut reader)?.repository, None)
+    } else {
+        return err!(VanishError::InvalidChildAccount);
+    };
+
+    require_keys_eq!(repository, *repository_id, VanishError::InvalidChildAccount);
+    Ok(payer)
+}
+
+// ============================================================================
+// Validation
+// ============================================================================
+
+/// Decode a program account passed unchecked, `None` while it does not exist.
+fn try_load<T: AccountDeserialize + Owner>(info: &AccountInfo) -> Result<Option<T>> {
+    if info.data_is_empty() || *info.owner != T::owner() {
+        return Ok(None);
+    }
+    Ok(Some(T::try_deserialize(&mut &info.try_borrow_data()?[..])?))
+}
+
+/// The `id` a repository created at `address` gets, going by the Tombstone
+/// there
+fn new_repository_id(address: &Pubkey, tombstone: &AccountInfo) -> Result<Pubkey> {
+    let generation = try_load::<Tombstone>(tombstone)?.map_or(0, |tomb| tomb.generation);
+    Ok(Repository::id_at(address, generation))
+}
+
+/// Pay `payer` back for `cost` lamports of rent it just put into accounts
+/// for a project's repository, from the project's RentVault when one is
+/// passed. Covers at most `cap` of the vault's, and nothing when the vault
+/// cannot afford it, leaving the cost with the payer. Returns the amount
+/// paid back.
+fn fund_rent<'info>(
+    rent_vault: &mut Option<Account<'info, RentVault>>,
+    project_repo: &Option<Account<'info, ProjectRepo>>,
+    payer: &AccountInfo<'info>,
+    cost: u64,
+    cap: impl Fn(&RentVault) -> u64,
+) -> Result<u64> {
+    let Some(vault) = rent_vault else {
+        return Ok(0);
+    };
+    let link = project_repo.as_ref().ok_or(VanishError::InvalidRentVault)?;
+    require_keys_eq!(link.project, vault.project, VanishError::InvalidRentVault);
+
+    let amount = cost.min(cap(vault));
+    let info = vault.to_account_info();
+    let available = info
+        .lamports()
+        .saturating_sub(Rent::get()?.minimum_balance(RentVault::SPACE));
+    if amount == 0 || amount > available {
+        return Ok(0);
+    }
+
+    **info.try_borrow_mut_lamports()? -= amount;
+    **payer.try_borrow_mut_lamports()? += amount;
+    vault.spent = vault.spent.saturating_add(amount);
+    Ok(amount)
+}
+
+/// Check that the owner let `notice` lapse: its grace period is over and
+/// the repository has not been pushed to or updated since it was filed.
+fn require_reclaimable(repo: &Repository, notice: &ReclaimNotice, now: i64) -> Result<()> {
+    require_live(repo)?;
+    require!(now >= notice.deadline, VanishError::ReclaimGraceOpen);
+    require!(
+        repo.updated_at < notice.filed_at,
+        VanishError::ReclaimAnswered
+    );
+    Ok(())
+}
+
+/// Check a timelocked action against the repository's Timelock, which
+/// `timelock` holds unless none was ever set
+fn require_unlocked(
+    timelock: &AccountInfo,
+    pending: &Option<Account<PendingAction>>,
+    action: &TimelockedAction,
+) -> Result<()> {
+    if timelock.data_is_empty() {
+        return Ok(());
+    }
+    let timelock = Timelock::try_deserialize(&mut &timelock.try_borrow_data()?[..])?;
+    require_queued(timelock.delay, pending, action)
+}
+
+/// With a non-zero `delay`, check that `action` was queued in `pending` and
+/// its delay has passed
+fn require_queued(
+    delay: i64,
+    pending: &Option<Account<PendingAction>>,
+    action: &TimelockedAction,
+) -> Result<()> {
+    if delay == 0 {
+        return Ok(());
+    }
+    let pending = pending.as_ref().ok_or(VanishError::ActionNotQueued)?;
+    require!(pending.action == *action, VanishError::ActionMismatch);
+    require!(
+        Clock::get()?.unix_timestamp >= pending.executable_at,
+        VanishError::TimelockPending
+    );
+    Ok(())
+}
+
+/// Check that `repo` is not awaiting purge. Deleted repositories take no
+/// writes until they are restored.
+fn require_live(repo: &Repository) -> Result<()> {
+    require!(repo.deleted_at == 0, VanishError::RepoDeleted);
+    Ok(())
+}
+
+/// Check that `authority` may administer `repo`: either it is the owner, or
+/// `admin` is its Collaborator account (seeds tie it to `authority`) and
+/// carries the admin role.
+fn require_admin(
+    repo: &Repository,
+    authority: &Pubkey,
+    admin: &Option<Account<Collaborator>>,
+) -> Result<()> {
+    require_live(repo)?;
+
+    if *authority == repo.owner {
+        return Ok(());
+    }
+
+    match admin {
+        Some(admin) if admin.is_admin => Ok(()),
+        _ => err!(VanishError::Unauthorized),
+    }
+}
+
+/// Check that `authority` may verify repositories: either it is the config
+/// admin, or `verifier` is its Verifier account (seeds tie it to `authority`).
+fn require_verifier(
+    config: &Config,
+    authority: &Pubkey,
+    verifier: &Option<Account<Verifier>>,
+) -> Result<()> {
+    if *authority == config.admin || verifier.is_some() {
+        return Ok(());
+    }
+    err!(VanishError::Unauthorized)
+}
+
+/// What lets a pusher push, as found by `push_access`
+enum PushAccess<'a> {
+    /// The owner, or a collaborator with push access: any branch
+    Full,
+    /// A member of the repository's project: the default branch
+    ProjectMember,
+    /// A collaborator without push access: the branches `grant` covers
+    Branches(&'a BranchGrant),
+}
+
+/// The push rules, for the push instructions and the can_push view alike.
+/// On top of require_writable, anyone but the owner needs a Collaborator
+/// account with push access, or one with a BranchGrant `grant`, or must push
+/// as a `project_member` (see pushes_as_member).
+fn push_access<'a>(
+    repo: &Repository,
+    pusher: &Pubkey,
+    collaborator: Option<&Collaborator>,
+    grant: Option<&'a BranchGrant>,
+    project_member: bool,
+) -> Result<PushAccess<'a>> {
+    require_writable(repo, pusher)?;
+
+    if *pusher == repo.owner || collaborator.is_some_and(|collab| collab.can_push) {
+        return Ok(PushAccess::Full);
+    }
+    if project_member {
+        return Ok(PushAccess::ProjectMember);
+    }
+    match (collaborator, grant) {
+        (Some(_), Some(grant)) => Ok(PushAccess::Branches(grant)),
+        _ => err!(VanishError::Unauthorized),
+    }
+}
+
+/// Update a pushing collaborator's last push time to `now`
+fn record_push_time(
+    repo: &Repository,
+    pusher: &Pubkey,
+    collaborator: &mut Option<Account<Collaborator>>,
+    now: i64,
+) {
+    if let Some(collab) = collaborator.as_mut().filter(|_| *pusher != repo.owner) {
+        collab.last_push_at = now;
+    }
+}
+
+/// Check that `pusher` may push to `repo` (see push_access) without a
+/// BranchGrant or project membership, and record the push time.
+fn require_pusher(
+    repo: &Repository,
+    pusher: &Pubkey,
+    collaborator: &mut Option<Account<Collaborator>>,
+    now: i64,
+) -> Result<()> {
+    push_access(repo, pusher, collaborator.as_deref(), None, false)?;
+    record_push_time(repo, pusher, collaborator, now);
+
+    Ok(())
+}
+
+/// Like require_pusher, but a collaborator without push access may still
+/// push the branches its `grant` covers. Returns the grant when the push is
+/// limited to it, for the caller to check each branch against.
+fn require_branch_pusher<'a>(
+    repo: &Repository,
+    pusher: &Pubkey,
+    collaborator: &mut Option<Account<Collaborator>>,
+    grant: &'a Option<Account<BranchGrant>>,
+    now: i64,
+) -> Result<Option<&'a BranchGrant>> {
+    let access = push_access(
+        repo,
+        pusher,
+        collaborator.as_deref(),
+        grant.as_deref(),
+        false,
+    )?;
+    record_push_time(repo, pusher, collaborator, now);
+
+    Ok(match access {
+        PushAccess::Branches(grant) => Some(grant),
+        _ => None,
+    })
+}
+
+/// Checks every push goes through, however the pusher got push access: the
+/// repository must be live and not archived, and unfrozen for anyone but the
+/// owner.
+fn require_writable(repo: &Repository, pusher: &Pubkey) -> Result<()> {
+    require_live(repo)?;
+    require!(
+        repo.flags & repo_flags::ARCHIVED == 0,
+        VanishError::RepoArchived
+    );
+    if *pusher != repo.owner {
+        require!(
+            repo.flags & repo_flags::FROZEN == 0,
+            VanishError::RepoFrozen
+        );
+    }
+    Ok(())
+}
+
+/// Count a push towards the repository's project, if `link` (its ProjectRepo
+/// PDA) shows it is in one, and tell whether `member` lets the pusher push
+/// as a member of that project.
+fn record_project_push(
+    link: &AccountInfo,
+    project: &mut Option<Account<Project>>,
+    member: &Option<Account<ProjectMember>>,
+    pusher: &Pubkey,
+    now: i64,
+) -> Result<bool> {
+    if link.data_is_empty() {
+        return Ok(false);
+    }
+    let link = ProjectRepo::try_deserialize(&mut &link.try_borrow_data()?[..])?;
+    let project = project
+        .as_mut()
+        .filter(|project| project.key() == link.project)
+        .ok_or(VanishError::InvalidProjectAccounts)?;
+
+    project.push_count += 1;
+    project.updated_at = now;
+
+    Ok(member
+        .as_ref()
+        .is_some_and(|member| pushes_as_member(&link, member, pusher)))
+}
+
+/// Whether `member` lets `pusher` push as a member of the project `link`
+/// puts the repository in
+fn pushes_as_member(link: &ProjectRepo, member: &ProjectMember, pusher: &Pubkey) -> bool {
+    member.project == link.project && member.user == *pusher && member.can_push
+}
+
+/// Append `action` to the repository's audit log. `entry` is the AuditEntry
+/// PDA at the repository's current `audit_count`, just created.
+fn record_audit(
+    repo: &mut Repository,
+    entry: &mut AuditEntry,
+    bump: u8,
+    actor: Pubkey,
+    action: AuditAction,
+    subject: Pubkey,
+    now: i64,
+) {
+    entry.repository = repo.id;
+    entry.seq = repo.audit_count;
+    entry.actor = actor;
+    entry.action = action;
+    entry.subject = subject;
+    entry.timestamp = now;
+    entry.bump = bump;
+    repo.audit_count += 1;
+}
+
+/// Append an entry to `project`'s audit log
+fn record_project_audit(
+    project: &mut Account<Project>,
+    entry: &mut ProjectAuditEntry,
+    bump: u8,
+    actor: Pubkey,
+    action: ProjectAuditAction,
+    subject: Pubkey,
+    now: i64,
+) {
+    entry.project = project.key();
+    entry.seq = project.audit_count;
+    entry.actor = actor;
+    entry.action = action;
+    entry.subject = subject;
+    entry.timestamp = now;
+    entry.bump = bump;
+    project.audit_count += 1;
+}
+
+/// Count a push to the repository with `repository_id` towards
+/// `contributor`'s Contribution account
+fn record_contribution(
+    contribution: &mut Contribution,
+    repository_id: Pubkey,
+    contributor: Pubkey,
+    bump: u8,
+    now: i64,
+) {
+    if contribution.push_count == 0 {
+        contribution.repository = repository_id;
+        contribution.contributor = contributor;
+        contribution.first_contributed_at = now;
+        contribution.bump = bump;
+    }
+    contribution.push_count = contribution.push_count.saturating_add(1);
+    contribution.last_contributed_at = now;
+}
+
+/// Count a push towards `co_author`, whose Contribution account `info` is
+/// created at `payer`'s expense if it does not exist yet
+fn credit_co_author<'info>(
+    info: &'info AccountInfo<'info>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    repository_id: Pubkey,
+    co_author: Pubkey,
+    now: i64,
+) -> Result<()> {
+    let (address, bump) = Pubkey::find_program_address(
+        &[b"contribution", repository_id.as_ref(), co_author.as_ref()],
+        &crate::ID,
+    );
+    require_keys_eq!(info.key(), address, VanishError::InvalidCoAuthors);
+
+    if info.data_is_empty() {
+        let seeds: &[&[u8]] = &[
+            b"contribution",
+            repository_id.as_ref(),
+            co_author.as_ref(),
+            &[bump],
+        ];
+        // Lamports sent there beforehand count towards the rent
+        let rent = Rent::get()?.minimum_balance(Contribution::SPACE);
+        if info.lamports() < rent {
+            system_program::transfer(
+                CpiContext::new(
+                    system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: payer.to_account_info(),
+                        to: info.clone(),
+                    },
+                ),
+                rent - info.lamports(),
+            )?;
+        }
+        system_program::allocate(
+            CpiContext::new_with_signer(
+                system_program.to_account_info(),
+                system_program::Allocate {
+                    account_to_allocate: info.clone(),
+                },
+                &[seeds],
+            ),
+            Contribution::SPACE as u64,
+        )?;
+        system_program::assign(
+            CpiContext::new_with_signer(
+                system_program.to_account_info(),
+                system_program::Assign {
+                    account_to_assign: info.clone(),
+                },
+                &[seeds],
+            ),
+            &crate::ID,
+        )?;
+        let empty = Contribution {
+            repository: repository_id,
+            contributor: co_author,
+            push_count: 0,
+            first_contributed_at: 0,
+            last_contributed_at: 0,
+            bump,
+        };
+        empty.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+    }
+
+    let mut contribution = Account::<Contribution>::try_from(info)?;
+    record_contribution(&mut contribution, repository_id, co_author, bump, now);
+    contribution.exit(&crate::ID)
+}
+
+/// Compare-and-swap check for a push replacing `current`: the pusher must
+/// name the head they built on, unless force pushes are allowed or nothing
+/// has been pushed yet.
+fn require_expected_head(
+    current: &impl PartialEq<Multihash>,
+    expected: Option<&Multihash>,
+    allow_force_push: bool,
+) -> Result<()> {
+    match expected {
+        Some(expected) => require!(current == expected, VanishError::StaleHead),
+        None => require!(
+            allow_force_push || *current == Multihash::default(),
+            VanishError::ForcePushNotAllowed
+        ),
+    }
+
+    Ok(())
+}
+
+/// Move `branch` to `tip` and update its leaf in the repository's refs tree,
+/// taking the next free leaf if it has none. `proof` must prove the leaf's
+/// current value against `refs_root`.
+fn set_ref_tip(
+    repo: &mut Repository,
+    branch: &mut Branch,
+    tip: Multihash,
+    proof: &[[u8; 32]],
+) -> Result<()> {
+    require!(
+        proof.len() == refs_tree::DEPTH,
+        VanishError::InvalidRefProof
+    );
+
+    let (index, leaf) = match branch.ref_index {
+        Some(index) => (index, refs_tree::leaf(&branch.name, &branch.head_commit)),
+        None => {
+            require!(
+                repo.ref_count < refs_tree::CAPACITY,
+                VanishError::RefsTreeFull
+            );
+            repo.ref_count += 1;
+            (repo.ref_count - 1, [0; 32])
+        }
+    };
+    require!(
+        refs_tree::root_with(leaf, index, proof) == repo.refs_root,
+        VanishError::InvalidRefProof
+    );
+
+    branch.ref_index = Some(index);
+    branch.head_commit = tip;
+    repo.refs_root = refs_tree::root_with(
+        refs_tree::leaf(&branch.name, &branch.head_commit),
+        index,
+        proof,
+    );
+
+    Ok(())
+}
+
+/// Point `submodule` at `target` and `commit`, as `pusher`
+fn pin_submodule(
+    repo: &mut Repository,
+    pusher: &Pubkey,
+    collaborator: &mut Option<Account<Collaborator>>,
+    target: &Repository,
+    submodule: &mut Submodule,
+    commit: Multihash,
+) -> Result<()> {
+    let clock = Clock::get()?;
+    require_pusher(repo, pusher, collaborator, clock.unix_timestamp)?;
+    require_live(target)?;
+    require!(commit.is_git_object_id(), VanishError::InvalidCommitHash);
+
+    submodule.target = target.id;
+    submodule.commit = commit;
+    submodule.updated_at = clock.unix_timestamp;
+    repo.updated_at = clock.unix_timestamp;
+    Ok(())
+}
+
+/// Submodule paths are relative and normalised: no leading or trailing
+/// slash, and no empty, `.` or `..` segments.
+fn is_valid_submodule_path(path: &str) -> bool {
+    !path.is_empty()
+        && path.len() <= 128
+        && path
+            .split('/')
+            .all(|segment| !segment.is_empty() && segment != "." && segment != "..")
+}
+
+/// Check that `mint` is a Token-2022 mint with the NonTransferable extension,
+/// no decimals, and `authority` as its mint authority.
+fn is_badge_mint(mint: &AccountInfo, authority: &Pubkey) -> Result<bool> {
+    if *mint.owner != token_2022::ID {
+        return Ok(false);
+    }
+
+    let data = mint.try_borrow_data()?;
+    let state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+
+    Ok(state.base.decimals == 0
+        && state.base.mint_authority == Some(*authority).into()
+        && state.get_extension::<NonTransferable>().is_ok())
+}
+
+/// Fee a Token-2022 mint withholds from a transfer that has to deliver
+/// `amount` to the recipient. Zero for mints without a transfer fee,
+/// including every classic SPL mint.
+fn inverse_transfer_fee(mint: &AccountInfo, amount: u64) -> Result<u64> {
+    let data = mint.try_borrow_data()?;
+    let state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+
+    match state.get_extension::<TransferFeeConfig>() {
+        Ok(config) => config
+            .calculate_inverse_epoch_fee(Clock::get()?.epoch, amount)
+            .ok_or_else(|| error!(VanishError::InvalidPricing)),
+        Err(_) => Ok(0),
+    }
+}
+
+/// Check that a repository name uses only ASCII letters, digits, `-`, `_`
+/// and `.`, and does not start with `.`, so it renders unambiguously and
+/// reads as a single path segment. Length is checked separately.
+pub fn is_valid_repo_name(name: &str) -> bool {
+    !name.starts_with('.')
+        && name
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_' || b == b'.')
+}
+
+/// Check that a package name is 1-32 characters of lowercase ASCII letters,
+/// digits, `-` and `_`, starting with a letter or digit.
+pub fn is_valid_package_name(name: &str) -> bool {
+    name.len() <= 32
+        && name
+            .bytes()
+            .next()
+            .is_some_and(|first| first.is_ascii_lowercase() || first.is_ascii_digit())
+        && name
+            .bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-' || b == b'_')
+}
+
+/// Check that a language tag is 1-16 characters of lowercase ASCII letters,
+/// digits, `+`, `#` and `-`, enough for names like `c++` or `objective-c`.
+pub fn is_valid_language_tag(tag: &str) -> bool {
+    (1..=Stats::MAX_LANGUAGE_LEN).contains(&tag.len())
+        && tag.bytes().all(|b| {
+            b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'+' || b == b'#' || b == b'-'
+        })
+}
+
+/// Check that an IPNS name is a libp2p key identifier, either as a CIDv1 in
+/// base36 (`k51...`) or as a legacy base58btc peer ID (`12D3KooW...`, `Qm...`).
+/// DNSLink names are intentionally not accepted.
+pub fn is_valid_ipns_name(name: &str) -> bool {
+    const BASE58: &str = "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+    if name.len() > 64 {
+        return false;
+    }
+
+    if let Some(rest) = name.strip_prefix('k') {
+        return name.len() >= 50
+            && rest
+                .bytes()
+                .all(|b| b.is_ascii_digit() || b.is_ascii_lowercase());
+    }
+
+    let peer_id = (name.starts_with("12D3KooW") && name.len() == 52)
+        || (name.starts_with("Qm") && name.len() == 46);
+
+    peer_id && name.chars().all(|c| BASE58.contains(c))
 }
@@ -0,0 +1,458 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_spl::token::spl_token;
+use anchor_spl::token_2022::spl_token_2022;
+use anchor_spl::token_2022::spl_token_2022::extension::{ExtensionType, StateWithExtensions};
+use common::*;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::system_instruction;
+use vanish_program::{accounts, instruction, AccessGrant, AccessPricing};
+
+fn pricing_ix(
+    authority: &Keypair,
+    repository: Pubkey,
+    mint: Option<Pubkey>,
+    price: u64,
+    duration: i64,
+) -> Instruction {
+    ix(
+        accounts::SetAccessPricing {
+            authority: authority.pubkey(),
+            repository,
+            admin: None,
+            pricing: pda::pricing(&repository),
+            system_program: system_program(),
+        },
+        instruction::SetAccessPricing {
+            mint,
+            price,
+            duration,
+        },
+    )
+}
+
+/// Token accounts for paying in an SPL mint
+struct Payment {
+    token_program: Pubkey,
+    mint: Pubkey,
+    from: Pubkey,
+    to: Pubkey,
+}
+
+impl Payment {
+    fn classic(mint: Pubkey, from: Pubkey, to: Pubkey) -> Option<Self> {
+        Some(Self {
+            token_program: spl_token::ID,
+            mint,
+            from,
+            to,
+        })
+    }
+}
+
+fn purchase_ix(
+    buyer: &Keypair,
+    repository: Pubkey,
+    owner: Pubkey,
+    payment: Option<Payment>,
+) -> Instruction {
+    ix(
+        accounts::PurchaseAccess {
+            buyer: buyer.pubkey(),
+            repository,
+            owner,
+            pricing: pda::pricing(&repository),
+            grant: pda::grant(&repository, &buyer.pubkey()),
+            buyer_token_account: payment.as_ref().map(|payment| payment.from),
+            owner_token_account: payment.as_ref().map(|payment| payment.to),
+            payment_mint: payment.as_ref().map(|payment| payment.mint),
+            token_program: payment.as_ref().map(|payment| payment.token_program),
+            system_program: system_program(),
+        },
+        instruction::PurchaseAccess {},
+    )
+}
+
+#[tokio::test]
+async fn set_access_pricing_validates_offer() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let stranger = env.user().await;
+    let private = env.create_repo(&owner, "private", true).await;
+    let public = env.create_repo(&owner, "public", false).await;
+
+    assert_error(
+        env.send(&[pricing_ix(&owner, public, None, 100, 60)], &[&owner])
+            .await,
+        VanishError::RepoNotPrivate,
+    );
+    assert_error(
+        env.send(&[pricing_ix(&owner, private, None, 0, 60)], &[&owner])
+            .await,
+        VanishError::InvalidPricing,
+    );
+    assert_error(
+        env.send(&[pricing_ix(&owner, private, None, 100, 0)], &[&owner])
+            .await,
+        VanishError::InvalidPricing,
+    );
+    assert_error(
+        env.send(
+            &[pricing_ix(&stranger, private, None, 100, 60)],
+            &[&stranger],
+        )
+        .await,
+        VanishError::Unauthorized,
+    );
+
+    env.send(&[pricing_ix(&owner, private, None, 100, 60)], &[&owner])
+        .await
+        .unwrap();
+    env.send(&[pricing_ix(&owner, private, None, 200, 90)], &[&owner])
+        .await
+        .unwrap();
+    let pricing: AccessPricing = env.account(pda::pricing(&private)).await;
+    assert_eq!(pricing.price, 200);
+    assert_eq!(pricing.duration, 90);
+    assert_eq!(pricing.mint, None);
+}
+
+#[tokio::test]
+async fn purchase_access_in_lamports_extends_grants() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let buyer = env.user().await;
+    let repository = env.create_repo(&owner, "repo", true).await;
+    env.send(
+        &[pricing_ix(&owner, repository, None, 1_000_000, 3600)],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+
+    let before = env.lamports(owner.pubkey()).await;
+    let now = env.now().await;
+    env.send(
+        &[purchase_ix(&buyer, repository, owner.pubkey(), None)],
+        &[&buyer],
+    )
+    .await
+    .unwrap();
+    assert_eq!(env.lamports(owner.pubkey()).await, before + 1_000_000);
+
+    let grant: AccessGrant = env.account(pda::grant(&repository, &buyer.pubkey())).await;
+    assert_eq!(grant.user, buyer.pubkey());
+    assert!(grant.expires_at >= now + 3600);
+    let first_expiry = grant.expires_at;
+
+    // Buying again while active stacks on top of the current grant
+    env.send(
+        &[purchase_ix(&buyer, repository, owner.pubkey(), None)],
+        &[&buyer],
+    )
+    .await
+    .unwrap();
+    let grant: AccessGrant = env.account(pda::grant(&repository, &buyer.pubkey())).await;
+    assert_eq!(grant.expires_at, first_expiry + 3600);
+}
+
+#[tokio::test]
+async fn purchase_access_pays_the_owner_only() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let buyer = env.user().await;
+    let thief = env.user().await;
+    let repository = env.create_repo(&owner, "repo", true).await;
+    env.send(&[pricing_ix(&owner, repository, None, 1000, 60)], &[&owner])
+        .await
+        .unwrap();
+
+    assert_error(
+        env.send(
+            &[purchase_ix(&buyer, repository, thief.pubkey(), None)],
+            &[&buyer],
+        )
+        .await,
+        AnchorError::ConstraintHasOne,
+    );
+}
+
+#[tokio::test]
+async fn purchase_access_in_tokens() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let buyer = env.user().await;
+    let repository = env.create_repo(&owner, "repo", true).await;
+
+    let mint_authority = Keypair::new();
+    let mint = env.create_mint(&mint_authority.pubkey(), 6).await;
+    let other_mint = env.create_mint(&mint_authority.pubkey(), 6).await;
+    let from = env.create_token_account(&mint, &buyer.pubkey()).await;
+    let to = env.create_token_account(&mint, &owner.pubkey()).await;
+    let wrong_to = env.create_token_account(&other_mint, &owner.pubkey()).await;
+    let not_owners = env.create_token_account(&mint, &buyer.pubkey()).await;
+    env.mint_to(&mint, &from, &mint_authority, 1000).await;
+
+    env.send(
+        &[pricing_ix(&owner, repository, Some(mint), 250, 60)],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+
+    assert_error(
+        env.send(
+            &[purchase_ix(&buyer, repository, owner.pubkey(), None)],
+            &[&buyer],
+        )
+        .await,
+        VanishError::InvalidPaymentAccount,
+    );
+    assert_error(
+        env.send(
+            &[purchase_ix(
+                &buyer,
+                repository,
+                owner.pubkey(),
+                Payment::classic(mint, from, wrong_to),
+            )],
+            &[&buyer],
+        )
+        .await,
+        VanishError::InvalidPaymentAccount,
+    );
+    assert_error(
+        env.send(
+            &[purchase_ix(
+                &buyer,
+                repository,
+                owner.pubkey(),
+                Payment::classic(mint, from, not_owners),
+            )],
+            &[&buyer],
+        )
+        .await,
+        VanishError::InvalidPaymentAccount,
+    );
+    assert_error(
+        env.send(
+            &[purchase_ix(
+                &buyer,
+                repository,
+                owner.pubkey(),
+                Payment::classic(other_mint, from, to),
+            )],
+            &[&buyer],
+        )
+        .await,
+        VanishError::InvalidPaymentAccount,
+    );
+
+    env.send(
+        &[purchase_ix(
+            &buyer,
+            repository,
+            owner.pubkey(),
+            Payment::classic(mint, from, to),
+        )],
+        &[&buyer],
+    )
+    .await
+    .unwrap();
+    assert_eq!(env.token_balance(from).await, 750);
+    assert_eq!(env.token_balance(to).await, 250);
+    assert!(env.exists(pda::grant(&repository, &buyer.pubkey())).await);
+}
+
+/// A Token-2022 mint charging `basis_points` on every transfer, along with
+/// fee-aware token accounts for `holders`
+async fn create_fee_mint(
+    env: &mut Env,
+    authority: &Keypair,
+    basis_points: u16,
+    holders: &[Pubkey],
+) -> (Pubkey, Vec<Pubkey>) {
+    let mint = Keypair::new();
+    let space = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&[
+        ExtensionType::TransferFeeConfig,
+    ])
+    .unwrap();
+    let rent = env.ctx.banks_client.get_rent().await.unwrap();
+    let instructions = [
+        system_instruction::create_account(
+            &env.payer(),
+            &mint.pubkey(),
+            rent.minimum_balance(space),
+            space as u64,
+            &spl_token_2022::ID,
+        ),
+        spl_token_2022::extension::transfer_fee::instruction::initialize_transfer_fee_config(
+            &spl_token_2022::ID,
+            &mint.pubkey(),
+            None,
+            None,
+            basis_points,
+            u64::MAX,
+        )
+        .unwrap(),
+        spl_token_2022::instruction::initialize_mint2(
+            &spl_token_2022::ID,
+            &mint.pubkey(),
+            &authority.pubkey(),
+            None,
+            0,
+        )
+        .unwrap(),
+    ];
+    env.send(&instructions, &[&mint]).await.unwrap();
+
+    let space = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Account>(&[
+        ExtensionType::TransferFeeAmount,
+    ])
+    .unwrap();
+    let mut accounts = Vec::new();
+    for holder in holders {
+        let account = Keypair::new();
+        let instructions = [
+            system_instruction::create_account(
+                &env.payer(),
+                &account.pubkey(),
+                rent.minimum_balance(space),
+                space as u64,
+                &spl_token_2022::ID,
+            ),
+            spl_token_2022::instruction::initialize_account3(
+                &spl_token_2022::ID,
+                &account.pubkey(),
+                &mint.pubkey(),
+                holder,
+            )
+            .unwrap(),
+        ];
+        env.send(&instructions, &[&account]).await.unwrap();
+        accounts.push(account.pubkey());
+    }
+    (mint.pubkey(), accounts)
+}
+
+async fn token_2022_balance(env: &mut Env, account: Pubkey) -> u64 {
+    let account = env
+        .ctx
+        .banks_client
+        .get_account(account)
+        .await
+        .unwrap()
+        .unwrap();
+    StateWithExtensions::<spl_token_2022::state::Account>::unpack(&account.data)
+        .unwrap()
+        .base
+        .amount
+}
+
+#[tokio::test]
+async fn purchase_access_covers_token_2022_transfer_fees() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let buyer = env.user().await;
+    let repository = env.create_repo(&owner, "repo", true).await;
+
+    // 1% on every transfer
+    let mint_authority = Keypair::new();
+    let (mint, accounts) = create_fee_mint(
+        &mut env,
+        &mint_authority,
+        100,
+        &[buyer.pubkey(), owner.pubkey()],
+    )
+    .await;
+    let (from, to) = (accounts[0], accounts[1]);
+    let mint_to = spl_token_2022::instruction::mint_to(
+        &spl_token_2022::ID,
+        &mint,
+        &from,
+        &mint_authority.pubkey(),
+        &[],
+        5000,
+    )
+    .unwrap();
+    env.send(&[mint_to], &[&mint_authority]).await.unwrap();
+
+    env.send(
+        &[pricing_ix(&owner, repository, Some(mint), 1000, 60)],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+
+    env.send(
+        &[purchase_ix(
+            &buyer,
+            repository,
+            owner.pubkey(),
+            Some(Payment {
+                token_program: spl_token_2022::ID,
+                mint,
+                from,
+                to,
+            }),
+        )],
+        &[&buyer],
+    )
+    .await
+    .unwrap();
+
+    // The owner gets the full price; the buyer covers the withheld fee
+    assert_eq!(token_2022_balance(&mut env, to).await, 1000);
+    assert_eq!(token_2022_balance(&mut env, from).await, 5000 - 1011);
+    assert!(env.exists(pda::grant(&repository, &buyer.pubkey())).await);
+}
+
+#[tokio::test]
+async fn remove_access_pricing_stops_sales() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let buyer = env.user().await;
+    let repository = env.create_repo(&owner, "repo", true).await;
+    env.send(&[pricing_ix(&owner, repository, None, 1000, 60)], &[&owner])
+        .await
+        .unwrap();
+    env.send(
+        &[purchase_ix(&buyer, repository, owner.pubkey(), None)],
+        &[&buyer],
+    )
+    .await
+    .unwrap();
+
+    let remove = |authority: &Keypair| {
+        ix(
+            accounts::RemoveAccessPricing {
+                authority: authority.pubkey(),
+                repository,
+                admin: None,
+                pricing: pda::pricing(&repository),
+            },
+            instruction::RemoveAccessPricing {},
+        )
+    };
+
+    assert_error(
+        env.send(&[remove(&buyer)], &[&buyer]).await,
+        VanishError::Unauthorized,
+    );
+    env.send(&[remove(&owner)], &[&owner]).await.unwrap();
+    assert!(!env.exists(pda::pricing(&repository)).await);
+
+    assert_error(
+        env.send(
+            &[purchase_ix(&buyer, repository, owner.pubkey(), None)],
+            &[&buyer],
+        )
+        .await,
+        AnchorError::AccountNotInitialized,
+    );
+
+    // Grants already bought stay
+    assert!(env.exists(pda::grant(&repository, &buyer.pubkey())).await);
+}
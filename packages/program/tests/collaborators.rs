@@ -0,0 +1,1630 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::*;
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::signature::{Keypair, Signer};
+use vanish_program::{
+    accounts, instruction, webhook_events, AccessKey, AuditAction, AuditEntry, Branch, BranchGrant,
+    Cid, Collaborator, CollaboratorSlot, HeadUpdate, KeyEnvelope, Project, ProjectAuditAction,
+    ProjectAuditEntry, ProjectMember, ReadGrant, RentVault, Repository, Webhook,
+};
+
+fn rotate_ix(authority: Pubkey, repository: Pubkey) -> Instruction {
+    ix(
+        accounts::RotateRepoKey {
+            authority,
+            repository,
+            admin: None,
+        },
+        instruction::RotateRepoKey {},
+    )
+}
+
+fn set_admin_ix(
+    authority: &Keypair,
+    admin: bool,
+    repository: Pubkey,
+    user: Pubkey,
+    is_admin: bool,
+    audit_entry: Pubkey,
+) -> Instruction {
+    ix(
+        accounts::SetCollaboratorAdmin {
+            authority: authority.pubkey(),
+            repository,
+            admin: admin.then(|| pda::collab(&repository, &authority.pubkey())),
+            collaborator_account: pda::collab(&repository, &user),
+            audit_entry,
+            system_program: system_program(),
+        },
+        instruction::SetCollaboratorAdmin { is_admin },
+    )
+}
+
+fn remove_ix(
+    authority: &Keypair,
+    admin: bool,
+    repository: Pubkey,
+    user: Pubkey,
+    is_private: bool,
+    audit_entry: Pubkey,
+    slots: RemovalSlots,
+) -> Instruction {
+    ix(
+        accounts::RemoveCollaborator {
+            authority: authority.pubkey(),
+            repository,
+            admin: admin.then(|| pda::collab(&repository, &authority.pubkey())),
+            collaborator_account: pda::collab(&repository, &user),
+            access_key: is_private.then(|| pda::access(&repository, &user)),
+            last_slot: slots.last_slot,
+            collaborator_slot: slots.collaborator_slot,
+            moved_collaborator: slots.moved_collaborator,
+            audit_entry,
+            system_program: system_program(),
+        },
+        instruction::RemoveCollaborator {},
+    )
+}
+
+/// Index slot accounts remove_collaborator needs to take `user` out
+struct RemovalSlots {
+    last_slot: Option<Pubkey>,
+    collaborator_slot: Option<Pubkey>,
+    moved_collaborator: Option<Pubkey>,
+}
+
+async fn removal_slots(env: &mut Env, repository: Pubkey, user: Pubkey) -> RemovalSlots {
+    let repo: Repository = env.account(repository).await;
+    let collab: Collaborator = env.account(pda::collab(&repository, &user)).await;
+    let Some(index) = collab.index else {
+        return RemovalSlots {
+            last_slot: None,
+            collaborator_slot: None,
+            moved_collaborator: None,
+        };
+    };
+
+    let last_slot = pda::collab_slot(&repo.id, repo.collaborator_count - 1);
+    if index + 1 == repo.collaborator_count {
+        return RemovalSlots {
+            last_slot: Some(last_slot),
+            collaborator_slot: None,
+            moved_collaborator: None,
+        };
+    }
+    let last: CollaboratorSlot = env.account(last_slot).await;
+    RemovalSlots {
+        last_slot: Some(last_slot),
+        collaborator_slot: Some(pda::collab_slot(&repo.id, index)),
+        moved_collaborator: Some(pda::collab(&repository, &last.user)),
+    }
+}
+
+async fn remove_collaborator(env: &mut Env, owner: &Keypair, repository: Pubkey, user: Pubkey) {
+    let audit_entry = env.audit_entry(repository).await;
+    let slots = removal_slots(env, repository, user).await;
+    let remove = remove_ix(owner, false, repository, user, false, audit_entry, slots);
+    env.send(&[remove], &[owner]).await.unwrap();
+}
+
+#[tokio::test]
+async fn add_collaborator_to_public_repo() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let user = Keypair::new().pubkey();
+    let repository = env.create_repo(&owner, "repo", false).await;
+
+    env.add_collaborator(&owner, repository, user, true, false)
+        .await;
+
+    let collab: Collaborator = env.account(pda::collab(&repository, &user)).await;
+    assert_eq!(collab.repository, repository);
+    assert_eq!(collab.user, user);
+    assert!(collab.can_push);
+    assert!(!collab.is_admin);
+    assert_eq!(collab.last_push_at, 0);
+
+    // Public repositories have no key to seal
+    let other = Keypair::new().pubkey();
+    let repo: Repository = env.account(repository).await;
+    let add = add_collaborator_ix(owner.pubkey(), None, repository, other, false, true, &repo);
+    assert_error(
+        env.send(&[add], &[&owner]).await,
+        VanishError::InvalidAccessKey,
+    );
+
+    let repo: Repository = env.account(repository).await;
+    let again = add_collaborator_ix(owner.pubkey(), None, repository, user, false, false, &repo);
+    assert_error(env.send(&[again], &[&owner]).await, ACCOUNT_ALREADY_IN_USE);
+}
+
+#[tokio::test]
+async fn private_collaborators_need_an_access_key() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let user = Keypair::new().pubkey();
+    let repository = env.create_repo(&owner, "repo", true).await;
+
+    let repo: Repository = env.account(repository).await;
+    let add = add_collaborator_ix(owner.pubkey(), None, repository, user, true, false, &repo);
+    assert_error(
+        env.send(&[add], &[&owner]).await,
+        VanishError::InvalidAccessKey,
+    );
+
+    env.add_collaborator(&owner, repository, user, true, true)
+        .await;
+    let access_key: AccessKey = env.account(pda::access(&repository, &user)).await;
+    assert_eq!(access_key.user, user);
+    assert_eq!(access_key.key_epoch, 0);
+    assert_eq!(access_key.envelope.ciphertext, envelope().ciphertext);
+}
+
+#[tokio::test]
+async fn add_collaborator_requires_admin() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let writer = env.user().await;
+    let admin = env.user().await;
+    let repository = env.create_repo(&owner, "repo", false).await;
+    env.add_collaborator(&owner, repository, writer.pubkey(), true, false)
+        .await;
+    env.add_admin(&owner, repository, admin.pubkey()).await;
+
+    let user = Keypair::new().pubkey();
+    let repo: Repository = env.account(repository).await;
+    let add = add_collaborator_ix(
+        writer.pubkey(),
+        Some(pda::collab(&repository, &writer.pubkey())),
+        repository,
+        user,
+        true,
+        false,
+        &repo,
+    );
+    assert_error(
+        env.send(&[add], &[&writer]).await,
+        VanishError::Unauthorized,
+    );
+
+    // Without the admin account even an admin is just a stranger
+    let repo: Repository = env.account(repository).await;
+    let add = add_collaborator_ix(admin.pubkey(), None, repository, user, true, false, &repo);
+    assert_error(env.send(&[add], &[&admin]).await, VanishError::Unauthorized);
+
+    let repo: Repository = env.account(repository).await;
+    let add = add_collaborator_ix(
+        admin.pubkey(),
+        Some(pda::collab(&repository, &admin.pubkey())),
+        repository,
+        user,
+        true,
+        false,
+        &repo,
+    );
+    env.send(&[add], &[&admin]).await.unwrap();
+    assert!(env.exists(pda::collab(&repository, &user)).await);
+}
+
+#[tokio::test]
+async fn rotate_and_reissue_keys() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let user = Keypair::new().pubkey();
+    let repository = env.create_repo(&owner, "repo", true).await;
+    let public = env.create_repo(&owner, "public", false).await;
+    env.add_collaborator(&owner, repository, user, false, true)
+        .await;
+
+    env.send(&[rotate_ix(owner.pubkey(), repository)], &[&owner])
+        .await
+        .unwrap();
+    let repo: Repository = env.account(repository).await;
+    assert_eq!(repo.key_epoch, 1);
+
+    assert_error(
+        env.send(&[rotate_ix(owner.pubkey(), public)], &[&owner])
+            .await,
+        VanishError::RepoNotPrivate,
+    );
+
+    let resealed = KeyEnvelope {
+        ciphertext: [9; 48],
+        ..envelope()
+    };
+    let reissue = ix(
+        accounts::ReissueAccessKey {
+            authority: owner.pubkey(),
+            repository,
+            admin: None,
+            access_key: pda::access(&repository, &user),
+            collaborator_account: Some(pda::collab(&repository, &user)),
+            read_grant: None,
+        },
+        instruction::ReissueAccessKey { envelope: resealed },
+    );
+    env.send(&[reissue], &[&owner]).await.unwrap();
+    let access_key: AccessKey = env.account(pda::access(&repository, &user)).await;
+    assert_eq!(access_key.key_epoch, 1);
+    assert_eq!(access_key.envelope.ciphertext, [9; 48]);
+}
+
+#[tokio::test]
+async fn reissue_requires_a_current_collaborator() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let user = Keypair::new().pubkey();
+    let removed = Keypair::new().pubkey();
+    let repository = env.create_repo(&owner, "repo", true).await;
+    env.add_collaborator(&owner, repository, user, false, true)
+        .await;
+    env.add_collaborator(&owner, repository, removed, false, true)
+        .await;
+    let audit_entry = env.audit_entry(repository).await;
+    let slots = removal_slots(&mut env, repository, removed).await;
+    env.send(
+        &[remove_ix(
+            &owner,
+            false,
+            repository,
+            removed,
+            true,
+            audit_entry,
+            slots,
+        )],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+
+    // The collaborator account of one user cannot vouch for another's key
+    let reissue = ix(
+        accounts::ReissueAccessKey {
+            authority: owner.pubkey(),
+            repository,
+            admin: None,
+            access_key: pda::access(&repository, &removed),
+            collaborator_account: Some(pda::collab(&repository, &user)),
+            read_grant: None,
+        },
+        instruction::ReissueAccessKey {
+            envelope: envelope(),
+        },
+    );
+    assert_error(
+        env.send(&[reissue], &[&owner]).await,
+        AnchorError::AccountNotInitialized,
+    );
+}
+
+#[tokio::test]
+async fn collaborators_are_enumerable_by_index() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let users: Vec<Pubkey> = (0..3).map(|_| Keypair::new().pubkey()).collect();
+    let repository = env.create_repo(&owner, "repo", false).await;
+    for user in &users {
+        env.add_collaborator(&owner, repository, *user, true, false)
+            .await;
+    }
+
+    let mut listed = Vec::new();
+    let repo: Repository = env.account(repository).await;
+    for index in 0..repo.collaborator_count {
+        let slot: CollaboratorSlot = env.account(pda::collab_slot(&repository, index)).await;
+        let collab: Collaborator = env.account(pda::collab(&repository, &slot.user)).await;
+        assert_eq!(collab.index, Some(index));
+        listed.push(slot.user);
+    }
+    assert_eq!(listed, users);
+
+    // Removing the first collaborator moves the last one into its slot
+    remove_collaborator(&mut env, &owner, repository, users[0]).await;
+    let repo: Repository = env.account(repository).await;
+    assert_eq!(repo.collaborator_count, 2);
+    let slot: CollaboratorSlot = env.account(pda::collab_slot(&repository, 0)).await;
+    assert_eq!(slot.user, users[2]);
+    let collab: Collaborator = env.account(pda::collab(&repository, &users[2])).await;
+    assert_eq!(collab.index, Some(0));
+    assert!(!env.exists(pda::collab_slot(&repository, 2)).await);
+
+    // Removing the last one just closes its slot
+    remove_collaborator(&mut env, &owner, repository, users[1]).await;
+    let repo: Repository = env.account(repository).await;
+    assert_eq!(repo.collaborator_count, 1);
+    assert!(!env.exists(pda::collab_slot(&repository, 1)).await);
+
+    // The slot accounts must describe the collaborator being removed
+    let audit_entry = env.audit_entry(repository).await;
+    let slots = RemovalSlots {
+        last_slot: None,
+        collaborator_slot: None,
+        moved_collaborator: None,
+    };
+    assert_error(
+        env.send(
+            &[remove_ix(
+                &owner,
+                false,
+                repository,
+                users[2],
+                false,
+                audit_entry,
+                slots,
+            )],
+            &[&owner],
+        )
+        .await,
+        VanishError::InvalidCollaboratorSlot,
+    );
+}
+
+#[tokio::test]
+async fn index_collaborator_slots_older_collaborators() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let cranker = env.user().await;
+    let legacy = Keypair::new().pubkey();
+    let repository = env.create_repo(&owner, "repo", false).await;
+
+    // Stand in for a collaborator added before index slots existed
+    let collab_address = pda::collab(&repository, &legacy);
+    let collab = Collaborator {
+        repository,
+        user: legacy,
+        can_push: true,
+        is_admin: false,
+        added_at: 0,
+        last_push_at: 0,
+        bump: Pubkey::find_program_address(
+            &[b"collab", repository.as_ref(), legacy.as_ref()],
+            &vanish_program::ID,
+        )
+        .1,
+        version: Collaborator::VERSION,
+        index: None,
+    };
+    env.set_state(collab_address, &collab, Collaborator::SPACE)
+        .await;
+
+    let index = ix(
+        accounts::IndexCollaborator {
+            payer: cranker.pubkey(),
+            repository,
+            collaborator_account: collab_address,
+            collaborator_slot: pda::collab_slot(&repository, 0),
+            system_program: system_program(),
+        },
+        instruction::IndexCollaborator {},
+    );
+    env.send(std::slice::from_ref(&index), &[&cranker])
+        .await
+        .unwrap();
+    let collab: Collaborator = env.account(collab_address).await;
+    assert_eq!(collab.index, Some(0));
+    let slot: CollaboratorSlot = env.account(pda::collab_slot(&repository, 0)).await;
+    assert_eq!(slot.user, legacy);
+
+    let index = ix(
+        accounts::IndexCollaborator {
+            payer: cranker.pubkey(),
+            repository,
+            collaborator_account: collab_address,
+            collaborator_slot: pda::collab_slot(&repository, 1),
+            system_program: system_program(),
+        },
+        instruction::IndexCollaborator {},
+    );
+    assert_error(
+        env.send(&[index], &[&cranker]).await,
+        VanishError::CollaboratorIndexed,
+    );
+}
+
+#[tokio::test]
+async fn read_grants_share_private_repositories() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let auditor = Keypair::new().pubkey();
+    let client = Keypair::new().pubkey();
+    let repository = env.create_repo(&owner, "repo", true).await;
+    let now = env.now().await;
+
+    let grant =
+        |reader: Pubkey, expires_at: i64, key_envelope: Option<KeyEnvelope>, audit_entry| {
+            ix(
+                accounts::GrantRead {
+                    authority: owner.pubkey(),
+                    repository,
+                    admin: None,
+                    read_grant: pda::read(&repository, &reader),
+                    access_key: key_envelope.map(|_| pda::access(&repository, &reader)),
+                    audit_entry,
+                    system_program: system_program(),
+                },
+                instruction::GrantRead {
+                    reader,
+                    expires_at,
+                    key_envelope,
+                },
+            )
+        };
+    let reissue = |reader: Pubkey| {
+        ix(
+            accounts::ReissueAccessKey {
+                authority: owner.pubkey(),
+                repository,
+                admin: None,
+                access_key: pda::access(&repository, &reader),
+                collaborator_account: None,
+                read_grant: Some(pda::read(&repository, &reader)),
+            },
+            instruction::ReissueAccessKey {
+                envelope: envelope(),
+            },
+        )
+    };
+
+    // Private repositories need the reader's key sealed
+    let audit_entry = env.audit_entry(repository).await;
+    assert_error(
+        env.send(&[grant(auditor, 0, None, audit_entry)], &[&owner])
+            .await,
+        VanishError::InvalidAccessKey,
+    );
+    assert_error(
+        env.send(
+            &[grant(auditor, now - 1, Some(envelope()), audit_entry)],
+            &[&owner],
+        )
+        .await,
+        VanishError::InvalidExpiry,
+    );
+    env.send(
+        &[grant(auditor, 0, Some(envelope()), audit_entry)],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+    let audit_entry = env.audit_entry(repository).await;
+    env.send(
+        &[grant(client, now + 60, Some(envelope()), audit_entry)],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+
+    let read_grant: ReadGrant = env.account(pda::read(&repository, &auditor)).await;
+    assert_eq!(read_grant.reader, auditor);
+    assert_eq!(read_grant.granted_by, owner.pubkey());
+    assert_eq!(read_grant.expires_at, 0);
+    assert!(read_grant.is_active(now + 1_000_000));
+    let access_key: AccessKey = env.account(pda::access(&repository, &auditor)).await;
+    assert_eq!(access_key.user, auditor);
+    assert!(!env.exists(pda::collab(&repository, &auditor)).await);
+
+    // Readers get re-issued keys until their grant runs out
+    env.send(&[rotate_ix(owner.pubkey(), repository)], &[&owner])
+        .await
+        .unwrap();
+    env.send(&[reissue(auditor)], &[&owner]).await.unwrap();
+    env.send(&[reissue(client)], &[&owner]).await.unwrap();
+    env.warp(60).await;
+    assert_error(
+        env.send(&[reissue(client)], &[&owner]).await,
+        VanishError::NoReadAccess,
+    );
+
+    let audit_entry = env.audit_entry(repository).await;
+    let revoke = ix(
+        accounts::RevokeRead {
+            authority: owner.pubkey(),
+            repository,
+            admin: None,
+            read_grant: pda::read(&repository, &auditor),
+            access_key: Some(pda::access(&repository, &auditor)),
+            audit_entry,
+            system_program: system_program(),
+        },
+        instruction::RevokeRead {},
+    );
+    env.send(&[revoke], &[&owner]).await.unwrap();
+    assert!(!env.exists(pda::read(&repository, &auditor)).await);
+    assert!(!env.exists(pda::access(&repository, &auditor)).await);
+    let entry: AuditEntry = env.account(audit_entry).await;
+    assert!(entry.action == AuditAction::ReadRevoked);
+    assert_eq!(entry.subject, auditor);
+}
+
+#[tokio::test]
+async fn admin_role_is_counted() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let admin = env.user().await;
+    let writer = env.user().await;
+    let repository = env.create_repo(&owner, "repo", false).await;
+    env.add_admin(&owner, repository, admin.pubkey()).await;
+    env.add_collaborator(&owner, repository, writer.pubkey(), true, false)
+        .await;
+
+    let repo: Repository = env.account(repository).await;
+    assert_eq!(repo.admin_count, 1);
+
+    // Setting the role it already has is a no-op
+    let audit_entry = env.audit_entry(repository).await;
+    env.send(
+        &[set_admin_ix(
+            &owner,
+            false,
+            repository,
+            admin.pubkey(),
+            true,
+            audit_entry,
+        )],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+    let repo: Repository = env.account(repository).await;
+    assert_eq!(repo.admin_count, 1);
+
+    // Admins can promote others, writers cannot
+    let audit_entry = env.audit_entry(repository).await;
+    assert_error(
+        env.send(
+            &[set_admin_ix(
+                &writer,
+                true,
+                repository,
+                writer.pubkey(),
+                true,
+                audit_entry,
+            )],
+            &[&writer],
+        )
+        .await,
+        VanishError::Unauthorized,
+    );
+    let audit_entry = env.audit_entry(repository).await;
+    env.send(
+        &[set_admin_ix(
+            &admin,
+            true,
+            repository,
+            writer.pubkey(),
+            true,
+            audit_entry,
+        )],
+        &[&admin],
+    )
+    .await
+    .unwrap();
+    let repo: Repository = env.account(repository).await;
+    assert_eq!(repo.admin_count, 2);
+
+    let audit_entry = env.audit_entry(repository).await;
+    env.send(
+        &[set_admin_ix(
+            &owner,
+            false,
+            repository,
+            writer.pubkey(),
+            false,
+            audit_entry,
+        )],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+    let repo: Repository = env.account(repository).await;
+    assert_eq!(repo.admin_count, 1);
+    let collab: Collaborator = env
+        .account(pda::collab(&repository, &writer.pubkey()))
+        .await;
+    assert!(!collab.is_admin);
+}
+
+#[tokio::test]
+async fn remove_collaborator_closes_accounts() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let admin = env.user().await;
+    let user = Keypair::new().pubkey();
+    let repository = env.create_repo(&owner, "repo", true).await;
+    env.add_collaborator(&owner, repository, user, true, true)
+        .await;
+
+    // A private repository's access key must go with the collaborator
+    let audit_entry = env.audit_entry(repository).await;
+    let slots = removal_slots(&mut env, repository, user).await;
+    assert_error(
+        env.send(
+            &[remove_ix(
+                &owner,
+                false,
+                repository,
+                user,
+                false,
+                audit_entry,
+                slots,
+            )],
+            &[&owner],
+        )
+        .await,
+        VanishError::InvalidAccessKey,
+    );
+
+    let audit_entry = env.audit_entry(repository).await;
+    let slots = removal_slots(&mut env, repository, user).await;
+    env.send(
+        &[remove_ix(
+            &owner,
+            false,
+            repository,
+            user,
+            true,
+            audit_entry,
+            slots,
+        )],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+    assert!(!env.exists(pda::collab(&repository, &user)).await);
+    assert!(!env.exists(pda::access(&repository, &user)).await);
+
+    // Removing an admin drops the admin count
+    let public = env.create_repo(&owner, "public", false).await;
+    env.add_admin(&owner, public, admin.pubkey()).await;
+    let audit_entry = env.audit_entry(public).await;
+    let slots = removal_slots(&mut env, public, admin.pubkey()).await;
+    env.send(
+        &[remove_ix(
+            &owner,
+            false,
+            public,
+            admin.pubkey(),
+            false,
+            audit_entry,
+            slots,
+        )],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+    let repo: Repository = env.account(public).await;
+    assert_eq!(repo.admin_count, 0);
+}
+
+#[tokio::test]
+async fn last_admin_of_ownerless_repo_stays() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let admin = env.user().await;
+    let repository = env.create_repo(&owner, "repo", false).await;
+    env.add_admin(&owner, repository, admin.pubkey()).await;
+
+    let renounce = ix(
+        accounts::RenounceOwnership {
+            owner: owner.pubkey(),
+            repository,
+            audit_entry: env.audit_entry(repository).await,
+            system_program: system_program(),
+        },
+        instruction::RenounceOwnership {},
+    );
+    env.send(&[renounce], &[&owner]).await.unwrap();
+
+    let audit_entry = env.audit_entry(repository).await;
+    let slots = removal_slots(&mut env, repository, admin.pubkey()).await;
+    assert_error(
+        env.send(
+            &[remove_ix(
+                &admin,
+                true,
+                repository,
+                admin.pubkey(),
+                false,
+                audit_entry,
+                slots,
+            )],
+            &[&admin],
+        )
+        .await,
+        VanishError::LastAdmin,
+    );
+
+    // The former owner lost every right with the renounce
+    let audit_entry = env.audit_entry(repository).await;
+    let slots = removal_slots(&mut env, repository, admin.pubkey()).await;
+    assert_error(
+        env.send(
+            &[remove_ix(
+                &owner,
+                false,
+                repository,
+                admin.pubkey(),
+                false,
+                audit_entry,
+                slots,
+            )],
+            &[&owner],
+        )
+        .await,
+        VanishError::Unauthorized,
+    );
+}
+
+fn add_to_project_ix(
+    admin: &Keypair,
+    owner: &Keypair,
+    project: Pubkey,
+    repository: Pubkey,
+    audit_entry: Pubkey,
+) -> Instruction {
+    ix(
+        accounts::AddRepoToProject {
+            admin: admin.pubkey(),
+            project,
+            owner: owner.pubkey(),
+            repository,
+            project_repo: pda::project_repo(&repository),
+            audit_entry,
+            system_program: system_program(),
+        },
+        instruction::AddRepoToProject {},
+    )
+}
+
+fn remove_from_project_ix(
+    authority: &Keypair,
+    admin: Pubkey,
+    project: Pubkey,
+    repository: Pubkey,
+    audit_entry: Pubkey,
+) -> Instruction {
+    ix(
+        accounts::RemoveRepoFromProject {
+            authority: authority.pubkey(),
+            project,
+            admin,
+            repository: Some(repository),
+            project_repo: pda::project_repo(&repository),
+            audit_entry,
+            system_program: system_program(),
+        },
+        instruction::RemoveRepoFromProject {},
+    )
+}
+
+/// A project `admin` runs, with `member` added
+async fn create_project(env: &mut Env, admin: &Keypair, member: Pubkey, can_push: bool) -> Pubkey {
+    let project = pda::project(&admin.pubkey(), "org");
+    let create = ix(
+        accounts::CreateProject {
+            admin: admin.pubkey(),
+            project,
+            system_program: system_program(),
+        },
+        instruction::CreateProject {
+            name: "org".to_string(),
+        },
+    );
+    let add = ix(
+        accounts::AddProjectMember {
+            admin: admin.pubkey(),
+            project,
+            project_member: pda::project_member(&project, &member),
+            audit_entry: pda::project_audit(&project, 0),
+            system_program: system_program(),
+        },
+        instruction::AddProjectMember {
+            user: member,
+            can_push,
+        },
+    );
+    env.send(&[create, add], &[admin]).await.unwrap();
+    project
+}
+
+/// push_update passing the project accounts, as a project member
+fn project_push_ix(pusher: Pubkey, repository: Pubkey, project: Pubkey, head: u8) -> Instruction {
+    let mut push = push_ix(pusher, repository, None, sha1(head));
+    push.accounts[6] = AccountMeta::new(project, false);
+    push.accounts[7] = AccountMeta::new_readonly(pda::project_member(&project, &pusher), false);
+    push
+}
+
+/// push_update passing the project accounts, as the repository owner
+fn owner_project_push_ix(
+    owner: Pubkey,
+    repository: Pubkey,
+    project: Pubkey,
+    expected_head: u8,
+    head: u8,
+) -> Instruction {
+    let mut push = push_onto_ix(
+        owner,
+        repository,
+        None,
+        Some(sha1(expected_head)),
+        sha1(head),
+    );
+    push.accounts[6] = AccountMeta::new(project, false);
+    push
+}
+
+#[tokio::test]
+async fn project_members_push_to_project_repositories() {
+    let mut env = Env::new().await;
+    let admin = env.user().await;
+    let owner = env.user().await;
+    let member = env.user().await;
+    let viewer = env.user().await;
+    let repository = env.create_repo(&owner, "in-project", false).await;
+    let outside = env.create_repo(&owner, "outside", false).await;
+    let project = create_project(&mut env, &admin, member.pubkey(), true).await;
+
+    // Both sides must agree to the link
+    let audit_entry = env.project_audit_entry(project).await;
+    let mut add = add_to_project_ix(&admin, &owner, project, repository, audit_entry);
+    add.accounts[2].is_signer = false;
+    assert!(env.send(&[add], &[&admin]).await.is_err());
+    env.send(
+        &[add_to_project_ix(
+            &admin,
+            &owner,
+            project,
+            repository,
+            audit_entry,
+        )],
+        &[&admin, &owner],
+    )
+    .await
+    .unwrap();
+
+    env.send(
+        &[project_push_ix(member.pubkey(), repository, project, 1)],
+        &[&member],
+    )
+    .await
+    .unwrap();
+    let repo: Repository = env.account(repository).await;
+    assert!(repo.head_commit == sha1(1));
+
+    // Pushes to project repositories must count towards the project
+    assert_error(
+        env.send(
+            &[push_ix(owner.pubkey(), repository, None, sha1(2))],
+            &[&owner],
+        )
+        .await,
+        VanishError::InvalidProjectAccounts,
+    );
+    env.send(
+        &[owner_project_push_ix(
+            owner.pubkey(),
+            repository,
+            project,
+            1,
+            2,
+        )],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+    let state: Project = env.account(project).await;
+    assert_eq!(state.repo_count, 1);
+    assert_eq!(state.member_count, 1);
+    assert_eq!(state.push_count, 2);
+
+    // Membership reaches project repositories only, and needs push access
+    assert_error(
+        env.send(
+            &[project_push_ix(member.pubkey(), outside, project, 3)],
+            &[&member],
+        )
+        .await,
+        VanishError::Unauthorized,
+    );
+    let add_viewer = ix(
+        accounts::AddProjectMember {
+            admin: admin.pubkey(),
+            project,
+            project_member: pda::project_member(&project, &viewer.pubkey()),
+            audit_entry: env.project_audit_entry(project).await,
+            system_program: system_program(),
+        },
+        instruction::AddProjectMember {
+            user: viewer.pubkey(),
+            can_push: false,
+        },
+    );
+    env.send(&[add_viewer], &[&admin]).await.unwrap();
+    assert_error(
+        env.send(
+            &[project_push_ix(viewer.pubkey(), repository, project, 3)],
+            &[&viewer],
+        )
+        .await,
+        VanishError::Unauthorized,
+    );
+}
+
+#[tokio::test]
+async fn project_links_are_removed_by_either_side() {
+    let mut env = Env::new().await;
+    let admin = env.user().await;
+    let owner = env.user().await;
+    let stranger = env.user().await;
+    let repository = env.create_repo(&owner, "repo", false).await;
+    let project = create_project(&mut env, &admin, stranger.pubkey(), true).await;
+    let audit_entry = env.project_audit_entry(project).await;
+    env.send(
+        &[add_to_project_ix(
+            &admin,
+            &owner,
+            project,
+            repository,
+            audit_entry,
+        )],
+        &[&admin, &owner],
+    )
+    .await
+    .unwrap();
+
+    let audit_entry = env.project_audit_entry(project).await;
+    assert_error(
+        env.send(
+            &[remove_from_project_ix(
+                &stranger,
+                admin.pubkey(),
+                project,
+                repository,
+                audit_entry,
+            )],
+            &[&stranger],
+        )
+        .await,
+        VanishError::Unauthorized,
+    );
+
+    // The repository owner can leave; the rent goes back to the admin who paid it
+    let before = env.lamports(admin.pubkey()).await;
+    env.send(
+        &[remove_from_project_ix(
+            &owner,
+            admin.pubkey(),
+            project,
+            repository,
+            audit_entry,
+        )],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+    assert!(!env.exists(pda::project_repo(&repository)).await);
+    assert_eq!(
+        env.lamports(admin.pubkey()).await,
+        before + env.rent(vanish_program::ProjectRepo::SPACE).await
+    );
+    let state: Project = env.account(project).await;
+    assert_eq!(state.repo_count, 0);
+
+    // Out of the project, plain pushes work again
+    env.send(
+        &[push_ix(owner.pubkey(), repository, None, sha1(1))],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+
+    let remove_member = ix(
+        accounts::RemoveProjectMember {
+            admin: admin.pubkey(),
+            project,
+            project_member: pda::project_member(&project, &stranger.pubkey()),
+            audit_entry: env.project_audit_entry(project).await,
+            system_program: system_program(),
+        },
+        instruction::RemoveProjectMember {},
+    );
+    env.send(&[remove_member], &[&admin]).await.unwrap();
+    assert!(
+        !env.exists(pda::project_member(&project, &stranger.pubkey()))
+            .await
+    );
+    let state: Project = env.account(project).await;
+    assert_eq!(state.member_count, 0);
+}
+
+#[tokio::test]
+async fn project_membership_changes_are_audited() {
+    let mut env = Env::new().await;
+    let admin = env.user().await;
+    let owner = env.user().await;
+    let member = env.user().await;
+    let repository = env.create_repo(&owner, "repo", false).await;
+    let project = create_project(&mut env, &admin, member.pubkey(), false).await;
+    let audit_entry = env.project_audit_entry(project).await;
+    env.send(
+        &[add_to_project_ix(
+            &admin,
+            &owner,
+            project,
+            repository,
+            audit_entry,
+        )],
+        &[&admin, &owner],
+    )
+    .await
+    .unwrap();
+
+    let set_role = |authority: &Keypair, audit_entry: Pubkey, can_push: bool| {
+        ix(
+            accounts::SetProjectMemberRole {
+                admin: authority.pubkey(),
+                project,
+                project_member: pda::project_member(&project, &member.pubkey()),
+                audit_entry,
+                system_program: system_program(),
+            },
+            instruction::SetProjectMemberRole { can_push },
+        )
+    };
+    let audit_entry = env.project_audit_entry(project).await;
+    assert_error(
+        env.send(&[set_role(&member, audit_entry, true)], &[&member])
+            .await,
+        AnchorError::ConstraintHasOne,
+    );
+    assert_error(
+        env.send(
+            &[project_push_ix(member.pubkey(), repository, project, 1)],
+            &[&member],
+        )
+        .await,
+        VanishError::Unauthorized,
+    );
+    env.send(&[set_role(&admin, audit_entry, true)], &[&admin])
+        .await
+        .unwrap();
+    let state: ProjectMember = env
+        .account(pda::project_member(&project, &member.pubkey()))
+        .await;
+    assert!(state.can_push);
+    env.send(
+        &[project_push_ix(member.pubkey(), repository, project, 1)],
+        &[&member],
+    )
+    .await
+    .unwrap();
+
+    // Every change is in the log, in order
+    let state: Project = env.account(project).await;
+    assert_eq!(state.audit_count, 3);
+    let expected = [
+        (ProjectAuditAction::MemberAdded, member.pubkey()),
+        (ProjectAuditAction::RepoAdded, repository),
+        (ProjectAuditAction::RoleChanged, member.pubkey()),
+    ];
+    for (seq, (action, subject)) in expected.into_iter().enumerate() {
+        let entry: ProjectAuditEntry = env.account(pda::project_audit(&project, seq as u64)).await;
+        assert_eq!(entry.project, project);
+        assert_eq!(entry.seq, seq as u64);
+        assert_eq!(entry.actor, admin.pubkey());
+        assert!(entry.action == action);
+        assert_eq!(entry.subject, subject);
+    }
+}
+
+#[tokio::test]
+async fn project_rent_vaults_cover_collaborator_and_branch_rent() {
+    let mut env = Env::new().await;
+    let admin = env.user().await;
+    let owner = env.user().await;
+    let member = env.user().await;
+    let project = create_project(&mut env, &admin, member.pubkey(), true).await;
+    let vault = pda::rent_vault(&project);
+    let mut repositories = Vec::new();
+    for name in ["one", "two"] {
+        let repository = env.create_repo(&owner, name, false).await;
+        let audit_entry = env.project_audit_entry(project).await;
+        env.send(
+            &[add_to_project_ix(
+                &admin,
+                &owner,
+                project,
+                repository,
+                audit_entry,
+            )],
+            &[&admin, &owner],
+        )
+        .await
+        .unwrap();
+        repositories.push(repository);
+    }
+    let outside = env.create_repo(&owner, "outside", false).await;
+
+    let set_vault = |collaborator_cap: u64, branch_cap: u64| {
+        ix(
+            accounts::SetRentVault {
+                admin: admin.pubkey(),
+                project,
+                rent_vault: vault,
+                system_program: system_program(),
+            },
+            instruction::SetRentVault {
+                collaborator_cap,
+                branch_cap,
+            },
+        )
+    };
+    let deposit = ix(
+        accounts::DepositRentVault {
+            depositor: owner.pubkey(),
+            rent_vault: vault,
+            system_program: system_program(),
+        },
+        instruction::DepositRentVault {
+            amount: 1_000_000_000,
+        },
+    );
+    env.send(&[set_vault(1_000_000_000, 0), deposit], &[&admin, &owner])
+        .await
+        .unwrap();
+
+    let add = |repository: Pubkey, repo: &Repository, in_project: bool| {
+        ix(
+            accounts::AddCollaborator {
+                authority: owner.pubkey(),
+                repository,
+                admin: None,
+                collaborator_account: pda::collab(&repository, &member.pubkey()),
+                collaborator_slot: pda::collab_slot(&repo.id, repo.collaborator_count),
+                access_key: None,
+                audit_entry: pda::audit(&repo.id, repo.audit_count),
+                project_repo: in_project.then(|| pda::project_repo(&repository)),
+                rent_vault: Some(vault),
+                system_program: system_program(),
+            },
+            instruction::AddCollaborator {
+                collaborator: member.pubkey(),
+                can_push: true,
+                key_envelope: None,
+            },
+        )
+    };
+    let push_branch = |repository: Pubkey| {
+        ix(
+            accounts::PushBranch {
+                pusher: owner.pubkey(),
+                repository,
+                collaborator_account: None,
+                branch_grant: None,
+                contribution: pda::contribution(&repository, &owner.pubkey()),
+                branch: pda::branch(&repository, "dev"),
+                project_repo: Some(pda::project_repo(&repository)),
+                rent_vault: Some(vault),
+                system_program: system_program(),
+            },
+            instruction::PushBranch {
+                branch_name: "dev".to_string(),
+                head_commit: sha1(1),
+                ipfs_cid: "QmDev".to_string(),
+                expected_head: None,
+                proof: lone_ref_proof(),
+            },
+        )
+    };
+
+    // Repositories outside the project cannot draw on its vault
+    let repo: Repository = env.account(outside).await;
+    assert_error(
+        env.send(&[add(outside, &repo, false)], &[&owner]).await,
+        VanishError::InvalidRentVault,
+    );
+
+    let repo: Repository = env.account(repositories[0]).await;
+    let before = env.lamports(vault).await;
+    env.send(&[add(repositories[0], &repo, true)], &[&owner])
+        .await
+        .unwrap();
+    let cost = env.rent(Collaborator::SPACE).await
+        + env.rent(CollaboratorSlot::SPACE).await
+        + env.rent(AuditEntry::SPACE).await;
+    assert_eq!(env.lamports(vault).await, before - cost);
+    let state: RentVault = env.account(vault).await;
+    assert_eq!(state.spent, cost);
+
+    // Branches are left to the pusher until the vault covers them
+    env.send(&[push_branch(repositories[0])], &[&owner])
+        .await
+        .unwrap();
+    let state: RentVault = env.account(vault).await;
+    assert_eq!(state.spent, cost);
+    env.send(&[set_vault(1_000_000_000, 1_000_000_000)], &[&admin])
+        .await
+        .unwrap();
+    env.send(&[push_branch(repositories[1])], &[&owner])
+        .await
+        .unwrap();
+    let state: RentVault = env.account(vault).await;
+    assert_eq!(state.spent, cost + env.rent(Branch::SPACE).await);
+
+    // The admin can take out what the vault holds beyond its rent
+    let withdraw = |admin: &Keypair, amount: u64| {
+        ix(
+            accounts::WithdrawRentVault {
+                admin: admin.pubkey(),
+                project,
+                rent_vault: vault,
+                recipient: admin.pubkey(),
+            },
+            instruction::WithdrawRentVault { amount },
+        )
+    };
+    let available = env.lamports(vault).await - env.rent(RentVault::SPACE).await;
+    assert!(env
+        .send(&[withdraw(&owner, available)], &[&owner])
+        .await
+        .is_err());
+    assert_error(
+        env.send(&[withdraw(&admin, available + 1)], &[&admin])
+            .await,
+        VanishError::InsufficientRentVault,
+    );
+    env.send(&[withdraw(&admin, available)], &[&admin])
+        .await
+        .unwrap();
+    assert_eq!(env.lamports(vault).await, env.rent(RentVault::SPACE).await);
+}
+
+#[tokio::test]
+async fn branch_grants_limit_pushes_to_matching_branches() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let writer = env.user().await;
+    let stranger = env.user().await;
+    let repository = env.create_repo(&owner, "repo", false).await;
+    env.add_collaborator(&owner, repository, writer.pubkey(), false, false)
+        .await;
+    let grant = pda::branch_grant(&repository, &writer.pubkey());
+
+    let set_grant = |authority: &Keypair, patterns: &[&str]| {
+        ix(
+            accounts::SetBranchGrant {
+                authority: authority.pubkey(),
+                repository,
+                admin: None,
+                collaborator_account: pda::collab(&repository, &writer.pubkey()),
+                branch_grant: grant,
+                system_program: system_program(),
+            },
+            instruction::SetBranchGrant {
+                patterns: patterns.iter().map(|pattern| pattern.to_string()).collect(),
+            },
+        )
+    };
+    let push_branch = |name: &str, granted: bool| {
+        ix(
+            accounts::PushBranch {
+                pusher: writer.pubkey(),
+                repository,
+                collaborator_account: Some(pda::collab(&repository, &writer.pubkey())),
+                branch_grant: granted.then_some(grant),
+                contribution: pda::contribution(&repository, &writer.pubkey()),
+                branch: pda::branch(&repository, name),
+                project_repo: None,
+                rent_vault: None,
+                system_program: system_program(),
+            },
+            instruction::PushBranch {
+                branch_name: name.to_string(),
+                head_commit: sha1(1),
+                ipfs_cid: "QmRefs".to_string(),
+                expected_head: None,
+                proof: lone_ref_proof(),
+            },
+        )
+    };
+
+    // Without a grant, a collaborator who cannot push cannot push branches
+    assert_error(
+        env.send(&[push_branch("docs/intro", false)], &[&writer])
+            .await,
+        VanishError::Unauthorized,
+    );
+
+    assert_error(
+        env.send(&[set_grant(&stranger, &["docs/*"])], &[&stranger])
+            .await,
+        VanishError::Unauthorized,
+    );
+    for patterns in [
+        &[][..],
+        &[""],
+        &["docs/*/intro"],
+        &["a", "b", "c", "d", "e"],
+    ] {
+        assert_error(
+            env.send(&[set_grant(&owner, patterns)], &[&owner]).await,
+            VanishError::InvalidBranchPattern,
+        );
+    }
+    env.send(&[set_grant(&owner, &["docs/*", "gh-pages"])], &[&owner])
+        .await
+        .unwrap();
+    let branch_grant: BranchGrant = env.account(grant).await;
+    assert_eq!(branch_grant.user, writer.pubkey());
+    assert_eq!(branch_grant.patterns, ["docs/*", "gh-pages"]);
+
+    assert_error(
+        env.send(&[push_branch("main", true)], &[&writer]).await,
+        VanishError::BranchNotGranted,
+    );
+    env.send(&[push_branch("docs/intro", true)], &[&writer])
+        .await
+        .unwrap();
+
+    // A grant covers branches only, never the repository head
+    let push = push_ix(
+        writer.pubkey(),
+        repository,
+        Some(pda::collab(&repository, &writer.pubkey())),
+        sha1(2),
+    );
+    let push_refs = ix(
+        accounts::PushRefs {
+            pusher: writer.pubkey(),
+            repository,
+            collaborator_account: Some(pda::collab(&repository, &writer.pubkey())),
+            branch_grant: Some(grant),
+            contribution: pda::contribution(&repository, &writer.pubkey()),
+            system_program: system_program(),
+        },
+        instruction::PushRefs {
+            head: Some(HeadUpdate {
+                head_commit: sha1(2),
+                ipfs_cid: Cid::parse(PUSH_CID).unwrap(),
+                expected_head: None,
+            }),
+            branches: vec![],
+        },
+    );
+    assert_error(
+        env.send(&[push_refs], &[&writer]).await,
+        VanishError::Unauthorized,
+    );
+    assert_error(
+        env.send(&[push], &[&writer]).await,
+        VanishError::Unauthorized,
+    );
+
+    env.send(
+        &[ix(
+            accounts::RemoveBranchGrant {
+                authority: owner.pubkey(),
+                repository,
+                admin: None,
+                branch_grant: grant,
+            },
+            instruction::RemoveBranchGrant {},
+        )],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+    assert!(!env.exists(grant).await);
+}
+
+#[tokio::test]
+async fn views_answer_push_and_read_questions() {
+    let mut env = Env::new().await;
+    let admin = env.user().await;
+    let owner = env.user().await;
+    let writer = env.user().await;
+    let member = env.user().await;
+    let stranger = Keypair::new().pubkey();
+    let repository = env.create_repo(&owner, "repo", false).await;
+    env.add_collaborator(&owner, repository, writer.pubkey(), false, false)
+        .await;
+
+    let can_push = |user: Pubkey, branch: Option<&str>, project: Option<Pubkey>| {
+        ix(
+            accounts::CanPush {
+                repository,
+                collaborator_account: pda::collab(&repository, &user),
+                branch_grant: pda::branch_grant(&repository, &user),
+                project_repo: pda::project_repo(&repository),
+                project_member: project.map(|project| pda::project_member(&project, &user)),
+            },
+            instruction::CanPush {
+                user,
+                branch: branch.map(str::to_string),
+            },
+        )
+    };
+    assert!(env.view::<bool>(can_push(owner.pubkey(), None, None)).await);
+    assert!(!env.view::<bool>(can_push(stranger, None, None)).await);
+    assert!(
+        !env.view::<bool>(can_push(writer.pubkey(), None, None))
+            .await
+    );
+
+    // Branch grants cover their branches only
+    let set_grant = ix(
+        accounts::SetBranchGrant {
+            authority: owner.pubkey(),
+            repository,
+            admin: None,
+            collaborator_account: pda::collab(&repository, &writer.pubkey()),
+            branch_grant: pda::branch_grant(&repository, &writer.pubkey()),
+            system_program: system_program(),
+        },
+        instruction::SetBranchGrant {
+            patterns: vec!["docs/*".to_string()],
+        },
+    );
+    env.send(&[set_grant], &[&owner]).await.unwrap();
+    assert!(
+        env.view::<bool>(can_push(writer.pubkey(), Some("docs/intro"), None))
+            .await
+    );
+    assert!(
+        !env.view::<bool>(can_push(writer.pubkey(), Some("main"), None))
+            .await
+    );
+
+    // Project members push to the default branch of linked repositories
+    let project = create_project(&mut env, &admin, member.pubkey(), true).await;
+    assert!(
+        !env.view::<bool>(can_push(member.pubkey(), None, Some(project)))
+            .await
+    );
+    let audit_entry = env.project_audit_entry(project).await;
+    env.send(
+        &[add_to_project_ix(
+            &admin,
+            &owner,
+            project,
+            repository,
+            audit_entry,
+        )],
+        &[&admin, &owner],
+    )
+    .await
+    .unwrap();
+    assert!(
+        env.view::<bool>(can_push(member.pubkey(), None, Some(project)))
+            .await
+    );
+    assert!(
+        !env.view::<bool>(can_push(member.pubkey(), Some("main"), Some(project)))
+            .await
+    );
+
+    // Reads of private repositories need access, and grants lapse
+    let private = env.create_repo(&owner, "private", true).await;
+    let can_read = |user: Pubkey| {
+        ix(
+            accounts::CanRead {
+                repository: private,
+                collaborator_account: pda::collab(&private, &user),
+                read_grant: pda::read(&private, &user),
+                access_grant: pda::grant(&private, &user),
+            },
+            instruction::CanRead { user },
+        )
+    };
+    assert!(env.view::<bool>(can_read(owner.pubkey())).await);
+    assert!(!env.view::<bool>(can_read(stranger)).await);
+    let now = env.now().await;
+    let audit_entry = env.audit_entry(private).await;
+    let grant = ix(
+        accounts::GrantRead {
+            authority: owner.pubkey(),
+            repository: private,
+            admin: None,
+            read_grant: pda::read(&private, &stranger),
+            access_key: Some(pda::access(&private, &stranger)),
+            audit_entry,
+            system_program: system_program(),
+        },
+        instruction::GrantRead {
+            reader: stranger,
+            expires_at: now + 60,
+            key_envelope: Some(envelope()),
+        },
+    );
+    env.send(&[grant], &[&owner]).await.unwrap();
+    assert!(env.view::<bool>(can_read(stranger)).await);
+    env.warp(60).await;
+    assert!(!env.view::<bool>(can_read(stranger)).await);
+}
+
+#[tokio::test]
+async fn webhooks_are_managed_by_admins() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let stranger = env.user().await;
+    let repository = env.create_repo(&owner, "repo", false).await;
+    let url_hash = [7; 32];
+    let webhook = pda::webhook(&repository, &url_hash);
+    // Bound to the repository, so another repository's webhook holding the
+    // same secret commits to something else
+    let secret_commitment = Webhook::commitment(&repository, b"secret");
+    assert_ne!(
+        secret_commitment,
+        Webhook::commitment(&Pubkey::new_unique(), b"secret")
+    );
+
+    let set_webhook = |authority: &Keypair, events: u32| {
+        ix(
+            accounts::SetWebhook {
+                authority: authority.pubkey(),
+                repository,
+                admin: None,
+                webhook,
+                system_program: system_program(),
+            },
+            instruction::SetWebhook {
+                url_hash,
+                secret_commitment,
+                events,
+            },
+        )
+    };
+
+    assert_error(
+        env.send(
+            &[set_webhook(&stranger, webhook_events::PUSH)],
+            &[&stranger],
+        )
+        .await,
+        VanishError::Unauthorized,
+    );
+    for events in [0, webhook_events::ALL + 1] {
+        assert_error(
+            env.send(&[set_webhook(&owner, events)], &[&owner]).await,
+            VanishError::InvalidWebhookEvents,
+        );
+    }
+
+    env.send(&[set_webhook(&owner, webhook_events::PUSH)], &[&owner])
+        .await
+        .unwrap();
+    env.send(
+        &[set_webhook(
+            &owner,
+            webhook_events::PUSH | webhook_events::STAR,
+        )],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+    let state: Webhook = env.account(webhook).await;
+    assert_eq!(state.url_hash, url_hash);
+    assert_eq!(state.secret_commitment, secret_commitment);
+    assert_eq!(state.events, webhook_events::PUSH | webhook_events::STAR);
+
+    let remove = |authority: &Keypair| {
+        ix(
+            accounts::RemoveWebhook {
+                authority: authority.pubkey(),
+                repository,
+                admin: None,
+                webhook,
+            },
+            instruction::RemoveWebhook {},
+        )
+    };
+    assert_error(
+        env.send(&[remove(&stranger)], &[&stranger]).await,
+        VanishError::Unauthorized,
+    );
+    env.send(&[remove(&owner)], &[&owner]).await.unwrap();
+    assert!(!env.exists(webhook).await);
+}
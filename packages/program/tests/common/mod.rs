@@ -0,0 +1,870 @@
+//! Shared harness for the integration tests: runs the program natively under
+//! solana-program-test and wraps the transaction plumbing every test needs.
+
+#![allow(dead_code)]
+
+use anchor_lang::prelude::{AccountInfo, ProgramError, Pubkey};
+use anchor_lang::solana_program::entrypoint::ProgramResult;
+use anchor_lang::solana_program::hash::hash;
+use anchor_lang::{
+    AccountDeserialize, AccountSerialize, AnchorDeserialize, InstructionData, ToAccountMetas,
+    ZeroCopy,
+};
+use anchor_spl::token::spl_token;
+use solana_program_test::{processor, BanksClientError, ProgramTest, ProgramTestContext};
+use solana_sdk::account::Account;
+use solana_sdk::clock::Clock;
+use solana_sdk::compute_budget::ComputeBudgetInstruction;
+use solana_sdk::instruction::{Instruction, InstructionError};
+use solana_sdk::native_token::LAMPORTS_PER_SOL;
+use solana_sdk::program_pack::Pack;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::system_instruction;
+use solana_sdk::transaction::{Transaction, TransactionError};
+use vanish_program::{
+    accounts, compression, instruction, refs_tree, Cid, KeyEnvelope, Multihash, Project,
+    Repository, SemVer,
+};
+use vanish_test_utils::process;
+
+pub use anchor_lang::error::ErrorCode as AnchorError;
+pub use vanish_program::VanishError;
+
+/// Stands in for spl-account-compression: checks that the tree authority
+/// signed and writes the instruction data into the tree account, so tests can
+/// inspect what the program sent.
+fn mock_compression(_program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let (tree, authority) = (&accounts[0], &accounts[1]);
+    if !authority.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let mut tree = tree.try_borrow_mut_data()?;
+    tree.fill(0);
+    tree[..data.len()].copy_from_slice(data);
+    Ok(())
+}
+
+/// Error code of the system program's "account already in use"
+pub const ACCOUNT_ALREADY_IN_USE: u32 = 0;
+
+pub struct Env {
+    pub ctx: ProgramTestContext,
+    /// Bumped per transaction so identical instructions still get distinct
+    /// signatures
+    nonce: u32,
+}
+
+impl Env {
+    pub async fn new() -> Self {
+        let mut program =
+            ProgramTest::new("vanish_program", vanish_program::ID, processor!(process));
+        program.prefer_bpf(false);
+        program.add_program(
+            "spl_account_compression",
+            compression::ID,
+            processor!(mock_compression),
+        );
+        let mut env = Self {
+            ctx: program.start_with_context().await,
+            nonce: 0,
+        };
+
+        // Every deployment starts by creating its Config, here administered
+        // by the test payer
+        let init = ix(
+            accounts::InitializeConfig {
+                admin: env.payer(),
+                config: pda::config(),
+                treasury: pda::treasury(),
+                system_program: system_program(),
+            },
+            instruction::InitializeConfig {
+                repo_fee: 0,
+                flags: 0,
+            },
+        );
+        env.send(&[init], &[]).await.unwrap();
+        env
+    }
+
+    pub fn payer(&self) -> Pubkey {
+        self.ctx.payer.pubkey()
+    }
+
+    /// A fresh keypair funded with 10 SOL
+    pub async fn user(&mut self) -> Keypair {
+        let user = Keypair::new();
+        let fund =
+            system_instruction::transfer(&self.payer(), &user.pubkey(), 10 * LAMPORTS_PER_SOL);
+        self.send(&[fund], &[]).await.unwrap();
+        user
+    }
+
+    /// Send `instructions` in one transaction paid for by the test payer
+    pub async fn send(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<(), BanksClientError> {
+        self.nonce += 1;
+        let mut all = vec![ComputeBudgetInstruction::set_compute_unit_limit(
+            1_400_000 - self.nonce,
+        )];
+        all.extend_from_slice(instructions);
+
+        let mut keypairs = vec![&self.ctx.payer];
+        keypairs.extend_from_slice(signers);
+
+        let blockhash = self.ctx.banks_client.get_latest_blockhash().await?;
+        let tx = Transaction::new_signed_with_payer(
+            &all,
+            Some(&self.ctx.payer.pubkey()),
+            &keypairs,
+            blockhash,
+        );
+        self.ctx.banks_client.process_transaction(tx).await
+    }
+
+    /// Simulate a view instruction and decode what it returns
+    pub async fn view<T: AnchorDeserialize>(&mut self, instruction: Instruction) -> T {
+        let blockhash = self.ctx.banks_client.get_latest_blockhash().await.unwrap();
+        let tx = Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&self.ctx.payer.pubkey()),
+            &[&self.ctx.payer],
+            blockhash,
+        );
+        let simulation = self
+            .ctx
+            .banks_client
+            .simulate_transaction(tx)
+            .await
+            .unwrap();
+        simulation.result.unwrap().unwrap();
+        let data = simulation
+            .simulation_details
+            .and_then(|details| details.return_data)
+            .expect("view returned nothing")
+            .data;
+        T::try_from_slice(&data).unwrap()
+    }
+
+    /// Fetch and deserialize a program account. Panics if it does not exist.
+    pub async fn account<T: AccountDeserialize>(&mut self, address: Pubkey) -> T {
+        let account = self
+            .ctx
+            .banks_client
+            .get_account(address)
+            .await
+            .unwrap()
+            .unwrap_or_else(|| panic!("account {address} does not exist"));
+        T::try_deserialize(&mut account.data.as_slice()).unwrap()
+    }
+
+    /// Fetch a zero-copy program account. Panics if it does not exist.
+    pub async fn load<T: ZeroCopy>(&mut self, address: Pubkey) -> T {
+        let account = self
+            .ctx
+            .banks_client
+            .get_account(address)
+            .await
+            .unwrap()
+            .unwrap_or_else(|| panic!("account {address} does not exist"));
+        assert_eq!(account.data[..8], T::DISCRIMINATOR);
+        bytemuck::pod_read_unaligned(&account.data[8..8 + std::mem::size_of::<T>()])
+    }
+
+    /// Address the next AuditEntry of the repository at `repository` goes to
+    pub async fn audit_entry(&mut self, repository: Pubkey) -> Pubkey {
+        let repo: Repository = self.account(repository).await;
+        pda::audit(&repo.id, repo.audit_count)
+    }
+
+    pub async fn project_audit_entry(&mut self, project: Pubkey) -> Pubkey {
+        let state: Project = self.account(project).await;
+        pda::project_audit(&project, state.audit_count)
+    }
+
+    pub async fn exists(&mut self, address: Pubkey) -> bool {
+        self.ctx
+            .banks_client
+            .get_account(address)
+            .await
+            .unwrap()
+            .is_some()
+    }
+
+    pub async fn data(&mut self, address: Pubkey) -> Vec<u8> {
+        let account = self.ctx.banks_client.get_account(address).await.unwrap();
+        account.map_or(Vec::new(), |account| account.data)
+    }
+
+    pub async fn data_len(&mut self, address: Pubkey) -> usize {
+        let account = self.ctx.banks_client.get_account(address).await.unwrap();
+        account.map_or(0, |account| account.data.len())
+    }
+
+    pub async fn lamports(&mut self, address: Pubkey) -> u64 {
+        self.ctx.banks_client.get_balance(address).await.unwrap()
+    }
+
+    pub async fn now(&mut self) -> i64 {
+        self.ctx
+            .banks_client
+            .get_sysvar::<Clock>()
+            .await
+            .unwrap()
+            .unix_timestamp
+    }
+
+    /// Rent-exempt minimum balance for `space` bytes
+    pub async fn rent(&mut self, space: usize) -> u64 {
+        let rent = self.ctx.banks_client.get_rent().await.unwrap();
+        rent.minimum_balance(space)
+    }
+
+    /// Overwrite a program account with `state` serialized into `space` bytes
+    /// and funded for exactly that size. Stands in for accounts created under
+    /// an older, smaller layout.
+    pub async fn set_state<T: AccountSerialize>(
+        &mut self,
+        address: Pubkey,
+        state: &T,
+        space: usize,
+    ) {
+        let mut data = Vec::new();
+        state.try_serialize(&mut data).unwrap();
+        data.resize(space, 0);
+        self.set_data(address, data).await;
+    }
+
+    /// Overwrite a program account with raw `data`, funded for exactly its
+    /// size. For zero-copy accounts, which `set_state` cannot serialize.
+    pub async fn set_data(&mut self, address: Pubkey, data: Vec<u8>) {
+        let account = Account {
+            lamports: self.rent(data.len()).await,
+            data,
+            owner: vanish_program::ID,
+            executable: false,
+            rent_epoch: 0,
+        };
+        self.ctx.set_account(&address, &account.into());
+    }
+
+    /// Move the clock `seconds` forward
+    pub async fn warp(&mut self, seconds: i64) {
+        let mut clock: Clock = self.ctx.banks_client.get_sysvar().await.unwrap();
+        clock.unix_timestamp += seconds;
+        self.ctx.set_sysvar(&clock);
+    }
+
+    /// Move the clock forward by `slots` slots
+    pub async fn warp_slots(&mut self, slots: u64) {
+        let mut clock: Clock = self.ctx.banks_client.get_sysvar().await.unwrap();
+        clock.slot += slots;
+        self.ctx.set_sysvar(&clock);
+    }
+
+    // ------------------------------------------------------------------------
+    // Fixtures
+    // ------------------------------------------------------------------------
+
+    /// Create a repository owned by `owner` and return its address
+    pub async fn create_repo(&mut self, owner: &Keypair, name: &str, is_private: bool) -> Pubkey {
+        let repository = pda::repo(&owner.pubkey(), name);
+        let create = ix(
+            accounts::CreateRepo {
+                owner: owner.pubkey(),
+                repository,
+                tombstone: pda::tombstone(&repository),
+                name_lock: pda::name_lock(&owner.pubkey(), name),
+                name_stake: None,
+                profile: pda::profile(&owner.pubkey()),
+                config: pda::config(),
+                treasury: pda::treasury(),
+                system_program: system_program(),
+            },
+            instruction::CreateRepo {
+                name: name.to_string(),
+                description: "A test repository".to_string(),
+                is_private,
+            },
+        );
+        self.send(&[create], &[owner]).await.unwrap();
+        repository
+    }
+
+    /// Add `user` as a collaborator, sealing a dummy key to them if the
+    /// repository is private
+    pub async fn add_collaborator(
+        &mut self,
+        owner: &Keypair,
+        repository: Pubkey,
+        user: Pubkey,
+        can_push: bool,
+        is_private: bool,
+    ) {
+        let repo: Repository = self.account(repository).await;
+        let add = add_collaborator_ix(
+            owner.pubkey(),
+            None,
+            repository,
+            user,
+            can_push,
+            is_private,
+            &repo,
+        );
+        self.send(&[add], &[owner]).await.unwrap();
+    }
+
+    /// Add `user` as a collaborator with the admin role
+    pub async fn add_admin(&mut self, owner: &Keypair, repository: Pubkey, user: Pubkey) {
+        self.add_collaborator(owner, repository, user, true, false)
+            .await;
+        let promote = ix(
+            accounts::SetCollaboratorAdmin {
+                authority: owner.pubkey(),
+                repository,
+                admin: None,
+                collaborator_account: pda::collab(&repository, &user),
+                audit_entry: self.audit_entry(repository).await,
+                system_program: system_program(),
+            },
+            instruction::SetCollaboratorAdmin { is_admin: true },
+        );
+        self.send(&[promote], &[owner]).await.unwrap();
+    }
+
+    /// Create a classic SPL mint with `decimals`, minting authority `authority`
+    pub async fn create_mint(&mut self, authority: &Pubkey, decimals: u8) -> Pubkey {
+        let mint = Keypair::new();
+        let rent = self.ctx.banks_client.get_rent().await.unwrap();
+        let instructions = [
+            system_instruction::create_account(
+                &self.payer(),
+                &mint.pubkey(),
+                rent.minimum_balance(spl_token::state::Mint::LEN),
+                spl_token::state::Mint::LEN as u64,
+                &spl_token::ID,
+            ),
+            spl_token::instruction::initialize_mint2(
+                &spl_token::ID,
+                &mint.pubkey(),
+                authority,
+                None,
+                decimals,
+            )
+            .unwrap(),
+        ];
+        self.send(&instructions, &[&mint]).await.unwrap();
+        mint.pubkey()
+    }
+
+    /// Create a classic SPL token account for `owner`
+    pub async fn create_token_account(&mut self, mint: &Pubkey, owner: &Pubkey) -> Pubkey {
+        let account = Keypair::new();
+        let rent = self.ctx.banks_client.get_rent().await.unwrap();
+        let instructions = [
+            system_instruction::create_account(
+                &self.payer(),
+                &account.pubkey(),
+                rent.minimum_balance(spl_token::state::Account::LEN),
+                spl_token::state::Account::LEN as u64,
+                &spl_token::ID,
+            ),
+            spl_token::instruction::initialize_account3(
+                &spl_token::ID,
+                &account.pubkey(),
+                mint,
+                owner,
+            )
+            .unwrap(),
+        ];
+        self.send(&instructions, &[&account]).await.unwrap();
+        account.pubkey()
+    }
+
+    pub async fn mint_to(&mut self, mint: &Pubkey, to: &Pubkey, authority: &Keypair, amount: u64) {
+        let mint_to = spl_token::instruction::mint_to(
+            &spl_token::ID,
+            mint,
+            to,
+            &authority.pubkey(),
+            &[],
+            amount,
+        )
+        .unwrap();
+        self.send(&[mint_to], &[authority]).await.unwrap();
+    }
+
+    pub async fn token_balance(&mut self, account: Pubkey) -> u64 {
+        let account = self
+            .ctx
+            .banks_client
+            .get_account(account)
+            .await
+            .unwrap()
+            .unwrap();
+        spl_token::state::Account::unpack(&account.data)
+            .unwrap()
+            .amount
+    }
+}
+
+// ============================================================================
+// Instructions
+// ============================================================================
+
+/// Build a program instruction from its Anchor accounts and arguments
+pub fn ix(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: vanish_program::ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+pub fn system_program() -> Pubkey {
+    solana_sdk::system_program::ID
+}
+
+/// `repo` is the repository as it stands, which picks the audit entry and
+/// index slot the instruction creates
+pub fn add_collaborator_ix(
+    authority: Pubkey,
+    admin: Option<Pubkey>,
+    repository: Pubkey,
+    user: Pubkey,
+    can_push: bool,
+    is_private: bool,
+    repo: &Repository,
+) -> Instruction {
+    ix(
+        accounts::AddCollaborator {
+            authority,
+            repository,
+            admin,
+            collaborator_account: pda::collab(&repo.id, &user),
+            collaborator_slot: pda::collab_slot(&repo.id, repo.collaborator_count),
+            access_key: is_private.then(|| pda::access(&repo.id, &user)),
+            audit_entry: pda::audit(&repo.id, repo.audit_count),
+            project_repo: None,
+            rent_vault: None,
+            system_program: system_program(),
+        },
+        instruction::AddCollaborator {
+            collaborator: user,
+            can_push,
+            key_envelope: is_private.then(envelope),
+        },
+    )
+}
+
+/// A push that names no expected head: the first push to a repository, or a
+/// force push
+pub fn push_ix(
+    pusher: Pubkey,
+    repository: Pubkey,
+    collaborator_account: Option<Pubkey>,
+    head_commit: Multihash,
+) -> Instruction {
+    push_onto_ix(pusher, repository, collaborator_account, None, head_commit)
+}
+
+pub fn push_onto_ix(
+    pusher: Pubkey,
+    repository: Pubkey,
+    collaborator_account: Option<Pubkey>,
+    expected_head: Option<Multihash>,
+    head_commit: Multihash,
+) -> Instruction {
+    ix(
+        accounts::PushUpdate {
+            pusher,
+            repository,
+            collaborator_account,
+            contribution: pda::contribution(&repository, &pusher),
+            push_log: None,
+            project_repo: pda::project_repo(&repository),
+            project: None,
+            project_member: None,
+            badge_config: None,
+            badge_mint: None,
+            badge_token_account: None,
+            token_2022_program: None,
+            system_program: system_program(),
+        },
+        instruction::PushUpdate {
+            head_commit,
+            ipfs_cid: Cid::parse(PUSH_CID).unwrap(),
+            expected_head,
+            co_authors: vec![],
+            note: None,
+            binding: None,
+        },
+    )
+}
+
+pub fn push_branch_ix(
+    pusher: Pubkey,
+    repository: Pubkey,
+    name: &str,
+    expected_head: Option<Multihash>,
+    head_commit: Multihash,
+    proof: Vec<[u8; 32]>,
+) -> Instruction {
+    ix(
+        accounts::PushBranch {
+            pusher,
+            repository,
+            collaborator_account: None,
+            branch_grant: None,
+            contribution: pda::contribution(&repository, &pusher),
+            branch: pda::branch(&repository, name),
+            project_repo: None,
+            rent_vault: None,
+            system_program: system_program(),
+        },
+        instruction::PushBranch {
+            branch_name: name.to_string(),
+            head_commit,
+            ipfs_cid: "QmRefs".to_string(),
+            expected_head,
+            proof,
+        },
+    )
+}
+
+/// Refs tree proof for a repository's only branch: every sibling subtree is
+/// empty
+pub fn lone_ref_proof() -> Vec<[u8; 32]> {
+    vec![[0; 32]; refs_tree::DEPTH]
+}
+
+// ============================================================================
+// Values
+// ============================================================================
+
+/// The archive push_ix pushes
+pub const PUSH_CID: &str = "QmcQcvDYP9jkh3Q5gWmMkWmhVTDfeJFUPSKtnZRBqDXXQY";
+
+/// A SHA-1 commit id filled with `byte`
+pub fn sha1(byte: u8) -> Multihash {
+    Multihash {
+        code: Multihash::SHA1,
+        digest: vec![byte; 20],
+    }
+}
+
+/// A SHA-256 digest filled with `byte`
+pub fn sha256(byte: u8) -> Multihash {
+    Multihash {
+        code: Multihash::SHA2_256,
+        digest: vec![byte; 32],
+    }
+}
+
+pub fn envelope() -> KeyEnvelope {
+    KeyEnvelope {
+        ephemeral_pubkey: [1; 32],
+        nonce: [2; 24],
+        ciphertext: [3; 48],
+    }
+}
+
+pub fn semver(major: u32, minor: u32, patch: u32) -> SemVer {
+    SemVer {
+        major,
+        minor,
+        patch,
+    }
+}
+
+// ============================================================================
+// Assertions
+// ============================================================================
+
+/// Custom error code a failed transaction aborted with
+pub fn error_code(result: Result<(), BanksClientError>) -> u32 {
+    match result.expect_err("transaction should have failed").unwrap() {
+        TransactionError::InstructionError(_, InstructionError::Custom(code)) => code,
+        err => panic!("expected a custom program error, got {err:?}"),
+    }
+}
+
+#[track_caller]
+pub fn assert_error(result: Result<(), BanksClientError>, expected: impl Into<u32>) {
+    assert_eq!(error_code(result), expected.into());
+}
+
+// ============================================================================
+// PDAs
+// ============================================================================
+
+pub mod pda {
+    use super::*;
+
+    fn find(seeds: &[&[u8]]) -> Pubkey {
+        Pubkey::find_program_address(seeds, &vanish_program::ID).0
+    }
+
+    pub fn config() -> Pubkey {
+        find(&[b"config"])
+    }
+
+    pub fn treasury() -> Pubkey {
+        find(&[b"treasury"])
+    }
+
+    pub fn profile(user: &Pubkey) -> Pubkey {
+        find(&[b"user", user.as_ref()])
+    }
+
+    pub fn repo(creator: &Pubkey, name: &str) -> Pubkey {
+        find(&[b"repo", creator.as_ref(), name.as_bytes()])
+    }
+
+    pub fn verifier(verifier: &Pubkey) -> Pubkey {
+        find(&[b"verifier", verifier.as_ref()])
+    }
+
+    pub fn deal_oracle(oracle: &Pubkey) -> Pubkey {
+        find(&[b"deal_oracle", oracle.as_ref()])
+    }
+
+    pub fn pinner(operator: &Pubkey) -> Pubkey {
+        find(&[b"pinner", operator.as_ref()])
+    }
+
+    pub fn branch_grant(id: &Pubkey, user: &Pubkey) -> Pubkey {
+        find(&[b"branch_grant", id.as_ref(), user.as_ref()])
+    }
+
+    pub fn webhook(id: &Pubkey, url_hash: &[u8; 32]) -> Pubkey {
+        find(&[b"webhook", id.as_ref(), url_hash])
+    }
+
+    pub fn reward_pool(id: &Pubkey) -> Pubkey {
+        find(&[b"reward_pool", id.as_ref()])
+    }
+
+    pub fn pin_attestation(id: &Pubkey, operator: &Pubkey) -> Pubkey {
+        find(&[b"pin", id.as_ref(), operator.as_ref()])
+    }
+
+    pub fn filecoin_deal(id: &Pubkey) -> Pubkey {
+        find(&[b"filecoin", id.as_ref()])
+    }
+
+    pub fn verification(id: &Pubkey) -> Pubkey {
+        find(&[b"verification", id.as_ref()])
+    }
+
+    pub fn stake(id: &Pubkey) -> Pubkey {
+        find(&[b"stake", id.as_ref()])
+    }
+
+    pub fn tombstone(address: &Pubkey) -> Pubkey {
+        find(&[b"tombstone", address.as_ref()])
+    }
+
+    pub fn purge_receipt(id: &Pubkey) -> Pubkey {
+        find(&[b"purged", id.as_ref()])
+    }
+
+    pub fn reclaim(id: &Pubkey) -> Pubkey {
+        find(&[b"reclaim", id.as_ref()])
+    }
+
+    pub fn recovery(id: &Pubkey) -> Pubkey {
+        find(&[b"recovery", id.as_ref()])
+    }
+
+    pub fn redirect(creator: &Pubkey, name: &str) -> Pubkey {
+        find(&[b"redirect", creator.as_ref(), name.as_bytes()])
+    }
+
+    pub fn activity(user: &Pubkey) -> Pubkey {
+        find(&[b"activity", user.as_ref()])
+    }
+
+    pub fn list(owner: &Pubkey, name: &str) -> Pubkey {
+        find(&[b"list", owner.as_ref(), name.as_bytes()])
+    }
+
+    pub fn list_entry(list: &Pubkey, id: &Pubkey) -> Pubkey {
+        find(&[b"list_entry", list.as_ref(), id.as_ref()])
+    }
+
+    pub fn name_lock(creator: &Pubkey, name: &str) -> Pubkey {
+        find(&[
+            b"name_lock",
+            creator.as_ref(),
+            &hash(name.to_ascii_lowercase().as_bytes()).to_bytes(),
+        ])
+    }
+
+    pub fn repo_name(name: &str) -> Pubkey {
+        find(&[b"dir", &hash(name.as_bytes()).to_bytes()])
+    }
+
+    pub fn timelock(id: &Pubkey) -> Pubkey {
+        find(&[b"timelock", id.as_ref()])
+    }
+
+    pub fn pending(id: &Pubkey) -> Pubkey {
+        find(&[b"pending", id.as_ref()])
+    }
+
+    pub fn stats(id: &Pubkey) -> Pubkey {
+        find(&[b"stats", id.as_ref()])
+    }
+
+    pub fn project(admin: &Pubkey, name: &str) -> Pubkey {
+        find(&[b"project", admin.as_ref(), name.as_bytes()])
+    }
+
+    pub fn project_repo(id: &Pubkey) -> Pubkey {
+        find(&[b"project_repo", id.as_ref()])
+    }
+
+    pub fn project_member(project: &Pubkey, user: &Pubkey) -> Pubkey {
+        find(&[b"project_member", project.as_ref(), user.as_ref()])
+    }
+
+    pub fn rent_vault(project: &Pubkey) -> Pubkey {
+        find(&[b"rent_vault", project.as_ref()])
+    }
+
+    pub fn project_audit(project: &Pubkey, seq: u64) -> Pubkey {
+        find(&[b"project_audit", project.as_ref(), &seq.to_le_bytes()])
+    }
+
+    pub fn branch(id: &Pubkey, name: &str) -> Pubkey {
+        find(&[b"branch", id.as_ref(), &hash(name.as_bytes()).to_bytes()])
+    }
+
+    pub fn fork_link(id: &Pubkey) -> Pubkey {
+        find(&[b"upstream", id.as_ref()])
+    }
+
+    pub fn submodule(id: &Pubkey, path: &str) -> Pubkey {
+        find(&[b"submodule", id.as_ref(), &hash(path.as_bytes()).to_bytes()])
+    }
+
+    pub fn collab(id: &Pubkey, user: &Pubkey) -> Pubkey {
+        find(&[b"collab", id.as_ref(), user.as_ref()])
+    }
+
+    pub fn read(id: &Pubkey, reader: &Pubkey) -> Pubkey {
+        find(&[b"read", id.as_ref(), reader.as_ref()])
+    }
+
+    pub fn collab_slot(id: &Pubkey, index: u32) -> Pubkey {
+        find(&[b"collab_slot", id.as_ref(), &index.to_le_bytes()])
+    }
+
+    pub fn contribution(id: &Pubkey, user: &Pubkey) -> Pubkey {
+        find(&[b"contribution", id.as_ref(), user.as_ref()])
+    }
+
+    pub fn audit(id: &Pubkey, seq: u64) -> Pubkey {
+        find(&[b"audit", id.as_ref(), &seq.to_le_bytes()])
+    }
+
+    pub fn snapshot(id: &Pubkey, taker: &Pubkey, slot: u64) -> Pubkey {
+        find(&[
+            b"snapshot",
+            id.as_ref(),
+            taker.as_ref(),
+            &slot.to_le_bytes(),
+        ])
+    }
+
+    pub fn history(id: &Pubkey, page: u32) -> Pubkey {
+        find(&[b"history", id.as_ref(), &page.to_le_bytes()])
+    }
+
+    pub fn access(id: &Pubkey, user: &Pubkey) -> Pubkey {
+        find(&[b"access", id.as_ref(), user.as_ref()])
+    }
+
+    pub fn pricing(id: &Pubkey) -> Pubkey {
+        find(&[b"pricing", id.as_ref()])
+    }
+
+    pub fn grant(id: &Pubkey, user: &Pubkey) -> Pubkey {
+        find(&[b"grant", id.as_ref(), user.as_ref()])
+    }
+
+    pub fn badges(id: &Pubkey) -> Pubkey {
+        find(&[b"badges", id.as_ref()])
+    }
+
+    pub fn push_log(id: &Pubkey) -> Pubkey {
+        find(&[b"push_log", id.as_ref()])
+    }
+
+    pub fn ci(id: &Pubkey, attestor: &Pubkey) -> Pubkey {
+        find(&[b"ci", id.as_ref(), attestor.as_ref()])
+    }
+
+    pub fn check(id: &Pubkey, commit: &Multihash, attestor: &Pubkey) -> Pubkey {
+        find(&[b"check", id.as_ref(), &commit.seed(), attestor.as_ref()])
+    }
+
+    pub fn release_policy(id: &Pubkey) -> Pubkey {
+        find(&[b"release_policy", id.as_ref()])
+    }
+
+    pub fn release(id: &Pubkey, tag: &str) -> Pubkey {
+        find(&[b"release", id.as_ref(), tag.as_bytes()])
+    }
+
+    pub fn approval(release: &Pubkey, manager: &Pubkey) -> Pubkey {
+        find(&[b"approval", release.as_ref(), manager.as_ref()])
+    }
+
+    pub fn builder(id: &Pubkey, builder: &Pubkey) -> Pubkey {
+        find(&[b"builder", id.as_ref(), builder.as_ref()])
+    }
+
+    pub fn attestation(release: &Pubkey, checksum: &Multihash, builder: &Pubkey) -> Pubkey {
+        find(&[
+            b"attestation",
+            release.as_ref(),
+            &checksum.seed(),
+            builder.as_ref(),
+        ])
+    }
+
+    pub fn nft(id: &Pubkey) -> Pubkey {
+        find(&[b"nft", id.as_ref()])
+    }
+
+    pub fn package(name: &str) -> Pubkey {
+        find(&[b"package", name.as_bytes()])
+    }
+
+    pub fn version(package: &Pubkey, version: &SemVer) -> Pubkey {
+        find(&[
+            b"version",
+            package.as_ref(),
+            &version.major.to_le_bytes(),
+            &version.minor.to_le_bytes(),
+            &version.patch.to_le_bytes(),
+        ])
+    }
+
+    pub fn star_tree() -> Pubkey {
+        find(&[b"star_tree"])
+    }
+
+    pub fn donations(id: &Pubkey) -> Pubkey {
+        find(&[b"donations", id.as_ref()])
+    }
+
+    pub fn star(user: &Pubkey, id: &Pubkey) -> Pubkey {
+        find(&[b"star", user.as_ref(), id.as_ref()])
+    }
+}
@@ -0,0 +1,635 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::*;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::signature::{Keypair, Signer};
+use vanish_program::{
+    accounts, config_flags, instruction, Config, FilecoinDeal, Profile, Verification,
+};
+
+fn update_ix(admin: Pubkey, new_admin: Pubkey, repo_fee: u64, flags: u8) -> Instruction {
+    ix(
+        accounts::UpdateConfig {
+            admin,
+            config: pda::config(),
+        },
+        instruction::UpdateConfig {
+            admin: new_admin,
+            repo_fee,
+            flags,
+        },
+    )
+}
+
+#[tokio::test]
+async fn config_is_created_once() {
+    let mut env = Env::new().await;
+    let squatter = env.user().await;
+
+    let config: Config = env.account(pda::config()).await;
+    assert_eq!(config.admin, env.payer());
+    assert_eq!(config.repo_fee, 0);
+    assert_eq!(config.flags, 0);
+
+    let init = ix(
+        accounts::InitializeConfig {
+            admin: squatter.pubkey(),
+            config: pda::config(),
+            treasury: pda::treasury(),
+            system_program: system_program(),
+        },
+        instruction::InitializeConfig {
+            repo_fee: 0,
+            flags: 0,
+        },
+    );
+    assert_error(
+        env.send(&[init], &[&squatter]).await,
+        ACCOUNT_ALREADY_IN_USE,
+    );
+
+    // Nor can a look-alike account stand in for it
+    let mut update = update_ix(squatter.pubkey(), squatter.pubkey(), 0, 0);
+    update.accounts[1].pubkey = Pubkey::new_unique();
+    assert_error(
+        env.send(&[update], &[&squatter]).await,
+        AnchorError::AccountNotInitialized,
+    );
+}
+
+#[tokio::test]
+async fn only_the_config_admin_updates_it() {
+    let mut env = Env::new().await;
+    let dao = env.user().await;
+    let payer = env.payer();
+
+    assert_error(
+        env.send(&[update_ix(dao.pubkey(), dao.pubkey(), 0, 0)], &[&dao])
+            .await,
+        VanishError::Unauthorized,
+    );
+    assert_error(
+        env.send(&[update_ix(payer, payer, 0, 1 << 7)], &[]).await,
+        VanishError::InvalidConfigFlags,
+    );
+
+    // Handing the role over leaves the old admin with nothing
+    env.send(&[update_ix(payer, dao.pubkey(), 5_000, 0)], &[])
+        .await
+        .unwrap();
+    let config: Config = env.account(pda::config()).await;
+    assert_eq!(config.admin, dao.pubkey());
+    assert_eq!(config.repo_fee, 5_000);
+
+    assert_error(
+        env.send(&[update_ix(payer, payer, 0, 0)], &[]).await,
+        VanishError::Unauthorized,
+    );
+}
+
+#[tokio::test]
+async fn paused_creation_rejects_new_repositories() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let payer = env.payer();
+    let before = env.create_repo(&owner, "before", false).await;
+
+    env.send(
+        &[update_ix(payer, payer, 0, config_flags::PAUSE_CREATION)],
+        &[],
+    )
+    .await
+    .unwrap();
+    let create = ix(
+        accounts::CreateRepo {
+            owner: owner.pubkey(),
+            repository: pda::repo(&owner.pubkey(), "after"),
+            tombstone: pda::tombstone(&pda::repo(&owner.pubkey(), "after")),
+            name_lock: pda::name_lock(&owner.pubkey(), "after"),
+            name_stake: None,
+            profile: pda::profile(&owner.pubkey()),
+            config: pda::config(),
+            treasury: pda::treasury(),
+            system_program: system_program(),
+        },
+        instruction::CreateRepo {
+            name: "after".to_string(),
+            description: String::new(),
+            is_private: false,
+        },
+    );
+    assert_error(
+        env.send(std::slice::from_ref(&create), &[&owner]).await,
+        VanishError::CreationPaused,
+    );
+
+    // Existing repositories keep working
+    env.send(&[push_ix(owner.pubkey(), before, None, sha1(1))], &[&owner])
+        .await
+        .unwrap();
+
+    env.send(&[update_ix(payer, payer, 0, 0)], &[])
+        .await
+        .unwrap();
+    env.send(&[create], &[&owner]).await.unwrap();
+}
+
+#[tokio::test]
+async fn repo_fee_is_paid_into_the_treasury() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let recipient = env.user().await;
+    let payer = env.payer();
+    let rent_minimum = env.rent(0).await;
+    assert_eq!(env.lamports(pda::treasury()).await, rent_minimum);
+
+    env.send(&[update_ix(payer, payer, 1_000_000, 0)], &[])
+        .await
+        .unwrap();
+    env.create_repo(&owner, "repo", false).await;
+    assert_eq!(
+        env.lamports(pda::treasury()).await,
+        rent_minimum + 1_000_000
+    );
+
+    let withdraw = |admin: Pubkey, amount: u64| {
+        ix(
+            accounts::WithdrawTreasury {
+                admin,
+                config: pda::config(),
+                treasury: pda::treasury(),
+                recipient: recipient.pubkey(),
+                system_program: system_program(),
+            },
+            instruction::WithdrawTreasury { amount },
+        )
+    };
+    assert_error(
+        env.send(&[withdraw(owner.pubkey(), 1_000_000)], &[&owner])
+            .await,
+        VanishError::Unauthorized,
+    );
+    assert_error(
+        env.send(&[withdraw(payer, 1_000_001)], &[]).await,
+        VanishError::InsufficientTreasury,
+    );
+
+    let before = env.lamports(recipient.pubkey()).await;
+    env.send(&[withdraw(payer, 1_000_000)], &[]).await.unwrap();
+    assert_eq!(env.lamports(recipient.pubkey()).await, before + 1_000_000);
+    assert_eq!(env.lamports(pda::treasury()).await, rent_minimum);
+}
+
+#[tokio::test]
+async fn repo_quota_caps_creation_per_user() {
+    let mut env = Env::new().await;
+    let verified = env.user().await;
+    let other = env.user().await;
+    let payer = env.payer();
+
+    let set_quota = ix(
+        accounts::UpdateConfig {
+            admin: payer,
+            config: pda::config(),
+        },
+        instruction::SetRepoQuota { max_repos: 1 },
+    );
+    env.send(&[set_quota], &[]).await.unwrap();
+
+    let create = |owner: &Keypair, name: &str| {
+        ix(
+            accounts::CreateRepo {
+                owner: owner.pubkey(),
+                repository: pda::repo(&owner.pubkey(), name),
+                tombstone: pda::tombstone(&pda::repo(&owner.pubkey(), name)),
+                name_lock: pda::name_lock(&owner.pubkey(), name),
+                name_stake: None,
+                profile: pda::profile(&owner.pubkey()),
+                config: pda::config(),
+                treasury: pda::treasury(),
+                system_program: system_program(),
+            },
+            instruction::CreateRepo {
+                name: name.to_string(),
+                description: String::new(),
+                is_private: false,
+            },
+        )
+    };
+    env.send(&[create(&verified, "one")], &[&verified])
+        .await
+        .unwrap();
+    assert_error(
+        env.send(&[create(&verified, "two")], &[&verified]).await,
+        VanishError::RepoQuotaExceeded,
+    );
+
+    let set_limit = |admin: Pubkey, limit: u32| {
+        ix(
+            accounts::SetRepoLimit {
+                admin,
+                config: pda::config(),
+                profile: pda::profile(&verified.pubkey()),
+                system_program: system_program(),
+            },
+            instruction::SetRepoLimit {
+                user: verified.pubkey(),
+                limit,
+            },
+        )
+    };
+    assert_error(
+        env.send(&[set_limit(other.pubkey(), 2)], &[&other]).await,
+        VanishError::Unauthorized,
+    );
+    env.send(&[set_limit(payer, 2)], &[]).await.unwrap();
+    env.send(&[create(&verified, "two")], &[&verified])
+        .await
+        .unwrap();
+    let profile: Profile = env.account(pda::profile(&verified.pubkey())).await;
+    assert_eq!(profile.repo_count, 2);
+    assert_eq!(profile.repo_limit, 2);
+
+    // Everyone else keeps the config-wide cap
+    env.send(&[create(&other, "one")], &[&other]).await.unwrap();
+    assert_error(
+        env.send(&[create(&other, "two")], &[&other]).await,
+        VanishError::RepoQuotaExceeded,
+    );
+}
+
+#[tokio::test]
+async fn rate_limits_cap_creations_and_stars_per_window() {
+    let mut env = Env::new().await;
+    let user = env.user().await;
+    let other = env.user().await;
+    let payer = env.payer();
+    let first = env.create_repo(&other, "first", false).await;
+    let second = env.create_repo(&other, "second", false).await;
+
+    let set_limits = |admin: Pubkey| {
+        ix(
+            accounts::UpdateConfig {
+                admin,
+                config: pda::config(),
+            },
+            instruction::SetRateLimits {
+                window_slots: 100,
+                max_creations: 1,
+                max_stars: 1,
+            },
+        )
+    };
+    assert_error(
+        env.send(&[set_limits(user.pubkey())], &[&user]).await,
+        VanishError::Unauthorized,
+    );
+    env.send(&[set_limits(payer)], &[]).await.unwrap();
+    let config: Config = env.account(pda::config()).await;
+    assert_eq!(config.rate_window_slots, 100);
+
+    let create = |name: &str| {
+        ix(
+            accounts::CreateRepo {
+                owner: user.pubkey(),
+                repository: pda::repo(&user.pubkey(), name),
+                tombstone: pda::tombstone(&pda::repo(&user.pubkey(), name)),
+                name_lock: pda::name_lock(&user.pubkey(), name),
+                name_stake: None,
+                profile: pda::profile(&user.pubkey()),
+                config: pda::config(),
+                treasury: pda::treasury(),
+                system_program: system_program(),
+            },
+            instruction::CreateRepo {
+                name: name.to_string(),
+                description: String::new(),
+                is_private: false,
+            },
+        )
+    };
+    let star = |repository: Pubkey| {
+        ix(
+            accounts::StarRepo {
+                user: user.pubkey(),
+                repository,
+                star_account: pda::star(&user.pubkey(), &repository),
+                donation_vault: pda::donations(&repository),
+                profile: pda::profile(&user.pubkey()),
+                config: pda::config(),
+                system_program: system_program(),
+            },
+            instruction::StarRepo { tip: 0 },
+        )
+    };
+
+    // Creations and stars are counted separately
+    env.send(&[create("one")], &[&user]).await.unwrap();
+    env.send(&[star(first)], &[&user]).await.unwrap();
+    assert_error(
+        env.send(&[create("two")], &[&user]).await,
+        VanishError::RateLimited,
+    );
+    assert_error(
+        env.send(&[star(second)], &[&user]).await,
+        VanishError::RateLimited,
+    );
+    let profile: Profile = env.account(pda::profile(&user.pubkey())).await;
+    assert_eq!(profile.version, Profile::VERSION);
+    assert_eq!((profile.window_creations, profile.window_stars), (1, 1));
+
+    // The next window starts over
+    env.warp_slots(100).await;
+    env.send(&[create("two")], &[&user]).await.unwrap();
+    env.send(&[star(second)], &[&user]).await.unwrap();
+    let profile: Profile = env.account(pda::profile(&user.pubkey())).await;
+    assert_eq!(profile.repo_count, 2);
+    assert_eq!((profile.window_creations, profile.window_stars), (1, 1));
+}
+
+#[tokio::test]
+async fn reserved_names_cannot_be_taken() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let payer = env.payer();
+
+    let reserve = |admin: Pubkey, names: &[&str]| {
+        ix(
+            accounts::UpdateConfig {
+                admin,
+                config: pda::config(),
+            },
+            instruction::SetReservedNames {
+                names: names.iter().map(|name| name.to_string()).collect(),
+            },
+        )
+    };
+    assert_error(
+        env.send(&[reserve(owner.pubkey(), &["admin"])], &[&owner])
+            .await,
+        VanishError::Unauthorized,
+    );
+    assert_error(
+        env.send(&[reserve(payer, &[".git"])], &[]).await,
+        VanishError::InvalidReservedNames,
+    );
+    env.send(&[reserve(payer, &["admin", "api"])], &[])
+        .await
+        .unwrap();
+    let config: Config = env.account(pda::config()).await;
+    assert_eq!(config.reserved_names, ["admin", "api"]);
+
+    let create = |name: &str| {
+        ix(
+            accounts::CreateRepo {
+                owner: owner.pubkey(),
+                repository: pda::repo(&owner.pubkey(), name),
+                tombstone: pda::tombstone(&pda::repo(&owner.pubkey(), name)),
+                name_lock: pda::name_lock(&owner.pubkey(), name),
+                name_stake: None,
+                profile: pda::profile(&owner.pubkey()),
+                config: pda::config(),
+                treasury: pda::treasury(),
+                system_program: system_program(),
+            },
+            instruction::CreateRepo {
+                name: name.to_string(),
+                description: String::new(),
+                is_private: false,
+            },
+        )
+    };
+    for name in ["admin", "API"] {
+        assert_error(
+            env.send(&[create(name)], &[&owner]).await,
+            VanishError::NameReserved,
+        );
+    }
+    env.send(&[create("admin-tools")], &[&owner]).await.unwrap();
+}
+
+#[tokio::test]
+async fn registered_verifiers_verify_repositories() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let verifier = env.user().await;
+    let stranger = env.user().await;
+    let payer = env.payer();
+    let repository = env.create_repo(&owner, "solana", false).await;
+
+    let verify = |signer: Pubkey, registered: bool, uri: &str| {
+        ix(
+            accounts::VerifyRepo {
+                verifier: signer,
+                config: pda::config(),
+                verifier_account: registered.then(|| pda::verifier(&signer)),
+                repository,
+                verification: pda::verification(&repository),
+                system_program: system_program(),
+            },
+            instruction::VerifyRepo {
+                uri: uri.to_string(),
+            },
+        )
+    };
+    let revoke = |signer: Pubkey, registered: bool, recipient: Pubkey| {
+        ix(
+            accounts::RevokeVerification {
+                authority: signer,
+                config: pda::config(),
+                verifier_account: registered.then(|| pda::verifier(&signer)),
+                repository,
+                verification: pda::verification(&repository),
+                verifier: recipient,
+            },
+            instruction::RevokeVerification {},
+        )
+    };
+
+    assert_error(
+        env.send(
+            &[verify(verifier.pubkey(), false, "https://example.org")],
+            &[&verifier],
+        )
+        .await,
+        VanishError::Unauthorized,
+    );
+    assert_error(
+        env.send(&[verify(payer, false, "")], &[]).await,
+        VanishError::InvalidVerificationUri,
+    );
+
+    // The config admin registers verifiers; nobody else can
+    let add = |admin: Pubkey| {
+        ix(
+            accounts::AddVerifier {
+                admin,
+                config: pda::config(),
+                verifier_account: pda::verifier(&verifier.pubkey()),
+                system_program: system_program(),
+            },
+            instruction::AddVerifier {
+                verifier: verifier.pubkey(),
+            },
+        )
+    };
+    assert_error(
+        env.send(&[add(stranger.pubkey())], &[&stranger]).await,
+        VanishError::Unauthorized,
+    );
+    env.send(&[add(payer)], &[]).await.unwrap();
+
+    env.send(
+        &[verify(
+            verifier.pubkey(),
+            true,
+            "https://example.org/solana",
+        )],
+        &[&verifier],
+    )
+    .await
+    .unwrap();
+    let verification: Verification = env.account(pda::verification(&repository)).await;
+    assert_eq!(verification.repository, repository);
+    assert_eq!(verification.verifier, verifier.pubkey());
+    assert_eq!(verification.uri, "https://example.org/solana");
+
+    assert_error(
+        env.send(
+            &[revoke(stranger.pubkey(), false, verifier.pubkey())],
+            &[&stranger],
+        )
+        .await,
+        VanishError::Unauthorized,
+    );
+
+    // The admin can revoke another verifier's verification, refunding them
+    let before = env.lamports(verifier.pubkey()).await;
+    env.send(&[revoke(payer, false, verifier.pubkey())], &[])
+        .await
+        .unwrap();
+    assert!(!env.exists(pda::verification(&repository)).await);
+    assert!(env.lamports(verifier.pubkey()).await > before);
+
+    // Once removed, the verifier can no longer verify
+    let remove = ix(
+        accounts::RemoveVerifier {
+            admin: payer,
+            config: pda::config(),
+            verifier_account: pda::verifier(&verifier.pubkey()),
+        },
+        instruction::RemoveVerifier {},
+    );
+    env.send(&[remove], &[]).await.unwrap();
+    assert_error(
+        env.send(
+            &[verify(
+                verifier.pubkey(),
+                true,
+                "https://example.org/solana",
+            )],
+            &[&verifier],
+        )
+        .await,
+        AnchorError::AccountNotInitialized,
+    );
+}
+
+#[tokio::test]
+async fn deal_oracles_attest_filecoin_deals() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let oracle = env.user().await;
+    let payer = env.payer();
+    let repository = env.create_repo(&owner, "archive", false).await;
+
+    let attest = |expires_at: i64| {
+        ix(
+            accounts::AttestFilecoinDeal {
+                oracle: oracle.pubkey(),
+                oracle_account: pda::deal_oracle(&oracle.pubkey()),
+                repository,
+                filecoin_deal: pda::filecoin_deal(&repository),
+            },
+            instruction::AttestFilecoinDeal {
+                deal_id: 42,
+                expires_at,
+            },
+        )
+    };
+    let flag = ix(
+        accounts::FlagLapsedDeal {
+            repository,
+            filecoin_deal: pda::filecoin_deal(&repository),
+        },
+        instruction::FlagLapsedDeal {},
+    );
+
+    env.send(
+        &[ix(
+            accounts::DeclareFilecoin {
+                authority: owner.pubkey(),
+                repository,
+                admin: None,
+                filecoin_deal: pda::filecoin_deal(&repository),
+                system_program: system_program(),
+            },
+            instruction::DeclareFilecoin {},
+        )],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+    env.send(
+        &[push_ix(owner.pubkey(), repository, None, sha1(1))],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+
+    // Only registered oracles attest
+    let now = env.now().await;
+    assert_error(
+        env.send(&[attest(now + 100)], &[&oracle]).await,
+        AnchorError::AccountNotInitialized,
+    );
+    env.send(
+        &[ix(
+            accounts::AddDealOracle {
+                admin: payer,
+                config: pda::config(),
+                oracle_account: pda::deal_oracle(&oracle.pubkey()),
+                system_program: system_program(),
+            },
+            instruction::AddDealOracle {
+                oracle: oracle.pubkey(),
+            },
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    assert_error(
+        env.send(&[attest(now)], &[&oracle]).await,
+        VanishError::InvalidExpiry,
+    );
+    env.send(&[attest(now + 100)], &[&oracle]).await.unwrap();
+
+    let deal: FilecoinDeal = env.account(pda::filecoin_deal(&repository)).await;
+    assert_eq!(deal.payload_cid, PUSH_CID);
+    assert_eq!(deal.deal_id, 42);
+    assert_eq!(deal.oracle, oracle.pubkey());
+    assert!(deal.active);
+
+    // A live deal covering the current archive cannot be flagged
+    assert_error(
+        env.send(std::slice::from_ref(&flag), &[]).await,
+        VanishError::DealNotLapsed,
+    );
+    env.warp(100).await;
+    env.send(std::slice::from_ref(&flag), &[]).await.unwrap();
+    let deal: FilecoinDeal = env.account(pda::filecoin_deal(&repository)).await;
+    assert!(!deal.active);
+    assert_error(env.send(&[flag], &[]).await, VanishError::DealNotLapsed);
+}
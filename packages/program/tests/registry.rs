@@ -0,0 +1,323 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::*;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::signature::{Keypair, Signer};
+use vanish_program::{
+    accounts, instruction, CheckRun, CheckStatus, Multihash, Package, PackageVersion, SemVer,
+};
+
+fn create_package_ix(
+    authority: &Keypair,
+    admin: bool,
+    repository: Pubkey,
+    name: &str,
+) -> Instruction {
+    ix(
+        accounts::CreatePackage {
+            authority: authority.pubkey(),
+            repository,
+            admin: admin.then(|| pda::collab(&repository, &authority.pubkey())),
+            package: pda::package(name),
+            system_program: system_program(),
+        },
+        instruction::CreatePackage {
+            name: name.to_string(),
+        },
+    )
+}
+
+fn publish_ix(authority: &Keypair, repository: Pubkey, name: &str, version: SemVer) -> Instruction {
+    let package = pda::package(name);
+    ix(
+        accounts::PublishVersion {
+            authority: authority.pubkey(),
+            repository,
+            admin: None,
+            package,
+            package_version: pda::version(&package, &version),
+            system_program: system_program(),
+        },
+        instruction::PublishVersion {
+            version,
+            ipfs_cid: "QmPackage".to_string(),
+        },
+    )
+}
+
+#[tokio::test]
+async fn create_package_claims_a_global_name() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let admin = env.user().await;
+    let rival = env.user().await;
+    let repository = env.create_repo(&owner, "repo", false).await;
+    let rival_repository = env.create_repo(&rival, "repo", false).await;
+    env.add_admin(&owner, repository, admin.pubkey()).await;
+
+    env.send(
+        &[create_package_ix(&admin, true, repository, "left-pad")],
+        &[&admin],
+    )
+    .await
+    .unwrap();
+    let package: Package = env.account(pda::package("left-pad")).await;
+    assert_eq!(package.name, "left-pad");
+    assert_eq!(package.repository, repository);
+    assert!(package.latest.is_none());
+
+    assert_error(
+        env.send(
+            &[create_package_ix(
+                &rival,
+                false,
+                rival_repository,
+                "left-pad",
+            )],
+            &[&rival],
+        )
+        .await,
+        ACCOUNT_ALREADY_IN_USE,
+    );
+    assert_error(
+        env.send(
+            &[create_package_ix(&rival, false, repository, "right-pad")],
+            &[&rival],
+        )
+        .await,
+        VanishError::Unauthorized,
+    );
+
+    for name in ["", "Upper", "-dash", "sp ace", "dot.name"] {
+        assert_error(
+            env.send(
+                &[create_package_ix(&owner, false, repository, name)],
+                &[&owner],
+            )
+            .await,
+            VanishError::InvalidPackageName,
+        );
+    }
+}
+
+#[tokio::test]
+async fn publish_version_must_increase() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let repository = env.create_repo(&owner, "repo", false).await;
+    env.send(
+        &[create_package_ix(&owner, false, repository, "pkg")],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+
+    env.send(
+        &[publish_ix(&owner, repository, "pkg", semver(1, 0, 0))],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+    env.send(
+        &[publish_ix(&owner, repository, "pkg", semver(1, 2, 0))],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+
+    let package: Package = env.account(pda::package("pkg")).await;
+    assert!(package.latest == Some(semver(1, 2, 0)));
+    assert_eq!(package.version_count, 2);
+    let version: PackageVersion = env
+        .account(pda::version(&pda::package("pkg"), &semver(1, 0, 0)))
+        .await;
+    assert_eq!(version.ipfs_cid, "QmPackage");
+
+    assert_error(
+        env.send(
+            &[publish_ix(&owner, repository, "pkg", semver(1, 1, 9))],
+            &[&owner],
+        )
+        .await,
+        VanishError::VersionNotIncreasing,
+    );
+
+    // A published version can never be replaced
+    assert_error(
+        env.send(
+            &[publish_ix(&owner, repository, "pkg", semver(1, 2, 0))],
+            &[&owner],
+        )
+        .await,
+        ACCOUNT_ALREADY_IN_USE,
+    );
+}
+
+#[tokio::test]
+async fn publish_version_from_the_owning_repository_only() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let rival = env.user().await;
+    let repository = env.create_repo(&owner, "repo", false).await;
+    let rival_repository = env.create_repo(&rival, "repo", false).await;
+    env.send(
+        &[create_package_ix(&owner, false, repository, "pkg")],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+
+    assert_error(
+        env.send(
+            &[publish_ix(&rival, rival_repository, "pkg", semver(9, 9, 9))],
+            &[&rival],
+        )
+        .await,
+        VanishError::Unauthorized,
+    );
+    assert_error(
+        env.send(
+            &[publish_ix(&rival, repository, "pkg", semver(9, 9, 9))],
+            &[&rival],
+        )
+        .await,
+        VanishError::Unauthorized,
+    );
+}
+
+#[tokio::test]
+async fn ci_attestors_post_check_runs() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let ci = env.user().await;
+    let impostor = env.user().await;
+    let repository = env.create_repo(&owner, "repo", false).await;
+
+    let add = ix(
+        accounts::AddCiAttestor {
+            authority: owner.pubkey(),
+            repository,
+            admin: None,
+            ci_attestor: pda::ci(&repository, &ci.pubkey()),
+            system_program: system_program(),
+        },
+        instruction::AddCiAttestor {
+            attestor: ci.pubkey(),
+        },
+    );
+    env.send(&[add], &[&owner]).await.unwrap();
+
+    let post = |attestor: &Keypair, commit: Multihash, status: CheckStatus| {
+        ix(
+            accounts::PostCheckRun {
+                attestor: attestor.pubkey(),
+                repository,
+                ci_attestor: pda::ci(&repository, &attestor.pubkey()),
+                check_run: pda::check(&repository, &commit, &attestor.pubkey()),
+                system_program: system_program(),
+            },
+            instruction::PostCheckRun {
+                commit,
+                status,
+                details_cid: "QmLogs".to_string(),
+            },
+        )
+    };
+
+    env.send(&[post(&ci, sha1(1), CheckStatus::Failure)], &[&ci])
+        .await
+        .unwrap();
+    env.send(&[post(&ci, sha1(1), CheckStatus::Success)], &[&ci])
+        .await
+        .unwrap();
+    let run: CheckRun = env
+        .account(pda::check(&repository, &sha1(1), &ci.pubkey()))
+        .await;
+    assert!(run.commit == sha1(1));
+    assert_eq!(run.attestor, ci.pubkey());
+    assert!(run.status == CheckStatus::Success);
+
+    assert_error(
+        env.send(
+            &[post(&impostor, sha1(1), CheckStatus::Success)],
+            &[&impostor],
+        )
+        .await,
+        AnchorError::AccountNotInitialized,
+    );
+    let sha512 = Multihash {
+        code: Multihash::SHA2_512,
+        digest: vec![1; 64],
+    };
+    assert_error(
+        env.send(&[post(&ci, sha512, CheckStatus::Success)], &[&ci])
+            .await,
+        VanishError::InvalidCommitHash,
+    );
+
+    let remove = ix(
+        accounts::RemoveCiAttestor {
+            authority: owner.pubkey(),
+            repository,
+            admin: None,
+            ci_attestor: pda::ci(&repository, &ci.pubkey()),
+        },
+        instruction::RemoveCiAttestor {},
+    );
+    env.send(&[remove], &[&owner]).await.unwrap();
+    assert_error(
+        env.send(&[post(&ci, sha1(2), CheckStatus::Success)], &[&ci])
+            .await,
+        AnchorError::AccountNotInitialized,
+    );
+
+    // Runs already posted stay
+    assert!(
+        env.exists(pda::check(&repository, &sha1(1), &ci.pubkey()))
+            .await
+    );
+}
+
+#[tokio::test]
+async fn ci_attestors_are_managed_by_admins() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let stranger = env.user().await;
+    let repository = env.create_repo(&owner, "repo", false).await;
+
+    let add = ix(
+        accounts::AddCiAttestor {
+            authority: stranger.pubkey(),
+            repository,
+            admin: None,
+            ci_attestor: pda::ci(&repository, &stranger.pubkey()),
+            system_program: system_program(),
+        },
+        instruction::AddCiAttestor {
+            attestor: stranger.pubkey(),
+        },
+    );
+    assert_error(
+        env.send(&[add], &[&stranger]).await,
+        VanishError::Unauthorized,
+    );
+
+    // The attestor account must be the one for the attestor being added
+    let add = ix(
+        accounts::AddCiAttestor {
+            authority: owner.pubkey(),
+            repository,
+            admin: None,
+            ci_attestor: pda::ci(&repository, &owner.pubkey()),
+            system_program: system_program(),
+        },
+        instruction::AddCiAttestor {
+            attestor: stranger.pubkey(),
+        },
+    );
+    assert_error(
+        env.send(&[add], &[&owner]).await,
+        AnchorError::ConstraintSeeds,
+    );
+}
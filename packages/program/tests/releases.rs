@@ -0,0 +1,641 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use common::*;
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::signature::{Keypair, Signer};
+use vanish_program::{
+    accounts, instruction, Attestation, Multihash, Release, ReleaseAsset, ReleasePolicy,
+    TimelockedAction,
+};
+
+fn policy_ix(
+    authority: &Keypair,
+    repository: Pubkey,
+    managers: Vec<Pubkey>,
+    threshold: u8,
+) -> Instruction {
+    ix(
+        accounts::SetReleasePolicy {
+            authority: authority.pubkey(),
+            repository,
+            admin: None,
+            release_policy: pda::release_policy(&repository),
+            timelock: pda::timelock(&repository),
+            pending_action: None,
+            system_program: system_program(),
+        },
+        instruction::SetReleasePolicy {
+            managers,
+            threshold,
+        },
+    )
+}
+
+fn create_ix(proposer: &Keypair, repository: Pubkey, tag: &str) -> Instruction {
+    ix(
+        accounts::CreateRelease {
+            proposer: proposer.pubkey(),
+            repository,
+            release_policy: pda::release_policy(&repository),
+            release: pda::release(&repository, tag),
+            system_program: system_program(),
+        },
+        instruction::CreateRelease {
+            tag: tag.to_string(),
+            commit: sha1(7),
+            ipfs_cid: "QmRelease".to_string(),
+        },
+    )
+}
+
+fn asset_ix(manager: &Keypair, repository: Pubkey, tag: &str, asset: ReleaseAsset) -> Instruction {
+    ix(
+        accounts::AddReleaseAsset {
+            manager: manager.pubkey(),
+            repository,
+            release_policy: pda::release_policy(&repository),
+            release: pda::release(&repository, tag),
+        },
+        instruction::AddReleaseAsset { asset },
+    )
+}
+
+fn approve_ix(manager: &Keypair, repository: Pubkey, tag: &str) -> Instruction {
+    let release = pda::release(&repository, tag);
+    ix(
+        accounts::ApproveRelease {
+            manager: manager.pubkey(),
+            repository,
+            release_policy: pda::release_policy(&repository),
+            release,
+            approval: pda::approval(&release, &manager.pubkey()),
+            system_program: system_program(),
+        },
+        instruction::ApproveRelease {},
+    )
+}
+
+fn asset(filename: &str, checksum: Multihash) -> ReleaseAsset {
+    ReleaseAsset {
+        filename: filename.to_string(),
+        size: 1024,
+        ipfs_cid: "QmAsset".to_string(),
+        checksum,
+    }
+}
+
+/// A repository with a 2-of-3 release policy
+async fn setup(env: &mut Env) -> (Keypair, Pubkey, [Keypair; 3]) {
+    let owner = env.user().await;
+    let managers = [env.user().await, env.user().await, env.user().await];
+    let repository = env.create_repo(&owner, "repo", false).await;
+    let keys = managers.iter().map(|m| m.pubkey()).collect();
+    env.send(&[policy_ix(&owner, repository, keys, 2)], &[&owner])
+        .await
+        .unwrap();
+    (owner, repository, managers)
+}
+
+#[tokio::test]
+async fn set_release_policy_validates_managers() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let stranger = env.user().await;
+    let repository = env.create_repo(&owner, "repo", false).await;
+    let (a, b) = (Keypair::new(), Keypair::new());
+    let (a, b, a_key, b_key) = (&a, &b, a.pubkey(), b.pubkey());
+
+    let invalid = [
+        (vec![], 1),
+        (vec![a_key, b_key], 0),
+        (vec![a_key, b_key], 3),
+        (vec![a_key, a_key], 1),
+        (
+            (0..=ReleasePolicy::MAX_MANAGERS)
+                .map(|_| Keypair::new().pubkey())
+                .collect(),
+            1,
+        ),
+    ];
+    for (managers, threshold) in invalid {
+        assert_error(
+            env.send(
+                &[policy_ix(&owner, repository, managers, threshold)],
+                &[&owner],
+            )
+            .await,
+            VanishError::InvalidReleasePolicy,
+        );
+    }
+
+    assert_error(
+        env.send(
+            &[policy_ix(&stranger, repository, vec![a_key], 1)],
+            &[&stranger],
+        )
+        .await,
+        VanishError::Unauthorized,
+    );
+
+    env.send(
+        &[policy_ix(&owner, repository, vec![a_key, b_key], 2)],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+
+    // Replacing it takes the threshold of the current managers
+    let replace = |signers: &[&Keypair]| {
+        let mut replace = policy_ix(&owner, repository, vec![b_key], 1);
+        replace.accounts.extend(
+            signers
+                .iter()
+                .map(|signer| AccountMeta::new_readonly(signer.pubkey(), true)),
+        );
+        replace
+    };
+    assert_error(
+        env.send(&[replace(&[])], &[&owner]).await,
+        VanishError::ReleasePolicyLocked,
+    );
+    assert_error(
+        env.send(&[replace(&[b])], &[&owner, b]).await,
+        VanishError::ReleasePolicyLocked,
+    );
+    env.send(&[replace(&[a, b])], &[&owner, a, b])
+        .await
+        .unwrap();
+    let policy: ReleasePolicy = env.account(pda::release_policy(&repository)).await;
+    assert_eq!(policy.managers, vec![b_key]);
+    assert_eq!(policy.threshold, 1);
+}
+
+#[tokio::test]
+async fn release_policies_can_be_replaced_through_the_timelock() {
+    let mut env = Env::new().await;
+    let (owner, repository, _) = setup(&mut env).await;
+    let successor = Keypair::new().pubkey();
+    let delay = 24 * 60 * 60;
+
+    let set_timelock = ix(
+        accounts::SetTimelock {
+            owner: owner.pubkey(),
+            repository,
+            timelock: pda::timelock(&repository),
+            pending_action: None,
+            system_program: system_program(),
+        },
+        instruction::SetTimelock { delay },
+    );
+    env.send(&[set_timelock], &[&owner]).await.unwrap();
+    let queue = |managers: Vec<Pubkey>| {
+        ix(
+            accounts::QueueAction {
+                owner: owner.pubkey(),
+                repository,
+                timelock: pda::timelock(&repository),
+                pending_action: pda::pending(&repository),
+                system_program: system_program(),
+            },
+            instruction::QueueAction {
+                action: TimelockedAction::SetReleasePolicy {
+                    policy: ReleasePolicy::digest(&managers, 1),
+                },
+            },
+        )
+    };
+    let mut replace = policy_ix(&owner, repository, vec![successor], 1);
+    replace.accounts[5].pubkey = pda::pending(&repository);
+    replace.accounts[5].is_writable = true;
+
+    // The queued policy must be the one being set, once the delay is over
+    env.send(&[queue(vec![owner.pubkey()])], &[&owner])
+        .await
+        .unwrap();
+    env.warp(delay).await;
+    assert_error(
+        env.send(&[replace.clone()], &[&owner]).await,
+        VanishError::ActionMismatch,
+    );
+    let cancel = ix(
+        accounts::CancelAction {
+            owner: owner.pubkey(),
+            repository,
+            pending_action: pda::pending(&repository),
+        },
+        instruction::CancelAction {},
+    );
+    env.send(&[cancel], &[&owner]).await.unwrap();
+
+    env.send(&[queue(vec![successor])], &[&owner])
+        .await
+        .unwrap();
+    assert_error(
+        env.send(&[replace.clone()], &[&owner]).await,
+        VanishError::TimelockPending,
+    );
+    env.warp(delay).await;
+    env.send(&[replace], &[&owner]).await.unwrap();
+    let policy: ReleasePolicy = env.account(pda::release_policy(&repository)).await;
+    assert_eq!(policy.managers, vec![successor]);
+    assert!(!env.exists(pda::pending(&repository)).await);
+}
+
+#[tokio::test]
+async fn create_release_by_managers_only() {
+    let mut env = Env::new().await;
+    let (owner, repository, managers) = setup(&mut env).await;
+
+    // Owning the repository does not make you a release manager
+    assert_error(
+        env.send(&[create_ix(&owner, repository, "v1.0.0")], &[&owner])
+            .await,
+        VanishError::NotReleaseManager,
+    );
+    assert_error(
+        env.send(&[create_ix(&managers[0], repository, "")], &[&managers[0]])
+            .await,
+        VanishError::InvalidReleaseTag,
+    );
+
+    env.send(
+        &[create_ix(&managers[0], repository, "v1.0.0")],
+        &[&managers[0]],
+    )
+    .await
+    .unwrap();
+    let release: Release = env.account(pda::release(&repository, "v1.0.0")).await;
+    assert_eq!(release.tag, "v1.0.0");
+    assert!(release.commit == sha1(7));
+    assert_eq!(release.proposer, managers[0].pubkey());
+    assert!(release.approvers.is_empty());
+    assert!(!release.is_final);
+
+    assert_error(
+        env.send(
+            &[create_ix(&managers[1], repository, "v1.0.0")],
+            &[&managers[1]],
+        )
+        .await,
+        ACCOUNT_ALREADY_IN_USE,
+    );
+}
+
+#[tokio::test]
+async fn create_release_needs_a_policy() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let repository = env.create_repo(&owner, "repo", false).await;
+
+    assert_error(
+        env.send(&[create_ix(&owner, repository, "v1")], &[&owner])
+            .await,
+        AnchorError::AccountNotInitialized,
+    );
+}
+
+#[tokio::test]
+async fn approvals_reach_threshold_and_finalize() {
+    let mut env = Env::new().await;
+    let (owner, repository, managers) = setup(&mut env).await;
+    env.send(
+        &[create_ix(&managers[0], repository, "v1")],
+        &[&managers[0]],
+    )
+    .await
+    .unwrap();
+
+    assert_error(
+        env.send(&[approve_ix(&owner, repository, "v1")], &[&owner])
+            .await,
+        VanishError::NotReleaseManager,
+    );
+
+    env.send(
+        &[approve_ix(&managers[0], repository, "v1")],
+        &[&managers[0]],
+    )
+    .await
+    .unwrap();
+    let release: Release = env.account(pda::release(&repository, "v1")).await;
+    assert_eq!(release.approvers, vec![managers[0].pubkey()]);
+    assert!(!release.is_final);
+
+    // One approval per manager
+    assert_error(
+        env.send(
+            &[approve_ix(&managers[0], repository, "v1")],
+            &[&managers[0]],
+        )
+        .await,
+        ACCOUNT_ALREADY_IN_USE,
+    );
+
+    env.send(
+        &[approve_ix(&managers[1], repository, "v1")],
+        &[&managers[1]],
+    )
+    .await
+    .unwrap();
+    let release: Release = env.account(pda::release(&repository, "v1")).await;
+    assert_eq!(release.approvers.len(), 2);
+    assert!(release.is_final);
+    assert!(release.finalized_at > 0);
+
+    assert_error(
+        env.send(
+            &[approve_ix(&managers[2], repository, "v1")],
+            &[&managers[2]],
+        )
+        .await,
+        VanishError::ReleaseAlreadyFinal,
+    );
+}
+
+#[tokio::test]
+async fn approvals_from_removed_managers_do_not_count() {
+    let mut env = Env::new().await;
+    let (owner, repository, managers) = setup(&mut env).await;
+    let newcomer = env.user().await;
+    env.send(
+        &[create_ix(&managers[0], repository, "v1")],
+        &[&managers[0]],
+    )
+    .await
+    .unwrap();
+    env.send(
+        &[approve_ix(&managers[0], repository, "v1")],
+        &[&managers[0]],
+    )
+    .await
+    .unwrap();
+
+    let mut replace = policy_ix(
+        &owner,
+        repository,
+        vec![managers[1].pubkey(), newcomer.pubkey()],
+        2,
+    );
+    for manager in &managers[1..] {
+        replace
+            .accounts
+            .push(AccountMeta::new_readonly(manager.pubkey(), true));
+    }
+    env.send(&[replace], &[&owner, &managers[1], &managers[2]])
+        .await
+        .unwrap();
+
+    env.send(
+        &[approve_ix(&managers[1], repository, "v1")],
+        &[&managers[1]],
+    )
+    .await
+    .unwrap();
+    let release: Release = env.account(pda::release(&repository, "v1")).await;
+    assert_eq!(release.approvers, vec![managers[1].pubkey()]);
+    assert!(!release.is_final);
+
+    env.send(&[approve_ix(&newcomer, repository, "v1")], &[&newcomer])
+        .await
+        .unwrap();
+    let release: Release = env.account(pda::release(&repository, "v1")).await;
+    assert!(release.is_final);
+}
+
+#[tokio::test]
+async fn release_assets_lock_at_first_approval() {
+    let mut env = Env::new().await;
+    let (owner, repository, managers) = setup(&mut env).await;
+    let manager = &managers[0];
+    env.send(&[create_ix(manager, repository, "v1")], &[manager])
+        .await
+        .unwrap();
+
+    env.send(
+        &[asset_ix(
+            manager,
+            repository,
+            "v1",
+            asset("app.tar.gz", sha256(1)),
+        )],
+        &[manager],
+    )
+    .await
+    .unwrap();
+
+    let invalid = [
+        (asset("", sha256(2)), VanishError::InvalidReleaseAsset),
+        (
+            asset("app.tar.gz", sha256(2)),
+            VanishError::InvalidReleaseAsset,
+        ),
+        (
+            ReleaseAsset {
+                ipfs_cid: String::new(),
+                ..asset("app.zip", sha256(2))
+            },
+            VanishError::InvalidReleaseAsset,
+        ),
+        (
+            asset(
+                "app.zip",
+                Multihash {
+                    code: 0x99,
+                    digest: vec![0; 32],
+                },
+            ),
+            VanishError::InvalidMultihash,
+        ),
+    ];
+    for (asset, error) in invalid {
+        assert_error(
+            env.send(&[asset_ix(manager, repository, "v1", asset)], &[manager])
+                .await,
+            error,
+        );
+    }
+
+    assert_error(
+        env.send(
+            &[asset_ix(
+                &owner,
+                repository,
+                "v1",
+                asset("evil.sh", sha256(3)),
+            )],
+            &[&owner],
+        )
+        .await,
+        VanishError::NotReleaseManager,
+    );
+
+    env.send(&[approve_ix(manager, repository, "v1")], &[manager])
+        .await
+        .unwrap();
+    assert_error(
+        env.send(
+            &[asset_ix(
+                manager,
+                repository,
+                "v1",
+                asset("late.zip", sha256(4)),
+            )],
+            &[manager],
+        )
+        .await,
+        VanishError::ReleaseLocked,
+    );
+
+    let release: Release = env.account(pda::release(&repository, "v1")).await;
+    assert_eq!(release.assets.len(), 1);
+    assert_eq!(release.assets[0].filename, "app.tar.gz");
+}
+
+#[tokio::test]
+async fn release_assets_are_capped() {
+    let mut env = Env::new().await;
+    let (_, repository, managers) = setup(&mut env).await;
+    let manager = &managers[0];
+    env.send(&[create_ix(manager, repository, "v1")], &[manager])
+        .await
+        .unwrap();
+
+    for i in 0..Release::MAX_ASSETS {
+        let asset = asset(&format!("asset-{i}"), sha256(i as u8));
+        env.send(&[asset_ix(manager, repository, "v1", asset)], &[manager])
+            .await
+            .unwrap();
+    }
+    assert_error(
+        env.send(
+            &[asset_ix(
+                manager,
+                repository,
+                "v1",
+                asset("one-more", sha256(99)),
+            )],
+            &[manager],
+        )
+        .await,
+        VanishError::TooManyReleaseAssets,
+    );
+}
+
+#[tokio::test]
+async fn builders_attest_release_assets() {
+    let mut env = Env::new().await;
+    let (owner, repository, managers) = setup(&mut env).await;
+    let manager = &managers[0];
+    let builder = env.user().await;
+    let impostor = env.user().await;
+    env.send(&[create_ix(manager, repository, "v1")], &[manager])
+        .await
+        .unwrap();
+    env.send(
+        &[asset_ix(
+            manager,
+            repository,
+            "v1",
+            asset("app.tar.gz", sha256(1)),
+        )],
+        &[manager],
+    )
+    .await
+    .unwrap();
+
+    let add = |authority: &Keypair| {
+        ix(
+            accounts::AddBuilder {
+                authority: authority.pubkey(),
+                repository,
+                admin: None,
+                builder_account: pda::builder(&repository, &builder.pubkey()),
+                system_program: system_program(),
+            },
+            instruction::AddBuilder {
+                builder: builder.pubkey(),
+            },
+        )
+    };
+    assert_error(
+        env.send(&[add(manager)], &[manager]).await,
+        VanishError::Unauthorized,
+    );
+    env.send(&[add(&owner)], &[&owner]).await.unwrap();
+
+    let release = pda::release(&repository, "v1");
+    let attest = |signer: &Keypair, checksum: Multihash, provenance_cid: &str| {
+        ix(
+            accounts::AttestBuild {
+                builder: signer.pubkey(),
+                repository,
+                builder_account: pda::builder(&repository, &signer.pubkey()),
+                release,
+                attestation: pda::attestation(&release, &checksum, &signer.pubkey()),
+                system_program: system_program(),
+            },
+            instruction::AttestBuild {
+                checksum,
+                provenance_cid: provenance_cid.to_string(),
+            },
+        )
+    };
+
+    assert_error(
+        env.send(
+            &[attest(&impostor, sha256(1), "QmProvenance")],
+            &[&impostor],
+        )
+        .await,
+        AnchorError::AccountNotInitialized,
+    );
+    assert_error(
+        env.send(&[attest(&builder, sha256(2), "QmProvenance")], &[&builder])
+            .await,
+        VanishError::UnknownReleaseAsset,
+    );
+    assert_error(
+        env.send(&[attest(&builder, sha256(1), "")], &[&builder])
+            .await,
+        VanishError::InvalidIpfsCid,
+    );
+
+    env.send(&[attest(&builder, sha256(1), "QmProvenance")], &[&builder])
+        .await
+        .unwrap();
+    let attestation: Attestation = env
+        .account(pda::attestation(&release, &sha256(1), &builder.pubkey()))
+        .await;
+    assert_eq!(attestation.release, release);
+    assert_eq!(attestation.builder, builder.pubkey());
+    assert_eq!(attestation.provenance_cid, "QmProvenance");
+
+    // Removed builders can no longer attest
+    let remove = ix(
+        accounts::RemoveBuilder {
+            authority: owner.pubkey(),
+            repository,
+            admin: None,
+            builder_account: pda::builder(&repository, &builder.pubkey()),
+        },
+        instruction::RemoveBuilder {},
+    );
+    env.send(&[remove], &[&owner]).await.unwrap();
+    env.send(
+        &[asset_ix(
+            manager,
+            repository,
+            "v1",
+            asset("app.zip", sha256(3)),
+        )],
+        &[manager],
+    )
+    .await
+    .unwrap();
+    assert_error(
+        env.send(&[attest(&builder, sha256(3), "QmProvenance")], &[&builder])
+            .await,
+        AnchorError::AccountNotInitialized,
+    );
+}
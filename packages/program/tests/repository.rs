@@ -0,0 +1,4588 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::hash::hash;
+use anchor_lang::{AccountSerialize, AnchorSerialize, Discriminator};
+use common::*;
+use solana_sdk::account::Account;
+use solana_sdk::clock::Clock;
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::system_instruction;
+use vanish_program::{
+    accounts, compression, instruction, refs_tree, repo_flags, snapshot_tree, ActivityCursor,
+    AuditAction, AuditEntry, Branch, Cid, Collaborator, Contribution, ContributorSnapshot,
+    DescriptionStorage, ForkLink, HeadUpdate, History, List, ListEntry, Multihash, NameStake,
+    PinAttestation, Pinner, Profile, PurgeReceipt, PushEntry, PushLog, PushNote, ReclaimNotice,
+    RecoveryPlan, Redirect, RefUpdate, RepoName, Repository, RewardPool, Star, Stats, Submodule,
+    Timelock, TimelockedAction, Tombstone,
+};
+
+fn create_ix(owner: Pubkey, name: &str, description: &str) -> Instruction {
+    ix(
+        accounts::CreateRepo {
+            owner,
+            repository: pda::repo(&owner, name),
+            tombstone: pda::tombstone(&pda::repo(&owner, name)),
+            name_lock: pda::name_lock(&owner, name),
+            name_stake: None,
+            profile: pda::profile(&owner),
+            config: pda::config(),
+            treasury: pda::treasury(),
+            system_program: system_program(),
+        },
+        instruction::CreateRepo {
+            name: name.to_string(),
+            description: description.to_string(),
+            is_private: false,
+        },
+    )
+}
+
+fn set_flags_ix(
+    authority: Pubkey,
+    admin: Option<Pubkey>,
+    repository: Pubkey,
+    flags: u8,
+    enabled: bool,
+) -> Instruction {
+    ix(
+        accounts::SetRepoFlags {
+            authority,
+            repository,
+            admin,
+        },
+        instruction::SetRepoFlags { flags, enabled },
+    )
+}
+
+fn delete_ix(
+    owner: &Keypair,
+    repository: Pubkey,
+    recovery_window: i64,
+    audit_entry: Pubkey,
+) -> Instruction {
+    ix(
+        accounts::DeleteRepo {
+            owner: owner.pubkey(),
+            repository,
+            timelock: pda::timelock(&repository),
+            pending_action: None,
+            audit_entry,
+            system_program: system_program(),
+        },
+        instruction::DeleteRepo { recovery_window },
+    )
+}
+
+fn purge_ix(owner: &Keypair, repository: Pubkey, children: &[(Pubkey, Pubkey)]) -> Instruction {
+    let mut purge = ix(
+        accounts::PurgeRepo {
+            owner: owner.pubkey(),
+            repository,
+            tombstone: pda::tombstone(&repository),
+            receipt: pda::purge_receipt(&repository),
+            profile: Some(pda::profile(&owner.pubkey())),
+            system_program: system_program(),
+        },
+        instruction::PurgeRepo {},
+    );
+    for (child, refund) in children {
+        purge.accounts.push(AccountMeta::new(*child, false));
+        purge.accounts.push(AccountMeta::new(*refund, false));
+    }
+    purge
+}
+
+const DAY: i64 = 24 * 60 * 60;
+
+#[tokio::test]
+async fn create_repo_initializes_state() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+
+    let address = env.create_repo(&owner, "my-repo", true).await;
+
+    let repo: Repository = env.account(address).await;
+    assert_eq!(repo.owner, owner.pubkey());
+    assert_eq!(repo.creator, owner.pubkey());
+    assert_eq!(repo.name, "my-repo");
+    assert_eq!(repo.description, "A test repository");
+    assert!(repo.is_private);
+    assert_eq!(repo.flags, 0);
+    assert!(repo.head_commit == Multihash::default());
+    assert_eq!(repo.stars, 0);
+    assert_eq!(repo.admin_count, 0);
+    assert_eq!(repo.id, address);
+}
+
+#[tokio::test]
+async fn create_repo_validates_inputs() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+
+    let empty = create_ix(owner.pubkey(), "", "");
+    assert_error(env.send(&[empty], &[&owner]).await, VanishError::NameEmpty);
+
+    let long_description = create_ix(owner.pubkey(), "repo", &"d".repeat(257));
+    assert_error(
+        env.send(&[long_description], &[&owner]).await,
+        VanishError::DescriptionTooLong,
+    );
+
+    for name in [".hidden", "a/b", "tab\tname", "caf\u{e9}"] {
+        assert_error(
+            env.send(&[create_ix(owner.pubkey(), name, "")], &[&owner])
+                .await,
+            VanishError::InvalidRepoName,
+        );
+    }
+
+    env.create_repo(&owner, "repo", false).await;
+    let duplicate = create_ix(owner.pubkey(), "repo", "");
+    assert_error(
+        env.send(&[duplicate], &[&owner]).await,
+        ACCOUNT_ALREADY_IN_USE,
+    );
+
+    // Names differing only in case share a lock
+    let shouting = create_ix(owner.pubkey(), "REPO", "");
+    assert_error(
+        env.send(&[shouting], &[&owner]).await,
+        ACCOUNT_ALREADY_IN_USE,
+    );
+}
+
+#[tokio::test]
+async fn create_from_template_copies_the_head() {
+    let mut env = Env::new().await;
+    let author = env.user().await;
+    let user = env.user().await;
+    let template = env.create_repo(&author, "starter", false).await;
+    let private = env.create_repo(&author, "private", true).await;
+    for repository in [template, private] {
+        env.send(
+            &[push_ix(author.pubkey(), repository, None, sha1(1))],
+            &[&author],
+        )
+        .await
+        .unwrap();
+        let flag = set_flags_ix(
+            author.pubkey(),
+            None,
+            repository,
+            repo_flags::TEMPLATE,
+            true,
+        );
+        env.send(&[flag], &[&author]).await.unwrap();
+    }
+
+    let create = |template: Pubkey| {
+        ix(
+            accounts::CreateFromTemplate {
+                owner: user.pubkey(),
+                repository: pda::repo(&user.pubkey(), "app"),
+                tombstone: pda::tombstone(&pda::repo(&user.pubkey(), "app")),
+                name_lock: pda::name_lock(&user.pubkey(), "app"),
+                name_stake: None,
+                profile: pda::profile(&user.pubkey()),
+                config: pda::config(),
+                treasury: pda::treasury(),
+                template,
+                system_program: system_program(),
+            },
+            instruction::CreateFromTemplate {
+                name: "app".to_string(),
+                description: String::new(),
+            },
+        )
+    };
+
+    // Private repositories cannot be shared this way, flagged or not
+    assert_error(
+        env.send(&[create(private)], &[&user]).await,
+        VanishError::NotATemplate,
+    );
+    let unflag = set_flags_ix(author.pubkey(), None, template, repo_flags::TEMPLATE, false);
+    env.send(&[unflag], &[&author]).await.unwrap();
+    assert_error(
+        env.send(&[create(template)], &[&user]).await,
+        VanishError::NotATemplate,
+    );
+    let flag = set_flags_ix(author.pubkey(), None, template, repo_flags::TEMPLATE, true);
+    env.send(&[flag], &[&author]).await.unwrap();
+
+    env.send(&[create(template)], &[&user]).await.unwrap();
+    let repo: Repository = env.account(pda::repo(&user.pubkey(), "app")).await;
+    let source: Repository = env.account(template).await;
+    assert_eq!(repo.owner, user.pubkey());
+    assert_eq!(repo.flags, 0);
+    assert!(!repo.is_private);
+    assert!(repo.head_commit == sha1(1));
+    assert!(repo.ipfs_cid == source.ipfs_cid);
+    assert_eq!(repo.template, Some(template));
+    let profile: Profile = env.account(pda::profile(&user.pubkey())).await;
+    assert_eq!(profile.repo_count, 1);
+}
+
+#[tokio::test]
+async fn create_repo_rejects_address_of_another_name() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+
+    let mut create = create_ix(owner.pubkey(), "repo", "");
+    create.accounts[1].pubkey = pda::repo(&owner.pubkey(), "other");
+    assert_error(
+        env.send(&[create], &[&owner]).await,
+        AnchorError::ConstraintSeeds,
+    );
+}
+
+#[tokio::test]
+async fn push_update_by_owner_and_writers() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let writer = env.user().await;
+    let reader = env.user().await;
+    let stranger = env.user().await;
+    let repository = env.create_repo(&owner, "repo", false).await;
+    env.add_collaborator(&owner, repository, writer.pubkey(), true, false)
+        .await;
+    env.add_collaborator(&owner, repository, reader.pubkey(), false, false)
+        .await;
+
+    let push = push_ix(owner.pubkey(), repository, None, sha1(1));
+    env.send(&[push], &[&owner]).await.unwrap();
+    let repo: Repository = env.account(repository).await;
+    assert!(repo.head_commit == sha1(1));
+    assert_eq!(repo.ipfs_cid.to_string(), PUSH_CID);
+
+    let push = push_onto_ix(
+        writer.pubkey(),
+        repository,
+        Some(pda::collab(&repository, &writer.pubkey())),
+        Some(sha1(1)),
+        sha256(2),
+    );
+    env.send(&[push], &[&writer]).await.unwrap();
+    let repo: Repository = env.account(repository).await;
+    assert!(repo.head_commit == sha256(2));
+    let collab: vanish_program::Collaborator = env
+        .account(pda::collab(&repository, &writer.pubkey()))
+        .await;
+    assert!(collab.last_push_at > 0);
+
+    let push = push_ix(
+        reader.pubkey(),
+        repository,
+        Some(pda::collab(&repository, &reader.pubkey())),
+        sha1(3),
+    );
+    assert_error(
+        env.send(&[push], &[&reader]).await,
+        VanishError::Unauthorized,
+    );
+
+    let push = push_ix(stranger.pubkey(), repository, None, sha1(3));
+    assert_error(
+        env.send(&[push], &[&stranger]).await,
+        VanishError::Unauthorized,
+    );
+}
+
+#[tokio::test]
+async fn push_update_validates_inputs() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let repository = env.create_repo(&owner, "repo", false).await;
+
+    let truncated = Multihash {
+        code: Multihash::SHA1,
+        digest: vec![1; 19],
+    };
+    let push = push_ix(owner.pubkey(), repository, None, truncated);
+    assert_error(
+        env.send(&[push], &[&owner]).await,
+        VanishError::InvalidCommitHash,
+    );
+
+    let blake3 = Multihash {
+        code: Multihash::BLAKE3,
+        digest: vec![1; 32],
+    };
+    let push = push_ix(owner.pubkey(), repository, None, blake3);
+    assert_error(
+        env.send(&[push], &[&owner]).await,
+        VanishError::InvalidCommitHash,
+    );
+
+    let push = ix(
+        accounts::PushUpdate {
+            pusher: owner.pubkey(),
+            repository,
+            collaborator_account: None,
+            contribution: pda::contribution(&repository, &owner.pubkey()),
+            push_log: None,
+            project_repo: pda::project_repo(&repository),
+            project: None,
+            project_member: None,
+            badge_config: None,
+            badge_mint: None,
+            badge_token_account: None,
+            token_2022_program: None,
+            system_program: system_program(),
+        },
+        instruction::PushUpdate {
+            head_commit: sha1(1),
+            // Claims a 32-byte digest it does not have
+            ipfs_cid: Cid::from_bytes(&[1, 0x55, 0x12, 0x20]).unwrap(),
+            expected_head: None,
+            co_authors: vec![],
+            note: None,
+            binding: None,
+        },
+    );
+    assert_error(
+        env.send(&[push], &[&owner]).await,
+        VanishError::InvalidIpfsCid,
+    );
+}
+
+#[test]
+fn cids_round_trip_through_text() {
+    let v1 = "bafkreigra7vdmkodezyyq5xqonlkjdpke3ysg5bprp2ezz27r6afz6ndpu";
+    for text in [PUSH_CID, v1, ""] {
+        let cid = Cid::parse(text).unwrap();
+        assert!(cid.is_valid());
+        assert_eq!(cid.to_string(), text);
+    }
+
+    // CIDv0 is a bare multihash, CIDv1 adds a version and codec
+    let v0 = Cid::parse(PUSH_CID).unwrap();
+    assert_eq!(v0.as_bytes().len(), 34);
+    assert_eq!(Cid::parse(v1).unwrap().as_bytes()[..2], [1, 0x55]);
+    // CIDv1 is printed in base32 whatever base it was read from
+    let z = Cid::parse("zb2rhkiFQGkczzmeEFdfcXeSRAAFiwAMnaDJD7y6EJmbVVhcG").unwrap();
+    assert_eq!(z.to_string(), v1);
+
+    for text in [
+        "QmPushCid",
+        "bafy!",
+        "mAXASIA",
+        &format!("b{}", "a".repeat(70)),
+    ] {
+        assert!(Cid::parse(text).is_none(), "{text}");
+    }
+}
+
+#[tokio::test]
+async fn push_update_rejects_collaborator_account_of_another_user() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let writer = env.user().await;
+    let stranger = env.user().await;
+    let repository = env.create_repo(&owner, "repo", false).await;
+    env.add_collaborator(&owner, repository, writer.pubkey(), true, false)
+        .await;
+
+    let push = push_ix(
+        stranger.pubkey(),
+        repository,
+        Some(pda::collab(&repository, &writer.pubkey())),
+        sha1(1),
+    );
+    assert_error(
+        env.send(&[push], &[&stranger]).await,
+        AnchorError::ConstraintSeeds,
+    );
+}
+
+#[tokio::test]
+async fn push_log_records_recent_pushes() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let stranger = env.user().await;
+    let repository = env.create_repo(&owner, "repo", false).await;
+
+    let init = |authority: &Keypair| {
+        ix(
+            accounts::InitPushLog {
+                authority: authority.pubkey(),
+                repository,
+                admin: None,
+                push_log: pda::push_log(&repository),
+                system_program: system_program(),
+            },
+            instruction::InitPushLog {},
+        )
+    };
+    assert_error(
+        env.send(&[init(&stranger)], &[&stranger]).await,
+        VanishError::Unauthorized,
+    );
+    env.send(&[init(&owner)], &[&owner]).await.unwrap();
+
+    let push = |push_log: Pubkey, commit: u8, note: Option<PushNote>| {
+        let expected_head = commit.checked_sub(1).map(sha1);
+        ix(
+            accounts::PushUpdate {
+                pusher: owner.pubkey(),
+                repository,
+                collaborator_account: None,
+                contribution: pda::contribution(&repository, &owner.pubkey()),
+                push_log: Some(push_log),
+                project_repo: pda::project_repo(&repository),
+                project: None,
+                project_member: None,
+                badge_config: None,
+                badge_mint: None,
+                badge_token_account: None,
+                token_2022_program: None,
+                system_program: system_program(),
+            },
+            instruction::PushUpdate {
+                head_commit: sha1(commit),
+                ipfs_cid: Cid::parse(PUSH_CID).unwrap(),
+                expected_head,
+                co_authors: vec![],
+                note,
+                binding: None,
+            },
+        )
+    };
+    let note = |changelog_cid: &str, message: &str| {
+        Some(PushNote {
+            changelog_cid: changelog_cid.to_string(),
+            message: message.to_string(),
+        })
+    };
+
+    for invalid in [
+        note("", ""),
+        note(&"Q".repeat(PushEntry::MAX_CHANGELOG_CID_LEN + 1), ""),
+        note("", &"m".repeat(PushEntry::MAX_MESSAGE_LEN + 1)),
+        note("", "nul\0byte"),
+    ] {
+        assert_error(
+            env.send(&[push(pda::push_log(&repository), 0, invalid)], &[&owner])
+                .await,
+            VanishError::InvalidPushNote,
+        );
+    }
+    env.send(
+        &[push(
+            pda::push_log(&repository),
+            0,
+            note("QmChangelog", "Fix the parser"),
+        )],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+    let log: PushLog = env.load(pda::push_log(&repository)).await;
+    assert_eq!(log.repository, repository);
+    assert_eq!(log.push_count, 1);
+    assert_eq!(log.entries[0].pusher, owner.pubkey());
+    assert!(log.entries[0].commit() == sha1(0));
+    assert_eq!(log.entries[0].changelog_cid(), "QmChangelog");
+    assert_eq!(log.entries[0].message(), "Fix the parser");
+
+    // Once full, the oldest entry is overwritten
+    for commit in 1..=PushLog::CAPACITY as u8 {
+        env.send(&[push(pda::push_log(&repository), commit, None)], &[&owner])
+            .await
+            .unwrap();
+    }
+    let log: PushLog = env.load(pda::push_log(&repository)).await;
+    assert_eq!(log.push_count, PushLog::CAPACITY as u64 + 1);
+    assert!(log.entries[0].commit() == sha1(PushLog::CAPACITY as u8));
+    assert_eq!(log.entries[0].changelog_cid(), "");
+    assert_eq!(log.entries[0].message(), "");
+    assert!(log.entries[1].commit() == sha1(1));
+
+    // Another repository's log is rejected
+    let other = env.create_repo(&owner, "other", false).await;
+    let init_other = ix(
+        accounts::InitPushLog {
+            authority: owner.pubkey(),
+            repository: other,
+            admin: None,
+            push_log: pda::push_log(&other),
+            system_program: system_program(),
+        },
+        instruction::InitPushLog {},
+    );
+    env.send(&[init_other], &[&owner]).await.unwrap();
+    assert_error(
+        env.send(&[push(pda::push_log(&other), 0, None)], &[&owner])
+            .await,
+        AnchorError::ConstraintSeeds,
+    );
+}
+
+#[tokio::test]
+async fn migrate_account_widens_push_log_entries() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let repository = env.create_repo(&owner, "repo", false).await;
+    let push_log = pda::push_log(&repository);
+    let mut push = push_ix(owner.pubkey(), repository, None, sha1(1));
+    push.accounts[4] = AccountMeta::new(push_log, false);
+    env.send(
+        &[
+            ix(
+                accounts::InitPushLog {
+                    authority: owner.pubkey(),
+                    repository,
+                    admin: None,
+                    push_log,
+                    system_program: system_program(),
+                },
+                instruction::InitPushLog {},
+            ),
+            push,
+        ],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+
+    // Roll the log back to entries without push notes
+    let data = env.data(push_log).await;
+    let (header, entries) = data.split_at(PushLog::SPACE - PushEntry::SPACE * PushLog::CAPACITY);
+    let mut old = header.to_vec();
+    for entry in entries.chunks(PushEntry::SPACE) {
+        old.extend_from_slice(&entry[..PushEntry::V0_SPACE]);
+    }
+    assert_eq!(old.len(), PushLog::V0_SPACE);
+    env.set_data(push_log, old).await;
+
+    let migrate = ix(
+        accounts::MigrateAccount {
+            payer: owner.pubkey(),
+            account: push_log,
+            system_program: system_program(),
+        },
+        instruction::MigrateAccount {},
+    );
+    env.send(std::slice::from_ref(&migrate), &[&owner])
+        .await
+        .unwrap();
+    assert_eq!(env.data_len(push_log).await, PushLog::SPACE);
+    assert_eq!(env.lamports(push_log).await, env.rent(PushLog::SPACE).await);
+    let log: PushLog = env.load(push_log).await;
+    assert_eq!(log.repository, repository);
+    assert_eq!(log.push_count, 1);
+    assert_eq!(log.entries[0].pusher, owner.pubkey());
+    assert!(log.entries[0].commit() == sha1(1));
+    assert_eq!(log.entries[0].message(), "");
+
+    assert_error(
+        env.send(&[migrate], &[&owner]).await,
+        VanishError::AccountUpToDate,
+    );
+}
+
+#[tokio::test]
+async fn pushes_are_counted_as_contributions() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let writer = env.user().await;
+    let repository = env.create_repo(&owner, "repo", false).await;
+    env.add_collaborator(&owner, repository, writer.pubkey(), true, false)
+        .await;
+
+    env.send(
+        &[push_ix(owner.pubkey(), repository, None, sha1(1))],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+    env.send(
+        &[push_onto_ix(
+            owner.pubkey(),
+            repository,
+            None,
+            Some(sha1(1)),
+            sha1(2),
+        )],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+    let mut branch = push_branch_ix(
+        writer.pubkey(),
+        repository,
+        "dev",
+        None,
+        sha1(3),
+        lone_ref_proof(),
+    );
+    branch.accounts[2] = AccountMeta::new(pda::collab(&repository, &writer.pubkey()), false);
+    env.send(&[branch], &[&writer]).await.unwrap();
+
+    let contribution: Contribution = env
+        .account(pda::contribution(&repository, &owner.pubkey()))
+        .await;
+    assert_eq!(contribution.repository, repository);
+    assert_eq!(contribution.contributor, owner.pubkey());
+    assert_eq!(contribution.push_count, 2);
+    assert!(contribution.first_contributed_at > 0);
+    assert!(contribution.last_contributed_at >= contribution.first_contributed_at);
+    let contribution: Contribution = env
+        .account(pda::contribution(&repository, &writer.pubkey()))
+        .await;
+    assert_eq!(contribution.contributor, writer.pubkey());
+    assert_eq!(contribution.push_count, 1);
+
+    // Rejected pushes count for nothing
+    assert_error(
+        env.send(
+            &[push_ix(owner.pubkey(), repository, None, sha1(4))],
+            &[&owner],
+        )
+        .await,
+        VanishError::ForcePushNotAllowed,
+    );
+    let contribution: Contribution = env
+        .account(pda::contribution(&repository, &owner.pubkey()))
+        .await;
+    assert_eq!(contribution.push_count, 2);
+
+    // Nor can a pusher credit someone else
+    let mut push = push_onto_ix(owner.pubkey(), repository, None, Some(sha1(2)), sha1(4));
+    push.accounts[3].pubkey = pda::contribution(&repository, &writer.pubkey());
+    assert_error(
+        env.send(&[push], &[&owner]).await,
+        AnchorError::ConstraintSeeds,
+    );
+}
+
+#[tokio::test]
+async fn contributor_snapshots_freeze_a_merkle_root() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let writer = env.user().await;
+    let fan = env.user().await;
+    let taker = env.user().await;
+    let repository = env.create_repo(&owner, "repo", false).await;
+    let other = env.create_repo(&owner, "other", false).await;
+    env.add_collaborator(&owner, repository, writer.pubkey(), true, false)
+        .await;
+    env.send(
+        &[push_ix(owner.pubkey(), repository, None, sha1(1))],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+    for repository in [repository, other] {
+        let star = ix(
+            accounts::StarRepo {
+                user: fan.pubkey(),
+                repository,
+                star_account: pda::star(&fan.pubkey(), &repository),
+                donation_vault: pda::donations(&repository),
+                profile: pda::profile(&fan.pubkey()),
+                config: pda::config(),
+                system_program: system_program(),
+            },
+            instruction::StarRepo { tip: 0 },
+        );
+        env.send(&[star], &[&fan]).await.unwrap();
+    }
+
+    let clock: Clock = env.ctx.banks_client.get_sysvar().await.unwrap();
+    let snapshot = |slot: u64, accounts: &[Pubkey]| {
+        let mut snapshot = ix(
+            accounts::SnapshotContributors {
+                taker: taker.pubkey(),
+                repository,
+                snapshot: pda::snapshot(&repository, &taker.pubkey(), slot),
+                system_program: system_program(),
+            },
+            instruction::SnapshotContributors { slot },
+        );
+        snapshot.accounts.extend(
+            accounts
+                .iter()
+                .map(|account| AccountMeta::new_readonly(*account, false)),
+        );
+        snapshot
+    };
+    let mut accounts = vec![
+        pda::contribution(&repository, &owner.pubkey()),
+        pda::star(&fan.pubkey(), &repository),
+        pda::collab(&repository, &writer.pubkey()),
+    ];
+    accounts.sort();
+
+    for slot in [clock.slot + 10, clock.slot.saturating_sub(1)] {
+        assert_error(
+            env.send(&[snapshot(slot, &accounts)], &[&taker]).await,
+            VanishError::SnapshotNotDue,
+        );
+    }
+    let reversed: Vec<_> = accounts.iter().rev().copied().collect();
+    assert_error(
+        env.send(&[snapshot(clock.slot, &reversed)], &[&taker])
+            .await,
+        VanishError::InvalidSnapshotAccounts,
+    );
+    // Another repository's star does not count
+    let mut foreign = accounts.clone();
+    foreign.push(pda::star(&fan.pubkey(), &other));
+    foreign.sort();
+    assert_error(
+        env.send(&[snapshot(clock.slot, &foreign)], &[&taker]).await,
+        VanishError::InvalidSnapshotAccounts,
+    );
+    // Nor can a star or a collaborator be left out
+    for left_out in [
+        pda::star(&fan.pubkey(), &repository),
+        pda::collab(&repository, &writer.pubkey()),
+    ] {
+        let subset: Vec<_> = accounts
+            .iter()
+            .copied()
+            .filter(|account| *account != left_out)
+            .collect();
+        assert_error(
+            env.send(&[snapshot(clock.slot, &subset)], &[&taker]).await,
+            VanishError::InvalidSnapshotAccounts,
+        );
+    }
+    // A collaborator still in the baseline layout is not read field by
+    // field: its `added_at` would pass for `is_admin`
+    let collab_address = pda::collab(&repository, &writer.pubkey());
+    let current = env.data(collab_address).await;
+    let collab: Collaborator = env.account(collab_address).await;
+    let mut baseline = Collaborator::DISCRIMINATOR.to_vec();
+    (
+        repository,
+        writer.pubkey(),
+        true,
+        0x6553_f101i64,
+        collab.bump,
+    )
+        .serialize(&mut baseline)
+        .unwrap();
+    env.set_data(collab_address, baseline).await;
+    assert_error(
+        env.send(&[snapshot(clock.slot, &accounts)], &[&taker])
+            .await,
+        VanishError::InvalidSnapshotAccounts,
+    );
+    env.set_data(collab_address, current).await;
+
+    env.send(&[snapshot(clock.slot, &accounts)], &[&taker])
+        .await
+        .unwrap();
+    let taken: ContributorSnapshot = env
+        .account(pda::snapshot(&repository, &taker.pubkey(), clock.slot))
+        .await;
+    assert_eq!(taken.taker, taker.pubkey());
+    assert_eq!(
+        (taken.contributions, taken.stars, taken.collaborators),
+        (1, 1, 1)
+    );
+    let leaves: Vec<_> = accounts
+        .iter()
+        .map(|account| {
+            if *account == pda::star(&fan.pubkey(), &repository) {
+                snapshot_tree::star(&fan.pubkey())
+            } else if *account == pda::collab(&repository, &writer.pubkey()) {
+                snapshot_tree::collaborator(&writer.pubkey(), true, false)
+            } else {
+                snapshot_tree::contribution(&owner.pubkey(), 1)
+            }
+        })
+        .collect();
+    assert_eq!(taken.root, refs_tree::root(&leaves));
+    let proof = refs_tree::proof(&leaves, 1);
+    assert_eq!(refs_tree::root_with(leaves[1], 1, &proof), taken.root);
+
+    let close = |user: &Keypair| {
+        ix(
+            accounts::CloseSnapshot {
+                taker: user.pubkey(),
+                snapshot: pda::snapshot(&repository, &taker.pubkey(), clock.slot),
+            },
+            instruction::CloseSnapshot {},
+        )
+    };
+    assert!(env.send(&[close(&fan)], &[&fan]).await.is_err());
+    env.send(&[close(&taker)], &[&taker]).await.unwrap();
+}
+
+#[tokio::test]
+async fn co_authors_are_credited_with_the_push() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let pair = env.user().await;
+    let repository = env.create_repo(&owner, "repo", false).await;
+    let guest = Keypair::new().pubkey();
+
+    let push = |co_authors: Vec<Pubkey>, accounts: Vec<Pubkey>, commit: u8| {
+        let mut push = ix(
+            accounts::PushUpdate {
+                pusher: owner.pubkey(),
+                repository,
+                collaborator_account: None,
+                contribution: pda::contribution(&repository, &owner.pubkey()),
+                push_log: None,
+                project_repo: pda::project_repo(&repository),
+                project: None,
+                project_member: None,
+                badge_config: None,
+                badge_mint: None,
+                badge_token_account: None,
+                token_2022_program: None,
+                system_program: system_program(),
+            },
+            instruction::PushUpdate {
+                head_commit: sha1(commit),
+                ipfs_cid: Cid::parse(PUSH_CID).unwrap(),
+                expected_head: commit.checked_sub(1).map(sha1),
+                co_authors,
+                note: None,
+                binding: None,
+            },
+        );
+        for account in accounts {
+            push.accounts.push(AccountMeta::new(account, false));
+        }
+        push
+    };
+    let contribution = |user: &Pubkey| pda::contribution(&repository, user);
+
+    env.send(
+        &[push(
+            vec![pair.pubkey()],
+            vec![contribution(&pair.pubkey())],
+            0,
+        )],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+    // The second push finds the pair's account already there
+    env.send(
+        &[push(
+            vec![pair.pubkey(), guest],
+            vec![contribution(&pair.pubkey()), contribution(&guest)],
+            1,
+        )],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+
+    let record: Contribution = env.account(contribution(&owner.pubkey())).await;
+    assert_eq!(record.push_count, 2);
+    let record: Contribution = env.account(contribution(&pair.pubkey())).await;
+    assert_eq!(record.repository, repository);
+    assert_eq!(record.contributor, pair.pubkey());
+    assert_eq!(record.push_count, 2);
+    let record: Contribution = env.account(contribution(&guest)).await;
+    assert_eq!(record.contributor, guest);
+    assert_eq!(record.push_count, 1);
+    assert_eq!(
+        env.lamports(contribution(&guest)).await,
+        env.rent(Contribution::SPACE).await
+    );
+
+    let too_many: Vec<Pubkey> = (0..=Contribution::MAX_CO_AUTHORS)
+        .map(|_| Pubkey::new_unique())
+        .collect();
+    let too_many_accounts = too_many.iter().map(contribution).collect();
+    for (co_authors, accounts) in [
+        (vec![owner.pubkey()], vec![contribution(&owner.pubkey())]),
+        (
+            vec![pair.pubkey(), pair.pubkey()],
+            vec![contribution(&pair.pubkey()), contribution(&pair.pubkey())],
+        ),
+        (vec![pair.pubkey()], vec![]),
+        (vec![pair.pubkey()], vec![contribution(&guest)]),
+        (too_many, too_many_accounts),
+    ] {
+        assert_error(
+            env.send(&[push(co_authors, accounts, 2)], &[&owner]).await,
+            VanishError::InvalidCoAuthors,
+        );
+    }
+}
+
+#[tokio::test]
+async fn archived_repositories_reject_pushes() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let repository = env.create_repo(&owner, "repo", false).await;
+
+    let archive = set_flags_ix(owner.pubkey(), None, repository, repo_flags::ARCHIVED, true);
+    env.send(&[archive], &[&owner]).await.unwrap();
+
+    let push = push_ix(owner.pubkey(), repository, None, sha1(1));
+    assert_error(
+        env.send(&[push], &[&owner]).await,
+        VanishError::RepoArchived,
+    );
+
+    let unarchive = set_flags_ix(
+        owner.pubkey(),
+        None,
+        repository,
+        repo_flags::ARCHIVED,
+        false,
+    );
+    env.send(&[unarchive], &[&owner]).await.unwrap();
+    let push = push_ix(owner.pubkey(), repository, None, sha1(1));
+    env.send(&[push], &[&owner]).await.unwrap();
+}
+
+#[tokio::test]
+async fn frozen_repositories_take_pushes_from_the_owner_only() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let admin = env.user().await;
+    let repository = env.create_repo(&owner, "repo", false).await;
+    env.add_admin(&owner, repository, admin.pubkey()).await;
+
+    let freeze = |authority: &Keypair, frozen: bool| {
+        ix(
+            accounts::SetFrozen {
+                owner: authority.pubkey(),
+                repository,
+            },
+            instruction::SetFrozen { frozen },
+        )
+    };
+    assert_error(
+        env.send(&[freeze(&admin, true)], &[&admin]).await,
+        AnchorError::ConstraintHasOne,
+    );
+    env.send(&[freeze(&owner, true)], &[&owner]).await.unwrap();
+
+    let admin_push = push_ix(
+        admin.pubkey(),
+        repository,
+        Some(pda::collab(&repository, &admin.pubkey())),
+        sha1(1),
+    );
+    assert_error(
+        env.send(&[admin_push], &[&admin]).await,
+        VanishError::RepoFrozen,
+    );
+    env.send(
+        &[push_ix(owner.pubkey(), repository, None, sha1(1))],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+
+    // Admins cannot lift it through the classification flags either
+    let thaw = set_flags_ix(
+        admin.pubkey(),
+        Some(pda::collab(&repository, &admin.pubkey())),
+        repository,
+        repo_flags::FROZEN,
+        false,
+    );
+    assert_error(
+        env.send(&[thaw], &[&admin]).await,
+        VanishError::InvalidRepoFlags,
+    );
+
+    env.send(&[freeze(&owner, false)], &[&owner]).await.unwrap();
+    let push = push_onto_ix(
+        admin.pubkey(),
+        repository,
+        Some(pda::collab(&repository, &admin.pubkey())),
+        Some(sha1(1)),
+        sha1(2),
+    );
+    env.send(&[push], &[&admin]).await.unwrap();
+}
+
+#[tokio::test]
+async fn deprecate_repo_points_at_a_successor() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let stranger = env.user().await;
+    let repository = env.create_repo(&owner, "old", false).await;
+    let successor = env.create_repo(&owner, "new", false).await;
+
+    let deprecate = |authority: &Keypair, account: Option<Pubkey>, successor: Option<Pubkey>| {
+        ix(
+            accounts::DeprecateRepo {
+                authority: authority.pubkey(),
+                repository,
+                admin: None,
+                successor: account,
+            },
+            instruction::DeprecateRepo { successor },
+        )
+    };
+    assert_error(
+        env.send(&[deprecate(&stranger, None, None)], &[&stranger])
+            .await,
+        VanishError::Unauthorized,
+    );
+    for (account, named) in [
+        (None, Some(successor)),
+        (Some(successor), None),
+        (Some(repository), Some(repository)),
+    ] {
+        assert_error(
+            env.send(&[deprecate(&owner, account, named)], &[&owner])
+                .await,
+            VanishError::InvalidSuccessor,
+        );
+    }
+
+    env.send(&[deprecate(&owner, None, None)], &[&owner])
+        .await
+        .unwrap();
+    let repo: Repository = env.account(repository).await;
+    assert_eq!(repo.flags, repo_flags::DEPRECATED);
+    assert!(repo.successor.is_none());
+
+    // The successor can be named later
+    env.send(
+        &[deprecate(&owner, Some(successor), Some(successor))],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+    let repo: Repository = env.account(repository).await;
+    assert_eq!(repo.successor, Some(successor));
+}
+
+#[tokio::test]
+async fn set_repo_flags_validates_bits() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let stranger = env.user().await;
+    let repository = env.create_repo(&owner, "repo", false).await;
+
+    let set = set_flags_ix(
+        owner.pubkey(),
+        None,
+        repository,
+        repo_flags::GENERATED | repo_flags::MIRROR,
+        true,
+    );
+    env.send(&[set], &[&owner]).await.unwrap();
+    let clear = set_flags_ix(
+        owner.pubkey(),
+        None,
+        repository,
+        repo_flags::GENERATED,
+        false,
+    );
+    env.send(&[clear], &[&owner]).await.unwrap();
+    let repo: Repository = env.account(repository).await;
+    assert_eq!(repo.flags, repo_flags::MIRROR);
+
+    // FORK is only set by fork_repo, which counts the fork
+    for flags in [0, repo_flags::DEPRECATED, repo_flags::FORK] {
+        let set = set_flags_ix(owner.pubkey(), None, repository, flags, true);
+        assert_error(
+            env.send(&[set], &[&owner]).await,
+            VanishError::InvalidRepoFlags,
+        );
+    }
+
+    let set = set_flags_ix(
+        stranger.pubkey(),
+        None,
+        repository,
+        repo_flags::MIRROR,
+        true,
+    );
+    assert_error(
+        env.send(&[set], &[&stranger]).await,
+        VanishError::Unauthorized,
+    );
+}
+
+#[tokio::test]
+async fn set_description_inline_and_ipfs() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let admin = env.user().await;
+    let writer = env.user().await;
+    let repository = env.create_repo(&owner, "repo", false).await;
+    env.add_admin(&owner, repository, admin.pubkey()).await;
+    env.add_collaborator(&owner, repository, writer.pubkey(), true, false)
+        .await;
+
+    let set = |authority: &Keypair, admin: bool, description: &str, storage| {
+        ix(
+            accounts::SetDescription {
+                authority: authority.pubkey(),
+                repository,
+                admin: admin.then(|| pda::collab(&repository, &authority.pubkey())),
+            },
+            instruction::SetDescription {
+                description: description.to_string(),
+                storage,
+            },
+        )
+    };
+
+    env.send(
+        &[set(&owner, false, "Inline", DescriptionStorage::Inline)],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+    env.send(
+        &[set(&admin, true, "QmReadme", DescriptionStorage::Ipfs)],
+        &[&admin],
+    )
+    .await
+    .unwrap();
+    let repo: Repository = env.account(repository).await;
+    assert_eq!(repo.description, "QmReadme");
+    assert!(repo.description_storage == DescriptionStorage::Ipfs);
+
+    let too_long = "d".repeat(257);
+    assert_error(
+        env.send(
+            &[set(&owner, false, &too_long, DescriptionStorage::Inline)],
+            &[&owner],
+        )
+        .await,
+        VanishError::DescriptionTooLong,
+    );
+    assert_error(
+        env.send(
+            &[set(&owner, false, "", DescriptionStorage::Ipfs)],
+            &[&owner],
+        )
+        .await,
+        VanishError::InvalidIpfsCid,
+    );
+
+    // Collaborators without the admin role cannot administer
+    assert_error(
+        env.send(
+            &[set(&writer, true, "Hijacked", DescriptionStorage::Inline)],
+            &[&writer],
+        )
+        .await,
+        VanishError::Unauthorized,
+    );
+}
+
+#[tokio::test]
+async fn admin_accounts_do_not_cross_repositories() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let admin = env.user().await;
+    let theirs = env.create_repo(&owner, "theirs", false).await;
+    let other = env.create_repo(&owner, "other", false).await;
+    env.add_admin(&owner, theirs, admin.pubkey()).await;
+
+    let set = set_flags_ix(
+        admin.pubkey(),
+        Some(pda::collab(&theirs, &admin.pubkey())),
+        other,
+        repo_flags::MIRROR,
+        true,
+    );
+    assert_error(
+        env.send(&[set], &[&admin]).await,
+        AnchorError::ConstraintSeeds,
+    );
+}
+
+#[tokio::test]
+async fn set_ipns_validates_names() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let repository = env.create_repo(&owner, "repo", false).await;
+
+    let set = |name: &str| {
+        ix(
+            accounts::SetIpns {
+                authority: owner.pubkey(),
+                repository,
+                admin: None,
+            },
+            instruction::SetIpns {
+                ipns_name: name.to_string(),
+            },
+        )
+    };
+
+    let name = format!("k51{}", "q".repeat(59));
+    env.send(&[set(&name)], &[&owner]).await.unwrap();
+    let repo: Repository = env.account(repository).await;
+    assert_eq!(repo.ipns_name, name);
+
+    env.send(&[set("")], &[&owner]).await.unwrap();
+
+    assert_error(
+        env.send(&[set("example.com")], &[&owner]).await,
+        VanishError::InvalidIpnsName,
+    );
+}
+
+#[tokio::test]
+async fn checkpoints_record_growth_history() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let fan = env.user().await;
+    let cranker = env.user().await;
+    let repository = env.create_repo(&owner, "repo", false).await;
+
+    let checkpoint = |page: u32| {
+        ix(
+            accounts::CheckpointRepo {
+                payer: cranker.pubkey(),
+                repository,
+                history: pda::history(&repository, page),
+                system_program: system_program(),
+            },
+            instruction::CheckpointRepo {},
+        )
+    };
+
+    env.send(&[checkpoint(0)], &[&cranker]).await.unwrap();
+    let clock: Clock = env.ctx.banks_client.get_sysvar().await.unwrap();
+
+    let star = ix(
+        accounts::StarRepo {
+            user: fan.pubkey(),
+            repository,
+            star_account: pda::star(&fan.pubkey(), &repository),
+            donation_vault: pda::donations(&repository),
+            profile: pda::profile(&fan.pubkey()),
+            config: pda::config(),
+            system_program: system_program(),
+        },
+        instruction::StarRepo { tip: 0 },
+    );
+    env.send(std::slice::from_ref(&star), &[&fan])
+        .await
+        .unwrap();
+    // One star per user, so checkpoints count users rather than calls
+    assert!(env.send(&[star], &[&fan]).await.is_err());
+
+    assert_error(
+        env.send(&[checkpoint(0)], &[&cranker]).await,
+        VanishError::CheckpointNotDue,
+    );
+    env.warp_slots(History::INTERVAL_SLOTS).await;
+    env.send(&[checkpoint(0)], &[&cranker]).await.unwrap();
+
+    let history: History = env.account(pda::history(&repository, 0)).await;
+    assert_eq!(history.repository, repository);
+    assert_eq!(history.page, 0);
+    assert_eq!(history.checkpoints.len(), 2);
+    assert_eq!(history.checkpoints[0].slot, clock.slot);
+    assert_eq!(history.checkpoints[0].stars, 0);
+    assert_eq!(history.checkpoints[1].stars, 1);
+    assert_eq!(history.checkpoints[1].forks, 0);
+    let repo: Repository = env.account(repository).await;
+    assert_eq!(repo.checkpoint_count, 2);
+    assert_eq!(repo.checkpointed_slot, history.checkpoints[1].slot);
+
+    // A full page moves checkpoints on to the next
+    let mut repo: Repository = env.account(repository).await;
+    repo.checkpoint_count = History::CAPACITY;
+    env.set_state(repository, &repo, Repository::SPACE).await;
+    env.warp_slots(History::INTERVAL_SLOTS).await;
+    assert_error(
+        env.send(&[checkpoint(0)], &[&cranker]).await,
+        AnchorError::ConstraintSeeds,
+    );
+    env.send(&[checkpoint(1)], &[&cranker]).await.unwrap();
+    let history: History = env.account(pda::history(&repository, 1)).await;
+    assert_eq!(history.page, 1);
+    assert_eq!(history.checkpoints.len(), 1);
+}
+
+#[tokio::test]
+async fn readme_and_metadata_cids_are_set_apart_from_the_archive() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let stranger = env.user().await;
+    let repository = env.create_repo(&owner, "repo", false).await;
+
+    let set_readme = |authority: &Keypair, cid: Cid| {
+        ix(
+            accounts::SetRepoDocument {
+                authority: authority.pubkey(),
+                repository,
+                admin: None,
+            },
+            instruction::SetReadmeCid { readme_cid: cid },
+        )
+    };
+    let set_metadata = |authority: &Keypair, cid: Cid| {
+        ix(
+            accounts::SetRepoDocument {
+                authority: authority.pubkey(),
+                repository,
+                admin: None,
+            },
+            instruction::SetMetadataCid { metadata_cid: cid },
+        )
+    };
+
+    let readme = Cid::parse(PUSH_CID).unwrap();
+    let metadata =
+        Cid::parse("bafkreigh2akiscaildcqabsyg3dfr6chu3fgpregiymsck7e7aqa4s52zy").unwrap();
+    env.send(
+        &[set_readme(&owner, readme), set_metadata(&owner, metadata)],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+    let repo: Repository = env.account(repository).await;
+    assert!(repo.readme_cid == readme);
+    assert!(repo.metadata_cid == metadata);
+    assert!(repo.ipfs_cid.is_empty());
+
+    assert_error(
+        env.send(&[set_readme(&stranger, Cid::default())], &[&stranger])
+            .await,
+        VanishError::Unauthorized,
+    );
+    assert_error(
+        env.send(
+            &[set_metadata(
+                &owner,
+                Cid::from_bytes(&[0x12, 0x20, 1]).unwrap(),
+            )],
+            &[&owner],
+        )
+        .await,
+        VanishError::InvalidIpfsCid,
+    );
+
+    env.send(&[set_readme(&owner, Cid::default())], &[&owner])
+        .await
+        .unwrap();
+    let repo: Repository = env.account(repository).await;
+    assert!(repo.readme_cid.is_empty());
+    assert!(repo.metadata_cid == metadata);
+
+    // A version 10 account sized to its last field no longer reads as a
+    // Repository, but migrates
+    let mut repo: Repository = env.account(repository).await;
+    repo.version = 10;
+    repo.pinners = vec![Pubkey::new_unique(); Repository::MAX_PINNERS];
+    let mut data = Vec::new();
+    repo.try_serialize(&mut data).unwrap();
+    data.truncate(data.len() - 2 * Cid::SPACE - 16); // readme_cid .. checkpointed_slot
+    env.set_data(repository, data).await;
+
+    env.send(
+        &[ix(
+            accounts::MigrateAccount {
+                payer: owner.pubkey(),
+                account: repository,
+                system_program: system_program(),
+            },
+            instruction::MigrateAccount {},
+        )],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+    let repo: Repository = env.account(repository).await;
+    assert_eq!(repo.version, Repository::VERSION);
+    assert_eq!(repo.pinners.len(), Repository::MAX_PINNERS);
+    assert!(repo.readme_cid.is_empty() && repo.metadata_cid.is_empty());
+}
+
+#[tokio::test]
+async fn push_branch_and_gc_objects() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let reader = env.user().await;
+    let repository = env.create_repo(&owner, "repo", false).await;
+    env.add_collaborator(&owner, repository, reader.pubkey(), false, false)
+        .await;
+
+    let branch_name = format!("feature/{}", "x".repeat(40));
+    let push = |pusher: &Keypair,
+                collaborator: bool,
+                name: &str,
+                expected_head: Option<Multihash>,
+                commit: Multihash| {
+        ix(
+            accounts::PushBranch {
+                pusher: pusher.pubkey(),
+                repository,
+                collaborator_account: collaborator
+                    .then(|| pda::collab(&repository, &pusher.pubkey())),
+                branch_grant: None,
+                contribution: pda::contribution(&repository, &pusher.pubkey()),
+                branch: pda::branch(&repository, name),
+                project_repo: None,
+                rent_vault: None,
+                system_program: system_program(),
+            },
+            instruction::PushBranch {
+                branch_name: name.to_string(),
+                head_commit: commit,
+                ipfs_cid: "QmRefs".to_string(),
+                expected_head,
+                proof: lone_ref_proof(),
+            },
+        )
+    };
+
+    env.send(
+        &[push(&owner, false, &branch_name, None, sha1(1))],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+    env.send(
+        &[push(&owner, false, &branch_name, Some(sha1(1)), sha1(2))],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+    let branch: Branch = env.account(pda::branch(&repository, &branch_name)).await;
+    assert_eq!(branch.name, branch_name);
+    assert!(branch.head_commit == sha1(2));
+    assert_eq!(branch.repository, repository);
+
+    assert_error(
+        env.send(&[push(&owner, false, "", None, sha1(1))], &[&owner])
+            .await,
+        VanishError::InvalidBranchName,
+    );
+    assert_error(
+        env.send(&[push(&reader, true, "main", None, sha1(1))], &[&reader])
+            .await,
+        VanishError::Unauthorized,
+    );
+
+    let gc = |objects_cid: &str| {
+        ix(
+            accounts::GcObjects {
+                authority: owner.pubkey(),
+                repository,
+                admin: None,
+            },
+            instruction::GcObjects {
+                objects_cid: objects_cid.to_string(),
+            },
+        )
+    };
+    env.send(&[gc("QmObjects")], &[&owner]).await.unwrap();
+    let repo: Repository = env.account(repository).await;
+    assert_eq!(repo.objects_cid, "QmObjects");
+    assert_error(
+        env.send(&[gc("")], &[&owner]).await,
+        VanishError::InvalidIpfsCid,
+    );
+}
+
+#[tokio::test]
+async fn pushes_must_name_the_head_they_replace() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let stranger = env.user().await;
+    let repository = env.create_repo(&owner, "repo", false).await;
+
+    let push = |expected_head: Option<Multihash>, commit: Multihash| {
+        push_onto_ix(owner.pubkey(), repository, None, expected_head, commit)
+    };
+    env.send(&[push(None, sha1(1))], &[&owner]).await.unwrap();
+    env.send(&[push(Some(sha1(1)), sha1(2))], &[&owner])
+        .await
+        .unwrap();
+
+    // A push built on an older head would drop the commits since
+    assert_error(
+        env.send(&[push(Some(sha1(1)), sha1(3))], &[&owner]).await,
+        VanishError::StaleHead,
+    );
+    assert_error(
+        env.send(&[push(None, sha1(3))], &[&owner]).await,
+        VanishError::ForcePushNotAllowed,
+    );
+
+    let allow = set_flags_ix(
+        owner.pubkey(),
+        None,
+        repository,
+        repo_flags::ALLOW_FORCE_PUSH,
+        true,
+    );
+    env.send(&[allow], &[&owner]).await.unwrap();
+    env.send(&[push(None, sha1(3))], &[&owner]).await.unwrap();
+    let repo: Repository = env.account(repository).await;
+    assert!(repo.head_commit == sha1(3));
+
+    // Branches are protected on their own
+    let push_branch = |expected_head: Option<Multihash>, commit: Multihash| {
+        push_branch_ix(
+            owner.pubkey(),
+            repository,
+            "dev",
+            expected_head,
+            commit,
+            lone_ref_proof(),
+        )
+    };
+    env.send(&[push_branch(None, sha1(1))], &[&owner])
+        .await
+        .unwrap();
+    assert_error(
+        env.send(&[push_branch(None, sha1(2))], &[&owner]).await,
+        VanishError::ForcePushNotAllowed,
+    );
+    assert_error(
+        env.send(&[push_branch(Some(sha1(9)), sha1(2))], &[&owner])
+            .await,
+        VanishError::StaleHead,
+    );
+
+    let set_force_push = |authority: &Keypair, allow: bool| {
+        ix(
+            accounts::SetBranchForcePush {
+                authority: authority.pubkey(),
+                repository,
+                admin: None,
+                branch: pda::branch(&repository, "dev"),
+            },
+            instruction::SetBranchForcePush { allow },
+        )
+    };
+    assert_error(
+        env.send(&[set_force_push(&stranger, true)], &[&stranger])
+            .await,
+        VanishError::Unauthorized,
+    );
+    env.send(&[set_force_push(&owner, true)], &[&owner])
+        .await
+        .unwrap();
+    env.send(&[push_branch(None, sha1(2))], &[&owner])
+        .await
+        .unwrap();
+    let branch: Branch = env.account(pda::branch(&repository, "dev")).await;
+    assert!(branch.head_commit == sha1(2));
+    assert!(branch.allow_force_push);
+
+    // Naming the wrong head still fails when force pushes are allowed
+    assert_error(
+        env.send(&[push_branch(Some(sha1(1)), sha1(3))], &[&owner])
+            .await,
+        VanishError::StaleHead,
+    );
+}
+
+#[tokio::test]
+async fn push_refs_updates_every_ref_or_none() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let repository = env.create_repo(&owner, "repo", false).await;
+    let other = env.create_repo(&owner, "other", false).await;
+    env.send(
+        &[push_branch_ix(
+            owner.pubkey(),
+            other,
+            "dev",
+            None,
+            sha1(1),
+            lone_ref_proof(),
+        )],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+
+    // Each new branch takes the next leaf of the refs tree
+    let leaf = |name: &str, commit: u8| refs_tree::leaf(name, &sha1(commit));
+    let mut leaves = vec![];
+    for name in ["dev", "docs"] {
+        let proof = refs_tree::proof(&leaves, leaves.len() as u32);
+        env.send(
+            &[push_branch_ix(
+                owner.pubkey(),
+                repository,
+                name,
+                None,
+                sha1(1),
+                proof,
+            )],
+            &[&owner],
+        )
+        .await
+        .unwrap();
+        leaves.push(leaf(name, 1));
+    }
+    let repo: Repository = env.account(repository).await;
+    assert!(repo.refs_root == refs_tree::root(&leaves));
+    assert_eq!(repo.ref_count, 2);
+
+    let update =
+        |expected_head: Option<Multihash>, commit: Multihash, proof: Vec<[u8; 32]>| RefUpdate {
+            head_commit: commit,
+            ipfs_cid: "QmRefs".to_string(),
+            expected_head,
+            proof,
+        };
+    let head = |expected_head: Option<Multihash>, commit: Multihash| HeadUpdate {
+        head_commit: commit,
+        ipfs_cid: Cid::parse(PUSH_CID).unwrap(),
+        expected_head,
+    };
+    let push_refs = |head: Option<HeadUpdate>, branches: Vec<(Pubkey, RefUpdate)>| {
+        let mut push = ix(
+            accounts::PushRefs {
+                pusher: owner.pubkey(),
+                repository,
+                collaborator_account: None,
+                branch_grant: None,
+                contribution: pda::contribution(&repository, &owner.pubkey()),
+                system_program: system_program(),
+            },
+            instruction::PushRefs {
+                head,
+                branches: branches.iter().map(|(_, update)| update.clone()).collect(),
+            },
+        );
+        for (branch, _) in &branches {
+            push.accounts.push(AccountMeta::new(*branch, false));
+        }
+        push
+    };
+    let dev = pda::branch(&repository, "dev");
+    let docs = pda::branch(&repository, "docs");
+
+    // Later updates prove against the tree the earlier ones left
+    env.send(
+        &[push_refs(
+            Some(head(None, sha1(2))),
+            vec![
+                (
+                    dev,
+                    update(Some(sha1(1)), sha1(2), refs_tree::proof(&leaves, 0)),
+                ),
+                (
+                    docs,
+                    update(
+                        Some(sha1(1)),
+                        sha1(3),
+                        refs_tree::proof(&[leaf("dev", 2), leaf("docs", 1)], 1),
+                    ),
+                ),
+            ],
+        )],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+    let leaves = [leaf("dev", 2), leaf("docs", 3)];
+    let repo: Repository = env.account(repository).await;
+    assert!(repo.head_commit == sha1(2));
+    assert!(repo.refs_root == refs_tree::root(&leaves));
+    let branch: Branch = env.account(dev).await;
+    assert!(branch.head_commit == sha1(2));
+    let branch: Branch = env.account(docs).await;
+    assert!(branch.head_commit == sha1(3));
+
+    // One stale ref fails the whole push
+    assert_error(
+        env.send(
+            &[push_refs(
+                Some(head(Some(sha1(2)), sha1(4))),
+                vec![
+                    (
+                        dev,
+                        update(Some(sha1(2)), sha1(4), refs_tree::proof(&leaves, 0)),
+                    ),
+                    (
+                        docs,
+                        update(Some(sha1(1)), sha1(4), refs_tree::proof(&leaves, 1)),
+                    ),
+                ],
+            )],
+            &[&owner],
+        )
+        .await,
+        VanishError::StaleHead,
+    );
+    let repo: Repository = env.account(repository).await;
+    assert!(repo.head_commit == sha1(2));
+    let branch: Branch = env.account(dev).await;
+    assert!(branch.head_commit == sha1(2));
+    assert!(repo.refs_root == refs_tree::root(&leaves));
+
+    // A proof that leaves out another branch is rejected
+    assert_error(
+        env.send(
+            &[push_refs(
+                None,
+                vec![(dev, update(Some(sha1(2)), sha1(4), lone_ref_proof()))],
+            )],
+            &[&owner],
+        )
+        .await,
+        VanishError::InvalidRefProof,
+    );
+
+    let proof = refs_tree::proof(&leaves, 0);
+    let mut missing_account = push_refs(
+        None,
+        vec![(dev, update(Some(sha1(2)), sha1(4), proof.clone()))],
+    );
+    missing_account.accounts.pop();
+    let other_repository = push_refs(
+        None,
+        vec![(
+            pda::branch(&other, "dev"),
+            update(Some(sha1(1)), sha1(4), proof),
+        )],
+    );
+    for push in [push_refs(None, vec![]), missing_account, other_repository] {
+        assert_error(
+            env.send(&[push], &[&owner]).await,
+            VanishError::InvalidRefUpdates,
+        );
+    }
+}
+
+#[tokio::test]
+async fn transfer_and_renounce_ownership() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let new_owner = env.user().await;
+    let admin = env.user().await;
+    let repository = env.create_repo(&owner, "repo", false).await;
+
+    let transfer = |from: &Keypair, to: Pubkey, audit_entry: Pubkey| {
+        ix(
+            accounts::TransferOwnership {
+                owner: from.pubkey(),
+                repository,
+                timelock: pda::timelock(&repository),
+                pending_action: None,
+                audit_entry,
+                system_program: system_program(),
+            },
+            instruction::TransferOwnership { new_owner: to },
+        )
+    };
+    let renounce = |from: &Keypair, audit_entry: Pubkey| {
+        ix(
+            accounts::RenounceOwnership {
+                owner: from.pubkey(),
+                repository,
+                audit_entry,
+                system_program: system_program(),
+            },
+            instruction::RenounceOwnership {},
+        )
+    };
+
+    let audit_entry = env.audit_entry(repository).await;
+    assert_error(
+        env.send(
+            &[transfer(&owner, Pubkey::default(), audit_entry)],
+            &[&owner],
+        )
+        .await,
+        VanishError::Unauthorized,
+    );
+    let audit_entry = env.audit_entry(repository).await;
+    env.send(
+        &[transfer(&owner, new_owner.pubkey(), audit_entry)],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+    let repo: Repository = env.account(repository).await;
+    assert_eq!(repo.owner, new_owner.pubkey());
+    assert_eq!(repo.creator, owner.pubkey());
+
+    // The previous owner has no say any more
+    let audit_entry = env.audit_entry(repository).await;
+    assert_error(
+        env.send(&[transfer(&owner, owner.pubkey(), audit_entry)], &[&owner])
+            .await,
+        AnchorError::ConstraintHasOne,
+    );
+
+    // Renouncing needs an admin to hand the repository to
+    let audit_entry = env.audit_entry(repository).await;
+    assert_error(
+        env.send(&[renounce(&new_owner, audit_entry)], &[&new_owner])
+            .await,
+        VanishError::LastAdmin,
+    );
+    env.add_admin(&new_owner, repository, admin.pubkey()).await;
+    let audit_entry = env.audit_entry(repository).await;
+    env.send(&[renounce(&new_owner, audit_entry)], &[&new_owner])
+        .await
+        .unwrap();
+    let repo: Repository = env.account(repository).await;
+    assert_eq!(repo.owner, Pubkey::default());
+
+    // The admin now runs the repository, and cannot demote themselves
+    let set = set_flags_ix(
+        admin.pubkey(),
+        Some(pda::collab(&repository, &admin.pubkey())),
+        repository,
+        repo_flags::MIRROR,
+        true,
+    );
+    env.send(&[set], &[&admin]).await.unwrap();
+    let demote = ix(
+        accounts::SetCollaboratorAdmin {
+            authority: admin.pubkey(),
+            repository,
+            admin: Some(pda::collab(&repository, &admin.pubkey())),
+            collaborator_account: pda::collab(&repository, &admin.pubkey()),
+            audit_entry: env.audit_entry(repository).await,
+            system_program: system_program(),
+        },
+        instruction::SetCollaboratorAdmin { is_admin: false },
+    );
+    assert_error(env.send(&[demote], &[&admin]).await, VanishError::LastAdmin);
+}
+
+#[tokio::test]
+async fn sensitive_actions_are_audited() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let admin = Keypair::new().pubkey();
+    let repository = env.create_repo(&owner, "repo", false).await;
+
+    env.add_admin(&owner, repository, admin).await;
+    let audit_entry = env.audit_entry(repository).await;
+    env.send(
+        &[delete_ix(&owner, repository, DAY, audit_entry)],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+    env.warp(DAY).await;
+    env.send(&[purge_ix(&owner, repository, &[])], &[&owner])
+        .await
+        .unwrap();
+    assert!(!env.exists(repository).await);
+
+    // The log outlives the repository
+    for (seq, action, subject) in [
+        (0, AuditAction::CollaboratorAdded, admin),
+        (1, AuditAction::CollaboratorPromoted, admin),
+        (2, AuditAction::Deleted, repository),
+    ] {
+        let entry: AuditEntry = env.account(pda::audit(&repository, seq)).await;
+        assert_eq!(entry.repository, repository);
+        assert_eq!(entry.seq, seq);
+        assert_eq!(entry.actor, owner.pubkey());
+        assert!(entry.action == action);
+        assert_eq!(entry.subject, subject);
+    }
+    assert!(!env.exists(pda::audit(&repository, 3)).await);
+}
+
+#[tokio::test]
+async fn delete_repo_is_owner_only() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let stranger = env.user().await;
+    let repository = env.create_repo(&owner, "repo", false).await;
+    env.create_repo(&stranger, "repo", false).await;
+
+    let audit_entry = env.audit_entry(repository).await;
+    assert_error(
+        env.send(
+            &[delete_ix(&stranger, repository, DAY, audit_entry)],
+            &[&stranger],
+        )
+        .await,
+        AnchorError::ConstraintHasOne,
+    );
+
+    let audit_entry = env.audit_entry(repository).await;
+    env.send(
+        &[delete_ix(&owner, repository, DAY, audit_entry)],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+    let repo: Repository = env.account(repository).await;
+    assert!(repo.deleted_at > 0);
+    assert_eq!(repo.purgeable_at, repo.deleted_at + DAY);
+
+    env.warp(DAY).await;
+    assert_error(
+        env.send(&[purge_ix(&stranger, repository, &[])], &[&stranger])
+            .await,
+        AnchorError::ConstraintHasOne,
+    );
+
+    let before = env.lamports(owner.pubkey()).await;
+    env.send(&[purge_ix(&owner, repository, &[])], &[&owner])
+        .await
+        .unwrap();
+    assert!(!env.exists(repository).await);
+    assert!(env.lamports(owner.pubkey()).await > before);
+}
+
+#[tokio::test]
+async fn deleted_repo_takes_no_writes_until_restored() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let fan = env.user().await;
+    let repository = env.create_repo(&owner, "repo", false).await;
+
+    let audit_entry = env.audit_entry(repository).await;
+    assert_error(
+        env.send(
+            &[delete_ix(&owner, repository, DAY - 1, audit_entry)],
+            &[&owner],
+        )
+        .await,
+        VanishError::RecoveryWindowTooShort,
+    );
+    assert_error(
+        env.send(&[purge_ix(&owner, repository, &[])], &[&owner])
+            .await,
+        VanishError::RepoNotDeleted,
+    );
+    let audit_entry = env.audit_entry(repository).await;
+    env.send(
+        &[delete_ix(&owner, repository, 7 * DAY, audit_entry)],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+
+    assert_error(
+        env.send(
+            &[push_ix(owner.pubkey(), repository, None, sha1(1))],
+            &[&owner],
+        )
+        .await,
+        VanishError::RepoDeleted,
+    );
+    let star = ix(
+        accounts::StarRepo {
+            user: fan.pubkey(),
+            repository,
+            star_account: pda::star(&fan.pubkey(), &repository),
+            donation_vault: pda::donations(&repository),
+            profile: pda::profile(&fan.pubkey()),
+            config: pda::config(),
+            system_program: system_program(),
+        },
+        instruction::StarRepo { tip: 0 },
+    );
+    assert_error(
+        env.send(std::slice::from_ref(&star), &[&fan]).await,
+        VanishError::RepoDeleted,
+    );
+    let repo: Repository = env.account(repository).await;
+    let add = add_collaborator_ix(
+        owner.pubkey(),
+        None,
+        repository,
+        fan.pubkey(),
+        true,
+        false,
+        &repo,
+    );
+    assert_error(env.send(&[add], &[&owner]).await, VanishError::RepoDeleted);
+    let audit_entry = env.audit_entry(repository).await;
+    assert_error(
+        env.send(
+            &[delete_ix(&owner, repository, DAY, audit_entry)],
+            &[&owner],
+        )
+        .await,
+        VanishError::RepoDeleted,
+    );
+
+    env.warp(7 * DAY - 60).await;
+    assert_error(
+        env.send(&[purge_ix(&owner, repository, &[])], &[&owner])
+            .await,
+        VanishError::RecoveryWindowOpen,
+    );
+
+    let restore = ix(
+        accounts::RestoreRepo {
+            owner: owner.pubkey(),
+            repository,
+        },
+        instruction::RestoreRepo {},
+    );
+    env.send(std::slice::from_ref(&restore), &[&owner])
+        .await
+        .unwrap();
+    let repo: Repository = env.account(repository).await;
+    assert_eq!(repo.deleted_at, 0);
+    env.send(&[star], &[&fan]).await.unwrap();
+    env.send(
+        &[push_ix(owner.pubkey(), repository, None, sha1(1))],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+
+    assert_error(
+        env.send(&[restore], &[&owner]).await,
+        VanishError::RepoNotDeleted,
+    );
+}
+
+#[tokio::test]
+async fn purge_repo_closes_child_accounts() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let fan = env.user().await;
+    let user = Keypair::new().pubkey();
+    let repository = env.create_repo(&owner, "repo", true).await;
+    let other = env.create_repo(&owner, "other", false).await;
+    env.add_collaborator(&owner, repository, user, true, true)
+        .await;
+    env.add_collaborator(&owner, other, user, true, false).await;
+    let star = ix(
+        accounts::StarRepo {
+            user: fan.pubkey(),
+            repository,
+            star_account: pda::star(&fan.pubkey(), &repository),
+            donation_vault: pda::donations(&repository),
+            profile: pda::profile(&fan.pubkey()),
+            config: pda::config(),
+            system_program: system_program(),
+        },
+        instruction::StarRepo { tip: 0 },
+    );
+    env.send(&[star], &[&fan]).await.unwrap();
+
+    let audit_entry = env.audit_entry(repository).await;
+    env.send(
+        &[delete_ix(&owner, repository, DAY, audit_entry)],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+    env.warp(DAY).await;
+    let purge = |children: &[(Pubkey, Pubkey)]| purge_ix(&owner, repository, children);
+    let star = pda::star(&fan.pubkey(), &repository);
+    let collab = pda::collab(&repository, &user);
+    let access_key = pda::access(&repository, &user);
+
+    // Stars go back to the user who paid for them
+    assert_error(
+        env.send(&[purge(&[(star, owner.pubkey())])], &[&owner])
+            .await,
+        VanishError::InvalidChildAccount,
+    );
+    assert_error(
+        env.send(
+            &[purge(&[(pda::collab(&other, &user), owner.pubkey())])],
+            &[&owner],
+        )
+        .await,
+        VanishError::InvalidChildAccount,
+    );
+
+    let star_rent = env.lamports(star).await;
+    let fan_before = env.lamports(fan.pubkey()).await;
+    env.send(
+        &[purge(&[
+            (collab, owner.pubkey()),
+            (access_key, owner.pubkey()),
+            (star, fan.pubkey()),
+        ])],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+
+    assert!(!env.exists(repository).await);
+    assert!(!env.exists(collab).await);
+    assert!(!env.exists(access_key).await);
+    assert!(!env.exists(star).await);
+    assert_eq!(env.lamports(fan.pubkey()).await, fan_before + star_rent);
+    assert!(env.exists(pda::collab(&other, &user)).await);
+
+    // The purged repository no longer counts against the owner's quota
+    let profile: Profile = env.account(pda::profile(&owner.pubkey())).await;
+    assert_eq!(profile.repo_count, 1);
+}
+
+fn sweep_ix(id: Pubkey, children: &[(Pubkey, Pubkey)]) -> Instruction {
+    let mut sweep = ix(
+        accounts::SweepPurged {
+            receipt: pda::purge_receipt(&id),
+        },
+        instruction::SweepPurged {},
+    );
+    for (child, refund) in children {
+        sweep.accounts.push(AccountMeta::new(*child, false));
+        sweep.accounts.push(AccountMeta::new(*refund, false));
+    }
+    sweep
+}
+
+#[tokio::test]
+async fn purged_ids_are_never_reused() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let writer = env.user().await;
+    let fan = env.user().await;
+    let repository = env.create_repo(&owner, "repo", false).await;
+    env.add_collaborator(&owner, repository, writer.pubkey(), true, false)
+        .await;
+    let star_ix = |repository: Pubkey| {
+        ix(
+            accounts::StarRepo {
+                user: fan.pubkey(),
+                repository,
+                star_account: pda::star(&fan.pubkey(), &repository),
+                donation_vault: pda::donations(&repository),
+                profile: pda::profile(&fan.pubkey()),
+                config: pda::config(),
+                system_program: system_program(),
+            },
+            instruction::StarRepo { tip: 0 },
+        )
+    };
+    env.send(&[star_ix(repository)], &[&fan]).await.unwrap();
+
+    // Purge closing only the name lock, leaving the collaborator and star
+    let audit_entry = env.audit_entry(repository).await;
+    env.send(
+        &[delete_ix(&owner, repository, DAY, audit_entry)],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+    env.warp(DAY).await;
+    let name_lock = pda::name_lock(&owner.pubkey(), "repo");
+    env.send(
+        &[purge_ix(&owner, repository, &[(name_lock, owner.pubkey())])],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+    let receipt: PurgeReceipt = env.account(pda::purge_receipt(&repository)).await;
+    assert_eq!(receipt.repository, repository);
+    assert_eq!(receipt.owner, owner.pubkey());
+
+    // The name can be taken again, but not the id
+    env.create_repo(&owner, "repo", false).await;
+    let repo: Repository = env.account(repository).await;
+    assert_eq!(repo.id, Repository::id_at(&repository, 1));
+    assert_ne!(repo.id, repository);
+    let stale_collab = pda::collab(&repository, &writer.pubkey());
+    let stale_star = pda::star(&fan.pubkey(), &repository);
+    assert!(env.exists(stale_collab).await);
+    assert_error(
+        env.send(
+            &[push_ix(
+                writer.pubkey(),
+                repository,
+                Some(stale_collab),
+                sha1(1),
+            )],
+            &[&writer],
+        )
+        .await,
+        AnchorError::ConstraintSeeds,
+    );
+
+    // Anyone may sweep what the purge left, refunding as purge_repo would,
+    // but nothing of the live repository
+    env.add_collaborator(&owner, repository, writer.pubkey(), true, false)
+        .await;
+    let live_collab = pda::collab(&repo.id, &writer.pubkey());
+    assert_error(
+        env.send(
+            &[sweep_ix(repository, &[(live_collab, owner.pubkey())])],
+            &[],
+        )
+        .await,
+        VanishError::InvalidChildAccount,
+    );
+    assert_error(
+        env.send(
+            &[sweep_ix(repository, &[(stale_star, owner.pubkey())])],
+            &[],
+        )
+        .await,
+        VanishError::InvalidChildAccount,
+    );
+    let fan_before = env.lamports(fan.pubkey()).await;
+    let star_rent = env.lamports(stale_star).await;
+    env.send(
+        &[sweep_ix(
+            repository,
+            &[(stale_collab, owner.pubkey()), (stale_star, fan.pubkey())],
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+    assert!(!env.exists(stale_collab).await);
+    assert!(!env.exists(stale_star).await);
+    assert!(env.exists(live_collab).await);
+    assert_eq!(env.lamports(fan.pubkey()).await, fan_before + star_rent);
+
+    // Only purged ids have a receipt to sweep against
+    assert_error(
+        env.send(&[sweep_ix(repo.id, &[(live_collab, owner.pubkey())])], &[])
+            .await,
+        AnchorError::AccountNotInitialized,
+    );
+}
+
+fn stats_ix(
+    pusher: &Keypair,
+    repository: Pubkey,
+    commits_added: u64,
+    packed_size: u64,
+    languages: &[&str],
+) -> Instruction {
+    ix(
+        accounts::UpdateStats {
+            pusher: pusher.pubkey(),
+            repository,
+            collaborator_account: None,
+            stats: pda::stats(&repository),
+            system_program: system_program(),
+        },
+        instruction::UpdateStats {
+            commits_added,
+            packed_size,
+            languages: languages.iter().map(|tag| tag.to_string()).collect(),
+        },
+    )
+}
+
+#[tokio::test]
+async fn update_stats_once_per_head() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let stranger = env.user().await;
+    let repository = env.create_repo(&owner, "repo", false).await;
+    env.send(
+        &[push_ix(owner.pubkey(), repository, None, sha1(1))],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+
+    env.send(
+        &[stats_ix(&owner, repository, 3, 1024, &["rust", "c++"])],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+    let stats: Stats = env.account(pda::stats(&repository)).await;
+    assert_eq!(stats.repository, repository);
+    assert_eq!(stats.head, sha1(1).seed());
+    assert_eq!(stats.commit_count, 3);
+    assert_eq!(stats.packed_size, 1024);
+    assert_eq!(stats.languages, ["rust", "c++"]);
+
+    // Counting a push twice would inflate the commit count
+    assert_error(
+        env.send(
+            &[stats_ix(&owner, repository, 3, 1024, &["rust"])],
+            &[&owner],
+        )
+        .await,
+        VanishError::StatsRecorded,
+    );
+
+    env.send(
+        &[push_onto_ix(
+            owner.pubkey(),
+            repository,
+            None,
+            Some(sha1(1)),
+            sha1(2),
+        )],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+    for (commits, size, languages) in [
+        (Stats::MAX_COMMITS_PER_PUSH + 1, 0, vec!["rust"]),
+        (1, Stats::MAX_PACKED_SIZE + 1, vec!["rust"]),
+        (1, 0, vec!["Rust"]),
+        (1, 0, vec![""]),
+        (1, 0, vec!["rust"; Stats::MAX_LANGUAGES + 1]),
+    ] {
+        assert_error(
+            env.send(
+                &[stats_ix(&owner, repository, commits, size, &languages)],
+                &[&owner],
+            )
+            .await,
+            VanishError::InvalidStats,
+        );
+    }
+    assert_error(
+        env.send(
+            &[stats_ix(&stranger, repository, 2, 2048, &["rust"])],
+            &[&stranger],
+        )
+        .await,
+        VanishError::Unauthorized,
+    );
+
+    env.send(
+        &[stats_ix(&owner, repository, 2, 2048, &["rust"])],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+    let stats: Stats = env.account(pda::stats(&repository)).await;
+    assert_eq!(stats.commit_count, 5);
+    assert_eq!(stats.packed_size, 2048);
+    assert_eq!(stats.languages, ["rust"]);
+}
+
+fn enroll_pinner_ix(authority: &Keypair, operator: &Keypair, repository: Pubkey) -> Instruction {
+    ix(
+        accounts::EnrollPinner {
+            operator: operator.pubkey(),
+            authority: authority.pubkey(),
+            pinner: pda::pinner(&operator.pubkey()),
+            repository,
+            admin: None,
+        },
+        instruction::EnrollPinner {},
+    )
+}
+
+fn unenroll_pinner_ix(authority: &Keypair, repository: Pubkey, operator: Pubkey) -> Instruction {
+    ix(
+        accounts::UnenrollPinner {
+            authority: authority.pubkey(),
+            repository,
+            admin: None,
+        },
+        instruction::UnenrollPinner { operator },
+    )
+}
+
+#[tokio::test]
+async fn pinners_stake_and_enroll_in_repositories() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let operator = env.user().await;
+    let stranger = env.user().await;
+    let repository = env.create_repo(&owner, "repo", false).await;
+
+    let register = |stake: u64| {
+        ix(
+            accounts::RegisterPinner {
+                operator: operator.pubkey(),
+                pinner: pda::pinner(&operator.pubkey()),
+                system_program: system_program(),
+            },
+            instruction::RegisterPinner { stake },
+        )
+    };
+    assert_error(
+        env.send(&[register(Pinner::MIN_STAKE - 1)], &[&operator])
+            .await,
+        VanishError::PinnerStakeTooLow,
+    );
+    env.send(&[register(Pinner::MIN_STAKE)], &[&operator])
+        .await
+        .unwrap();
+    assert_eq!(
+        env.lamports(pda::pinner(&operator.pubkey())).await,
+        env.rent(Pinner::SPACE).await + Pinner::MIN_STAKE
+    );
+
+    // The repository has to approve the pinner
+    assert_error(
+        env.send(
+            &[enroll_pinner_ix(&operator, &operator, repository)],
+            &[&operator],
+        )
+        .await,
+        VanishError::Unauthorized,
+    );
+    env.send(
+        &[enroll_pinner_ix(&owner, &operator, repository)],
+        &[&owner, &operator],
+    )
+    .await
+    .unwrap();
+    let repo: Repository = env.account(repository).await;
+    assert_eq!(repo.pinners, [operator.pubkey()]);
+    assert_error(
+        env.send(
+            &[enroll_pinner_ix(&owner, &operator, repository)],
+            &[&owner, &operator],
+        )
+        .await,
+        VanishError::PinnerEnrolled,
+    );
+
+    // Only the pinner or a repository admin can take it off the set
+    assert_error(
+        env.send(
+            &[unenroll_pinner_ix(&stranger, repository, operator.pubkey())],
+            &[&stranger],
+        )
+        .await,
+        VanishError::Unauthorized,
+    );
+    env.send(
+        &[unenroll_pinner_ix(&owner, repository, operator.pubkey())],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+    let repo: Repository = env.account(repository).await;
+    assert!(repo.pinners.is_empty());
+    assert_error(
+        env.send(
+            &[unenroll_pinner_ix(&operator, repository, operator.pubkey())],
+            &[&operator],
+        )
+        .await,
+        VanishError::PinnerNotEnrolled,
+    );
+
+    // An unbonding pinner cannot enroll, and its stake stays locked for the
+    // unbonding period
+    let unbond = ix(
+        accounts::UnbondPinner {
+            operator: operator.pubkey(),
+            pinner: pda::pinner(&operator.pubkey()),
+        },
+        instruction::UnbondPinner {},
+    );
+    let withdraw = ix(
+        accounts::WithdrawPinnerStake {
+            operator: operator.pubkey(),
+            pinner: pda::pinner(&operator.pubkey()),
+        },
+        instruction::WithdrawPinnerStake {},
+    );
+    assert_error(
+        env.send(std::slice::from_ref(&withdraw), &[&operator])
+            .await,
+        VanishError::PinnerBonded,
+    );
+    env.send(&[unbond], &[&operator]).await.unwrap();
+    assert_error(
+        env.send(
+            &[enroll_pinner_ix(&owner, &operator, repository)],
+            &[&owner, &operator],
+        )
+        .await,
+        VanishError::PinnerUnbonding,
+    );
+    env.warp(Pinner::UNBONDING_PERIOD).await;
+    let before = env.lamports(operator.pubkey()).await;
+    env.send(&[withdraw], &[&operator]).await.unwrap();
+    assert!(!env.exists(pda::pinner(&operator.pubkey())).await);
+    assert!(env.lamports(operator.pubkey()).await > before + Pinner::MIN_STAKE);
+}
+
+#[tokio::test]
+async fn distribute_rewards_pays_bonded_pinners() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let bonded = env.user().await;
+    let unbonding = env.user().await;
+    let silent = env.user().await;
+    let payer = env.payer();
+    let repository = env.create_repo(&owner, "repo", false).await;
+    let pool = pda::reward_pool(&repository);
+
+    for operator in [&bonded, &unbonding, &silent] {
+        env.send(
+            &[
+                ix(
+                    accounts::RegisterPinner {
+                        operator: operator.pubkey(),
+                        pinner: pda::pinner(&operator.pubkey()),
+                        system_program: system_program(),
+                    },
+                    instruction::RegisterPinner {
+                        stake: Pinner::MIN_STAKE,
+                    },
+                ),
+                enroll_pinner_ix(&owner, operator, repository),
+            ],
+            &[&owner, operator],
+        )
+        .await
+        .unwrap();
+    }
+
+    let attest = |operator: &Keypair| {
+        ix(
+            accounts::AttestPinned {
+                operator: operator.pubkey(),
+                pinner: pda::pinner(&operator.pubkey()),
+                repository,
+                pin_attestation: pda::pin_attestation(&repository, &operator.pubkey()),
+                system_program: system_program(),
+            },
+            instruction::AttestPinned {},
+        )
+    };
+    // There is no archive to pin until something is pushed
+    assert_error(
+        env.send(&[attest(&bonded)], &[&bonded]).await,
+        VanishError::InvalidIpfsCid,
+    );
+    env.send(
+        &[push_ix(owner.pubkey(), repository, None, sha1(1))],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+    assert_error(
+        env.send(&[attest(&owner)], &[&owner]).await,
+        AnchorError::AccountNotInitialized,
+    );
+    env.send(&[attest(&unbonding)], &[&unbonding])
+        .await
+        .unwrap();
+    let attestation: PinAttestation = env
+        .account(pda::pin_attestation(&repository, &unbonding.pubkey()))
+        .await;
+    assert_eq!(attestation.cid.to_string(), PUSH_CID);
+
+    env.send(
+        &[ix(
+            accounts::UnbondPinner {
+                operator: unbonding.pubkey(),
+                pinner: pda::pinner(&unbonding.pubkey()),
+            },
+            instruction::UnbondPinner {},
+        )],
+        &[&unbonding],
+    )
+    .await
+    .unwrap();
+
+    env.send(
+        &[
+            ix(
+                accounts::SetRewardRate {
+                    authority: owner.pubkey(),
+                    repository,
+                    admin: None,
+                    reward_pool: pool,
+                    system_program: system_program(),
+                },
+                instruction::SetRewardRate { rate: 1_000 },
+            ),
+            ix(
+                accounts::FundRewardPool {
+                    funder: owner.pubkey(),
+                    repository,
+                    reward_pool: pool,
+                    system_program: system_program(),
+                },
+                instruction::FundRewardPool { amount: 2_500 },
+            ),
+        ],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+
+    // The treasury can top the pool up, down to its rent-exempt minimum
+    let from_treasury = |amount: u64| {
+        ix(
+            accounts::FundRewardPoolFromTreasury {
+                admin: payer,
+                config: pda::config(),
+                treasury: pda::treasury(),
+                repository,
+                reward_pool: pool,
+                system_program: system_program(),
+            },
+            instruction::FundRewardPoolFromTreasury { amount },
+        )
+    };
+    let treasury = env.lamports(pda::treasury()).await;
+    let spare = treasury - env.rent(0).await;
+    assert_error(
+        env.send(&[from_treasury(spare + 1)], &[]).await,
+        VanishError::InsufficientTreasury,
+    );
+    let fund = system_instruction::transfer(&payer, &pda::treasury(), 500);
+    env.send(&[fund, from_treasury(spare + 500)], &[])
+        .await
+        .unwrap();
+    assert_eq!(
+        env.lamports(pool).await,
+        env.rent(RewardPool::SPACE).await + 3_000 + spare
+    );
+
+    let distribute = |operators: &[&Keypair]| {
+        let mut instruction = ix(
+            accounts::DistributeRewards {
+                repository,
+                reward_pool: pool,
+            },
+            instruction::DistributeRewards {},
+        );
+        for operator in operators {
+            instruction.accounts.extend([
+                AccountMeta::new_readonly(pda::pinner(&operator.pubkey()), false),
+                AccountMeta::new_readonly(
+                    pda::pin_attestation(&repository, &operator.pubkey()),
+                    false,
+                ),
+                AccountMeta::new(operator.pubkey(), false),
+            ]);
+        }
+        instruction
+    };
+    let all = [&bonded, &unbonding, &silent];
+    assert_error(
+        env.send(&[distribute(&all)], &[]).await,
+        VanishError::RewardsNotDue,
+    );
+
+    // Two intervals are due; only the bonded pinner that attested is paid
+    env.warp(2 * RewardPool::INTERVAL).await;
+    env.send(&[attest(&bonded)], &[&bonded]).await.unwrap();
+    assert_error(
+        env.send(&[distribute(&[&bonded, &unbonding])], &[]).await,
+        VanishError::InvalidRewardAccounts,
+    );
+    assert_error(
+        env.send(&[distribute(&[&unbonding, &bonded, &silent])], &[])
+            .await,
+        VanishError::InvalidRewardAccounts,
+    );
+    let bonded_before = env.lamports(bonded.pubkey()).await;
+    let unbonding_before = env.lamports(unbonding.pubkey()).await;
+    let silent_before = env.lamports(silent.pubkey()).await;
+    env.send(&[distribute(&all)], &[]).await.unwrap();
+    assert_eq!(env.lamports(bonded.pubkey()).await, bonded_before + 2_000);
+    assert_eq!(env.lamports(unbonding.pubkey()).await, unbonding_before);
+    assert_eq!(env.lamports(silent.pubkey()).await, silent_before);
+    let reward_pool: RewardPool = env.account(pool).await;
+    assert_eq!(reward_pool.distributed, 2_000);
+
+    // An attestation older than an interval earns nothing
+    env.warp(RewardPool::INTERVAL).await;
+    env.send(&[distribute(&all)], &[]).await.unwrap();
+    assert_eq!(env.lamports(bonded.pubkey()).await, bonded_before + 2_000);
+
+    // The next payout is capped by what is left in the pool
+    env.warp(RewardPool::INTERVAL).await;
+    env.send(&[attest(&bonded)], &[&bonded]).await.unwrap();
+    env.send(&[distribute(&all)], &[]).await.unwrap();
+    assert_eq!(env.lamports(bonded.pubkey()).await, bonded_before + 3_000);
+    assert_eq!(
+        env.lamports(pool).await,
+        env.rent(RewardPool::SPACE).await + spare
+    );
+}
+
+fn set_timelock_ix(owner: &Keypair, repository: Pubkey, delay: i64, queued: bool) -> Instruction {
+    ix(
+        accounts::SetTimelock {
+            owner: owner.pubkey(),
+            repository,
+            timelock: pda::timelock(&repository),
+            pending_action: queued.then(|| pda::pending(&repository)),
+            system_program: system_program(),
+        },
+        instruction::SetTimelock { delay },
+    )
+}
+
+fn queue_ix(owner: &Keypair, repository: Pubkey, action: TimelockedAction) -> Instruction {
+    ix(
+        accounts::QueueAction {
+            owner: owner.pubkey(),
+            repository,
+            timelock: pda::timelock(&repository),
+            pending_action: pda::pending(&repository),
+            system_program: system_program(),
+        },
+        instruction::QueueAction { action },
+    )
+}
+
+fn cancel_ix(owner: &Keypair, repository: Pubkey) -> Instruction {
+    ix(
+        accounts::CancelAction {
+            owner: owner.pubkey(),
+            repository,
+            pending_action: pda::pending(&repository),
+        },
+        instruction::CancelAction {},
+    )
+}
+
+fn timelocked_transfer_ix(
+    owner: &Keypair,
+    repository: Pubkey,
+    new_owner: Pubkey,
+    audit_entry: Pubkey,
+    queued: bool,
+) -> Instruction {
+    ix(
+        accounts::TransferOwnership {
+            owner: owner.pubkey(),
+            repository,
+            timelock: pda::timelock(&repository),
+            pending_action: queued.then(|| pda::pending(&repository)),
+            audit_entry,
+            system_program: system_program(),
+        },
+        instruction::TransferOwnership { new_owner },
+    )
+}
+
+#[tokio::test]
+async fn timelock_delays_ownership_transfer() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let new_owner = env.user().await;
+    let repository = env.create_repo(&owner, "repo", false).await;
+    env.send(
+        &[set_timelock_ix(&owner, repository, DAY, false)],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+
+    let audit_entry = env.audit_entry(repository).await;
+    let transfer = |to: Pubkey, queued: bool| {
+        timelocked_transfer_ix(&owner, repository, to, audit_entry, queued)
+    };
+    assert_error(
+        env.send(&[transfer(new_owner.pubkey(), false)], &[&owner])
+            .await,
+        VanishError::ActionNotQueued,
+    );
+
+    let action = TimelockedAction::TransferOwnership {
+        new_owner: new_owner.pubkey(),
+    };
+    env.send(&[queue_ix(&owner, repository, action.clone())], &[&owner])
+        .await
+        .unwrap();
+    assert_error(
+        env.send(&[queue_ix(&owner, repository, action)], &[&owner])
+            .await,
+        ACCOUNT_ALREADY_IN_USE,
+    );
+    assert_error(
+        env.send(&[transfer(new_owner.pubkey(), true)], &[&owner])
+            .await,
+        VanishError::TimelockPending,
+    );
+
+    env.warp(DAY).await;
+    assert_error(
+        env.send(&[transfer(owner.pubkey(), true)], &[&owner]).await,
+        VanishError::ActionMismatch,
+    );
+    env.send(&[transfer(new_owner.pubkey(), true)], &[&owner])
+        .await
+        .unwrap();
+    let repo: Repository = env.account(repository).await;
+    assert_eq!(repo.owner, new_owner.pubkey());
+    assert!(!env.exists(pda::pending(&repository)).await);
+}
+
+#[tokio::test]
+async fn timelock_is_lowered_through_the_queue() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let stranger = env.user().await;
+    let repository = env.create_repo(&owner, "repo", false).await;
+
+    assert_error(
+        env.send(
+            &[queue_ix(&owner, repository, TimelockedAction::Delete)],
+            &[&owner],
+        )
+        .await,
+        AnchorError::AccountNotInitialized,
+    );
+    assert_error(
+        env.send(
+            &[set_timelock_ix(
+                &owner,
+                repository,
+                Timelock::MAX_DELAY + 1,
+                false,
+            )],
+            &[&owner],
+        )
+        .await,
+        VanishError::InvalidTimelockDelay,
+    );
+    env.send(
+        &[set_timelock_ix(&owner, repository, DAY, false)],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+
+    // Raising is immediate, lowering is not
+    env.send(
+        &[set_timelock_ix(&owner, repository, 2 * DAY, false)],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+    assert_error(
+        env.send(
+            &[set_timelock_ix(&owner, repository, DAY, false)],
+            &[&owner],
+        )
+        .await,
+        VanishError::ActionNotQueued,
+    );
+    assert_error(
+        env.send(
+            &[queue_ix(
+                &owner,
+                repository,
+                TimelockedAction::SetDelay { delay: 3 * DAY },
+            )],
+            &[&owner],
+        )
+        .await,
+        VanishError::InvalidTimelockDelay,
+    );
+
+    // Queued actions can be cancelled by the owner only
+    let lower = TimelockedAction::SetDelay { delay: 0 };
+    env.send(&[queue_ix(&owner, repository, lower.clone())], &[&owner])
+        .await
+        .unwrap();
+    assert_error(
+        env.send(&[cancel_ix(&stranger, repository)], &[&stranger])
+            .await,
+        AnchorError::ConstraintHasOne,
+    );
+    env.send(&[cancel_ix(&owner, repository)], &[&owner])
+        .await
+        .unwrap();
+    assert!(!env.exists(pda::pending(&repository)).await);
+
+    env.send(&[queue_ix(&owner, repository, lower)], &[&owner])
+        .await
+        .unwrap();
+    env.warp(2 * DAY).await;
+    env.send(&[set_timelock_ix(&owner, repository, 0, true)], &[&owner])
+        .await
+        .unwrap();
+    let timelock: Timelock = env.account(pda::timelock(&repository)).await;
+    assert_eq!(timelock.delay, 0);
+
+    // Without a delay nothing waits, and nothing can be queued
+    assert_error(
+        env.send(
+            &[queue_ix(&owner, repository, TimelockedAction::Delete)],
+            &[&owner],
+        )
+        .await,
+        VanishError::NoTimelock,
+    );
+    let audit_entry = env.audit_entry(repository).await;
+    env.send(
+        &[timelocked_transfer_ix(
+            &owner,
+            repository,
+            stranger.pubkey(),
+            audit_entry,
+            false,
+        )],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn rename_repo_keeps_children_and_leaves_redirect() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let writer = env.user().await;
+    let repository = env.create_repo(&owner, "old-name", false).await;
+    env.add_collaborator(&owner, repository, writer.pubkey(), true, false)
+        .await;
+
+    let new_repository = pda::repo(&owner.pubkey(), "new-name");
+    let audit_entry = env.audit_entry(repository).await;
+    let rename = |redirect: bool, leave_redirect: bool| {
+        ix(
+            accounts::RenameRepo {
+                authority: owner.pubkey(),
+                repository,
+                admin: None,
+                new_repository,
+                tombstone: pda::tombstone(&repository),
+                name_lock: Some(pda::name_lock(&owner.pubkey(), "old-name")),
+                new_name_lock: pda::name_lock(&owner.pubkey(), "new-name"),
+                config: pda::config(),
+                redirect: redirect.then(|| pda::redirect(&owner.pubkey(), "old-name")),
+                timelock: pda::timelock(&repository),
+                pending_action: None,
+                audit_entry,
+                system_program: system_program(),
+            },
+            instruction::RenameRepo {
+                new_name: "new-name".to_string(),
+                leave_redirect,
+            },
+        )
+    };
+
+    assert_error(
+        env.send(&[rename(false, true)], &[&owner]).await,
+        VanishError::InvalidRedirect,
+    );
+    assert_error(
+        env.send(&[rename(true, false)], &[&owner]).await,
+        VanishError::InvalidRedirect,
+    );
+
+    env.send(&[rename(true, true)], &[&owner]).await.unwrap();
+    assert!(!env.exists(repository).await);
+
+    let repo: Repository = env.account(new_repository).await;
+    assert_eq!(repo.name, "new-name");
+    assert_eq!(repo.id, repository);
+    assert!(
+        !env.exists(pda::name_lock(&owner.pubkey(), "old-name"))
+            .await
+    );
+    assert!(
+        env.exists(pda::name_lock(&owner.pubkey(), "new-name"))
+            .await
+    );
+
+    // So does the audit log
+    let entry: AuditEntry = env.account(audit_entry).await;
+    assert!(entry.action == AuditAction::Renamed);
+    assert_eq!(entry.subject, new_repository);
+    assert_eq!(
+        env.audit_entry(new_repository).await,
+        pda::audit(&repository, 2)
+    );
+
+    let redirect: Redirect = env
+        .account(pda::redirect(&owner.pubkey(), "old-name"))
+        .await;
+    assert_eq!(redirect.target, new_repository);
+    assert_eq!(redirect.name, "old-name");
+
+    // Collaborators are seeded by the stable id, so they keep working
+    let mut push = push_ix(
+        writer.pubkey(),
+        new_repository,
+        Some(pda::collab(&repository, &writer.pubkey())),
+        sha1(1),
+    );
+    push.accounts[3].pubkey = pda::contribution(&repository, &writer.pubkey());
+    push.accounts[5].pubkey = pda::project_repo(&repository);
+    env.send(&[push], &[&writer]).await.unwrap();
+
+    // A repository created under the old name takes a fresh id, so the
+    // renamed repository's children stay its own
+    let tombstone: Tombstone = env.account(pda::tombstone(&repository)).await;
+    assert_eq!(tombstone.generation, 1);
+    env.create_repo(&owner, "old-name", false).await;
+    let newcomer: Repository = env.account(repository).await;
+    assert_eq!(newcomer.id, Repository::id_at(&repository, 1));
+    assert_ne!(newcomer.id, repo.id);
+}
+
+#[tokio::test]
+async fn rename_repo_requires_admin() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let stranger = env.user().await;
+    let repository = env.create_repo(&owner, "repo", false).await;
+
+    let rename = ix(
+        accounts::RenameRepo {
+            authority: stranger.pubkey(),
+            repository,
+            admin: None,
+            new_repository: pda::repo(&owner.pubkey(), "stolen"),
+            tombstone: pda::tombstone(&repository),
+            name_lock: Some(pda::name_lock(&owner.pubkey(), "repo")),
+            new_name_lock: pda::name_lock(&owner.pubkey(), "stolen"),
+            config: pda::config(),
+            redirect: None,
+            timelock: pda::timelock(&repository),
+            pending_action: None,
+            audit_entry: env.audit_entry(repository).await,
+            system_program: system_program(),
+        },
+        instruction::RenameRepo {
+            new_name: "stolen".to_string(),
+            leave_redirect: false,
+        },
+    );
+    assert_error(
+        env.send(&[rename], &[&stranger]).await,
+        VanishError::Unauthorized,
+    );
+}
+
+fn claim_name_ix(authority: &Keypair, admin: bool, repository: Pubkey, name: &str) -> Instruction {
+    ix(
+        accounts::ClaimRepoName {
+            authority: authority.pubkey(),
+            repository,
+            admin: admin.then(|| pda::collab(&repository, &authority.pubkey())),
+            repo_name: pda::repo_name(name),
+            system_program: system_program(),
+        },
+        instruction::ClaimRepoName {},
+    )
+}
+
+fn release_name_ix(
+    authority: &Keypair,
+    claimer: Pubkey,
+    repository: Pubkey,
+    name: &str,
+) -> Instruction {
+    ix(
+        accounts::ReleaseRepoName {
+            authority: authority.pubkey(),
+            repo_name: pda::repo_name(name),
+            claimer,
+            repository,
+            admin: None,
+        },
+        instruction::ReleaseRepoName {},
+    )
+}
+
+#[tokio::test]
+async fn claim_repo_name_is_first_come() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let admin = env.user().await;
+    let rival = env.user().await;
+    let repository = env.create_repo(&owner, "vanish", false).await;
+    let rival_repository = env.create_repo(&rival, "vanish", false).await;
+    env.add_admin(&owner, repository, admin.pubkey()).await;
+
+    assert_error(
+        env.send(
+            &[claim_name_ix(&rival, false, repository, "vanish")],
+            &[&rival],
+        )
+        .await,
+        VanishError::Unauthorized,
+    );
+
+    env.send(
+        &[claim_name_ix(&admin, true, repository, "vanish")],
+        &[&admin],
+    )
+    .await
+    .unwrap();
+    let entry: RepoName = env.account(pda::repo_name("vanish")).await;
+    assert_eq!(entry.name, "vanish");
+    assert_eq!(entry.creator, owner.pubkey());
+    assert_eq!(entry.repository, repository);
+    assert_eq!(entry.claimer, admin.pubkey());
+    // The entry resolves to the repository from the name alone
+    assert_eq!(pda::repo(&entry.creator, &entry.name), repository);
+
+    assert_error(
+        env.send(
+            &[claim_name_ix(&rival, false, rival_repository, "vanish")],
+            &[&rival],
+        )
+        .await,
+        ACCOUNT_ALREADY_IN_USE,
+    );
+}
+
+#[tokio::test]
+async fn release_repo_name_once_the_repository_moves() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let stranger = env.user().await;
+    let repository = env.create_repo(&owner, "vanish", false).await;
+    env.send(
+        &[claim_name_ix(&owner, false, repository, "vanish")],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+
+    assert_error(
+        env.send(
+            &[release_name_ix(
+                &stranger,
+                owner.pubkey(),
+                repository,
+                "vanish",
+            )],
+            &[&stranger],
+        )
+        .await,
+        VanishError::Unauthorized,
+    );
+
+    let rename = ix(
+        accounts::RenameRepo {
+            authority: owner.pubkey(),
+            repository,
+            admin: None,
+            new_repository: pda::repo(&owner.pubkey(), "renamed"),
+            tombstone: pda::tombstone(&repository),
+            name_lock: Some(pda::name_lock(&owner.pubkey(), "vanish")),
+            new_name_lock: pda::name_lock(&owner.pubkey(), "renamed"),
+            config: pda::config(),
+            redirect: None,
+            timelock: pda::timelock(&repository),
+            pending_action: None,
+            audit_entry: env.audit_entry(repository).await,
+            system_program: system_program(),
+        },
+        instruction::RenameRepo {
+            new_name: "renamed".to_string(),
+            leave_redirect: false,
+        },
+    );
+    env.send(&[rename], &[&owner]).await.unwrap();
+
+    // The entry is stale now, so anyone may clear it; the rent still goes
+    // back to the claimer
+    assert_error(
+        env.send(
+            &[release_name_ix(
+                &stranger,
+                stranger.pubkey(),
+                repository,
+                "vanish",
+            )],
+            &[&stranger],
+        )
+        .await,
+        AnchorError::ConstraintHasOne,
+    );
+    let before = env.lamports(owner.pubkey()).await;
+    env.send(
+        &[release_name_ix(
+            &stranger,
+            owner.pubkey(),
+            repository,
+            "vanish",
+        )],
+        &[&stranger],
+    )
+    .await
+    .unwrap();
+    assert!(!env.exists(pda::repo_name("vanish")).await);
+    assert_eq!(
+        env.lamports(owner.pubkey()).await,
+        before + env.rent(RepoName::SPACE).await
+    );
+}
+
+#[tokio::test]
+async fn resize_repository_grows_old_accounts() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let stranger = env.user().await;
+    let repository = env.create_repo(&owner, "repo", false).await;
+
+    // Stand in for an account created under a smaller layout
+    let old_space = Repository::SPACE - 64;
+    let repo: Repository = env.account(repository).await;
+    env.set_state(repository, &repo, old_space).await;
+
+    let resize = |authority: &Keypair| {
+        ix(
+            accounts::ResizeRepository {
+                authority: authority.pubkey(),
+                repository,
+                admin: None,
+                system_program: system_program(),
+            },
+            instruction::ResizeRepository {},
+        )
+    };
+
+    assert_error(
+        env.send(&[resize(&stranger)], &[&stranger]).await,
+        VanishError::Unauthorized,
+    );
+
+    let before = env.lamports(owner.pubkey()).await;
+    env.send(&[resize(&owner)], &[&owner]).await.unwrap();
+    assert_eq!(env.data_len(repository).await, Repository::SPACE);
+    assert_eq!(
+        before - env.lamports(owner.pubkey()).await,
+        env.rent(Repository::SPACE).await - env.rent(old_space).await
+    );
+    let repo: Repository = env.account(repository).await;
+    assert_eq!(repo.name, "repo");
+    assert_eq!(repo.owner, owner.pubkey());
+}
+
+#[tokio::test]
+async fn migrate_account_upgrades_old_layouts() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let fan = env.user().await;
+    let writer = Keypair::new().pubkey();
+    let repository = env.create_repo(&owner, "repo", false).await;
+    env.add_collaborator(&owner, repository, writer, true, false)
+        .await;
+    let star = ix(
+        accounts::StarRepo {
+            user: fan.pubkey(),
+            repository,
+            star_account: pda::star(&fan.pubkey(), &repository),
+            donation_vault: pda::donations(&repository),
+            profile: pda::profile(&fan.pubkey()),
+            config: pda::config(),
+            system_program: system_program(),
+        },
+        instruction::StarRepo { tip: 0 },
+    );
+    env.send(&[star], &[&fan]).await.unwrap();
+
+    let migrate = |account: Pubkey| {
+        ix(
+            accounts::MigrateAccount {
+                payer: fan.pubkey(),
+                account,
+                system_program: system_program(),
+            },
+            instruction::MigrateAccount {},
+        )
+    };
+
+    // Accounts created today are already current
+    assert_error(
+        env.send(&[migrate(repository)], &[&fan]).await,
+        VanishError::AccountUpToDate,
+    );
+
+    // Roll the repository and collaborator back to the layouts first
+    // deployed, written field by field as that program stored them
+    let repo: Repository = env.account(repository).await;
+    let legacy_repo = |owner: Pubkey| {
+        let mut data = Repository::DISCRIMINATOR.to_vec();
+        (
+            owner,
+            "repo".to_string(),
+            "first".to_string(),
+            false,
+            1i64,
+            2i64,
+            "01".repeat(20),
+            PUSH_CID.to_string(),
+            3u64,
+            repo.bump,
+        )
+            .serialize(&mut data)
+            .unwrap();
+        data.resize(8 + 32 + 68 + 260 + 1 + 8 + 8 + 44 + 68 + 8 + 1, 0);
+        data
+    };
+    // Seeded by its owner, so a transferred one cannot be placed
+    env.set_data(repository, legacy_repo(fan.pubkey())).await;
+    assert_error(
+        env.send(&[migrate(repository)], &[&fan]).await,
+        VanishError::UnsupportedAccount,
+    );
+    env.set_data(repository, legacy_repo(owner.pubkey())).await;
+
+    // The low byte of `added_at` sits where `is_admin` does now
+    let added_at = 0x6553_f101i64;
+    let collab_address = pda::collab(&repository, &writer);
+    let mut data = Collaborator::DISCRIMINATOR.to_vec();
+    let collab: Collaborator = env.account(collab_address).await;
+    (repository, writer, true, added_at, collab.bump)
+        .serialize(&mut data)
+        .unwrap();
+    assert_eq!(data.len(), 8 + 32 + 32 + 1 + 8 + 1);
+    env.set_data(collab_address, data).await;
+
+    // The star and branch go back to their layouts without a version byte
+    let star_address = pda::star(&fan.pubkey(), &repository);
+    let mut star: Star = env.account(star_address).await;
+    star.version = 0;
+    env.set_state(star_address, &star, Star::SPACE - 1).await;
+    let branch_address = pda::branch(&repository, "main");
+    let branch = Branch {
+        repository,
+        name: "main".to_string(),
+        head_commit: sha1(1),
+        ipfs_cid: "QmRefs".to_string(),
+        updated_at: 0,
+        bump: 0,
+        allow_force_push: false,
+        version: 0,
+        ref_index: None,
+    };
+    env.set_state(branch_address, &branch, Branch::SPACE - 7)
+        .await;
+
+    for (address, space) in [
+        (repository, Repository::SPACE),
+        (collab_address, Collaborator::SPACE),
+        (star_address, Star::SPACE),
+        (branch_address, Branch::SPACE),
+    ] {
+        env.send(&[migrate(address)], &[&fan]).await.unwrap();
+        assert_eq!(env.data_len(address).await, space);
+        assert_eq!(env.lamports(address).await, env.rent(space).await);
+    }
+
+    let repo: Repository = env.account(repository).await;
+    assert_eq!(repo.version, Repository::VERSION);
+    assert_eq!(repo.name, "repo");
+    assert_eq!(repo.description, "first");
+    assert_eq!((repo.owner, repo.creator), (owner.pubkey(), owner.pubkey()));
+    assert_eq!(repo.id, repository);
+    assert_eq!((repo.created_at, repo.updated_at, repo.stars), (1, 2, 3));
+    assert!(repo.head_commit == sha1(1));
+    assert_eq!(repo.ipfs_cid.to_string(), PUSH_CID);
+    let collab: Collaborator = env.account(collab_address).await;
+    assert_eq!(collab.version, Collaborator::VERSION);
+    assert_eq!((collab.repository, collab.user), (repository, writer));
+    assert!(collab.can_push);
+    assert!(!collab.is_admin);
+    assert_eq!(collab.added_at, added_at);
+    assert_eq!(collab.index, None);
+    let star: Star = env.account(star_address).await;
+    assert_eq!(star.version, Star::VERSION);
+    assert_eq!(star.user, fan.pubkey());
+    let branch: Branch = env.account(branch_address).await;
+    assert_eq!(branch.version, Branch::VERSION);
+    assert!(branch.head_commit == sha1(1));
+    assert!(!branch.allow_force_push);
+
+    // Other account types have no versioned layout
+    let redirect = Keypair::new().pubkey();
+    let state = Redirect {
+        creator: owner.pubkey(),
+        target: repository,
+        name: "old".to_string(),
+        created_at: 0,
+        bump: 0,
+    };
+    env.set_state(redirect, &state, Redirect::SPACE).await;
+    assert_error(
+        env.send(&[migrate(redirect)], &[&fan]).await,
+        VanishError::UnsupportedAccount,
+    );
+    assert_error(
+        env.send(&[migrate(owner.pubkey())], &[&fan]).await,
+        AnchorError::ConstraintOwner,
+    );
+}
+
+#[tokio::test]
+async fn star_and_unstar() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let fan = env.user().await;
+    let repository = env.create_repo(&owner, "repo", false).await;
+
+    let star = ix(
+        accounts::StarRepo {
+            user: fan.pubkey(),
+            repository,
+            star_account: pda::star(&fan.pubkey(), &repository),
+            donation_vault: pda::donations(&repository),
+            profile: pda::profile(&fan.pubkey()),
+            config: pda::config(),
+            system_program: system_program(),
+        },
+        instruction::StarRepo { tip: 0 },
+    );
+    let unstar = ix(
+        accounts::UnstarRepo {
+            user: fan.pubkey(),
+            repository,
+            star_account: pda::star(&fan.pubkey(), &repository),
+        },
+        instruction::UnstarRepo {},
+    );
+
+    env.send(std::slice::from_ref(&star), &[&fan])
+        .await
+        .unwrap();
+    let repo: Repository = env.account(repository).await;
+    assert_eq!(repo.stars, 1);
+
+    assert_error(env.send(&[star], &[&fan]).await, ACCOUNT_ALREADY_IN_USE);
+
+    env.send(std::slice::from_ref(&unstar), &[&fan])
+        .await
+        .unwrap();
+    let repo: Repository = env.account(repository).await;
+    assert_eq!(repo.stars, 0);
+    assert!(!env.exists(pda::star(&fan.pubkey(), &repository)).await);
+
+    assert_error(
+        env.send(&[unstar], &[&fan]).await,
+        AnchorError::AccountNotInitialized,
+    );
+}
+
+#[tokio::test]
+async fn star_tips_go_to_the_donation_vault() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let fan = env.user().await;
+    let stranger = env.user().await;
+    let repository = env.create_repo(&owner, "repo", false).await;
+    let vault = pda::donations(&repository);
+    let rent_minimum = env.rent(0).await;
+
+    let star = |user: &Keypair, tip: u64| {
+        ix(
+            accounts::StarRepo {
+                user: user.pubkey(),
+                repository,
+                star_account: pda::star(&user.pubkey(), &repository),
+                donation_vault: vault,
+                profile: pda::profile(&user.pubkey()),
+                config: pda::config(),
+                system_program: system_program(),
+            },
+            instruction::StarRepo { tip },
+        )
+    };
+    assert_error(
+        env.send(&[star(&fan, rent_minimum - 1)], &[&fan]).await,
+        VanishError::TipBelowRent,
+    );
+    env.send(&[star(&fan, rent_minimum + 5_000)], &[&fan])
+        .await
+        .unwrap();
+    assert_eq!(env.lamports(vault).await, rent_minimum + 5_000);
+
+    // Once the vault holds its rent, any tip goes
+    env.send(&[star(&stranger, 1)], &[&stranger]).await.unwrap();
+    assert_eq!(env.lamports(vault).await, rent_minimum + 5_001);
+    let repo: Repository = env.account(repository).await;
+    assert_eq!(repo.stars, 2);
+
+    let withdraw = |owner: &Keypair, amount: u64| {
+        ix(
+            accounts::WithdrawDonations {
+                owner: owner.pubkey(),
+                repository,
+                donation_vault: vault,
+                system_program: system_program(),
+            },
+            instruction::WithdrawDonations { amount },
+        )
+    };
+    assert_error(
+        env.send(&[withdraw(&stranger, 5_001)], &[&stranger]).await,
+        AnchorError::ConstraintHasOne,
+    );
+    assert_error(
+        env.send(&[withdraw(&owner, 5_002)], &[&owner]).await,
+        VanishError::InsufficientDonations,
+    );
+
+    let before = env.lamports(owner.pubkey()).await;
+    env.send(&[withdraw(&owner, 5_001)], &[&owner])
+        .await
+        .unwrap();
+    assert_eq!(env.lamports(vault).await, rent_minimum);
+    assert!(env.lamports(owner.pubkey()).await > before);
+}
+
+#[tokio::test]
+async fn reconcile_stars_recounts_star_accounts() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let first = env.user().await;
+    let second = env.user().await;
+    let payer = env.payer();
+    let repository = env.create_repo(&owner, "repo", false).await;
+    let other = env.create_repo(&owner, "other", false).await;
+
+    let star = |user: &Keypair, repository: Pubkey| {
+        ix(
+            accounts::StarRepo {
+                user: user.pubkey(),
+                repository,
+                star_account: pda::star(&user.pubkey(), &repository),
+                donation_vault: pda::donations(&repository),
+                profile: pda::profile(&user.pubkey()),
+                config: pda::config(),
+                system_program: system_program(),
+            },
+            instruction::StarRepo { tip: 0 },
+        )
+    };
+    env.send(&[star(&first, repository)], &[&first])
+        .await
+        .unwrap();
+    env.send(&[star(&second, repository)], &[&second])
+        .await
+        .unwrap();
+    env.send(&[star(&first, other)], &[&first]).await.unwrap();
+
+    let mut stars = vec![
+        pda::star(&first.pubkey(), &repository),
+        pda::star(&second.pubkey(), &repository),
+    ];
+    stars.sort();
+    let reconcile = |admin: Option<Pubkey>, compressed: u64, stars: &[Pubkey]| {
+        let mut reconcile = ix(
+            accounts::ReconcileStars {
+                repository,
+                config: pda::config(),
+                admin,
+            },
+            instruction::ReconcileStars { compressed },
+        );
+        for star in stars {
+            reconcile
+                .accounts
+                .push(AccountMeta::new_readonly(*star, false));
+        }
+        reconcile
+    };
+    let set_stars = |mut repo: Repository, stars: u64| {
+        repo.stars = stars;
+        repo
+    };
+
+    // A counter that fell behind is raised by anyone
+    let repo = set_stars(env.account(repository).await, 0);
+    env.set_state(repository, &repo, Repository::SPACE).await;
+    env.send(&[reconcile(None, 0, &stars)], &[]).await.unwrap();
+    let repo: Repository = env.account(repository).await;
+    assert_eq!(repo.stars, 2);
+
+    // Lowering it, or counting compressed stars, takes the config admin
+    env.set_state(repository, &set_stars(repo, 7), Repository::SPACE)
+        .await;
+    env.send(&[reconcile(None, 0, &stars)], &[]).await.unwrap();
+    let repo: Repository = env.account(repository).await;
+    assert_eq!(repo.stars, 7);
+    let stranger = env.user().await;
+    assert_error(
+        env.send(
+            &[reconcile(Some(stranger.pubkey()), 1, &stars)],
+            &[&stranger],
+        )
+        .await,
+        VanishError::Unauthorized,
+    );
+    env.send(&[reconcile(Some(payer), 1, &stars)], &[])
+        .await
+        .unwrap();
+    let repo: Repository = env.account(repository).await;
+    assert_eq!(repo.stars, 3);
+
+    let reversed: Vec<Pubkey> = stars.iter().rev().copied().collect();
+    let foreign = [stars[0], stars[1], pda::star(&first.pubkey(), &other)];
+    for stars in [&reversed[..], &[stars[0], stars[0]], &foreign] {
+        assert_error(
+            env.send(&[reconcile(Some(payer), 0, stars)], &[]).await,
+            VanishError::InvalidStarAccounts,
+        );
+    }
+}
+
+#[tokio::test]
+async fn weighted_stars_decay_over_time() {
+    const WEIGHT: u64 = Repository::STAR_WEIGHT;
+    const HALF_LIFE: i64 = Repository::STAR_HALF_LIFE;
+
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let early = env.user().await;
+    let late = env.user().await;
+    let repository = env.create_repo(&owner, "repo", false).await;
+
+    let star = |user: &Keypair| {
+        ix(
+            accounts::StarRepo {
+                user: user.pubkey(),
+                repository,
+                star_account: pda::star(&user.pubkey(), &repository),
+                donation_vault: pda::donations(&repository),
+                profile: pda::profile(&user.pubkey()),
+                config: pda::config(),
+                system_program: system_program(),
+            },
+            instruction::StarRepo { tip: 0 },
+        )
+    };
+    let decay = ix(
+        accounts::DecayStars { repository },
+        instruction::DecayStars {},
+    );
+    let weighted_stars = |repo: Repository| repo.weighted_stars;
+    let assert_near = |actual: u64, expected: u64| {
+        assert!(actual.abs_diff(expected) <= 2, "{actual} != {expected}");
+    };
+
+    env.send(&[star(&early)], &[&early]).await.unwrap();
+    assert_eq!(weighted_stars(env.account(repository).await), WEIGHT);
+
+    env.warp(HALF_LIFE).await;
+    env.send(std::slice::from_ref(&decay), &[]).await.unwrap();
+    assert_near(weighted_stars(env.account(repository).await), WEIGHT / 2);
+    env.send(&[star(&late)], &[&late]).await.unwrap();
+    assert_near(
+        weighted_stars(env.account(repository).await),
+        WEIGHT + WEIGHT / 2,
+    );
+
+    // Decaying in steps lands where decaying at once would
+    for _ in 0..4 {
+        env.warp(HALF_LIFE / 4).await;
+        env.send(std::slice::from_ref(&decay), &[]).await.unwrap();
+    }
+    assert_near(
+        weighted_stars(env.account(repository).await),
+        (WEIGHT + WEIGHT / 2) / 2,
+    );
+    assert_near(
+        Repository::decayed(WEIGHT, HALF_LIFE / 2),
+        (WEIGHT as f64 / 2f64.sqrt()) as u64,
+    );
+
+    // Unstarring takes off what is left of that star's weight
+    let unstar = ix(
+        accounts::UnstarRepo {
+            user: early.pubkey(),
+            repository,
+            star_account: pda::star(&early.pubkey(), &repository),
+        },
+        instruction::UnstarRepo {},
+    );
+    env.send(&[unstar], &[&early]).await.unwrap();
+    assert_near(weighted_stars(env.account(repository).await), WEIGHT / 2);
+}
+
+#[tokio::test]
+async fn compressed_stars_go_through_the_star_tree() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let fan = env.user().await;
+    let repository = env.create_repo(&owner, "repo", false).await;
+    let repo: Repository = env.account(repository).await;
+
+    let merkle_tree = Keypair::new().pubkey();
+    let account = Account {
+        lamports: env.rent(256).await,
+        data: vec![0; 256],
+        owner: compression::ID,
+        executable: false,
+        rent_epoch: 0,
+    };
+    env.ctx.set_account(&merkle_tree, &account.into());
+
+    let init = |merkle_tree: Pubkey| {
+        ix(
+            accounts::InitStarTree {
+                payer: owner.pubkey(),
+                star_tree: pda::star_tree(),
+                merkle_tree,
+                compression_program: compression::ID,
+                noop_program: compression::noop::ID,
+                system_program: system_program(),
+            },
+            instruction::InitStarTree {
+                max_depth: 14,
+                max_buffer_size: 64,
+            },
+        )
+    };
+    let star = ix(
+        accounts::CompressedStar {
+            user: fan.pubkey(),
+            repository,
+            star_tree: pda::star_tree(),
+            merkle_tree,
+            compression_program: compression::ID,
+            noop_program: compression::noop::ID,
+            profile: pda::profile(&fan.pubkey()),
+            config: pda::config(),
+            system_program: system_program(),
+        },
+        instruction::StarRepoCompressed {},
+    );
+    let unstar = |merkle_tree: Pubkey| {
+        let mut unstar = ix(
+            accounts::CompressedStar {
+                user: fan.pubkey(),
+                repository,
+                star_tree: pda::star_tree(),
+                merkle_tree,
+                compression_program: compression::ID,
+                noop_program: compression::noop::ID,
+                profile: pda::profile(&fan.pubkey()),
+                config: pda::config(),
+                system_program: system_program(),
+            },
+            instruction::UnstarRepoCompressed {
+                root: [7; 32],
+                index: 3,
+            },
+        );
+        // One proof node
+        unstar
+            .accounts
+            .push(AccountMeta::new_readonly(Pubkey::new_unique(), false));
+        unstar
+    };
+    let tree_data = |name: &str, args: &[&[u8]]| {
+        let mut data = hash(format!("global:{name}").as_bytes()).to_bytes()[..8].to_vec();
+        args.iter().for_each(|arg| data.extend_from_slice(arg));
+        data.resize(256, 0);
+        data
+    };
+
+    // The tree must already belong to the compression program
+    assert_error(
+        env.send(&[init(Keypair::new().pubkey())], &[&owner]).await,
+        AnchorError::ConstraintOwner,
+    );
+    env.send(&[init(merkle_tree)], &[&owner]).await.unwrap();
+    assert_eq!(
+        env.data(merkle_tree).await,
+        tree_data(
+            "init_empty_merkle_tree",
+            &[&14u32.to_le_bytes(), &64u32.to_le_bytes()]
+        )
+    );
+
+    let leaf = compression::star_leaf(&fan.pubkey(), &repo.id);
+    env.send(&[star], &[&fan]).await.unwrap();
+    assert_eq!(env.data(merkle_tree).await, tree_data("append", &[&leaf]));
+    // The tree cannot tell repeat stars apart, so the counter waits for
+    // reconcile_stars
+    let repo: Repository = env.account(repository).await;
+    assert_eq!(repo.stars, 0);
+
+    env.send(&[unstar(merkle_tree)], &[&fan]).await.unwrap();
+    assert_eq!(
+        env.data(merkle_tree).await,
+        tree_data(
+            "replace_leaf",
+            &[&[7; 32], &leaf, &[0; 32], &3u32.to_le_bytes()]
+        )
+    );
+    let repo: Repository = env.account(repository).await;
+    assert_eq!(repo.stars, 0);
+
+    // Only the tree the StarTree points at can be modified
+    assert_error(
+        env.send(&[unstar(Keypair::new().pubkey())], &[&fan]).await,
+        AnchorError::ConstraintHasOne,
+    );
+}
+
+#[tokio::test]
+async fn lists_curate_repositories() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let curator = env.user().await;
+    let stranger = env.user().await;
+    let first = env.create_repo(&owner, "repo", false).await;
+    let second = env.create_repo(&owner, "other", false).await;
+    let list = pda::list(&curator.pubkey(), "rust");
+
+    let create = |name: &str| {
+        ix(
+            accounts::CreateList {
+                owner: curator.pubkey(),
+                list: pda::list(&curator.pubkey(), name),
+                system_program: system_program(),
+            },
+            instruction::CreateList {
+                name: name.to_string(),
+                description: "Crates worth a look".to_string(),
+                is_private: false,
+            },
+        )
+    };
+    assert_error(
+        env.send(&[create("")], &[&curator]).await,
+        VanishError::InvalidListName,
+    );
+    env.send(&[create("rust")], &[&curator]).await.unwrap();
+
+    let add = |owner: &Keypair, repository: Pubkey| {
+        ix(
+            accounts::AddToList {
+                owner: owner.pubkey(),
+                list,
+                repository,
+                list_entry: pda::list_entry(&list, &repository),
+                system_program: system_program(),
+            },
+            instruction::AddToList {},
+        )
+    };
+    let remove = |repository: Pubkey| {
+        ix(
+            accounts::RemoveFromList {
+                owner: curator.pubkey(),
+                list,
+                list_entry: pda::list_entry(&list, &repository),
+            },
+            instruction::RemoveFromList {},
+        )
+    };
+    assert_error(
+        env.send(&[add(&stranger, first)], &[&stranger]).await,
+        AnchorError::ConstraintHasOne,
+    );
+    env.send(&[add(&curator, first), add(&curator, second)], &[&curator])
+        .await
+        .unwrap();
+    assert_error(
+        env.send(&[add(&curator, first)], &[&curator]).await,
+        ACCOUNT_ALREADY_IN_USE,
+    );
+    let entry: ListEntry = env.account(pda::list_entry(&list, &first)).await;
+    assert_eq!(entry.list, list);
+    assert_eq!(entry.repository, first);
+
+    env.send(
+        &[ix(
+            accounts::UpdateList {
+                owner: curator.pubkey(),
+                list,
+            },
+            instruction::UpdateList {
+                description: String::new(),
+                is_private: true,
+            },
+        )],
+        &[&curator],
+    )
+    .await
+    .unwrap();
+    let state: List = env.account(list).await;
+    assert_eq!(state.name, "rust");
+    assert!(state.is_private);
+    assert_eq!(state.entry_count, 2);
+
+    let delete = ix(
+        accounts::DeleteList {
+            owner: curator.pubkey(),
+            list,
+        },
+        instruction::DeleteList {},
+    );
+    assert_error(
+        env.send(std::slice::from_ref(&delete), &[&curator]).await,
+        VanishError::ListNotEmpty,
+    );
+    env.send(&[remove(first), remove(second)], &[&curator])
+        .await
+        .unwrap();
+    assert!(!env.exists(pda::list_entry(&list, &first)).await);
+    env.send(&[delete], &[&curator]).await.unwrap();
+    assert!(!env.exists(list).await);
+}
+
+#[tokio::test]
+async fn activity_cursor_only_moves_forward() {
+    let mut env = Env::new().await;
+    let user = env.user().await;
+    let cursor = pda::activity(&user.pubkey());
+
+    let mut clock: Clock = env.ctx.banks_client.get_sysvar().await.unwrap();
+    clock.slot = 100;
+    env.ctx.set_sysvar(&clock);
+
+    let mark = |slot: u64| {
+        ix(
+            accounts::MarkActivitySeen {
+                user: user.pubkey(),
+                activity_cursor: cursor,
+                system_program: system_program(),
+            },
+            instruction::MarkActivitySeen { slot },
+        )
+    };
+    env.send(&[mark(50)], &[&user]).await.unwrap();
+    for slot in [40, 101] {
+        assert_error(
+            env.send(&[mark(slot)], &[&user]).await,
+            VanishError::InvalidActivitySlot,
+        );
+    }
+    env.send(&[mark(100)], &[&user]).await.unwrap();
+
+    let state: ActivityCursor = env.account(cursor).await;
+    assert_eq!(state.user, user.pubkey());
+    assert_eq!(state.slot, 100);
+}
+
+#[tokio::test]
+async fn short_names_are_staked_until_the_repo_is_used() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let payer = env.payer();
+    let set_stake = ix(
+        accounts::UpdateConfig {
+            admin: payer,
+            config: pda::config(),
+        },
+        instruction::SetNameStake {
+            amount: 1_000_000_000,
+            max_name_len: 4,
+            lock_period: 30 * DAY,
+        },
+    );
+    env.send(&[set_stake], &[]).await.unwrap();
+
+    let create = |name: &str, staked: bool| {
+        let repository = pda::repo(&owner.pubkey(), name);
+        ix(
+            accounts::CreateRepo {
+                owner: owner.pubkey(),
+                repository,
+                tombstone: pda::tombstone(&repository),
+                name_lock: pda::name_lock(&owner.pubkey(), name),
+                name_stake: staked.then(|| pda::stake(&repository)),
+                profile: pda::profile(&owner.pubkey()),
+                config: pda::config(),
+                treasury: pda::treasury(),
+                system_program: system_program(),
+            },
+            instruction::CreateRepo {
+                name: name.to_string(),
+                description: String::new(),
+                is_private: false,
+            },
+        )
+    };
+    assert_error(
+        env.send(&[create("abc", false)], &[&owner]).await,
+        VanishError::InvalidNameStake,
+    );
+    assert_error(
+        env.send(&[create("long-name", true)], &[&owner]).await,
+        VanishError::InvalidNameStake,
+    );
+
+    env.send(&[create("abc", true)], &[&owner]).await.unwrap();
+    let repository = pda::repo(&owner.pubkey(), "abc");
+    let stake: NameStake = env.account(pda::stake(&repository)).await;
+    assert_eq!(stake.repository, repository);
+    assert_eq!(stake.staker, owner.pubkey());
+    assert_eq!(stake.amount, 1_000_000_000);
+    let escrowed = env.lamports(pda::stake(&repository)).await;
+    assert_eq!(escrowed, env.rent(NameStake::SPACE).await + 1_000_000_000);
+
+    let release = ix(
+        accounts::ReleaseNameStake {
+            staker: owner.pubkey(),
+            repository,
+            config: pda::config(),
+            name_stake: pda::stake(&repository),
+        },
+        instruction::ReleaseNameStake {},
+    );
+    assert_error(
+        env.send(std::slice::from_ref(&release), &[&owner]).await,
+        VanishError::StakeLocked,
+    );
+
+    // Sitting on the name for the lock period is not activity
+    env.warp(31 * DAY).await;
+    assert_error(
+        env.send(std::slice::from_ref(&release), &[&owner]).await,
+        VanishError::StakeLocked,
+    );
+
+    env.send(
+        &[push_ix(owner.pubkey(), repository, None, sha1(1))],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+    let before = env.lamports(owner.pubkey()).await;
+    env.send(&[release], &[&owner]).await.unwrap();
+    assert!(!env.exists(pda::stake(&repository)).await);
+    assert_eq!(env.lamports(owner.pubkey()).await, before + escrowed);
+
+    // Purging a repository refunds its stake too
+    env.send(&[create("xyz", true)], &[&owner]).await.unwrap();
+    let repository = pda::repo(&owner.pubkey(), "xyz");
+    let audit_entry = env.audit_entry(repository).await;
+    env.send(
+        &[delete_ix(&owner, repository, DAY, audit_entry)],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+    env.warp(DAY).await;
+    let purge = purge_ix(
+        &owner,
+        repository,
+        &[(pda::stake(&repository), owner.pubkey())],
+    );
+    env.send(&[purge], &[&owner]).await.unwrap();
+    assert!(!env.exists(pda::stake(&repository)).await);
+}
+
+#[tokio::test]
+async fn abandoned_repos_can_be_reclaimed() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let claimant = env.user().await;
+    let stranger = env.user().await;
+    let payer = env.payer();
+    let policy = |idle_period: i64| {
+        ix(
+            accounts::UpdateConfig {
+                admin: payer,
+                config: pda::config(),
+            },
+            instruction::SetReclaimPolicy {
+                idle_period,
+                grace_period: 14 * DAY,
+                bond: 1_000_000_000,
+            },
+        )
+    };
+    assert_error(
+        env.send(&[policy(-1)], &[]).await,
+        VanishError::InvalidReclaimPolicy,
+    );
+
+    let repository = env.create_repo(&owner, "abc", false).await;
+    env.send(
+        &[claim_name_ix(&owner, false, repository, "abc")],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+    let notice = pda::reclaim(&repository);
+    let file = |claimant: &Keypair| {
+        ix(
+            accounts::FileReclaimNotice {
+                claimant: claimant.pubkey(),
+                repository,
+                config: pda::config(),
+                notice,
+                system_program: system_program(),
+            },
+            instruction::FileReclaimNotice {},
+        )
+    };
+    let contest = |authority: &Keypair| {
+        ix(
+            accounts::ContestReclaim {
+                authority: authority.pubkey(),
+                repository,
+                admin: None,
+                notice,
+                claimant: claimant.pubkey(),
+                treasury: pda::treasury(),
+                config: pda::config(),
+            },
+            instruction::ContestReclaim {},
+        )
+    };
+    let reclaim_name = ix(
+        accounts::ReclaimRepoName {
+            claimant: claimant.pubkey(),
+            repository,
+            notice,
+            repo_name: pda::repo_name("abc"),
+            claimer: owner.pubkey(),
+        },
+        instruction::ReclaimRepoName {},
+    );
+    assert_error(
+        env.send(&[file(&claimant)], &[&claimant]).await,
+        VanishError::ReclaimDisabled,
+    );
+
+    env.send(&[policy(90 * DAY)], &[]).await.unwrap();
+    assert_error(
+        env.send(&[file(&claimant)], &[&claimant]).await,
+        VanishError::RepoNotAbandoned,
+    );
+    env.warp(90 * DAY).await;
+    assert_error(
+        env.send(&[file(&owner)], &[&owner]).await,
+        VanishError::Unauthorized,
+    );
+    env.send(&[file(&claimant)], &[&claimant]).await.unwrap();
+    let state: ReclaimNotice = env.account(notice).await;
+    assert_eq!(state.repository, repository);
+    assert_eq!(state.claimant, claimant.pubkey());
+    assert_eq!(state.deadline, state.filed_at + 14 * DAY);
+    assert_eq!(
+        env.lamports(notice).await,
+        env.rent(ReclaimNotice::SPACE).await + 1_000_000_000
+    );
+    assert_error(
+        env.send(std::slice::from_ref(&reclaim_name), &[&claimant])
+            .await,
+        VanishError::ReclaimGraceOpen,
+    );
+
+    // Answering forfeits the bond
+    assert_error(
+        env.send(&[contest(&stranger)], &[&stranger]).await,
+        VanishError::Unauthorized,
+    );
+    let treasury = env.lamports(pda::treasury()).await;
+    env.send(&[contest(&owner)], &[&owner]).await.unwrap();
+    assert!(!env.exists(notice).await);
+    assert_eq!(
+        env.lamports(pda::treasury()).await,
+        treasury + 1_000_000_000
+    );
+
+    // So does pushing, after which anyone can close the notice
+    env.send(&[file(&claimant)], &[&claimant]).await.unwrap();
+    env.send(
+        &[push_ix(owner.pubkey(), repository, None, sha1(1))],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+    env.warp(14 * DAY).await;
+    assert_error(
+        env.send(std::slice::from_ref(&reclaim_name), &[&claimant])
+            .await,
+        VanishError::ReclaimAnswered,
+    );
+    env.send(&[contest(&stranger)], &[&stranger]).await.unwrap();
+
+    // A lapsed notice frees the directory entry, then hands over the repository
+    env.warp(90 * DAY).await;
+    env.send(&[file(&claimant)], &[&claimant]).await.unwrap();
+    env.warp(14 * DAY).await;
+    let before = env.lamports(claimant.pubkey()).await;
+    env.send(&[reclaim_name], &[&claimant]).await.unwrap();
+    assert!(!env.exists(pda::repo_name("abc")).await);
+    assert!(env.lamports(claimant.pubkey()).await > before + 1_000_000_000);
+
+    env.send(&[file(&claimant)], &[&claimant]).await.unwrap();
+    env.warp(14 * DAY).await;
+    let audit_entry = env.audit_entry(repository).await;
+    let reclaim = ix(
+        accounts::ReclaimRepo {
+            claimant: claimant.pubkey(),
+            repository,
+            notice,
+            audit_entry,
+            system_program: system_program(),
+        },
+        instruction::ReclaimRepo {},
+    );
+    env.send(&[reclaim], &[&claimant]).await.unwrap();
+    assert!(!env.exists(notice).await);
+    let repo: Repository = env.account(repository).await;
+    assert_eq!(repo.owner, claimant.pubkey());
+    let entry: AuditEntry = env.account(audit_entry).await;
+    assert!(entry.action == AuditAction::OwnershipTransferred);
+    assert_eq!(entry.subject, claimant.pubkey());
+}
+
+#[tokio::test]
+async fn recovery_authorities_take_over_from_inactive_owners() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let backup = env.user().await;
+    let heir = env.user().await;
+    let repository = env.create_repo(&owner, "abc", false).await;
+    let plan = pda::recovery(&repository);
+
+    let set = |recovery_authority: Pubkey, inactivity_period: i64| {
+        ix(
+            accounts::SetRecoveryPlan {
+                owner: owner.pubkey(),
+                repository,
+                recovery_plan: plan,
+                system_program: system_program(),
+            },
+            instruction::SetRecoveryPlan {
+                recovery_authority,
+                inactivity_period,
+            },
+        )
+    };
+    let heartbeat = ix(
+        accounts::RecoveryHeartbeat {
+            owner: owner.pubkey(),
+            repository,
+            recovery_plan: plan,
+        },
+        instruction::RecoveryHeartbeat {},
+    );
+    let claim = |owner: Pubkey, audit_entry: Pubkey| {
+        ix(
+            accounts::ClaimRecovery {
+                recovery_authority: backup.pubkey(),
+                repository,
+                recovery_plan: plan,
+                owner_contribution: pda::contribution(&repository, &owner),
+                audit_entry,
+                system_program: system_program(),
+            },
+            instruction::ClaimRecovery {},
+        )
+    };
+
+    assert_error(
+        env.send(&[set(backup.pubkey(), 0)], &[&owner]).await,
+        VanishError::InvalidRecoveryPlan,
+    );
+    assert_error(
+        env.send(&[set(owner.pubkey(), 180 * DAY)], &[&owner]).await,
+        VanishError::InvalidRecoveryPlan,
+    );
+    env.send(&[set(backup.pubkey(), 180 * DAY)], &[&owner])
+        .await
+        .unwrap();
+    let state: RecoveryPlan = env.account(plan).await;
+    assert_eq!(state.owner, owner.pubkey());
+    assert_eq!(state.recovery_authority, backup.pubkey());
+
+    let audit_entry = env.audit_entry(repository).await;
+    assert_error(
+        env.send(&[claim(owner.pubkey(), audit_entry)], &[&backup])
+            .await,
+        VanishError::OwnerActive,
+    );
+
+    // Heartbeats and the owner's own pushes restart the timer
+    env.warp(170 * DAY).await;
+    env.send(std::slice::from_ref(&heartbeat), &[&owner])
+        .await
+        .unwrap();
+    env.warp(170 * DAY).await;
+    env.send(
+        &[push_ix(owner.pubkey(), repository, None, sha1(1))],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+    env.warp(170 * DAY).await;
+    assert_error(
+        env.send(&[claim(owner.pubkey(), audit_entry)], &[&backup])
+            .await,
+        VanishError::OwnerActive,
+    );
+
+    // Cancelling returns the rent, and a new plan can follow
+    let cancel = ix(
+        accounts::CancelRecoveryPlan {
+            owner: owner.pubkey(),
+            repository,
+            recovery_plan: plan,
+        },
+        instruction::CancelRecoveryPlan {},
+    );
+    env.send(&[cancel], &[&owner]).await.unwrap();
+    assert!(!env.exists(plan).await);
+    env.send(&[set(backup.pubkey(), 180 * DAY)], &[&owner])
+        .await
+        .unwrap();
+
+    // Plans only apply to the owner who set them
+    let transfer = |from: &Keypair, to: Pubkey, audit_entry: Pubkey| {
+        timelocked_transfer_ix(from, repository, to, audit_entry, false)
+    };
+    let audit_entry = env.audit_entry(repository).await;
+    env.send(&[transfer(&owner, heir.pubkey(), audit_entry)], &[&owner])
+        .await
+        .unwrap();
+    env.warp(180 * DAY).await;
+    let audit_entry = env.audit_entry(repository).await;
+    assert_error(
+        env.send(&[claim(heir.pubkey(), audit_entry)], &[&backup])
+            .await,
+        VanishError::RecoveryPlanLapsed,
+    );
+    env.send(&[transfer(&heir, owner.pubkey(), audit_entry)], &[&heir])
+        .await
+        .unwrap();
+
+    let audit_entry = env.audit_entry(repository).await;
+    env.send(&[claim(owner.pubkey(), audit_entry)], &[&backup])
+        .await
+        .unwrap();
+    assert!(!env.exists(plan).await);
+    let repo: Repository = env.account(repository).await;
+    assert_eq!(repo.owner, backup.pubkey());
+    let entry: AuditEntry = env.account(audit_entry).await;
+    assert!(entry.action == AuditAction::OwnershipTransferred);
+    assert_eq!(entry.subject, backup.pubkey());
+}
+
+#[tokio::test]
+async fn forks_sync_with_their_upstream() {
+    let mut env = Env::new().await;
+    let alice = env.user().await;
+    let bob = env.user().await;
+    let upstream = env.create_repo(&alice, "lib", false).await;
+    let other = env.create_repo(&alice, "other", false).await;
+    let secret = env.create_repo(&alice, "secret", true).await;
+    let plain = env.create_repo(&bob, "plain", false).await;
+    env.send(
+        &[push_ix(alice.pubkey(), upstream, None, sha1(1))],
+        &[&alice],
+    )
+    .await
+    .unwrap();
+    env.send(&[push_ix(alice.pubkey(), other, None, sha1(9))], &[&alice])
+        .await
+        .unwrap();
+    let push_dev = ix(
+        accounts::PushBranch {
+            pusher: alice.pubkey(),
+            repository: upstream,
+            collaborator_account: None,
+            branch_grant: None,
+            contribution: pda::contribution(&upstream, &alice.pubkey()),
+            branch: pda::branch(&upstream, "dev"),
+            project_repo: None,
+            rent_vault: None,
+            system_program: system_program(),
+        },
+        instruction::PushBranch {
+            branch_name: "dev".to_string(),
+            head_commit: sha1(2),
+            ipfs_cid: "QmDev".to_string(),
+            expected_head: None,
+            proof: lone_ref_proof(),
+        },
+    );
+    env.send(&[push_dev], &[&alice]).await.unwrap();
+
+    let fork = pda::repo(&bob.pubkey(), "lib");
+    let fork_ix = |upstream: Pubkey| {
+        ix(
+            accounts::ForkRepo {
+                owner: bob.pubkey(),
+                repository: fork,
+                tombstone: pda::tombstone(&fork),
+                name_lock: pda::name_lock(&bob.pubkey(), "lib"),
+                name_stake: None,
+                profile: pda::profile(&bob.pubkey()),
+                config: pda::config(),
+                treasury: pda::treasury(),
+                upstream,
+                fork_link: pda::fork_link(&fork),
+                system_program: system_program(),
+            },
+            instruction::ForkRepo {
+                name: "lib".to_string(),
+                description: String::new(),
+            },
+        )
+    };
+    assert_error(
+        env.send(&[fork_ix(secret)], &[&bob]).await,
+        VanishError::PrivateUpstream,
+    );
+    env.send(&[fork_ix(upstream)], &[&bob]).await.unwrap();
+    let repo: Repository = env.account(fork).await;
+    assert_eq!(repo.flags, repo_flags::FORK);
+    assert!(repo.head_commit == sha1(1));
+    assert_eq!(repo.ipfs_cid.to_string(), PUSH_CID);
+    let link: ForkLink = env.account(pda::fork_link(&fork)).await;
+    assert_eq!(link.upstream, upstream);
+    assert!(link.synced_commit == sha1(1));
+
+    // Counted when the fork is created
+    let repo: Repository = env.account(upstream).await;
+    assert_eq!(repo.forks, 1);
+
+    let sync = |repository: Pubkey,
+                upstream: Pubkey,
+                branch: Option<&str>,
+                expected_head: Option<Multihash>| {
+        ix(
+            accounts::SyncWithUpstream {
+                pusher: bob.pubkey(),
+                repository,
+                collaborator_account: None,
+                upstream,
+                fork_link: pda::fork_link(&repository),
+                upstream_branch: branch.map(|name| pda::branch(&upstream, name)),
+                branch: branch.map(|name| pda::branch(&repository, name)),
+                system_program: system_program(),
+            },
+            instruction::SyncWithUpstream {
+                branch_name: branch.map(str::to_string),
+                proof: branch.map(|_| lone_ref_proof()).unwrap_or_default(),
+                expected_head,
+            },
+        )
+    };
+    // Only repositories fork_repo created have an upstream to sync with
+    assert_error(
+        env.send(&[sync(plain, upstream, None, None)], &[&bob])
+            .await,
+        AnchorError::AccountNotInitialized,
+    );
+    assert_error(
+        env.send(&[sync(fork, other, None, Some(sha1(1)))], &[&bob])
+            .await,
+        VanishError::UpstreamMismatch,
+    );
+
+    env.send(&[sync(fork, upstream, Some("dev"), None)], &[&bob])
+        .await
+        .unwrap();
+    let branch: Branch = env.account(pda::branch(&fork, "dev")).await;
+    assert_eq!(branch.repository, fork);
+    assert!(branch.head_commit == sha1(2));
+    assert_eq!(branch.ipfs_cid, "QmDev");
+    let repo: Repository = env.account(fork).await;
+    assert!(repo.refs_root == refs_tree::root(&[refs_tree::leaf("dev", &sha1(2))]));
+    let link: ForkLink = env.account(pda::fork_link(&fork)).await;
+    assert!(link.synced_commit == sha1(2));
+
+    // Syncs replace what the fork has like any push would
+    env.send(
+        &[push_onto_ix(
+            alice.pubkey(),
+            upstream,
+            None,
+            Some(sha1(1)),
+            sha1(3),
+        )],
+        &[&alice],
+    )
+    .await
+    .unwrap();
+    assert_error(
+        env.send(&[sync(fork, upstream, None, None)], &[&bob]).await,
+        VanishError::ForcePushNotAllowed,
+    );
+    assert_error(
+        env.send(&[sync(fork, upstream, None, Some(sha1(9)))], &[&bob])
+            .await,
+        VanishError::StaleHead,
+    );
+    env.send(&[sync(fork, upstream, None, Some(sha1(1)))], &[&bob])
+        .await
+        .unwrap();
+    let repo: Repository = env.account(fork).await;
+    assert!(repo.head_commit == sha1(3));
+    assert_error(
+        env.send(&[sync(fork, upstream, Some("dev"), None)], &[&bob])
+            .await,
+        VanishError::ForcePushNotAllowed,
+    );
+    env.send(&[sync(fork, upstream, Some("dev"), Some(sha1(2)))], &[&bob])
+        .await
+        .unwrap();
+
+    // Syncing does not count the fork again
+    let repo: Repository = env.account(upstream).await;
+    assert_eq!(repo.forks, 1);
+}
+
+#[tokio::test]
+async fn submodules_pin_other_repositories() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let stranger = env.user().await;
+    let repository = env.create_repo(&owner, "app", false).await;
+    let lib = env.create_repo(&owner, "lib", false).await;
+    let vendored = env.create_repo(&stranger, "vendored", false).await;
+
+    let add = |pusher: &Keypair, path: &str, target: Pubkey, commit: Multihash| {
+        ix(
+            accounts::AddSubmodule {
+                pusher: pusher.pubkey(),
+                repository,
+                collaborator_account: None,
+                target,
+                submodule: pda::submodule(&repository, path),
+                system_program: system_program(),
+            },
+            instruction::AddSubmodule {
+                path: path.to_string(),
+                commit,
+            },
+        )
+    };
+    for path in ["", "/abs", "trailing/", "a//b", "../escape", "./lib"] {
+        assert_error(
+            env.send(&[add(&owner, path, lib, sha1(1))], &[&owner])
+                .await,
+            VanishError::InvalidSubmodulePath,
+        );
+    }
+    assert_error(
+        env.send(&[add(&stranger, "deps/lib", lib, sha1(1))], &[&stranger])
+            .await,
+        VanishError::Unauthorized,
+    );
+
+    env.send(&[add(&owner, "deps/lib", lib, sha1(1))], &[&owner])
+        .await
+        .unwrap();
+    let submodule: Submodule = env.account(pda::submodule(&repository, "deps/lib")).await;
+    assert_eq!(submodule.repository, repository);
+    assert_eq!(submodule.path, "deps/lib");
+    assert_eq!(submodule.target, lib);
+    assert!(submodule.commit == sha1(1));
+
+    let update = ix(
+        accounts::UpdateSubmodule {
+            pusher: owner.pubkey(),
+            repository,
+            collaborator_account: None,
+            target: vendored,
+            submodule: pda::submodule(&repository, "deps/lib"),
+        },
+        instruction::UpdateSubmodule { commit: sha1(2) },
+    );
+    env.send(&[update], &[&owner]).await.unwrap();
+    let submodule: Submodule = env.account(pda::submodule(&repository, "deps/lib")).await;
+    assert_eq!(submodule.target, vendored);
+    assert!(submodule.commit == sha1(2));
+
+    let remove = ix(
+        accounts::RemoveSubmodule {
+            pusher: owner.pubkey(),
+            repository,
+            collaborator_account: None,
+            submodule: pda::submodule(&repository, "deps/lib"),
+        },
+        instruction::RemoveSubmodule {},
+    );
+    env.send(&[remove], &[&owner]).await.unwrap();
+    assert!(!env.exists(pda::submodule(&repository, "deps/lib")).await);
+}
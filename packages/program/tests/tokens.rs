@@ -0,0 +1,515 @@
+mod common;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_spl::token::spl_token;
+use anchor_spl::token::spl_token::instruction::AuthorityType;
+use anchor_spl::token_2022::spl_token_2022;
+use anchor_spl::token_2022::spl_token_2022::extension::{ExtensionType, StateWithExtensions};
+use common::*;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::system_instruction;
+use vanish_program::{accounts, instruction, BadgeConfig, Cid, Multihash, RepoNft, Repository};
+
+/// A fixed-supply NFT held by `holder`: returns the mint and the holder's
+/// token account
+async fn create_nft(env: &mut Env, holder: &Keypair) -> (Pubkey, Pubkey) {
+    let mint = env.create_mint(&holder.pubkey(), 0).await;
+    let account = env.create_token_account(&mint, &holder.pubkey()).await;
+    env.mint_to(&mint, &account, holder, 1).await;
+    let freeze_supply = spl_token::instruction::set_authority(
+        &spl_token::ID,
+        &mint,
+        None,
+        AuthorityType::MintTokens,
+        &holder.pubkey(),
+        &[],
+    )
+    .unwrap();
+    env.send(&[freeze_supply], &[holder]).await.unwrap();
+    (mint, account)
+}
+
+fn wrap_ix(
+    owner: &Keypair,
+    repository: Pubkey,
+    mint: Pubkey,
+    token_account: Pubkey,
+    audit_entry: Pubkey,
+) -> Instruction {
+    ix(
+        accounts::WrapRepoToNft {
+            owner: owner.pubkey(),
+            repository,
+            mint,
+            owner_token_account: token_account,
+            repo_nft: pda::nft(&repository),
+            timelock: pda::timelock(&repository),
+            pending_action: None,
+            audit_entry,
+            system_program: system_program(),
+        },
+        instruction::WrapRepoToNft {},
+    )
+}
+
+fn unwrap_ix(
+    holder: &Keypair,
+    repository: Pubkey,
+    mint: Pubkey,
+    token_account: Pubkey,
+    audit_entry: Pubkey,
+) -> Instruction {
+    ix(
+        accounts::UnwrapRepoFromNft {
+            holder: holder.pubkey(),
+            repository,
+            repo_nft: pda::nft(&repository),
+            mint,
+            holder_token_account: token_account,
+            audit_entry,
+            system_program: system_program(),
+        },
+        instruction::UnwrapRepoFromNft {},
+    )
+}
+
+#[tokio::test]
+async fn wrap_requires_a_fixed_supply_nft() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let repository = env.create_repo(&owner, "repo", false).await;
+
+    // Still mintable
+    let mint = env.create_mint(&owner.pubkey(), 0).await;
+    let account = env.create_token_account(&mint, &owner.pubkey()).await;
+    env.mint_to(&mint, &account, &owner, 1).await;
+    let audit_entry = env.audit_entry(repository).await;
+    assert_error(
+        env.send(
+            &[wrap_ix(&owner, repository, mint, account, audit_entry)],
+            &[&owner],
+        )
+        .await,
+        VanishError::InvalidNftMint,
+    );
+
+    // Held by someone else
+    let other = env.user().await;
+    let (mint, _) = create_nft(&mut env, &other).await;
+    let empty = env.create_token_account(&mint, &owner.pubkey()).await;
+    let audit_entry = env.audit_entry(repository).await;
+    assert_error(
+        env.send(
+            &[wrap_ix(&owner, repository, mint, empty, audit_entry)],
+            &[&owner],
+        )
+        .await,
+        VanishError::InvalidNftMint,
+    );
+
+    // Only the owner can wrap
+    let (mint, account) = create_nft(&mut env, &other).await;
+    let audit_entry = env.audit_entry(repository).await;
+    assert_error(
+        env.send(
+            &[wrap_ix(&other, repository, mint, account, audit_entry)],
+            &[&other],
+        )
+        .await,
+        AnchorError::ConstraintHasOne,
+    );
+}
+
+#[tokio::test]
+async fn nft_holder_controls_wrapped_repo() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let buyer = env.user().await;
+    let repository = env.create_repo(&owner, "repo", false).await;
+    let (mint, owner_account) = create_nft(&mut env, &owner).await;
+
+    let audit_entry = env.audit_entry(repository).await;
+    env.send(
+        &[wrap_ix(
+            &owner,
+            repository,
+            mint,
+            owner_account,
+            audit_entry,
+        )],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+    let repo: Repository = env.account(repository).await;
+    assert_eq!(repo.owner, pda::nft(&repository));
+    let repo_nft: RepoNft = env.account(pda::nft(&repository)).await;
+    assert_eq!(repo_nft.mint, mint);
+    assert_eq!(repo_nft.repository, repository);
+
+    // While wrapped nobody acts as owner directly
+    let transfer = ix(
+        accounts::TransferOwnership {
+            owner: owner.pubkey(),
+            repository,
+            timelock: pda::timelock(&repository),
+            pending_action: None,
+            audit_entry: env.audit_entry(repository).await,
+            system_program: system_program(),
+        },
+        instruction::TransferOwnership {
+            new_owner: buyer.pubkey(),
+        },
+    );
+    assert_error(
+        env.send(&[transfer], &[&owner]).await,
+        AnchorError::ConstraintHasOne,
+    );
+
+    // Selling the NFT hands over the repository
+    let buyer_account = env.create_token_account(&mint, &buyer.pubkey()).await;
+    let sell = spl_token::instruction::transfer(
+        &spl_token::ID,
+        &owner_account,
+        &buyer_account,
+        &owner.pubkey(),
+        &[],
+        1,
+    )
+    .unwrap();
+    env.send(&[sell], &[&owner]).await.unwrap();
+
+    let audit_entry = env.audit_entry(repository).await;
+    assert_error(
+        env.send(
+            &[unwrap_ix(
+                &owner,
+                repository,
+                mint,
+                owner_account,
+                audit_entry,
+            )],
+            &[&owner],
+        )
+        .await,
+        VanishError::InvalidNftMint,
+    );
+
+    let (other_mint, other_account) = create_nft(&mut env, &buyer).await;
+    let audit_entry = env.audit_entry(repository).await;
+    assert_error(
+        env.send(
+            &[unwrap_ix(
+                &buyer,
+                repository,
+                other_mint,
+                other_account,
+                audit_entry,
+            )],
+            &[&buyer],
+        )
+        .await,
+        AnchorError::ConstraintHasOne,
+    );
+
+    let audit_entry = env.audit_entry(repository).await;
+    env.send(
+        &[unwrap_ix(
+            &buyer,
+            repository,
+            mint,
+            buyer_account,
+            audit_entry,
+        )],
+        &[&buyer],
+    )
+    .await
+    .unwrap();
+    let repo: Repository = env.account(repository).await;
+    assert_eq!(repo.owner, buyer.pubkey());
+    assert!(!env.exists(pda::nft(&repository)).await);
+}
+
+// ============================================================================
+// Badges
+// ============================================================================
+
+/// Create a Token-2022 mint, optionally non-transferable
+async fn create_mint_2022(
+    env: &mut Env,
+    authority: &Pubkey,
+    decimals: u8,
+    non_transferable: bool,
+) -> Pubkey {
+    let mint = Keypair::new();
+    let extensions: &[ExtensionType] = if non_transferable {
+        &[ExtensionType::NonTransferable]
+    } else {
+        &[]
+    };
+    let space = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(extensions)
+        .unwrap();
+    let rent = env.ctx.banks_client.get_rent().await.unwrap();
+
+    let mut instructions = vec![system_instruction::create_account(
+        &env.payer(),
+        &mint.pubkey(),
+        rent.minimum_balance(space),
+        space as u64,
+        &spl_token_2022::ID,
+    )];
+    if non_transferable {
+        instructions.push(
+            spl_token_2022::instruction::initialize_non_transferable_mint(
+                &spl_token_2022::ID,
+                &mint.pubkey(),
+            )
+            .unwrap(),
+        );
+    }
+    instructions.push(
+        spl_token_2022::instruction::initialize_mint2(
+            &spl_token_2022::ID,
+            &mint.pubkey(),
+            authority,
+            None,
+            decimals,
+        )
+        .unwrap(),
+    );
+    env.send(&instructions, &[&mint]).await.unwrap();
+    mint.pubkey()
+}
+
+/// Create a Token-2022 account for a non-transferable mint
+async fn create_badge_account(env: &mut Env, mint: &Pubkey, owner: &Pubkey) -> Pubkey {
+    let account = Keypair::new();
+    let space = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Account>(&[
+        ExtensionType::ImmutableOwner,
+        ExtensionType::NonTransferableAccount,
+    ])
+    .unwrap();
+    let rent = env.ctx.banks_client.get_rent().await.unwrap();
+
+    let instructions = [
+        system_instruction::create_account(
+            &env.payer(),
+            &account.pubkey(),
+            rent.minimum_balance(space),
+            space as u64,
+            &spl_token_2022::ID,
+        ),
+        spl_token_2022::instruction::initialize_immutable_owner(
+            &spl_token_2022::ID,
+            &account.pubkey(),
+        )
+        .unwrap(),
+        spl_token_2022::instruction::initialize_account3(
+            &spl_token_2022::ID,
+            &account.pubkey(),
+            mint,
+            owner,
+        )
+        .unwrap(),
+    ];
+    env.send(&instructions, &[&account]).await.unwrap();
+    account.pubkey()
+}
+
+async fn badge_balance(env: &mut Env, account: Pubkey) -> u64 {
+    let account = env
+        .ctx
+        .banks_client
+        .get_account(account)
+        .await
+        .unwrap()
+        .unwrap();
+    StateWithExtensions::<spl_token_2022::state::Account>::unpack(&account.data)
+        .unwrap()
+        .base
+        .amount
+}
+
+fn configure_ix(
+    authority: &Keypair,
+    repository: Pubkey,
+    mint: Pubkey,
+    enabled: bool,
+) -> Instruction {
+    ix(
+        accounts::ConfigureBadges {
+            authority: authority.pubkey(),
+            repository,
+            admin: None,
+            badge_config: pda::badges(&repository),
+            badge_mint: mint,
+            system_program: system_program(),
+        },
+        instruction::ConfigureBadges { enabled },
+    )
+}
+
+fn badge_push_ix(
+    pusher: &Keypair,
+    repository: Pubkey,
+    collaborator: bool,
+    expected_head: Option<Multihash>,
+    badge: Option<(Pubkey, Pubkey)>,
+) -> Instruction {
+    ix(
+        accounts::PushUpdate {
+            pusher: pusher.pubkey(),
+            repository,
+            collaborator_account: collaborator.then(|| pda::collab(&repository, &pusher.pubkey())),
+            contribution: pda::contribution(&repository, &pusher.pubkey()),
+            push_log: None,
+            project_repo: pda::project_repo(&repository),
+            project: None,
+            project_member: None,
+            badge_config: Some(pda::badges(&repository)),
+            badge_mint: badge.map(|(mint, _)| mint),
+            badge_token_account: badge.map(|(_, account)| account),
+            token_2022_program: badge.map(|_| spl_token_2022::ID),
+            system_program: system_program(),
+        },
+        instruction::PushUpdate {
+            head_commit: sha1(1),
+            ipfs_cid: Cid::parse(PUSH_CID).unwrap(),
+            expected_head,
+            co_authors: vec![],
+            note: None,
+            binding: None,
+        },
+    )
+}
+
+#[tokio::test]
+async fn configure_badges_validates_mint() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let stranger = env.user().await;
+    let repository = env.create_repo(&owner, "repo", false).await;
+    let config = pda::badges(&repository);
+
+    let invalid = [
+        create_mint_2022(&mut env, &config, 0, false).await,
+        create_mint_2022(&mut env, &config, 2, true).await,
+        create_mint_2022(&mut env, &owner.pubkey(), 0, true).await,
+    ];
+    for mint in invalid {
+        assert_error(
+            env.send(&[configure_ix(&owner, repository, mint, true)], &[&owner])
+                .await,
+            VanishError::InvalidBadgeMint,
+        );
+    }
+
+    // Classic SPL mints fail the Token-2022 owner check
+    let classic = env.create_mint(&config, 0).await;
+    assert_error(
+        env.send(
+            &[configure_ix(&owner, repository, classic, true)],
+            &[&owner],
+        )
+        .await,
+        VanishError::InvalidBadgeMint,
+    );
+
+    let mint = create_mint_2022(&mut env, &config, 0, true).await;
+    assert_error(
+        env.send(
+            &[configure_ix(&stranger, repository, mint, true)],
+            &[&stranger],
+        )
+        .await,
+        VanishError::Unauthorized,
+    );
+    env.send(&[configure_ix(&owner, repository, mint, true)], &[&owner])
+        .await
+        .unwrap();
+    let badge_config: BadgeConfig = env.account(config).await;
+    assert_eq!(badge_config.mint, mint);
+    assert!(badge_config.enabled);
+}
+
+#[tokio::test]
+async fn pushes_mint_contribution_badges() {
+    let mut env = Env::new().await;
+    let owner = env.user().await;
+    let writer = env.user().await;
+    let repository = env.create_repo(&owner, "repo", false).await;
+    env.add_collaborator(&owner, repository, writer.pubkey(), true, false)
+        .await;
+
+    let mint = create_mint_2022(&mut env, &pda::badges(&repository), 0, true).await;
+    env.send(&[configure_ix(&owner, repository, mint, true)], &[&owner])
+        .await
+        .unwrap();
+    let writer_badges = create_badge_account(&mut env, &mint, &writer.pubkey()).await;
+    let owner_badges = create_badge_account(&mut env, &mint, &owner.pubkey()).await;
+
+    env.send(
+        &[badge_push_ix(
+            &writer,
+            repository,
+            true,
+            None,
+            Some((mint, writer_badges)),
+        )],
+        &[&writer],
+    )
+    .await
+    .unwrap();
+    assert_eq!(badge_balance(&mut env, writer_badges).await, 1);
+
+    // Badges go to the pusher, not to an account of their choosing
+    assert_error(
+        env.send(
+            &[badge_push_ix(
+                &writer,
+                repository,
+                true,
+                Some(sha1(1)),
+                Some((mint, owner_badges)),
+            )],
+            &[&writer],
+        )
+        .await,
+        VanishError::InvalidBadgeAccounts,
+    );
+
+    // Passing the config opts in, so the rest of the badge accounts are required
+    assert_error(
+        env.send(
+            &[badge_push_ix(
+                &writer,
+                repository,
+                true,
+                Some(sha1(1)),
+                None,
+            )],
+            &[&writer],
+        )
+        .await,
+        VanishError::InvalidBadgeAccounts,
+    );
+
+    // Disabled badges are skipped
+    env.send(&[configure_ix(&owner, repository, mint, false)], &[&owner])
+        .await
+        .unwrap();
+    env.send(
+        &[badge_push_ix(
+            &writer,
+            repository,
+            true,
+            Some(sha1(1)),
+            Some((mint, writer_badges)),
+        )],
+        &[&writer],
+    )
+    .await
+    .unwrap();
+    assert_eq!(badge_balance(&mut env, writer_badges).await, 1);
+}
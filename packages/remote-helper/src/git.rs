@@ -0,0 +1,60 @@
+//! The git commands the helper shells out to.
+//!
+//! Git runs the helper with `GIT_DIR` set, so these act on the repository
+//! being pushed from or fetched into.
+
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::{bail, Context, Result};
+
+/// Resolve a revision to its full commit id.
+pub fn rev_parse(rev: &str) -> Result<String> {
+    git(&["rev-parse", "--verify", &format!("{rev}^{{commit}}")])
+}
+
+/// First line of a commit's message.
+pub fn subject(commit: &str) -> Result<String> {
+    git(&["log", "-1", "--format=%s", commit])
+}
+
+/// Whether `ancestor` is reachable from `descendant`.
+pub fn is_ancestor(ancestor: &str, descendant: &str) -> Result<bool> {
+    let status = Command::new("git")
+        .args(["merge-base", "--is-ancestor", ancestor, descendant])
+        .status()
+        .context("failed to run git")?;
+    Ok(status.success())
+}
+
+/// `<commit> <ref>` lines for the refs in a bundle, HEAD included.
+pub fn bundle_heads(path: &Path) -> Result<Vec<(String, String)>> {
+    let heads = git(&["bundle", "list-heads", &path.to_string_lossy()])?;
+    Ok(heads
+        .lines()
+        .filter_map(|line| line.split_once(' '))
+        .map(|(commit, name)| (commit.to_string(), name.to_string()))
+        .collect())
+}
+
+/// Copy a bundle's objects into the repository.
+pub fn unbundle(path: &Path) -> Result<()> {
+    git(&["bundle", "unbundle", &path.to_string_lossy()]).map(drop)
+}
+
+fn git(args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .args(args)
+        .output()
+        .context("failed to run git")?;
+
+    if !output.status.success() {
+        bail!(
+            "git {} failed: {}",
+            args[0],
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
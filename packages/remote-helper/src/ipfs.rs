@@ -0,0 +1,44 @@
+//! Archive storage configured from the environment.
+//!
+//! `VANISH_IPFS_BACKEND` picks `kubo`, `pinata` or `web3`. Without it, the
+//! backend follows from the credentials present: `VANISH_IPFS_API_KEY` (and
+//! `VANISH_IPFS_API_SECRET`) for Pinata, `VANISH_IPFS_TOKEN` for
+//! web3.storage, and otherwise the Kubo node at `VANISH_IPFS_API`.
+//! Archives are read back through `VANISH_IPFS_GATEWAY`.
+
+use anyhow::{bail, Result};
+use vanish_client::storage::{Kubo, Pinata, Storage, Web3Storage};
+
+pub fn storage() -> Result<Storage> {
+    let var = |name: &str| std::env::var(name).ok().filter(|value| !value.is_empty());
+
+    let backend = var("VANISH_IPFS_BACKEND").unwrap_or_else(|| {
+        if var("VANISH_IPFS_API_KEY").is_some() {
+            "pinata".into()
+        } else if var("VANISH_IPFS_TOKEN").is_some() {
+            "web3".into()
+        } else {
+            "kubo".into()
+        }
+    });
+
+    let storage = match backend.as_str() {
+        "kubo" => Storage::new(Kubo::new(
+            &var("VANISH_IPFS_API").unwrap_or_else(|| "http://127.0.0.1:5001".into()),
+        )),
+        "pinata" => Storage::new(Pinata::with_endpoint(
+            &var("VANISH_IPFS_ENDPOINT").unwrap_or_else(|| Pinata::ENDPOINT.into()),
+            var("VANISH_IPFS_API_KEY").unwrap_or_default(),
+            var("VANISH_IPFS_API_SECRET").unwrap_or_default(),
+        )),
+        "web3" => Storage::new(Web3Storage::new(
+            var("VANISH_IPFS_TOKEN").unwrap_or_default(),
+        )),
+        other => bail!("unknown VANISH_IPFS_BACKEND {other}; expected kubo, pinata or web3"),
+    };
+
+    Ok(match var("VANISH_IPFS_GATEWAY") {
+        Some(gateway) => storage.with_gateway(&gateway),
+        None => storage,
+    })
+}
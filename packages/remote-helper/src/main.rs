@@ -0,0 +1,403 @@
+//! Git remote helper for `vanish://creator/name` remotes.
+//!
+//! Git runs `git-remote-vanish <remote> <url>` and talks to it over stdin
+//! and stdout (see gitremote-helpers(7)). A push packs the branch the
+//! remote's HEAD points at, with the remote's other refs, into a
+//! deterministic [`Archive`], pins it to IPFS and records it with
+//! push_update. A fetch or clone downloads the archive the on-chain
+//! `ipfs_cid` names and unpacks it, or unbundles it when it is a git bundle
+//! uploaded by the SDK's `packRepository`. A private repository's archive
+//! is decrypted with the key sealed in the `VANISH_KEYPAIR` holder's
+//! AccessKey.
+//!
+//! Configuration comes from the environment: `VANISH_CLUSTER` (localnet,
+//! devnet or mainnet) with the `VANISH_RPC_URL`, `VANISH_WS_URL` and
+//! `VANISH_PROGRAM_ID` overrides of its profile, `VANISH_KEYPAIR`, `VANISH_COMPUTE_UNITS` and `VANISH_PRIORITY_FEE` (each
+//! `auto` or a number), and the `VANISH_IPFS_*` variables read by
+//! [`ipfs::storage`].
+
+mod git;
+mod ipfs;
+
+use std::io::{self, BufRead, Write};
+use std::process::ExitCode;
+
+use anyhow::{anyhow, bail, Context, Result};
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{read_keypair_file, Keypair},
+    signer::Signer,
+};
+use tempfile::TempDir;
+use vanish_client::{
+    archive::Archive,
+    cluster::{Cluster, Profile},
+    commit, encryption, instructions, pda,
+    program::{Cid, PushEntry, PushNote, Repository},
+    storage::Storage,
+    ComputeBudget, VanishClient,
+};
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    match run().await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("fatal: vanish: {err:#}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run() -> Result<()> {
+    let url = std::env::args()
+        .nth(2)
+        .context("usage: git-remote-vanish <remote> <url>")?;
+    let mut remote = Remote::new(&url)?;
+
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    let mut stdout = io::stdout();
+
+    while let Some(line) = lines.next() {
+        let line = line?;
+        let mut batch = vec![line.clone()];
+        if line.starts_with("fetch ") || line.starts_with("push ") {
+            // Fetches and pushes come in batches ended by a blank line
+            for line in lines.by_ref() {
+                let line = line?;
+                if line.is_empty() {
+                    break;
+                }
+                batch.push(line);
+            }
+        }
+
+        let response = match line.split_whitespace().next() {
+            None => return Ok(()),
+            Some("capabilities") => "fetch\npush\n\n".to_string(),
+            Some("list") => remote.list().await?,
+            Some("fetch") => {
+                remote.fetch()?;
+                "\n".to_string()
+            }
+            Some("push") => remote.push(&batch).await?,
+            Some(command) => bail!("unsupported command {command}"),
+        };
+        stdout.write_all(response.as_bytes())?;
+        stdout.flush()?;
+    }
+
+    Ok(())
+}
+
+struct Remote {
+    client: VanishClient,
+    storage: Storage,
+    creator: Pubkey,
+    name: String,
+    /// Archive downloaded by `list`, unpacked by `fetch`
+    fetched: Option<Fetched>,
+    /// Refs `list` found, which a push keeps
+    listing: Option<Listing>,
+}
+
+#[derive(Default)]
+struct Listing {
+    /// Branch the remote's HEAD points at, `None` before the first push
+    head_ref: Option<String>,
+    /// `(name, commit)` of every other ref
+    refs: Vec<(String, String)>,
+}
+
+enum Fetched {
+    Archive(Archive),
+    /// Directory holding `repo.bundle`
+    Bundle(TempDir),
+}
+
+impl Remote {
+    fn new(url: &str) -> Result<Self> {
+        // `vanish://creator/name`, or `creator/name` when written `vanish::creator/name`
+        let path = url.strip_prefix("vanish://").unwrap_or(url);
+        let (creator, name) = path
+            .trim_end_matches('/')
+            .split_once('/')
+            .with_context(|| format!("{url} is not a vanish://creator/name url"))?;
+
+        let cluster = match std::env::var("VANISH_CLUSTER") {
+            Ok(cluster) => cluster
+                .parse()
+                .map_err(|err| anyhow!("VANISH_CLUSTER: {err}"))?,
+            Err(_) => Cluster::default(),
+        };
+        let profile = Profile::from_env(cluster)?;
+
+        let mut budget = ComputeBudget::default();
+        if let Ok(units) = std::env::var("VANISH_COMPUTE_UNITS") {
+            budget.units = units
+                .parse()
+                .map_err(|err| anyhow!("VANISH_COMPUTE_UNITS: {err}"))?;
+        }
+        if let Ok(fee) = std::env::var("VANISH_PRIORITY_FEE") {
+            budget.fee = fee
+                .parse()
+                .map_err(|err| anyhow!("VANISH_PRIORITY_FEE: {err}"))?;
+        }
+
+        Ok(Self {
+            client: VanishClient::from_profile(&profile)?.with_compute_budget(budget),
+            storage: ipfs::storage()?,
+            creator: creator
+                .parse()
+                .map_err(|_| anyhow!("{creator} is not a valid creator address"))?,
+            name: name.to_string(),
+            fetched: None,
+            listing: None,
+        })
+    }
+
+    fn address(&self) -> Pubkey {
+        pda::repo(&self.creator, &self.name)
+    }
+
+    async fn repository(&self) -> Result<Option<Repository>> {
+        let address = self.address();
+        self.client
+            .try_account(&address)
+            .await
+            .with_context(|| format!("failed to fetch repository {address}"))
+    }
+
+    /// Refs in the pushed archive, empty for a repository that does not
+    /// exist or was never pushed.
+    async fn list(&mut self) -> Result<String> {
+        let repo = match self.repository().await? {
+            Some(repo) if !repo.ipfs_cid.is_empty() => repo,
+            _ => {
+                self.listing = Some(Listing::default());
+                return Ok("\n".to_string());
+            }
+        };
+        let mut data = self.storage.get(&repo.ipfs_cid.to_string()).await?;
+        if repo.is_private {
+            let reader = keypair()?;
+            let key = self
+                .client
+                .repo_key(&repo, &reader)
+                .await
+                .with_context(|| {
+                    format!(
+                        "{}/{} is private and {} holds no usable access key",
+                        self.creator,
+                        self.name,
+                        reader.pubkey()
+                    )
+                })?;
+            data = encryption::decrypt(&key, &data)?;
+        }
+        let (heads, fetched) = if Archive::is_archive(&data) {
+            let archive = Archive::from_bytes(&data)?;
+            let mut heads = vec![(archive.head.clone(), "HEAD".to_string())];
+            heads.extend(
+                archive
+                    .refs
+                    .iter()
+                    .map(|(name, commit)| (commit.clone(), name.clone())),
+            );
+            (heads, Fetched::Archive(archive))
+        } else {
+            let dir = tempfile::tempdir()?;
+            let path = dir.path().join("repo.bundle");
+            std::fs::write(&path, data)?;
+            (git::bundle_heads(&path)?, Fetched::Bundle(dir))
+        };
+
+        // The archive must be the one push_update recorded
+        let head = commit::to_hex(&repo.head_commit.to_multihash());
+        match heads.iter().find(|(_, name)| name == "HEAD") {
+            Some((commit, _)) if *commit == head => {}
+            _ => bail!(
+                "archive {} does not match on-chain head {head}",
+                repo.ipfs_cid
+            ),
+        }
+
+        let mut response = String::new();
+        for (commit, name) in heads.iter().filter(|(_, name)| name != "HEAD") {
+            response.push_str(&format!("{commit} {name}\n"));
+        }
+        let head_ref = heads
+            .iter()
+            .find(|(commit, name)| *commit == head && name.starts_with("refs/heads/"))
+            .map(|(_, name)| name.clone());
+        if let Some(name) = &head_ref {
+            response.push_str(&format!("@{name} HEAD\n"));
+        }
+        response.push('\n');
+
+        let refs = heads
+            .into_iter()
+            .filter(|(_, name)| name != "HEAD" && Some(name) != head_ref.as_ref())
+            .map(|(commit, name)| (name, commit))
+            .collect();
+        self.fetched = Some(fetched);
+        self.listing = Some(Listing { head_ref, refs });
+        Ok(response)
+    }
+
+    fn fetch(&self) -> Result<()> {
+        match self.fetched.as_ref().context("fetch before list")? {
+            Fetched::Archive(archive) => Ok(archive.unpack(None)?),
+            Fetched::Bundle(dir) => git::unbundle(&dir.path().join("repo.bundle")),
+        }
+    }
+
+    /// Push the checked-out branch. The archive's HEAD has to be the commit
+    /// recorded on chain, so only the branch the remote's HEAD points at can
+    /// be pushed, or any one branch before the first push.
+    async fn push(&self, batch: &[String]) -> Result<String> {
+        let mut response = String::new();
+        let mut pushed = false;
+
+        for line in batch {
+            let spec = line.trim_start_matches("push ");
+            let (force, spec) = match spec.strip_prefix('+') {
+                Some(spec) => (true, spec),
+                None => (false, spec),
+            };
+            let (src, dst) = spec
+                .split_once(':')
+                .with_context(|| format!("malformed push {spec}"))?;
+
+            let result = if pushed {
+                Err("push one branch at a time".to_string())
+            } else if src.is_empty() {
+                Err("deleting refs is not supported".to_string())
+            } else {
+                self.push_ref(src, dst, force).await
+            };
+            match result {
+                Ok(()) => {
+                    pushed = true;
+                    response.push_str(&format!("ok {dst}\n"));
+                }
+                Err(reason) => response.push_str(&format!("error {dst} {reason}\n")),
+            }
+        }
+
+        response.push('\n');
+        Ok(response)
+    }
+
+    /// Returns the reason for git to show when the push is refused.
+    async fn push_ref(&self, src: &str, dst: &str, force: bool) -> Result<(), String> {
+        let local = git::rev_parse(src).map_err(|err| err.to_string())?;
+        if git::rev_parse("HEAD").ok().as_deref() != Some(local.as_str()) {
+            return Err("only the checked-out branch can be pushed".to_string());
+        }
+
+        self.upload(&local, dst, force)
+            .await
+            .map_err(|err| format!("{err:#}"))
+    }
+
+    async fn upload(&self, local: &str, dst: &str, force: bool) -> Result<()> {
+        let pusher = keypair()?;
+        let repo = match self.repository().await? {
+            Some(repo) => repo,
+            None if pusher.pubkey() == self.creator => {
+                eprintln!("vanish: creating {}/{}", self.creator, self.name);
+                self.client
+                    .create_repo(&pusher, &self.name, "", false)
+                    .await
+                    .context("failed to create repository")?;
+                self.repository()
+                    .await?
+                    .context("repository missing after creation")?
+            }
+            None => bail!("{}/{} does not exist", self.creator, self.name),
+        };
+        if repo.is_private {
+            bail!("private repositories are encrypted; push them with the vanish CLI");
+        }
+
+        // A rewrite goes through without an expected head, so the program
+        // only accepts it when the repository allows force pushes
+        let remote = repo.head_commit.to_multihash();
+        let expected_head =
+            if remote.digest.is_empty() || git::is_ancestor(&commit::to_hex(&remote), local)? {
+                Some(remote)
+            } else if force {
+                None
+            } else {
+                bail!("non-fast-forward");
+            };
+
+        // Every push moves the on-chain head, which is the archive's HEAD
+        let listing = self.listing.as_ref().context("push before list")?;
+        match &listing.head_ref {
+            Some(head_ref) if head_ref != dst => {
+                bail!("only {head_ref}, which HEAD points at, can be pushed")
+            }
+            None if !repo.ipfs_cid.is_empty() => {
+                bail!("the remote's HEAD is not on a branch")
+            }
+            _ => {}
+        }
+        // The new archive replaces the old one, so it carries the other refs
+        // over; their objects must have been fetched
+        let mut refs: Vec<_> = listing
+            .refs
+            .iter()
+            .filter(|(name, _)| name != dst)
+            .map(|(name, commit)| (name.as_str(), commit.as_str()))
+            .collect();
+        refs.push((dst, local));
+        let archive = Archive::pack(None, local, &refs)
+            .context("failed to pack the remote's refs; fetch them first")?;
+        let cid = self.storage.add(archive.to_bytes()).await?;
+        eprintln!("vanish: pinned archive {cid}");
+        let cid = Cid::parse(&cid).with_context(|| format!("{cid} is not a CID"))?;
+
+        let head_commit =
+            commit::from_hex(local).with_context(|| format!("{local} is not a commit id"))?;
+        let project = self.client.project_push(&repo, &pusher.pubkey()).await?;
+        let note = PushNote {
+            changelog_cid: String::new(),
+            message: truncate(git::subject(local)?, PushEntry::MAX_MESSAGE_LEN),
+        };
+        let push = instructions::push_update(
+            &pusher.pubkey(),
+            &repo,
+            head_commit,
+            cid,
+            expected_head,
+            project.as_ref(),
+            note.is_valid().then_some(note),
+            Some(archive.binding()),
+        );
+        let signature = self.client.send(&[push], &pusher, &[]).await?;
+        eprintln!("vanish: recorded in {signature}");
+
+        Ok(())
+    }
+}
+
+/// `text` cut to at most `max` bytes, on a character boundary.
+fn truncate(mut text: String, max: usize) -> String {
+    let mut len = text.len().min(max);
+    while !text.is_char_boundary(len) {
+        len -= 1;
+    }
+    text.truncate(len);
+    text
+}
+
+fn keypair() -> Result<Keypair> {
+    let path = std::env::var("VANISH_KEYPAIR").unwrap_or_else(|_| {
+        let home = std::env::var("HOME").unwrap_or_default();
+        format!("{home}/.config/solana/id.json")
+    });
+    read_keypair_file(&path).map_err(|err| anyhow!("failed to read keypair {path}: {err}"))
+}
@@ -198,10 +198,89 @@ describe('SolRepoClient', () => {
   });
 
   describe('getRepository', () => {
+    const encodeString = (value: string): Buffer => {
+      const length = Buffer.alloc(4);
+      length.writeUInt32LE(value.length);
+      return Buffer.concat([length, Buffer.from(value)]);
+    };
+
+    const encodeObjectId = (code: number, digest: Buffer): Buffer =>
+      Buffer.concat([Buffer.from([code]), digest, Buffer.alloc(32 - digest.length)]);
+
+    // CIDv0 of sha256("push")
+    const cid = Buffer.concat([
+      Buffer.from([0x12, 0x20]),
+      Buffer.from('d107ea3629c326718876f07356a48dea26f123742f8bf44ce75f8f805cf9a37d', 'hex'),
+    ]);
+    const encodeCid = (bytes: Buffer): Buffer =>
+      Buffer.concat([Buffer.from([bytes.length]), bytes, Buffer.alloc(40 - bytes.length)]);
+
+    const encodeRepo = (description: string, storage: number): Buffer =>
+      Buffer.concat([
+        Buffer.alloc(8), // discriminator
+        keypair.publicKey.toBuffer(), // owner
+        keypair.publicKey.toBuffer(), // creator
+        Buffer.from([0]), // flags
+        encodeString('my-repo'),
+        encodeString(description),
+        Buffer.from([storage, 0]), // description_storage, is_private
+        Buffer.alloc(20), // key_epoch, created_at, updated_at
+        encodeObjectId(0x11, Buffer.alloc(20, 0xaa)), // head_commit (SHA-1)
+        encodeCid(cid), // ipfs_cid
+        encodeString(''), // objects_cid
+        encodeString(''), // ipns_name
+        Buffer.alloc(132), // stars .. purgeable_at, refs_root, ref_count, weighted_stars .. audit_count, successor, template, collaborator_count, pinners
+      ]);
+
+    const mockAccountData = (data: Buffer): void => {
+      // eslint-disable-next-line @typescript-eslint/no-explicit-any
+      (client as any).connection.getAccountInfo.mockResolvedValue({ data });
+    };
+
     it('should return null when repository not found', async () => {
       const repo = await client.getRepository(keypair.publicKey, 'non-existent');
       expect(repo).toBeNull();
     });
+
+    it('should return inline description as stored', async () => {
+      mockAccountData(encodeRepo('A short description', 0));
+
+      const repo = await client.getRepository(keypair.publicKey, 'my-repo');
+      expect(repo?.description).toBe('A short description');
+      expect(repo?.descriptionStorage).toBe('inline');
+      expect(repo?.ipfsCid).toBe('QmcQcvDYP9jkh3Q5gWmMkWmhVTDfeJFUPSKtnZRBqDXXQY');
+      expect(repo?.headCommit).toBe('aa'.repeat(20));
+    });
+
+    it('should follow the redirect left by a rename', async () => {
+      const target = Keypair.generate().publicKey;
+      const redirect = Buffer.concat([
+        Buffer.alloc(8), // discriminator
+        keypair.publicKey.toBuffer(), // creator
+        target.toBuffer(),
+        encodeString('old-name'),
+        Buffer.alloc(9), // created_at, bump
+      ]);
+
+      // eslint-disable-next-line @typescript-eslint/no-explicit-any
+      const getAccountInfo = (client as any).connection.getAccountInfo;
+      getAccountInfo
+        .mockResolvedValueOnce(null)
+        .mockResolvedValueOnce({ data: redirect })
+        .mockResolvedValueOnce({ data: encodeRepo('Renamed', 0) });
+
+      const repo = await client.getRepository(keypair.publicKey, 'old-name');
+      expect(repo?.name).toBe('my-repo');
+      expect(getAccountInfo).toHaveBeenLastCalledWith(target);
+    });
+
+    it('should resolve description stored on IPFS', async () => {
+      mockAccountData(encodeRepo('QmDescriptionCid', 1));
+
+      const repo = await client.getRepository(keypair.publicKey, 'my-repo');
+      expect(repo?.description).toBe('mock-bundle');
+      expect(repo?.descriptionStorage).toBe('ipfs');
+    });
   });
 
   describe('listUserRepositories', () => {
@@ -211,18 +290,68 @@ describe('SolRepoClient', () => {
     });
   });
 
+  describe('findDormantCollaborators', () => {
+    const encodeCollaborator = (
+      user: PublicKey,
+      canPush: boolean,
+      addedAt: number,
+      lastPushAt: number
+    ): Buffer => {
+      const data = Buffer.alloc(92);
+      keypair.publicKey.toBuffer().copy(data, 8);
+      user.toBuffer().copy(data, 40);
+      data.writeUInt8(canPush ? 1 : 0, 72);
+      data.writeBigInt64LE(BigInt(addedAt), 74);
+      data.writeBigInt64LE(BigInt(lastPushAt), 82);
+      return data;
+    };
+
+    it('should flag writers who have not pushed recently', async () => {
+      const active = Keypair.generate().publicKey;
+      const dormant = Keypair.generate().publicKey;
+      const neverPushed = Keypair.generate().publicKey;
+      const readOnly = Keypair.generate().publicKey;
+
+      // eslint-disable-next-line @typescript-eslint/no-explicit-any
+      (client as any).connection.getProgramAccounts.mockResolvedValueOnce(
+        [
+          encodeCollaborator(active, true, 100, 950),
+          encodeCollaborator(dormant, true, 100, 200),
+          encodeCollaborator(neverPushed, true, 100, 0),
+          encodeCollaborator(readOnly, false, 100, 0),
+        ].map((data) => ({ account: { data } }))
+      );
+
+      const result = await client.findDormantCollaborators(keypair.publicKey, 500, 1000);
+      expect(result.map((collab) => collab.user)).toEqual([dormant, neverPushed]);
+    });
+  });
+
   describe('cloneRepository', () => {
     beforeEach(() => {
       // Mock getRepository to return a repo
       jest.spyOn(client, 'getRepository').mockResolvedValue({
         name: 'test-repo',
         owner: keypair.publicKey,
+        flags: 0,
         description: 'Test',
+        descriptionStorage: 'inline',
         isPrivate: false,
+        keyEpoch: 0,
         createdAt: Date.now(),
         updatedAt: Date.now(),
         headCommit: 'a'.repeat(40),
         ipfsCid: 'QmTestCid',
+        objectsCid: '',
+        ipnsName: '',
+        stars: 0,
+        deletedAt: 0,
+        refsRoot: '0'.repeat(64),
+        weightedStars: 0,
+        successor: null,
+        template: null,
+        collaboratorCount: 0,
+        pinners: [],
       });
     });
 
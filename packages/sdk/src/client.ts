@@ -7,10 +7,23 @@ import {
   SystemProgram,
   sendAndConfirmTransaction,
 } from '@solana/web3.js';
-import { PROGRAM_ID, SEEDS, NETWORKS } from './constants';
-import { Repository, Commit, PushResult, RepoListItem, UserProfile } from './types';
+import { PROGRAM_ID, SEEDS, NETWORKS, ACCOUNT_SIZES, REPO_FLAGS_OFFSET } from './constants';
+import {
+  Repository,
+  Commit,
+  PushResult,
+  RepoListItem,
+  UserProfile,
+  CollaboratorInfo,
+  AuditAction,
+  AuditEntry,
+  Verification,
+  ReadGrant,
+} from './types';
 import { IPFSStorage, packRepository, getRepoMetadata } from './storage';
-import { deriveEncryptionKey, signMessage } from './crypto';
+import { deriveEncryptionKey, signMessage, toBase32 } from './crypto';
+import bs58 from 'bs58';
+import { createHash } from 'crypto';
 
 export interface Logger {
   log: (message: string) => void;
@@ -18,6 +31,19 @@ export interface Logger {
   error: (message: string) => void;
 }
 
+// Indexed by the on-chain AuditAction discriminant
+const AUDIT_ACTIONS: AuditAction[] = [
+  'ownershipTransferred',
+  'collaboratorAdded',
+  'collaboratorPromoted',
+  'collaboratorDemoted',
+  'collaboratorRemoved',
+  'renamed',
+  'deleted',
+  'readGranted',
+  'readRevoked',
+];
+
 const noopLogger: Logger = {
   log: () => {},
   warn: () => {},
@@ -65,6 +91,26 @@ export class VanishClient {
     return this.keypair?.publicKey || null;
   }
 
+  /**
+   * Derive the program-wide config PDA
+   */
+  getConfigPDA(): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from(SEEDS.CONFIG)],
+      PROGRAM_ID
+    );
+  }
+
+  /**
+   * Derive the PDA collecting protocol fees
+   */
+  getTreasuryPDA(): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from(SEEDS.TREASURY)],
+      PROGRAM_ID
+    );
+  }
+
   /**
    * Derive the PDA for a repository
    */
@@ -79,6 +125,61 @@ export class VanishClient {
     );
   }
 
+  /**
+   * Derive the PDA a renamed repository leaves under its old name
+   */
+  getRedirectPDA(creator: PublicKey, repoName: string): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from(SEEDS.REDIRECT), creator.toBuffer(), Buffer.from(repoName)],
+      PROGRAM_ID
+    );
+  }
+
+  /**
+   * Derive the PDA locking a repository name for its creator, whatever its
+   * case
+   */
+  getNameLockPDA(creator: PublicKey, repoName: string): [PublicKey, number] {
+    const nameHash = createHash('sha256')
+      .update(repoName.toLowerCase())
+      .digest();
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from(SEEDS.NAME_LOCK), creator.toBuffer(), nameHash],
+      PROGRAM_ID
+    );
+  }
+
+  /**
+   * Derive the PDA marking a repository as verified, from the address it was
+   * created at
+   */
+  getVerificationPDA(repository: PublicKey): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from(SEEDS.VERIFICATION), repository.toBuffer()],
+      PROGRAM_ID
+    );
+  }
+
+  /**
+   * Derive the PDA granting `reader` read access without collaborator rights
+   */
+  getReadGrantPDA(repository: PublicKey, reader: PublicKey): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from(SEEDS.READ_GRANT), repository.toBuffer(), reader.toBuffer()],
+      PROGRAM_ID
+    );
+  }
+
+  /**
+   * Derive the PDA holding a collaborator's sealed key for a private repository
+   */
+  getAccessKeyPDA(repository: PublicKey, user: PublicKey): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from(SEEDS.ACCESS_KEY), repository.toBuffer(), user.toBuffer()],
+      PROGRAM_ID
+    );
+  }
+
   /**
    * Derive the PDA for a user profile
    */
@@ -90,18 +191,38 @@ export class VanishClient {
   }
 
   /**
-   * Create a new repository on-chain
+   * Derive the PDA escrowing the stake for a repository's short name
+   */
+  getNameStakePDA(repository: PublicKey): [PublicKey, number] {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from(SEEDS.NAME_STAKE), repository.toBuffer()],
+      PROGRAM_ID
+    );
+  }
+
+  /**
+   * Create a new repository on-chain. Names shorter than the config's stake
+   * length need `stakeName`, which escrows the configured stake.
    */
   async createRepository(
     name: string,
     description: string,
-    isPrivate: boolean
+    isPrivate: boolean,
+    options: { stakeName?: boolean } = {}
   ): Promise<string> {
     if (!this.keypair) {
       throw new Error('Wallet not connected');
     }
 
     const [repoPDA] = this.getRepoPDA(this.keypair.publicKey, name);
+    const [nameLockPDA] = this.getNameLockPDA(this.keypair.publicKey, name);
+    const [configPDA] = this.getConfigPDA();
+    const [treasuryPDA] = this.getTreasuryPDA();
+    const [profilePDA] = this.getUserPDA(this.keypair.publicKey);
+    // Anchor reads the program id as "not passed" for optional accounts
+    const nameStake = options.stakeName
+      ? this.getNameStakePDA(repoPDA)[0]
+      : PROGRAM_ID;
 
     // Create instruction data
     const instructionData = Buffer.alloc(256);
@@ -131,6 +252,11 @@ export class VanishClient {
       keys: [
         { pubkey: this.keypair.publicKey, isSigner: true, isWritable: true },
         { pubkey: repoPDA, isSigner: false, isWritable: true },
+        { pubkey: nameLockPDA, isSigner: false, isWritable: true },
+        { pubkey: nameStake, isSigner: false, isWritable: !!options.stakeName },
+        { pubkey: profilePDA, isSigner: false, isWritable: true },
+        { pubkey: configPDA, isSigner: false, isWritable: false },
+        { pubkey: treasuryPDA, isSigner: false, isWritable: true },
         { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
       ],
       programId: PROGRAM_ID,
@@ -287,13 +413,26 @@ export class VanishClient {
     const [repoPDA] = this.getRepoPDA(owner, repoName);
 
     try {
-      const accountInfo = await this.connection.getAccountInfo(repoPDA);
+      let accountInfo = await this.connection.getAccountInfo(repoPDA);
       if (!accountInfo) {
-        return null;
+        // The repository may have been renamed; follow its redirect
+        const [redirectPDA] = this.getRedirectPDA(owner, repoName);
+        const redirect = await this.connection.getAccountInfo(redirectPDA);
+        if (!redirect) {
+          return null;
+        }
+
+        // Skip discriminator and creator to reach the target address
+        const target = new PublicKey(redirect.data.slice(40, 72));
+        accountInfo = await this.connection.getAccountInfo(target);
+        if (!accountInfo) {
+          return null;
+        }
       }
 
       // Parse account data
-      return this.parseRepoAccount(accountInfo.data, owner);
+      const repo = this.parseRepoAccount(accountInfo.data, owner);
+      return await this.resolveDescription(repo);
     } catch {
       return null;
     }
@@ -324,83 +463,399 @@ export class VanishClient {
   }
 
   /**
-   * Parse repository account data
+   * List repositories whose classification flags are exactly `flags`
+   * (e.g. REPO_FLAGS.FORK | REPO_FLAGS.ARCHIVED), filtered server-side
    */
-  private parseRepoAccount(data: Buffer, owner: PublicKey): Repository {
-    // Simplified parsing - in production, use borsh or anchor
+  async listRepositoriesByFlags(flags: number): Promise<RepoListItem[]> {
+    const accounts = await this.connection.getProgramAccounts(PROGRAM_ID, {
+      filters: [
+        {
+          memcmp: {
+            offset: REPO_FLAGS_OFFSET,
+            bytes: bs58.encode(Buffer.from([flags])),
+          },
+        },
+      ],
+    });
+
+    return accounts.map((account) => this.parseRepoListItem(account.account.data));
+  }
+
+  /**
+   * List the collaborators of a repository, including when each last pushed
+   */
+  async listCollaborators(repository: PublicKey): Promise<CollaboratorInfo[]> {
+    const sizes = [
+      ACCOUNT_SIZES.COLLABORATOR,
+      ACCOUNT_SIZES.COLLABORATOR_V1,
+      ACCOUNT_SIZES.COLLABORATOR_V0,
+    ];
+    const results = await Promise.all(
+      sizes.map((dataSize) =>
+        this.connection.getProgramAccounts(PROGRAM_ID, {
+          filters: [
+            { dataSize },
+            {
+              memcmp: {
+                offset: 8, // After discriminator
+                bytes: repository.toBase58(),
+              },
+            },
+          ],
+        })
+      )
+    );
+
+    return results
+      .flat()
+      .map((account) => this.parseCollaboratorAccount(account.account.data));
+  }
+
+  /**
+   * List the collaborators holding index slots 0 to `count` (a repository's
+   * `collaboratorCount`) with two batched account fetches instead of a
+   * program account scan. `repository` is the address the repository was
+   * created at. Collaborators added before slots existed are left out until
+   * they are indexed.
+   */
+  async listCollaboratorsByIndex(repository: PublicKey, count: number): Promise<CollaboratorInfo[]> {
+    const slots = Array.from({ length: count }, (_, index) => {
+      const seed = Buffer.alloc(4);
+      seed.writeUInt32LE(index);
+      return PublicKey.findProgramAddressSync(
+        [Buffer.from(SEEDS.COLLABORATOR_SLOT), repository.toBuffer(), seed],
+        PROGRAM_ID
+      )[0];
+    });
+
+    // Skip discriminator, repository and index to reach the user
+    const users = (await this.connection.getMultipleAccountsInfo(slots))
+      .filter((slot): slot is NonNullable<typeof slot> => slot !== null)
+      .map((slot) => new PublicKey(slot.data.slice(8 + 32 + 4, 8 + 32 + 4 + 32)));
+
+    const collaborators = await this.connection.getMultipleAccountsInfo(
+      users.map(
+        (user) =>
+          PublicKey.findProgramAddressSync(
+            [Buffer.from(SEEDS.COLLABORATOR), repository.toBuffer(), user.toBuffer()],
+            PROGRAM_ID
+          )[0]
+      )
+    );
+
+    return collaborators
+      .filter((collab): collab is NonNullable<typeof collab> => collab !== null)
+      .map((collab) => this.parseCollaboratorAccount(collab.data));
+  }
+
+  /**
+   * Read a repository's audit log, oldest first. `repository` is the address
+   * the repository was created at; entries remain after it is purged.
+   */
+  async listAuditLog(repository: PublicKey): Promise<AuditEntry[]> {
+    const accounts = await this.connection.getProgramAccounts(PROGRAM_ID, {
+      filters: [
+        { dataSize: ACCOUNT_SIZES.AUDIT_ENTRY },
+        {
+          memcmp: {
+            offset: 8, // After discriminator
+            bytes: repository.toBase58(),
+          },
+        },
+      ],
+    });
+
+    return accounts
+      .map((account) => this.parseAuditEntry(account.account.data))
+      .sort((a, b) => a.seq - b.seq);
+  }
+
+  /**
+   * Get the read grant issued to `reader`, or null if there is none. Callers
+   * deciding access should also check `expiresAt`.
+   */
+  async getReadGrant(repository: PublicKey, reader: PublicKey): Promise<ReadGrant | null> {
+    const [grantPDA] = this.getReadGrantPDA(repository, reader);
+    const accountInfo = await this.connection.getAccountInfo(grantPDA);
+    if (!accountInfo) {
+      return null;
+    }
+
+    const data = accountInfo.data;
+    let offset = 8 + 32 + 32; // Skip discriminator, repository and reader
+
+    const grantedBy = new PublicKey(data.slice(offset, offset + 32));
+    offset += 32;
+
+    const grantedAt = Number(data.readBigInt64LE(offset));
+    offset += 8;
+
+    const expiresAt = Number(data.readBigInt64LE(offset));
+
+    return { repository, reader, grantedBy, grantedAt, expiresAt };
+  }
+
+  /**
+   * Get a repository's verification, or null if it is not verified.
+   * `repository` is the address the repository was created at.
+   */
+  async getVerification(repository: PublicKey): Promise<Verification | null> {
+    const [verificationPDA] = this.getVerificationPDA(repository);
+    const accountInfo = await this.connection.getAccountInfo(verificationPDA);
+    if (!accountInfo) {
+      return null;
+    }
+
+    const data = accountInfo.data;
     let offset = 8; // Skip discriminator
 
-    const nameLength = data.readUInt8(offset);
+    const verifier = new PublicKey(data.slice(offset + 32, offset + 64));
+    offset += 64;
+
+    const uriLen = data.readUInt32LE(offset);
+    offset += 4;
+    const uri = data.slice(offset, offset + uriLen).toString('utf8');
+    offset += uriLen;
+
+    const verifiedAt = Number(data.readBigInt64LE(offset));
+
+    return { repository, verifier, uri, verifiedAt };
+  }
+
+  /**
+   * Find collaborators with push access who haven't pushed within `maxIdleSeconds`.
+   * Collaborators who never pushed are measured from when they were added.
+   */
+  async findDormantCollaborators(
+    repository: PublicKey,
+    maxIdleSeconds: number,
+    now: number = Math.floor(Date.now() / 1000)
+  ): Promise<CollaboratorInfo[]> {
+    const collaborators = await this.listCollaborators(repository);
+
+    return collaborators.filter((collab) => {
+      const lastActive = collab.lastPushAt || collab.addedAt;
+      return collab.canPush && now - lastActive > maxIdleSeconds;
+    });
+  }
+
+  /**
+   * Parse collaborator account data
+   */
+  private parseCollaboratorAccount(data: Buffer): CollaboratorInfo {
+    let offset = 8; // Skip discriminator
+
+    const repository = new PublicKey(data.slice(offset, offset + 32));
+    offset += 32;
+
+    const user = new PublicKey(data.slice(offset, offset + 32));
+    offset += 32;
+
+    const canPush = data.readUInt8(offset) === 1;
     offset += 1;
-    const name = data.toString('utf-8', offset, offset + nameLength);
-    offset += nameLength;
 
-    const descLength = data.readUInt16LE(offset);
-    offset += 2;
-    const description = data.toString('utf-8', offset, offset + descLength);
-    offset += descLength;
+    const isAdmin = data.readUInt8(offset) === 1;
+    offset += 1;
+
+    const addedAt = Number(data.readBigInt64LE(offset));
+    offset += 8;
+
+    const lastPushAt = Number(data.readBigInt64LE(offset));
+    offset += 8 + 1 + 1; // last_push_at, bump, version
+
+    // Older layouts end before the Option<u32> index
+    const index =
+      data.length > offset && data.readUInt8(offset) === 1 ? data.readUInt32LE(offset + 1) : null;
+
+    return { repository, user, canPush, isAdmin, addedAt, lastPushAt, index };
+  }
+
+  /**
+   * Parse audit entry account data
+   */
+  private parseAuditEntry(data: Buffer): AuditEntry {
+    let offset = 8; // Skip discriminator
+
+    const repository = new PublicKey(data.slice(offset, offset + 32));
+    offset += 32;
+
+    const seq = Number(data.readBigUInt64LE(offset));
+    offset += 8;
+
+    const actor = new PublicKey(data.slice(offset, offset + 32));
+    offset += 32;
+
+    const action = AUDIT_ACTIONS[data.readUInt8(offset)];
+    offset += 1;
+
+    const subject = new PublicKey(data.slice(offset, offset + 32));
+    offset += 32;
+
+    const timestamp = Number(data.readBigInt64LE(offset));
+
+    return { repository, seq, actor, action, subject, timestamp };
+  }
+
+  /**
+   * Parse repository account data (Borsh layout of the on-chain Repository)
+   */
+  private parseRepoAccount(data: Buffer, owner: PublicKey): Repository {
+    let offset = REPO_FLAGS_OFFSET; // Skip discriminator, owner and creator
+
+    const flags = data.readUInt8(offset);
+    offset += 1;
+
+    const readString = (): string => {
+      const length = data.readUInt32LE(offset);
+      offset += 4;
+      const value = data.toString('utf-8', offset, offset + length);
+      offset += length;
+      return value;
+    };
+
+    // ObjectId: a u8 multihash code (0x11 SHA-1, 0x12 SHA-256, 0 before
+    // the first push), then the digest zero-padded to 32 bytes
+    const readObjectId = (): string => {
+      const code = data.readUInt8(offset);
+      const length = code === 0x11 ? 20 : code === 0x12 ? 32 : 0;
+      const digest = data.toString('hex', offset + 1, offset + 1 + length);
+      offset += 33;
+      return digest;
+    };
+
+    // Cid: a u8 length, then the binary CID padded to 40 bytes, printed as
+    // IPFS does: base58btc for CIDv0, multibase base32 for CIDv1
+    const readCid = (): string => {
+      const length = Math.min(data.readUInt8(offset), 40);
+      const bytes = data.slice(offset + 1, offset + 1 + length);
+      offset += 41;
+      if (length === 0) {
+        return '';
+      }
+      if (bytes[0] === 0x12 && bytes[1] === 0x20) {
+        return bs58.encode(bytes);
+      }
+      return 'b' + toBase32(bytes);
+    };
+
+    const name = readString();
+    const description = readString();
+
+    const descriptionStorage = data.readUInt8(offset) === 1 ? 'ipfs' : 'inline';
+    offset += 1;
 
     const isPrivate = data.readUInt8(offset) === 1;
     offset += 1;
 
+    const keyEpoch = data.readUInt32LE(offset);
+    offset += 4;
+
     const createdAt = Number(data.readBigInt64LE(offset));
     offset += 8;
 
     const updatedAt = Number(data.readBigInt64LE(offset));
     offset += 8;
 
-    const headCommit = data.toString('utf-8', offset, offset + 40);
-    offset += 40;
+    const headCommit = readObjectId();
+    const ipfsCid = readCid();
+    const objectsCid = readString();
+    const ipnsName = readString();
 
-    const cidLength = data.readUInt8(offset);
-    offset += 1;
-    const ipfsCid = data.toString('utf-8', offset, offset + cidLength);
+    const stars = Number(data.readBigUInt64LE(offset));
+    offset += 8 + 4 + 32 + 1 + 1; // stars, admin_count, id, bump, version
+
+    const deletedAt = Number(data.readBigInt64LE(offset));
+    offset += 8 + 8; // deleted_at, purgeable_at
+
+    const refsRoot = data.toString('hex', offset, offset + 32);
+    offset += 32 + 4; // refs_root, ref_count
+
+    // Fixed point, one star = 2^20
+    const weightedStars = Number(data.readBigUInt64LE(offset)) / 2 ** 20;
+    offset += 8 + 8 + 8; // weighted_stars, stars_decayed_at, audit_count
+
+    // Option<Pubkey>: a tag byte, then the key when present
+    const readOptionalKey = (): PublicKey | null => {
+      const present = data.readUInt8(offset) === 1;
+      offset += 1;
+      if (!present) {
+        return null;
+      }
+      const key = new PublicKey(data.slice(offset, offset + 32));
+      offset += 32;
+      return key;
+    };
+
+    const successor = readOptionalKey();
+    const template = readOptionalKey();
+    const collaboratorCount = data.readUInt32LE(offset);
+    offset += 4;
+
+    const pinnerCount = data.readUInt32LE(offset);
+    offset += 4;
+    const pinners: PublicKey[] = [];
+    for (let i = 0; i < pinnerCount; i++) {
+      pinners.push(new PublicKey(data.slice(offset, offset + 32)));
+      offset += 32;
+    }
 
     return {
       name,
       owner,
+      flags,
       description,
+      descriptionStorage,
       isPrivate,
+      keyEpoch,
       createdAt,
       updatedAt,
       headCommit,
       ipfsCid,
+      objectsCid,
+      ipnsName,
+      stars,
+      deletedAt,
+      refsRoot,
+      weightedStars,
+      successor,
+      template,
+      collaboratorCount,
+      pinners,
     };
   }
 
   /**
-   * Parse repository list item from account data
+   * Replace an off-chain description CID with the text it points to.
+   * If the CID can't be fetched the repository is returned unchanged.
    */
-  private parseRepoListItem(data: Buffer): RepoListItem {
-    let offset = 8;
-
-    const ownerBytes = data.slice(offset, offset + 32);
-    offset += 32;
-
-    const nameLength = data.readUInt8(offset);
-    offset += 1;
-    const name = data.toString('utf-8', offset, offset + nameLength);
-    offset += nameLength;
-
-    const descLength = data.readUInt16LE(offset);
-    offset += 2;
-    const description = data.toString('utf-8', offset, offset + descLength);
-    offset += descLength;
-
-    const isPrivate = data.readUInt8(offset) === 1;
-    offset += 1;
+  private async resolveDescription(repo: Repository): Promise<Repository> {
+    if (repo.descriptionStorage !== 'ipfs') {
+      return repo;
+    }
 
-    offset += 8; // Skip createdAt
+    try {
+      const content = await this.storage.download(repo.description);
+      return { ...repo, description: content.toString('utf-8') };
+    } catch {
+      this.logger.warn(`Could not fetch description from IPFS: ${repo.description}`);
+      return repo;
+    }
+  }
 
-    const updatedAt = Number(data.readBigInt64LE(offset));
+  /**
+   * Parse repository list item from account data
+   */
+  private parseRepoListItem(data: Buffer): RepoListItem {
+    const owner = new PublicKey(data.slice(8, 40));
+    const repo = this.parseRepoAccount(data, owner);
 
     return {
-      name,
-      owner: new PublicKey(ownerBytes).toBase58(),
-      description,
-      isPrivate,
-      lastUpdated: new Date(updatedAt * 1000),
-      stars: 0,
+      name: repo.name,
+      owner: owner.toBase58(),
+      description: repo.description,
+      isPrivate: repo.isPrivate,
+      lastUpdated: new Date(repo.updatedAt * 1000),
+      stars: repo.stars,
     };
   }
 }
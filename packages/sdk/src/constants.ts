@@ -18,6 +18,41 @@ export const SEEDS = {
   USER: 'user',
   COMMIT: 'commit',
   COLLABORATOR: 'collab',
+  COLLABORATOR_SLOT: 'collab_slot',
+  ACCESS_KEY: 'access',
+  REDIRECT: 'redirect',
+  NAME_LOCK: 'name_lock',
+  CONFIG: 'config',
+  TREASURY: 'treasury',
+  NAME_STAKE: 'stake',
+  AUDIT: 'audit',
+  VERIFICATION: 'verification',
+  READ_GRANT: 'read',
+} as const;
+
+// Repository classification bits, stored in a single byte
+export const REPO_FLAGS = {
+  FORK: 1 << 0,
+  MIRROR: 1 << 1,
+  GENERATED: 1 << 2,
+  ARCHIVED: 1 << 3,
+  ALLOW_FORCE_PUSH: 1 << 4,
+  FROZEN: 1 << 5,
+  DEPRECATED: 1 << 6,
+  TEMPLATE: 1 << 7,
+} as const;
+
+// Offset of the flags byte in repository account data (discriminator + owner + creator)
+export const REPO_FLAGS_OFFSET = 8 + 32 + 32;
+
+// On-chain account sizes, used to tell account types apart in getProgramAccounts
+export const ACCOUNT_SIZES = {
+  COLLABORATOR: 8 + 32 + 32 + 1 + 1 + 8 + 8 + 1 + 1 + 1 + 4,
+  // Collaborators created before the index slot, until migrate_account runs
+  COLLABORATOR_V1: 8 + 32 + 32 + 1 + 1 + 8 + 8 + 1 + 1,
+  // Collaborators created before the version byte, until migrate_account runs
+  COLLABORATOR_V0: 8 + 32 + 32 + 1 + 1 + 8 + 8 + 1,
+  AUDIT_ENTRY: 8 + 32 + 8 + 32 + 1 + 32 + 8 + 1,
 } as const;
 
 // Network endpoints
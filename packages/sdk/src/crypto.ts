@@ -118,3 +118,25 @@ export function toBase58(data: Uint8Array): string {
 export function fromBase58(str: string): Uint8Array {
   return bs58.decode(str);
 }
+
+/**
+ * Encode bytes to lowercase, unpadded RFC 4648 base32, as CIDv1 are printed
+ */
+export function toBase32(data: Uint8Array): string {
+  const alphabet = 'abcdefghijklmnopqrstuvwxyz234567';
+  let text = '';
+  let buffer = 0;
+  let bits = 0;
+  for (const byte of data) {
+    buffer = ((buffer << 8) | byte) & 0xfff;
+    bits += 8;
+    while (bits >= 5) {
+      bits -= 5;
+      text += alphabet[(buffer >> bits) & 31];
+    }
+  }
+  if (bits > 0) {
+    text += alphabet[(buffer << (5 - bits)) & 31];
+  }
+  return text;
+}
@@ -1,14 +1,99 @@
 import { PublicKey } from '@solana/web3.js';
 
+/**
+ * Where a repository description is stored. With 'ipfs' the on-chain field
+ * holds a CID and the text itself lives off-chain.
+ */
+export type DescriptionStorage = 'inline' | 'ipfs';
+
 export interface Repository {
   name: string;
   owner: PublicKey;
+  /** Classification bits, see REPO_FLAGS */
+  flags: number;
   description: string;
+  descriptionStorage: DescriptionStorage;
   isPrivate: boolean;
+  /** Bumped on every key rotation; archives are encrypted under the current epoch's key */
+  keyEpoch: number;
   createdAt: number;
   updatedAt: number;
   headCommit: string;
   ipfsCid: string;
+  /** Shared object store referenced by per-branch archives */
+  objectsCid: string;
+  ipnsName: string;
+  stars: number;
+  /** When the repository was deleted, 0 while live. Deleted repositories can be restored until purged. */
+  deletedAt: number;
+  /** Hex merkle root over every branch tip, to check a ref listing served off-chain against */
+  refsRoot: string;
+  /** Trending score in stars, each star's weight halving weekly; as of the last star, unstar or decay_stars */
+  weightedStars: number;
+  /** Repository that replaces this one, if it is deprecated (REPO_FLAGS.DEPRECATED) and names one */
+  successor: PublicKey | null;
+  /** Template the repository was created from, by its original address */
+  template: PublicKey | null;
+  /** Collaborators holding an index slot, see listCollaboratorsByIndex */
+  collaboratorCount: number;
+  /** Operators of the pinners enrolled to keep the repository's archives available */
+  pinners: PublicKey[];
+}
+
+/** Order matches the on-chain AuditAction enum */
+export type AuditAction =
+  | 'ownershipTransferred'
+  | 'collaboratorAdded'
+  | 'collaboratorPromoted'
+  | 'collaboratorDemoted'
+  | 'collaboratorRemoved'
+  | 'renamed'
+  | 'deleted'
+  | 'readGranted'
+  | 'readRevoked';
+
+export interface AuditEntry {
+  /** Address the repository was created at, which stays the same across renames */
+  repository: PublicKey;
+  seq: number;
+  actor: PublicKey;
+  action: AuditAction;
+  /** New owner, collaborator, new address after a rename, or the repository itself when deleted */
+  subject: PublicKey;
+  timestamp: number;
+}
+
+export interface ReadGrant {
+  /** Address the repository was created at, which stays the same across renames */
+  repository: PublicKey;
+  reader: PublicKey;
+  grantedBy: PublicKey;
+  grantedAt: number;
+  /** When the grant lapses, 0 if it never does */
+  expiresAt: number;
+}
+
+export interface Verification {
+  /** Address the repository was created at, which stays the same across renames */
+  repository: PublicKey;
+  /** Config admin or registered verifier who verified it */
+  verifier: PublicKey;
+  /** Where the attestation backing the verification lives */
+  uri: string;
+  verifiedAt: number;
+}
+
+export interface CollaboratorInfo {
+  repository: PublicKey;
+  user: PublicKey;
+  canPush: boolean;
+  /** Admins can manage collaborators and settings, and run ownerless repositories */
+  isAdmin: boolean;
+  addedAt: number;
+  /** Unix timestamp of the collaborator's last push, or 0 if they never pushed */
+  lastPushAt: number;
+  /** Index slot, or null for collaborators added before slots existed */
+  index: number | null;
 }
 
 export interface Commit {
@@ -0,0 +1,351 @@
+//! Deterministic Vanish fixtures for integrators.
+//!
+//! [`Fixture`] runs the program natively under solana-program-test, with a
+//! Config administered by the test payer, and builds repositories through
+//! the program's own instructions, so every fixture is a state the program
+//! can actually reach. Keypairs are derived from labels, so the same
+//! fixture has the same addresses on every run. [`Fixture::write_account_dir`]
+//! dumps what was built for `solana-test-validator --account-dir`, for
+//! frontends that develop against a local validator.
+//!
+//! ```no_run
+//! # async fn example() -> Result<(), solana_program_test::BanksClientError> {
+//! use solana_sdk::signer::Signer;
+//! use vanish_program::Multihash;
+//! use vanish_test_utils::Fixture;
+//!
+//! let mut fixture = Fixture::new().await;
+//! let owner = fixture.user("owner").await;
+//! let writer = fixture.user("writer").await;
+//! let fan = fixture.user("fan").await;
+//! let commit = Multihash { code: Multihash::SHA1, digest: vec![1; 20] };
+//!
+//! let repository = fixture
+//!     .repo(&owner, "vanish")
+//!     .head(commit.clone())
+//!     .collaborator(writer.pubkey(), true)
+//!     .star(&fan)
+//!     .branch("dev", commit)
+//!     .create()
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::path::Path;
+
+use anchor_lang::prelude::{AccountInfo, Pubkey};
+use anchor_lang::solana_program::entrypoint::ProgramResult;
+use anchor_lang::{AccountDeserialize, InstructionData, ToAccountMetas};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use solana_program_test::{processor, BanksClientError, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    compute_budget::ComputeBudgetInstruction,
+    instruction::Instruction,
+    native_token::LAMPORTS_PER_SOL,
+    signature::{keypair_from_seed, Keypair},
+    signer::Signer,
+    system_instruction,
+    transaction::Transaction,
+};
+use vanish_client::{encryption, encryption::RepoKey, instructions, pda};
+use vanish_program::{accounts, instruction, refs_tree, Cid, Multihash, Repository};
+
+/// The archive CID fixtures push; nothing is uploaded behind it.
+pub const FIXTURE_CID: &str = "QmcQcvDYP9jkh3Q5gWmMkWmhVTDfeJFUPSKtnZRBqDXXQY";
+
+/// The program's entrypoint as a solana-program-test processor, for
+/// harnesses that build their own [`ProgramTest`].
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    // The Anchor entrypoint wants the account slice to outlive the infos in it
+    let accounts = Box::leak(Box::new(accounts.to_vec()));
+    vanish_program::entry(program_id, accounts, data)
+}
+
+/// The program running under solana-program-test.
+pub struct Fixture {
+    pub ctx: ProgramTestContext,
+    /// Bumped per transaction so identical instructions still get distinct
+    /// signatures
+    nonce: u32,
+}
+
+impl Fixture {
+    /// Start the program and create its Config, administered by the payer.
+    pub async fn new() -> Self {
+        let mut program =
+            ProgramTest::new("vanish_program", vanish_program::ID, processor!(process));
+        program.prefer_bpf(false);
+        let mut fixture = Self {
+            ctx: program.start_with_context().await,
+            nonce: 0,
+        };
+
+        let init = Instruction {
+            program_id: vanish_program::ID,
+            accounts: accounts::InitializeConfig {
+                admin: fixture.payer(),
+                config: pda::config(),
+                treasury: pda::treasury(),
+                system_program: solana_sdk::system_program::ID,
+            }
+            .to_account_metas(None),
+            data: instruction::InitializeConfig {
+                repo_fee: 0,
+                flags: 0,
+            }
+            .data(),
+        };
+        fixture
+            .send(&[init], &[])
+            .await
+            .expect("a fresh deployment takes its Config");
+        fixture
+    }
+
+    pub fn payer(&self) -> Pubkey {
+        self.ctx.payer.pubkey()
+    }
+
+    /// The keypair for `label`, the same on every run.
+    pub fn keypair(label: &str) -> Keypair {
+        let seed = Sha256::digest(format!("vanish-test-utils {label}"));
+        keypair_from_seed(&seed).expect("a 32-byte seed")
+    }
+
+    /// The keypair for `label`, funded with 10 SOL.
+    pub async fn user(&mut self, label: &str) -> Keypair {
+        let user = Self::keypair(label);
+        let fund =
+            system_instruction::transfer(&self.payer(), &user.pubkey(), 10 * LAMPORTS_PER_SOL);
+        self.send(&[fund], &[])
+            .await
+            .expect("the payer funds fixture users");
+        user
+    }
+
+    /// Send `instructions` in one transaction paid for by the payer.
+    pub async fn send(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<(), BanksClientError> {
+        self.nonce += 1;
+        let mut all = vec![ComputeBudgetInstruction::set_compute_unit_limit(
+            1_400_000 - self.nonce,
+        )];
+        all.extend_from_slice(instructions);
+
+        let mut keypairs = vec![&self.ctx.payer];
+        keypairs.extend_from_slice(signers);
+
+        let blockhash = self.ctx.banks_client.get_latest_blockhash().await?;
+        let tx = Transaction::new_signed_with_payer(
+            &all,
+            Some(&self.ctx.payer.pubkey()),
+            &keypairs,
+            blockhash,
+        );
+        self.ctx.banks_client.process_transaction(tx).await
+    }
+
+    /// Fetch and decode a program account, `None` when it does not exist.
+    pub async fn account<T: AccountDeserialize>(
+        &mut self,
+        address: Pubkey,
+    ) -> Result<Option<T>, BanksClientError> {
+        let Some(account) = self.ctx.banks_client.get_account(address).await? else {
+            return Ok(None);
+        };
+        Ok(T::try_deserialize(&mut account.data.as_slice()).ok())
+    }
+
+    /// Start building a repository `owner` creates as `name`.
+    pub fn repo<'a>(&'a mut self, owner: &'a Keypair, name: &str) -> RepoBuilder<'a> {
+        RepoBuilder {
+            fixture: self,
+            owner,
+            name: name.to_string(),
+            description: String::new(),
+            key: None,
+            head: None,
+            collaborators: Vec::new(),
+            stars: Vec::new(),
+            branches: Vec::new(),
+        }
+    }
+
+    /// Write `addresses` into `dir` as `solana account --output json`
+    /// files, which `solana-test-validator --account-dir` loads at genesis.
+    /// Returns how many existed and were written.
+    pub async fn write_account_dir(
+        &mut self,
+        addresses: &[Pubkey],
+        dir: &Path,
+    ) -> std::io::Result<usize> {
+        std::fs::create_dir_all(dir)?;
+        let mut written = 0;
+        for address in addresses {
+            let account = self
+                .ctx
+                .banks_client
+                .get_account(*address)
+                .await
+                .map_err(std::io::Error::other)?;
+            let Some(account) = account else {
+                continue;
+            };
+            let file = json!({
+                "pubkey": address.to_string(),
+                "account": {
+                    "lamports": account.lamports,
+                    "data": [STANDARD.encode(&account.data), "base64"],
+                    "owner": account.owner.to_string(),
+                    "executable": account.executable,
+                    "rentEpoch": account.rent_epoch,
+                },
+            });
+            std::fs::write(
+                dir.join(format!("{address}.json")),
+                serde_json::to_vec_pretty(&file)?,
+            )?;
+            written += 1;
+        }
+        Ok(written)
+    }
+}
+
+/// A repository to fabricate, built up call by call and created by
+/// [`RepoBuilder::create`].
+pub struct RepoBuilder<'a> {
+    fixture: &'a mut Fixture,
+    owner: &'a Keypair,
+    name: String,
+    description: String,
+    /// Set for private repositories
+    key: Option<RepoKey>,
+    head: Option<Multihash>,
+    collaborators: Vec<(Pubkey, bool)>,
+    stars: Vec<&'a Keypair>,
+    branches: Vec<(String, Multihash)>,
+}
+
+impl<'a> RepoBuilder<'a> {
+    pub fn description(mut self, description: &str) -> Self {
+        self.description = description.to_string();
+        self
+    }
+
+    /// Make the repository private, sealing `key` to every collaborator.
+    pub fn private(mut self, key: RepoKey) -> Self {
+        self.key = Some(key);
+        self
+    }
+
+    /// Push `commit` to the default branch.
+    pub fn head(mut self, commit: Multihash) -> Self {
+        self.head = Some(commit);
+        self
+    }
+
+    /// Add `user` as a collaborator, able to push when `can_push`.
+    pub fn collaborator(mut self, user: Pubkey, can_push: bool) -> Self {
+        self.collaborators.push((user, can_push));
+        self
+    }
+
+    /// Star the repository as `user`.
+    pub fn star(mut self, user: &'a Keypair) -> Self {
+        self.stars.push(user);
+        self
+    }
+
+    /// Push a branch `name` at `commit`, as the owner.
+    pub fn branch(mut self, name: &str, commit: Multihash) -> Self {
+        self.branches.push((name.to_string(), commit));
+        self
+    }
+
+    /// Create the repository and everything asked for, one transaction per
+    /// step, returning its address.
+    pub async fn create(self) -> Result<Pubkey, BanksClientError> {
+        let Self {
+            fixture,
+            owner,
+            name,
+            description,
+            key,
+            head,
+            collaborators,
+            stars,
+            branches,
+        } = self;
+        let address = pda::repo(&owner.pubkey(), &name);
+        let create =
+            instructions::create_repo(&owner.pubkey(), &name, &description, key.is_some(), false);
+        fixture.send(&[create], &[owner]).await?;
+
+        if let Some(commit) = head {
+            let repo = repository(fixture, address).await?;
+            let push = instructions::push_update(
+                &owner.pubkey(),
+                &repo,
+                commit,
+                Cid::parse(FIXTURE_CID).unwrap(),
+                None,
+                None,
+                None,
+                None,
+            );
+            fixture.send(&[push], &[owner]).await?;
+        }
+
+        for (user, can_push) in collaborators {
+            let repo = repository(fixture, address).await?;
+            let envelope = key
+                .as_ref()
+                .map(|key| encryption::seal(key, &user).expect("fixture users are Ed25519 keys"));
+            let add =
+                instructions::add_collaborator(&owner.pubkey(), &repo, &user, can_push, envelope);
+            fixture.send(&[add], &[owner]).await?;
+        }
+
+        for user in stars {
+            let repo = repository(fixture, address).await?;
+            let star = instructions::star_repo(&user.pubkey(), &repo, 0);
+            fixture.send(&[star], &[user]).await?;
+        }
+
+        // New branches take the next leaves of the refs tree
+        let mut leaves = Vec::new();
+        for (branch, commit) in branches {
+            let repo = repository(fixture, address).await?;
+            let proof = refs_tree::proof(&leaves, repo.ref_count);
+            let push = instructions::push_branch(
+                &owner.pubkey(),
+                &repo,
+                &branch,
+                commit.clone(),
+                FIXTURE_CID,
+                None,
+                proof,
+            );
+            fixture.send(&[push], &[owner]).await?;
+            leaves.push(refs_tree::leaf(&branch, &commit));
+        }
+
+        Ok(address)
+    }
+}
+
+async fn repository(
+    fixture: &mut Fixture,
+    address: Pubkey,
+) -> Result<Repository, BanksClientError> {
+    Ok(fixture
+        .account(address)
+        .await?
+        .expect("the repository was just created"))
+}
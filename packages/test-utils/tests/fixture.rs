@@ -0,0 +1,78 @@
+use solana_sdk::signer::Signer;
+use vanish_client::pda;
+use vanish_program::{Branch, Cid, Collaborator, Multihash, Repository, Star};
+use vanish_test_utils::{Fixture, FIXTURE_CID};
+
+#[tokio::test]
+async fn the_documented_fixture_builds() {
+    let mut fixture = Fixture::new().await;
+    let owner = fixture.user("owner").await;
+    let writer = fixture.user("writer").await;
+    let fan = fixture.user("fan").await;
+    let commit = Multihash {
+        code: Multihash::SHA1,
+        digest: vec![1; 20],
+    };
+
+    let repository = fixture
+        .repo(&owner, "vanish")
+        .description("fixture")
+        .head(commit.clone())
+        .collaborator(writer.pubkey(), true)
+        .star(&fan)
+        .branch("dev", commit.clone())
+        .create()
+        .await
+        .unwrap();
+    assert_eq!(repository, pda::repo(&owner.pubkey(), "vanish"));
+
+    let repo: Repository = fixture.account(repository).await.unwrap().unwrap();
+    assert_eq!(repo.owner, owner.pubkey());
+    assert_eq!(repo.description, "fixture");
+    assert!(repo.head_commit == commit);
+    assert!(repo.ipfs_cid == Cid::parse(FIXTURE_CID).unwrap());
+    assert_eq!(repo.stars, 1);
+    assert_eq!(repo.ref_count, 1);
+
+    let collab: Collaborator = fixture
+        .account(pda::collab(&repo.id, &writer.pubkey()))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(collab.user, writer.pubkey());
+    assert!(collab.can_push);
+
+    let star: Star = fixture
+        .account(pda::star(&fan.pubkey(), &repo.id))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(star.repository, repository);
+
+    let branch: Branch = fixture
+        .account(pda::branch(&repo.id, "dev"))
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(branch.head_commit == commit);
+    assert_eq!(branch.ipfs_cid, FIXTURE_CID);
+    assert_eq!(branch.ref_index, Some(0));
+
+    // The same labels give the same addresses on every run
+    assert_eq!(owner.pubkey(), Fixture::keypair("owner").pubkey());
+    assert_ne!(owner.pubkey(), writer.pubkey());
+
+    let dir = std::env::temp_dir().join(format!("vanish-fixture-{}", std::process::id()));
+    let missing = pda::repo(&owner.pubkey(), "missing");
+    let written = fixture
+        .write_account_dir(&[repository, missing], &dir)
+        .await
+        .unwrap();
+    assert_eq!(written, 1);
+    let file: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(dir.join(format!("{repository}.json"))).unwrap())
+            .unwrap();
+    assert_eq!(file["pubkey"], repository.to_string());
+    assert_eq!(file["account"]["owner"], vanish_program::ID.to_string());
+    std::fs::remove_dir_all(dir).unwrap();
+}
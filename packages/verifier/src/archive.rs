@@ -0,0 +1,69 @@
+//! Fetching and unpacking repository archives.
+//!
+//! Archives are stored on IPFS under the repository's `ipfs_cid`: the
+//! deterministic archives git-remote-vanish pushes, or the `git bundle --all`
+//! files produced by the SDK's `packRepository`.
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::{bail, Context, Result};
+use vanish_client::archive::Archive;
+
+/// Download an archive through an IPFS HTTP gateway.
+pub fn download(gateway: &str, cid: &str) -> Result<Vec<u8>> {
+    let url = format!("{}/{}", gateway.trim_end_matches('/'), cid);
+
+    let response = reqwest::blocking::get(&url)
+        .and_then(|response| response.error_for_status())
+        .with_context(|| format!("failed to download {url}"))?;
+
+    Ok(response.bytes()?.to_vec())
+}
+
+/// The commit an archive's HEAD points at. Archives are checked object by
+/// object; bundles are unpacked.
+pub fn head(data: &[u8]) -> Result<String> {
+    if Archive::is_archive(data) {
+        return Ok(Archive::from_bytes(data)?.head);
+    }
+    bundle_head(data)
+}
+
+/// Unpack a bundle into a scratch repository and return the commit its HEAD
+/// points at.
+pub fn bundle_head(bundle: &[u8]) -> Result<String> {
+    let dir = tempfile::tempdir()?;
+    let bundle_path = dir.path().join("repo.bundle");
+    let clone_path = dir.path().join("repo.git");
+    fs::write(&bundle_path, bundle)?;
+
+    git(
+        dir.path(),
+        &[
+            "clone".as_ref(),
+            "--bare".as_ref(),
+            "--quiet".as_ref(),
+            bundle_path.as_os_str(),
+            clone_path.as_os_str(),
+        ],
+    )
+    .context("archive is not a valid git bundle")?;
+
+    git(&clone_path, &["rev-parse".as_ref(), "HEAD".as_ref()]).context("archive has no HEAD commit")
+}
+
+fn git(cwd: &Path, args: &[&std::ffi::OsStr]) -> Result<String> {
+    let output = Command::new("git")
+        .current_dir(cwd)
+        .args(args)
+        .output()
+        .context("failed to run git")?;
+
+    if !output.status.success() {
+        bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
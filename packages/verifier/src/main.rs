@@ -0,0 +1,136 @@
+//! Vanish pack verifier.
+//!
+//! Fetches a repository account, downloads the archive its `ipfs_cid` points
+//! at, unpacks it and checks that the archive's git HEAD equals the on-chain
+//! `head_commit`. The outcome is printed as a report signed by the verifier's
+//! keypair, so anyone can publish it and anyone else can check who vouched
+//! for it.
+
+mod archive;
+mod report;
+
+use std::process::ExitCode;
+
+use anchor_lang::AccountDeserialize;
+use anyhow::{anyhow, bail, Context, Result};
+use clap::{ArgGroup, Parser};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{pubkey::Pubkey, signature::read_keypair_file, signer::Signer};
+use vanish_program::Repository;
+
+use crate::report::Report;
+
+#[derive(Parser)]
+#[command(
+    name = "vanish-verify",
+    version,
+    about = "Verify a Vanish repository archive against its on-chain record",
+    group(ArgGroup::new("target").required(true).args(["repo", "owner"]))
+)]
+struct Args {
+    /// Repository account address
+    #[arg(long)]
+    repo: Option<Pubkey>,
+
+    /// Repository owner (used with --name to derive the repository address)
+    #[arg(long, requires = "name")]
+    owner: Option<Pubkey>,
+
+    /// Repository name
+    #[arg(long, requires = "owner")]
+    name: Option<String>,
+
+    /// Solana RPC endpoint
+    #[arg(
+        long,
+        env = "VANISH_RPC_URL",
+        default_value = "https://api.devnet.solana.com"
+    )]
+    url: String,
+
+    /// IPFS gateway used to fetch the archive
+    #[arg(
+        long,
+        env = "VANISH_IPFS_GATEWAY",
+        default_value = "https://ipfs.io/ipfs/"
+    )]
+    gateway: String,
+
+    /// Keypair used to sign the verification report
+    #[arg(
+        long,
+        env = "VANISH_KEYPAIR",
+        default_value = "~/.config/solana/id.json"
+    )]
+    keypair: String,
+}
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(1),
+        Err(err) => {
+            eprintln!("error: {err:#}");
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// Returns whether the archive matched the on-chain record.
+fn run() -> Result<bool> {
+    let args = Args::parse();
+
+    let address = match (args.repo, args.owner, args.name.as_deref()) {
+        (Some(repo), _, _) => repo,
+        (None, Some(owner), Some(name)) => repository_address(&owner, name),
+        _ => unreachable!("clap requires --repo or --owner with --name"),
+    };
+
+    let signer = read_keypair_file(expand_home(&args.keypair))
+        .map_err(|err| anyhow!("failed to read keypair {}: {err}", args.keypair))?;
+
+    let rpc = RpcClient::new(args.url);
+    let account = rpc
+        .get_account(&address)
+        .with_context(|| format!("failed to fetch repository {address}"))?;
+    if account.owner != vanish_program::ID {
+        bail!("{address} is not owned by the Vanish program");
+    }
+
+    let repo = Repository::try_deserialize(&mut account.data.as_slice())
+        .map_err(|err| anyhow!("{address} is not a Vanish repository: {err}"))?;
+    if repo.ipfs_cid.is_empty() {
+        bail!("repository {} has not been pushed yet", repo.name);
+    }
+    if repo.is_private {
+        bail!(
+            "repository {} is private; its archive is encrypted and cannot be verified",
+            repo.name
+        );
+    }
+
+    let data = archive::download(&args.gateway, &repo.ipfs_cid.to_string())?;
+    let archive_head = archive::head(&data)?;
+
+    let report = Report::new(&address, &repo, archive_head, &signer.pubkey());
+    let signed = report.sign(&signer)?;
+    println!("{}", serde_json::to_string_pretty(&signed)?);
+
+    Ok(signed.report.matches)
+}
+
+/// Derive a repository address the same way `create_repo` does.
+fn repository_address(owner: &Pubkey, name: &str) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"repo", owner.as_ref(), name.as_bytes()],
+        &vanish_program::ID,
+    )
+    .0
+}
+
+fn expand_home(path: &str) -> String {
+    match (path.strip_prefix("~/"), std::env::var("HOME")) {
+        (Some(rest), Ok(home)) => format!("{home}/{rest}"),
+        _ => path.to_string(),
+    }
+}
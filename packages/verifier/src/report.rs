@@ -0,0 +1,74 @@
+//! Signed verification reports.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::Result;
+use serde::Serialize;
+use solana_sdk::{pubkey::Pubkey, signature::Keypair, signer::Signer};
+use vanish_program::Repository;
+
+/// Outcome of checking one repository archive.
+#[derive(Serialize)]
+pub struct Report {
+    pub repository: String,
+    pub owner: String,
+    pub name: String,
+    pub ipfs_cid: String,
+    pub onchain_head: String,
+    pub archive_head: String,
+    pub matches: bool,
+    pub verified_at: u64,
+    pub verifier: String,
+}
+
+/// A report together with the verifier's signature over it.
+#[derive(Serialize)]
+pub struct SignedReport {
+    pub report: Report,
+    /// Base58 ed25519 signature by `report.verifier` over the compact JSON
+    /// encoding of `report`.
+    pub signature: String,
+}
+
+impl Report {
+    pub fn new(
+        address: &Pubkey,
+        repo: &Repository,
+        archive_head: String,
+        verifier: &Pubkey,
+    ) -> Self {
+        let verified_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default();
+
+        let onchain_head: String = repo
+            .head_commit
+            .digest()
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect();
+
+        Self {
+            repository: address.to_string(),
+            owner: repo.owner.to_string(),
+            name: repo.name.clone(),
+            ipfs_cid: repo.ipfs_cid.to_string(),
+            matches: archive_head == onchain_head,
+            onchain_head,
+            archive_head,
+            verified_at,
+            verifier: verifier.to_string(),
+        }
+    }
+
+    pub fn sign(self, signer: &Keypair) -> Result<SignedReport> {
+        let message = serde_json::to_vec(&self)?;
+        let signature = signer.sign_message(&message).to_string();
+
+        Ok(SignedReport {
+            report: self,
+            signature,
+        })
+    }
+}